@@ -0,0 +1,84 @@
+// Imports
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rnote_bench::fixtures::FIXTURES;
+use rnote_engine::engine::export::{DocExportFormat, DocExportPrefs};
+use rnote_engine::engine::EngineSnapshot;
+use rnote_engine::Progress;
+
+fn serialization(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialization");
+    group.sample_size(10);
+
+    for fixture in FIXTURES {
+        let engine = fixture.build_engine();
+        let bytes = futures::executor::block_on(engine.save_as_rnote_bytes(String::new()))
+            .unwrap()
+            .unwrap();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+
+        group.bench_function(BenchmarkId::new("save", fixture.name), |b| {
+            b.iter(|| {
+                futures::executor::block_on(
+                    engine.save_as_rnote_bytes(format!("{}.rnote", fixture.name)),
+                )
+                .unwrap()
+                .unwrap()
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("load", fixture.name),
+            &bytes,
+            |b, bytes| {
+                b.iter(|| {
+                    futures::executor::block_on(EngineSnapshot::load_from_rnote_bytes(
+                        bytes.clone(),
+                        Progress::default(),
+                    ))
+                    .unwrap()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn export(c: &mut Criterion) {
+    let mut group = c.benchmark_group("export");
+    group.sample_size(10);
+
+    for fixture in FIXTURES {
+        let engine = fixture.build_engine();
+
+        for export_format in [
+            DocExportFormat::Svg,
+            DocExportFormat::Pdf,
+            DocExportFormat::Xopp,
+        ] {
+            let doc_export_prefs = DocExportPrefs {
+                export_format,
+                ..Default::default()
+            };
+
+            group.bench_function(
+                BenchmarkId::new(export_format.file_ext(), fixture.name),
+                |b| {
+                    b.iter(|| {
+                        futures::executor::block_on(engine.export_doc(
+                            fixture.name.to_string(),
+                            Some(doc_export_prefs),
+                            Progress::default(),
+                        ))
+                        .unwrap()
+                        .unwrap()
+                    })
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, serialization, export);
+criterion_main!(benches);
@@ -0,0 +1,46 @@
+// Imports
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rnote_bench::fixtures::FIXTURES;
+
+// Renames
+extern crate nalgebra as na;
+
+/// The zoom levels the viewport rendering is regenerated at.
+const ZOOMS: [f64; 3] = [0.5, 1.0, 2.0];
+
+fn viewport_rendering(c: &mut Criterion) {
+    let mut group = c.benchmark_group("viewport_rendering");
+    group.sample_size(20);
+
+    for fixture in FIXTURES {
+        let mut engine = fixture.build_engine();
+        let _ = engine.camera.set_size(na::vector![1600.0, 1200.0]);
+
+        for zoom in ZOOMS {
+            let _ = engine.camera.zoom_to(zoom);
+            let viewport = engine.camera.viewport();
+            let image_scale = engine.camera.image_scale();
+            let keys = engine
+                .store
+                .stroke_keys_as_rendered_intersecting_bounds(viewport);
+
+            group.throughput(Throughput::Elements(keys.len() as u64));
+            group.bench_with_input(
+                BenchmarkId::new(fixture.name, format!("zoom {zoom}")),
+                &keys,
+                |b, keys| {
+                    b.iter(|| {
+                        engine
+                            .store
+                            .regenerate_rendering_for_strokes(keys, viewport, image_scale)
+                    })
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, viewport_rendering);
+criterion_main!(benches);
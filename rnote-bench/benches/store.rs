@@ -0,0 +1,92 @@
+// Imports
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use rnote_bench::fixtures::{self, FIXTURES};
+use rnote_engine::{RnoteEngine, StrokeStore};
+
+// Renames
+extern crate nalgebra as na;
+
+/// The eraser width used for the collision benchmarks.
+const ERASER_WIDTH: f64 = 20.0;
+/// The number of eraser positions of a sweep.
+const ERASER_SWEEP_STEPS: usize = 200;
+
+fn stroke_insertion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stroke_insertion");
+
+    for fixture in FIXTURES {
+        let strokes = fixture.generate_strokes();
+        group.throughput(Throughput::Elements(strokes.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(fixture.name),
+            &strokes,
+            |b, strokes| {
+                b.iter_batched(
+                    || strokes.clone(),
+                    |strokes| {
+                        let mut store = StrokeStore::default();
+                        for stroke in strokes {
+                            store.insert_stroke(stroke, None);
+                        }
+                        store
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn eraser_collision(c: &mut Criterion) {
+    let mut group = c.benchmark_group("eraser_collision");
+    let sweep = fixtures::eraser_sweep(ERASER_WIDTH, ERASER_SWEEP_STEPS);
+    group.throughput(Throughput::Elements(sweep.len() as u64));
+
+    for fixture in FIXTURES {
+        let snapshot = fixture.snapshot();
+        let setup = || {
+            let mut engine = RnoteEngine::default();
+            let _ = engine.load_snapshot(snapshot.clone());
+            let _ = engine.camera.set_size(na::vector![1600.0, 1200.0]);
+            engine
+        };
+
+        group.bench_function(BenchmarkId::new("trash", fixture.name), |b| {
+            b.iter_batched(
+                setup,
+                |mut engine| {
+                    let viewport = engine.camera.viewport();
+                    for &eraser_bounds in sweep.iter() {
+                        let _ = engine
+                            .store
+                            .trash_colliding_strokes(eraser_bounds, viewport);
+                    }
+                    engine
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(BenchmarkId::new("split", fixture.name), |b| {
+            b.iter_batched(
+                setup,
+                |mut engine| {
+                    let viewport = engine.camera.viewport();
+                    for &eraser_bounds in sweep.iter() {
+                        let _ = engine
+                            .store
+                            .split_colliding_strokes(eraser_bounds, viewport);
+                    }
+                    engine
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, stroke_insertion, eraser_collision);
+criterion_main!(benches);
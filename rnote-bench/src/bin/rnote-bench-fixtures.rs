@@ -0,0 +1,48 @@
+//! Writes the generated benchmark fixtures as `.rnote` files, e.g. to profile loading them in the app.
+//!
+//! Usage: `rnote-bench-fixtures <output-dir> [fixture-names..]`. Without names, all fixtures are written.
+
+// Imports
+use anyhow::Context;
+use rnote_bench::fixtures::{Fixture, FIXTURES};
+use std::path::PathBuf;
+
+fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let output_dir =
+        PathBuf::from(args.next().context(
+            "missing argument, usage: rnote-bench-fixtures <output-dir> [fixture-names..]",
+        )?);
+    let names = args.collect::<Vec<String>>();
+    let fixtures = if names.is_empty() {
+        FIXTURES.to_vec()
+    } else {
+        names
+            .iter()
+            .map(|name| {
+                Fixture::by_name(name).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "unknown fixture `{name}`, available: {}",
+                        FIXTURES.map(|f| f.name).join(", ")
+                    )
+                })
+            })
+            .collect::<anyhow::Result<Vec<Fixture>>>()?
+    };
+
+    std::fs::create_dir_all(&output_dir)?;
+    for fixture in fixtures {
+        let path = output_dir.join(format!("{}.rnote", fixture.name));
+        let bytes = fixture.save_as_rnote_bytes()?;
+        std::fs::write(&path, bytes)
+            .with_context(|| format!("writing fixture to `{}` failed", path.display()))?;
+        println!(
+            "wrote fixture `{}` with {} strokes to `{}`",
+            fixture.name,
+            fixture.n_strokes(),
+            path.display()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+// Imports
+use p2d::bounding_volume::Aabb;
+use rand::{Rng, SeedableRng};
+use rnote_compose::penpath::Element;
+use rnote_compose::style::smooth::SmoothOptions;
+use rnote_compose::style::textured::TexturedOptions;
+use rnote_compose::transform::TransformBehaviour;
+use rnote_compose::{PenPath, Style};
+use rnote_engine::document::Format;
+use rnote_engine::engine::builder::DocumentBuilder;
+use rnote_engine::engine::EngineSnapshot;
+use rnote_engine::strokes::{BrushStroke, Stroke};
+use rnote_engine::RnoteEngine;
+
+/// The seed of the generator, kept fixed so the fixtures are identical between runs.
+pub const FIXTURES_SEED: u64 = 0x726e_6f74_65;
+
+/// A generated document the benchmarks are run on.
+///
+/// The strokes resemble handwriting: short, dense pen strokes in lines across the pages,
+/// with varying pressure and a few textured strokes mixed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fixture {
+    /// The name, used as benchmark id and file name.
+    pub name: &'static str,
+    pub n_pages: usize,
+    pub strokes_per_page: usize,
+    pub elements_per_stroke: usize,
+}
+
+/// The representative documents.
+pub const FIXTURES: [Fixture; 3] = [
+    // A few pages of notes
+    Fixture {
+        name: "notes",
+        n_pages: 4,
+        strokes_per_page: 250,
+        elements_per_stroke: 40,
+    },
+    // A long lecture script
+    Fixture {
+        name: "lecture",
+        n_pages: 60,
+        strokes_per_page: 400,
+        elements_per_stroke: 40,
+    },
+    // Densely filled pages with long strokes
+    Fixture {
+        name: "dense",
+        n_pages: 8,
+        strokes_per_page: 2000,
+        elements_per_stroke: 120,
+    },
+];
+
+impl Fixture {
+    /// The fixture with the given name.
+    pub fn by_name(name: &str) -> Option<Self> {
+        FIXTURES.iter().find(|f| f.name == name).copied()
+    }
+
+    /// The total number of strokes.
+    pub fn n_strokes(&self) -> usize {
+        self.n_pages * self.strokes_per_page
+    }
+
+    /// Generate the pen strokes of the fixture, positioned on the pages of the default format.
+    pub fn generate_strokes(&self) -> Vec<Stroke> {
+        let page_height = Format::default().height;
+        self.generate_page_strokes()
+            .into_iter()
+            .enumerate()
+            .flat_map(|(page, strokes)| {
+                strokes.into_iter().map(move |mut stroke| {
+                    stroke.translate(na::vector![0.0, page as f64 * page_height]);
+                    stroke
+                })
+            })
+            .collect()
+    }
+
+    /// Build the engine holding the fixture document.
+    pub fn build_engine(&self) -> RnoteEngine {
+        self.doc_builder().build()
+    }
+
+    /// Take a snapshot of the fixture document.
+    pub fn snapshot(&self) -> EngineSnapshot {
+        self.build_engine().take_snapshot()
+    }
+
+    /// Generate the fixture document and save it as `.rnote` file bytes.
+    pub fn save_as_rnote_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        self.doc_builder()
+            .save_as_rnote_bytes(&format!("{}.rnote", self.name))
+    }
+
+    fn doc_builder(&self) -> DocumentBuilder {
+        let mut builder = DocumentBuilder::new();
+        for (page, strokes) in self.generate_page_strokes().into_iter().enumerate() {
+            if page > 0 {
+                builder = builder.add_page();
+            }
+            for stroke in strokes {
+                builder = builder.with_stroke(stroke);
+            }
+        }
+        builder
+    }
+
+    /// The strokes of every page, positioned relative to the page.
+    fn generate_page_strokes(&self) -> Vec<Vec<Stroke>> {
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(FIXTURES_SEED);
+        let format = Format::default();
+        let margin = 60.0;
+        let n_lines = 30;
+        let line_height = (format.height - 2.0 * margin) / n_lines as f64;
+        let strokes_per_line = (self.strokes_per_page / n_lines).max(1);
+
+        (0..self.n_pages)
+            .map(|_| {
+                (0..self.strokes_per_page)
+                    .map(|i| {
+                        // Fill the page line by line, like handwriting
+                        let line = (i / strokes_per_line) % n_lines;
+                        let x_frac = (i % strokes_per_line) as f64 / strokes_per_line as f64;
+                        let start = na::vector![
+                            margin + x_frac * (format.width - 2.0 * margin),
+                            margin
+                                + line as f64 * line_height
+                                + rng.gen_range(0.0..line_height * 0.5)
+                        ];
+                        let elements = random_walk(&mut rng, start, self.elements_per_stroke);
+                        Stroke::BrushStroke(BrushStroke::from_penpath(
+                            PenPath::try_from_elements(elements).unwrap(),
+                            random_style(&mut rng),
+                        ))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Bounds that sweep across the upper part of the first page, as the eraser would during a stroke.
+pub fn eraser_sweep(width: f64, n_steps: usize) -> Vec<Aabb> {
+    let format = Format::default();
+    (0..n_steps)
+        .map(|i| {
+            let t = i as f64 / n_steps.max(1) as f64;
+            let center = na::vector![
+                60.0 + t * (format.width - 120.0),
+                120.0 + (t * std::f64::consts::TAU * 3.0).sin() * 60.0
+            ];
+            Aabb::from_half_extents(na::Point2::from(center), na::Vector2::repeat(width * 0.5))
+        })
+        .collect()
+}
+
+fn random_walk(
+    rng: &mut rand_pcg::Pcg64,
+    start: na::Vector2<f64>,
+    n_elements: usize,
+) -> Vec<Element> {
+    let mut pos = start;
+    let mut angle = rng.gen_range(-0.5..0.5_f64);
+    (0..n_elements.max(1))
+        .map(|_| {
+            angle += rng.gen_range(-0.6..0.6);
+            pos += na::vector![angle.cos(), angle.sin()] * rng.gen_range(0.5..2.5);
+            Element::new(pos, rng.gen_range(0.2..1.0))
+        })
+        .collect()
+}
+
+fn random_style(rng: &mut rand_pcg::Pcg64) -> Style {
+    let mut style = if rng.gen_bool(0.1) {
+        Style::Textured(TexturedOptions::default())
+    } else {
+        Style::Smooth(SmoothOptions::default())
+    };
+    style.set_stroke_width(rng.gen_range(1.0..4.0));
+    style
+}
@@ -0,0 +1,17 @@
+#![warn(missing_debug_implementations)]
+
+//! The rnote-bench crate holds the performance regression benchmarks of the engine and the fixtures they run on.
+//!
+//! The fixtures are large documents that are generated deterministically, so the results of different runs stay comparable.
+//! Run the benchmarks with `cargo bench -p rnote-bench`. To catch regressions, save a baseline on the last release with
+//! `cargo bench -p rnote-bench -- --save-baseline release` and compare against it with `-- --baseline release`.
+
+// Modules
+pub mod fixtures;
+
+// Re-exports
+pub use fixtures::Fixture;
+
+// Renames
+extern crate nalgebra as na;
+extern crate parry2d_f64 as p2d;
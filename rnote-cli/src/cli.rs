@@ -1,7 +1,16 @@
+use crate::convert::{self, ConvertFormat};
+#[cfg(feature = "scripting")]
+use crate::script;
+use crate::transform::{self, ExportTransforms};
+use crate::{inspect, merge, render, repair, watch};
 use clap::{Parser, Subcommand};
-use rnote_engine::engine::export::{DocExportFormat, DocExportPrefs};
-use rnote_engine::engine::EngineSnapshot;
-use rnote_engine::RnoteEngine;
+use rnote_engine::engine::export::{DocExportFormat, DocExportPrefs, ExportBackgroundColor};
+use rnote_engine::engine::imposition::PagesPerSheet;
+use rnote_engine::engine::{
+    EngineSnapshot, HeaderFooter, MarkdownExport, Watermark, WatermarkContent, WatermarkPages,
+    WatermarkPosition,
+};
+use rnote_engine::{exporters, Progress, RnoteEngine};
 use smol::fs::File;
 use smol::io::{AsyncReadExt, AsyncWriteExt};
 use std::path::{Path, PathBuf};
@@ -41,10 +50,15 @@ pub(crate) enum Commands {
     /// The export format is recognized from the file extension of the output file.{n}
     /// When using --output-format, the same file name is used with the extension changed.{n}
     /// --output-file and --output-format are mutually exclusive but one of them is required.{n}
-    /// Currently `.svg`, `.xopp` and `.pdf` are supported.{n}
+    /// Currently `.svg`, `.xopp`, `.pdf` and `.tiff` are supported,{n}
+    /// and the file extensions of registered custom exporters.{n}
     /// Usages: {n}
-    /// rnote-cli export --output-file [filename.(svg|xopp|pdf)] [1 file]{n}
-    /// rnote-cli export --output-format [svg|xopp|pdf] [list of files]
+    /// rnote-cli export --output-file [filename.(svg|xopp|pdf|tiff|md)] [1 file]{n}
+    /// rnote-cli export --output-format [svg|xopp|pdf|tiff|md] [list of files]{n}
+    /// Markdown exports place the drawings as images in a `<filename>-assets` directory next to the Markdown file.{n}
+    /// With --watch, the files are re-exported whenever they change until the process is terminated.{n}
+    /// The document can be transformed before it is exported with --map-color, --rotate, --scale and --crop-to-content,{n}
+    /// which are applied in this order. The input files are not modified.
     Export {
         /// the rnote save file
         rnote_files: Vec<PathBuf>,
@@ -60,6 +74,162 @@ pub(crate) enum Commands {
         /// export with background pattern
         #[arg(short = 'p', long)]
         with_pattern: Option<bool>,
+        /// the color of the exported background, independent of the document background.{n}
+        /// One of `document`, `transparent`, `white` or a hex color (`#rrggbb` or `#rrggbbaa`).
+        #[arg(long)]
+        background_color: Option<String>,
+        /// watch the rnote files and re-export them when they change
+        #[arg(short = 'w', long)]
+        watch: bool,
+        /// crop the document to the bounds of its content
+        #[arg(long)]
+        crop_to_content: bool,
+        /// convert text to glyph outlines when exporting to svg,{n}
+        /// so that the exported file renders the same without the fonts installed
+        #[arg(long)]
+        text_as_paths: bool,
+        /// scale the document by the factor
+        #[arg(long)]
+        scale: Option<f64>,
+        /// rotate the document by the angle, in degrees
+        #[arg(long, allow_negative_numbers(true))]
+        rotate: Option<f64>,
+        /// replace a color with another, in the form `old=new` with hex colors (`#rrggbb` or `#rrggbbaa`).{n}
+        /// Can be given multiple times.
+        #[arg(long)]
+        map_color: Vec<String>,
+        /// overlay a text watermark on the exported pages
+        #[arg(long, conflicts_with("watermark_svg"))]
+        watermark: Option<String>,
+        /// overlay the svg file as watermark on the exported pages
+        #[arg(long, conflicts_with("watermark"))]
+        watermark_svg: Option<PathBuf>,
+        /// the position of the watermark.{n}
+        /// One of `center`, `top-left`, `top-right`, `bottom-left` or `bottom-right`.
+        #[arg(long)]
+        watermark_position: Option<String>,
+        /// the opacity of the watermark, between 0.0 and 1.0
+        #[arg(long)]
+        watermark_opacity: Option<f64>,
+        /// only place the watermark on the first exported page
+        #[arg(long)]
+        watermark_first_page: bool,
+        /// place a header on every exported page.{n}
+        /// Can contain the placeholders `{page}`, `{pages}`, `{title}` and `{date}`.
+        #[arg(long)]
+        header: Option<String>,
+        /// place a footer on every exported page.{n}
+        /// Can contain the placeholders `{page}`, `{pages}`, `{title}` and `{date}`.
+        #[arg(long)]
+        footer: Option<String>,
+        /// the number of pages that are placed on every A4 sheet when exporting to pdf. One of 1, 2, 4 or 6.
+        #[arg(long)]
+        pages_per_sheet: Option<usize>,
+        /// arrange the pages as booklet on A4 sheets when exporting to pdf.{n}
+        /// The sheets need to be printed double-sided, flipping on the short edge, then folded and stapled.
+        #[arg(long)]
+        booklet: bool,
+    },
+    /// Converts multiple files in parallel and saves them in the output directory in the desired format.{n}
+    /// Inputs can be `.rnote` or `.xopp` files. Wildcards (`*`, `?`) in the file names are expanded.{n}
+    /// Currently `rnote`, `svg`, `xopp`, `pdf` and `tiff` are supported as output format,{n}
+    /// and the file extensions of registered custom exporters.{n}
+    /// Usage: {n}
+    /// rnote-cli convert --output-dir [dir] --output-format [rnote|svg|xopp|pdf|tiff] [list of files or patterns]
+    Convert {
+        /// the input files or patterns
+        #[arg(required(true))]
+        inputs: Vec<String>,
+        /// the output directory. Is created if it does not exist.
+        #[arg(short = 'o', long)]
+        output_dir: PathBuf,
+        /// the output format.
+        #[arg(short = 'f', long)]
+        output_format: String,
+        /// export with background
+        #[arg(short = 'b', long)]
+        with_background: Option<bool>,
+        /// export with background pattern
+        #[arg(short = 'p', long)]
+        with_pattern: Option<bool>,
+        /// When converting .xopp files, the import dpi can be specified.{n}
+        /// Else the default (96) is used.
+        #[arg(long)]
+        xopp_dpi: Option<f64>,
+        /// the number of parallel workers. Defaults to the available parallelism.
+        #[arg(short = 'j', long)]
+        jobs: Option<usize>,
+        /// overwrite existing output files. Input files are never overwritten.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Merges multiple Rnote files into one document and saves it as a rnote save file.{n}
+    /// The first file determines the format, layout and background of the merged document.{n}
+    /// The content of the following files is appended page-wise,{n}
+    /// or side-by-side when the first document has an infinite layout.{n}
+    /// Usage: {n}
+    /// rnote-cli merge --output-file [filename.rnote] [list of files]
+    Merge {
+        /// the rnote save files to merge
+        #[arg(required(true))]
+        rnote_files: Vec<PathBuf>,
+        /// the merged output rnote save file
+        #[arg(short = 'o', long)]
+        output_file: PathBuf,
+    },
+    /// Prints information about the specified Rnote file(s).{n}
+    /// Includes the file format version, page count, stroke counts by type and embedded image sizes.
+    Inspect {
+        /// the rnote save files
+        rnote_files: Vec<PathBuf>,
+        /// additionally dump the entire document as Json
+        #[arg(long)]
+        json: bool,
+    },
+    /// Repairs a damaged or truncated Rnote file.{n}
+    /// Parses as much of the file as possible, drops unreadable strokes with a report and writes a repaired copy.{n}
+    /// When no output file is given, the copy is saved as `<name>-repaired.rnote` next to the input file.
+    Repair {
+        /// the damaged rnote save file
+        rnote_file: PathBuf,
+        /// the repaired output rnote save file
+        #[arg(short = 'o', long)]
+        output_file: Option<PathBuf>,
+    },
+    /// Renders a region of the Rnote file with its background into a Png image.{n}
+    /// Without a region, the entire document is rendered. Can be used to generate thumbnails.{n}
+    /// Usage: {n}
+    /// rnote-cli render --output-file [filename.png] --region [x,y,width,height] --scale [factor] [1 file]{n}
+    /// rnote-cli render --output-file [filename.png] --size [pixels] [1 file]
+    Render {
+        /// the rnote save file
+        rnote_file: PathBuf,
+        /// the output Png file
+        #[arg(short = 'o', long)]
+        output_file: PathBuf,
+        /// the rendered region in document coordinates, in the form `x,y,width,height`
+        #[arg(short = 'r', long, allow_negative_numbers(true))]
+        region: Option<String>,
+        /// the scale factor of the image
+        #[arg(short = 's', long, default_value_t = 1.0, conflicts_with("size"))]
+        scale: f64,
+        /// the size of the longer side of the image in pixels. Exclusive with scale.
+        #[arg(long)]
+        size: Option<u32>,
+    },
+    /// Runs a rhai script on the Rnote file to automate edits, e.g. numbering pages or stamping headers.{n}
+    /// The modified document is saved as the output file, or else overwrites the input file.{n}
+    /// Exports that are requested by the script with `export_doc(file_name)` are performed afterwards.
+    #[cfg(feature = "scripting")]
+    Script {
+        /// the rnote save file
+        rnote_file: PathBuf,
+        /// the rhai script file
+        #[arg(short = 's', long)]
+        script_file: PathBuf,
+        /// the modified output rnote save file
+        #[arg(short = 'o', long)]
+        output_file: Option<PathBuf>,
     },
 }
 
@@ -145,9 +315,27 @@ pub(crate) async fn run() -> anyhow::Result<()> {
             output_format,
             with_background,
             with_pattern,
+            background_color,
+            watch,
+            crop_to_content,
+            text_as_paths,
+            scale,
+            rotate,
+            map_color,
+            watermark,
+            watermark_svg,
+            watermark_position,
+            watermark_opacity,
+            watermark_first_page,
+            header,
+            footer,
+            pages_per_sheet,
+            booklet,
         } => {
             println!("Exporting..");
 
+            let transforms = ExportTransforms::new(crop_to_content, scale, rotate, &map_color)?;
+
             // apply given arguments to export prefs
             engine.export_prefs.doc_export_prefs = create_doc_export_prefs_from_args(
                 output_file.as_deref(),
@@ -155,6 +343,63 @@ pub(crate) async fn run() -> anyhow::Result<()> {
                 with_background,
                 with_pattern,
             )?;
+            engine.export_prefs.doc_export_prefs.text_as_paths = text_as_paths;
+            if let Some(background_color) = background_color {
+                engine.export_prefs.doc_export_prefs.background_color =
+                    parse_export_background_color(&background_color)?;
+            }
+            let watermark_content = match (watermark, watermark_svg) {
+                (Some(text), _) => Some(WatermarkContent::Text(text)),
+                (None, Some(svg_file)) => Some(WatermarkContent::Svg(
+                    smol::fs::read_to_string(&svg_file).await?,
+                )),
+                (None, None) => None,
+            };
+            engine.export_watermark = watermark_content
+                .map(|content| {
+                    create_watermark_from_args(
+                        content,
+                        watermark_position.as_deref(),
+                        watermark_opacity,
+                        watermark_first_page,
+                    )
+                })
+                .transpose()?;
+            if let Some(pages_per_sheet) = pages_per_sheet {
+                engine.export_prefs.doc_export_prefs.pages_per_sheet = match pages_per_sheet {
+                    1 => PagesPerSheet::One,
+                    2 => PagesPerSheet::Two,
+                    4 => PagesPerSheet::Four,
+                    6 => PagesPerSheet::Six,
+                    _ => {
+                        return Err(anyhow::anyhow!(
+                            "Invalid number of pages per sheet `{pages_per_sheet}`, expected 1, 2, 4 or 6"
+                        ))
+                    }
+                };
+            }
+            engine.export_prefs.doc_export_prefs.booklet = booklet;
+            if header.is_some() || footer.is_some() {
+                engine.export_header_footer = Some(HeaderFooter {
+                    header: header.unwrap_or_default(),
+                    footer: footer.unwrap_or_default(),
+                    ..Default::default()
+                });
+            }
+            // The extension of the output files when exporting with --output-format
+            let output_file_ext = match output_format.as_deref() {
+                Some(format)
+                    if exporters::custom_exporter_for_ext(format).is_some()
+                        || is_markdown_ext(format) =>
+                {
+                    format.to_lowercase()
+                }
+                _ => engine
+                    .export_prefs
+                    .doc_export_prefs
+                    .export_format
+                    .file_ext(),
+            };
 
             match output_file {
                 Some(ref output_file) => match rnote_files.get(0) {
@@ -165,14 +410,20 @@ pub(crate) async fn run() -> anyhow::Result<()> {
 
                         let rnote_file_disp = rnote_file.display().to_string();
                         let output_file_disp = output_file.display().to_string();
-                        let pb = indicatif::ProgressBar::new_spinner().with_message(format!(
+                        let (pb, progress) = export_progress_bar(format!(
                             "Exporting \"{rnote_file_disp}\" to: \"{output_file_disp}\""
                         ));
-                        pb.set_draw_target(indicatif::ProgressDrawTarget::stdout());
-                        pb.enable_steady_tick(Duration::from_millis(8));
 
                         // export
-                        if let Err(e) = export_to_file(&mut engine, rnote_file, output_file).await {
+                        if let Err(e) = export_to_file(
+                            &mut engine,
+                            rnote_file,
+                            output_file,
+                            &transforms,
+                            progress,
+                        )
+                        .await
+                        {
                             let msg = format!("Export \"{rnote_file_disp}\" to: \"{output_file_disp}\" failed, Err {e:?}");
                             if pb.is_hidden() {
                                 println!("{msg}")
@@ -196,13 +447,7 @@ pub(crate) async fn run() -> anyhow::Result<()> {
                         .iter()
                         .map(|file| {
                             let mut output = file.clone();
-                            output.set_extension(
-                                engine
-                                    .export_prefs
-                                    .doc_export_prefs
-                                    .export_format
-                                    .file_ext(),
-                            );
+                            output.set_extension(&output_file_ext);
                             output
                         })
                         .collect::<Vec<PathBuf>>();
@@ -210,15 +455,19 @@ pub(crate) async fn run() -> anyhow::Result<()> {
                     for (rnote_file, output_file) in rnote_files.iter().zip(output_files.iter()) {
                         let rnote_file_disp = rnote_file.display().to_string();
                         let output_file_disp = output_file.display().to_string();
-                        let pb = indicatif::ProgressBar::new_spinner();
-                        pb.set_draw_target(indicatif::ProgressDrawTarget::stdout());
-                        pb.set_message(format!(
+                        let (pb, progress) = export_progress_bar(format!(
                             "Exporting \"{rnote_file_disp}\" to: \"{output_file_disp}\""
                         ));
-                        pb.enable_steady_tick(Duration::from_millis(8));
 
                         // export
-                        if let Err(e) = export_to_file(&mut engine, &rnote_file, &output_file).await
+                        if let Err(e) = export_to_file(
+                            &mut engine,
+                            rnote_file,
+                            output_file,
+                            &transforms,
+                            progress,
+                        )
+                        .await
                         {
                             let msg = format!("Export \"{rnote_file_disp}\" to: \"{output_file_disp}\" failed, Err {e:?}");
                             if pb.is_hidden() {
@@ -240,6 +489,167 @@ pub(crate) async fn run() -> anyhow::Result<()> {
             }
 
             println!("Export Finished!");
+
+            if watch {
+                let export_files = match output_file {
+                    Some(output_file) => rnote_files
+                        .into_iter()
+                        .map(|rnote_file| (rnote_file, output_file.clone()))
+                        .collect::<Vec<(PathBuf, PathBuf)>>(),
+                    None => rnote_files
+                        .into_iter()
+                        .map(|rnote_file| {
+                            let output_file = rnote_file.with_extension(&output_file_ext);
+                            (rnote_file, output_file)
+                        })
+                        .collect::<Vec<(PathBuf, PathBuf)>>(),
+                };
+
+                watch::watch_export(&mut engine, export_files, &transforms).await?;
+            }
+        }
+        Commands::Convert {
+            inputs,
+            output_dir,
+            output_format,
+            with_background,
+            with_pattern,
+            xopp_dpi,
+            jobs,
+            force,
+        } => {
+            println!("Converting..");
+
+            let output_format = output_format.parse::<ConvertFormat>()?;
+            let mut doc_export_prefs = DocExportPrefs::default();
+            if let ConvertFormat::Doc(export_format) = &output_format {
+                doc_export_prefs.export_format = *export_format;
+            }
+            if let Some(with_background) = with_background {
+                doc_export_prefs.with_background = with_background;
+            }
+            if let Some(with_pattern) = with_pattern {
+                doc_export_prefs.with_pattern = with_pattern;
+            }
+
+            convert::run_convert(
+                inputs,
+                output_dir,
+                output_format,
+                doc_export_prefs,
+                jobs,
+                xopp_dpi,
+                force,
+            )?;
+
+            println!("Convert finished!");
+        }
+        Commands::Merge {
+            rnote_files,
+            output_file,
+        } => {
+            println!("Merging..");
+
+            let output_file_disp = output_file.display().to_string();
+            let pb = indicatif::ProgressBar::new_spinner().with_message(format!(
+                "Merging {} files to: \"{output_file_disp}\"",
+                rnote_files.len()
+            ));
+            pb.set_draw_target(indicatif::ProgressDrawTarget::stdout());
+            pb.enable_steady_tick(Duration::from_millis(8));
+
+            // merge
+            if let Err(e) = merge::merge_files(&mut engine, &rnote_files, &output_file).await {
+                let msg = format!("Merging to \"{output_file_disp}\" failed, Err: {e:?}");
+                if pb.is_hidden() {
+                    println!("{msg}");
+                }
+                pb.abandon_with_message(msg);
+                return Err(e);
+            } else {
+                let msg = format!("Merging to \"{output_file_disp}\" succeeded");
+                if pb.is_hidden() {
+                    println!("{msg}");
+                }
+                pb.finish_with_message(msg);
+            }
+
+            println!("Merge finished!");
+        }
+        Commands::Inspect { rnote_files, json } => {
+            for rnote_file in rnote_files.into_iter() {
+                if let Err(e) = inspect::inspect_file(&mut engine, &rnote_file, json).await {
+                    println!(
+                        "Inspecting file \"{}\" failed, Err: {e:?}",
+                        rnote_file.display()
+                    );
+                    return Err(e);
+                }
+            }
+        }
+        Commands::Repair {
+            rnote_file,
+            output_file,
+        } => {
+            let output_file =
+                output_file.unwrap_or_else(|| repair::default_output_file(&rnote_file));
+            let output_file_disp = output_file.display().to_string();
+
+            // repair
+            if let Err(e) = repair::repair_file(&mut engine, &rnote_file, &output_file).await {
+                println!(
+                    "Repairing \"{}\" to: \"{output_file_disp}\" failed, Err: {e:?}",
+                    rnote_file.display()
+                );
+                return Err(e);
+            }
+
+            println!("Repaired copy saved to: \"{output_file_disp}\"");
+        }
+        Commands::Render {
+            rnote_file,
+            output_file,
+            region,
+            scale,
+            size,
+        } => {
+            let region = region.as_deref().map(render::parse_region).transpose()?;
+            let output_file_disp = output_file.display().to_string();
+
+            if let Err(e) =
+                render::render_file(&mut engine, &rnote_file, &output_file, region, scale, size)
+                    .await
+            {
+                println!(
+                    "Rendering \"{}\" to: \"{output_file_disp}\" failed, Err: {e:?}",
+                    rnote_file.display()
+                );
+                return Err(e);
+            }
+
+            println!("Rendered image saved to: \"{output_file_disp}\"");
+        }
+        #[cfg(feature = "scripting")]
+        Commands::Script {
+            rnote_file,
+            script_file,
+            output_file,
+        } => {
+            let output_file = output_file.unwrap_or_else(|| rnote_file.clone());
+            let output_file_disp = output_file.display().to_string();
+
+            if let Err(e) =
+                script::run_script(&mut engine, &rnote_file, &script_file, &output_file).await
+            {
+                println!(
+                    "Running script \"{}\" on \"{}\" failed, Err: {e:?}",
+                    script_file.display(),
+                    rnote_file.display()
+                );
+                return Err(e);
+            }
+
+            println!("Modified document saved to: \"{output_file_disp}\"");
         }
     }
 
@@ -256,7 +666,7 @@ pub(crate) async fn test_file(
         .read_to_end(&mut rnote_bytes)
         .await?;
 
-    let _ = EngineSnapshot::load_from_rnote_bytes(rnote_bytes).await?;
+    let _ = EngineSnapshot::load_from_rnote_bytes(rnote_bytes, Progress::default()).await?;
     // Loading a valid engine snapshot can't fail, so we skip it
     Ok(())
 }
@@ -289,17 +699,72 @@ pub(crate) async fn import_file(
     Ok(())
 }
 
-fn get_export_format(format: &str) -> anyhow::Result<DocExportFormat> {
+pub(crate) fn get_export_format(format: &str) -> anyhow::Result<DocExportFormat> {
     match format {
         "svg" => Ok(DocExportFormat::Svg),
         "xopp" => Ok(DocExportFormat::Xopp),
         "pdf" => Ok(DocExportFormat::Pdf),
+        "tiff" | "tif" => Ok(DocExportFormat::Tiff),
         ext => Err(anyhow::anyhow!(
             "Could not create doc export prefs, unsupported export file extension `{ext}`"
         )),
     }
 }
 
+/// Like [get_export_format], but also accepts Markdown and the file extensions of registered custom exporters.
+///
+/// These don't use the export format of the prefs, so the default is returned for them.
+fn get_export_format_or_custom(format: &str) -> anyhow::Result<DocExportFormat> {
+    if exporters::custom_exporter_for_ext(format).is_some() || is_markdown_ext(format) {
+        return Ok(DocExportFormat::default());
+    }
+    get_export_format(format)
+}
+
+fn is_markdown_ext(ext: &str) -> bool {
+    ext.eq_ignore_ascii_case(MarkdownExport::FILE_EXT)
+}
+
+fn parse_export_background_color(color: &str) -> anyhow::Result<ExportBackgroundColor> {
+    match color.to_lowercase().as_str() {
+        "document" => Ok(ExportBackgroundColor::Document),
+        "transparent" => Ok(ExportBackgroundColor::Transparent),
+        "white" => Ok(ExportBackgroundColor::White),
+        _ => Ok(ExportBackgroundColor::Custom(transform::parse_hex_color(
+            color,
+        )?)),
+    }
+}
+
+fn create_watermark_from_args(
+    content: WatermarkContent,
+    position: Option<&str>,
+    opacity: Option<f64>,
+    first_page: bool,
+) -> anyhow::Result<Watermark> {
+    let mut watermark = Watermark {
+        content,
+        ..Default::default()
+    };
+    if let Some(position) = position {
+        watermark.position = match position.to_lowercase().as_str() {
+            "center" => WatermarkPosition::Center,
+            "top-left" => WatermarkPosition::TopLeft,
+            "top-right" => WatermarkPosition::TopRight,
+            "bottom-left" => WatermarkPosition::BottomLeft,
+            "bottom-right" => WatermarkPosition::BottomRight,
+            _ => return Err(anyhow::anyhow!("Invalid watermark position `{position}`")),
+        };
+    }
+    if let Some(opacity) = opacity {
+        watermark.opacity = opacity.clamp(0.0, 1.0);
+    }
+    if first_page {
+        watermark.pages = WatermarkPages::First;
+    }
+    Ok(watermark)
+}
+
 pub(crate) fn create_doc_export_prefs_from_args(
     output_file: Option<impl AsRef<Path>>,
     output_format: Option<&str>,
@@ -308,14 +773,14 @@ pub(crate) fn create_doc_export_prefs_from_args(
 ) -> anyhow::Result<DocExportPrefs> {
     let format = match (output_file, output_format) {
         (Some(file), None) => match file.as_ref().extension().and_then(|ext| ext.to_str()) {
-            Some(extension) => get_export_format(extension),
+            Some(extension) => get_export_format_or_custom(extension),
             None => {
                 return Err(anyhow::anyhow!(
                     "Output file needs to have an extension to determine the file type"
                 ))
             }
         },
-        (None, Some(out_format)) => get_export_format(out_format),
+        (None, Some(out_format)) => get_export_format_or_custom(out_format),
         // unreachable because they are exclusive (conflicts_with)
         (Some(_), Some(_)) => {
             return Err(anyhow::anyhow!(
@@ -345,10 +810,15 @@ pub(crate) fn create_doc_export_prefs_from_args(
     Ok(prefs)
 }
 
+/// Export the rnote file to the output file.
+///
+/// Loading the file takes up the first third of the progress, exporting the rest.
 pub(crate) async fn export_to_file(
     engine: &mut RnoteEngine,
     rnote_file: impl AsRef<Path>,
     output_file: impl AsRef<Path>,
+    transforms: &ExportTransforms,
+    progress: Progress,
 ) -> anyhow::Result<()> {
     let Some(export_file_name) = output_file.as_ref().file_name().map(|s| s.to_string_lossy().to_string()) else {
         return Err(anyhow::anyhow!("Failed to get filename from output_file"));
@@ -360,11 +830,37 @@ pub(crate) async fn export_to_file(
         .read_to_end(&mut rnote_bytes)
         .await?;
 
-    let engine_snapshot = EngineSnapshot::load_from_rnote_bytes(rnote_bytes).await?;
+    let engine_snapshot =
+        EngineSnapshot::load_from_rnote_bytes(rnote_bytes, progress.scoped(0.0, 0.3)).await?;
     let _ = engine.load_snapshot(engine_snapshot);
+    transforms.apply(engine);
+
+    if output_file
+        .as_ref()
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map_or(false, is_markdown_ext)
+    {
+        let name = output_file
+            .as_ref()
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let markdown_export = engine
+            .export_doc_as_markdown(name, None, progress.scoped(0.3, 1.0))
+            .await??;
+        return markdown_export.write_to_file(output_file);
+    }
 
     // We applied the prefs previously to the engine
-    let export_bytes = engine.export_doc(export_file_name, None).await??;
+    let export_bytes = export_doc_to_bytes(
+        engine,
+        output_file.as_ref(),
+        export_file_name,
+        None,
+        progress.scoped(0.3, 1.0),
+    )
+    .await?;
 
     let mut fh = File::create(output_file).await?;
     fh.write_all(&export_bytes).await?;
@@ -372,3 +868,49 @@ pub(crate) async fn export_to_file(
 
     Ok(())
 }
+
+/// Export the document, with the custom exporter registered for the extension of the output file if there is one.
+///
+/// Custom exporters don't report their progress.
+pub(crate) async fn export_doc_to_bytes(
+    engine: &RnoteEngine,
+    output_file: &Path,
+    export_file_name: String,
+    doc_export_prefs_override: Option<DocExportPrefs>,
+    progress: Progress,
+) -> anyhow::Result<Vec<u8>> {
+    match output_file
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .filter(|ext| exporters::custom_exporter_for_ext(ext).is_some())
+    {
+        Some(ext) => {
+            engine
+                .export_doc_w_custom_exporter(ext, export_file_name, doc_export_prefs_override)
+                .await?
+        }
+        None => {
+            engine
+                .export_doc(export_file_name, doc_export_prefs_override, progress)
+                .await?
+        }
+    }
+}
+
+/// A progress bar for an export, together with the progress that advances it.
+fn export_progress_bar(msg: String) -> (indicatif::ProgressBar, Progress) {
+    const PROGRESS_BAR_LEN: u64 = 100;
+    let pb = indicatif::ProgressBar::new(PROGRESS_BAR_LEN)
+        .with_style(
+            indicatif::ProgressStyle::with_template("{msg} [{wide_bar}] {percent}%")
+                .unwrap()
+                .progress_chars("=> "),
+        )
+        .with_message(msg);
+    pb.set_draw_target(indicatif::ProgressDrawTarget::stdout());
+    let progress = Progress::with_callback({
+        let pb = pb.clone();
+        move |fraction| pb.set_position((fraction * PROGRESS_BAR_LEN as f64).round() as u64)
+    });
+    (pb, progress)
+}
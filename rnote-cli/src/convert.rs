@@ -0,0 +1,352 @@
+// Imports
+use crate::cli;
+use rnote_engine::engine::export::{DocExportFormat, DocExportPrefs};
+use rnote_engine::engine::EngineSnapshot;
+use rnote_engine::{exporters, Progress, RnoteEngine};
+use smol::fs::File;
+use smol::io::{AsyncReadExt, AsyncWriteExt};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// The target format of a conversion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ConvertFormat {
+    /// Rnote save file.
+    Rnote,
+    /// Any of the document export formats.
+    Doc(DocExportFormat),
+    /// The format of the custom exporter registered for the file extension.
+    Custom(String),
+}
+
+impl ConvertFormat {
+    pub(crate) fn file_ext(&self) -> String {
+        match self {
+            Self::Rnote => String::from("rnote"),
+            Self::Doc(format) => format.file_ext(),
+            Self::Custom(file_ext) => file_ext.clone(),
+        }
+    }
+}
+
+impl std::str::FromStr for ConvertFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rnote" => Ok(Self::Rnote),
+            s if exporters::custom_exporter_for_ext(s).is_some() => {
+                Ok(Self::Custom(s.to_lowercase()))
+            }
+            s => Ok(Self::Doc(cli::get_export_format(s)?)),
+        }
+    }
+}
+
+/// Converts all input files to the given format in parallel and saves the results in the output directory.
+///
+/// Inputs can contain `*` and `?` wildcards in their file name component.
+/// Nothing is converted when output files would collide or overwrite input files,
+/// or when they already exist and `force` is not set.
+/// Errors are reported per file. Returns an error when at least one conversion failed.
+pub(crate) fn run_convert(
+    inputs: Vec<String>,
+    output_dir: PathBuf,
+    output_format: ConvertFormat,
+    doc_export_prefs: DocExportPrefs,
+    jobs: Option<usize>,
+    xopp_dpi: Option<f64>,
+    force: bool,
+) -> anyhow::Result<()> {
+    let input_files = expand_inputs(inputs)?;
+    if input_files.is_empty() {
+        return Err(anyhow::anyhow!("No input files found."));
+    }
+    std::fs::create_dir_all(&output_dir)?;
+    let output_dir = output_dir.canonicalize()?;
+    // Canonicalize the inputs, so that the same file matched by different inputs is only converted once.
+    // Inputs that can't be canonicalized are kept, their conversion then fails and is reported.
+    let mut unique_input_files = HashSet::new();
+    let conversions = input_files
+        .into_iter()
+        .map(|input_file| input_file.canonicalize().unwrap_or(input_file))
+        .filter(|input_file| unique_input_files.insert(input_file.clone()))
+        .map(|input_file| {
+            let mut output_file = output_dir.join(input_file.file_name().unwrap_or_default());
+            output_file.set_extension(output_format.file_ext());
+            (input_file, output_file)
+        })
+        .collect::<Vec<(PathBuf, PathBuf)>>();
+    check_output_files(&conversions, force)?;
+
+    let n_files = conversions.len();
+    let n_workers = jobs
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        })
+        .clamp(1, n_files);
+
+    let queue = Arc::new(Mutex::new(VecDeque::from(conversions)));
+    let failed = Arc::new(Mutex::new(Vec::<(PathBuf, anyhow::Error)>::new()));
+
+    let pb = indicatif::ProgressBar::new(n_files as u64);
+    pb.set_draw_target(indicatif::ProgressDrawTarget::stdout());
+    pb.set_message(format!(
+        "Converting {n_files} files with {n_workers} workers"
+    ));
+
+    let workers = (0..n_workers)
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            let failed = Arc::clone(&failed);
+            let pb = pb.clone();
+            let output_format = output_format.clone();
+
+            std::thread::spawn(move || {
+                // Each worker needs its own engine, it is not thread safe
+                let mut engine = RnoteEngine::default();
+                engine.export_prefs.doc_export_prefs = doc_export_prefs;
+                if let Some(xopp_dpi) = xopp_dpi {
+                    engine.import_prefs.xopp_import_prefs.dpi = xopp_dpi;
+                }
+
+                loop {
+                    let Some((input_file, output_file)) = queue.lock().unwrap().pop_front() else {
+                        break;
+                    };
+                    let input_file_disp = input_file.display().to_string();
+                    let output_file_disp = output_file.display().to_string();
+
+                    if let Err(e) = smol::block_on(convert_file(
+                        &mut engine,
+                        &input_file,
+                        &output_file,
+                        &output_format,
+                    )) {
+                        pb.println(format!(
+                            "Converting \"{input_file_disp}\" to: \"{output_file_disp}\" failed, Err: {e:?}"
+                        ));
+                        failed.lock().unwrap().push((input_file, e));
+                    }
+                    pb.inc(1);
+                }
+            })
+        })
+        .collect::<Vec<std::thread::JoinHandle<()>>>();
+
+    for worker in workers {
+        if worker.join().is_err() {
+            log::error!("Joining convert worker thread failed, the thread panicked.");
+        }
+    }
+
+    let failed = std::mem::take(&mut *failed.lock().unwrap());
+    if failed.is_empty() {
+        pb.finish_with_message(format!("Converted {n_files} files"));
+        Ok(())
+    } else {
+        pb.abandon_with_message(format!(
+            "Converting {} of {n_files} files failed",
+            failed.len()
+        ));
+        Err(anyhow::anyhow!(
+            "Converting failed for files: {}",
+            failed
+                .iter()
+                .map(|(file, _)| format!("\"{}\"", file.display()))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ))
+    }
+}
+
+/// Checks that no two inputs are converted to the same output file and that no output file overwrites an input file.
+///
+/// Expects canonical paths. Existing output files are only allowed when `force` is set.
+fn check_output_files(conversions: &[(PathBuf, PathBuf)], force: bool) -> anyhow::Result<()> {
+    let input_files = conversions
+        .iter()
+        .map(|(input_file, _)| input_file.as_path())
+        .collect::<HashSet<&Path>>();
+    let mut inputs_by_output = HashMap::<&Path, Vec<&Path>>::new();
+    for (input_file, output_file) in conversions {
+        inputs_by_output
+            .entry(output_file.as_path())
+            .or_default()
+            .push(input_file.as_path());
+    }
+
+    let mut errors = vec![];
+    for (input_file, output_file) in conversions {
+        let output_file_disp = output_file.display();
+        if input_files.contains(output_file.as_path()) {
+            errors.push(format!(
+                "output file \"{output_file_disp}\" would overwrite an input file"
+            ));
+            continue;
+        }
+        let inputs = &inputs_by_output[output_file.as_path()];
+        if inputs.len() > 1 {
+            // Only report the collision once, for the first input
+            if inputs[0] == input_file.as_path() {
+                errors.push(format!(
+                    "inputs {} would all be converted to \"{output_file_disp}\"",
+                    inputs
+                        .iter()
+                        .map(|input| format!("\"{}\"", input.display()))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ));
+            }
+            continue;
+        }
+        if !force && output_file.exists() {
+            errors.push(format!(
+                "output file \"{output_file_disp}\" already exists, use --force to overwrite it"
+            ));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Converting aborted, nothing was converted:\n{}",
+            errors.join("\n")
+        ))
+    }
+}
+
+/// Loads the input file into the engine and saves it in the output format.
+///
+/// `.rnote` and `.xopp` input files are supported.
+pub(crate) async fn convert_file(
+    engine: &mut RnoteEngine,
+    input_file: impl AsRef<Path>,
+    output_file: impl AsRef<Path>,
+    output_format: &ConvertFormat,
+) -> anyhow::Result<()> {
+    let Some(output_file_name) = output_file
+        .as_ref()
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+    else {
+        return Err(anyhow::anyhow!("Failed to get filename from output_file"));
+    };
+
+    let mut input_bytes = vec![];
+    File::open(input_file.as_ref())
+        .await?
+        .read_to_end(&mut input_bytes)
+        .await?;
+
+    let snapshot = match input_file.as_ref().extension().and_then(|ext| ext.to_str()) {
+        Some("rnote") => {
+            EngineSnapshot::load_from_rnote_bytes(input_bytes, Progress::default()).await?
+        }
+        Some("xopp") => {
+            EngineSnapshot::load_from_xopp_bytes(input_bytes, engine.import_prefs.xopp_import_prefs)
+                .await?
+        }
+        ext => {
+            return Err(anyhow::anyhow!(
+                "Unsupported input file extension `{}`",
+                ext.unwrap_or_default()
+            ))
+        }
+    };
+    let _ = engine.load_snapshot(snapshot);
+
+    let output_bytes = match output_format {
+        ConvertFormat::Rnote => engine.save_as_rnote_bytes(output_file_name).await??,
+        ConvertFormat::Doc(export_format) => {
+            let doc_export_prefs = DocExportPrefs {
+                export_format: *export_format,
+                ..engine.export_prefs.doc_export_prefs
+            };
+            engine
+                .export_doc(
+                    output_file_name,
+                    Some(doc_export_prefs),
+                    Progress::default(),
+                )
+                .await??
+        }
+        ConvertFormat::Custom(file_ext) => {
+            engine
+                .export_doc_w_custom_exporter(file_ext, output_file_name, None)
+                .await??
+        }
+    };
+
+    let mut fh = File::create(output_file).await?;
+    fh.write_all(&output_bytes).await?;
+    fh.sync_all().await?;
+
+    Ok(())
+}
+
+/// Expands the inputs that contain wildcards into the matching files, sorted by their path.
+///
+/// Wildcards (`*` matching any number of characters, `?` matching one character) are only supported in the file name component.
+/// Inputs without wildcards are passed through unmodified.
+pub(crate) fn expand_inputs(inputs: Vec<String>) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+
+    for input in inputs {
+        if !input.contains(['*', '?']) {
+            files.push(PathBuf::from(input));
+            continue;
+        }
+        let input = PathBuf::from(input);
+        let Some(pattern) = input.file_name().and_then(|s| s.to_str()) else {
+            return Err(anyhow::anyhow!(
+                "Failed to get file name pattern from input \"{}\"",
+                input.display()
+            ));
+        };
+        let dir = match input.parent() {
+            Some(parent) if parent.as_os_str().is_empty() => Path::new("."),
+            Some(parent) => parent,
+            None => Path::new("."),
+        };
+        if dir.to_string_lossy().contains(['*', '?']) {
+            return Err(anyhow::anyhow!(
+                "Wildcards are only supported in the file name, input: \"{}\"",
+                input.display()
+            ));
+        }
+        let re = wildcard_to_regex(pattern)?;
+
+        let mut matched = std::fs::read_dir(dir)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if !path.is_file() {
+                    return None;
+                }
+                re.is_match(path.file_name()?.to_str()?).then_some(path)
+            })
+            .collect::<Vec<PathBuf>>();
+        matched.sort();
+        files.append(&mut matched);
+    }
+
+    Ok(files)
+}
+
+fn wildcard_to_regex(pattern: &str) -> anyhow::Result<regex::Regex> {
+    let re_str = pattern
+        .split('*')
+        .map(|part| {
+            part.split('?')
+                .map(regex::escape)
+                .collect::<Vec<String>>()
+                .join(".")
+        })
+        .collect::<Vec<String>>()
+        .join(".*");
+    Ok(regex::Regex::new(&format!("^{re_str}$"))?)
+}
@@ -0,0 +1,138 @@
+// Imports
+use rnote_compose::helpers::SplitOrder;
+use rnote_compose::shapes::ShapeBehaviour;
+use rnote_engine::engine::EngineSnapshot;
+use rnote_engine::fileformats::rnoteformat::RnoteFile;
+use rnote_engine::strokes::Stroke;
+use rnote_engine::{Progress, RnoteEngine};
+use smol::fs::File;
+use smol::io::AsyncReadExt;
+use std::path::Path;
+
+/// Prints information about the rnote file to stdout.
+///
+/// When `json` is true, the entire document is additionally dumped as pretty-printed Json.
+pub(crate) async fn inspect_file(
+    engine: &mut RnoteEngine,
+    rnote_file: impl AsRef<Path>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let mut rnote_bytes = vec![];
+    File::open(rnote_file.as_ref())
+        .await?
+        .read_to_end(&mut rnote_bytes)
+        .await?;
+    let file_size = rnote_bytes.len();
+    let version = RnoteFile::load_version_from_bytes(&rnote_bytes)?;
+    let snapshot = EngineSnapshot::load_from_rnote_bytes(rnote_bytes, Progress::default()).await?;
+
+    println!("File: \"{}\"", rnote_file.as_ref().display());
+    println!("  file size: {}", format_bytes(file_size));
+    println!("  format version: {version}");
+
+    let mut n_brushstrokes = 0;
+    let mut n_shapestrokes = 0;
+    let mut n_textstrokes = 0;
+    let mut vectorimages = vec![];
+    let mut bitmapimages = vec![];
+    for stroke in snapshot.stroke_components.values() {
+        match stroke.as_ref() {
+            Stroke::BrushStroke(_) => n_brushstrokes += 1,
+            Stroke::ShapeStroke(_) => n_shapestrokes += 1,
+            Stroke::TextStroke(_) => n_textstrokes += 1,
+            Stroke::VectorImage(vectorimage) => vectorimages.push(vectorimage),
+            Stroke::BitmapImage(bitmapimage) => bitmapimages.push(bitmapimage),
+        }
+    }
+
+    let document = snapshot.document;
+    println!("Document:");
+    println!("  layout: {}", document.layout.to_string());
+    println!(
+        "  bounds: x: {:.1}, y: {:.1}, width: {:.1}, height: {:.1}",
+        document.x, document.y, document.width, document.height
+    );
+    println!(
+        "  format: width: {:.1}, height: {:.1}, dpi: {:.1}",
+        document.format.width, document.format.height, document.format.dpi
+    );
+
+    let _ = engine.load_snapshot(snapshot.clone());
+    println!("  pages: {}", engine.document.calc_n_pages());
+    println!(
+        "  pages with content: {}",
+        engine.pages_bounds_w_content(SplitOrder::default()).len()
+    );
+
+    let metadata = &snapshot.metadata;
+    if !metadata.is_empty() {
+        println!("Metadata:");
+        println!("  title: {}", metadata.title);
+        println!("  author: {}", metadata.author);
+        println!("  subject: {}", metadata.subject);
+        println!("  keywords: {}", metadata.keywords_joined());
+        for (key, value) in metadata.custom.iter() {
+            println!("  {key}: {value}");
+        }
+    }
+
+    println!("Strokes: {}", snapshot.stroke_components.len());
+    println!("  brushstrokes: {n_brushstrokes}");
+    println!("  shapestrokes: {n_shapestrokes}");
+    println!("  textstrokes: {n_textstrokes}");
+    println!("  vectorimages: {}", vectorimages.len());
+    for (i, vectorimage) in vectorimages.iter().enumerate() {
+        let bounds = vectorimage.bounds();
+        println!(
+            "    [{i}] svg data: {}, intrinsic size: {:.1}x{:.1}, at: ({:.1}, {:.1})",
+            format_bytes(vectorimage.svg_data.len()),
+            vectorimage.intrinsic_size[0],
+            vectorimage.intrinsic_size[1],
+            bounds.mins[0],
+            bounds.mins[1]
+        );
+    }
+    println!("  bitmapimages: {}", bitmapimages.len());
+    for (i, bitmapimage) in bitmapimages.iter().enumerate() {
+        let bounds = bitmapimage.bounds();
+        println!(
+            "    [{i}] pixel data: {}, pixel size: {}x{}, at: ({:.1}, {:.1})",
+            format_bytes(bitmapimage.image.data.len()),
+            bitmapimage.image.pixel_width,
+            bitmapimage.image.pixel_height,
+            bounds.mins[0],
+            bounds.mins[1]
+        );
+    }
+
+    println!("Embedded fonts: {}", snapshot.embedded_fonts.len());
+    for embedded_font in snapshot.embedded_fonts.iter() {
+        println!(
+            "  \"{}\": {}",
+            embedded_font.family,
+            format_bytes(embedded_font.data.len())
+        );
+    }
+
+    if json {
+        println!("Json:");
+        println!("{}", serde_json::to_string_pretty(&snapshot)?);
+    }
+
+    Ok(())
+}
+
+fn format_bytes(n_bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = n_bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{n_bytes} {}", UNITS[unit])
+    } else {
+        format!("{size:.2} {}", UNITS[unit])
+    }
+}
@@ -3,7 +3,47 @@
 //! The cli interface is not (yet) stable and could change at any time.
 
 pub(crate) mod cli;
+pub(crate) mod convert;
+pub(crate) mod inspect;
+pub(crate) mod merge;
+pub(crate) mod render;
+pub(crate) mod repair;
+#[cfg(feature = "scripting")]
+pub(crate) mod script;
+pub(crate) mod transform;
+pub(crate) mod watch;
 
-fn main() -> anyhow::Result<()> {
-    smol::block_on(async { cli::run().await })
+// Imports
+use rnote_engine::EngineError;
+use std::process::ExitCode;
+
+// Renames
+extern crate nalgebra as na;
+extern crate parry2d_f64 as p2d;
+
+fn main() -> ExitCode {
+    match smol::block_on(async { cli::run().await }) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            if matches!(EngineError::find(&e), Some(EngineError::Background)) {
+                eprintln!("Hint: the background could not be rendered, try exporting with `--with-background false`.");
+            }
+            exit_code(&e)
+        }
+    }
+}
+
+/// The exit code for the error, distinguishing the kinds of engine errors.
+///
+/// Follows the conventions of `sysexits.h`, so scripts can tell unreadable input apart from failed I/O.
+fn exit_code(e: &anyhow::Error) -> ExitCode {
+    match EngineError::find(e) {
+        Some(EngineError::Decode(_))
+        | Some(EngineError::UnsupportedFormat(_))
+        | Some(EngineError::InvalidGeometry(_)) => ExitCode::from(65),
+        Some(EngineError::Io(_)) => ExitCode::from(74),
+        Some(EngineError::Cancelled) => ExitCode::from(130),
+        Some(EngineError::Background) | None => ExitCode::FAILURE,
+    }
 }
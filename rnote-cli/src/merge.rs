@@ -0,0 +1,69 @@
+// Imports
+use rnote_engine::engine::EngineSnapshot;
+use rnote_engine::{Progress, RnoteEngine};
+use smol::fs::File;
+use smol::io::{AsyncReadExt, AsyncWriteExt};
+use std::path::{Path, PathBuf};
+
+/// Merges the given rnote files into one document and saves it as the output file.
+///
+/// The first file determines the document format, layout and background.
+/// The following files are appended page-wise, or side-by-side when the first document has an infinite layout.
+pub(crate) async fn merge_files(
+    engine: &mut RnoteEngine,
+    rnote_files: &[PathBuf],
+    output_file: impl AsRef<Path>,
+) -> anyhow::Result<()> {
+    let Some(output_file_name) = output_file
+        .as_ref()
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+    else {
+        return Err(anyhow::anyhow!("Failed to get filename from output_file"));
+    };
+    let mut rnote_files = rnote_files.iter();
+    let Some(first_file) = rnote_files.next() else {
+        return Err(anyhow::anyhow!("No files to merge"));
+    };
+
+    let _ = engine.load_snapshot(load_snapshot(first_file).await?);
+    for rnote_file in rnote_files {
+        let snapshot = load_snapshot(rnote_file).await?;
+        let (format, merged_format) = (snapshot.document.format, engine.document.format);
+        if format.width != merged_format.width
+            || format.height != merged_format.height
+            || format.dpi != merged_format.dpi
+        {
+            log::warn!(
+                "The format of \"{}\" differs from the format of the merged document, using the latter.",
+                rnote_file.display()
+            );
+        }
+        let _ = engine.append_snapshot(snapshot);
+    }
+
+    let rnote_bytes = engine.save_as_rnote_bytes(output_file_name).await??;
+
+    let mut fh = File::create(output_file).await?;
+    fh.write_all(&rnote_bytes).await?;
+    fh.sync_all().await?;
+
+    Ok(())
+}
+
+async fn load_snapshot(rnote_file: impl AsRef<Path>) -> anyhow::Result<EngineSnapshot> {
+    let mut rnote_bytes = vec![];
+    File::open(rnote_file.as_ref())
+        .await?
+        .read_to_end(&mut rnote_bytes)
+        .await?;
+
+    EngineSnapshot::load_from_rnote_bytes(rnote_bytes, Progress::default())
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "Loading \"{}\" failed, Err: {e:?}",
+                rnote_file.as_ref().display()
+            )
+        })
+}
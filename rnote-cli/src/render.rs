@@ -0,0 +1,66 @@
+// Imports
+use p2d::bounding_volume::Aabb;
+use rnote_engine::engine::EngineSnapshot;
+use rnote_engine::{Progress, RnoteEngine};
+use smol::fs::File;
+use smol::io::{AsyncReadExt, AsyncWriteExt};
+use std::path::Path;
+
+/// Parses a region in the form `x,y,width,height`, in document coordinates.
+pub(crate) fn parse_region(region: &str) -> anyhow::Result<Aabb> {
+    let values = region
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|e| anyhow::anyhow!("invalid region `{region}`, Err: {e:?}"))?;
+    let [x, y, width, height] = values[..] else {
+        return Err(anyhow::anyhow!(
+            "invalid region `{region}`, expected the form `x,y,width,height`"
+        ));
+    };
+    if width <= 0.0 || height <= 0.0 {
+        return Err(anyhow::anyhow!(
+            "invalid region `{region}`, width and height must be positive"
+        ));
+    }
+    Ok(Aabb::new(
+        na::point![x, y],
+        na::point![x + width, y + height],
+    ))
+}
+
+/// Renders a region of the rnote file into a Png image and saves it as the output file.
+///
+/// When no region is given, the entire document is rendered.
+/// When a size is given, the image is scaled so that its longer side is the size in pixels, which is what thumbnailers expect.
+pub(crate) async fn render_file(
+    engine: &mut RnoteEngine,
+    rnote_file: impl AsRef<Path>,
+    output_file: impl AsRef<Path>,
+    region: Option<Aabb>,
+    scale: f64,
+    size: Option<u32>,
+) -> anyhow::Result<()> {
+    let mut rnote_bytes = vec![];
+    File::open(rnote_file.as_ref())
+        .await?
+        .read_to_end(&mut rnote_bytes)
+        .await?;
+    let snapshot = EngineSnapshot::load_from_rnote_bytes(rnote_bytes, Progress::default()).await?;
+    let _ = engine.load_snapshot(snapshot);
+
+    let region = region.unwrap_or_else(|| engine.document.bounds());
+    let image_scale = match size {
+        Some(size) => f64::from(size.max(1)) / region.extents().max(),
+        None => scale,
+    };
+    let png_bytes = engine
+        .render_region(region, image_scale)?
+        .into_encoded_bytes(image::ImageOutputFormat::Png)?;
+
+    let mut fh = File::create(output_file).await?;
+    fh.write_all(&png_bytes).await?;
+    fh.sync_all().await?;
+
+    Ok(())
+}
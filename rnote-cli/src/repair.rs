@@ -0,0 +1,62 @@
+// Imports
+use rnote_engine::engine::EngineSnapshot;
+use rnote_engine::RnoteEngine;
+use smol::fs::File;
+use smol::io::{AsyncReadExt, AsyncWriteExt};
+use std::path::{Path, PathBuf};
+
+/// The default output file for the repaired copy of the rnote file, `<name>-repaired.rnote` next to it.
+pub(crate) fn default_output_file(rnote_file: impl AsRef<Path>) -> PathBuf {
+    let stem = rnote_file
+        .as_ref()
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    rnote_file
+        .as_ref()
+        .with_file_name(format!("{stem}-repaired.rnote"))
+}
+
+/// Recovers as much as possible from a damaged or truncated rnote file and saves a repaired copy as the output file.
+///
+/// Prints a report about the problems found and the strokes that were dropped.
+pub(crate) async fn repair_file(
+    engine: &mut RnoteEngine,
+    rnote_file: impl AsRef<Path>,
+    output_file: impl AsRef<Path>,
+) -> anyhow::Result<()> {
+    let Some(output_file_name) = output_file
+        .as_ref()
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+    else {
+        return Err(anyhow::anyhow!("Failed to get filename from output_file"));
+    };
+
+    let mut rnote_bytes = vec![];
+    File::open(rnote_file.as_ref())
+        .await?
+        .read_to_end(&mut rnote_bytes)
+        .await?;
+
+    let (snapshot, report) = EngineSnapshot::repair_from_rnote_bytes(rnote_bytes).await?;
+
+    println!("Repair report for \"{}\":", rnote_file.as_ref().display());
+    if report.is_clean() {
+        println!("  no problems found");
+    }
+    for issue in report.issues.iter() {
+        println!("  - {issue}");
+    }
+    println!("  strokes recovered: {}", report.n_strokes_recovered);
+    println!("  strokes dropped: {}", report.n_strokes_dropped);
+
+    let _ = engine.load_snapshot(snapshot);
+    let rnote_bytes = engine.save_as_rnote_bytes(output_file_name).await??;
+
+    let mut fh = File::create(output_file).await?;
+    fh.write_all(&rnote_bytes).await?;
+    fh.sync_all().await?;
+
+    Ok(())
+}
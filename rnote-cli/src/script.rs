@@ -0,0 +1,71 @@
+// Imports
+use crate::cli;
+use rnote_engine::engine::EngineSnapshot;
+use rnote_engine::{Progress, RnoteEngine};
+use smol::fs::File;
+use smol::io::{AsyncReadExt, AsyncWriteExt};
+use std::path::Path;
+
+/// Runs the script on the rnote file, saves the modified document as the output file and performs the exports the script requested.
+///
+/// Export file names are relative to the current working directory.
+pub(crate) async fn run_script(
+    engine: &mut RnoteEngine,
+    rnote_file: impl AsRef<Path>,
+    script_file: impl AsRef<Path>,
+    output_file: impl AsRef<Path>,
+) -> anyhow::Result<()> {
+    let mut rnote_bytes = vec![];
+    File::open(rnote_file.as_ref())
+        .await?
+        .read_to_end(&mut rnote_bytes)
+        .await?;
+    let mut script = String::new();
+    File::open(script_file.as_ref())
+        .await?
+        .read_to_string(&mut script)
+        .await?;
+
+    let engine_snapshot =
+        EngineSnapshot::load_from_rnote_bytes(rnote_bytes, Progress::default()).await?;
+    let _ = engine.load_snapshot(engine_snapshot);
+
+    let (outcome, _) = engine.run_script(&script)?;
+    for line in outcome.output.iter() {
+        println!("{line}");
+    }
+
+    // The output file is the edited file itself by default, so it is replaced atomically
+    engine
+        .save_as_rnote_file(output_file.as_ref().to_path_buf())
+        .await??;
+
+    for export_file in outcome.export_requests.iter() {
+        let export_file = Path::new(export_file);
+        let Some(export_file_name) = export_file
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+        else {
+            return Err(anyhow::anyhow!(
+                "Failed to get filename from requested export file `{}`",
+                export_file.display()
+            ));
+        };
+        let prefs = cli::create_doc_export_prefs_from_args(Some(export_file), None, None, None)?;
+        let export_bytes = cli::export_doc_to_bytes(
+            engine,
+            export_file,
+            export_file_name,
+            Some(prefs),
+            Progress::default(),
+        )
+        .await?;
+
+        let mut fh = File::create(export_file).await?;
+        fh.write_all(&export_bytes).await?;
+        fh.sync_all().await?;
+        println!("Exported to: \"{}\"", export_file.display());
+    }
+
+    Ok(())
+}
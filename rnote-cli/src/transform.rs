@@ -0,0 +1,90 @@
+// Imports
+use rnote_compose::Color;
+use rnote_engine::RnoteEngine;
+
+/// Transformations that are applied to the loaded document before it is exported.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ExportTransforms {
+    /// Crop the document to the bounds of its content.
+    pub(crate) crop_to_content: bool,
+    /// Scale the document by the factor.
+    pub(crate) scale: Option<f64>,
+    /// Rotate the document by the angle, in degrees.
+    pub(crate) rotate: Option<f64>,
+    /// Replace the first color with the second.
+    pub(crate) color_maps: Vec<(Color, Color)>,
+}
+
+impl ExportTransforms {
+    pub(crate) fn new(
+        crop_to_content: bool,
+        scale: Option<f64>,
+        rotate: Option<f64>,
+        map_colors: &[String],
+    ) -> anyhow::Result<Self> {
+        if let Some(scale) = scale {
+            if !scale.is_finite() || scale <= 0.0 {
+                return Err(anyhow::anyhow!(
+                    "Invalid scale factor `{scale}`, must be larger than zero"
+                ));
+            }
+        }
+        if let Some(rotate) = rotate {
+            if !rotate.is_finite() {
+                return Err(anyhow::anyhow!("Invalid rotation angle `{rotate}`"));
+            }
+        }
+        let color_maps = map_colors
+            .iter()
+            .map(|map_color| parse_color_map(map_color))
+            .collect::<anyhow::Result<Vec<(Color, Color)>>>()?;
+
+        Ok(Self {
+            crop_to_content,
+            scale,
+            rotate,
+            color_maps,
+        })
+    }
+
+    /// Applies the transformations to the document currently loaded in the engine.
+    ///
+    /// Colors are replaced first, then the document is rotated, scaled and finally cropped.
+    pub(crate) fn apply(&self, engine: &mut RnoteEngine) {
+        for (from, to) in self.color_maps.iter() {
+            let _ = engine.doc_replace_color(*from, *to);
+        }
+        if let Some(rotate) = self.rotate {
+            let _ = engine.doc_rotate(rotate.to_radians());
+        }
+        if let Some(scale) = self.scale {
+            let _ = engine.doc_scale(scale);
+        }
+        if self.crop_to_content {
+            let _ = engine.doc_crop_to_content();
+        }
+    }
+}
+
+/// Parses a color map in the form `old=new`, where the colors are given in hex notation (`#rrggbb` or `#rrggbbaa`).
+fn parse_color_map(map_color: &str) -> anyhow::Result<(Color, Color)> {
+    let Some((from, to)) = map_color.split_once('=') else {
+        return Err(anyhow::anyhow!(
+            "Invalid color map `{map_color}`, expected the form `old=new`"
+        ));
+    };
+    Ok((parse_hex_color(from)?, parse_hex_color(to)?))
+}
+
+pub(crate) fn parse_hex_color(color: &str) -> anyhow::Result<Color> {
+    let hex = color.trim().trim_start_matches('#');
+    let value = u32::from_str_radix(hex, 16)
+        .map_err(|e| anyhow::anyhow!("Invalid color `{color}`, Err: {e:?}"))?;
+    match hex.len() {
+        6 => Ok(Color::from((value << 8) | 0xff)),
+        8 => Ok(Color::from(value)),
+        _ => Err(anyhow::anyhow!(
+            "Invalid color `{color}`, expected `#rrggbb` or `#rrggbbaa`"
+        )),
+    }
+}
@@ -0,0 +1,94 @@
+// Imports
+use crate::cli;
+use crate::transform::ExportTransforms;
+use notify::{EventKind, RecursiveMode, Watcher};
+use rnote_engine::{Progress, RnoteEngine};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Saving a file often emits multiple events in quick succession, which are collected within this timeout.
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Watches the input rnote files and re-exports them to their output files when they change.
+///
+/// Runs until the process is terminated. Failed exports are reported but do not stop watching.
+pub(crate) async fn watch_export(
+    engine: &mut RnoteEngine,
+    export_files: Vec<(PathBuf, PathBuf)>,
+    transforms: &ExportTransforms,
+) -> anyhow::Result<()> {
+    let (event_sender, event_receiver) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(event_sender)?;
+
+    // The parent directories are watched, because files are often replaced on save instead of modified in place.
+    let mut watched_dirs = HashSet::new();
+    let mut watched_files = vec![];
+    for (rnote_file, output_file) in export_files {
+        let Some(file_name) = rnote_file.file_name() else {
+            return Err(anyhow::anyhow!(
+                "Failed to get filename from rnote file \"{}\"",
+                rnote_file.display()
+            ));
+        };
+        let dir = match rnote_file.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.canonicalize()?,
+            _ => std::env::current_dir()?,
+        };
+        watched_files.push((dir.join(file_name), rnote_file, output_file));
+        watched_dirs.insert(dir);
+    }
+    for dir in watched_dirs.iter() {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+
+    println!(
+        "Watching {} file(s) for changes, press Ctrl+C to stop..",
+        watched_files.len()
+    );
+
+    loop {
+        let mut changed = HashSet::new();
+        let mut collect_changed = |event: notify::Result<notify::Event>| match event {
+            Ok(event) => {
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    changed.extend(event.paths);
+                }
+            }
+            Err(e) => log::error!("Watching files for changes reported an error, Err: {e:?}"),
+        };
+
+        collect_changed(event_receiver.recv()?);
+        while let Ok(event) = event_receiver.recv_timeout(DEBOUNCE_TIMEOUT) {
+            collect_changed(event);
+        }
+
+        for (_, rnote_file, output_file) in watched_files
+            .iter()
+            .filter(|(watched_path, _, _)| changed.contains(watched_path))
+        {
+            // The file might have been removed or only be partially written, in which case it is picked up with the next event
+            if !rnote_file.exists() {
+                continue;
+            }
+            let rnote_file_disp = rnote_file.display().to_string();
+            let output_file_disp = output_file.display().to_string();
+
+            if let Err(e) = cli::export_to_file(
+                engine,
+                rnote_file,
+                output_file,
+                transforms,
+                Progress::default(),
+            )
+            .await
+            {
+                println!(
+                    "Re-export \"{rnote_file_disp}\" to: \"{output_file_disp}\" failed, Err {e:?}"
+                );
+            } else {
+                println!("Re-export \"{rnote_file_disp}\" to: \"{output_file_disp}\" succeeded");
+            }
+        }
+    }
+}
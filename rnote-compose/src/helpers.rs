@@ -152,7 +152,7 @@ where
     fn new_zero() -> Self;
     /// New Aabb, ensuring its mins, maxs are valid (maxs >= mins)
     fn new_positive(start: na::Point2<f64>, end: na::Point2<f64>) -> Self;
-    /// Asserts the Aabb is valid
+    /// Asserts the Aabb is valid, meaning it has finite coordinates and non-negative extents
     fn assert_valid(&self) -> anyhow::Result<()>;
     /// Translates the Aabb by a offset
     fn translate(&self, offset: na::Vector2<f64>) -> Self;
@@ -222,7 +222,13 @@ impl AabbHelpers for Aabb {
     }
 
     fn assert_valid(&self) -> anyhow::Result<()> {
-        if self.extents()[0] < 0.0
+        if !self
+            .mins
+            .coords
+            .iter()
+            .chain(self.maxs.coords.iter())
+            .all(|v| v.is_finite())
+            || self.extents()[0] < 0.0
             || self.extents()[1] < 0.0
             || self.maxs[0] < self.mins[0]
             || self.maxs[1] < self.mins[1]
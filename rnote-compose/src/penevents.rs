@@ -127,6 +127,9 @@ pub enum ShortcutKey {
     /// Secondary button of the stylus.
     #[serde(rename = "stylus_secondary_button")]
     StylusSecondaryButton,
+    /// The eraser end of the stylus touching the surface.
+    #[serde(rename = "stylus_eraser_end")]
+    StylusEraserEnd,
     /// Secondary mouse button.
     #[serde(rename = "mouse_secondary_button")]
     MouseSecondaryButton,
@@ -138,6 +138,119 @@ impl PenPath {
         Some(Self { start, segments })
     }
 
+    /// Simplify the path with the Ramer-Douglas-Peucker algorithm.
+    ///
+    /// Elements that deviate less than `epsilon` from the simplified path are removed.
+    /// The shape of the segments is lost, the simplified path consists of line segments.
+    pub fn simplified(&self, epsilon: f64) -> Self {
+        let elements = self.clone().into_elements();
+        let last = elements.len() - 1;
+        let mut keep = vec![false; elements.len()];
+        keep[0] = true;
+        keep[last] = true;
+
+        // Iterative instead of recursive, because imported paths can consist of a huge number of elements
+        let mut ranges = vec![(0, last)];
+        while let Some((first, last)) = ranges.pop() {
+            if last <= first + 1 {
+                continue;
+            }
+            let (farthest, dist) = elements[first + 1..last]
+                .iter()
+                .enumerate()
+                .map(|(i, el)| {
+                    (
+                        first + 1 + i,
+                        dist_to_line_segment(el.pos, elements[first].pos, elements[last].pos),
+                    )
+                })
+                .fold(
+                    (first, 0.0),
+                    |acc, (i, dist)| {
+                        if dist > acc.1 {
+                            (i, dist)
+                        } else {
+                            acc
+                        }
+                    },
+                );
+            if dist > epsilon {
+                keep[farthest] = true;
+                ranges.push((first, farthest));
+                ranges.push((farthest, last));
+            }
+        }
+
+        let mut simplified = elements
+            .into_iter()
+            .zip(keep)
+            .filter_map(|(el, keep)| keep.then_some(el));
+        // There is always at least the start element
+        let start = simplified.next().unwrap();
+        Self::new_w_segments(start, simplified.map(|end| Segment::LineTo { end }))
+    }
+
+    /// Smooth the path by repeatedly moving each element towards the center of its neighbours.
+    ///
+    /// Elements are moved proportionally to the local curvature, so jitter is removed while
+    /// the overall shape is kept. `strength` ranges from 0.0 (unchanged) to 1.0.
+    /// The first and last element stay in place and the shape of the segments is lost.
+    pub fn smoothed(&self, strength: f64, iterations: usize) -> Self {
+        let factor = strength.clamp(0.0, 1.0) * 0.5;
+        let mut elements = self.clone().into_elements();
+
+        for _ in 0..iterations {
+            let prev_elements = elements.clone();
+            for (i, el) in elements
+                .iter_mut()
+                .enumerate()
+                .skip(1)
+                .take(prev_elements.len().saturating_sub(2))
+            {
+                let neighbours_center = (prev_elements[i - 1].pos + prev_elements[i + 1].pos) * 0.5;
+                let neighbours_pressure =
+                    (prev_elements[i - 1].pressure + prev_elements[i + 1].pressure) * 0.5;
+                el.pos += (neighbours_center - el.pos) * factor;
+                el.pressure += (neighbours_pressure - el.pressure) * factor;
+            }
+        }
+
+        let mut elements = elements.into_iter();
+        let start = elements.next().unwrap();
+        Self::new_w_segments(start, elements.map(|end| Segment::LineTo { end }))
+    }
+
+    /// Connect the elements of the path with smooth catmull-rom curves.
+    pub fn curved(&self) -> Self {
+        let elements = self.clone().into_elements();
+        if elements.len() < 3 {
+            return Self::new_w_segments(
+                elements[0],
+                elements[1..].iter().map(|&end| Segment::LineTo { end }),
+            );
+        }
+
+        let segments = (0..elements.len() - 1).map(|i| {
+            // The end elements are duplicated to have neighbours for the first and last curve
+            let first = elements[i.saturating_sub(1)].pos;
+            let second = elements[i].pos;
+            let third = elements[i + 1].pos;
+            let forth = elements[(i + 2).min(elements.len() - 1)].pos;
+            let end = elements[i + 1];
+
+            match CubicBezier::new_w_catmull_rom(first, second, third, forth) {
+                Some(cubbez) => Segment::CubBezTo {
+                    cp1: cubbez.cp1,
+                    cp2: cubbez.cp2,
+                    end,
+                },
+                None => Segment::LineTo { end },
+            }
+        });
+
+        Self::new_w_segments(elements[0], segments)
+    }
+
     /// Checks whether bounds collide with the path. If it does, it returns the indices of the colliding segments
     ///
     /// `loosened` loosens the segments hitboxes by the value
@@ -262,6 +375,21 @@ impl Extend<Segment> for PenPath {
     }
 }
 
+/// The distance of the point to the line segment between start and end.
+fn dist_to_line_segment(
+    point: na::Vector2<f64>,
+    start: na::Vector2<f64>,
+    end: na::Vector2<f64>,
+) -> f64 {
+    let line = end - start;
+    let len_squared = line.norm_squared();
+    if len_squared == 0.0 {
+        return (point - start).norm();
+    }
+    let t = ((point - start).dot(&line) / len_squared).clamp(0.0, 1.0);
+    (point - (start + line * t)).norm()
+}
+
 /// Calculates the number subsegment elements (for hitboxes/ flattening of bezier curve)
 /// for the given segment length, capped with a maximum no of hitbox elements
 pub(crate) fn no_subsegments_for_segment_len(len: f64) -> i32 {
@@ -4,6 +4,7 @@ mod arrow;
 pub mod cubbez;
 mod ellipse;
 mod line;
+mod polygon;
 /// quadratic bezier curves
 pub mod quadbez;
 mod rectangle;
@@ -15,6 +16,7 @@ pub use arrow::Arrow;
 pub use cubbez::CubicBezier;
 pub use ellipse::Ellipse;
 pub use line::Line;
+pub use polygon::Polygon;
 pub use quadbez::QuadraticBezier;
 pub use rectangle::Rectangle;
 pub use shape::Shape;
@@ -0,0 +1,92 @@
+// Imports
+use super::Line;
+use crate::helpers::Vector2Helpers;
+use crate::shapes::ShapeBehaviour;
+use crate::transform::TransformBehaviour;
+use p2d::bounding_volume::Aabb;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "polygon")]
+/// A closed polygon.
+pub struct Polygon {
+    #[serde(rename = "points")]
+    /// The points. The last point is connected back to the first.
+    pub points: Vec<na::Vector2<f64>>,
+}
+
+impl TransformBehaviour for Polygon {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.points.iter_mut().for_each(|point| *point += offset);
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        let mut isometry = na::Isometry2::identity();
+        isometry.append_rotation_wrt_point_mut(&na::UnitComplex::new(angle), &center);
+
+        self.points
+            .iter_mut()
+            .for_each(|point| *point = isometry.transform_point(&(*point).into()).coords);
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.points
+            .iter_mut()
+            .for_each(|point| *point = point.component_mul(&scale));
+    }
+}
+
+impl ShapeBehaviour for Polygon {
+    fn bounds(&self) -> Aabb {
+        let Some(first) = self.points.first() else {
+            return Aabb::new(na::point![0.0, 0.0], na::point![0.0, 0.0]);
+        };
+
+        self.points.iter().fold(
+            Aabb::new((*first).into(), (*first).into()),
+            |mut bounds, point| {
+                bounds.take_point((*point).into());
+                bounds
+            },
+        )
+    }
+
+    fn hitboxes(&self) -> Vec<Aabb> {
+        self.outline_lines()
+            .into_iter()
+            .flat_map(|line| line.hitboxes())
+            .collect()
+    }
+}
+
+impl Polygon {
+    /// Create a new polygon from the points.
+    pub fn new(points: Vec<na::Vector2<f64>>) -> Self {
+        Self { points }
+    }
+
+    /// The outline lines, including the one closing the polygon.
+    pub fn outline_lines(&self) -> Vec<Line> {
+        self.points
+            .iter()
+            .zip(self.points.iter().cycle().skip(1))
+            .map(|(start, end)| Line {
+                start: *start,
+                end: *end,
+            })
+            .collect()
+    }
+
+    /// Convert to kurbo shape.
+    pub fn to_kurbo(&self) -> kurbo::BezPath {
+        let mut bez_path = kurbo::BezPath::new();
+        let mut points = self.points.iter();
+
+        if let Some(first) = points.next() {
+            bez_path.move_to(first.to_kurbo_point());
+            points.for_each(|point| bez_path.line_to(point.to_kurbo_point()));
+            bez_path.close_path();
+        }
+        bez_path
+    }
+}
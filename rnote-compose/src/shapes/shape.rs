@@ -1,5 +1,7 @@
 // Imports
-use super::{Arrow, CubicBezier, Ellipse, Line, QuadraticBezier, Rectangle, ShapeBehaviour};
+use super::{
+    Arrow, CubicBezier, Ellipse, Line, Polygon, QuadraticBezier, Rectangle, ShapeBehaviour,
+};
 use crate::transform::TransformBehaviour;
 use p2d::bounding_volume::Aabb;
 use serde::{Deserialize, Serialize};
@@ -26,6 +28,9 @@ pub enum Shape {
     #[serde(rename = "cubbez")]
     /// A cubic bezier curve shape.
     CubicBezier(CubicBezier),
+    #[serde(rename = "polygon")]
+    /// A polygon shape.
+    Polygon(Polygon),
 }
 
 impl Default for Shape {
@@ -55,6 +60,9 @@ impl TransformBehaviour for Shape {
             Self::CubicBezier(cubbez) => {
                 cubbez.translate(offset);
             }
+            Self::Polygon(polygon) => {
+                polygon.translate(offset);
+            }
         }
     }
 
@@ -78,6 +86,9 @@ impl TransformBehaviour for Shape {
             Self::CubicBezier(cubbez) => {
                 cubbez.rotate(angle, center);
             }
+            Self::Polygon(polygon) => {
+                polygon.rotate(angle, center);
+            }
         }
     }
 
@@ -101,6 +112,9 @@ impl TransformBehaviour for Shape {
             Self::CubicBezier(cubbez) => {
                 cubbez.scale(scale);
             }
+            Self::Polygon(polygon) => {
+                polygon.scale(scale);
+            }
         }
     }
 }
@@ -114,6 +128,7 @@ impl ShapeBehaviour for Shape {
             Self::Ellipse(ellipse) => ellipse.bounds(),
             Self::QuadraticBezier(quadbez) => quadbez.bounds(),
             Self::CubicBezier(cubbez) => cubbez.bounds(),
+            Self::Polygon(polygon) => polygon.bounds(),
         }
     }
     fn hitboxes(&self) -> Vec<Aabb> {
@@ -124,6 +139,7 @@ impl ShapeBehaviour for Shape {
             Self::Ellipse(ellipse) => ellipse.hitboxes(),
             Self::QuadraticBezier(quadbez) => quadbez.hitboxes(),
             Self::CubicBezier(cubbez) => cubbez.hitboxes(),
+            Self::Polygon(polygon) => polygon.hitboxes(),
         }
     }
 }
@@ -0,0 +1,71 @@
+// Imports
+use crate::style::PressureCurve;
+use crate::Color;
+use serde::{Deserialize, Serialize};
+
+/// Options for shapes that can be drawn in an airbrush style.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "airbrush_options")]
+pub struct AirbrushOptions {
+    /// An optional seed to generate reproducible sprays.
+    #[serde(rename = "seed")]
+    pub seed: Option<u64>,
+    /// Stroke width, the diameter of the spray.
+    #[serde(rename = "stroke_width", with = "crate::serialize::f64_dp3")]
+    pub stroke_width: f64,
+    /// Stroke color. When set to None, the stroke is not drawn.
+    #[serde(rename = "stroke_color")]
+    pub stroke_color: Option<Color>,
+    /// The amount of particles that are sprayed per second.
+    #[serde(rename = "flow", with = "crate::serialize::f64_dp3")]
+    pub flow: f64,
+    /// Pressure curve, applied to the flow.
+    #[serde(rename = "pressure_curve")]
+    pub pressure_curve: PressureCurve,
+}
+
+impl Default for AirbrushOptions {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            stroke_width: 30.0,
+            stroke_color: Some(Color::BLACK),
+            flow: Self::FLOW_DEFAULT,
+            pressure_curve: PressureCurve::Linear,
+        }
+    }
+}
+
+impl AirbrushOptions {
+    /// The interval (in seconds) in which the elements of airbrush paths are emitted.
+    ///
+    /// Every segment of the path sprays the particles of one interval, so the spray accumulates while the pen is held.
+    pub const EMISSION_INTERVAL: f64 = 1.0 / 60.0;
+    /// Minimum flow.
+    pub const FLOW_MIN: f64 = 10.0;
+    /// Maximum flow.
+    pub const FLOW_MAX: f64 = 5000.0;
+    /// Default flow.
+    pub const FLOW_DEFAULT: f64 = 600.0;
+    /// The opacity of a single particle, relative to the stroke color.
+    pub(super) const PARTICLE_OPACITY: f64 = 0.35;
+    /// The particle radius relative to the stroke width.
+    pub(super) const PARTICLE_RADIUS_FACTOR: f64 = 0.02;
+    /// The particle radius range, independent of the stroke width.
+    pub(super) const PARTICLE_RADIUS_RANGE: (f64, f64) = (0.4, 2.0);
+    /// The number of layers the particles are distributed on.
+    ///
+    /// Overlapping particles on the same layer are united, on different layers their opacities add up.
+    pub(super) const PARTICLE_LAYERS: usize = 8;
+
+    /// Advances the seed.
+    pub fn advance_seed(&mut self) {
+        self.seed = self.seed.map(crate::utils::seed_advance)
+    }
+
+    /// The radius of the particles.
+    pub(super) fn particle_radius(&self) -> f64 {
+        (self.stroke_width * Self::PARTICLE_RADIUS_FACTOR)
+            .clamp(Self::PARTICLE_RADIUS_RANGE.0, Self::PARTICLE_RADIUS_RANGE.1)
+    }
+}
@@ -0,0 +1,110 @@
+// Modules
+mod airbrushoptions;
+
+// Re-exports
+pub use airbrushoptions::AirbrushOptions;
+
+// Imports
+use super::Composer;
+use crate::helpers::Vector2Helpers;
+use crate::penpath::{Element, Segment};
+use crate::shapes::{Line, ShapeBehaviour};
+use crate::PenPath;
+use kurbo::Shape;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rand::Rng;
+use rand_distr::{Distribution, Normal, Uniform};
+
+impl Composer<AirbrushOptions> for Line {
+    fn composed_bounds(&self, options: &AirbrushOptions) -> Aabb {
+        self.bounds()
+            .loosened(options.stroke_width * 0.5 + options.particle_radius())
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &AirbrushOptions) {
+        PenPath::new_w_segments(
+            Element::new(self.start, 1.0),
+            [Segment::LineTo {
+                end: Element::new(self.end, 1.0),
+            }],
+        )
+        .draw_composed(cx, options);
+    }
+}
+
+impl Composer<AirbrushOptions> for PenPath {
+    fn composed_bounds(&self, options: &AirbrushOptions) -> Aabb {
+        self.bounds()
+            .loosened(options.stroke_width * 0.5 + options.particle_radius())
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &AirbrushOptions) {
+        let Some(color) = options.stroke_color else {
+            return;
+        };
+        let spray_radius = options.stroke_width * 0.5;
+        if spray_radius <= 0.0 {
+            return;
+        }
+        let particle_radius = options.particle_radius();
+        // Most of the particles land near the center, with a soft falloff to the edge of the spray
+        let distr_offset = Normal::new(0.0, spray_radius * 0.5).unwrap();
+        let distr_t = Uniform::from(0.0..1.0);
+
+        let mut layers = vec![kurbo::BezPath::new(); AirbrushOptions::PARTICLE_LAYERS];
+        let mut n_particles_total = 0;
+        let mut spray = |start: Element, end: Element, seed: Option<u64>| {
+            let mut rng = crate::utils::new_rng_default_pcg64(seed);
+            let amount = (options.flow
+                * AirbrushOptions::EMISSION_INTERVAL
+                * options
+                    .pressure_curve
+                    .apply(1.0, (start.pressure + end.pressure) * 0.5))
+            .max(0.0);
+            // The fractional part is sprayed by chance, so that low flows still accumulate
+            let n_particles = amount.floor() as usize + usize::from(rng.gen_bool(amount.fract()));
+
+            for _ in 0..n_particles {
+                let center = start.pos.lerp(&end.pos, distr_t.sample(&mut rng));
+                let mut offset =
+                    na::vector![distr_offset.sample(&mut rng), distr_offset.sample(&mut rng)];
+                if offset.magnitude() > spray_radius {
+                    offset = offset.normalize() * spray_radius * distr_t.sample(&mut rng);
+                }
+                let particle =
+                    kurbo::Circle::new((center + offset).to_kurbo_point(), particle_radius);
+
+                layers[n_particles_total % AirbrushOptions::PARTICLE_LAYERS]
+                    .extend(particle.path_elements(0.1));
+                n_particles_total += 1;
+            }
+        };
+
+        if self.segments.is_empty() {
+            spray(self.start, self.start, options.seed);
+        }
+        let mut prev = self.start;
+        let mut seed = options.seed;
+        for seg in self.segments.iter() {
+            let end = seg.end();
+            // The particles are sprayed along the line between the elements, which are emitted in short intervals
+            spray(prev, end, seed);
+            seed = seed.map(crate::utils::seed_advance);
+            prev = end;
+        }
+
+        cx.save().unwrap();
+        let particle_color = piet::Color::rgba(
+            color.r,
+            color.g,
+            color.b,
+            color.a * AirbrushOptions::PARTICLE_OPACITY,
+        );
+        for layer in layers {
+            if !layer.elements().is_empty() {
+                cx.fill(layer, &particle_color);
+            }
+        }
+        cx.restore().unwrap();
+    }
+}
@@ -0,0 +1,54 @@
+// Imports
+use super::graintexture::GrainTexture;
+use crate::style::PressureCurve;
+use crate::Color;
+use serde::{Deserialize, Serialize};
+
+/// Options for shapes that can be drawn in a grain style.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "grain_options")]
+pub struct GrainOptions {
+    /// Stroke width.
+    #[serde(rename = "stroke_width", with = "crate::serialize::f64_dp3")]
+    pub stroke_width: f64,
+    /// Stroke color. When set to None, the stroke is not drawn.
+    #[serde(rename = "stroke_color")]
+    pub stroke_color: Option<Color>,
+    /// The grain texture.
+    #[serde(rename = "texture")]
+    pub texture: GrainTexture,
+    /// Pressure curve.
+    #[serde(rename = "pressure_curve")]
+    pub pressure_curve: PressureCurve,
+}
+
+impl Default for GrainOptions {
+    fn default() -> Self {
+        Self {
+            stroke_width: 4.0,
+            stroke_color: Some(Color::BLACK),
+            texture: GrainTexture::default(),
+            pressure_curve: PressureCurve::Const,
+        }
+    }
+}
+
+impl GrainOptions {
+    /// The opacity of strokes drawn with no pressure.
+    pub(super) const OPACITY_MIN: f64 = 0.25;
+    /// The number of discrete opacity levels that the pressure is mapped to.
+    ///
+    /// Parts of the stroke with the same level are drawn together, so they don't add up where they overlap.
+    pub(super) const OPACITY_LEVELS: usize = 8;
+
+    /// The opacity level for the given pressure, ranging [0, OPACITY_LEVELS).
+    pub(super) fn opacity_level(pressure: f64) -> usize {
+        let opacity = Self::OPACITY_MIN + (1.0 - Self::OPACITY_MIN) * pressure.clamp(0.0, 1.0);
+        ((opacity * Self::OPACITY_LEVELS as f64).ceil() as usize).clamp(1, Self::OPACITY_LEVELS) - 1
+    }
+
+    /// The opacity for the opacity level.
+    pub(super) fn level_opacity(level: usize) -> f64 {
+        (level + 1) as f64 / Self::OPACITY_LEVELS as f64
+    }
+}
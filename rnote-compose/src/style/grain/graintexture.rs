@@ -0,0 +1,134 @@
+// Imports
+use anyhow::Context;
+use once_cell::sync::Lazy;
+use rand_distr::{Distribution, Uniform};
+use serde::{Deserialize, Serialize};
+
+/// The grain texture that modulates the coverage of a grain styled stroke, imitating the medium on paper.
+#[derive(
+    Debug,
+    Eq,
+    PartialEq,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "grain_texture")]
+pub enum GrainTexture {
+    /// Fine, dense grain along the paper fibers.
+    #[serde(rename = "pencil")]
+    Pencil = 0,
+    /// Coarse, clumpy grain.
+    #[serde(rename = "charcoal")]
+    Charcoal,
+    /// Dense, streaky grain with a high coverage.
+    #[serde(rename = "marker")]
+    Marker,
+}
+
+impl Default for GrainTexture {
+    fn default() -> Self {
+        Self::Pencil
+    }
+}
+
+impl TryFrom<u32> for GrainTexture {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value)
+            .with_context(|| format!("GrainTexture try_from::<u32>() for value {value} failed"))
+    }
+}
+
+/// The parameters the stamp of a grain texture is generated with.
+struct StampParams {
+    n_grains: usize,
+    radius_x: (f64, f64),
+    radius_y: (f64, f64),
+    /// The maximum deviation of the grain rotation from the horizontal paper fibers.
+    rotation_spread: f64,
+}
+
+impl GrainTexture {
+    /// The extents of the square stamp, which is tiled in document coordinates.
+    pub const STAMP_SIZE: f64 = 32.0;
+    /// The maximum radius of a single grain, by which grains can reach into neighbouring tiles.
+    pub const GRAIN_RADIUS_MAX: f64 = 2.5;
+    /// A fixed seed, so that the textures are the same across sessions.
+    const STAMP_SEED: u64 = 0x6772_6169_6e00;
+
+    /// The opacity of the base shading under the grain, relative to the stroke color.
+    pub fn base_opacity(&self) -> f64 {
+        match self {
+            Self::Pencil => 0.15,
+            Self::Charcoal => 0.25,
+            Self::Marker => 0.5,
+        }
+    }
+
+    /// The precomputed stamp of the texture. Tiles seamlessly in every direction.
+    ///
+    /// Grains overlap the stamp bounds by up to `GRAIN_RADIUS_MAX`.
+    pub fn stamp(&self) -> &'static kurbo::BezPath {
+        static PENCIL_STAMP: Lazy<kurbo::BezPath> = Lazy::new(|| GrainTexture::Pencil.gen_stamp());
+        static CHARCOAL_STAMP: Lazy<kurbo::BezPath> =
+            Lazy::new(|| GrainTexture::Charcoal.gen_stamp());
+        static MARKER_STAMP: Lazy<kurbo::BezPath> = Lazy::new(|| GrainTexture::Marker.gen_stamp());
+
+        match self {
+            Self::Pencil => &PENCIL_STAMP,
+            Self::Charcoal => &CHARCOAL_STAMP,
+            Self::Marker => &MARKER_STAMP,
+        }
+    }
+
+    fn stamp_params(&self) -> StampParams {
+        match self {
+            Self::Pencil => StampParams {
+                n_grains: 360,
+                radius_x: (0.6, 1.4),
+                radius_y: (0.3, 0.7),
+                rotation_spread: std::f64::consts::FRAC_PI_8,
+            },
+            Self::Charcoal => StampParams {
+                n_grains: 140,
+                radius_x: (0.8, 2.5),
+                radius_y: (0.6, 1.6),
+                rotation_spread: std::f64::consts::FRAC_PI_2,
+            },
+            Self::Marker => StampParams {
+                n_grains: 420,
+                radius_x: (0.8, 1.6),
+                radius_y: (0.6, 1.2),
+                rotation_spread: std::f64::consts::FRAC_PI_8 * 0.5,
+            },
+        }
+    }
+
+    fn gen_stamp(&self) -> kurbo::BezPath {
+        use kurbo::Shape;
+
+        let params = self.stamp_params();
+        let mut rng = crate::utils::new_rng_default_pcg64(Some(Self::STAMP_SEED));
+        let distr_pos = Uniform::from(0.0..Self::STAMP_SIZE);
+        let distr_rx = Uniform::from(params.radius_x.0..params.radius_x.1);
+        let distr_ry = Uniform::from(params.radius_y.0..params.radius_y.1);
+        let distr_rot = Uniform::from(-params.rotation_spread..params.rotation_spread);
+
+        let mut stamp = kurbo::BezPath::new();
+        for _ in 0..params.n_grains {
+            let grain = kurbo::Ellipse::new(
+                kurbo::Point::new(distr_pos.sample(&mut rng), distr_pos.sample(&mut rng)),
+                kurbo::Vec2::new(distr_rx.sample(&mut rng), distr_ry.sample(&mut rng)),
+                distr_rot.sample(&mut rng),
+            );
+            // All grains need the same orientation, so that overlapping ones get united with the non-zero fill rule
+            stamp.extend(grain.path_elements(0.1));
+        }
+        stamp
+    }
+}
@@ -0,0 +1,174 @@
+// Modules
+mod grainoptions;
+mod graintexture;
+
+// Re-exports
+pub use grainoptions::GrainOptions;
+pub use graintexture::GrainTexture;
+
+// Imports
+use super::smooth::compose_lines_variable_width;
+use super::Composer;
+use crate::helpers::Vector2Helpers;
+use crate::penpath::{self, Element, Segment};
+use crate::shapes::{CubicBezier, Line, QuadraticBezier, ShapeBehaviour};
+use crate::PenPath;
+use kurbo::Shape;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use std::collections::BTreeSet;
+
+impl Composer<GrainOptions> for Line {
+    fn composed_bounds(&self, options: &GrainOptions) -> Aabb {
+        self.bounds().loosened(options.stroke_width * 0.5)
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &GrainOptions) {
+        PenPath::new_w_segments(
+            Element::new(self.start, 1.0),
+            [Segment::LineTo {
+                end: Element::new(self.end, 1.0),
+            }],
+        )
+        .draw_composed(cx, options);
+    }
+}
+
+impl Composer<GrainOptions> for PenPath {
+    fn composed_bounds(&self, options: &GrainOptions) -> Aabb {
+        self.bounds().loosened(options.stroke_width * 0.5)
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &GrainOptions) {
+        let Some(color) = options.stroke_color else {
+            return;
+        };
+        let n_segs = self.segments.len();
+        let mut single_pos = true;
+        let mut prev = self.start;
+
+        // The outlines and the stamp tiles they cover, grouped by their opacity level
+        let mut outlines = vec![kurbo::BezPath::new(); GrainOptions::OPACITY_LEVELS];
+        let mut tiles = vec![BTreeSet::<(i64, i64)>::new(); GrainOptions::OPACITY_LEVELS];
+        let mut add_outline = |outline: kurbo::BezPath, pressure: f64| {
+            let level = GrainOptions::opacity_level(pressure);
+            let bounds = outline.bounding_box().inflate(
+                GrainTexture::GRAIN_RADIUS_MAX,
+                GrainTexture::GRAIN_RADIUS_MAX,
+            );
+            let (x_min, y_min) = (
+                (bounds.x0 / GrainTexture::STAMP_SIZE).floor() as i64,
+                (bounds.y0 / GrainTexture::STAMP_SIZE).floor() as i64,
+            );
+            let (x_max, y_max) = (
+                (bounds.x1 / GrainTexture::STAMP_SIZE).floor() as i64,
+                (bounds.y1 / GrainTexture::STAMP_SIZE).floor() as i64,
+            );
+            for x in x_min..=x_max {
+                for y in y_min..=y_max {
+                    tiles[level].insert((x, y));
+                }
+            }
+            outlines[level].extend(outline);
+        };
+
+        for seg in self.segments.iter() {
+            if seg.end().pos == self.start.pos && n_segs <= 1 {
+                break;
+            } else {
+                single_pos = false;
+            }
+
+            let end = seg.end();
+            let lines = match seg {
+                Segment::LineTo { end } => vec![Line {
+                    start: prev.pos,
+                    end: end.pos,
+                }],
+                Segment::QuadBezTo { cp, end } => {
+                    let quadbez = QuadraticBezier {
+                        start: prev.pos,
+                        cp: *cp,
+                        end: end.pos,
+                    };
+                    let n_splits =
+                        penpath::no_subsegments_for_segment_len(quadbez.to_kurbo().perimeter(0.25))
+                            .max(2);
+                    quadbez.approx_with_lines(n_splits)
+                }
+                Segment::CubBezTo { cp1, cp2, end } => {
+                    let cubbez = CubicBezier {
+                        start: prev.pos,
+                        cp1: *cp1,
+                        cp2: *cp2,
+                        end: end.pos,
+                    };
+                    let n_splits =
+                        penpath::no_subsegments_for_segment_len(cubbez.to_kurbo().perimeter(0.25))
+                            .max(2);
+                    cubbez.approx_with_lines(n_splits)
+                }
+            };
+            let outline = compose_lines_variable_width(
+                &lines,
+                options
+                    .pressure_curve
+                    .apply(options.stroke_width, prev.pressure),
+                options
+                    .pressure_curve
+                    .apply(options.stroke_width, end.pressure),
+            );
+            add_outline(outline, (prev.pressure + end.pressure) * 0.5);
+
+            prev = end;
+        }
+
+        // Single element/position strokes need special treatment to be rendered
+        if n_segs == 0 || single_pos {
+            let start_width = options
+                .pressure_curve
+                .apply(options.stroke_width, self.start.pressure);
+            add_outline(
+                kurbo::Circle::new(self.start.pos.to_kurbo_point(), start_width * 0.5).to_path(0.1),
+                self.start.pressure,
+            );
+        }
+
+        let stamp = options.texture.stamp();
+
+        cx.save().unwrap();
+        for (level, (outline, tiles)) in outlines.into_iter().zip(tiles).enumerate() {
+            if outline.elements().is_empty() {
+                continue;
+            }
+            let opacity = color.a * GrainOptions::level_opacity(level);
+
+            // The grain is fixed to the document, so it does not shift while the stroke is drawn
+            let mut grain = kurbo::BezPath::new();
+            for (x, y) in tiles {
+                let offset = kurbo::Affine::translate(kurbo::Vec2::new(
+                    x as f64 * GrainTexture::STAMP_SIZE,
+                    y as f64 * GrainTexture::STAMP_SIZE,
+                ));
+                grain.extend(stamp.elements().iter().map(|el| offset * *el));
+            }
+
+            cx.save().unwrap();
+            cx.clip(outline.clone());
+            cx.fill(
+                outline,
+                &piet::Color::rgba(
+                    color.r,
+                    color.g,
+                    color.b,
+                    opacity * options.texture.base_opacity(),
+                ),
+            );
+            cx.fill(
+                grain,
+                &piet::Color::rgba(color.r, color.g, color.b, opacity),
+            );
+            cx.restore().unwrap();
+        }
+        cx.restore().unwrap();
+    }
+}
@@ -1,6 +1,10 @@
 // Modules
+/// The airbrush module for airbrush styles
+pub mod airbrush;
 /// Composer
 mod composer;
+/// The grain module for grain styles
+pub mod grain;
 /// Draw helpers
 pub mod indicators;
 /// The rough module for rough styles
@@ -11,12 +15,14 @@ pub mod smooth;
 pub mod textured;
 
 // Re-exports
+use self::airbrush::AirbrushOptions;
+use self::grain::GrainOptions;
 use self::rough::RoughOptions;
 use self::smooth::SmoothOptions;
 use self::textured::TexturedOptions;
 
 // Imports
-use crate::shapes::{Arrow, CubicBezier, Ellipse, Line, QuadraticBezier, Rectangle};
+use crate::shapes::{Arrow, CubicBezier, Ellipse, Line, Polygon, QuadraticBezier, Rectangle};
 use crate::{Color, PenPath, Shape};
 use anyhow::Context;
 pub use composer::Composer;
@@ -35,6 +41,12 @@ pub enum Style {
     /// A textured style
     #[serde(rename = "textured")]
     Textured(TexturedOptions),
+    /// A grain style
+    #[serde(rename = "grain")]
+    Grain(GrainOptions),
+    /// An airbrush style
+    #[serde(rename = "airbrush")]
+    Airbrush(AirbrushOptions),
 }
 
 impl Default for Style {
@@ -50,6 +62,19 @@ impl Style {
             Style::Smooth(options) => options.stroke_width,
             Style::Rough(options) => options.stroke_width,
             Style::Textured(options) => options.stroke_width,
+            Style::Grain(options) => options.stroke_width,
+            Style::Airbrush(options) => options.stroke_width,
+        }
+    }
+
+    /// Set the stroke width. Available on all styles.
+    pub fn set_stroke_width(&mut self, stroke_width: f64) {
+        match self {
+            Style::Smooth(options) => options.stroke_width = stroke_width,
+            Style::Rough(options) => options.stroke_width = stroke_width,
+            Style::Textured(options) => options.stroke_width = stroke_width,
+            Style::Grain(options) => options.stroke_width = stroke_width,
+            Style::Airbrush(options) => options.stroke_width = stroke_width,
         }
     }
 
@@ -59,6 +84,19 @@ impl Style {
             Style::Smooth(options) => options.stroke_width,
             Style::Rough(options) => options.stroke_width + RoughOptions::ROUGH_BOUNDS_MARGIN,
             Style::Textured(options) => options.stroke_width,
+            Style::Grain(options) => options.stroke_width,
+            Style::Airbrush(options) => options.stroke_width,
+        }
+    }
+
+    /// Smooth options with the stroke width and color of the style.
+    ///
+    /// Used to compose shapes and pen paths with styles that don't support them.
+    pub fn smooth_fallback(&self) -> SmoothOptions {
+        SmoothOptions {
+            stroke_width: self.stroke_width(),
+            stroke_color: self.stroke_color(),
+            ..SmoothOptions::default()
         }
     }
 
@@ -68,6 +106,49 @@ impl Style {
             Style::Smooth(_) => {}
             Style::Rough(options) => options.advance_seed(),
             Style::Textured(options) => options.advance_seed(),
+            Style::Grain(_) => {}
+            Style::Airbrush(options) => options.advance_seed(),
+        }
+    }
+
+    /// The seed of the style, for styles that have one.
+    pub fn seed(&self) -> Option<u64> {
+        match self {
+            Style::Smooth(_) => None,
+            Style::Rough(options) => options.seed,
+            Style::Textured(options) => options.seed,
+            Style::Grain(_) => None,
+            Style::Airbrush(options) => options.seed,
+        }
+    }
+
+    /// Whether the style is drawn with randomness.
+    pub fn is_randomized(&self) -> bool {
+        matches!(
+            self,
+            Style::Rough(_) | Style::Textured(_) | Style::Airbrush(_)
+        )
+    }
+
+    /// Set the seed of the style, for styles that have one.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        match self {
+            Style::Smooth(_) => {}
+            Style::Rough(options) => options.seed = seed,
+            Style::Textured(options) => options.seed = seed,
+            Style::Grain(_) => {}
+            Style::Airbrush(options) => options.seed = seed,
+        }
+    }
+
+    /// The stroke color of the style.
+    pub fn stroke_color(&self) -> Option<Color> {
+        match self {
+            Style::Smooth(options) => options.stroke_color,
+            Style::Rough(options) => options.stroke_color,
+            Style::Textured(options) => options.stroke_color,
+            Style::Grain(options) => options.stroke_color,
+            Style::Airbrush(options) => options.stroke_color,
         }
     }
 
@@ -77,6 +158,8 @@ impl Style {
             Style::Smooth(options) => options.stroke_color = Some(color),
             Style::Rough(options) => options.stroke_color = Some(color),
             Style::Textured(options) => options.stroke_color = Some(color),
+            Style::Grain(options) => options.stroke_color = Some(color),
+            Style::Airbrush(options) => options.stroke_color = Some(color),
         };
     }
 
@@ -85,8 +168,50 @@ impl Style {
         match self {
             Style::Smooth(options) => options.fill_color = Some(color),
             Style::Rough(options) => options.fill_color = Some(color),
-            Style::Textured(_) => {}
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => {}
+        };
+    }
+
+    /// Whether the stroke or fill color is equal to the given color.
+    ///
+    /// Colors are compared with 8 bit precision.
+    pub fn has_color(&self, color: Color) -> bool {
+        let matches = |other: Option<Color>| {
+            other.map_or(false, |other| u32::from(other) == u32::from(color))
         };
+
+        match self {
+            Style::Smooth(options) => matches(options.stroke_color) || matches(options.fill_color),
+            Style::Rough(options) => matches(options.stroke_color) || matches(options.fill_color),
+            Style::Textured(options) => matches(options.stroke_color),
+            Style::Grain(options) => matches(options.stroke_color),
+            Style::Airbrush(options) => matches(options.stroke_color),
+        }
+    }
+
+    /// Replace the stroke and fill colors that are equal to `from` with `to`.
+    ///
+    /// Colors are compared with 8 bit precision. Returns true when a color was replaced.
+    pub fn replace_color(&mut self, from: Color, to: Color) -> bool {
+        let replace = |color: &mut Option<Color>| match color {
+            Some(color) if u32::from(*color) == u32::from(from) => {
+                *color = to;
+                true
+            }
+            _ => false,
+        };
+
+        match self {
+            Style::Smooth(options) => {
+                replace(&mut options.stroke_color) | replace(&mut options.fill_color)
+            }
+            Style::Rough(options) => {
+                replace(&mut options.stroke_color) | replace(&mut options.fill_color)
+            }
+            Style::Textured(options) => replace(&mut options.stroke_color),
+            Style::Grain(options) => replace(&mut options.stroke_color),
+            Style::Airbrush(options) => replace(&mut options.stroke_color),
+        }
     }
 }
 
@@ -96,6 +221,8 @@ impl Composer<Style> for Line {
             Style::Smooth(options) => self.composed_bounds(options),
             Style::Rough(options) => self.composed_bounds(options),
             Style::Textured(options) => self.composed_bounds(options),
+            Style::Grain(options) => self.composed_bounds(options),
+            Style::Airbrush(options) => self.composed_bounds(options),
         }
     }
 
@@ -104,6 +231,8 @@ impl Composer<Style> for Line {
             Style::Smooth(options) => self.draw_composed(cx, options),
             Style::Rough(options) => self.draw_composed(cx, options),
             Style::Textured(options) => self.draw_composed(cx, options),
+            Style::Grain(options) => self.draw_composed(cx, options),
+            Style::Airbrush(options) => self.draw_composed(cx, options),
         }
     }
 }
@@ -113,7 +242,9 @@ impl Composer<Style> for Arrow {
         match options {
             Style::Smooth(options) => self.composed_bounds(options),
             Style::Rough(options) => self.composed_bounds(options),
-            Style::Textured(_options) => unimplemented!(),
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => {
+                self.composed_bounds(&options.smooth_fallback())
+            }
         }
     }
 
@@ -121,7 +252,9 @@ impl Composer<Style> for Arrow {
         match options {
             Style::Smooth(options) => self.draw_composed(cx, options),
             Style::Rough(options) => self.draw_composed(cx, options),
-            Style::Textured(_options) => unimplemented!(),
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => {
+                self.draw_composed(cx, &options.smooth_fallback())
+            }
         }
     }
 }
@@ -131,7 +264,9 @@ impl Composer<Style> for Rectangle {
         match options {
             Style::Smooth(options) => self.composed_bounds(options),
             Style::Rough(options) => self.composed_bounds(options),
-            Style::Textured(_options) => unimplemented!(),
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => {
+                self.composed_bounds(&options.smooth_fallback())
+            }
         }
     }
 
@@ -139,7 +274,9 @@ impl Composer<Style> for Rectangle {
         match options {
             Style::Smooth(options) => self.draw_composed(cx, options),
             Style::Rough(options) => self.draw_composed(cx, options),
-            Style::Textured(_options) => unimplemented!(),
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => {
+                self.draw_composed(cx, &options.smooth_fallback())
+            }
         }
     }
 }
@@ -149,7 +286,9 @@ impl Composer<Style> for Ellipse {
         match options {
             Style::Smooth(options) => self.composed_bounds(options),
             Style::Rough(options) => self.composed_bounds(options),
-            Style::Textured(_options) => unimplemented!(),
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => {
+                self.composed_bounds(&options.smooth_fallback())
+            }
         }
     }
 
@@ -157,7 +296,9 @@ impl Composer<Style> for Ellipse {
         match options {
             Style::Smooth(options) => self.draw_composed(cx, options),
             Style::Rough(options) => self.draw_composed(cx, options),
-            Style::Textured(_options) => unimplemented!(),
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => {
+                self.draw_composed(cx, &options.smooth_fallback())
+            }
         }
     }
 }
@@ -167,7 +308,9 @@ impl Composer<Style> for QuadraticBezier {
         match options {
             Style::Smooth(options) => self.composed_bounds(options),
             Style::Rough(options) => self.composed_bounds(options),
-            Style::Textured(_options) => unimplemented!(),
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => {
+                self.composed_bounds(&options.smooth_fallback())
+            }
         }
     }
 
@@ -175,7 +318,9 @@ impl Composer<Style> for QuadraticBezier {
         match options {
             Style::Smooth(options) => self.draw_composed(cx, options),
             Style::Rough(options) => self.draw_composed(cx, options),
-            Style::Textured(_options) => unimplemented!(),
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => {
+                self.draw_composed(cx, &options.smooth_fallback())
+            }
         }
     }
 }
@@ -185,7 +330,31 @@ impl Composer<Style> for CubicBezier {
         match options {
             Style::Smooth(options) => self.composed_bounds(options),
             Style::Rough(options) => self.composed_bounds(options),
-            Style::Textured(_options) => unimplemented!(),
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => {
+                self.composed_bounds(&options.smooth_fallback())
+            }
+        }
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &Style) {
+        match options {
+            Style::Smooth(options) => self.draw_composed(cx, options),
+            Style::Rough(options) => self.draw_composed(cx, options),
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => {
+                self.draw_composed(cx, &options.smooth_fallback())
+            }
+        }
+    }
+}
+
+impl Composer<Style> for Polygon {
+    fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
+        match options {
+            Style::Smooth(options) => self.composed_bounds(options),
+            Style::Rough(options) => self.composed_bounds(options),
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => {
+                self.composed_bounds(&options.smooth_fallback())
+            }
         }
     }
 
@@ -193,7 +362,9 @@ impl Composer<Style> for CubicBezier {
         match options {
             Style::Smooth(options) => self.draw_composed(cx, options),
             Style::Rough(options) => self.draw_composed(cx, options),
-            Style::Textured(_options) => unimplemented!(),
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => {
+                self.draw_composed(cx, &options.smooth_fallback())
+            }
         }
     }
 }
@@ -202,16 +373,20 @@ impl Composer<Style> for PenPath {
     fn composed_bounds(&self, options: &Style) -> p2d::bounding_volume::Aabb {
         match options {
             Style::Smooth(options) => self.composed_bounds(options),
-            Style::Rough(_) => unimplemented!(),
+            Style::Rough(_) => self.composed_bounds(&options.smooth_fallback()),
             Style::Textured(options) => self.composed_bounds(options),
+            Style::Grain(options) => self.composed_bounds(options),
+            Style::Airbrush(options) => self.composed_bounds(options),
         }
     }
 
     fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &Style) {
         match options {
             Style::Smooth(options) => self.draw_composed(cx, options),
-            Style::Rough(_) => unimplemented!(),
+            Style::Rough(_) => self.draw_composed(cx, &options.smooth_fallback()),
             Style::Textured(options) => self.draw_composed(cx, options),
+            Style::Grain(options) => self.draw_composed(cx, options),
+            Style::Airbrush(options) => self.draw_composed(cx, options),
         }
     }
 }
@@ -225,6 +400,7 @@ impl Composer<Style> for Shape {
             Shape::Ellipse(ellipse) => ellipse.composed_bounds(options),
             Shape::QuadraticBezier(quadratic_bezier) => quadratic_bezier.composed_bounds(options),
             Shape::CubicBezier(cubic_bezier) => cubic_bezier.composed_bounds(options),
+            Shape::Polygon(polygon) => polygon.composed_bounds(options),
         }
     }
 
@@ -236,6 +412,7 @@ impl Composer<Style> for Shape {
             Shape::Ellipse(ellipse) => ellipse.draw_composed(cx, options),
             Shape::QuadraticBezier(quadratic_bezier) => quadratic_bezier.draw_composed(cx, options),
             Shape::CubicBezier(cubic_bezier) => cubic_bezier.draw_composed(cx, options),
+            Shape::Polygon(polygon) => polygon.draw_composed(cx, options),
         }
     }
 }
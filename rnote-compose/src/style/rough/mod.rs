@@ -10,6 +10,7 @@ use super::Composer;
 use crate::helpers::Vector2Helpers;
 use crate::shapes::Arrow;
 use crate::shapes::Line;
+use crate::shapes::Polygon;
 use crate::shapes::Rectangle;
 use crate::shapes::{CubicBezier, ShapeBehaviour};
 use crate::shapes::{Ellipse, QuadraticBezier};
@@ -203,6 +204,29 @@ impl Composer<RoughOptions> for CubicBezier {
     }
 }
 
+impl Composer<RoughOptions> for Polygon {
+    fn composed_bounds(&self, options: &RoughOptions) -> p2d::bounding_volume::Aabb {
+        self.bounds()
+            .loosened(options.stroke_width * 0.5 + RoughOptions::ROUGH_BOUNDS_MARGIN)
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &RoughOptions) {
+        cx.save().unwrap();
+
+        let points = self
+            .points
+            .iter()
+            .map(|point| roughr::Point2D::new(point[0] as f32, point[1] as f32))
+            .collect::<Vec<roughr::Point2D<f32>>>();
+        let drawable =
+            rough_piet::KurboGenerator::new(generate_roughr_options(options)).polygon(&points);
+
+        drawable.draw(cx);
+
+        cx.restore().unwrap();
+    }
+}
+
 impl Composer<RoughOptions> for crate::Shape {
     fn composed_bounds(&self, options: &RoughOptions) -> Aabb {
         match self {
@@ -212,6 +236,7 @@ impl Composer<RoughOptions> for crate::Shape {
             crate::Shape::Ellipse(ellipse) => ellipse.composed_bounds(options),
             crate::Shape::QuadraticBezier(quadbez) => quadbez.composed_bounds(options),
             crate::Shape::CubicBezier(cubbez) => cubbez.composed_bounds(options),
+            crate::Shape::Polygon(polygon) => polygon.composed_bounds(options),
         }
     }
 
@@ -223,6 +248,7 @@ impl Composer<RoughOptions> for crate::Shape {
             crate::Shape::Ellipse(ellipse) => ellipse.draw_composed(cx, options),
             crate::Shape::QuadraticBezier(quadbez) => quadbez.draw_composed(cx, options),
             crate::Shape::CubicBezier(cubbez) => cubbez.draw_composed(cx, options),
+            crate::Shape::Polygon(polygon) => polygon.draw_composed(cx, options),
         }
     }
 }
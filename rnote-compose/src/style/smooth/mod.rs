@@ -10,6 +10,7 @@ use crate::helpers::Vector2Helpers;
 use crate::penpath::{self, Segment};
 use crate::shapes::Ellipse;
 use crate::shapes::Line;
+use crate::shapes::Polygon;
 use crate::shapes::QuadraticBezier;
 use crate::shapes::Rectangle;
 use crate::shapes::ShapeBehaviour;
@@ -144,6 +145,28 @@ impl Composer<SmoothOptions> for CubicBezier {
     }
 }
 
+impl Composer<SmoothOptions> for Polygon {
+    fn composed_bounds(&self, options: &SmoothOptions) -> Aabb {
+        self.bounds().loosened(options.stroke_width * 0.5)
+    }
+
+    fn draw_composed(&self, cx: &mut impl piet::RenderContext, options: &SmoothOptions) {
+        cx.save().unwrap();
+        let polygon = self.to_kurbo();
+
+        if let Some(fill_color) = options.fill_color {
+            let fill_brush = cx.solid_brush(fill_color.into());
+            cx.fill(polygon.clone(), &fill_brush);
+        }
+
+        if let Some(stroke_color) = options.stroke_color {
+            let stroke_brush = cx.solid_brush(stroke_color.into());
+            cx.stroke(polygon, &stroke_brush, options.stroke_width);
+        }
+        cx.restore().unwrap();
+    }
+}
+
 impl Composer<SmoothOptions> for PenPath {
     fn composed_bounds(&self, options: &SmoothOptions) -> Aabb {
         self.bounds().loosened(options.stroke_width * 0.5)
@@ -185,7 +208,6 @@ impl Composer<SmoothOptions> for PenPath {
                             }],
                             width_start,
                             width_end,
-                            options,
                         );
 
                         prev = *end;
@@ -211,8 +233,7 @@ impl Composer<SmoothOptions> for PenPath {
                         )
                         .max(2);
                         let lines = quadbez.approx_with_lines(n_splits);
-                        let bez_path =
-                            compose_lines_variable_width(&lines, width_start, width_end, options);
+                        let bez_path = compose_lines_variable_width(&lines, width_start, width_end);
 
                         prev = *end;
                         bez_path
@@ -238,8 +259,7 @@ impl Composer<SmoothOptions> for PenPath {
                         )
                         .max(2);
                         let lines = cubbez.approx_with_lines(n_splits);
-                        let bez_path =
-                            compose_lines_variable_width(&lines, width_start, width_end, options);
+                        let bez_path = compose_lines_variable_width(&lines, width_start, width_end);
 
                         prev = *end;
                         bez_path
@@ -278,6 +298,7 @@ impl Composer<SmoothOptions> for crate::Shape {
             crate::Shape::Ellipse(ellipse) => ellipse.composed_bounds(options),
             crate::Shape::QuadraticBezier(quadbez) => quadbez.composed_bounds(options),
             crate::Shape::CubicBezier(cubbez) => cubbez.composed_bounds(options),
+            crate::Shape::Polygon(polygon) => polygon.composed_bounds(options),
         }
     }
 
@@ -289,16 +310,16 @@ impl Composer<SmoothOptions> for crate::Shape {
             crate::Shape::Ellipse(ellipse) => ellipse.draw_composed(cx, options),
             crate::Shape::QuadraticBezier(quadbez) => quadbez.draw_composed(cx, options),
             crate::Shape::CubicBezier(cubbez) => cubbez.draw_composed(cx, options),
+            crate::Shape::Polygon(polygon) => polygon.draw_composed(cx, options),
         }
     }
 }
 
 /// Composes lines with variable width. Must be drawn with only a fill.
-fn compose_lines_variable_width(
+pub(crate) fn compose_lines_variable_width(
     lines: &[Line],
     start_width: f64,
     end_width: f64,
-    _options: &SmoothOptions,
 ) -> kurbo::BezPath {
     // The the lines variable is ghosted here, to make sure we can only use the filtered
     let lines = lines
@@ -1,6 +1,8 @@
 // Imports
 use p2d::bounding_volume::Aabb;
 use rand::{Rng, SeedableRng};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 
 /// Matches when a Xml header is present
 const XML_HEADER_REGEX: &str = r#"<\?xml[^\?>]*\?>"#;
@@ -113,6 +115,19 @@ pub fn svg_random_id_prefix() -> String {
         .collect::<String>()
 }
 
+/// Generate a alphanumeric prefix for Svg Id's that is derived from the given data.
+///
+/// Avoids Id collisions like [svg_random_id_prefix], but stays the same for the same data, so that exports are reproducible.
+pub fn svg_id_prefix_from_data(data: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    rand_pcg::Pcg64::seed_from_u64(hasher.finish())
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(8)
+        .map(char::from)
+        .collect::<String>()
+}
+
 /// Generate a new seed by generating a random value seeded from the old seed using the Pcg algorithm.
 pub fn seed_advance(seed: u64) -> u64 {
     let mut rng = rand_pcg::Pcg64::seed_from_u64(seed);
@@ -0,0 +1,11 @@
+#![no_main]
+
+// Imports
+use libfuzzer_sys::fuzz_target;
+use rnote_engine::render;
+use rnote_engine::strokes::BitmapImage;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = render::Image::read_dpi_from_encoded_bytes(data);
+    let _ = BitmapImage::import_from_image_bytes(data, nalgebra::Vector2::zeros(), None);
+});
@@ -0,0 +1,29 @@
+#![no_main]
+
+// Imports
+use libfuzzer_sys::fuzz_target;
+use rnote_engine::engine::EngineSnapshot;
+use rnote_engine::RnoteEngine;
+use std::io::Write;
+
+/// Inputs that are not gzip compressed are compressed first, so that the fuzzer can mutate the Json directly.
+fn gzipped(data: &[u8]) -> Vec<u8> {
+    if data.starts_with(&[0x1f, 0x8b]) {
+        return data.to_vec();
+    }
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fuzz_target!(|data: &[u8]| {
+    let bytes = gzipped(data);
+
+    // Loading parsed snapshots into the engine builds the stroke key tree and updates the stroke geometry
+    if let Ok(snapshot) = EngineSnapshot::parse_rnote_bytes(&bytes) {
+        let _ = RnoteEngine::default().load_snapshot(snapshot);
+    }
+    if let Ok((snapshot, _report)) = EngineSnapshot::parse_rnote_bytes_repaired(&bytes) {
+        let _ = RnoteEngine::default().load_snapshot(snapshot);
+    }
+});
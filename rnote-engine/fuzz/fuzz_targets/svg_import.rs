@@ -0,0 +1,13 @@
+#![no_main]
+
+// Imports
+use libfuzzer_sys::fuzz_target;
+use rnote_engine::strokes::VectorImage;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(svg_data) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let _ = VectorImage::import_from_svg_data(svg_data, nalgebra::Vector2::zeros(), None);
+});
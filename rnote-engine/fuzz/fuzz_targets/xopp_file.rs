@@ -0,0 +1,26 @@
+#![no_main]
+
+// Imports
+use libfuzzer_sys::fuzz_target;
+use rnote_engine::engine::import::XoppImportPrefs;
+use rnote_engine::engine::EngineSnapshot;
+use rnote_engine::RnoteEngine;
+use std::io::Write;
+
+/// Inputs that are not gzip compressed are compressed first, so that the fuzzer can mutate the Xml directly.
+fn gzipped(data: &[u8]) -> Vec<u8> {
+    if data.starts_with(&[0x1f, 0x8b]) {
+        return data.to_vec();
+    }
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fuzz_target!(|data: &[u8]| {
+    let bytes = gzipped(data);
+
+    if let Ok(snapshot) = EngineSnapshot::parse_xopp_bytes(&bytes, XoppImportPrefs::default()) {
+        let _ = RnoteEngine::default().load_snapshot(snapshot);
+    }
+});
@@ -10,6 +10,61 @@ use crate::document::Layout;
 use crate::engine::{EngineTask, EngineTaskSender};
 use crate::tasks::{OneOffTaskError, OneOffTaskHandle};
 use crate::{Document, WidgetFlags};
+use rnote_compose::{color, Color};
+
+/// Configuration of the overlays that are drawn on top of the document, like the selection and its handles.
+///
+/// Overlay sizes are in surface coordinates and independent of the document zoom,
+/// but are scaled by the UI scale factor.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "overlay_config")]
+pub struct OverlayConfig {
+    /// The UI scale factor, scaling all overlays. Useful for high-dpi tablets or touch input.
+    #[serde(rename = "ui_scale")]
+    pub ui_scale: f64,
+    /// The size of handles like the selection resize and rotate nodes.
+    #[serde(rename = "handle_size")]
+    pub handle_size: f64,
+    /// The tolerance around handles where they are still hit by the pen.
+    #[serde(rename = "hit_tolerance")]
+    pub hit_tolerance: f64,
+    /// The outline color of selections.
+    #[serde(rename = "selection_outline_color")]
+    pub selection_outline_color: Color,
+    /// The fill color of selections.
+    #[serde(rename = "selection_fill_color")]
+    pub selection_fill_color: Color,
+}
+
+impl Default for OverlayConfig {
+    fn default() -> Self {
+        Self {
+            ui_scale: 1.0,
+            handle_size: 18.0,
+            hit_tolerance: 4.0,
+            selection_outline_color: Color::from(color::GNOME_BRIGHTS[4].with_alpha(0.941)),
+            selection_fill_color: Color::from(color::GNOME_BRIGHTS[2].with_alpha(0.050)),
+        }
+    }
+}
+
+impl OverlayConfig {
+    pub const UI_SCALE_MIN: f64 = 0.5;
+    pub const UI_SCALE_MAX: f64 = 4.0;
+    pub const HANDLE_SIZE_MIN: f64 = 8.0;
+    pub const HANDLE_SIZE_MAX: f64 = 64.0;
+    pub const HIT_TOLERANCE_MAX: f64 = 32.0;
+
+    /// Clamps the values to their valid ranges.
+    pub fn clamped(mut self) -> Self {
+        self.ui_scale = self.ui_scale.clamp(Self::UI_SCALE_MIN, Self::UI_SCALE_MAX);
+        self.handle_size = self
+            .handle_size
+            .clamp(Self::HANDLE_SIZE_MIN, Self::HANDLE_SIZE_MAX);
+        self.hit_tolerance = self.hit_tolerance.clamp(0.0, Self::HIT_TOLERANCE_MAX);
+        self
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "camera")]
@@ -26,12 +81,18 @@ pub struct Camera {
     /// The temporary zoom. Is used to overlay the "permanent" zoom.
     #[serde(rename = "temporary_zoom")]
     temporary_zoom: f64,
+    /// The view rotation in radians around the viewport center. Only affects the view, not the document.
+    #[serde(rename = "rotation")]
+    rotation: f64,
 
     /// The scale factor of the surface, usually 1.0 or 2.0 for high-dpi screens.
     ///
     /// This value could become a non-integer value in the future, so it is stored as float.
     #[serde(rename = "scale_factor")]
     pub scale_factor: f64,
+    /// The configuration of the overlays.
+    #[serde(rename = "overlay_config")]
+    pub overlay_config: OverlayConfig,
 
     #[serde(skip)]
     pub zoom_task_handle: Option<crate::tasks::OneOffTaskHandle>,
@@ -44,7 +105,9 @@ impl Default for Camera {
             size: na::vector![800.0, 600.0],
             zoom: 1.0,
             temporary_zoom: 1.0,
+            rotation: 0.0,
             scale_factor: 1.0,
+            overlay_config: OverlayConfig::default(),
             zoom_task_handle: None,
         }
     }
@@ -60,6 +123,10 @@ impl Camera {
     pub const DRAG_ZOOM_MAGN_ZOOM_FACTOR: f64 = 0.005;
     pub const OVERSHOOT_HORIZONTAL: f64 = 96.0;
     pub const OVERSHOOT_VERTICAL: f64 = 96.0;
+    /// The rotation step when rotating the view in steps.
+    pub const ROTATION_STEP: f64 = std::f64::consts::FRAC_PI_2;
+    /// The margin around bounds that are zoomed to fit, in surface coordinates.
+    pub const ZOOM_FIT_MARGIN: f64 = 32.0;
 
     pub fn with_zoom(mut self, zoom: f64) -> Self {
         self.zoom = zoom.clamp(Self::ZOOM_MIN, Self::ZOOM_MAX);
@@ -75,6 +142,11 @@ impl Camera {
         self
     }
 
+    pub fn with_rotation(mut self, rotation: f64) -> Self {
+        self.rotation = rotation.rem_euclid(std::f64::consts::TAU);
+        self
+    }
+
     /// The current viewport offset in surface coordinate space.
     pub fn offset(&self) -> na::Vector2<f64> {
         self.offset
@@ -202,11 +274,64 @@ impl Camera {
         widget_flags
     }
 
+    /// The view rotation in radians, in the range 0.0..TAU.
+    pub fn rotation(&self) -> f64 {
+        self.rotation
+    }
+
+    /// Rotate the view around the viewport center to the given angle in radians.
+    pub fn rotate_to(&mut self, rotation: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.rotation = rotation.rem_euclid(std::f64::consts::TAU);
+        widget_flags.redraw = true;
+        widget_flags.resize = true;
+        widget_flags
+    }
+
+    /// Rotate a vector in surface coordinates into the unrotated surface coordinate space that the offset is in.
+    ///
+    /// Used to map pointer movement deltas onto the camera offset when the view is rotated.
+    pub fn unrotate_surface_vec(&self, vec: na::Vector2<f64>) -> na::Vector2<f64> {
+        na::Rotation2::new(-self.rotation) * vec
+    }
+
+    /// Rotate a position in surface coordinates around the viewport center into the unrotated surface coordinate space.
+    pub fn unrotate_surface_pos(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        let center = self.size * 0.5;
+        center + self.unrotate_surface_vec(pos - center)
+    }
+
     /// The total zoom of the camera, including the temporary zoom.
     pub fn total_zoom(&self) -> f64 {
         self.zoom * self.temporary_zoom
     }
 
+    /// The zoom that overlays are drawn with, which is the total zoom divided by the UI scale.
+    ///
+    /// Overlay sizes in surface coordinates are divided by it to get their sizes in document coordinates.
+    pub fn overlay_zoom(&self) -> f64 {
+        self.total_zoom() / self.overlay_config.ui_scale
+    }
+
+    /// The size of overlay handles in document coordinates.
+    pub fn overlay_handle_size(&self) -> f64 {
+        self.overlay_config.handle_size / self.overlay_zoom()
+    }
+
+    /// The hit tolerance around overlay handles in document coordinates.
+    pub fn overlay_hit_tolerance(&self) -> f64 {
+        self.overlay_config.hit_tolerance / self.overlay_zoom()
+    }
+
+    /// The total zoom at which the bounds fit into the viewport, keeping the given margin in surface coordinates.
+    pub fn zoom_to_fit_bounds(&self, bounds: Aabb, margin: f64) -> f64 {
+        let extents = bounds.extents();
+        let available = (self.size - na::Vector2::from_element(2.0 * margin)).map(|v| v.max(1.0));
+        (available[0] / extents[0].max(1.0))
+            .min(available[1] / extents[1].max(1.0))
+            .clamp(Self::ZOOM_MIN, Self::ZOOM_MAX)
+    }
+
     /// The scaling factor for generating pixel images with the current permanent zoom.
     ///
     /// Takes the scale factor in account
@@ -215,13 +340,18 @@ impl Camera {
     }
 
     /// The viewport in document coordinate space.
+    ///
+    /// When the view is rotated, this is the bounding box of the rotated viewport.
     pub fn viewport(&self) -> Aabb {
         let total_zoom = self.total_zoom();
 
-        Aabb::new_positive(
-            (self.offset / total_zoom).into(),
-            ((self.offset + self.size) / total_zoom).into(),
-        )
+        if self.rotation == 0.0 {
+            return Aabb::new_positive(
+                (self.offset / total_zoom).into(),
+                ((self.offset + self.size) / total_zoom).into(),
+            );
+        }
+        self.transform_inv_bounds(Aabb::new(na::point![0.0, 0.0], self.size.into()))
     }
 
     /// The current viewport center in document coordinate space.
@@ -241,13 +371,33 @@ impl Camera {
     }
 
     /// Transform Aabb from document coords to surface coords.
+    ///
+    /// When the view is rotated, this is the bounding box of the rotated bounds.
     pub fn transform_bounds(&self, bounds: Aabb) -> Aabb {
-        bounds.scale(self.total_zoom()).translate(-self.offset)
+        if self.rotation == 0.0 {
+            return bounds.scale(self.total_zoom()).translate(-self.offset);
+        }
+        Self::transformed_aabb(bounds, &self.transform())
     }
 
     /// Transform Aabb from surface coords to document coords.
+    ///
+    /// When the view is rotated, this is the bounding box of the rotated bounds.
     pub fn transform_inv_bounds(&self, bounds: Aabb) -> Aabb {
-        bounds.translate(self.offset).scale(1.0 / self.total_zoom())
+        if self.rotation == 0.0 {
+            return bounds.translate(self.offset).scale(1.0 / self.total_zoom());
+        }
+        Self::transformed_aabb(bounds, &self.transform().inverse())
+    }
+
+    fn transformed_aabb(bounds: Aabb, transform: &na::Affine2<f64>) -> Aabb {
+        let corners = [
+            transform * bounds.mins,
+            transform * na::point![bounds.maxs[0], bounds.mins[1]],
+            transform * bounds.maxs,
+            transform * na::point![bounds.mins[0], bounds.maxs[1]],
+        ];
+        Aabb::from_points(&corners)
     }
 
     /// The transform from document coords to surface coords.
@@ -256,9 +406,15 @@ impl Camera {
     pub fn transform(&self) -> na::Affine2<f64> {
         let total_zoom = self.total_zoom();
 
+        let center = self.size * 0.5;
+
         na::try_convert(
             // LHS is applied onto RHS, so the order is scaling by zoom -> Translation by offset
-            na::Translation2::from(-self.offset).to_homogeneous()
+            // -> rotation around the viewport center
+            na::Translation2::from(center).to_homogeneous()
+                * na::Rotation2::new(self.rotation).to_homogeneous()
+                * na::Translation2::from(-center).to_homogeneous()
+                * na::Translation2::from(-self.offset).to_homogeneous()
                 * na::Scale2::from(na::Vector2::from_element(total_zoom)).to_homogeneous(),
         )
         .unwrap()
@@ -272,10 +428,14 @@ impl Camera {
     pub fn transform_for_gtk_snapshot(&self) -> gsk::Transform {
         let total_zoom = self.total_zoom();
 
+        let center = self.size * 0.5;
+
         gsk::Transform::new()
+            .translate(&graphene::Point::new(center[0] as f32, center[1] as f32))
+            .rotate(self.rotation.to_degrees() as f32)
             .translate(&graphene::Point::new(
-                -self.offset[0] as f32,
-                -self.offset[1] as f32,
+                -(center[0] + self.offset[0]) as f32,
+                -(center[1] + self.offset[1]) as f32,
             ))
             .scale(total_zoom as f32, total_zoom as f32)
     }
@@ -0,0 +1,116 @@
+// Imports
+use std::time::{Duration, Instant};
+
+/// The easing curve of an animation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseOutCubic,
+    EaseInOutCubic,
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Self::EaseOutCubic
+    }
+}
+
+impl Easing {
+    /// Map the linear progress `t` in the range 0.0..=1.0 onto the curve.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOutCubic => {
+                if t < 0.5 {
+                    4.0 * t.powi(3)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) * 0.5
+                }
+            }
+        }
+    }
+}
+
+/// The state of a camera animation at a point in time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraAnimationFrame {
+    /// The total zoom.
+    pub zoom: f64,
+    /// The viewport center in document coordinate space.
+    pub center: na::Vector2<f64>,
+    /// Whether this is the last frame of the animation.
+    pub finished: bool,
+}
+
+/// Tweens the camera zoom and viewport center from a start to a target state.
+///
+/// The animation is not driven by itself, the holder requests frames for the current time,
+/// usually from the frame clock of the UI toolkit.
+#[derive(Debug, Clone)]
+pub struct CameraAnimation {
+    start: Instant,
+    duration: Duration,
+    easing: Easing,
+    from_zoom: f64,
+    from_center: na::Vector2<f64>,
+    to_zoom: f64,
+    to_center: na::Vector2<f64>,
+}
+
+impl CameraAnimation {
+    /// The default duration of camera animations.
+    pub const DURATION_DEFAULT: Duration = Duration::from_millis(250);
+
+    pub fn new(from: (f64, na::Vector2<f64>), to: (f64, na::Vector2<f64>), start: Instant) -> Self {
+        Self {
+            start,
+            duration: Self::DURATION_DEFAULT,
+            easing: Easing::default(),
+            from_zoom: from.0,
+            from_center: from.1,
+            to_zoom: to.0,
+            to_center: to.1,
+        }
+    }
+
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// The target zoom and viewport center.
+    pub fn target(&self) -> (f64, na::Vector2<f64>) {
+        (self.to_zoom, self.to_center)
+    }
+
+    /// The animation frame at the given time.
+    pub fn frame(&self, now: Instant) -> CameraAnimationFrame {
+        let t = if self.duration.is_zero() {
+            1.0
+        } else {
+            (now.saturating_duration_since(self.start).as_secs_f64() / self.duration.as_secs_f64())
+                .clamp(0.0, 1.0)
+        };
+        if t >= 1.0 {
+            return CameraAnimationFrame {
+                zoom: self.to_zoom,
+                center: self.to_center,
+                finished: true,
+            };
+        }
+        let eased = self.easing.apply(t);
+        CameraAnimationFrame {
+            // Interpolating the zoom logarithmically feels uniform for zooming in and out
+            zoom: self.from_zoom * (self.to_zoom / self.from_zoom).powf(eased),
+            center: self.from_center.lerp(&self.to_center, eased),
+            finished: false,
+        }
+    }
+}
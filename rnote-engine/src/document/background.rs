@@ -63,7 +63,9 @@ pub fn gen_hline_pattern(
     color: Color,
     line_width: f64,
 ) -> svg::node::element::Element {
-    let pattern_id = rnote_compose::utils::svg_random_id_prefix() + "_bg_hline_pattern";
+    let pattern_id = rnote_compose::utils::svg_id_prefix_from_data(
+        format!("{:?}", (bounds, spacing, color, line_width)).as_bytes(),
+    ) + "_bg_hline_pattern";
 
     let line_offset = line_width * 0.5;
 
@@ -105,7 +107,13 @@ pub fn gen_grid_pattern(
     color: Color,
     line_width: f64,
 ) -> svg::node::element::Element {
-    let pattern_id = rnote_compose::utils::svg_random_id_prefix() + "_bg_grid_pattern";
+    let pattern_id = rnote_compose::utils::svg_id_prefix_from_data(
+        format!(
+            "{:?}",
+            (bounds, row_spacing, column_spacing, color, line_width)
+        )
+        .as_bytes(),
+    ) + "_bg_grid_pattern";
 
     let line_offset = line_width * 0.5;
 
@@ -156,7 +164,13 @@ pub fn gen_dots_pattern(
     color: Color,
     dots_width: f64,
 ) -> svg::node::element::Element {
-    let pattern_id = rnote_compose::utils::svg_random_id_prefix() + "_bg_dots_pattern";
+    let pattern_id = rnote_compose::utils::svg_id_prefix_from_data(
+        format!(
+            "{:?}",
+            (bounds, row_spacing, column_spacing, color, dots_width)
+        )
+        .as_bytes(),
+    ) + "_bg_dots_pattern";
 
     let pattern = element::Definitions::new().add(
         element::Pattern::new()
@@ -203,7 +217,9 @@ pub fn gen_iso_grid_pattern(
     // spacing: side length of the equilateral triangle
     // pattern_width: two times the height of the equilateral triangle
 
-    let pattern_id = rnote_compose::utils::svg_random_id_prefix() + "_bg_iso_grid_pattern";
+    let pattern_id = rnote_compose::utils::svg_id_prefix_from_data(
+        format!("{:?}", (bounds, spacing, color, line_width)).as_bytes(),
+    ) + "_bg_iso_grid_pattern";
     let pattern_width = calc_width_iso_pattern(spacing);
 
     let line_offset = line_width * 0.5;
@@ -275,7 +291,9 @@ pub fn gen_iso_dots_pattern(
     // spacing: side length of the equilateral triangle
     // pattern_width: two times the height of the equilateral triangle
 
-    let pattern_id = rnote_compose::utils::svg_random_id_prefix() + "_bg_iso_dots_pattern";
+    let pattern_id = rnote_compose::utils::svg_id_prefix_from_data(
+        format!("{:?}", (bounds, spacing, color, hexagon_height)).as_bytes(),
+    ) + "_bg_iso_dots_pattern";
     let pattern_width = calc_width_iso_pattern(spacing);
 
     let hexagon_path = |x_offset: f64, y_offset: f64| {
@@ -1,4 +1,5 @@
 // Imports
+use p2d::bounding_volume::Aabb;
 use rnote_compose::{color, Color};
 use serde::{Deserialize, Serialize};
 
@@ -86,6 +87,10 @@ pub enum MeasureUnit {
     Mm,
     #[serde(rename = "cm")]
     Cm,
+    #[serde(rename = "in")]
+    In,
+    #[serde(rename = "pt")]
+    Pt,
 }
 
 impl Default for MeasureUnit {
@@ -106,6 +111,34 @@ impl TryFrom<u32> for MeasureUnit {
 
 impl MeasureUnit {
     pub const AMOUNT_MM_IN_INCH: f64 = 25.4;
+    pub const AMOUNT_PT_IN_INCH: f64 = 72.0;
+
+    /// The abbreviation of the unit.
+    pub fn abbreviation(&self) -> &'static str {
+        match self {
+            MeasureUnit::Px => "px",
+            MeasureUnit::Mm => "mm",
+            MeasureUnit::Cm => "cm",
+            MeasureUnit::In => "in",
+            MeasureUnit::Pt => "pt",
+        }
+    }
+
+    /// Whether the unit is a physical unit, independent of the dpi.
+    pub fn is_physical(&self) -> bool {
+        !matches!(self, MeasureUnit::Px)
+    }
+
+    /// The amount of this unit in one inch, or None for pixels which depend on the dpi.
+    fn amount_in_inch(&self) -> Option<f64> {
+        match self {
+            MeasureUnit::Px => None,
+            MeasureUnit::Mm => Some(Self::AMOUNT_MM_IN_INCH),
+            MeasureUnit::Cm => Some(Self::AMOUNT_MM_IN_INCH / 10.0),
+            MeasureUnit::In => Some(1.0),
+            MeasureUnit::Pt => Some(Self::AMOUNT_PT_IN_INCH),
+        }
+    }
 
     pub fn convert_measurement(
         value: f64,
@@ -114,16 +147,14 @@ impl MeasureUnit {
         desired_unit: MeasureUnit,
         desired_dpi: f64,
     ) -> f64 {
-        let value_in_px = match value_unit {
-            MeasureUnit::Px => value,
-            MeasureUnit::Mm => (value / Self::AMOUNT_MM_IN_INCH) * value_dpi,
-            MeasureUnit::Cm => ((value * 10.0) / Self::AMOUNT_MM_IN_INCH) * value_dpi,
+        let value_in_px = match value_unit.amount_in_inch() {
+            None => value,
+            Some(amount_in_inch) => (value / amount_in_inch) * value_dpi,
         };
 
-        match desired_unit {
-            MeasureUnit::Px => value_in_px,
-            MeasureUnit::Mm => (value_in_px / desired_dpi) * Self::AMOUNT_MM_IN_INCH,
-            MeasureUnit::Cm => (value_in_px / desired_dpi) * Self::AMOUNT_MM_IN_INCH / 10.0,
+        match desired_unit.amount_in_inch() {
+            None => value_in_px,
+            Some(amount_in_inch) => (value_in_px / desired_dpi) * amount_in_inch,
         }
     }
 }
@@ -143,6 +174,70 @@ impl Default for Orientation {
     }
 }
 
+/// The margins of a page, inside which the printable area is located.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "page_margins")]
+pub struct PageMargins {
+    #[serde(rename = "top", with = "rnote_compose::serialize::f64_dp3")]
+    pub top: f64,
+    #[serde(rename = "bottom", with = "rnote_compose::serialize::f64_dp3")]
+    pub bottom: f64,
+    #[serde(rename = "left", with = "rnote_compose::serialize::f64_dp3")]
+    pub left: f64,
+    #[serde(rename = "right", with = "rnote_compose::serialize::f64_dp3")]
+    pub right: f64,
+}
+
+impl Default for PageMargins {
+    fn default() -> Self {
+        Self {
+            top: 0.0,
+            bottom: 0.0,
+            left: 0.0,
+            right: 0.0,
+        }
+    }
+}
+
+impl PageMargins {
+    pub const MARGIN_MAX: f64 = 5000.0;
+
+    pub fn is_zero(&self) -> bool {
+        self.top <= 0.0 && self.bottom <= 0.0 && self.left <= 0.0 && self.right <= 0.0
+    }
+
+    /// The printable area inside the margins of the page.
+    ///
+    /// Margins that would exceed the page are reduced, so the area is always valid.
+    pub fn printable_area(&self, page_bounds: Aabb) -> Aabb {
+        let extents = page_bounds.extents();
+        let left = self.left.clamp(0.0, extents[0] * 0.5);
+        let right = self.right.clamp(0.0, extents[0] * 0.5);
+        let top = self.top.clamp(0.0, extents[1] * 0.5);
+        let bottom = self.bottom.clamp(0.0, extents[1] * 0.5);
+
+        Aabb::new(
+            page_bounds.mins + na::vector![left, top],
+            page_bounds.maxs - na::vector![right, bottom],
+        )
+    }
+
+    /// The scale and offset that fit a page with the given size into its printable area, keeping the aspect ratio.
+    ///
+    /// The scaled page is centered inside the printable area.
+    pub fn fit_into_printable_area(&self, page_size: na::Vector2<f64>) -> (f64, na::Vector2<f64>) {
+        let page_bounds = Aabb::new(na::point![0.0, 0.0], page_size.into());
+        let printable_area = self.printable_area(page_bounds);
+        let printable_size = printable_area.extents();
+        let scale = (printable_size[0] / page_size[0])
+            .min(printable_size[1] / page_size[1])
+            .max(0.0);
+        let offset = printable_area.mins.coords + (printable_size - page_size * scale) * 0.5;
+
+        (scale, offset)
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(default, rename = "format")]
 pub struct Format {
@@ -158,6 +253,9 @@ pub struct Format {
     pub border_color: Color,
     #[serde(rename = "show_borders")]
     pub show_borders: bool,
+    /// The page margins, drawn as guides together with the borders.
+    #[serde(rename = "margins")]
+    pub margins: PageMargins,
 }
 
 impl Default for Format {
@@ -169,6 +267,7 @@ impl Default for Format {
             orientation: Orientation::default(),
             border_color: Color::from(Self::BORDER_COLOR_DEFAULT),
             show_borders: true,
+            margins: PageMargins::default(),
         }
     }
 }
@@ -187,4 +286,9 @@ impl Format {
     pub const DPI_DEFAULT: f64 = 96.0;
 
     pub const BORDER_COLOR_DEFAULT: piet::Color = color::GNOME_BRIGHTS[2];
+
+    /// The scale from document coordinates to points (1/72 inch), the unit of Pdf surfaces.
+    pub fn scale_to_pt(&self) -> f64 {
+        MeasureUnit::convert_measurement(1.0, MeasureUnit::Px, self.dpi, MeasureUnit::Pt, self.dpi)
+    }
 }
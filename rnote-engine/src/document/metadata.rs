@@ -0,0 +1,144 @@
+// Imports
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The metadata of a document, describing it in the exported files.
+///
+/// Exported into the document information of Pdf's and the `<metadata>` element of Svg's.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename = "document_metadata")]
+pub struct DocumentMetadata {
+    /// The title. When empty, the file name is used as title on export.
+    #[serde(rename = "title")]
+    pub title: String,
+    #[serde(rename = "author")]
+    pub author: String,
+    #[serde(rename = "subject")]
+    pub subject: String,
+    #[serde(rename = "keywords")]
+    pub keywords: Vec<String>,
+    /// Additional user defined fields.
+    #[serde(rename = "custom")]
+    pub custom: BTreeMap<String, String>,
+}
+
+impl DocumentMetadata {
+    /// The namespace of the custom fields in the exported Svg metadata.
+    pub const SVG_CUSTOM_NAMESPACE: &str = "urn:rnote:metadata";
+
+    pub fn is_empty(&self) -> bool {
+        self.title.is_empty()
+            && self.author.is_empty()
+            && self.subject.is_empty()
+            && self.keywords.is_empty()
+            && self.custom.is_empty()
+    }
+
+    /// The title, or the fallback when the title is not set.
+    pub fn title_or<'a>(&'a self, fallback: &'a str) -> &'a str {
+        if self.title.trim().is_empty() {
+            fallback
+        } else {
+            &self.title
+        }
+    }
+
+    /// The keywords separated by commas, as expected in the Pdf document information.
+    pub fn keywords_joined(&self) -> String {
+        self.keywords
+            .iter()
+            .map(|k| k.trim())
+            .filter(|k| !k.is_empty())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
+
+    /// Set the keywords from a comma separated list.
+    pub fn set_keywords_from_str(&mut self, keywords: &str) {
+        self.keywords = keywords
+            .split(',')
+            .map(|k| k.trim())
+            .filter(|k| !k.is_empty())
+            .map(String::from)
+            .collect();
+    }
+
+    /// Set the metadata in the document information of the Pdf surface.
+    ///
+    /// The custom fields are not exported, because setting custom document information needs a newer cairo version.
+    pub(crate) fn apply_to_pdf_surface(
+        &self,
+        surface: &cairo::PdfSurface,
+        fallback_title: &str,
+    ) -> anyhow::Result<()> {
+        surface.set_metadata(cairo::PdfMetadata::Title, self.title_or(fallback_title))?;
+        if !self.author.is_empty() {
+            surface.set_metadata(cairo::PdfMetadata::Author, &self.author)?;
+        }
+        if !self.subject.is_empty() {
+            surface.set_metadata(cairo::PdfMetadata::Subject, &self.subject)?;
+        }
+        let keywords = self.keywords_joined();
+        if !keywords.is_empty() {
+            surface.set_metadata(cairo::PdfMetadata::Keywords, &keywords)?;
+        }
+        Ok(())
+    }
+
+    /// Generates the `<title>` and `<metadata>` elements for the Svg, using the Dublin Core elements in RDF like Inkscape.
+    ///
+    /// The mapping follows XMP: the keywords are the `dc:subject` and the subject is the `dc:description`.
+    /// Returns an empty string when no metadata is set.
+    pub(crate) fn gen_svg_data(&self) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let mut w = xmlwriter::XmlWriter::new(xmlwriter::Options {
+            indent: xmlwriter::Indent::None,
+            ..Default::default()
+        });
+        if !self.title.is_empty() {
+            w.start_element("title");
+            w.write_text(&self.title);
+            w.end_element();
+        }
+        w.start_element("metadata");
+        w.start_element("rdf:RDF");
+        w.write_attribute("xmlns:rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#");
+        w.write_attribute("xmlns:dc", "http://purl.org/dc/elements/1.1/");
+        if !self.custom.is_empty() {
+            w.write_attribute("xmlns:rnote", Self::SVG_CUSTOM_NAMESPACE);
+        }
+        w.start_element("rdf:Description");
+        w.write_attribute("rdf:about", "");
+        for (element, value) in [
+            ("dc:title", &self.title),
+            ("dc:creator", &self.author),
+            ("dc:description", &self.subject),
+        ] {
+            if !value.is_empty() {
+                w.start_element(element);
+                w.write_text(value);
+                w.end_element();
+            }
+        }
+        if !self.keywords.is_empty() {
+            w.start_element("dc:subject");
+            w.start_element("rdf:Bag");
+            for keyword in self.keywords.iter() {
+                w.start_element("rdf:li");
+                w.write_text(keyword);
+                w.end_element();
+            }
+            w.end_element();
+            w.end_element();
+        }
+        for (key, value) in self.custom.iter() {
+            w.start_element("rnote:field");
+            w.write_attribute("rnote:key", key);
+            w.write_text(value);
+            w.end_element();
+        }
+        w.end_document()
+    }
+}
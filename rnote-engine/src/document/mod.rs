@@ -1,10 +1,12 @@
 // Modules
 pub mod background;
 pub mod format;
+pub mod metadata;
 
 // Re-exports
 pub use background::Background;
-pub use format::Format;
+pub use format::{Format, MeasureUnit, PageMargins};
+pub use metadata::DocumentMetadata;
 
 // Imports
 use crate::{Camera, StrokeStore, WidgetFlags};
@@ -80,6 +82,63 @@ impl std::string::ToString for Layout {
     }
 }
 
+/// Whether touch input draws or pans the canvas in a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "touch_drawing_policy")]
+pub enum TouchDrawingPolicy {
+    /// Follow the application wide touch drawing setting.
+    #[serde(rename = "follow_app")]
+    FollowApp,
+    /// Fingers draw with the current pen.
+    #[serde(rename = "finger_draws")]
+    FingerDraws,
+    /// Fingers only pan and zoom the canvas.
+    #[serde(rename = "finger_pans")]
+    FingerPans,
+}
+
+impl Default for TouchDrawingPolicy {
+    fn default() -> Self {
+        Self::FollowApp
+    }
+}
+
+impl TouchDrawingPolicy {
+    /// Whether touch input should draw, given the application wide touch drawing setting.
+    pub fn finger_draws(self, app_touch_drawing: bool) -> bool {
+        match self {
+            Self::FollowApp => app_touch_drawing,
+            Self::FingerDraws => true,
+            Self::FingerPans => false,
+        }
+    }
+}
+
+impl std::str::FromStr for TouchDrawingPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "follow-app" => Ok(Self::FollowApp),
+            "finger-draws" => Ok(Self::FingerDraws),
+            "finger-pans" => Ok(Self::FingerPans),
+            s => Err(anyhow::anyhow!(
+                "TouchDrawingPolicy from_string failed, invalid name: {s}"
+            )),
+        }
+    }
+}
+
+impl std::string::ToString for TouchDrawingPolicy {
+    fn to_string(&self) -> String {
+        match self {
+            Self::FollowApp => String::from("follow-app"),
+            Self::FingerDraws => String::from("finger-draws"),
+            Self::FingerPans => String::from("finger-pans"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(default, rename = "document")]
 pub struct Document {
@@ -97,6 +156,25 @@ pub struct Document {
     pub background: Background,
     #[serde(rename = "layout", alias = "expand_mode")]
     pub layout: Layout,
+    #[serde(rename = "touch_drawing_policy")]
+    pub touch_drawing_policy: TouchDrawingPolicy,
+    /// The total offset the content was moved by when re-anchoring the origin.
+    ///
+    /// Always a multiple of the format size, so positions in the original coordinate space are the current ones minus this offset.
+    #[serde(rename = "origin_offset")]
+    pub origin_offset: na::Vector2<f64>,
+    /// The unit in which measurements and sizes of the document are displayed and entered.
+    #[serde(rename = "measure_unit")]
+    pub measure_unit: MeasureUnit,
+    /// The scale of the drawing, one unit on the document corresponds to this many units in the real world.
+    #[serde(rename = "measure_scale", with = "rnote_compose::serialize::f64_dp3")]
+    pub measure_scale: f64,
+    /// Whether exports are reproducible, producing identical Svg and Pdf files for the same document.
+    ///
+    /// Randomized styles without a seed are then drawn with seeds derived from the strokes,
+    /// and no timestamps are written into exported Pdf's.
+    #[serde(rename = "deterministic_exports")]
+    pub deterministic_exports: bool,
 }
 
 impl Default for Document {
@@ -109,6 +187,11 @@ impl Default for Document {
             format: Format::default(),
             background: Background::default(),
             layout: Layout::default(),
+            touch_drawing_policy: TouchDrawingPolicy::default(),
+            origin_offset: na::Vector2::zeros(),
+            measure_unit: MeasureUnit::default(),
+            measure_scale: 1.0,
+            deterministic_exports: false,
         }
     }
 }
@@ -122,6 +205,19 @@ impl Document {
         b: 0.0,
         a: 0.35,
     };
+    pub const MEASURE_SCALE_MIN: f64 = 0.001;
+    pub const MEASURE_SCALE_MAX: f64 = 100_000.0;
+
+    /// Convert a length on the document to the real-world length in the measurement unit, respecting the measurement scale.
+    pub fn measured_length(&self, length: f64) -> f64 {
+        MeasureUnit::convert_measurement(
+            length,
+            MeasureUnit::Px,
+            self.format.dpi,
+            self.measure_unit,
+            self.format.dpi,
+        ) * self.measure_scale
+    }
 
     pub fn bounds(&self) -> Aabb {
         Aabb::new(
@@ -0,0 +1,63 @@
+// Imports
+use super::RnoteEngine;
+use crate::store::StrokeKey;
+use crate::WidgetFlags;
+use p2d::bounding_volume::Aabb;
+use rnote_compose::shapes::ShapeBehaviour;
+use std::time::Instant;
+
+/// A stroke that has a description (alt-text), reported to accessibility tooling.
+#[derive(Debug, Clone)]
+pub struct DescribedStroke {
+    /// The key of the stroke.
+    pub key: StrokeKey,
+    /// The bounds of the stroke in document coordinate space.
+    pub bounds: Aabb,
+    /// The accessible role of the stroke, e.g. "Image".
+    pub role: &'static str,
+    /// The description of the stroke.
+    pub description: String,
+}
+
+impl RnoteEngine {
+    /// The description (alt-text) of the stroke, if it has one.
+    pub fn stroke_description(&self, key: StrokeKey) -> Option<String> {
+        self.store
+            .get_stroke_ref(key)
+            .and_then(|stroke| stroke.description().map(|d| d.to_string()))
+    }
+
+    /// Set the description (alt-text) of the stroke. `None` or an empty description removes it.
+    ///
+    /// Text strokes don't support descriptions, because their text is accessible directly.
+    pub fn stroke_set_description(
+        &mut self,
+        key: StrokeKey,
+        description: Option<String>,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if !self.store.set_stroke_description(key, description) {
+            return widget_flags;
+        }
+        widget_flags.merge(self.record(Instant::now()));
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
+    /// The strokes that have a description and are visible in the current viewport, in rendering order.
+    pub fn described_strokes_in_viewport(&self) -> Vec<DescribedStroke> {
+        self.store
+            .stroke_keys_as_rendered_intersecting_bounds(self.camera.viewport())
+            .into_iter()
+            .filter_map(|key| {
+                let stroke = self.store.get_stroke_ref(key)?;
+                Some(DescribedStroke {
+                    key,
+                    bounds: stroke.bounds(),
+                    role: stroke.accessible_role(),
+                    description: stroke.description()?.to_string(),
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,341 @@
+// Imports
+use super::RnoteEngine;
+use crate::store::StrokeKey;
+use crate::WidgetFlags;
+use p2d::bounding_volume::Aabb;
+use p2d::query::PointQuery;
+use rnote_compose::helpers::SplitOrder;
+use rnote_compose::shapes::ShapeBehaviour;
+use serde::{Deserialize, Serialize};
+use slotmap::{Key, KeyData};
+use std::path::{Path, PathBuf};
+
+/// A named position in the document, that can be linked to with a [DocAnchor::Bookmark].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "bookmark")]
+pub struct Bookmark {
+    /// The label shown to the user.
+    #[serde(rename = "label")]
+    pub label: String,
+    /// The position in document coordinate space.
+    #[serde(rename = "pos", with = "rnote_compose::serialize::na_vector2_f64_dp3")]
+    pub pos: na::Vector2<f64>,
+}
+
+/// A stable location in a document, that external applications can link to.
+///
+/// The string representation is user-visible and stays valid when the document is saved and reopened:
+/// `page-<n>` for the n-th page (starting at 1), `bookmark-<id>` for a bookmark and `stroke-<id>` for a stroke.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocAnchor {
+    /// The page with the number, starting at 1.
+    Page(usize),
+    /// The bookmark with the id.
+    Bookmark(String),
+    /// The stroke with the key.
+    ///
+    /// The key is stored in the save file, so it is stable for as long as the stroke exists.
+    Stroke(StrokeKey),
+}
+
+impl DocAnchor {
+    const PAGE_PREFIX: &str = "page-";
+    const BOOKMARK_PREFIX: &str = "bookmark-";
+    const STROKE_PREFIX: &str = "stroke-";
+}
+
+impl std::str::FromStr for DocAnchor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(n) = s.strip_prefix(Self::PAGE_PREFIX) {
+            match n.parse::<usize>() {
+                Ok(n) if n >= 1 => Ok(Self::Page(n)),
+                _ => Err(anyhow::anyhow!("invalid page number in anchor `{s}`")),
+            }
+        } else if let Some(id) = s.strip_prefix(Self::BOOKMARK_PREFIX) {
+            if is_valid_bookmark_id(id) {
+                Ok(Self::Bookmark(id.to_string()))
+            } else {
+                Err(anyhow::anyhow!("invalid bookmark id in anchor `{s}`"))
+            }
+        } else if let Some(id) = s.strip_prefix(Self::STROKE_PREFIX) {
+            let ffi = u64::from_str_radix(id, 16)
+                .map_err(|e| anyhow::anyhow!("invalid stroke id in anchor `{s}`, Err: {e:?}"))?;
+            Ok(Self::Stroke(StrokeKey::from(KeyData::from_ffi(ffi))))
+        } else {
+            Err(anyhow::anyhow!("unknown anchor `{s}`"))
+        }
+    }
+}
+
+impl std::fmt::Display for DocAnchor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Page(n) => write!(f, "{}{n}", Self::PAGE_PREFIX),
+            Self::Bookmark(id) => write!(f, "{}{id}", Self::BOOKMARK_PREFIX),
+            Self::Stroke(key) => write!(f, "{}{:x}", Self::STROKE_PREFIX, key.data().as_ffi()),
+        }
+    }
+}
+
+/// A link to a document, optionally to an anchor in it, in the form `rnote://<absolute file path>#<anchor>`.
+///
+/// The app registers itself as handler for the `rnote` URI scheme, so that notes in other applications can
+/// link to a specific spot in a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeepLink {
+    pub file: PathBuf,
+    pub anchor: Option<DocAnchor>,
+}
+
+impl DeepLink {
+    /// The URI scheme of deep links.
+    pub const SCHEME: &str = "rnote";
+
+    pub fn new(file: impl AsRef<Path>, anchor: Option<DocAnchor>) -> Self {
+        Self {
+            file: file.as_ref().to_path_buf(),
+            anchor,
+        }
+    }
+
+    /// Parse a deep link URI.
+    pub fn parse(uri: &str) -> anyhow::Result<Self> {
+        let Some(rest) = uri
+            .split_once(':')
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case(Self::SCHEME))
+            .map(|(_, rest)| rest)
+        else {
+            return Err(anyhow::anyhow!("`{uri}` is not a {} URI", Self::SCHEME));
+        };
+        let (path, anchor) = match rest.split_once('#') {
+            Some((path, anchor)) => (path, Some(percent_decode(anchor)?.parse::<DocAnchor>()?)),
+            None => (rest, None),
+        };
+        // Without an authority the path directly follows the `//`
+        let mut path = percent_decode(path.strip_prefix("//").unwrap_or(path))?;
+        // Windows paths are written with a leading slash like `/C:/..`
+        if path.len() >= 3 && path.starts_with('/') && path.as_bytes()[2] == b':' {
+            path.remove(0);
+        }
+        if path.is_empty() {
+            return Err(anyhow::anyhow!("`{uri}` does not contain a file path"));
+        }
+        Ok(Self {
+            file: PathBuf::from(path),
+            anchor,
+        })
+    }
+
+    /// The URI of the deep link.
+    pub fn to_uri(&self) -> String {
+        let path = self.file.to_string_lossy().replace('\\', "/");
+        let mut uri = format!("{}://", Self::SCHEME);
+        if !path.starts_with('/') {
+            uri.push('/');
+        }
+        uri.push_str(&percent_encode(&path));
+        if let Some(anchor) = &self.anchor {
+            uri.push('#');
+            uri.push_str(&anchor.to_string());
+        }
+        uri
+    }
+}
+
+impl RnoteEngine {
+    /// The anchor of the stroke, if it exists.
+    pub fn stroke_anchor(&self, key: StrokeKey) -> Option<DocAnchor> {
+        self.store
+            .get_stroke_ref(key)
+            .map(|_| DocAnchor::Stroke(key))
+    }
+
+    /// The anchor for the current view, for linking back to it.
+    ///
+    /// That is the focused stroke, the first selected stroke or the page at the center of the viewport.
+    pub fn anchor_for_current_view(&self) -> Option<DocAnchor> {
+        if let Some(key) = self
+            .focused_stroke()
+            .or_else(|| self.store.selection_keys_as_rendered().first().copied())
+        {
+            return Some(DocAnchor::Stroke(key));
+        }
+        let center = na::Point2::from(self.camera.viewport_center());
+        self.document
+            .pages_bounds(SplitOrder::default())
+            .iter()
+            .position(|bounds| bounds.contains_local_point(&center))
+            .map(|i| DocAnchor::Page(i + 1))
+    }
+
+    /// Add a bookmark at the position and return its anchor.
+    ///
+    /// The id of the bookmark is derived from the label and made unique in the document.
+    pub fn add_bookmark(&mut self, label: &str, pos: na::Vector2<f64>) -> DocAnchor {
+        let base_id = bookmark_id_from_label(label);
+        let mut id = base_id.clone();
+        let mut n = 1;
+        while self.doc_bookmarks.contains_key(&id) {
+            n += 1;
+            id = format!("{base_id}-{n}");
+        }
+        self.doc_bookmarks.insert(
+            id.clone(),
+            Bookmark {
+                label: label.trim().to_string(),
+                pos,
+            },
+        );
+        DocAnchor::Bookmark(id)
+    }
+
+    /// Remove the bookmark with the id. Returns the removed bookmark.
+    pub fn remove_bookmark(&mut self, id: &str) -> Option<Bookmark> {
+        self.doc_bookmarks.remove(id)
+    }
+
+    /// The bounds the anchor points to in document coordinate space.
+    ///
+    /// Returns None if the anchor does not exist (anymore) in the document.
+    pub fn anchor_bounds(&self, anchor: &DocAnchor) -> Option<Aabb> {
+        match anchor {
+            DocAnchor::Page(n) => self
+                .document
+                .pages_bounds(SplitOrder::default())
+                .get(n.checked_sub(1)?)
+                .copied(),
+            DocAnchor::Bookmark(id) => self
+                .doc_bookmarks
+                .get(id)
+                .map(|bookmark| Aabb::new(bookmark.pos.into(), bookmark.pos.into())),
+            DocAnchor::Stroke(key) => {
+                if self.store.trashed(*key) != Some(false) {
+                    return None;
+                }
+                self.store
+                    .get_stroke_ref(*key)
+                    .map(|stroke| stroke.bounds())
+            }
+        }
+    }
+
+    /// Move the camera to the anchor.
+    ///
+    /// Pages are shown from their top, bookmarks are centered and strokes are focused.
+    pub fn jump_to_anchor(&mut self, anchor: &DocAnchor) -> anyhow::Result<WidgetFlags> {
+        let Some(bounds) = self.anchor_bounds(anchor) else {
+            return Err(anyhow::anyhow!(
+                "anchor `{anchor}` does not exist in the document"
+            ));
+        };
+        let zoom = self.camera.total_zoom();
+        let widget_flags = match anchor {
+            DocAnchor::Page(_) => {
+                let viewport_extents = self.camera.viewport().extents();
+                let center = na::vector![
+                    bounds.center()[0],
+                    bounds.mins[1] + viewport_extents[1] * 0.5
+                ];
+                self.camera_animate_to(zoom, center, true)
+            }
+            DocAnchor::Bookmark(_) => self.camera_animate_to(zoom, bounds.center().coords, true),
+            DocAnchor::Stroke(key) => {
+                let mut widget_flags = self.set_focused_stroke(Some(*key));
+                widget_flags.merge(self.camera_animate_to(zoom, bounds.center().coords, true));
+                widget_flags
+            }
+        };
+        Ok(widget_flags)
+    }
+}
+
+fn is_valid_bookmark_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Derives a bookmark id from the label, keeping only the ascii alphanumeric characters, separated by dashes.
+fn bookmark_id_from_label(label: &str) -> String {
+    let id = label
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_ascii_lowercase())
+        .collect::<Vec<String>>()
+        .join("-");
+    if id.is_empty() {
+        String::from("bookmark")
+    } else {
+        id
+    }
+}
+
+/// Percent-encodes everything except the unreserved characters and the path separators.
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                encoded.push(b as char)
+            }
+            b => encoded.push_str(&format!("%{b:02X}")),
+        }
+    }
+    encoded
+}
+
+fn percent_decode(s: &str) -> anyhow::Result<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| anyhow::anyhow!("invalid percent-encoding in `{s}`"))?;
+            decoded.push(hex);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Ok(String::from_utf8(decoded)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deep_link_roundtrip() {
+        let link = DeepLink::new(
+            "/home/user/My Notes/lecture #3.rnote",
+            Some(DocAnchor::Stroke(StrokeKey::from(KeyData::from_ffi(
+                (1 << 32) | 17,
+            )))),
+        );
+        let uri = link.to_uri();
+        assert_eq!(
+            uri,
+            "rnote:///home/user/My%20Notes/lecture%20%233.rnote#stroke-100000011"
+        );
+        assert_eq!(DeepLink::parse(&uri).unwrap(), link);
+
+        let link = DeepLink::parse("rnote:///C:/notes/a.rnote#page-2").unwrap();
+        assert_eq!(link.file, PathBuf::from("C:/notes/a.rnote"));
+        assert_eq!(link.anchor, Some(DocAnchor::Page(2)));
+
+        assert!(DeepLink::parse("file:///notes/a.rnote").is_err());
+        assert!(DeepLink::parse("rnote:///notes/a.rnote#page-0").is_err());
+        assert_eq!(
+            bookmark_id_from_label(" Chapter 2: Proofs "),
+            "chapter-2-proofs"
+        );
+    }
+}
@@ -0,0 +1,170 @@
+// Imports
+use super::RnoteEngine;
+use crate::document::{Background, Format, Layout};
+use crate::store::chrono_comp::StrokeLayer;
+use crate::strokes::textstroke::TextStyle;
+use crate::strokes::{BrushStroke, ShapeStroke, Stroke, TextStroke};
+use rnote_compose::penpath::Element;
+use rnote_compose::transform::TransformBehaviour;
+use rnote_compose::{PenPath, Shape, Style};
+
+/// Builds documents from code, e.g. in scripts that generate `.rnote` files.
+///
+/// The document starts with a single page. Pages are stacked vertically, and the positions of added strokes are
+/// relative to the upper left corner of the current page, which is the last added one.
+///
+/// ```rust, ignore
+/// let bytes = DocumentBuilder::new()
+///     .with_format(Format::default())
+///     .with_text("Title", na::vector![40.0, 40.0], TextStyle::default())
+///     .with_shape(
+///         Shape::Line(Line { start: na::vector![40.0, 100.0], end: na::vector![400.0, 100.0] }),
+///         Style::Smooth(SmoothOptions::default()),
+///     )?
+///     .add_page()
+///     .with_pen_stroke(elements, Style::Smooth(SmoothOptions::default()))?
+///     .save_as_rnote_bytes("generated.rnote")?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct DocumentBuilder {
+    format: Format,
+    background: Background,
+    layout: Layout,
+    n_pages: usize,
+    strokes: Vec<(Stroke, Option<StrokeLayer>)>,
+}
+
+impl Default for DocumentBuilder {
+    fn default() -> Self {
+        Self {
+            format: Format::default(),
+            background: Background::default(),
+            layout: Layout::FixedSize,
+            n_pages: 1,
+            strokes: vec![],
+        }
+    }
+}
+
+impl DocumentBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The page format. Should be set before adding pages and strokes, because they are positioned by the page size.
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// The document layout, fixed size by default.
+    ///
+    /// Documents in other layouts are resized to fit the strokes, but the pages are still used to position them.
+    pub fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Append a page, which then becomes the current one.
+    pub fn add_page(mut self) -> Self {
+        self.n_pages += 1;
+        self
+    }
+
+    /// The number of pages.
+    pub fn n_pages(&self) -> usize {
+        self.n_pages
+    }
+
+    /// The offset of the upper left corner of the current page on the document.
+    pub fn current_page_offset(&self) -> na::Vector2<f64> {
+        na::vector![0.0, (self.n_pages - 1) as f64 * self.format.height]
+    }
+
+    /// Add a stroke, positioned relative to the current page.
+    pub fn with_stroke(mut self, mut stroke: Stroke) -> Self {
+        stroke.translate(self.current_page_offset());
+        self.strokes.push((stroke, None));
+        self
+    }
+
+    /// Add a pen stroke through the elements, positioned relative to the current page.
+    ///
+    /// Empty element iterators are skipped. Fails for styles that are not supported for pen strokes.
+    pub fn with_pen_stroke(
+        self,
+        elements: impl IntoIterator<Item = Element>,
+        style: Style,
+    ) -> anyhow::Result<Self> {
+        if let Style::Rough(_) = style {
+            return Err(anyhow::anyhow!(
+                "DocumentBuilder can't add pen stroke, the rough style is not supported for pen strokes."
+            ));
+        }
+        let Some(path) = PenPath::try_from_elements(elements) else {
+            log::warn!("DocumentBuilder skipped adding pen stroke without elements.");
+            return Ok(self);
+        };
+        Ok(self.with_stroke(Stroke::BrushStroke(BrushStroke::from_penpath(path, style))))
+    }
+
+    /// Add a shape stroke, positioned relative to the current page.
+    ///
+    /// Fails for styles that are not supported for shapes, only the smooth and rough styles are.
+    pub fn with_shape(self, shape: Shape, style: Style) -> anyhow::Result<Self> {
+        match style {
+            Style::Smooth(_) | Style::Rough(_) => {
+                Ok(self.with_stroke(Stroke::ShapeStroke(ShapeStroke::new(shape, style))))
+            }
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => Err(anyhow::anyhow!(
+                "DocumentBuilder can't add shape, only the smooth and rough styles are supported for shapes."
+            )),
+        }
+    }
+
+    /// Add a text stroke with its upper left corner at the position, relative to the current page.
+    pub fn with_text(
+        self,
+        text: impl Into<String>,
+        upper_left_pos: na::Vector2<f64>,
+        text_style: TextStyle,
+    ) -> Self {
+        self.with_stroke(Stroke::TextStroke(TextStroke::new(
+            text.into(),
+            upper_left_pos,
+            text_style,
+        )))
+    }
+
+    /// Build the engine holding the document and its strokes.
+    pub fn build(self) -> RnoteEngine {
+        let mut engine = RnoteEngine::default();
+        engine.document.format = self.format;
+        engine.document.background = self.background;
+        engine.document.layout = self.layout;
+
+        for (stroke, layer) in self.strokes {
+            engine.store.insert_stroke(stroke, layer);
+        }
+        let _ = engine.doc_resize_to_fit_strokes();
+        if self.layout == Layout::FixedSize {
+            // Keep trailing empty pages
+            engine.document.height = engine
+                .document
+                .height
+                .max(self.n_pages as f64 * self.format.height);
+        }
+        engine
+    }
+
+    /// Build the document and save it as `.rnote` file bytes.
+    pub fn save_as_rnote_bytes(self, file_name: &str) -> anyhow::Result<Vec<u8>> {
+        futures::executor::block_on(self.build().save_as_rnote_bytes(file_name.to_string()))
+            .map_err(|e| anyhow::anyhow!("Saving built document failed, Err: {e:?}"))?
+    }
+}
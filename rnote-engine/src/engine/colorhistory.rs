@@ -0,0 +1,54 @@
+// Imports
+use rnote_compose::Color;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// The recently used stroke colors, most recent first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "color_history")]
+pub struct ColorHistory {
+    #[serde(rename = "colors")]
+    colors: VecDeque<Color>,
+}
+
+impl ColorHistory {
+    /// The maximum number of colors that are remembered.
+    pub const LEN_MAX: usize = 16;
+
+    /// The colors, most recent first.
+    pub fn colors(&self) -> Vec<Color> {
+        self.colors.iter().copied().collect()
+    }
+
+    /// Whether no colors are remembered.
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Mark the color as the most recently used one.
+    ///
+    /// Colors are compared with 8 bit precision, fully transparent colors are ignored.
+    /// Returns true when the history has changed.
+    pub fn push(&mut self, color: Color) -> bool {
+        if color.a == 0.0 {
+            return false;
+        }
+        if self
+            .colors
+            .front()
+            .map_or(false, |front| u32::from(*front) == u32::from(color))
+        {
+            return false;
+        }
+        self.colors
+            .retain(|other| u32::from(*other) != u32::from(color));
+        self.colors.push_front(color);
+        self.colors.truncate(Self::LEN_MAX);
+        true
+    }
+
+    /// Forget all colors.
+    pub fn clear(&mut self) {
+        self.colors.clear();
+    }
+}
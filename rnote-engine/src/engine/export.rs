@@ -1,13 +1,22 @@
 // Imports
-use super::{EngineConfig, RnoteEngine, StrokeContent};
+use super::imposition::{self, PagesPerSheet, Sheet, SheetSlot, SHEET_SIZE_A4_PT};
+use super::{
+    EngineConfig, EngineSnapshot, HeaderFooter, HeaderFooterFields, MarkdownExportPrefs,
+    PrintPrefs, RnoteEngine, StrokeContent, Watermark,
+};
 use crate::fileformats::rnoteformat::RnoteFile;
 use crate::fileformats::{xoppformat, FileFormatSaver};
-use crate::render;
+use crate::fillpatterns::FillPattern;
+use crate::render::{self, DownscaleFilter};
+use crate::store::StrokeKey;
+use crate::{EngineError, Progress};
 use anyhow::Context;
 use futures::channel::oneshot;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rayon::prelude::*;
 use rnote_compose::helpers::SplitOrder;
 use rnote_compose::transform::TransformBehaviour;
+use rnote_compose::Color;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -33,6 +42,8 @@ pub enum DocExportFormat {
     Pdf,
     #[serde(rename = "xopp")]
     Xopp,
+    #[serde(rename = "tiff")]
+    Tiff,
 }
 
 impl Default for DocExportFormat {
@@ -61,10 +72,69 @@ impl DocExportFormat {
             DocExportFormat::Svg => String::from("svg"),
             DocExportFormat::Pdf => String::from("pdf"),
             DocExportFormat::Xopp => String::from("xopp"),
+            DocExportFormat::Tiff => String::from("tiff"),
         }
     }
 }
 
+/// A range of pages, with zero-based and inclusive page indices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "page_range")]
+pub struct PageRange {
+    /// The index of the first page.
+    #[serde(rename = "first")]
+    pub first: usize,
+    /// The index of the last page.
+    #[serde(rename = "last")]
+    pub last: usize,
+}
+
+impl PageRange {
+    /// Whether the page index is inside the range.
+    pub fn contains(&self, page: usize) -> bool {
+        (self.first..=self.last).contains(&page)
+    }
+}
+
+/// The background color of exports.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "export_background_color")]
+pub enum ExportBackgroundColor {
+    /// The background color of the document.
+    #[serde(rename = "document")]
+    Document,
+    #[serde(rename = "transparent")]
+    Transparent,
+    #[serde(rename = "white")]
+    White,
+    #[serde(rename = "custom")]
+    Custom(Color),
+}
+
+impl Default for ExportBackgroundColor {
+    fn default() -> Self {
+        Self::Document
+    }
+}
+
+impl ExportBackgroundColor {
+    /// Replace the background color of the content.
+    ///
+    /// The background pattern is kept, so that for example dark documents can be exported on white paper.
+    pub fn apply(self, mut content: StrokeContent) -> StrokeContent {
+        let color = match self {
+            Self::Document => return content,
+            Self::Transparent => Color::TRANSPARENT,
+            Self::White => Color::WHITE,
+            Self::Custom(color) => color,
+        };
+        if let Some(background) = content.background.as_mut() {
+            background.color = color;
+        }
+        content
+    }
+}
+
 /// Document export preferences.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(default, rename = "doc_export_prefs")]
@@ -75,12 +145,50 @@ pub struct DocExportPrefs {
     /// Whether the background pattern should be exported.
     #[serde(rename = "with_pattern")]
     pub with_pattern: bool,
+    /// The color of the background, when it is exported.
+    #[serde(rename = "background_color")]
+    pub background_color: ExportBackgroundColor,
     /// The export format.
     #[serde(rename = "export_format")]
     pub export_format: DocExportFormat,
     /// The page order when documents with layouts that expand in horizontal and vertical directions are cut into pages.
     #[serde(rename = "page_order")]
     pub page_order: SplitOrder,
+    /// The bitmap scale-factor in relation to the actual size, when exporting to a bitmap format.
+    #[serde(rename = "bitmap_scalefactor")]
+    pub bitmap_scalefactor: f64,
+    /// The range of pages that should be exported. All pages are exported when None.
+    #[serde(rename = "page_range")]
+    pub page_range: Option<PageRange>,
+    /// Whether every page should be cropped to the bounds of its content.
+    #[serde(rename = "crop_to_content")]
+    pub crop_to_content: bool,
+    /// The margin around the content when cropping to it.
+    #[serde(rename = "crop_margin")]
+    pub crop_margin: f64,
+    /// Whether the pages are scaled down to fit into the printable area inside the page margins of the format.
+    ///
+    /// Only has an effect on Pdf export and printing when the pages are not cropped to their content,
+    /// and on Pdf export only when a single page is placed on every sheet and no booklet is exported.
+    #[serde(rename = "fit_to_printable_area")]
+    pub fit_to_printable_area: bool,
+    /// The number of pages placed onto every A4 sheet, when exporting to Pdf.
+    #[serde(rename = "pages_per_sheet")]
+    pub pages_per_sheet: PagesPerSheet,
+    /// The gutter between the pages on a sheet, in document coordinate space.
+    #[serde(rename = "n_up_gutter", with = "rnote_compose::serialize::f64_dp3")]
+    pub n_up_gutter: f64,
+    /// Whether the pages are arranged as booklet on A4 sheets, when exporting to Pdf.
+    ///
+    /// The exported sheets are meant to be printed double-sided and folded in the middle. Takes precedence over the
+    /// pages per sheet, the gutter is used as space around the fold.
+    #[serde(rename = "booklet")]
+    pub booklet: bool,
+    /// Whether text is converted to glyph outlines, when exporting to Svg.
+    ///
+    /// The exported file then renders the same without the fonts being installed.
+    #[serde(rename = "text_as_paths")]
+    pub text_as_paths: bool,
 }
 
 impl Default for DocExportPrefs {
@@ -88,8 +196,18 @@ impl Default for DocExportPrefs {
         Self {
             with_background: true,
             with_pattern: true,
+            background_color: ExportBackgroundColor::default(),
             export_format: DocExportFormat::default(),
             page_order: SplitOrder::default(),
+            bitmap_scalefactor: 1.8,
+            page_range: None,
+            crop_to_content: false,
+            crop_margin: 12.0,
+            fit_to_printable_area: false,
+            pages_per_sheet: PagesPerSheet::default(),
+            n_up_gutter: 12.0,
+            booklet: false,
+            text_as_paths: false,
         }
     }
 }
@@ -120,6 +238,8 @@ pub enum DocPagesExportFormat {
     Png,
     #[serde(rename = "jpeg")]
     Jpeg,
+    #[serde(rename = "tiff")]
+    Tiff,
 }
 
 impl Default for DocPagesExportFormat {
@@ -147,6 +267,7 @@ impl DocPagesExportFormat {
             Self::Svg => String::from("svg"),
             Self::Png => String::from("png"),
             Self::Jpeg => String::from("jpg"),
+            Self::Tiff => String::from("tiff"),
         }
     }
 }
@@ -161,6 +282,9 @@ pub struct DocPagesExportPrefs {
     /// Whether the background pattern should be exported.
     #[serde(rename = "with_pattern")]
     pub with_pattern: bool,
+    /// The color of the background, when it is exported.
+    #[serde(rename = "background_color")]
+    pub background_color: ExportBackgroundColor,
     /// Export format
     #[serde(rename = "export_format")]
     pub export_format: DocPagesExportFormat,
@@ -173,10 +297,32 @@ pub struct DocPagesExportPrefs {
     /// Quality when exporting as Jpeg.
     #[serde(rename = "jpg_quality")]
     pub jpeg_quality: u8,
+    /// The supersampling factor for bitmap exports.
+    ///
+    /// The bitmap is rendered at this multiple of its size and then downscaled to reduce aliasing. 1 disables supersampling.
+    #[serde(rename = "bitmap_supersampling")]
+    pub bitmap_supersampling: u32,
+    /// The filter used when downscaling supersampled bitmaps.
+    #[serde(rename = "bitmap_downscale_filter")]
+    pub bitmap_downscale_filter: DownscaleFilter,
+    /// The range of pages that should be exported. All pages are exported when None.
+    #[serde(rename = "page_range")]
+    pub page_range: Option<PageRange>,
+    /// Whether every page should be cropped to the bounds of its content.
+    #[serde(rename = "crop_to_content")]
+    pub crop_to_content: bool,
+    /// The margin around the content when cropping to it.
+    #[serde(rename = "crop_margin")]
+    pub crop_margin: f64,
+    /// Whether text is converted to glyph outlines, when exporting to Svg.
+    #[serde(rename = "text_as_paths")]
+    pub text_as_paths: bool,
 }
 
 impl DocPagesExportPrefs {
     const MARGIN: f64 = 0.0;
+    pub const BITMAP_SUPERSAMPLING_MIN: u32 = 1;
+    pub const BITMAP_SUPERSAMPLING_MAX: u32 = render::Image::SUPERSAMPLING_MAX;
 }
 
 impl Default for DocPagesExportPrefs {
@@ -184,10 +330,17 @@ impl Default for DocPagesExportPrefs {
         Self {
             with_background: true,
             with_pattern: true,
+            background_color: ExportBackgroundColor::default(),
             export_format: DocPagesExportFormat::default(),
             page_order: SplitOrder::default(),
             bitmap_scalefactor: 1.8,
             jpeg_quality: 85,
+            bitmap_supersampling: 1,
+            bitmap_downscale_filter: DownscaleFilter::default(),
+            page_range: None,
+            crop_to_content: false,
+            crop_margin: 12.0,
+            text_as_paths: false,
         }
     }
 }
@@ -214,6 +367,8 @@ pub enum SelectionExportFormat {
     Png,
     #[serde(rename = "jpeg")]
     Jpeg,
+    #[serde(rename = "pdf")]
+    Pdf,
 }
 
 impl Default for SelectionExportFormat {
@@ -228,6 +383,7 @@ impl SelectionExportFormat {
             SelectionExportFormat::Svg => String::from("svg"),
             SelectionExportFormat::Png => String::from("png"),
             SelectionExportFormat::Jpeg => String::from("jpg"),
+            SelectionExportFormat::Pdf => String::from("pdf"),
         }
     }
 }
@@ -254,6 +410,9 @@ pub struct SelectionExportPrefs {
     /// Whether the background pattern should be exported.
     #[serde(rename = "with_pattern")]
     pub with_pattern: bool,
+    /// The color of the background, when it is exported.
+    #[serde(rename = "background_color")]
+    pub background_color: ExportBackgroundColor,
     /// Export format.
     #[serde(rename = "export_format")]
     pub export_format: SelectionExportFormat,
@@ -263,19 +422,45 @@ pub struct SelectionExportPrefs {
     /// Quality when exporting as Jpeg.
     #[serde(rename = "jpg_quality")]
     pub jpeg_quality: u8,
+    /// The supersampling factor for bitmap exports.
+    ///
+    /// The bitmap is rendered at this multiple of its size and then downscaled to reduce aliasing. 1 disables supersampling.
+    #[serde(rename = "bitmap_supersampling")]
+    pub bitmap_supersampling: u32,
+    /// The filter used when downscaling supersampled bitmaps.
+    #[serde(rename = "bitmap_downscale_filter")]
+    pub bitmap_downscale_filter: DownscaleFilter,
     /// The margins of the export extending the bounds of the selection.
     #[serde(rename = "margin")]
     pub margin: f64,
 }
 
+impl SelectionExportPrefs {
+    pub const BITMAP_SUPERSAMPLING_MIN: u32 = 1;
+    pub const BITMAP_SUPERSAMPLING_MAX: u32 = render::Image::SUPERSAMPLING_MAX;
+
+    /// The DPI of bitmap exports, derived from the bitmap scale-factor and the document DPI.
+    pub fn bitmap_dpi(&self, document_dpi: f64) -> f64 {
+        self.bitmap_scalefactor * document_dpi
+    }
+
+    /// Set the bitmap scale-factor so that bitmap exports have the given DPI.
+    pub fn set_bitmap_dpi(&mut self, dpi: f64, document_dpi: f64) {
+        self.bitmap_scalefactor = dpi / document_dpi;
+    }
+}
+
 impl Default for SelectionExportPrefs {
     fn default() -> Self {
         Self {
             with_background: true,
             with_pattern: false,
+            background_color: ExportBackgroundColor::default(),
             export_format: SelectionExportFormat::Svg,
             bitmap_scalefactor: 1.8,
             jpeg_quality: 85,
+            bitmap_supersampling: 1,
+            bitmap_downscale_filter: DownscaleFilter::default(),
             margin: 12.0,
         }
     }
@@ -294,6 +479,55 @@ pub struct ExportPrefs {
     /// Selection export preferences.
     #[serde(rename = "selection_export_prefs")]
     pub selection_export_prefs: SelectionExportPrefs,
+    /// Print preferences.
+    #[serde(rename = "print_prefs")]
+    pub print_prefs: PrintPrefs,
+    /// Markdown export preferences.
+    #[serde(rename = "markdown_export_prefs")]
+    pub markdown_export_prefs: MarkdownExportPrefs,
+}
+
+impl ExportPrefs {
+    /// Clamps the values to their valid ranges.
+    pub fn clamped(mut self) -> Self {
+        self.doc_pages_export_prefs.bitmap_supersampling =
+            self.doc_pages_export_prefs.bitmap_supersampling.clamp(
+                DocPagesExportPrefs::BITMAP_SUPERSAMPLING_MIN,
+                DocPagesExportPrefs::BITMAP_SUPERSAMPLING_MAX,
+            );
+        self.selection_export_prefs.bitmap_supersampling =
+            self.selection_export_prefs.bitmap_supersampling.clamp(
+                SelectionExportPrefs::BITMAP_SUPERSAMPLING_MIN,
+                SelectionExportPrefs::BITMAP_SUPERSAMPLING_MAX,
+            );
+        self
+    }
+}
+
+/// A named export preset.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "export_preset")]
+pub struct ExportPreset {
+    /// The name of the preset.
+    #[serde(rename = "name")]
+    pub name: String,
+    /// The export preferences of the preset.
+    #[serde(rename = "export_prefs")]
+    pub export_prefs: ExportPrefs,
+}
+
+/// Serialize the snapshot into the bytes of a .rnote file.
+pub(crate) fn rnote_bytes_from_snapshot(
+    mut engine_snapshot: EngineSnapshot,
+    file_name: &str,
+) -> anyhow::Result<Vec<u8>> {
+    engine_snapshot.strip_instance_strokes();
+    engine_snapshot.embed_fonts();
+    engine_snapshot.collect_fill_patterns();
+    let rnote_file = RnoteFile {
+        engine_snapshot: ijson::to_value(&engine_snapshot)?,
+    };
+    rnote_file.save_as_bytes(file_name)
 }
 
 impl RnoteEngine {
@@ -306,15 +540,10 @@ impl RnoteEngine {
         file_name: String,
     ) -> oneshot::Receiver<anyhow::Result<Vec<u8>>> {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
-        let engine_snapshot = self.take_snapshot();
+        let engine_snapshot = self.take_snapshot_deferred();
         rayon::spawn(move || {
-            let result = || -> anyhow::Result<Vec<u8>> {
-                let rnote_file = RnoteFile {
-                    engine_snapshot: ijson::to_value(&engine_snapshot)?,
-                };
-                rnote_file.save_as_bytes(&file_name)
-            };
-            if let Err(_data) = oneshot_sender.send(result()) {
+            let result = rnote_bytes_from_snapshot(engine_snapshot.finish(), &file_name);
+            if let Err(_data) = oneshot_sender.send(result) {
                 log::error!("Sending result to receiver in save_as_rnote_bytes() failed. Receiver was already dropped.");
             }
         });
@@ -329,21 +558,78 @@ impl RnoteEngine {
             penholder: self.penholder.clone_config(),
             import_prefs: self.import_prefs,
             export_prefs: self.export_prefs,
+            export_presets: self.export_presets.clone(),
+            export_watermark: self.export_watermark.clone(),
+            export_header_footer: self.export_header_footer.clone(),
+            navigation_prefs: self.navigation_prefs,
+            overlay_config: self.camera.overlay_config,
+            gesture_config: self.gesture_config,
+            render_cache_prefs: self.render_cache_prefs,
+            save_prefs: self.save_prefs,
             pen_sounds: self.pen_sounds(),
+            color_history: self.color_history.clone(),
         }
     }
 
+    /// The names of the stored export presets.
+    pub fn list_export_presets(&self) -> Vec<String> {
+        self.export_presets
+            .iter()
+            .map(|preset| preset.name.clone())
+            .collect()
+    }
+
+    /// Store the current export preferences as preset with the given name.
+    ///
+    /// Replaces an existing preset with the same name.
+    pub fn save_export_preset(&mut self, name: String) {
+        let preset = ExportPreset {
+            name,
+            export_prefs: self.export_prefs,
+        };
+        match self
+            .export_presets
+            .iter_mut()
+            .find(|existing| existing.name == preset.name)
+        {
+            Some(existing) => *existing = preset,
+            None => self.export_presets.push(preset),
+        }
+    }
+
+    /// Apply the export preset with the given name to the export preferences.
+    pub fn apply_export_preset(&mut self, name: &str) -> anyhow::Result<()> {
+        let preset = self
+            .export_presets
+            .iter()
+            .find(|preset| preset.name == name)
+            .ok_or_else(|| anyhow::anyhow!("No export preset with name `{name}` found."))?;
+        self.export_prefs = preset.export_prefs.clamped();
+        Ok(())
+    }
+
+    /// Remove the export preset with the given name.
+    ///
+    /// Returns true if the preset existed.
+    pub fn remove_export_preset(&mut self, name: &str) -> bool {
+        let n_presets = self.export_presets.len();
+        self.export_presets.retain(|preset| preset.name != name);
+        self.export_presets.len() != n_presets
+    }
+
     pub fn extract_document_content(&self) -> StrokeContent {
-        StrokeContent::default()
-            .with_strokes(
-                self.store
-                    .get_strokes_arc(&self.store.stroke_keys_as_rendered()),
-            )
-            .with_bounds(Some(
-                self.bounds_w_content_extended()
-                    .unwrap_or(self.document.bounds()),
-            ))
-            .with_background(Some(self.document.background))
+        self.with_export_seeds(
+            StrokeContent::default()
+                .with_strokes(
+                    self.store
+                        .get_strokes_arc(&self.store.stroke_keys_as_rendered()),
+                )
+                .with_bounds(Some(
+                    self.bounds_w_content_extended()
+                        .unwrap_or(self.document.bounds()),
+                ))
+                .with_background(Some(self.document.background)),
+        )
     }
 
     pub fn extract_pages_content(&self, page_order: SplitOrder) -> Vec<StrokeContent> {
@@ -361,6 +647,7 @@ impl RnoteEngine {
                     .with_bounds(Some(bounds))
                     .with_background(Some(self.document.background))
             })
+            .map(|content| self.with_export_seeds(content))
             .collect()
     }
 
@@ -370,12 +657,53 @@ impl RnoteEngine {
             return None;
         }
         Some(
-            StrokeContent::default()
-                .with_strokes(self.store.get_strokes_arc(&selection_keys))
-                .with_background(Some(self.document.background)),
+            self.with_export_seeds(
+                StrokeContent::default()
+                    .with_strokes(self.store.get_strokes_arc(&selection_keys))
+                    .with_background(Some(self.document.background)),
+            ),
         )
     }
 
+    /// Assign seeds to the strokes of the content when the document has deterministic exports enabled.
+    fn with_export_seeds(&self, content: StrokeContent) -> StrokeContent {
+        if self.document.deterministic_exports {
+            content.with_deterministic_seeds()
+        } else {
+            content
+        }
+    }
+
+    /// The overrides for exported pages, with the title of the document metadata, or else the given title.
+    fn export_page_overrides(
+        &self,
+        background_color: ExportBackgroundColor,
+        title: Option<&str>,
+    ) -> ExportPageOverrides<'_> {
+        let title = if self.doc_metadata.title.is_empty() {
+            title.unwrap_or_default().to_string()
+        } else {
+            self.doc_metadata.title.clone()
+        };
+        ExportPageOverrides {
+            background_color,
+            watermark: self.export_watermark.as_ref(),
+            header_footer: self.export_header_footer.as_ref(),
+            title,
+            date: export_date(self.document.deterministic_exports),
+        }
+    }
+
+    /// Export a single stroke as standalone Svg, with a viewBox tightly fitting the stroke bounds extended by the margin.
+    pub fn export_stroke_as_svg(&self, key: StrokeKey, margin: f64) -> anyhow::Result<String> {
+        self.store
+            .get_stroke_ref(key)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Exporting stroke as Svg failed, no stroke for key {key:?}")
+            })?
+            .gen_standalone_svg(margin)
+    }
+
     /// Export the current engine config as Json string.
     pub fn export_engine_config_as_json(&self) -> anyhow::Result<String> {
         Ok(serde_json::to_string(&self.extract_engine_config())?)
@@ -389,35 +717,93 @@ impl RnoteEngine {
     }
 
     /// Export the document.
+    ///
+    /// The progress is advanced with the exported pages, and the export fails with [EngineError::Cancelled]
+    /// when it gets cancelled.
     pub fn export_doc(
         &self,
         title: String,
         doc_export_prefs_override: Option<DocExportPrefs>,
+        progress: Progress,
     ) -> oneshot::Receiver<Result<Vec<u8>, anyhow::Error>> {
         let doc_export_prefs =
             doc_export_prefs_override.unwrap_or(self.export_prefs.doc_export_prefs);
 
         match doc_export_prefs.export_format {
-            DocExportFormat::Svg => self.export_doc_as_svg_bytes(doc_export_prefs_override),
-            DocExportFormat::Pdf => self.export_doc_as_pdf_bytes(title, doc_export_prefs_override),
+            DocExportFormat::Svg => {
+                self.export_doc_as_svg_bytes(doc_export_prefs_override, progress)
+            }
+            DocExportFormat::Pdf => {
+                self.export_doc_as_pdf_bytes(title, doc_export_prefs_override, progress)
+            }
             DocExportFormat::Xopp => {
-                self.export_doc_as_xopp_bytes(title, doc_export_prefs_override)
+                self.export_doc_as_xopp_bytes(title, doc_export_prefs_override, progress)
+            }
+            DocExportFormat::Tiff => {
+                self.export_doc_as_tiff_bytes(doc_export_prefs_override, progress)
             }
         }
     }
 
+    /// Export the document with the custom exporter registered for the file extension.
+    ///
+    /// The export format of the preferences is ignored.
+    pub fn export_doc_w_custom_exporter(
+        &self,
+        file_ext: &str,
+        title: String,
+        doc_export_prefs_override: Option<DocExportPrefs>,
+    ) -> oneshot::Receiver<Result<Vec<u8>, anyhow::Error>> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
+        let doc_export_prefs =
+            doc_export_prefs_override.unwrap_or(self.export_prefs.doc_export_prefs);
+        let exporter = crate::exporters::custom_exporter_for_ext(file_ext);
+        let file_ext = file_ext.to_string();
+        let engine_snapshot = self.take_snapshot_deferred();
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<u8>> {
+                let engine_snapshot = engine_snapshot.finish();
+                let exporter = exporter.ok_or_else(|| {
+                    EngineError::UnsupportedFormat(format!(
+                        "no custom exporter registered for file extension `{file_ext}`"
+                    ))
+                })?;
+                (exporter.export)(engine_snapshot, doc_export_prefs, title).with_context(|| {
+                    format!("Exporting with custom exporter `{}` failed", exporter.name)
+                })
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("Sending result to receiver failed. Receiver already dropped.");
+            }
+        });
+
+        oneshot_receiver
+    }
+
     /// Export the doc with the strokes as Svg.
     fn export_doc_as_svg_bytes(
         &self,
         doc_export_prefs_override: Option<DocExportPrefs>,
+        progress: Progress,
     ) -> oneshot::Receiver<Result<Vec<u8>, anyhow::Error>> {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
         let doc_export_prefs =
             doc_export_prefs_override.unwrap_or(self.export_prefs.doc_export_prefs);
-        let doc_content = self.extract_document_content();
+        let mut doc_content = self.extract_document_content();
+        if doc_export_prefs.crop_to_content {
+            doc_content = doc_content.cropped_to_strokes(doc_export_prefs.crop_margin);
+        }
+        let doc_content = self
+            .export_page_overrides(doc_export_prefs.background_color, None)
+            .apply(0, 1, doc_content)
+            .with_text_as_paths(doc_export_prefs.text_as_paths);
+        let metadata = self.doc_metadata.clone();
 
         rayon::spawn(move || {
             let result = || -> anyhow::Result<Vec<u8>> {
+                progress.check_cancelled()?;
                 let doc_svg = doc_content
                     .gen_svg(
                         doc_export_prefs.with_background,
@@ -425,9 +811,11 @@ impl RnoteEngine {
                         DocExportPrefs::MARGIN,
                     )?
                     .ok_or(anyhow::anyhow!("Generating doc svg failed, returned None."))?;
+                progress.advance_to(1.0);
+                let svg_data = metadata.gen_svg_data() + doc_svg.svg_data.as_str();
                 Ok(rnote_compose::utils::add_xml_header(
                     rnote_compose::utils::wrap_svg_root(
-                        doc_svg.svg_data.as_str(),
+                        svg_data.as_str(),
                         Some(doc_svg.bounds),
                         Some(doc_svg.bounds),
                         false,
@@ -450,26 +838,90 @@ impl RnoteEngine {
         &self,
         title: String,
         doc_export_prefs_override: Option<DocExportPrefs>,
+        progress: Progress,
     ) -> oneshot::Receiver<anyhow::Result<Vec<u8>>> {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
         let doc_export_prefs =
             doc_export_prefs_override.unwrap_or(self.export_prefs.doc_export_prefs);
-        let pages_content = self.extract_pages_content(doc_export_prefs.page_order);
+        let pages_content = prepare_pages_content(
+            self.extract_pages_content(doc_export_prefs.page_order),
+            doc_export_prefs.page_range,
+            doc_export_prefs.crop_to_content,
+            doc_export_prefs.crop_margin,
+            &self.export_page_overrides(doc_export_prefs.background_color, Some(title.as_str())),
+        );
         let format_size = na::vector![self.document.format.width, self.document.format.height];
+        let margins = self.document.format.margins;
+        // Pdf pages are sized in points, so that the exported pages have the physical size of the format
+        let scale_to_pt = self.document.format.scale_to_pt();
+        let fit_to_printable_area = doc_export_prefs.fit_to_printable_area
+            && !doc_export_prefs.crop_to_content
+            && !margins.is_zero();
+        let metadata = self.doc_metadata.clone();
+        let deterministic = self.document.deterministic_exports;
 
         rayon::spawn(move || {
             let result = || -> anyhow::Result<Vec<u8>> {
-                let target_surface =
-                    cairo::PdfSurface::for_stream(format_size[0], format_size[1], Vec::<u8>::new())
-                        .context("Creating Pdf target surface failed.")?;
+                let pages: Vec<(StrokeContent, Aabb)> = pages_content?
+                    .into_iter()
+                    .filter_map(|page_content| {
+                        let page_bounds = page_content.bounds()?;
+                        Some((page_content, page_bounds))
+                    })
+                    .collect();
+                let page_sizes = pages
+                    .iter()
+                    .map(|(_, page_bounds)| page_bounds.extents() * scale_to_pt)
+                    .collect::<Vec<na::Vector2<f64>>>();
+                let sheet_size = na::Vector2::from(SHEET_SIZE_A4_PT);
+                let sheets = match doc_export_prefs.pages_per_sheet {
+                    _ if doc_export_prefs.booklet => imposition::impose_booklet(
+                        &page_sizes,
+                        &[sheet_size.yx(), sheet_size],
+                        doc_export_prefs.n_up_gutter * scale_to_pt,
+                    ),
+                    PagesPerSheet::One => page_sizes
+                        .iter()
+                        .enumerate()
+                        .map(|(page, &page_size)| {
+                            let (scale, offset) = if fit_to_printable_area {
+                                let (scale, offset) =
+                                    margins.fit_into_printable_area(page_size / scale_to_pt);
+                                (scale, offset * scale_to_pt)
+                            } else {
+                                (1.0, na::Vector2::zeros())
+                            };
+                            Sheet {
+                                size: page_size,
+                                slots: vec![SheetSlot {
+                                    page,
+                                    scale,
+                                    offset,
+                                }],
+                            }
+                        })
+                        .collect::<Vec<Sheet>>(),
+                    pages_per_sheet => imposition::impose_n_up(
+                        &page_sizes,
+                        &[sheet_size, sheet_size.yx()],
+                        pages_per_sheet,
+                        doc_export_prefs.n_up_gutter * scale_to_pt,
+                    ),
+                };
+                let target_surface = cairo::PdfSurface::for_stream(
+                    format_size[0] * scale_to_pt,
+                    format_size[1] * scale_to_pt,
+                    Vec::<u8>::new(),
+                )
+                .context("Creating Pdf target surface failed.")?;
 
-                target_surface
-                    .set_metadata(cairo::PdfMetadata::Title, title.as_str())
-                    .context("Set pdf surface title metadata failed.")?;
+                metadata
+                    .apply_to_pdf_surface(&target_surface, &title)
+                    .context("Set pdf surface document metadata failed.")?;
                 target_surface
                     .set_metadata(
                         cairo::PdfMetadata::CreateDate,
-                        crate::utils::now_formatted_string().as_str(),
+                        pdf_create_date(deterministic).as_str(),
                     )
                     .context("Set pdf surface date metadata failed.")?;
 
@@ -478,26 +930,57 @@ impl RnoteEngine {
                     let cairo_cx = cairo::Context::new(&target_surface)
                         .context("Creating new cairo context for pdf target surface failed.")?;
 
-                    for (i, page_content) in pages_content.into_iter().enumerate() {
-                        let Some(page_bounds) = page_content.bounds() else {
-                            continue;
-                        };
-                        cairo_cx.save()?;
-                        cairo_cx.translate(-page_bounds.mins[0], -page_bounds.mins[1]);
-                        page_content.draw_to_cairo(
-                            &cairo_cx,
-                            doc_export_prefs.with_background,
-                            doc_export_prefs.with_pattern,
-                            DocExportPrefs::MARGIN,
-                            RnoteEngine::STROKE_EXPORT_IMAGE_SCALE,
-                        )?;
+                    let stage = progress.stage(0.0, 1.0, pages.len());
+                    for (i, sheet) in sheets.iter().enumerate() {
+                        let sheet_num = i + 1;
+                        // Cropped pages differ in size
+                        target_surface
+                            .set_size(sheet.size[0], sheet.size[1])
+                            .context("Setting size of pdf target surface failed.")?;
+                        for slot in sheet.slots.iter() {
+                            // Dropping the surface discards the pages that were already written
+                            progress.check_cancelled()?;
+                            stage.step();
+                            let (page_content, page_bounds) = &pages[slot.page];
+                            let scale = slot.scale * scale_to_pt;
+                            // Cairo can't attach alt-text to tagged figures, so the stroke descriptions
+                            // are additionally listed in the document outline, linking to the strokes.
+                            for (bounds, role, description) in page_content.described_strokes() {
+                                let pos = slot.offset
+                                    + (bounds.mins.coords - page_bounds.mins.coords) * scale;
+                                target_surface
+                                    .add_outline(
+                                        cairo::PDF_OUTLINE_ROOT,
+                                        &format!("{role}: {description}"),
+                                        &format!(
+                                            "page={sheet_num} pos=[{:.3} {:.3}]",
+                                            pos[0], pos[1]
+                                        ),
+                                        cairo::PdfOutline::empty(),
+                                    )
+                                    .context("Adding stroke description to pdf outline failed.")?;
+                            }
+                            cairo_cx.save()?;
+                            cairo_cx.translate(slot.offset[0], slot.offset[1]);
+                            cairo_cx.scale(scale, scale);
+                            cairo_cx.translate(-page_bounds.mins[0], -page_bounds.mins[1]);
+                            page_content.draw_to_cairo(
+                                &cairo_cx,
+                                doc_export_prefs.with_background,
+                                doc_export_prefs.with_pattern,
+                                DocExportPrefs::MARGIN,
+                                RnoteEngine::STROKE_EXPORT_IMAGE_SCALE,
+                            )?;
+                            page_content.draw_text_layers_to_cairo(&cairo_cx)?;
+                            cairo_cx.restore()?;
+                        }
                         cairo_cx.show_page().map_err(|e| {
                             anyhow::anyhow!(
-                                "Showing page failed when exporting page {i} as pdf, Err: {e:?}"
+                                "Showing page failed when exporting sheet {i} as pdf, Err: {e:?}"
                             )
                         })?;
-                        cairo_cx.restore()?;
                     }
+                    stage.finish()?;
                 }
                 let data = *target_surface
                     .finish_output_stream()
@@ -518,20 +1001,90 @@ impl RnoteEngine {
         oneshot_receiver
     }
 
+    /// Export the doc with the strokes as multi-page Tiff, one page for every document page.
+    fn export_doc_as_tiff_bytes(
+        &self,
+        doc_export_prefs_override: Option<DocExportPrefs>,
+        progress: Progress,
+    ) -> oneshot::Receiver<anyhow::Result<Vec<u8>>> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
+        let doc_export_prefs =
+            doc_export_prefs_override.unwrap_or(self.export_prefs.doc_export_prefs);
+        let pages_content = prepare_pages_content(
+            self.extract_pages_content(doc_export_prefs.page_order),
+            doc_export_prefs.page_range,
+            doc_export_prefs.crop_to_content,
+            doc_export_prefs.crop_margin,
+            &self.export_page_overrides(doc_export_prefs.background_color, None),
+        );
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<u8>> {
+                let pages_content = pages_content?;
+                // Encoding the pages is the last part
+                let stage = progress.stage(0.0, 0.9, pages_content.len());
+                let page_images = pages_content
+                    .into_par_iter()
+                    .enumerate()
+                    .map(|(i, page_content)| {
+                        progress.check_cancelled()?;
+                        let page_svg = page_content
+                            .gen_svg(
+                                doc_export_prefs.with_background,
+                                doc_export_prefs.with_pattern,
+                                DocExportPrefs::MARGIN,
+                            )?
+                            .ok_or(anyhow::anyhow!(
+                                "Generating Svg for page {i} failed, returned None."
+                            ))?;
+                        let page_svg_bounds = page_svg.bounds;
+
+                        let page_image = render::Image::gen_image_from_svg(
+                            page_svg,
+                            page_svg_bounds,
+                            doc_export_prefs.bitmap_scalefactor,
+                        );
+                        stage.step();
+                        page_image
+                    })
+                    .collect::<anyhow::Result<Vec<render::Image>>>()?;
+                stage.finish()?;
+
+                let data = render::Image::encode_images_as_multipage_tiff(page_images)?;
+                progress.advance_to(1.0);
+                Ok(data)
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("Sending result to receiver in export_doc_as_tiff_bytes() failed. Receiver already dropped.");
+            }
+        });
+
+        oneshot_receiver
+    }
+
     /// Export the document as a Xournal++ .xopp file.
     fn export_doc_as_xopp_bytes(
         &self,
         title: String,
         doc_export_prefs_override: Option<DocExportPrefs>,
+        progress: Progress,
     ) -> oneshot::Receiver<Result<Vec<u8>, anyhow::Error>> {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
         let doc_export_prefs =
             doc_export_prefs_override.unwrap_or(self.export_prefs.doc_export_prefs);
-        let pages_content = self.extract_pages_content(doc_export_prefs.page_order);
+        let pages_content = prepare_pages_content(
+            self.extract_pages_content(doc_export_prefs.page_order),
+            doc_export_prefs.page_range,
+            doc_export_prefs.crop_to_content,
+            doc_export_prefs.crop_margin,
+            &self.export_page_overrides(doc_export_prefs.background_color, Some(title.as_str())),
+        );
         let document = self.document;
 
         rayon::spawn(move || {
             let result = || -> anyhow::Result<Vec<u8>> {
+                let pages_content = pages_content?;
                 // Only one background for all pages
                 let xopp_background = xoppformat::XoppBackground {
                     name: None,
@@ -543,9 +1096,12 @@ impl RnoteEngine {
 
                 // xopp spec needs at least one page in vec,
                 // but it is fine because pages_bounds_w_content() always produces at least one.
+                let stage = progress.stage(0.0, 0.9, pages_content.len());
                 let pages = pages_content
                     .into_iter()
+                    .take_while(|_| !progress.is_cancelled())
                     .filter_map(|page_content| {
+                        stage.step();
                         let page_bounds = page_content.bounds()?;
                         // Translate strokes to to page mins and convert to XoppStrokStyle
                         let xopp_strokestyles = page_content
@@ -624,6 +1180,7 @@ impl RnoteEngine {
                         })
                     })
                     .collect::<Vec<xoppformat::XoppPage>>();
+                stage.finish()?;
 
                 let xopp_title = String::from("Xournal++ document - see https://github.com/xournalpp/xournalpp (exported from Rnote - see https://github.com/flxzt/rnote)");
 
@@ -635,7 +1192,9 @@ impl RnoteEngine {
                 };
                 let xopp_file = xoppformat::XoppFile { xopp_root };
 
-                xopp_file.save_as_bytes(&title)
+                let data = xopp_file.save_as_bytes(&title)?;
+                progress.advance_to(1.0);
+                Ok(data)
             };
 
             if let Err(_data) = oneshot_sender.send(result()) {
@@ -658,7 +1217,7 @@ impl RnoteEngine {
             DocPagesExportFormat::Svg => {
                 self.export_doc_pages_as_svgs_bytes(doc_pages_export_prefs_override)
             }
-            DocPagesExportFormat::Png | DocPagesExportFormat::Jpeg => {
+            DocPagesExportFormat::Png | DocPagesExportFormat::Jpeg | DocPagesExportFormat::Tiff => {
                 self.export_doc_pages_as_bitmap_bytes(doc_pages_export_prefs_override)
             }
         }
@@ -672,15 +1231,23 @@ impl RnoteEngine {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<Vec<u8>>>>();
         let doc_pages_export_prefs =
             doc_pages_export_prefs_override.unwrap_or(self.export_prefs.doc_pages_export_prefs);
-        let pages_content = self.extract_pages_content(doc_pages_export_prefs.page_order);
+        let pages_content = prepare_pages_content(
+            self.extract_pages_content(doc_pages_export_prefs.page_order),
+            doc_pages_export_prefs.page_range,
+            doc_pages_export_prefs.crop_to_content,
+            doc_pages_export_prefs.crop_margin,
+            &self.export_page_overrides(doc_pages_export_prefs.background_color, None),
+        );
 
         rayon::spawn(move || {
             let result = || -> anyhow::Result<Vec<Vec<u8>>> {
+                let pages_content = pages_content?;
                 pages_content
                     .into_par_iter()
                     .enumerate()
                     .map(|(i, page_content)| {
                         let page_svg = page_content
+                            .with_text_as_paths(doc_pages_export_prefs.text_as_paths)
                             .gen_svg(
                                 doc_pages_export_prefs.with_background,
                                 doc_pages_export_prefs.with_pattern,
@@ -721,16 +1288,25 @@ impl RnoteEngine {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<Vec<u8>>>>();
         let doc_pages_export_prefs =
             doc_pages_export_prefs_override.unwrap_or(self.export_prefs.doc_pages_export_prefs);
-        let pages_content = self.extract_pages_content(doc_pages_export_prefs.page_order);
+        let pages_content = prepare_pages_content(
+            self.extract_pages_content(doc_pages_export_prefs.page_order),
+            doc_pages_export_prefs.page_range,
+            doc_pages_export_prefs.crop_to_content,
+            doc_pages_export_prefs.crop_margin,
+            &self.export_page_overrides(doc_pages_export_prefs.background_color, None),
+        );
+        let document_dpi = self.document.format.dpi;
 
         rayon::spawn(move || {
             let result = || -> Result<Vec<Vec<u8>>, anyhow::Error> {
+                let pages_content = pages_content?;
                 let bitmapimage_format = match doc_pages_export_prefs.export_format {
                     DocPagesExportFormat::Svg => return Err(anyhow::anyhow!("Extracting bitmap image format from doc pages export prefs failed, not set to a bitmap format.")),
                     DocPagesExportFormat::Png => image::ImageOutputFormat::Png,
                     DocPagesExportFormat::Jpeg => {
                         image::ImageOutputFormat::Jpeg(doc_pages_export_prefs.jpeg_quality)
                     }
+                    DocPagesExportFormat::Tiff => image::ImageOutputFormat::Tiff,
                 };
                 pages_content
                     .into_par_iter()
@@ -747,12 +1323,17 @@ impl RnoteEngine {
                             ))?;
                         let page_svg_bounds = page_svg.bounds;
 
-                        render::Image::gen_image_from_svg(
+                        render::Image::gen_image_from_svg_supersampled(
                             page_svg,
                             page_svg_bounds,
                             doc_pages_export_prefs.bitmap_scalefactor,
+                            doc_pages_export_prefs.bitmap_supersampling,
+                            doc_pages_export_prefs.bitmap_downscale_filter,
                         )?
-                        .into_encoded_bytes(bitmapimage_format.clone())
+                        .into_encoded_bytes_with_dpi(
+                            bitmapimage_format.clone(),
+                            document_dpi * doc_pages_export_prefs.bitmap_scalefactor,
+                        )
                     })
                     .collect()
             };
@@ -764,6 +1345,47 @@ impl RnoteEngine {
         oneshot_receiver
     }
 
+    /// Export the selection as a tileable fill pattern, with the bounds of the selection as tile.
+    ///
+    /// The pattern then needs to be added to the document with [RnoteEngine::add_doc_fill_pattern].
+    pub fn export_selection_as_fill_pattern(
+        &self,
+        name: String,
+    ) -> oneshot::Receiver<anyhow::Result<Option<FillPattern>>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<Option<FillPattern>>>();
+        let selection_content = self.extract_selection_content();
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Option<FillPattern>> {
+                let Some(selection_content) = selection_content else {
+                    return Ok(None);
+                };
+                let Some(tile_svg) = selection_content.gen_svg(false, false, 0.0)? else {
+                    return Ok(None);
+                };
+                let tile_bounds = tile_svg.bounds;
+                let tile_size = tile_bounds.extents();
+                if tile_size.min() < FillPattern::TILE_SIZE_MIN {
+                    return Err(anyhow::anyhow!(
+                        "selection is too small to be used as fill pattern"
+                    ));
+                }
+                let image = render::Image::gen_image_from_svg(
+                    tile_svg,
+                    tile_bounds,
+                    FillPattern::IMAGE_SCALE,
+                )?;
+                Ok(Some(FillPattern::new(name, tile_size, image)))
+            };
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("Sending result to receiver failed. Receiver already dropped.");
+            }
+        });
+
+        oneshot_receiver
+    }
+
     /// Exports the current selection.
     pub fn export_selection(
         &self,
@@ -779,6 +1401,9 @@ impl RnoteEngine {
             SelectionExportFormat::Png | SelectionExportFormat::Jpeg => {
                 self.export_selection_as_bitmap_bytes(selection_export_prefs_override)
             }
+            SelectionExportFormat::Pdf => {
+                self.export_selection_as_pdf_bytes(selection_export_prefs_override)
+            }
         }
     }
 
@@ -791,7 +1416,9 @@ impl RnoteEngine {
             oneshot::channel::<anyhow::Result<Option<Vec<u8>>>>();
         let selection_export_prefs =
             selection_export_prefs_override.unwrap_or(self.export_prefs.selection_export_prefs);
-        let selection_content = self.extract_selection_content();
+        let selection_content = self
+            .extract_selection_content()
+            .map(|content| selection_export_prefs.background_color.apply(content));
 
         rayon::spawn(move || {
             let result = || -> Result<Option<Vec<u8>>, anyhow::Error> {
@@ -834,7 +1461,10 @@ impl RnoteEngine {
             oneshot::channel::<anyhow::Result<Option<Vec<u8>>>>();
         let selection_export_prefs =
             selection_export_prefs_override.unwrap_or(self.export_prefs.selection_export_prefs);
-        let selection_content = self.extract_selection_content();
+        let selection_content = self
+            .extract_selection_content()
+            .map(|content| selection_export_prefs.background_color.apply(content));
+        let document_dpi = self.document.format.dpi;
 
         rayon::spawn(move || {
             let result = || -> Result<Option<Vec<u8>>, anyhow::Error> {
@@ -846,7 +1476,7 @@ impl RnoteEngine {
                 };
                 let selection_svg_bounds = selection_svg.bounds;
                 let bitmapimage_format = match selection_export_prefs.export_format {
-                    SelectionExportFormat::Svg => return Err(anyhow::anyhow!("Extracting bitmap image format from doc pages export prefs failed, not set to a bitmap format.")),
+                    SelectionExportFormat::Svg | SelectionExportFormat::Pdf => return Err(anyhow::anyhow!("Extracting bitmap image format from doc pages export prefs failed, not set to a bitmap format.")),
                     SelectionExportFormat::Png => image::ImageOutputFormat::Png,
                     SelectionExportFormat::Jpeg => {
                         image::ImageOutputFormat::Jpeg(selection_export_prefs.jpeg_quality)
@@ -854,12 +1484,17 @@ impl RnoteEngine {
                 };
 
                 Ok(Some(
-                    render::Image::gen_image_from_svg(
+                    render::Image::gen_image_from_svg_supersampled(
                         selection_svg,
                         selection_svg_bounds,
                         selection_export_prefs.bitmap_scalefactor,
+                        selection_export_prefs.bitmap_supersampling,
+                        selection_export_prefs.bitmap_downscale_filter,
                     )?
-                    .into_encoded_bytes(bitmapimage_format)?,
+                    .into_encoded_bytes_with_dpi(
+                        bitmapimage_format,
+                        selection_export_prefs.bitmap_dpi(document_dpi),
+                    )?,
                 ))
             };
             if let Err(_data) = oneshot_sender.send(result()) {
@@ -869,4 +1504,174 @@ impl RnoteEngine {
 
         oneshot_receiver
     }
+
+    /// Export the selection as a single page Pdf.
+    fn export_selection_as_pdf_bytes(
+        &self,
+        selection_export_prefs_override: Option<SelectionExportPrefs>,
+    ) -> oneshot::Receiver<Result<Option<Vec<u8>>, anyhow::Error>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<Option<Vec<u8>>>>();
+        let selection_export_prefs =
+            selection_export_prefs_override.unwrap_or(self.export_prefs.selection_export_prefs);
+        let selection_content = self
+            .extract_selection_content()
+            .map(|content| selection_export_prefs.background_color.apply(content));
+        let scale_to_pt = self.document.format.scale_to_pt();
+        let deterministic = self.document.deterministic_exports;
+
+        rayon::spawn(move || {
+            let result = || -> Result<Option<Vec<u8>>, anyhow::Error> {
+                let Some(selection_content) = selection_content else {
+                    return Ok(None);
+                };
+                let Some(selection_bounds) = selection_content.bounds() else {
+                    return Ok(None);
+                };
+                let page_bounds = selection_bounds.loosened(selection_export_prefs.margin);
+                let target_surface = cairo::PdfSurface::for_stream(
+                    page_bounds.extents()[0] * scale_to_pt,
+                    page_bounds.extents()[1] * scale_to_pt,
+                    Vec::<u8>::new(),
+                )
+                .context("Creating Pdf target surface failed.")?;
+                target_surface
+                    .set_metadata(
+                        cairo::PdfMetadata::CreateDate,
+                        pdf_create_date(deterministic).as_str(),
+                    )
+                    .context("Set pdf surface date metadata failed.")?;
+
+                // New scope to avoid errors when flushing
+                {
+                    let cairo_cx = cairo::Context::new(&target_surface)
+                        .context("Creating new cairo context for pdf target surface failed.")?;
+                    cairo_cx.scale(scale_to_pt, scale_to_pt);
+                    cairo_cx.translate(-page_bounds.mins[0], -page_bounds.mins[1]);
+                    selection_content.draw_to_cairo(
+                        &cairo_cx,
+                        selection_export_prefs.with_background,
+                        selection_export_prefs.with_pattern,
+                        selection_export_prefs.margin,
+                        RnoteEngine::STROKE_EXPORT_IMAGE_SCALE,
+                    )?;
+                    selection_content.draw_text_layers_to_cairo(&cairo_cx)?;
+                    cairo_cx.show_page().map_err(|e| {
+                        anyhow::anyhow!(
+                            "Showing page failed when exporting selection as pdf, Err: {e:?}"
+                        )
+                    })?;
+                }
+                let data = *target_surface
+                    .finish_output_stream()
+                    .map_err(|e| anyhow::anyhow!("Finishing outputstream failed with Err: {e:?}"))?
+                    .downcast::<Vec<u8>>()
+                    .map_err(|e| {
+                        anyhow::anyhow!("Downcasting finished output stream failed with Err: {e:?}")
+                    })?;
+
+                Ok(Some(data))
+            };
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("Sending result to receiver failed. Receiver already dropped.");
+            }
+        });
+
+        oneshot_receiver
+    }
+}
+
+/// The replacements and overlays that are applied to the exported pages.
+struct ExportPageOverrides<'a> {
+    background_color: ExportBackgroundColor,
+    watermark: Option<&'a Watermark>,
+    header_footer: Option<&'a HeaderFooter>,
+    /// The title the header and footer placeholders are replaced with.
+    title: String,
+    /// The date the header and footer placeholders are replaced with.
+    date: String,
+}
+
+impl ExportPageOverrides<'_> {
+    /// Apply to the content of the exported page with the index.
+    fn apply(&self, page: usize, n_pages: usize, content: StrokeContent) -> StrokeContent {
+        let header_footer = self.header_footer.map(|header_footer| {
+            header_footer.expanded(&HeaderFooterFields {
+                page: page + 1,
+                n_pages,
+                title: self.title.clone(),
+                date: self.date.clone(),
+            })
+        });
+        self.background_color
+            .apply(content)
+            .with_watermark(
+                self.watermark
+                    .filter(|watermark| watermark.is_on_page(page))
+                    .cloned(),
+            )
+            .with_header_footer(header_footer)
+    }
+}
+
+/// Select the pages content in the page range and crop it to the bounds of the strokes, extended by the margin, if enabled.
+///
+/// The overrides are applied to the selected pages, which are numbered from the first selected page.
+///
+/// Returns an error if the page range does not contain any pages.
+fn prepare_pages_content(
+    pages_content: Vec<StrokeContent>,
+    page_range: Option<PageRange>,
+    crop_to_content: bool,
+    margin: f64,
+    overrides: &ExportPageOverrides,
+) -> anyhow::Result<Vec<StrokeContent>> {
+    let n_pages = pages_content.len();
+    let pages_content = pages_content
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| page_range.map_or(true, |range| range.contains(*i)))
+        .map(|(_, page_content)| page_content)
+        .collect::<Vec<StrokeContent>>();
+    let n_selected = pages_content.len();
+    let pages_content = pages_content
+        .into_iter()
+        .enumerate()
+        .map(|(i, page_content)| {
+            let page_content = overrides.apply(i, n_selected, page_content);
+            if crop_to_content {
+                page_content.cropped_to_strokes(margin)
+            } else {
+                page_content
+            }
+        })
+        .collect::<Vec<StrokeContent>>();
+    if pages_content.is_empty() {
+        return Err(anyhow::anyhow!(
+            "The export page range {page_range:?} does not contain any of the {n_pages} pages."
+        ));
+    }
+    Ok(pages_content)
+}
+
+/// The date the header and footer placeholders are replaced with.
+///
+/// Deterministic exports use a fixed date, like [pdf_create_date].
+fn export_date(deterministic: bool) -> String {
+    if deterministic {
+        String::from("1970-01-01")
+    } else {
+        chrono::Local::now().format("%Y-%m-%d").to_string()
+    }
+}
+
+/// The creation date written into exported Pdf's.
+///
+/// Deterministic exports use a fixed date, so that exporting the same document produces identical files.
+fn pdf_create_date(deterministic: bool) -> String {
+    if deterministic {
+        String::from("1970-01-01_00:00:00")
+    } else {
+        crate::utils::now_formatted_string()
+    }
 }
@@ -0,0 +1,304 @@
+// Imports
+use super::{NavigationDirection, RnoteEngine};
+use crate::WidgetFlags;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// A recognized multi-finger touch gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    /// A short tap with two fingers.
+    TwoFingerTap,
+    /// A short tap with three fingers.
+    ThreeFingerTap,
+    /// A swipe with three fingers in the given direction.
+    ThreeFingerSwipe(NavigationDirection),
+}
+
+/// The action that is dispatched when a gesture is recognized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "gesture_action")]
+pub enum GestureAction {
+    #[serde(rename = "none")]
+    None,
+    #[serde(rename = "undo")]
+    Undo,
+    #[serde(rename = "redo")]
+    Redo,
+}
+
+/// Maps the gestures to their actions.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "gesture_config")]
+pub struct GestureConfig {
+    /// Whether gestures are recognized at all.
+    #[serde(rename = "enabled")]
+    pub enabled: bool,
+    #[serde(rename = "two_finger_tap")]
+    pub two_finger_tap: GestureAction,
+    #[serde(rename = "three_finger_tap")]
+    pub three_finger_tap: GestureAction,
+    #[serde(rename = "three_finger_swipe_left")]
+    pub three_finger_swipe_left: GestureAction,
+    #[serde(rename = "three_finger_swipe_right")]
+    pub three_finger_swipe_right: GestureAction,
+    #[serde(rename = "three_finger_swipe_up")]
+    pub three_finger_swipe_up: GestureAction,
+    #[serde(rename = "three_finger_swipe_down")]
+    pub three_finger_swipe_down: GestureAction,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            two_finger_tap: GestureAction::Undo,
+            three_finger_tap: GestureAction::Redo,
+            three_finger_swipe_left: GestureAction::None,
+            three_finger_swipe_right: GestureAction::None,
+            three_finger_swipe_up: GestureAction::None,
+            three_finger_swipe_down: GestureAction::None,
+        }
+    }
+}
+
+impl GestureConfig {
+    /// The action mapped to the gesture.
+    pub fn action(&self, gesture: Gesture) -> GestureAction {
+        match gesture {
+            Gesture::TwoFingerTap => self.two_finger_tap,
+            Gesture::ThreeFingerTap => self.three_finger_tap,
+            Gesture::ThreeFingerSwipe(NavigationDirection::Left) => self.three_finger_swipe_left,
+            Gesture::ThreeFingerSwipe(NavigationDirection::Right) => self.three_finger_swipe_right,
+            Gesture::ThreeFingerSwipe(NavigationDirection::Up) => self.three_finger_swipe_up,
+            Gesture::ThreeFingerSwipe(NavigationDirection::Down) => self.three_finger_swipe_down,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TouchPoint {
+    start: na::Vector2<f64>,
+    current: na::Vector2<f64>,
+}
+
+/// Interprets raw touch sequences as multi-finger gestures.
+///
+/// Positions are expected in surface coordinates, so that the thresholds are independent of the zoom.
+#[derive(Debug, Clone, Default)]
+pub struct GestureRecognizer {
+    active: HashMap<u64, TouchPoint>,
+    ended: Vec<TouchPoint>,
+    max_touches: usize,
+    start: Option<Instant>,
+    cancelled: bool,
+}
+
+impl GestureRecognizer {
+    /// The maximum duration of a tap.
+    pub const TAP_TIMEOUT: Duration = Duration::from_millis(250);
+    /// The maximum distance a finger may move during a tap.
+    pub const TAP_SLOP: f64 = 12.0;
+    /// The maximum duration of a swipe.
+    pub const SWIPE_TIMEOUT: Duration = Duration::from_millis(600);
+    /// The minimum mean distance the fingers need to travel for a swipe.
+    pub const SWIPE_MIN_DISTANCE: f64 = 80.0;
+
+    /// A touch point with the given id began.
+    pub fn touch_begin(&mut self, id: u64, pos: na::Vector2<f64>, now: Instant) {
+        if self.active.is_empty() {
+            self.reset();
+            self.start = Some(now);
+        }
+        self.active.insert(
+            id,
+            TouchPoint {
+                start: pos,
+                current: pos,
+            },
+        );
+        self.max_touches = self.max_touches.max(self.active.len() + self.ended.len());
+    }
+
+    /// A touch point with the given id moved.
+    pub fn touch_update(&mut self, id: u64, pos: na::Vector2<f64>) {
+        if let Some(touch) = self.active.get_mut(&id) {
+            touch.current = pos;
+        }
+    }
+
+    /// A touch point with the given id ended.
+    ///
+    /// Returns the recognized gesture once the last touch point of the sequence has ended.
+    pub fn touch_end(&mut self, id: u64, pos: na::Vector2<f64>, now: Instant) -> Option<Gesture> {
+        let mut touch = self.active.remove(&id)?;
+        touch.current = pos;
+        self.ended.push(touch);
+        if !self.active.is_empty() {
+            return None;
+        }
+        let gesture = self.recognize(now);
+        self.reset();
+        gesture
+    }
+
+    /// A touch point with the given id was cancelled, which discards the entire sequence.
+    pub fn touch_cancel(&mut self, id: u64) {
+        self.active.remove(&id);
+        self.cancelled = true;
+        if self.active.is_empty() {
+            self.reset();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.active.clear();
+        self.ended.clear();
+        self.max_touches = 0;
+        self.start = None;
+        self.cancelled = false;
+    }
+
+    fn recognize(&self, now: Instant) -> Option<Gesture> {
+        if self.cancelled || self.ended.is_empty() {
+            return None;
+        }
+        let duration = now.duration_since(self.start?);
+        let max_movement = self
+            .ended
+            .iter()
+            .map(|t| (t.current - t.start).magnitude())
+            .fold(0.0, f64::max);
+
+        match self.max_touches {
+            2 if duration <= Self::TAP_TIMEOUT && max_movement <= Self::TAP_SLOP => {
+                Some(Gesture::TwoFingerTap)
+            }
+            3 if duration <= Self::TAP_TIMEOUT && max_movement <= Self::TAP_SLOP => {
+                Some(Gesture::ThreeFingerTap)
+            }
+            3 if duration <= Self::SWIPE_TIMEOUT => {
+                let mean_offset = self
+                    .ended
+                    .iter()
+                    .map(|t| t.current - t.start)
+                    .sum::<na::Vector2<f64>>()
+                    / self.ended.len() as f64;
+                if mean_offset.magnitude() < Self::SWIPE_MIN_DISTANCE {
+                    return None;
+                }
+                let direction = if mean_offset[0].abs() > mean_offset[1].abs() {
+                    if mean_offset[0] < 0.0 {
+                        NavigationDirection::Left
+                    } else {
+                        NavigationDirection::Right
+                    }
+                } else if mean_offset[1] < 0.0 {
+                    NavigationDirection::Up
+                } else {
+                    NavigationDirection::Down
+                };
+                Some(Gesture::ThreeFingerSwipe(direction))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A phase of a touch point, as reported by the UI toolkit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchPhase {
+    Begin,
+    Update,
+    End,
+    Cancel,
+}
+
+impl RnoteEngine {
+    /// Feed a touch event into the gesture recognizer and dispatch the mapped action of a recognized gesture.
+    ///
+    /// `id` identifies the touch point within a sequence, `pos` is in surface coordinates.
+    pub fn handle_touch_event(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        pos: na::Vector2<f64>,
+        now: Instant,
+    ) -> WidgetFlags {
+        if !self.gesture_config.enabled {
+            return WidgetFlags::default();
+        }
+        let gesture = match phase {
+            TouchPhase::Begin => {
+                self.gesture_recognizer.touch_begin(id, pos, now);
+                None
+            }
+            TouchPhase::Update => {
+                self.gesture_recognizer.touch_update(id, pos);
+                None
+            }
+            TouchPhase::End => self.gesture_recognizer.touch_end(id, pos, now),
+            TouchPhase::Cancel => {
+                self.gesture_recognizer.touch_cancel(id);
+                None
+            }
+        };
+        match gesture {
+            Some(gesture) => self.dispatch_gesture(gesture, now),
+            None => WidgetFlags::default(),
+        }
+    }
+
+    /// Dispatch the action that is mapped to the gesture.
+    pub fn dispatch_gesture(&mut self, gesture: Gesture, now: Instant) -> WidgetFlags {
+        match self.gesture_config.action(gesture) {
+            GestureAction::None => WidgetFlags::default(),
+            GestureAction::Undo => self.undo(now),
+            GestureAction::Redo => self.redo(now),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognize_taps_and_swipes() {
+        let now = Instant::now();
+        let mut recognizer = GestureRecognizer::default();
+
+        recognizer.touch_begin(0, na::vector![10.0, 10.0], now);
+        recognizer.touch_begin(1, na::vector![50.0, 10.0], now);
+        assert_eq!(recognizer.touch_end(0, na::vector![11.0, 10.0], now), None);
+        assert_eq!(
+            recognizer.touch_end(1, na::vector![50.0, 12.0], now),
+            Some(Gesture::TwoFingerTap)
+        );
+
+        for id in 0..3 {
+            recognizer.touch_begin(id, na::vector![id as f64 * 40.0, 100.0], now);
+        }
+        let later = now + Duration::from_millis(200);
+        assert_eq!(
+            recognizer.touch_end(0, na::vector![-100.0, 100.0], later),
+            None
+        );
+        assert_eq!(
+            recognizer.touch_end(1, na::vector![-60.0, 100.0], later),
+            None
+        );
+        assert_eq!(
+            recognizer.touch_end(2, na::vector![-20.0, 100.0], later),
+            Some(Gesture::ThreeFingerSwipe(NavigationDirection::Left))
+        );
+
+        // A slow two finger press is not a tap
+        recognizer.touch_begin(0, na::vector![0.0, 0.0], now);
+        recognizer.touch_begin(1, na::vector![10.0, 0.0], now);
+        let later = now + Duration::from_millis(500);
+        assert_eq!(recognizer.touch_end(0, na::vector![0.0, 0.0], later), None);
+        assert_eq!(recognizer.touch_end(1, na::vector![10.0, 0.0], later), None);
+    }
+}
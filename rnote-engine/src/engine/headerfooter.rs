@@ -0,0 +1,127 @@
+// Imports
+use crate::render;
+use crate::strokes::textoutlines;
+use crate::strokes::textstroke::{TextAlignment, TextStyle};
+use p2d::bounding_volume::Aabb;
+use rnote_compose::Color;
+use serde::{Deserialize, Serialize};
+
+/// The values the placeholders of the header and footer templates are replaced with.
+#[derive(Debug, Clone, Default)]
+pub struct HeaderFooterFields {
+    /// The number of the exported page, starting at 1.
+    pub page: usize,
+    /// The number of exported pages.
+    pub n_pages: usize,
+    pub title: String,
+    pub date: String,
+}
+
+/// A header and footer that are placed into the margins of the exported pages.
+///
+/// The templates can contain the placeholders `{page}`, `{pages}`, `{title}` and `{date}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "header_footer")]
+pub struct HeaderFooter {
+    /// The template of the header. Nothing is placed when empty.
+    #[serde(rename = "header")]
+    pub header: String,
+    #[serde(rename = "header_alignment")]
+    pub header_alignment: TextAlignment,
+    /// The template of the footer. Nothing is placed when empty.
+    #[serde(rename = "footer")]
+    pub footer: String,
+    #[serde(rename = "footer_alignment")]
+    pub footer_alignment: TextAlignment,
+    #[serde(rename = "font_size")]
+    pub font_size: f64,
+    #[serde(rename = "color")]
+    pub color: Color,
+    /// The distance to the edges of the page.
+    #[serde(rename = "margin")]
+    pub margin: f64,
+}
+
+impl Default for HeaderFooter {
+    fn default() -> Self {
+        Self {
+            header: String::new(),
+            header_alignment: TextAlignment::Center,
+            footer: String::from("{page} / {pages}"),
+            footer_alignment: TextAlignment::Center,
+            font_size: 14.0,
+            color: Color::BLACK,
+            margin: 32.0,
+        }
+    }
+}
+
+impl HeaderFooter {
+    /// Replace the placeholders in the template with the fields.
+    pub fn expand_template(template: &str, fields: &HeaderFooterFields) -> String {
+        template
+            .replace("{page}", &fields.page.to_string())
+            .replace("{pages}", &fields.n_pages.to_string())
+            .replace("{title}", &fields.title)
+            .replace("{date}", &fields.date)
+    }
+
+    /// The header and footer with the placeholders replaced with the fields.
+    pub fn expanded(&self, fields: &HeaderFooterFields) -> Self {
+        Self {
+            header: Self::expand_template(&self.header, fields),
+            footer: Self::expand_template(&self.footer, fields),
+            ..self.clone()
+        }
+    }
+
+    /// Generate the Svg of the header and footer placed on the page with the bounds.
+    ///
+    /// Expects the placeholders to be already replaced, see [HeaderFooter::expanded].
+    pub fn gen_svg(&self, page_bounds: Aabb) -> anyhow::Result<render::Svg> {
+        let width = (page_bounds.extents()[0] - 2.0 * self.margin).max(0.0);
+        let mut svg = render::Svg {
+            svg_data: String::new(),
+            bounds: page_bounds,
+        };
+
+        for (template, alignment, is_header) in [
+            (&self.header, self.header_alignment, true),
+            (&self.footer, self.footer_alignment, false),
+        ] {
+            if template.trim().is_empty() {
+                continue;
+            }
+            let text_style = TextStyle {
+                font_size: self.font_size,
+                color: self.color,
+                max_width: Some(width),
+                alignment,
+                ..TextStyle::default()
+            };
+            let outlines = textoutlines::text_outlines(template, &text_style)?;
+            let Some(outlines_bounds) = textoutlines::outlines_bounds(&outlines) else {
+                continue;
+            };
+            let offset = if is_header {
+                na::vector![
+                    page_bounds.mins[0] + self.margin,
+                    page_bounds.mins[1] + self.margin - outlines_bounds.mins[1]
+                ]
+            } else {
+                na::vector![
+                    page_bounds.mins[0] + self.margin,
+                    page_bounds.maxs[1] - self.margin - outlines_bounds.maxs[1]
+                ]
+            };
+            let group = textoutlines::outlines_svg_group(outlines).set(
+                "transform",
+                format!("translate({:.3} {:.3})", offset[0], offset[1]),
+            );
+            svg.svg_data += rnote_compose::utils::svg_node_to_string(&group)?.as_str();
+        }
+        // Wrapping clips it to the page
+        svg.wrap_svg_root(Some(page_bounds), Some(page_bounds), false);
+        Ok(svg)
+    }
+}
@@ -0,0 +1,175 @@
+// Imports
+use super::RnoteEngine;
+use crate::utils::{GdkRGBAHelpers, GrapheneRectHelpers};
+use gtk4::{gdk, graphene, Snapshot};
+use p2d::bounding_volume::Aabb;
+use rnote_compose::helpers::{AabbHelpers, SplitOrder};
+use rnote_compose::Color;
+
+/// What the heatmap overlay visualizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeatmapMode {
+    /// The overlay is not shown.
+    Off,
+    /// The number of strokes in a region.
+    Density,
+    /// How recently the strokes in a region were created.
+    Recency,
+}
+
+impl Default for HeatmapMode {
+    fn default() -> Self {
+        Self::Off
+    }
+}
+
+impl std::str::FromStr for HeatmapMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "density" => Ok(Self::Density),
+            "recency" => Ok(Self::Recency),
+            s => Err(anyhow::anyhow!(
+                "HeatmapMode from_string failed, invalid name: {s}"
+            )),
+        }
+    }
+}
+
+impl std::string::ToString for HeatmapMode {
+    fn to_string(&self) -> String {
+        match self {
+            Self::Off => String::from("off"),
+            Self::Density => String::from("density"),
+            Self::Recency => String::from("recency"),
+        }
+    }
+}
+
+impl RnoteEngine {
+    /// The size of the heatmap cells in surface coordinates.
+    pub const HEATMAP_CELL_SIZE: f64 = 48.0;
+    /// The alpha of the cells with the highest value.
+    const HEATMAP_MAX_ALPHA: f64 = 0.5;
+    const HEATMAP_COLOR_DENSITY: Color = Color {
+        r: 0.9,
+        g: 0.3,
+        b: 0.0,
+        a: 1.0,
+    };
+    const HEATMAP_COLOR_OLD: Color = Color {
+        r: 0.1,
+        g: 0.4,
+        b: 0.9,
+        a: 1.0,
+    };
+    const HEATMAP_COLOR_RECENT: Color = Color {
+        r: 0.9,
+        g: 0.1,
+        b: 0.1,
+        a: 1.0,
+    };
+
+    /// The heatmap cells intersecting the viewport that contain strokes, with their value in the range [0.0, 1.0].
+    ///
+    /// For [HeatmapMode::Density] the value is the stroke count relative to the cell with the most strokes in the viewport,
+    /// for [HeatmapMode::Recency] it is the creation time of the newest stroke in the cell
+    /// relative to the creation time range of all strokes.
+    /// The cells are aligned to the origin, so they don't move when the viewport is scrolled.
+    pub fn heatmap_cells(&self, mode: HeatmapMode) -> Vec<(Aabb, f64)> {
+        if mode == HeatmapMode::Off {
+            return vec![];
+        }
+        let cell_size = Self::HEATMAP_CELL_SIZE / self.camera.total_zoom();
+        let cells = self
+            .camera
+            .viewport()
+            .split_extended_origin_aligned(na::vector![cell_size, cell_size], SplitOrder::default())
+            .into_iter()
+            .filter_map(|cell| {
+                let keys = self
+                    .store
+                    .stroke_keys_as_rendered_intersecting_bounds(cell)
+                    .into_iter()
+                    .filter(|&key| self.store.visible_at_playback_time(key))
+                    .collect::<Vec<_>>();
+                if keys.is_empty() {
+                    return None;
+                }
+                let value = match mode {
+                    HeatmapMode::Off => unreachable!(),
+                    HeatmapMode::Density => keys.len() as f64,
+                    // Strokes without timestamps count as the oldest
+                    HeatmapMode::Recency => keys
+                        .iter()
+                        .map(|&key| self.store.created(key).unwrap_or(0))
+                        .max()
+                        .unwrap_or(0) as f64,
+                };
+                Some((cell, value))
+            })
+            .collect::<Vec<(Aabb, f64)>>();
+
+        let (min, max) = match mode {
+            HeatmapMode::Off => unreachable!(),
+            HeatmapMode::Density => (0.0, cells.iter().map(|(_, v)| *v).fold(0.0, f64::max)),
+            HeatmapMode::Recency => match self.playback_time_range() {
+                Some((oldest, newest)) => (
+                    oldest as f64,
+                    self.store.playback_time.unwrap_or(newest).min(newest) as f64,
+                ),
+                None => (0.0, 0.0),
+            },
+        };
+        let range = max - min;
+
+        cells
+            .into_iter()
+            .map(|(cell, value)| {
+                let value = if range > 0.0 {
+                    ((value - min) / range).clamp(0.0, 1.0)
+                } else {
+                    1.0
+                };
+                (cell, value)
+            })
+            .collect()
+    }
+
+    pub(super) fn draw_heatmap_to_gtk_snapshot(&self, snapshot: &Snapshot) {
+        if self.heatmap_mode == HeatmapMode::Off {
+            return;
+        }
+        snapshot.push_clip(&graphene::Rect::from_p2d_aabb(self.document.bounds()));
+
+        for (cell, value) in self.heatmap_cells(self.heatmap_mode) {
+            let (color, alpha) = match self.heatmap_mode {
+                HeatmapMode::Off => unreachable!(),
+                HeatmapMode::Density => (Self::HEATMAP_COLOR_DENSITY, value),
+                HeatmapMode::Recency => (
+                    Color::new(
+                        Self::HEATMAP_COLOR_OLD.r
+                            + (Self::HEATMAP_COLOR_RECENT.r - Self::HEATMAP_COLOR_OLD.r) * value,
+                        Self::HEATMAP_COLOR_OLD.g
+                            + (Self::HEATMAP_COLOR_RECENT.g - Self::HEATMAP_COLOR_OLD.g) * value,
+                        Self::HEATMAP_COLOR_OLD.b
+                            + (Self::HEATMAP_COLOR_RECENT.b - Self::HEATMAP_COLOR_OLD.b) * value,
+                        1.0,
+                    ),
+                    0.6,
+                ),
+            };
+            snapshot.append_color(
+                &gdk::RGBA::from_compose_color(Color {
+                    a: Self::HEATMAP_MAX_ALPHA * alpha,
+                    ..color
+                }),
+                &graphene::Rect::from_p2d_aabb(cell),
+            );
+        }
+
+        snapshot.pop();
+    }
+}
@@ -1,12 +1,16 @@
 // Imports
 use super::{EngineConfig, EngineViewMut, StrokeContent};
+use crate::document::Format;
 use crate::pens::Pen;
 use crate::pens::PenStyle;
 use crate::store::chrono_comp::StrokeLayer;
 use crate::store::StrokeKey;
-use crate::strokes::{BitmapImage, Stroke, VectorImage};
-use crate::{RnoteEngine, WidgetFlags};
+use crate::strokes::{svgconvert, BitmapImage, Stroke, TextStroke, VectorImage};
+use crate::{EngineError, Progress, RnoteEngine, WidgetFlags};
 use futures::channel::oneshot;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rnote_compose::shapes::ShapeBehaviour;
+use rnote_compose::transform::TransformBehaviour;
 use serde::{Deserialize, Serialize};
 use std::ops::Range;
 use std::path::PathBuf;
@@ -72,6 +76,10 @@ impl TryFrom<u32> for PdfImportPageSpacing {
     }
 }
 
+/// The share of the Pdf import progress that is spent rendering the pages with poppler,
+/// the rest is spent converting the rendered pages into strokes.
+pub(crate) const PDF_IMPORT_RENDER_PROGRESS: f64 = 0.7;
+
 /// Pdf import preferences.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(default, rename = "pdf_import_prefs")]
@@ -88,6 +96,12 @@ pub struct PdfImportPrefs {
     /// The scalefactor when importing as bitmap image
     #[serde(rename = "bitmap_scalefactor")]
     pub bitmap_scalefactor: f64,
+    /// Whether handwriting ink is converted to editable brush strokes when importing as vector image.
+    #[serde(rename = "ink_to_brushstrokes")]
+    pub ink_to_brushstrokes: bool,
+    /// Whether a border is drawn around the imported pages.
+    #[serde(rename = "page_borders")]
+    pub page_borders: bool,
 }
 
 impl Default for PdfImportPrefs {
@@ -97,6 +111,148 @@ impl Default for PdfImportPrefs {
             page_width_perc: 50.0,
             page_spacing: PdfImportPageSpacing::default(),
             bitmap_scalefactor: 1.8,
+            ink_to_brushstrokes: false,
+            page_borders: true,
+        }
+    }
+}
+
+/// Svg import preferences.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default, rename = "svg_import_prefs")]
+pub struct SvgImportPrefs {
+    /// Whether primitive elements are converted into editable shape and text strokes.
+    ///
+    /// When false, the Svg is imported as a single vector image.
+    #[serde(rename = "as_editable_shapes")]
+    pub as_editable_shapes: bool,
+}
+
+/// Bitmap image import preferences.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "bitmap_import_prefs")]
+pub struct BitmapImportPrefs {
+    /// Whether the DPI stored in the image metadata is respected when sizing the image.
+    #[serde(rename = "respect_dpi")]
+    pub respect_dpi: bool,
+    /// The maximum initial size in percentage to the format size.
+    #[serde(rename = "max_size_perc")]
+    pub max_size_perc: f64,
+    /// Whether the image is scaled to fit the format size.
+    #[serde(rename = "fit_to_page")]
+    pub fit_to_page: bool,
+    /// Whether the scan filter is applied, for photographed documents and whiteboards.
+    #[serde(rename = "scan_filter")]
+    pub scan_filter: bool,
+    /// Whether the image is converted to grayscale.
+    #[serde(rename = "grayscale")]
+    pub grayscale: bool,
+}
+
+impl Default for BitmapImportPrefs {
+    fn default() -> Self {
+        Self {
+            respect_dpi: true,
+            max_size_perc: 100.0,
+            fit_to_page: false,
+            scan_filter: false,
+            grayscale: false,
+        }
+    }
+}
+
+impl BitmapImportPrefs {
+    /// Calculates the size in document coordinates for an image with the given pixel size and DPI.
+    pub fn calc_image_size(
+        &self,
+        pixel_size: na::Vector2<f64>,
+        image_dpi: Option<f64>,
+        format: &Format,
+    ) -> na::Vector2<f64> {
+        let mut size = match image_dpi {
+            Some(image_dpi) if self.respect_dpi => pixel_size * (format.dpi / image_dpi),
+            _ => pixel_size,
+        };
+        if size[0] <= 0.0 || size[1] <= 0.0 {
+            return size;
+        }
+        let format_size = na::vector![format.width, format.height];
+
+        if self.fit_to_page {
+            size *= (format_size[0] / size[0]).min(format_size[1] / size[1]);
+        } else {
+            let max_size = format_size * (self.max_size_perc / 100.0);
+            let scale = (max_size[0] / size[0]).min(max_size[1] / size[1]);
+            if scale < 1.0 {
+                size *= scale;
+            }
+        }
+
+        size
+    }
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "multi_import_placement")]
+pub enum MultiImportPlacement {
+    /// Items are placed in rows and columns.
+    #[serde(rename = "grid")]
+    Grid = 0,
+    /// Items are placed below each other.
+    #[serde(rename = "vertical_flow")]
+    VerticalFlow,
+}
+
+impl Default for MultiImportPlacement {
+    fn default() -> Self {
+        Self::Grid
+    }
+}
+
+impl TryFrom<u32> for MultiImportPlacement {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value).ok_or_else(|| {
+            anyhow::anyhow!(
+                "MultiImportPlacement try_from::<u32>() for value {} failed",
+                value
+            )
+        })
+    }
+}
+
+/// Preferences for importing multiple items at once.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "multi_import_prefs")]
+pub struct MultiImportPrefs {
+    /// How the items are placed.
+    #[serde(rename = "placement")]
+    pub placement: MultiImportPlacement,
+    /// The spacing between the items.
+    #[serde(rename = "spacing")]
+    pub spacing: f64,
+    /// The number of grid columns. Calculated from the number of items when None.
+    #[serde(rename = "grid_columns")]
+    pub grid_columns: Option<usize>,
+}
+
+impl Default for MultiImportPrefs {
+    fn default() -> Self {
+        Self {
+            placement: MultiImportPlacement::default(),
+            spacing: 24.0,
+            grid_columns: None,
         }
     }
 }
@@ -116,6 +272,223 @@ impl Default for XoppImportPrefs {
     }
 }
 
+/// Where pasted content is placed.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "paste_mode")]
+pub enum PasteMode {
+    /// At the top left corner of the viewport.
+    #[serde(rename = "viewport")]
+    Viewport = 0,
+    /// At the original coordinates of the content.
+    #[serde(rename = "in_place")]
+    InPlace,
+    /// Centered at the pointer position.
+    #[serde(rename = "at_cursor")]
+    AtCursor,
+    /// At the original coordinates, offset further with every repeated paste of the same content.
+    #[serde(rename = "cascaded")]
+    Cascaded,
+}
+
+impl Default for PasteMode {
+    fn default() -> Self {
+        Self::Viewport
+    }
+}
+
+impl TryFrom<u32> for PasteMode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value)
+            .ok_or_else(|| anyhow::anyhow!("PasteMode try_from::<u32>() for value {value} failed"))
+    }
+}
+
+impl std::str::FromStr for PasteMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewport" => Ok(Self::Viewport),
+            "in-place" => Ok(Self::InPlace),
+            "at-cursor" => Ok(Self::AtCursor),
+            "cascaded" => Ok(Self::Cascaded),
+            s => Err(anyhow::anyhow!(
+                "PasteMode from_string failed, invalid name: {s}"
+            )),
+        }
+    }
+}
+
+impl std::string::ToString for PasteMode {
+    fn to_string(&self) -> String {
+        match self {
+            Self::Viewport => String::from("viewport"),
+            Self::InPlace => String::from("in-place"),
+            Self::AtCursor => String::from("at-cursor"),
+            Self::Cascaded => String::from("cascaded"),
+        }
+    }
+}
+
+/// How imported images and Pdf pages are fitted to the page they are imported into.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "page_fit_mode")]
+pub enum PageFitMode {
+    /// The content keeps its size and position.
+    #[serde(rename = "actual_size")]
+    ActualSize = 0,
+    /// The content is scaled to fit entirely inside the printable area of the page and centered on it.
+    #[serde(rename = "contain")]
+    Contain,
+    /// The content is scaled to cover the entire printable area of the page and centered on it.
+    #[serde(rename = "cover")]
+    Cover,
+}
+
+impl Default for PageFitMode {
+    fn default() -> Self {
+        Self::ActualSize
+    }
+}
+
+impl TryFrom<u32> for PageFitMode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value).ok_or_else(|| {
+            anyhow::anyhow!("PageFitMode try_from::<u32>() for value {value} failed")
+        })
+    }
+}
+
+impl std::str::FromStr for PageFitMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "actual-size" => Ok(Self::ActualSize),
+            "contain" => Ok(Self::Contain),
+            "cover" => Ok(Self::Cover),
+            s => Err(anyhow::anyhow!(
+                "PageFitMode from_string failed, invalid name: {s}"
+            )),
+        }
+    }
+}
+
+impl std::string::ToString for PageFitMode {
+    fn to_string(&self) -> String {
+        match self {
+            Self::ActualSize => String::from("actual-size"),
+            Self::Contain => String::from("contain"),
+            Self::Cover => String::from("cover"),
+        }
+    }
+}
+
+impl PageFitMode {
+    /// Fit the imported strokes to the pages they are placed on.
+    ///
+    /// Every stroke in the document layer (e.g. a Pdf page) starts a new group together with the strokes following it,
+    /// so that the ink of a Pdf page stays on it. A group is placed on the page containing its top left corner,
+    /// and only groups holding images are fitted, text and drawn strokes keep their size.
+    pub fn fit_strokes_to_pages(
+        &self,
+        strokes: &mut [(Stroke, Option<StrokeLayer>)],
+        format: &Format,
+    ) {
+        let format_size = na::vector![format.width, format.height];
+        if *self == Self::ActualSize || format_size[0] <= 0.0 || format_size[1] <= 0.0 {
+            return;
+        }
+        let mut group_starts = strokes
+            .iter()
+            .enumerate()
+            .filter(|(i, (_, layer))| *i == 0 || *layer == Some(StrokeLayer::Document))
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+        group_starts.push(strokes.len());
+
+        for group_range in group_starts.windows(2) {
+            let group = &mut strokes[group_range[0]..group_range[1]];
+            if !group.iter().any(|(stroke, _)| {
+                matches!(stroke, Stroke::BitmapImage(_) | Stroke::VectorImage(_))
+            }) {
+                continue;
+            }
+            let Some(bounds) = group
+                .iter()
+                .map(|(stroke, _)| stroke.bounds())
+                .reduce(|acc, b| acc.merged(&b))
+            else {
+                continue;
+            };
+            if bounds.extents()[0] <= 0.0 || bounds.extents()[1] <= 0.0 {
+                continue;
+            }
+            let page_mins = bounds
+                .mins
+                .coords
+                .component_div(&format_size)
+                .map(|v| v.floor())
+                .component_mul(&format_size);
+            let target = format.margins.printable_area(Aabb::new(
+                page_mins.into(),
+                (page_mins + format_size).into(),
+            ));
+            let ratios = target.extents().component_div(&bounds.extents());
+            let scale = match self {
+                Self::ActualSize => 1.0,
+                Self::Contain => ratios.min(),
+                Self::Cover => ratios.max(),
+            };
+            let offset = target.center().coords - bounds.center().coords * scale;
+
+            for (stroke, _) in group.iter_mut() {
+                stroke.scale(na::Vector2::repeat(scale));
+                stroke.translate(offset);
+            }
+        }
+    }
+}
+
+/// The placement of inserted stroke content.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PastePlacement {
+    /// With the top left corner at the position, in document coordinate space.
+    At(na::Vector2<f64>),
+    /// At the top left corner of the viewport.
+    Viewport,
+    /// At the original coordinates of the content.
+    InPlace,
+    /// Centered at the position, in document coordinate space.
+    CenteredAt(na::Vector2<f64>),
+    /// At the original coordinates, offset further with every repeated paste of the same content.
+    Cascaded,
+}
+
 /// Import preferences.
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 #[serde(default, rename = "import_prefs")]
@@ -123,9 +496,24 @@ pub struct ImportPrefs {
     /// Pdf import preferences
     #[serde(rename = "pdf_import_prefs")]
     pub pdf_import_prefs: PdfImportPrefs,
+    /// Svg import preferences
+    #[serde(rename = "svg_import_prefs")]
+    pub svg_import_prefs: SvgImportPrefs,
+    /// Bitmap image import preferences
+    #[serde(rename = "bitmap_import_prefs")]
+    pub bitmap_import_prefs: BitmapImportPrefs,
+    /// Multiple items import preferences
+    #[serde(rename = "multi_import_prefs")]
+    pub multi_import_prefs: MultiImportPrefs,
     /// Xournal++ `.xopp` file import preferences
     #[serde(rename = "xopp_import_prefs")]
     pub xopp_import_prefs: XoppImportPrefs,
+    /// Where pasted content is placed
+    #[serde(rename = "paste_mode")]
+    pub paste_mode: PasteMode,
+    /// How imported images and Pdf pages are fitted to the page
+    #[serde(rename = "page_fit_mode")]
+    pub page_fit_mode: PageFitMode,
 }
 
 impl RnoteEngine {
@@ -139,10 +527,22 @@ impl RnoteEngine {
 
         self.document = engine_config.document;
         self.pens_config = engine_config.pens_config;
+        // The registered pen plugins are not part of the config
+        let pen_plugins = std::mem::take(&mut self.penholder.pen_plugins);
         self.penholder = engine_config.penholder;
+        self.penholder.pen_plugins = pen_plugins;
         self.import_prefs = engine_config.import_prefs;
-        self.export_prefs = engine_config.export_prefs;
+        self.export_prefs = engine_config.export_prefs.clamped();
+        self.export_presets = engine_config.export_presets;
+        self.export_watermark = engine_config.export_watermark;
+        self.export_header_footer = engine_config.export_header_footer;
+        self.navigation_prefs = engine_config.navigation_prefs;
+        self.camera.overlay_config = engine_config.overlay_config.clamped();
+        self.gesture_config = engine_config.gesture_config;
+        self.render_cache_prefs = engine_config.render_cache_prefs;
+        self.save_prefs = engine_config.save_prefs;
         self.pen_sounds = engine_config.pen_sounds;
+        self.color_history = engine_config.color_history;
 
         // Set the pen sounds to update the audioplayer
         self.set_pen_sounds(self.pen_sounds, data_dir);
@@ -190,7 +590,8 @@ impl RnoteEngine {
 
         rayon::spawn(move || {
             let result = || -> anyhow::Result<VectorImage> {
-                let svg_str = String::from_utf8(bytes)?;
+                let svg_str = String::from_utf8(bytes)
+                    .map_err(|e| EngineError::Decode(format!("bytes are not valid UTF-8, {e}")))?;
 
                 VectorImage::import_from_svg_data(&svg_str, pos, None)
             };
@@ -203,19 +604,73 @@ impl RnoteEngine {
         oneshot_receiver
     }
 
+    /// Generate strokes for the Svg bytes, according to the Svg import preferences.
+    ///
+    /// The bytes are expected to be from a valid UTF-8 encoded Svg string.
+    /// Either a single vector image, or editable shape and text strokes together with a vector image for the remaining elements
+    /// are generated.
+    pub fn generate_svg_strokes_from_bytes(
+        &self,
+        pos: na::Vector2<f64>,
+        bytes: Vec<u8>,
+    ) -> oneshot::Receiver<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>>();
+        let svg_import_prefs = self.import_prefs.svg_import_prefs;
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>> {
+                let svg_str = String::from_utf8(bytes)
+                    .map_err(|e| EngineError::Decode(format!("bytes are not valid UTF-8, {e}")))?;
+
+                if svg_import_prefs.as_editable_shapes {
+                    let (vectorimage, strokes) =
+                        svgconvert::import_svg_as_strokes(&svg_str, pos, None)?;
+                    Ok(vectorimage
+                        .map(Stroke::VectorImage)
+                        .into_iter()
+                        .chain(strokes)
+                        .map(|s| (s, None))
+                        .collect())
+                } else {
+                    Ok(vec![(
+                        Stroke::VectorImage(VectorImage::import_from_svg_data(
+                            &svg_str, pos, None,
+                        )?),
+                        None,
+                    )])
+                }
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in generate_svg_strokes_from_bytes() failed. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+
     /// Generate a bitmapimage for the bytes.
     ///
     /// The bytes are expected to be from a valid bitmap image (Png/Jpeg).
+    /// The image is sized according to its DPI metadata and the bitmap import preferences.
     pub fn generate_bitmapimage_from_bytes(
         &self,
         pos: na::Vector2<f64>,
         bytes: Vec<u8>,
     ) -> oneshot::Receiver<anyhow::Result<BitmapImage>> {
         let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<BitmapImage>>();
+        let bitmap_import_prefs = self.import_prefs.bitmap_import_prefs;
+        let format = self.document.format;
 
         rayon::spawn(move || {
             let result = || -> anyhow::Result<BitmapImage> {
-                BitmapImage::import_from_image_bytes(&bytes, pos, None)
+                BitmapImage::import_from_image_bytes_w_prefs(
+                    &bytes,
+                    pos,
+                    bitmap_import_prefs,
+                    &format,
+                )
             };
 
             if let Err(_data) = oneshot_sender.send(result()) {
@@ -226,15 +681,50 @@ impl RnoteEngine {
         oneshot_receiver
     }
 
+    /// Generate a bitmapimage for the bytes, centered in the current viewport.
+    ///
+    /// Used for inserting captured screenshots, which should appear where the user is currently looking.
+    pub fn generate_bitmapimage_from_bytes_viewport_centered(
+        &self,
+        bytes: Vec<u8>,
+    ) -> oneshot::Receiver<anyhow::Result<BitmapImage>> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<BitmapImage>>();
+        let bitmap_import_prefs = self.import_prefs.bitmap_import_prefs;
+        let format = self.document.format;
+        let viewport_center = self.camera.viewport().center().coords;
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<BitmapImage> {
+                let mut bitmapimage = BitmapImage::import_from_image_bytes_w_prefs(
+                    &bytes,
+                    na::Vector2::zeros(),
+                    bitmap_import_prefs,
+                    &format,
+                )?;
+                bitmapimage.translate(viewport_center - bitmapimage.bounds().center().coords);
+                Ok(bitmapimage)
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in generate_bitmapimage_from_bytes_viewport_centered() failed. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+
     /// Generate image strokes for each page for the bytes.
     ///
     /// The bytes are expected to be from a valid Pdf.
+    /// The progress is advanced while the pages are rendered and converted, and the import fails with
+    /// [EngineError::Cancelled] when it gets cancelled.
     #[allow(clippy::type_complexity)]
     pub fn generate_pdf_pages_from_bytes(
         &self,
         bytes: Vec<u8>,
         insert_pos: na::Vector2<f64>,
         page_range: Option<Range<u32>>,
+        progress: Progress,
     ) -> oneshot::Receiver<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>> {
         let (oneshot_sender, oneshot_receiver) =
             oneshot::channel::<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>>();
@@ -251,12 +741,37 @@ impl RnoteEngine {
                             insert_pos,
                             page_range,
                             &format,
+                            &progress,
                         )?
                         .into_iter()
                         .map(|s| (Stroke::BitmapImage(s), Some(StrokeLayer::Document)))
                         .collect::<Vec<(Stroke, Option<StrokeLayer>)>>();
                         Ok(bitmapimages)
                     }
+                    PdfImportPagesType::Vector if pdf_import_prefs.ink_to_brushstrokes => {
+                        let pages = VectorImage::import_from_pdf_bytes_w_ink(
+                            &bytes,
+                            pdf_import_prefs,
+                            insert_pos,
+                            page_range,
+                            &format,
+                            &progress,
+                        )?
+                        .into_iter()
+                        .flat_map(|(vectorimage, brushstrokes)| {
+                            std::iter::once((
+                                Stroke::VectorImage(vectorimage),
+                                Some(StrokeLayer::Document),
+                            ))
+                            .chain(
+                                brushstrokes
+                                    .into_iter()
+                                    .map(|s| (Stroke::BrushStroke(s), None)),
+                            )
+                        })
+                        .collect::<Vec<(Stroke, Option<StrokeLayer>)>>();
+                        Ok(pages)
+                    }
                     PdfImportPagesType::Vector => {
                         let vectorimages = VectorImage::import_from_pdf_bytes(
                             &bytes,
@@ -264,6 +779,7 @@ impl RnoteEngine {
                             insert_pos,
                             page_range,
                             &format,
+                            &progress,
                         )?
                         .into_iter()
                         .map(|s| (Stroke::VectorImage(s), Some(StrokeLayer::Document)))
@@ -281,13 +797,55 @@ impl RnoteEngine {
         oneshot_receiver
     }
 
+    /// Generate text strokes for the bytes.
+    ///
+    /// The bytes are expected to be valid UTF-8 encoded plain text or Markdown.
+    /// The text style of the typewriter is used as base style.
+    pub fn generate_textstrokes_from_bytes(
+        &self,
+        pos: na::Vector2<f64>,
+        bytes: Vec<u8>,
+        markdown: bool,
+    ) -> oneshot::Receiver<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>>();
+        let text_style = self.pens_config.typewriter_config.text_style.clone();
+        let document = self.document;
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>> {
+                let text = String::from_utf8(bytes)
+                    .map_err(|e| EngineError::Decode(format!("bytes are not valid UTF-8, {e}")))?;
+
+                Ok(
+                    TextStroke::import_from_text(&text, markdown, pos, &text_style, &document)
+                        .into_iter()
+                        .map(|s| (Stroke::TextStroke(s), None))
+                        .collect(),
+                )
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in generate_textstrokes_from_bytes() failed. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+
     /// Import the generated strokes into the store.
+    ///
+    /// Images and Pdf pages are fitted to their page according to the page fit mode.
     pub fn import_generated_strokes(
         &mut self,
-        strokes: Vec<(Stroke, Option<StrokeLayer>)>,
+        mut strokes: Vec<(Stroke, Option<StrokeLayer>)>,
     ) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
 
+        self.import_prefs
+            .page_fit_mode
+            .fit_strokes_to_pages(&mut strokes, &self.document.format);
+
         // we need to always deselect all strokes -
         // even tough changing the pen style deselects too, it does only when the pen is actually different.
         let all_strokes = self.store.stroke_keys_as_rendered();
@@ -315,6 +873,56 @@ impl RnoteEngine {
         widget_flags
     }
 
+    /// Import multiple items of generated strokes, laid out according to the multi import preferences.
+    ///
+    /// Each item holds the strokes that were generated for it, e.g. from an image file or a single Pdf page.
+    /// The items keep their relative layout and are placed starting at `pos` without overlapping each other.
+    pub fn import_generated_strokes_w_placement(
+        &mut self,
+        items: Vec<Vec<(Stroke, Option<StrokeLayer>)>>,
+        pos: na::Vector2<f64>,
+    ) -> WidgetFlags {
+        let multi_import_prefs = self.import_prefs.multi_import_prefs;
+        let items = items
+            .into_iter()
+            .filter_map(|item| {
+                let bounds = item
+                    .iter()
+                    .map(|(stroke, _)| stroke.bounds())
+                    .reduce(|acc, b| acc.merged(&b))?;
+                Some((item, bounds))
+            })
+            .collect::<Vec<(Vec<(Stroke, Option<StrokeLayer>)>, Aabb)>>();
+        let n_columns = match multi_import_prefs.placement {
+            MultiImportPlacement::Grid => multi_import_prefs
+                .grid_columns
+                .unwrap_or_else(|| (items.len() as f64).sqrt().ceil() as usize)
+                .max(1),
+            MultiImportPlacement::VerticalFlow => 1,
+        };
+
+        let mut strokes = vec![];
+        let mut x = pos[0];
+        let mut row_y = pos[1];
+        let mut row_height: f64 = 0.0;
+        for (i, (item, bounds)) in items.into_iter().enumerate() {
+            if i > 0 && i % n_columns == 0 {
+                x = pos[0];
+                row_y += row_height + multi_import_prefs.spacing;
+                row_height = 0.0;
+            }
+            let offset = na::vector![x, row_y] - bounds.mins.coords;
+            strokes.extend(item.into_iter().map(|(mut stroke, layer)| {
+                stroke.translate(offset);
+                (stroke, layer)
+            }));
+            x += bounds.extents()[0] + multi_import_prefs.spacing;
+            row_height = row_height.max(bounds.extents()[1]);
+        }
+
+        self.import_generated_strokes(strokes)
+    }
+
     /// Insert text.
     pub fn insert_text(
         &mut self,
@@ -350,15 +958,27 @@ impl RnoteEngine {
         Ok(widget_flags)
     }
 
-    /// Insert the stroke content.
+    /// The offset between repeated pastes in cascaded paste placement.
+    pub const PASTE_CASCADE_OFFSET: na::Vector2<f64> = na::vector![16.0, 16.0];
+
+    /// Insert the stroke content with the given placement.
     ///
     /// The data usually comes from the clipboard, drag-and-drop, ..
     pub fn insert_stroke_content(
         &mut self,
         content: StrokeContent,
-        pos: na::Vector2<f64>,
+        placement: PastePlacement,
     ) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
+        let Some(content_bounds) = content
+            .strokes
+            .iter()
+            .map(|s| s.bounds())
+            .reduce(|acc, x| acc.merged(&x))
+        else {
+            return widget_flags;
+        };
+        let pos = self.paste_pos(content_bounds, placement);
 
         // we need to always deselect all strokes
         // even though changing the pen style deselects too, but only when the pen is actually different.
@@ -388,4 +1008,32 @@ impl RnoteEngine {
 
         widget_flags
     }
+
+    /// The top left position of pasted content with the given bounds.
+    fn paste_pos(&mut self, content_bounds: Aabb, placement: PastePlacement) -> na::Vector2<f64> {
+        if placement != PastePlacement::Cascaded {
+            self.paste_cascade = None;
+        }
+        match placement {
+            PastePlacement::At(pos) => pos,
+            PastePlacement::Viewport => self
+                .camera
+                .transform()
+                .inverse()
+                .transform_point(&na::Point2::from(Stroke::IMPORT_OFFSET_DEFAULT))
+                .coords
+                .maxs(&na::vector![self.document.x, self.document.y]),
+            PastePlacement::InPlace => content_bounds.mins.coords,
+            PastePlacement::CenteredAt(center) => center - content_bounds.half_extents(),
+            PastePlacement::Cascaded => {
+                // Repeated pastes of the same content are recognized by their original bounds
+                let n = match self.paste_cascade {
+                    Some((bounds, n)) if bounds == content_bounds => n + 1,
+                    _ => 1,
+                };
+                self.paste_cascade = Some((content_bounds, n));
+                content_bounds.mins.coords + Self::PASTE_CASCADE_OFFSET * n as f64
+            }
+        }
+    }
 }
@@ -0,0 +1,264 @@
+// Imports
+use serde::{Deserialize, Serialize};
+
+/// The size of an A4 sheet in portrait orientation, in points.
+pub const SHEET_SIZE_A4_PT: [f64; 2] = [595.276, 841.89];
+
+/// The number of document pages that are placed onto a single sheet.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "pages_per_sheet")]
+pub enum PagesPerSheet {
+    #[serde(rename = "one")]
+    One,
+    #[serde(rename = "two")]
+    Two,
+    #[serde(rename = "four")]
+    Four,
+    #[serde(rename = "six")]
+    Six,
+}
+
+impl Default for PagesPerSheet {
+    fn default() -> Self {
+        Self::One
+    }
+}
+
+impl TryFrom<u32> for PagesPerSheet {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value).ok_or_else(|| {
+            anyhow::anyhow!("PagesPerSheet try_from::<u32>() for value {} failed", value)
+        })
+    }
+}
+
+impl PagesPerSheet {
+    pub fn n(self) -> usize {
+        match self {
+            PagesPerSheet::One => 1,
+            PagesPerSheet::Two => 2,
+            PagesPerSheet::Four => 4,
+            PagesPerSheet::Six => 6,
+        }
+    }
+}
+
+/// The placement of a page on a sheet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SheetSlot {
+    /// The index of the placed page.
+    pub page: usize,
+    /// The scale-factor of the page.
+    pub scale: f64,
+    /// The position of the upper left corner of the page on the sheet.
+    pub offset: na::Vector2<f64>,
+}
+
+/// A sheet with the pages that are placed onto it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sheet {
+    pub size: na::Vector2<f64>,
+    pub slots: Vec<SheetSlot>,
+}
+
+/// Place the pages with the sizes onto sheets in a grid, in reading order.
+///
+/// The sheet size that fits the pages best is picked from the candidates, e.g. portrait and landscape orientation.
+/// Pages are scaled down to fit into the cells of the grid, which are separated by the gutter.
+pub fn impose_n_up(
+    page_sizes: &[na::Vector2<f64>],
+    sheet_sizes: &[na::Vector2<f64>],
+    pages_per_sheet: PagesPerSheet,
+    gutter: f64,
+) -> Vec<Sheet> {
+    let n = pages_per_sheet.n();
+    let max_page_size = page_sizes
+        .iter()
+        .fold(na::Vector2::<f64>::zeros(), |acc, size| acc.sup(size));
+    let Some((sheet_size, cols, rows)) = sheet_sizes
+        .iter()
+        .flat_map(|&sheet_size| {
+            (1..=n)
+                .filter(|cols| n % cols == 0)
+                .map(move |cols| (sheet_size, cols, n / cols))
+        })
+        .max_by(|a, b| {
+            let scale_a = cell_scale(max_page_size, a.0, a.1, a.2, gutter);
+            let scale_b = cell_scale(max_page_size, b.0, b.1, b.2, gutter);
+            scale_a.total_cmp(&scale_b)
+        })
+    else {
+        return vec![];
+    };
+    let cell_size = cell_size(sheet_size, cols, rows, gutter);
+
+    page_sizes
+        .chunks(n)
+        .enumerate()
+        .map(|(i, chunk)| Sheet {
+            size: sheet_size,
+            slots: chunk
+                .iter()
+                .enumerate()
+                .map(|(j, page_size)| {
+                    let (col, row) = (j % cols, j / cols);
+                    let scale = fit_scale(*page_size, cell_size);
+                    let cell_offset = na::vector![
+                        col as f64 * (cell_size[0] + gutter),
+                        row as f64 * (cell_size[1] + gutter)
+                    ];
+                    SheetSlot {
+                        page: i * n + j,
+                        scale,
+                        offset: cell_offset + (cell_size - page_size * scale) * 0.5,
+                    }
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Reorder and pair the pages with the sizes for folded printing, so that the printed sheets can be stacked,
+/// folded in the middle and stapled into a booklet.
+///
+/// Every returned sheet is one side of a physical sheet, with two pages side by side. The sides alternate between
+/// front and back, so they need to be printed double-sided, flipping on the short edge.
+/// The page count is padded with blank pages to a multiple of four. Pages are scaled down to fit into the halves
+/// of the sheet, aligned to the fold, which is in the middle of the gutter.
+pub fn impose_booklet(
+    page_sizes: &[na::Vector2<f64>],
+    sheet_sizes: &[na::Vector2<f64>],
+    gutter: f64,
+) -> Vec<Sheet> {
+    let max_page_size = page_sizes
+        .iter()
+        .fold(na::Vector2::<f64>::zeros(), |acc, size| acc.sup(size));
+    let Some(sheet_size) = sheet_sizes.iter().copied().max_by(|a, b| {
+        cell_scale(max_page_size, *a, 2, 1, gutter).total_cmp(&cell_scale(
+            max_page_size,
+            *b,
+            2,
+            1,
+            gutter,
+        ))
+    }) else {
+        return vec![];
+    };
+    let cell_size = cell_size(sheet_size, 2, 1, gutter);
+    let n_pages = (page_sizes.len() + 3) / 4 * 4;
+
+    (0..n_pages / 2)
+        .map(|side| {
+            let k = side / 2;
+            let (left, right) = if side % 2 == 0 {
+                (n_pages - 1 - 2 * k, 2 * k)
+            } else {
+                (2 * k + 1, n_pages - 2 - 2 * k)
+            };
+            let slots = [(left, true), (right, false)]
+                .into_iter()
+                .filter_map(|(page, is_left)| {
+                    let page_size = page_sizes.get(page)?;
+                    let scale = fit_scale(*page_size, cell_size);
+                    let scaled_size = page_size * scale;
+                    let x = if is_left {
+                        cell_size[0] - scaled_size[0]
+                    } else {
+                        cell_size[0] + gutter
+                    };
+                    Some(SheetSlot {
+                        page,
+                        scale,
+                        offset: na::vector![x, (cell_size[1] - scaled_size[1]) * 0.5],
+                    })
+                })
+                .collect();
+            Sheet {
+                size: sheet_size,
+                slots,
+            }
+        })
+        .collect()
+}
+
+fn cell_size(
+    sheet_size: na::Vector2<f64>,
+    cols: usize,
+    rows: usize,
+    gutter: f64,
+) -> na::Vector2<f64> {
+    na::vector![
+        ((sheet_size[0] - gutter * (cols - 1) as f64) / cols as f64).max(0.0),
+        ((sheet_size[1] - gutter * (rows - 1) as f64) / rows as f64).max(0.0)
+    ]
+}
+
+fn cell_scale(
+    page_size: na::Vector2<f64>,
+    sheet_size: na::Vector2<f64>,
+    cols: usize,
+    rows: usize,
+    gutter: f64,
+) -> f64 {
+    fit_scale(page_size, cell_size(sheet_size, cols, rows, gutter))
+}
+
+/// The scale-factor that fits the size into the area, without scaling it up.
+fn fit_scale(size: na::Vector2<f64>, area: na::Vector2<f64>) -> f64 {
+    if size[0] <= 0.0 || size[1] <= 0.0 {
+        return 1.0;
+    }
+    (area[0] / size[0]).min(area[1] / size[1]).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_up_portrait_pages_on_landscape_sheet() {
+        let page_size = na::vector![210.0, 297.0];
+        let portrait = na::vector![210.0, 297.0];
+        let landscape = na::vector![297.0, 210.0];
+        let sheets = impose_n_up(
+            &[page_size; 3],
+            &[portrait, landscape],
+            PagesPerSheet::Two,
+            0.0,
+        );
+
+        assert_eq!(sheets.len(), 2);
+        assert_eq!(sheets[0].size, landscape);
+        assert_eq!(sheets[0].slots.len(), 2);
+        assert_eq!(sheets[1].slots.len(), 1);
+        assert_eq!(sheets[1].slots[0].page, 2);
+        // The second page is placed in the right half
+        assert!(sheets[0].slots[1].offset[0] >= 297.0 * 0.5 - 1e-9);
+        assert!((sheets[0].slots[0].scale - 210.0 / 297.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn booklet_page_order() {
+        let page_size = na::vector![148.0, 210.0];
+        let sheets = impose_booklet(&[page_size; 6], &[na::vector![297.0, 210.0]], 0.0);
+        let pages = sheets
+            .iter()
+            .map(|sheet| sheet.slots.iter().map(|slot| slot.page).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        // Padded to eight pages, the blank pages 6 and 7 are left out
+        assert_eq!(pages, vec![vec![0], vec![1], vec![5, 2], vec![3, 4]]);
+    }
+}
@@ -0,0 +1,258 @@
+// Imports
+use super::{EngineTask, RnoteEngine};
+use crate::render;
+use crate::utils::GrapheneRectHelpers;
+use crate::{DrawBehaviour, WidgetFlags};
+use gtk4::{graphene, gsk, prelude::*, Snapshot};
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rnote_compose::helpers::{AabbHelpers, SplitOrder};
+use std::collections::HashMap;
+
+/// Below this total zoom the strokes are rendered into composite images per region of the document (level of detail),
+/// instead of rendering an image for every single stroke.
+pub const LOD_ZOOM_THRESHOLD: f64 = 0.3;
+/// The size of the composite images in pixels.
+const LOD_TILE_SIZE: f64 = 512.0;
+
+/// The index of a composite tile, its origin divided by the tile size.
+pub(super) type LodTileIndex = (i64, i64);
+
+#[derive(Debug, Clone)]
+enum LodTileState {
+    /// A task is rendering the tile, identified by the request id.
+    Busy(u64),
+    /// The tile has no strokes.
+    Empty,
+    /// The rendernode and the number of bytes of its image.
+    Rendered(gsk::RenderNode, usize),
+}
+
+/// The composite images of the level of detail rendering.
+#[derive(Debug, Default)]
+pub(super) struct LodCache {
+    image_scale: f64,
+    tiles: HashMap<LodTileIndex, LodTileState>,
+    next_request: u64,
+}
+
+impl LodCache {
+    fn clear(&mut self) {
+        self.tiles.clear();
+    }
+}
+
+fn tile_index(tile_bounds: Aabb, tile_size: f64) -> LodTileIndex {
+    (
+        (tile_bounds.mins[0] / tile_size).round() as i64,
+        (tile_bounds.mins[1] / tile_size).round() as i64,
+    )
+}
+
+impl RnoteEngine {
+    /// Whether the level of detail rendering is used for the current zoom.
+    pub fn lod_active(&self) -> bool {
+        self.camera.total_zoom() < LOD_ZOOM_THRESHOLD
+    }
+
+    /// The image scale of the composite images, rounded down to a power of two,
+    /// so that they don't need to be regenerated for small zoom changes.
+    fn lod_image_scale(&self) -> f64 {
+        2.0_f64.powf(self.camera.image_scale().log2().floor())
+    }
+
+    /// Drop all composite images, e.g. when what is visible changed without modifying the strokes.
+    pub(super) fn lod_clear_cache(&mut self) {
+        self.lod_cache.clear();
+    }
+
+    /// Regenerate the composite images of the current viewport that are outdated or missing.
+    pub(super) fn update_lod_rendering_current_viewport(&mut self) {
+        let changed_bounds = self.store.take_changed_bounds();
+        if !self.lod_active() {
+            self.lod_cache.clear();
+            return;
+        }
+        let image_scale = self.lod_image_scale();
+        if self.lod_cache.image_scale != image_scale {
+            self.lod_cache.clear();
+            self.lod_cache.image_scale = image_scale;
+        }
+        let tile_size = LOD_TILE_SIZE / image_scale;
+        let viewport = self.camera.viewport();
+        let render_bounds =
+            viewport.extend_by(viewport.extents() * render::VIEWPORT_EXTENTS_MARGIN_FACTOR);
+
+        // Drop the outdated and the far away tiles
+        self.lod_cache.tiles.retain(|&(x, y), _| {
+            let tile_bounds = Aabb::new(
+                na::point![x as f64 * tile_size, y as f64 * tile_size],
+                na::point![(x + 1) as f64 * tile_size, (y + 1) as f64 * tile_size],
+            );
+            tile_bounds.intersects(&render_bounds)
+                && !changed_bounds
+                    .map(|changed| changed.intersects(&tile_bounds))
+                    .unwrap_or(false)
+        });
+
+        for tile_bounds in render_bounds
+            .split_extended_origin_aligned(na::vector![tile_size, tile_size], SplitOrder::default())
+        {
+            let tile_index = tile_index(tile_bounds, tile_size);
+            if self.lod_cache.tiles.contains_key(&tile_index) {
+                continue;
+            }
+            let strokes = self
+                .store
+                .stroke_keys_as_rendered_intersecting_bounds(tile_bounds)
+                .into_iter()
+                .filter(|&key| self.store.visible_at_playback_time(key))
+                .filter_map(|key| self.store.get_stroke_arc(key))
+                .collect::<Vec<_>>();
+            if strokes.is_empty() {
+                self.lod_cache.tiles.insert(tile_index, LodTileState::Empty);
+                continue;
+            }
+
+            let request = self.lod_cache.next_request;
+            self.lod_cache.next_request += 1;
+            self.lod_cache
+                .tiles
+                .insert(tile_index, LodTileState::Busy(request));
+            let tasks_tx = self.tasks_tx();
+
+            rayon::spawn(move || {
+                let image = render::Image::gen_with_piet(
+                    |piet_cx| {
+                        for stroke in strokes.iter() {
+                            stroke.draw(piet_cx, image_scale)?;
+                        }
+                        Ok(())
+                    },
+                    tile_bounds,
+                    image_scale,
+                );
+                match image {
+                    Ok(image) => {
+                        tasks_tx
+                            .unbounded_send(EngineTask::UpdateLodTile {
+                                tile_index,
+                                request,
+                                image,
+                            })
+                            .unwrap_or_else(|e| {
+                                log::error!("tasks_tx.send() UpdateLodTile failed, Err: {e:?}");
+                            });
+                    }
+                    Err(e) => {
+                        log::debug!("generating level of detail tile image failed, Err: {e:?}");
+                    }
+                }
+            });
+        }
+    }
+
+    /// The number of bytes held by the composite images.
+    pub(super) fn lod_tiles_bytes(&self) -> usize {
+        self.lod_cache
+            .tiles
+            .values()
+            .map(|tile| match tile {
+                LodTileState::Rendered(_, bytes) => *bytes,
+                LodTileState::Busy(_) | LodTileState::Empty => 0,
+            })
+            .sum()
+    }
+
+    /// Drop the composite images that are outside of the viewport.
+    ///
+    /// Returns the number of freed bytes.
+    pub(super) fn lod_evict_tiles_outside_viewport(&mut self) -> usize {
+        let bytes_before = self.lod_tiles_bytes();
+        let tile_size = LOD_TILE_SIZE / self.lod_cache.image_scale;
+        let viewport = self.camera.viewport();
+        self.lod_cache.tiles.retain(|&(x, y), _| {
+            Aabb::new(
+                na::point![x as f64 * tile_size, y as f64 * tile_size],
+                na::point![(x + 1) as f64 * tile_size, (y + 1) as f64 * tile_size],
+            )
+            .intersects(&viewport)
+        });
+        bytes_before.saturating_sub(self.lod_tiles_bytes())
+    }
+
+    /// Replace the tile with the rendered image, if it was not invalidated in the meantime.
+    pub(super) fn lod_handle_rendered_tile(
+        &mut self,
+        tile_index: LodTileIndex,
+        request: u64,
+        image: render::Image,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let Some(LodTileState::Busy(busy_request)) = self.lod_cache.tiles.get(&tile_index) else {
+            return widget_flags;
+        };
+        if *busy_request != request {
+            return widget_flags;
+        }
+        match image.to_rendernode() {
+            Ok(rendernode) => {
+                self.lod_cache.tiles.insert(
+                    tile_index,
+                    LodTileState::Rendered(rendernode, image.data.len()),
+                );
+                widget_flags.redraw = true;
+            }
+            Err(e) => {
+                self.lod_cache.tiles.remove(&tile_index);
+                log::error!("generating rendernode for level of detail tile failed, Err: {e:?}");
+            }
+        }
+        widget_flags
+    }
+
+    /// Draw the strokes with the composite images.
+    ///
+    /// Where the images are missing or outdated, the renderings of the individual strokes are drawn.
+    pub(super) fn draw_lod_to_gtk_snapshot(&self, snapshot: &Snapshot, doc_bounds: Aabb) {
+        let viewport = self.camera.viewport();
+        let image_scale = self.lod_image_scale();
+        let tile_size = LOD_TILE_SIZE / image_scale;
+        // The tiles were rendered for a different zoom if the scale does not match
+        let cache_valid = self.lod_cache.image_scale == image_scale;
+        let changed_bounds = self.store.changed_bounds();
+
+        snapshot.push_clip(&graphene::Rect::from_p2d_aabb(doc_bounds));
+
+        for tile_bounds in viewport
+            .split_extended_origin_aligned(na::vector![tile_size, tile_size], SplitOrder::default())
+        {
+            let outdated = changed_bounds
+                .map(|changed| changed.intersects(&tile_bounds))
+                .unwrap_or(false);
+            let tile_state = if outdated || !cache_valid {
+                None
+            } else {
+                self.lod_cache
+                    .tiles
+                    .get(&tile_index(tile_bounds, tile_size))
+            };
+
+            match tile_state {
+                Some(LodTileState::Rendered(rendernode, _)) => snapshot.append_node(rendernode),
+                Some(LodTileState::Empty) => {}
+                Some(LodTileState::Busy(_)) | None => {
+                    if let Some(clip_bounds) = tile_bounds.intersection(&doc_bounds) {
+                        self.store.draw_strokes_to_gtk_snapshot(
+                            snapshot,
+                            clip_bounds,
+                            tile_bounds,
+                            self.camera.image_scale(),
+                        );
+                    }
+                }
+            }
+        }
+
+        snapshot.pop();
+    }
+}
@@ -0,0 +1,490 @@
+// Imports
+use super::save::write_file_atomic;
+use super::{RnoteEngine, StrokeContent};
+use crate::document::{Background, DocumentMetadata};
+use crate::render;
+use crate::strokes::textstroke::{FontStyle, RangedTextAttribute, TextAttribute, TextStroke};
+use crate::strokes::Stroke;
+use crate::Progress;
+use futures::channel::oneshot;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rnote_compose::shapes::ShapeBehaviour;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The file format of the images the drawings are exported to.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "markdown_asset_format")]
+pub enum MarkdownAssetFormat {
+    #[serde(rename = "png")]
+    Png,
+    #[serde(rename = "svg")]
+    Svg,
+}
+
+impl Default for MarkdownAssetFormat {
+    fn default() -> Self {
+        Self::Png
+    }
+}
+
+impl MarkdownAssetFormat {
+    pub fn file_ext(self) -> String {
+        match self {
+            MarkdownAssetFormat::Png => String::from("png"),
+            MarkdownAssetFormat::Svg => String::from("svg"),
+        }
+    }
+}
+
+/// Preferences for exporting the document as Markdown.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "markdown_export_prefs")]
+pub struct MarkdownExportPrefs {
+    /// The file format of the drawings.
+    #[serde(rename = "asset_format")]
+    pub asset_format: MarkdownAssetFormat,
+    /// Whether the background is exported with the drawings.
+    #[serde(rename = "with_background")]
+    pub with_background: bool,
+    /// Whether the background pattern is exported with the drawings.
+    #[serde(rename = "with_pattern")]
+    pub with_pattern: bool,
+    /// The scale-factor of Png drawings in relation to the actual size.
+    #[serde(rename = "bitmap_scalefactor")]
+    pub bitmap_scalefactor: f64,
+    /// Strokes that are closer to each other than this distance are exported as one drawing.
+    #[serde(rename = "drawing_spacing")]
+    pub drawing_spacing: f64,
+    /// The margin around the drawings.
+    #[serde(rename = "margin")]
+    pub margin: f64,
+}
+
+impl Default for MarkdownExportPrefs {
+    fn default() -> Self {
+        Self {
+            asset_format: MarkdownAssetFormat::default(),
+            with_background: false,
+            with_pattern: false,
+            bitmap_scalefactor: 1.8,
+            drawing_spacing: 48.0,
+            margin: 12.0,
+        }
+    }
+}
+
+/// An image file the exported Markdown links to.
+#[derive(Debug, Clone)]
+pub struct MarkdownAsset {
+    /// The path of the file, relative to the Markdown file.
+    pub path: String,
+    pub bytes: Vec<u8>,
+}
+
+/// The document exported as Markdown, together with the drawings it links to.
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownExport {
+    pub markdown: String,
+    pub assets: Vec<MarkdownAsset>,
+}
+
+impl MarkdownExport {
+    /// The extension of Markdown files.
+    pub const FILE_EXT: &str = "md";
+
+    /// Write the Markdown to the file at the given path, and the assets at their relative paths next to it.
+    ///
+    /// The assets are written first, so that the links are valid once the Markdown file exists.
+    pub fn write_to_file(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        for asset in self.assets.iter() {
+            let asset_path = dir.join(&asset.path);
+            if let Some(asset_dir) = asset_path.parent() {
+                std::fs::create_dir_all(asset_dir)?;
+            }
+            write_file_atomic(&asset_path, &asset.bytes)?;
+        }
+        write_file_atomic(path, self.markdown.as_bytes())
+    }
+}
+
+/// A block of the exported Markdown.
+enum Block {
+    Text(Arc<Stroke>),
+    /// The index of the drawing.
+    Drawing(usize),
+}
+
+/// Strokes that are exported together as one image, with the index of every stroke in the rendering order.
+struct Drawing {
+    bounds: Aabb,
+    strokes: Vec<(usize, Arc<Stroke>)>,
+}
+
+impl RnoteEngine {
+    /// Export the document as Markdown, with the text strokes as paragraphs and headings
+    /// and the other strokes grouped into drawings, which are exported as linked image assets.
+    ///
+    /// The name is the name of the Markdown file without the extension. The assets are placed in the
+    /// `<name>-assets` directory next to it. The document metadata is exported as front matter.
+    pub fn export_doc_as_markdown(
+        &self,
+        name: String,
+        markdown_export_prefs_override: Option<MarkdownExportPrefs>,
+        progress: Progress,
+    ) -> oneshot::Receiver<anyhow::Result<MarkdownExport>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<MarkdownExport>>();
+        let prefs =
+            markdown_export_prefs_override.unwrap_or(self.export_prefs.markdown_export_prefs);
+        let strokes = self
+            .store
+            .get_strokes_arc(&self.store.stroke_keys_as_rendered());
+        let background = self.document.background;
+        let metadata = self.doc_metadata.clone();
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<MarkdownExport> {
+                let (blocks, drawings) = split_into_blocks(strokes, prefs.drawing_spacing);
+                let assets_dir = format!("{}-assets", name.replace(['/', '\\'], "-"));
+                let body_font_size = body_font_size(&blocks);
+
+                let stage = progress.stage(0.0, 1.0, drawings.len());
+                let mut export = MarkdownExport {
+                    markdown: gen_front_matter(&metadata),
+                    assets: Vec::with_capacity(drawings.len()),
+                };
+                for block in blocks {
+                    progress.check_cancelled()?;
+                    let block_markdown = match block {
+                        Block::Text(stroke) => {
+                            let Stroke::TextStroke(textstroke) = stroke.as_ref() else {
+                                continue;
+                            };
+                            textstroke_to_markdown(textstroke, body_font_size)
+                        }
+                        Block::Drawing(i) => {
+                            stage.step();
+                            let path = format!(
+                                "{assets_dir}/drawing-{}.{}",
+                                i + 1,
+                                prefs.asset_format.file_ext()
+                            );
+                            let Some(bytes) = gen_drawing_asset(&drawings[i], background, &prefs)?
+                            else {
+                                continue;
+                            };
+                            let markdown =
+                                format!("![Drawing {}]({})", i + 1, encode_link_path(&path));
+                            export.assets.push(MarkdownAsset { path, bytes });
+                            markdown
+                        }
+                    };
+                    if block_markdown.trim().is_empty() {
+                        continue;
+                    }
+                    export.markdown.push_str(&block_markdown);
+                    export.markdown.push_str("\n\n");
+                }
+                stage.finish()?;
+                Ok(export)
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("Sending result to receiver failed. Receiver already dropped.");
+            }
+        });
+
+        oneshot_receiver
+    }
+}
+
+/// Splits the strokes into the text blocks and the drawings, and returns the blocks in reading order.
+///
+/// Text strokes that overlap a drawing are labels and become part of it.
+fn split_into_blocks(strokes: Vec<Arc<Stroke>>, spacing: f64) -> (Vec<Block>, Vec<Drawing>) {
+    let (texts, others): (Vec<(usize, Arc<Stroke>)>, Vec<(usize, Arc<Stroke>)>) = strokes
+        .into_iter()
+        .enumerate()
+        .partition(|(_, stroke)| matches!(stroke.as_ref(), Stroke::TextStroke(_)));
+
+    let mut drawings: Vec<Drawing> = vec![];
+    for (i, stroke) in others {
+        let mut drawing = Drawing {
+            bounds: stroke.bounds(),
+            strokes: vec![(i, stroke)],
+        };
+        // Absorb the drawings within the spacing, until the drawings are separated from each other again
+        while let Some(close_i) = drawings.iter().position(|other| {
+            other
+                .bounds
+                .loosened(spacing * 0.5)
+                .intersects(&drawing.bounds.loosened(spacing * 0.5))
+        }) {
+            let close = drawings.swap_remove(close_i);
+            drawing.bounds.merge(&close.bounds);
+            drawing.strokes.extend(close.strokes);
+        }
+        drawings.push(drawing);
+    }
+
+    let mut blocks = vec![];
+    for (i, stroke) in texts {
+        let bounds = stroke.bounds();
+        match drawings
+            .iter_mut()
+            .find(|drawing| drawing.bounds.intersects(&bounds))
+        {
+            Some(drawing) => {
+                drawing.bounds.merge(&bounds);
+                drawing.strokes.push((i, stroke));
+            }
+            None => blocks.push((bounds, Block::Text(stroke))),
+        }
+    }
+    for drawing in drawings.iter_mut() {
+        drawing.strokes.sort_by_key(|(i, _)| *i);
+    }
+    // Top to bottom, then left to right
+    drawings.sort_by(|a, b| {
+        (a.bounds.mins[1], a.bounds.mins[0])
+            .partial_cmp(&(b.bounds.mins[1], b.bounds.mins[0]))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    blocks.extend(
+        drawings
+            .iter()
+            .enumerate()
+            .map(|(i, drawing)| (drawing.bounds, Block::Drawing(i))),
+    );
+    blocks.sort_by(|(a, _), (b, _)| {
+        (a.mins[1], a.mins[0])
+            .partial_cmp(&(b.mins[1], b.mins[0]))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    (
+        blocks.into_iter().map(|(_, block)| block).collect(),
+        drawings,
+    )
+}
+
+/// The font size of the body text, the median of the font sizes of the text blocks.
+fn body_font_size(blocks: &[Block]) -> f64 {
+    let mut font_sizes = blocks
+        .iter()
+        .filter_map(|block| match block {
+            Block::Text(stroke) => match stroke.as_ref() {
+                Stroke::TextStroke(textstroke) => Some(textstroke.text_style.font_size),
+                _ => None,
+            },
+            Block::Drawing(_) => None,
+        })
+        .collect::<Vec<f64>>();
+    font_sizes.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    font_sizes.get(font_sizes.len() / 2).copied().unwrap_or(1.0)
+}
+
+/// Text with a larger font size than the body text becomes a heading, monospace text a code block.
+fn textstroke_to_markdown(textstroke: &TextStroke, body_font_size: f64) -> String {
+    let text = textstroke.text.trim_end();
+    let style = &textstroke.text_style;
+    if style.font_family.to_lowercase().contains("mono") {
+        return format!("```\n{text}\n```");
+    }
+    let size_ratio = style.font_size / body_font_size;
+    let heading_level = if size_ratio >= 1.8 {
+        Some(1)
+    } else if size_ratio >= 1.45 {
+        Some(2)
+    } else if size_ratio >= 1.2 {
+        Some(3)
+    } else {
+        None
+    };
+    match heading_level {
+        // Headings are single lines
+        Some(level) if !text.contains('\n') => {
+            format!("{} {}", "#".repeat(level), text.trim())
+        }
+        _ => markdown_inline(
+            &textstroke.text,
+            &style.ranged_text_attributes,
+            style.font_weight,
+        )
+        .trim_end()
+        .to_string(),
+    }
+}
+
+/// Applies the bold and italic ranged text attributes as inline Markdown markers.
+///
+/// The inverse of the parsing when importing Markdown.
+fn markdown_inline(text: &str, attrs: &[RangedTextAttribute], base_weight: u16) -> String {
+    const BOLD_WEIGHT_MIN: u16 = 600;
+    let format_at = |pos: usize| -> (bool, bool) {
+        let mut bold = false;
+        let mut italic = false;
+        for attr in attrs.iter().filter(|attr| attr.range.contains(&pos)) {
+            match attr.attribute {
+                TextAttribute::FontWeight(weight) => {
+                    bold = weight >= BOLD_WEIGHT_MIN && base_weight < BOLD_WEIGHT_MIN
+                }
+                TextAttribute::Style(FontStyle::Italic) => italic = true,
+                TextAttribute::Style(FontStyle::Regular) => italic = false,
+                _ => {}
+            }
+        }
+        (bold, italic)
+    };
+
+    let mut markdown = String::with_capacity(text.len());
+    let mut current = (false, false);
+    for (pos, c) in text.char_indices() {
+        // Markers can't span line breaks
+        let next = if c == '\n' {
+            (false, false)
+        } else {
+            format_at(pos)
+        };
+        if next != current {
+            // Close the inner italic marker first, then the bold one
+            if current.1 {
+                markdown.push('*');
+            }
+            if current.0 && !next.0 {
+                markdown.push_str("**");
+            }
+            if next.0 && !current.0 {
+                markdown.push_str("**");
+            }
+            if next.1 {
+                markdown.push('*');
+            }
+            current = next;
+        }
+        markdown.push(c);
+    }
+    if current.1 {
+        markdown.push('*');
+    }
+    if current.0 {
+        markdown.push_str("**");
+    }
+    markdown
+}
+
+/// The document metadata as Yaml front matter, which note taking apps use for the title and tags.
+fn gen_front_matter(metadata: &DocumentMetadata) -> String {
+    if metadata.is_empty() {
+        return String::new();
+    }
+    // Json strings are valid Yaml scalars, with all special characters escaped
+    let quote = |s: &str| serde_json::to_string(s).unwrap_or_default();
+    let mut front_matter = String::from("---\n");
+    for (key, value) in [
+        ("title", &metadata.title),
+        ("author", &metadata.author),
+        ("subject", &metadata.subject),
+    ] {
+        if !value.is_empty() {
+            front_matter.push_str(&format!("{key}: {}\n", quote(value)));
+        }
+    }
+    if !metadata.keywords.is_empty() {
+        front_matter.push_str(&format!(
+            "tags: [{}]\n",
+            metadata
+                .keywords
+                .iter()
+                .map(|k| quote(k))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+    }
+    for (key, value) in metadata.custom.iter() {
+        front_matter.push_str(&format!("{}: {}\n", quote(key), quote(value)));
+    }
+    front_matter.push_str("---\n\n");
+    front_matter
+}
+
+fn gen_drawing_asset(
+    drawing: &Drawing,
+    background: Background,
+    prefs: &MarkdownExportPrefs,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let content = StrokeContent::default()
+        .with_strokes(drawing.strokes.iter().map(|(_, s)| Arc::clone(s)).collect())
+        .with_background(Some(background));
+    let Some(svg) = content.gen_svg(prefs.with_background, prefs.with_pattern, prefs.margin)?
+    else {
+        return Ok(None);
+    };
+    let bytes = match prefs.asset_format {
+        MarkdownAssetFormat::Svg => rnote_compose::utils::add_xml_header(
+            rnote_compose::utils::wrap_svg_root(
+                svg.svg_data.as_str(),
+                Some(svg.bounds),
+                Some(svg.bounds),
+                false,
+            )
+            .as_str(),
+        )
+        .into_bytes(),
+        MarkdownAssetFormat::Png => {
+            let bounds = svg.bounds;
+            render::Image::gen_image_from_svg(svg, bounds, prefs.bitmap_scalefactor)?
+                .into_encoded_bytes(image::ImageOutputFormat::Png)?
+        }
+    };
+    Ok(Some(bytes))
+}
+
+/// Escapes the characters that would end or break a link destination.
+fn encode_link_path(path: &str) -> String {
+    path.replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+        .replace('<', "%3C")
+        .replace('>', "%3E")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inline_markers_from_attributes() {
+        let attrs = vec![
+            RangedTextAttribute {
+                range: 4..8,
+                attribute: TextAttribute::FontWeight(700),
+            },
+            RangedTextAttribute {
+                range: 13..19,
+                attribute: TextAttribute::Style(FontStyle::Italic),
+            },
+        ];
+        assert_eq!(
+            markdown_inline("the bold and italic text", &attrs, 400),
+            "the **bold** and *italic* text"
+        );
+        // Bold text stays unmarked when the whole text is bold
+        assert_eq!(markdown_inline("bold", &attrs, 700), "bold");
+    }
+}
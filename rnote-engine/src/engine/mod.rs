@@ -1,24 +1,69 @@
 // Modules
+pub mod accessibility;
+pub mod anchors;
+pub mod builder;
+pub mod colorhistory;
 pub mod export;
+pub mod gestures;
+pub mod headerfooter;
+pub mod heatmap;
 pub mod import;
+pub mod imposition;
+pub mod lod;
+pub mod markdown;
+pub mod navigation;
+pub mod ocr;
+pub mod overview;
+pub mod pages;
+pub mod playback;
+pub mod print;
+pub mod rendercache;
 pub mod rendering;
+pub mod save;
 pub mod snapshot;
 pub mod strokecontent;
+pub mod viewports;
 pub mod visual_debug;
+pub mod watermark;
+pub mod zoomfit;
 
 // Re-exports
-pub use export::ExportPrefs;
-pub use import::ImportPrefs;
-pub use snapshot::EngineSnapshot;
+pub use accessibility::DescribedStroke;
+pub use anchors::{Bookmark, DeepLink, DocAnchor};
+pub use builder::DocumentBuilder;
+pub use colorhistory::ColorHistory;
+pub use export::{ExportPrefs, ExportPreset};
+pub use gestures::{Gesture, GestureAction, GestureConfig, GestureRecognizer, TouchPhase};
+pub use headerfooter::{HeaderFooter, HeaderFooterFields};
+pub use heatmap::HeatmapMode;
+pub use import::{ImportPrefs, PageFitMode, PasteMode, PastePlacement};
+pub use markdown::{MarkdownAssetFormat, MarkdownExport, MarkdownExportPrefs};
+pub use navigation::{NavigationDirection, NavigationPrefs};
+pub use overview::{Overview, OverviewViewport};
+pub use print::{PrintJob, PrintPrefs, PrintTarget, PrintTile};
+pub use rendercache::{RenderCachePrefs, RenderCacheUsage};
+pub use save::SavePrefs;
+pub use snapshot::{DeferredSnapshot, EngineSnapshot, RepairReport, ViewState};
 pub use strokecontent::StrokeContent;
+pub use viewports::{SecondaryViewport, ViewportKey};
+pub use watermark::{Watermark, WatermarkContent, WatermarkPages, WatermarkPosition};
+pub use zoomfit::ZoomFitTarget;
 
 // Imports
-use crate::document::Layout;
+use crate::camera::OverlayConfig;
+use crate::cameraanimation::CameraAnimation;
+use crate::document::{DocumentMetadata, Format, Layout};
+use crate::fillpatterns::FillPattern;
+use crate::pens::penbehaviour::PenProgress;
+use crate::pens::shortcuts::ShortcutAction;
 use crate::pens::{Pen, PenStyle};
 use crate::pens::{PenMode, PensConfig};
+use crate::spellcheck::SpellcheckProvider;
 use crate::store::render_comp::{self, RenderCompState};
 use crate::store::StrokeKey;
 use crate::strokes::strokebehaviour::GeneratedStrokeImages;
+use crate::strokes::textstroke::TextSearchPattern;
+use crate::strokes::Stroke;
 use crate::{render, AudioPlayer, WidgetFlags};
 use crate::{Camera, Document, PenHolder, StrokeStore};
 use futures::channel::{mpsc, oneshot};
@@ -27,7 +72,12 @@ use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::helpers::{AabbHelpers, SplitOrder};
 use rnote_compose::penevents::{PenEvent, ShortcutKey};
 use rnote_compose::shapes::ShapeBehaviour;
+use rnote_compose::transform::TransformBehaviour;
+use rnote_compose::Color;
 use serde::{Deserialize, Serialize};
+use slotmap::SlotMap;
+use std::collections::BTreeMap;
+use std::ops::Range;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
@@ -95,8 +145,32 @@ pub enum EngineTask {
         /// The generated images
         images: GeneratedStrokeImages,
     },
+    /// Replace a composite image of the level of detail rendering.
+    UpdateLodTile {
+        /// The index of the tile.
+        tile_index: lod::LodTileIndex,
+        /// The id of the render request, to discard outdated images.
+        request: u64,
+        /// The rendered image.
+        image: render::Image,
+    },
+    /// Replace the rendering of a stroke in a secondary viewport with the generated images.
+    UpdateViewportStrokeWithImages {
+        /// The key of the secondary viewport.
+        viewport_key: ViewportKey,
+        /// The stroke key.
+        key: StrokeKey,
+        /// The stroke the images were generated from.
+        stroke: Arc<Stroke>,
+        /// The generated images.
+        images: GeneratedStrokeImages,
+        /// The image scale-factor the render task was using while generating the images.
+        image_scale: f64,
+    },
     /// Requests that the typewriter cursor should be blinked/toggled
     BlinkTypewriterCursor,
+    /// Requests that the airbrush emits the spray for the elapsed time, while the pen is held down
+    EmitAirbrushSpray,
     /// Change the permanent zoom to the given value
     Zoom(f64),
     /// Indicates that the application is quitting. Sent to quit the handler which receives the tasks.
@@ -117,8 +191,26 @@ pub struct EngineConfig {
     import_prefs: ImportPrefs,
     #[serde(rename = "export_prefs")]
     export_prefs: ExportPrefs,
+    #[serde(rename = "export_presets")]
+    export_presets: Vec<ExportPreset>,
+    #[serde(rename = "export_watermark")]
+    export_watermark: Option<Watermark>,
+    #[serde(rename = "export_header_footer")]
+    export_header_footer: Option<HeaderFooter>,
+    #[serde(rename = "navigation_prefs")]
+    navigation_prefs: NavigationPrefs,
+    #[serde(rename = "overlay_config")]
+    overlay_config: OverlayConfig,
+    #[serde(rename = "gesture_config")]
+    gesture_config: GestureConfig,
+    #[serde(rename = "render_cache_prefs")]
+    render_cache_prefs: RenderCachePrefs,
+    #[serde(rename = "save_prefs")]
+    save_prefs: SavePrefs,
     #[serde(rename = "pen_sounds")]
     pen_sounds: bool,
+    #[serde(rename = "color_history")]
+    color_history: ColorHistory,
 }
 
 pub type EngineTaskSender = mpsc::UnboundedSender<EngineTask>;
@@ -143,13 +235,66 @@ pub struct RnoteEngine {
     pub import_prefs: ImportPrefs,
     #[serde(rename = "export_prefs")]
     pub export_prefs: ExportPrefs,
+    #[serde(rename = "export_presets")]
+    pub export_presets: Vec<ExportPreset>,
+    /// The watermark that is overlaid on document and pages exports.
+    #[serde(rename = "export_watermark")]
+    pub export_watermark: Option<Watermark>,
+    /// The header and footer that are placed on document and pages exports.
+    #[serde(rename = "export_header_footer")]
+    pub export_header_footer: Option<HeaderFooter>,
+    #[serde(rename = "navigation_prefs")]
+    pub navigation_prefs: NavigationPrefs,
+    #[serde(rename = "gesture_config")]
+    pub gesture_config: GestureConfig,
+    #[serde(rename = "render_cache_prefs")]
+    pub render_cache_prefs: RenderCachePrefs,
+    #[serde(rename = "save_prefs")]
+    pub save_prefs: SavePrefs,
     #[serde(rename = "pen_sounds")]
     pen_sounds: bool,
+    /// The recently used stroke colors across all documents.
+    #[serde(rename = "color_history")]
+    pub color_history: ColorHistory,
+    /// The recently used stroke colors in the current document.
+    #[serde(rename = "doc_color_history")]
+    pub doc_color_history: ColorHistory,
+    /// The metadata of the current document.
+    #[serde(rename = "doc_metadata")]
+    pub doc_metadata: DocumentMetadata,
+    /// The bookmarks of the current document, by their id.
+    #[serde(rename = "doc_bookmarks")]
+    pub doc_bookmarks: BTreeMap<String, Bookmark>,
+    /// The fill patterns of the current document.
+    #[serde(rename = "doc_fill_patterns")]
+    pub doc_fill_patterns: Vec<FillPattern>,
 
     #[serde(skip)]
     pub audioplayer: Option<AudioPlayer>,
     #[serde(skip)]
     pub visual_debug: bool,
+    /// The analysis overlay that is drawn on top of the strokes.
+    #[serde(skip)]
+    pub heatmap_mode: HeatmapMode,
+    // The stroke that has the keyboard focus
+    #[serde(skip)]
+    focused_stroke: Option<StrokeKey>,
+    #[serde(skip)]
+    gesture_recognizer: GestureRecognizer,
+    #[serde(skip)]
+    camera_animation: Option<CameraAnimation>,
+    #[serde(skip)]
+    secondary_viewports: SlotMap<ViewportKey, SecondaryViewport>,
+    #[serde(skip)]
+    overview_cache: Option<overview::OverviewCache>,
+    #[serde(skip)]
+    lod_cache: lod::LodCache,
+    // When the memory budget of the render caches was last enforced
+    #[serde(skip)]
+    render_cache_checked: Option<Instant>,
+    // The original bounds of the last content pasted with cascaded placement and how often it was pasted
+    #[serde(skip)]
+    paste_cascade: Option<(Aabb, usize)>,
     // the task sender. Must not be modified, only cloned.
     #[serde(skip)]
     pub tasks_tx: EngineTaskSender,
@@ -175,10 +320,31 @@ impl Default for RnoteEngine {
 
             import_prefs: ImportPrefs::default(),
             export_prefs: ExportPrefs::default(),
+            export_presets: Vec::default(),
+            export_watermark: None,
+            export_header_footer: None,
+            navigation_prefs: NavigationPrefs::default(),
+            gesture_config: GestureConfig::default(),
+            render_cache_prefs: RenderCachePrefs::default(),
+            save_prefs: SavePrefs::default(),
             pen_sounds: false,
+            color_history: ColorHistory::default(),
+            doc_color_history: ColorHistory::default(),
+            doc_metadata: DocumentMetadata::default(),
+            doc_bookmarks: BTreeMap::default(),
+            doc_fill_patterns: Vec::default(),
 
             audioplayer: None,
             visual_debug: false,
+            heatmap_mode: HeatmapMode::default(),
+            focused_stroke: None,
+            gesture_recognizer: GestureRecognizer::default(),
+            camera_animation: None,
+            secondary_viewports: SlotMap::with_key(),
+            overview_cache: None,
+            lod_cache: lod::LodCache::default(),
+            render_cache_checked: None,
+            paste_cascade: None,
             tasks_tx,
             tasks_rx: Some(tasks_rx),
             background_tile_image: None,
@@ -219,6 +385,28 @@ impl RnoteEngine {
         self.pen_sounds
     }
 
+    /// Enable or disable the ruler drawing aid. When it gets enabled, it is placed in the center of the viewport.
+    pub fn set_ruler_enabled(&mut self, enabled: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let viewport = self.camera.viewport();
+        self.pens_config
+            .drawing_aids
+            .set_ruler_enabled(enabled, viewport);
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Enable or disable the compass drawing aid. When it gets enabled, it is placed in the center of the viewport.
+    pub fn set_compass_enabled(&mut self, enabled: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let viewport = self.camera.viewport();
+        self.pens_config
+            .drawing_aids
+            .set_compass_enabled(enabled, viewport);
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
     /// Enables/disables the pen sounds.
     ///
     /// If pen sound should be enabled, the pkg data dir must be provided.
@@ -245,25 +433,63 @@ impl RnoteEngine {
 
     /// Takes a snapshot of the current state.
     pub fn take_snapshot(&self) -> EngineSnapshot {
-        let mut store_history_entry = self.store.create_history_entry();
+        self.take_snapshot_deferred().finish()
+    }
 
-        // Remove all trashed strokes
-        let trashed_keys = store_history_entry
-            .trash_components
-            .iter()
-            .filter_map(|(key, trash_comp)| if trash_comp.trashed { Some(key) } else { None })
-            .collect::<Vec<StrokeKey>>();
+    /// Take a snapshot of the current state that still contains the trashed strokes.
+    ///
+    /// It shares the components with the store, so it is cheap to take.
+    /// Should be finished on a worker thread, which is where the components are copied if needed.
+    pub fn take_snapshot_deferred(&self) -> DeferredSnapshot {
+        let store_history_entry = self.store.create_history_entry();
+
+        DeferredSnapshot {
+            snapshot: EngineSnapshot {
+                document: self.document,
+                stroke_components: store_history_entry.stroke_components,
+                chrono_components: store_history_entry.chrono_components,
+                instance_components: store_history_entry.instance_components,
+                chrono_counter: store_history_entry.chrono_counter,
+                embedded_fonts: vec![],
+                view_state: Some(self.view_state()),
+                color_history: self.doc_color_history.clone(),
+                metadata: self.doc_metadata.clone(),
+                bookmarks: self.doc_bookmarks.clone(),
+                fill_patterns: self.doc_fill_patterns.clone(),
+            },
+            trash_components: store_history_entry.trash_components,
+        }
+    }
 
-        for key in trashed_keys {
-            Arc::make_mut(&mut store_history_entry.stroke_components).remove(key);
+    /// The current state of the view onto the document.
+    pub fn view_state(&self) -> ViewState {
+        let pen_style = self.penholder.current_pen_style();
+        ViewState {
+            viewport_center: self.camera.viewport_center(),
+            zoom: self.camera.zoom(),
+            rotation: self.camera.rotation(),
+            pen_style,
+            pen_preset: self.pens_config.current_preset(pen_style),
         }
+    }
 
-        EngineSnapshot {
-            document: self.document,
-            stroke_components: Arc::clone(&store_history_entry.stroke_components),
-            chrono_components: Arc::clone(&store_history_entry.chrono_components),
-            chrono_counter: store_history_entry.chrono_counter,
+    /// Restore a view state, e.g. taken from a loaded snapshot.
+    ///
+    /// The camera size needs to be set beforehand, so that the viewport can be centered correctly.
+    pub fn restore_view_state(&mut self, view_state: &ViewState) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if let Some(i) = view_state.pen_preset {
+            self.pens_config.apply_preset(view_state.pen_style, i);
         }
+        widget_flags.merge(self.change_pen_style(view_state.pen_style));
+        widget_flags.merge(self.camera.rotate_to(view_state.rotation));
+        widget_flags.merge(self.camera_animate_to(
+            view_state.zoom,
+            view_state.viewport_center,
+            false,
+        ));
+        widget_flags.refresh_ui = true;
+        widget_flags
     }
 
     /// Imports an engine snapshot. A save file should always be loaded with this method.
@@ -273,12 +499,108 @@ impl RnoteEngine {
         let mut widget_flags = WidgetFlags::default();
 
         self.document = snapshot.document;
+        self.doc_color_history = snapshot.color_history;
+        self.doc_metadata = snapshot.metadata;
+        self.doc_bookmarks = snapshot.bookmarks;
+        crate::fillpatterns::register_fill_patterns(&snapshot.fill_patterns);
+        self.doc_fill_patterns = snapshot.fill_patterns;
+        self.focused_stroke = None;
+        crate::fonts::register_embedded_fonts(&snapshot.embedded_fonts);
         widget_flags.merge(self.store.import_from_snapshot(&snapshot));
         widget_flags.merge(self.current_pen_update_state());
 
         widget_flags
     }
 
+    /// Appends the content of an engine snapshot to the current document.
+    ///
+    /// In fixed-size and continuous-vertical layouts the content is placed page-wise below the current content,
+    /// in the infinite layouts it is placed side-by-side to the right, aligned to the format.
+    /// The strokes are inserted with new keys, keeping their chronological order.
+    /// The format and background of the current document are kept for the appended content.
+    ///
+    /// The store then needs to update its rendering.
+    pub fn append_snapshot(&mut self, snapshot: EngineSnapshot) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let format_size = na::vector![self.document.format.width, self.document.format.height];
+        if format_size[0] <= 0.0 || format_size[1] <= 0.0 {
+            log::error!("Appending snapshot failed, document format has an invalid size.");
+            return widget_flags;
+        }
+        let align_down = |v: na::Vector2<f64>| {
+            na::vector![
+                (v[0] / format_size[0]).floor() * format_size[0],
+                (v[1] / format_size[1]).floor() * format_size[1]
+            ]
+        };
+        let align_up = |v: f64, size: f64| (v / size).ceil() * size;
+        crate::fonts::register_embedded_fonts(&snapshot.embedded_fonts);
+
+        let mut snapshot_keys = snapshot
+            .stroke_components
+            .keys()
+            .collect::<Vec<StrokeKey>>();
+        snapshot_keys.sort_unstable_by_key(|&key| snapshot.chrono_components.get(key).cloned());
+        let Some(snapshot_content_bounds) = snapshot_keys
+            .iter()
+            .filter_map(|&key| snapshot.stroke_components.get(key).map(|s| s.bounds()))
+            .reduce(|acc, b| acc.merged(&b))
+        else {
+            // Nothing to append
+            return widget_flags;
+        };
+        let snapshot_origin = align_down(snapshot_content_bounds.mins.coords);
+        let content_bounds = self
+            .store
+            .bounds_for_strokes(&self.store.stroke_keys_as_rendered());
+
+        let target_origin = match self.document.layout {
+            Layout::FixedSize => na::vector![0.0, self.document.y + self.document.height],
+            Layout::ContinuousVertical => na::vector![
+                0.0,
+                content_bounds
+                    .map(|b| align_up(b.maxs[1], format_size[1]))
+                    .unwrap_or(0.0)
+            ],
+            Layout::SemiInfinite | Layout::Infinite => match content_bounds {
+                Some(content_bounds) => na::vector![
+                    align_up(content_bounds.maxs[0], format_size[0]),
+                    align_down(content_bounds.mins.coords)[1]
+                ],
+                None => na::vector![0.0, 0.0],
+            },
+        };
+        let offset = target_origin - snapshot_origin;
+
+        for key in snapshot_keys {
+            let (Some(stroke), Some(chrono_comp)) = (
+                snapshot.stroke_components.get(key),
+                snapshot.chrono_components.get(key),
+            ) else {
+                continue;
+            };
+            let mut stroke = (**stroke).clone();
+            stroke.translate(offset);
+            self.store.insert_stroke(stroke, Some(chrono_comp.layer));
+        }
+
+        if self.document.layout == Layout::FixedSize {
+            let snapshot_content_height = align_up(
+                snapshot_content_bounds.maxs[1] - snapshot_origin[1],
+                format_size[1],
+            );
+            self.document.height += snapshot_content_height;
+        }
+        widget_flags.merge(self.doc_resize_autoexpand());
+        widget_flags.merge(self.current_pen_update_state());
+        widget_flags.merge(self.store.record(Instant::now()));
+        widget_flags.redraw = true;
+        widget_flags.resize = true;
+        widget_flags.store_modified = true;
+
+        widget_flags
+    }
+
     /// Records the current store state and saves it as a history entry.
     pub fn record(&mut self, now: Instant) -> WidgetFlags {
         self.store.record(now)
@@ -294,7 +616,9 @@ impl RnoteEngine {
         let mut widget_flags = WidgetFlags::default();
 
         widget_flags.merge(self.store.undo(now));
+        widget_flags.merge(self.doc_restore_recorded_height());
         widget_flags.merge(self.doc_resize_autoexpand());
+        widget_flags.merge(self.doc_expand_fixed_size_to_fit_strokes());
         widget_flags.merge(self.current_pen_update_state());
         self.update_rendering_current_viewport();
         widget_flags.redraw = true;
@@ -307,7 +631,9 @@ impl RnoteEngine {
         let mut widget_flags = WidgetFlags::default();
 
         widget_flags.merge(self.store.redo(now));
+        widget_flags.merge(self.doc_restore_recorded_height());
         widget_flags.merge(self.doc_resize_autoexpand());
+        widget_flags.merge(self.doc_expand_fixed_size_to_fit_strokes());
         widget_flags.merge(self.current_pen_update_state());
         self.update_rendering_current_viewport();
         widget_flags.redraw = true;
@@ -328,6 +654,7 @@ impl RnoteEngine {
         let mut widget_flags = WidgetFlags::default();
 
         widget_flags.merge(self.store.clear());
+        self.focused_stroke = None;
         widget_flags.merge(self.current_pen_update_state());
 
         widget_flags
@@ -367,7 +694,9 @@ impl RnoteEngine {
             } => {
                 if let Some(state) = self.store.render_comp_state(key) {
                     match state {
-                        RenderCompState::Complete | RenderCompState::ForViewport(_) => {
+                        RenderCompState::Complete
+                        | RenderCompState::ForViewport(_)
+                        | RenderCompState::Downgraded(_) => {
                             // The rendering was already regenerated in the meantime,
                             // so we just discard the the render task result
                         }
@@ -387,6 +716,7 @@ impl RnoteEngine {
                                 // to when the render task was started,
                                 // the new images are considered valid and can replace the old.
                                 self.store.replace_rendering_with_images(key, images);
+                                widget_flags.merge(self.enforce_render_cache_budget_throttled());
                             }
                             widget_flags.redraw = true;
                         }
@@ -407,6 +737,44 @@ impl RnoteEngine {
                     widget_flags.redraw = true;
                 }
             }
+            EngineTask::EmitAirbrushSpray => {
+                if let Pen::Brush(brush) = self.penholder.current_pen_mut() {
+                    widget_flags.merge(brush.emit_airbrush_spray(
+                        Instant::now(),
+                        &mut EngineViewMut {
+                            tasks_tx: self.tasks_tx.clone(),
+                            pens_config: &mut self.pens_config,
+                            doc: &mut self.document,
+                            store: &mut self.store,
+                            camera: &mut self.camera,
+                            audioplayer: &mut self.audioplayer,
+                        },
+                    ));
+                }
+            }
+            EngineTask::UpdateViewportStrokeWithImages {
+                viewport_key,
+                key,
+                stroke,
+                images,
+                image_scale,
+            } => {
+                widget_flags.merge(self.viewport_handle_rendered_images(
+                    viewport_key,
+                    key,
+                    stroke,
+                    images,
+                    image_scale,
+                ));
+            }
+            EngineTask::UpdateLodTile {
+                tile_index,
+                request,
+                image,
+            } => {
+                widget_flags.merge(self.lod_handle_rendered_tile(tile_index, request, image));
+                widget_flags.merge(self.enforce_render_cache_budget_throttled());
+            }
             EngineTask::Zoom(zoom) => {
                 widget_flags.merge(self.camera.zoom_temporarily_to(1.0));
                 widget_flags.merge(self.camera.zoom_to(zoom));
@@ -434,7 +802,8 @@ impl RnoteEngine {
         pen_mode: Option<PenMode>,
         now: Instant,
     ) -> WidgetFlags {
-        self.penholder.handle_pen_event(
+        let pen_style = self.penholder.current_pen_style_w_override();
+        let widget_flags = self.penholder.handle_pen_event(
             event,
             pen_mode,
             now,
@@ -446,7 +815,54 @@ impl RnoteEngine {
                 camera: &mut self.camera,
                 audioplayer: &mut self.audioplayer,
             },
-        )
+        );
+        // The color of a finished stroke counts as used
+        if widget_flags.store_modified
+            && self.penholder.current_pen_progress() == PenProgress::Finished
+        {
+            if let Some(color) = self.pens_config.stroke_color(pen_style) {
+                self.record_used_color(color);
+            }
+        }
+        widget_flags
+    }
+
+    /// Mark the color as the most recently used one, in the document and the global color history.
+    pub fn record_used_color(&mut self, color: Color) {
+        self.color_history.push(color);
+        self.doc_color_history.push(color);
+    }
+
+    /// Set a custom metadata field of the current document. An empty value removes the field.
+    pub fn set_doc_metadata_field(&mut self, key: &str, value: &str) {
+        let key = key.trim();
+        if key.is_empty() {
+            return;
+        }
+        if value.is_empty() {
+            self.doc_metadata.custom.remove(key);
+        } else {
+            self.doc_metadata
+                .custom
+                .insert(key.to_string(), value.to_string());
+        }
+    }
+
+    /// The recently used colors, most recent first.
+    ///
+    /// The colors used in the current document come first, followed by the remaining colors from the global history.
+    pub fn recent_colors(&self) -> Vec<Color> {
+        let mut colors = self.doc_color_history.colors();
+        for color in self.color_history.colors() {
+            if !colors
+                .iter()
+                .any(|other| u32::from(*other) == u32::from(color))
+            {
+                colors.push(color);
+            }
+        }
+        colors.truncate(ColorHistory::LEN_MAX);
+        colors
     }
 
     /// Handle a pressed shortcut key.
@@ -455,6 +871,11 @@ impl RnoteEngine {
         shortcut_key: ShortcutKey,
         now: Instant,
     ) -> WidgetFlags {
+        match self.penholder.get_shortcut_action(shortcut_key) {
+            Some(ShortcutAction::Undo) => return self.undo(now),
+            Some(ShortcutAction::Redo) => return self.redo(now),
+            _ => {}
+        }
         self.penholder.handle_pressed_shortcut_key(
             shortcut_key,
             now,
@@ -469,6 +890,30 @@ impl RnoteEngine {
         )
     }
 
+    /// Save the config of the current pen as new preset.
+    ///
+    /// Returns the index of the saved preset, or None when the pen has no presets or the maximum number is reached.
+    pub fn save_pen_preset(&mut self) -> Option<usize> {
+        self.pens_config
+            .save_preset(self.penholder.current_pen_style_w_override())
+    }
+
+    /// Apply the next saved preset of the current pen.
+    pub fn cycle_pen_preset(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if self
+            .pens_config
+            .cycle_preset(self.penholder.current_pen_style_w_override())
+            .is_none()
+        {
+            return widget_flags;
+        }
+        widget_flags.merge(self.current_pen_update_state());
+        widget_flags.refresh_ui = true;
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
     /// Change the pen style.
     pub fn change_pen_style(&mut self, new_style: PenStyle) -> WidgetFlags {
         self.penholder.change_style(
@@ -484,6 +929,21 @@ impl RnoteEngine {
         )
     }
 
+    /// Select the registered pen plugin with the given id and change to the plugin pen style.
+    pub fn change_pen_plugin(&mut self, id: String) -> WidgetFlags {
+        self.penholder.change_pen_plugin(
+            id,
+            &mut EngineViewMut {
+                tasks_tx: self.tasks_tx(),
+                pens_config: &mut self.pens_config,
+                doc: &mut self.document,
+                store: &mut self.store,
+                camera: &mut self.camera,
+                audioplayer: &mut self.audioplayer,
+            },
+        )
+    }
+
     /// Change the pen style (temporary) override.
     pub fn change_pen_style_override(
         &mut self,
@@ -620,6 +1080,52 @@ impl RnoteEngine {
         self.document.expand_autoexpand(&self.camera)
     }
 
+    /// Re-anchor the origin when in infinite layout, so that the content that grew above or left of the origin
+    /// starts at the origin page again.
+    ///
+    /// The content is moved by whole pages, so the page boundaries stay the same relative to the content.
+    /// The movement is accumulated in the document's origin offset and the camera follows along,
+    /// so the view does not jump.
+    /// Background and strokes rendering then need to be updated.
+    pub fn doc_reanchor_origin(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if self.document.layout != Layout::Infinite
+            || self.document.format.width <= 0.0
+            || self.document.format.height <= 0.0
+        {
+            return widget_flags;
+        }
+        let keys = self.store.stroke_keys_as_rendered();
+        let Some(content_bounds) = self.store.bounds_for_strokes(&keys) else {
+            return widget_flags;
+        };
+        let format_size = na::vector![self.document.format.width, self.document.format.height];
+        let offset = na::vector![
+            (-content_bounds.mins[0] / format_size[0]).ceil().max(0.0) * format_size[0],
+            (-content_bounds.mins[1] / format_size[1]).ceil().max(0.0) * format_size[1]
+        ];
+        if offset == na::Vector2::zeros() {
+            return widget_flags;
+        }
+
+        let all_keys = self.store.stroke_keys_unordered();
+        self.store.translate_strokes(&all_keys, offset);
+        self.store.translate_strokes_images(&all_keys, offset);
+        self.store.update_geometry_for_strokes(&all_keys);
+        self.document.origin_offset += offset;
+        self.document.x += offset[0];
+        self.document.y += offset[1];
+        let viewport_center = self.camera.viewport_center();
+        widget_flags.merge(self.camera.set_viewport_center(viewport_center + offset));
+        widget_flags.merge(self.doc_resize_autoexpand());
+        widget_flags.merge(self.current_pen_update_state());
+        widget_flags.merge(self.record(Instant::now()));
+
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
     /// Add a page to the document when in fixed size layout.
     ///
     /// Returns true when document is in fixed size layout and a pages was added,
@@ -662,6 +1168,401 @@ impl RnoteEngine {
         true
     }
 
+    /// Crop the document to the bounds of its content, resulting in a single page.
+    ///
+    /// Background and strokes rendering then need to be updated.
+    pub fn doc_crop_to_content(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let keys = self.store.stroke_keys_as_rendered();
+        let Some(content_bounds) = self.store.bounds_for_strokes(&keys) else {
+            return widget_flags;
+        };
+        let offset = -content_bounds.mins.coords;
+
+        self.store.translate_strokes(&keys, offset);
+        self.store.translate_strokes_images(&keys, offset);
+        self.document.x = 0.0;
+        self.document.y = 0.0;
+        self.document.width = content_bounds.extents()[0];
+        self.document.height = content_bounds.extents()[1];
+        self.document.format.width = self
+            .document
+            .width
+            .clamp(Format::WIDTH_MIN, Format::WIDTH_MAX);
+        self.document.format.height = self
+            .document
+            .height
+            .clamp(Format::HEIGHT_MIN, Format::HEIGHT_MAX);
+        self.document.layout = Layout::FixedSize;
+
+        widget_flags.resize = true;
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags.refresh_ui = true;
+        widget_flags
+    }
+
+    /// Scale the document together with its content and the stroke widths by the factor.
+    ///
+    /// Background and strokes rendering then need to be updated.
+    pub fn doc_scale(&mut self, factor: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let keys = self.store.stroke_keys_as_rendered();
+        let scale = na::Vector2::new(factor, factor);
+        let pivot = na::vector![self.document.x, self.document.y];
+
+        self.store.scale_strokes_with_pivot(&keys, scale, pivot);
+        self.store
+            .scale_strokes_images_with_pivot(&keys, scale, pivot);
+        self.store.scale_stroke_widths(&keys, factor);
+        self.store.update_geometry_for_strokes(&keys);
+        self.document.width *= factor;
+        self.document.height *= factor;
+        self.document.format.width =
+            (self.document.format.width * factor).clamp(Format::WIDTH_MIN, Format::WIDTH_MAX);
+        self.document.format.height =
+            (self.document.format.height * factor).clamp(Format::HEIGHT_MIN, Format::HEIGHT_MAX);
+
+        widget_flags.resize = true;
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags.refresh_ui = true;
+        widget_flags
+    }
+
+    /// Rotate the document together with its content by the angle (in radians) around its center.
+    ///
+    /// The document and its format are changed to the bounds of the rotated document and format,
+    /// so rotating by multiples of 90 degrees swaps their width and height.
+    ///
+    /// Background and strokes rendering then need to be updated.
+    pub fn doc_rotate(&mut self, angle: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let keys = self.store.stroke_keys_as_rendered();
+        let doc_bounds = self.document.bounds();
+        let center = doc_bounds.center();
+        let rotation = na::Isometry2::rotation_wrt_point(na::UnitComplex::new(angle), center);
+        let rotated_doc_bounds = doc_bounds.transform_by(&rotation);
+        let rotated_format_bounds = Aabb::new(
+            na::point![0.0, 0.0],
+            na::point![self.document.format.width, self.document.format.height],
+        )
+        .transform_by(&na::Isometry2::rotation(angle));
+        // Move the content back to the document origin
+        let offset = doc_bounds.mins.coords - rotated_doc_bounds.mins.coords;
+
+        self.store.rotate_strokes(&keys, angle, center);
+        self.store.rotate_strokes_images(&keys, angle, center);
+        self.store.translate_strokes(&keys, offset);
+        self.store.translate_strokes_images(&keys, offset);
+        self.document.width = rotated_doc_bounds.extents()[0];
+        self.document.height = rotated_doc_bounds.extents()[1];
+        self.document.format.width =
+            rotated_format_bounds.extents()[0].clamp(Format::WIDTH_MIN, Format::WIDTH_MAX);
+        self.document.format.height =
+            rotated_format_bounds.extents()[1].clamp(Format::HEIGHT_MIN, Format::HEIGHT_MAX);
+
+        widget_flags.resize = true;
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags.refresh_ui = true;
+        widget_flags
+    }
+
+    /// Replace all stroke, fill and text colors in the document that are equal to `from` with `to`.
+    ///
+    /// Colors are compared with 8 bit precision. Strokes rendering then needs to be updated.
+    pub fn doc_replace_color(&mut self, from: Color, to: Color) -> WidgetFlags {
+        let keys = self.store.stroke_keys_as_rendered();
+        self.store.replace_colors(&keys, from, to)
+    }
+
+    /// Erase all strokes that are fully inside the bounds of the current selection, including the selected ones.
+    pub fn erase_in_selection_bounds(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let Some(selection_bounds) = self.store.selection_bounds() else {
+            return widget_flags;
+        };
+        let (trashed, flags) = self.store.trash_strokes_in_bounds(selection_bounds);
+        if trashed.is_empty() {
+            return widget_flags;
+        }
+        widget_flags.merge(flags);
+        widget_flags.merge(self.current_pen_update_state());
+        widget_flags.merge(self.doc_resize_autoexpand());
+        widget_flags.merge(self.record(Instant::now()));
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Erase all strokes in the document that have a stroke, fill or text color equal to `color`.
+    ///
+    /// When `pen_style` is set, only the strokes drawn by this pen are erased.
+    pub fn erase_by_color(&mut self, color: Color, pen_style: Option<PenStyle>) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let keys = self.store.stroke_keys_as_rendered();
+        let (trashed, flags) = self.store.trash_strokes_w_color(&keys, color, pen_style);
+        if trashed.is_empty() {
+            return widget_flags;
+        }
+        widget_flags.merge(flags);
+        widget_flags.merge(self.current_pen_update_state());
+        widget_flags.merge(self.doc_resize_autoexpand());
+        widget_flags.merge(self.record(Instant::now()));
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Smooth and simplify the selected brush strokes with the strength, ranging from 0.0 to 1.0.
+    ///
+    /// Strokes rendering then needs to be updated.
+    pub fn smooth_selection(&mut self, strength: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let keys = self.store.selection_keys_as_rendered();
+        let smoothed = self.store.smooth_brushstrokes(&keys, strength);
+        if smoothed.is_empty() {
+            return widget_flags;
+        }
+        self.store.update_geometry_for_strokes(&smoothed);
+        widget_flags.merge(self.current_pen_update_state());
+        widget_flags.merge(self.doc_resize_autoexpand());
+        widget_flags.merge(self.record(Instant::now()));
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
+    /// Add the fill pattern to the current document, so that it is stored in its file. Returns its id.
+    ///
+    /// Adding a pattern that is already part of the document does nothing.
+    pub fn add_doc_fill_pattern(&mut self, fill_pattern: FillPattern) -> String {
+        crate::fillpatterns::register_fill_patterns([&fill_pattern]);
+        let id = fill_pattern.id.clone();
+        if !self.doc_fill_patterns.iter().any(|p| p.id == id) {
+            self.doc_fill_patterns.push(fill_pattern);
+        }
+        id
+    }
+
+    /// Remove the fill pattern from the current document.
+    ///
+    /// Shapes that are still filled with it keep it stored in the file. Returns the removed pattern.
+    pub fn remove_doc_fill_pattern(&mut self, id: &str) -> Option<FillPattern> {
+        let i = self.doc_fill_patterns.iter().position(|p| p.id == id)?;
+        Some(self.doc_fill_patterns.remove(i))
+    }
+
+    /// Fill the selected shapes with the fill pattern, or remove their fill pattern when `None` is given.
+    ///
+    /// Strokes rendering then needs to be updated.
+    pub fn set_selection_fill_pattern(&mut self, id: Option<String>) -> WidgetFlags {
+        let keys = self.store.selection_keys_as_rendered();
+        let mut widget_flags = self.store.change_fill_patterns(&keys, id);
+        if widget_flags.store_modified {
+            widget_flags.merge(self.record(Instant::now()));
+        }
+        widget_flags
+    }
+
+    /// Find the matches of the pattern in all text strokes and image text layers of the document.
+    ///
+    /// Returns the keys of the strokes that contain matches, together with the byte ranges of the matches.
+    /// The bounds of matches in images can be retrieved with [RnoteEngine::text_layer_match_bounds()].
+    pub fn text_find(&self, pattern: &TextSearchPattern) -> Vec<(StrokeKey, Vec<Range<usize>>)> {
+        let keys = self.store.stroke_keys_as_rendered();
+        self.store.find_text(&keys, pattern)
+    }
+
+    /// The bounds on the document of the matches in the text layer of the image, as returned by [RnoteEngine::text_find()].
+    pub fn text_layer_match_bounds(&self, key: StrokeKey, ranges: &[Range<usize>]) -> Vec<Aabb> {
+        self.store.text_layer_ranges_bounds(key, ranges)
+    }
+
+    /// Replace the matches of the pattern in all text strokes of the document, as a single undoable change.
+    ///
+    /// Returns the keys of the modified text strokes. Strokes rendering then needs to be updated.
+    pub fn text_replace_all(
+        &mut self,
+        pattern: &TextSearchPattern,
+        replacement: &str,
+    ) -> (Vec<StrokeKey>, WidgetFlags) {
+        let keys = self.store.stroke_keys_as_rendered();
+        let (modified_keys, mut widget_flags) =
+            self.store.replace_text(&keys, pattern, replacement);
+        if !modified_keys.is_empty() {
+            widget_flags.merge(self.store.record(Instant::now()));
+            widget_flags.merge(self.doc_resize_autoexpand());
+            // The typewriter cursors might be invalid now
+            widget_flags.merge(self.current_pen_update_state());
+        }
+        (modified_keys, widget_flags)
+    }
+
+    /// The byte ranges of the misspelled words in the text stroke.
+    pub fn text_misspelled_ranges(
+        &self,
+        key: StrokeKey,
+        provider: &dyn SpellcheckProvider,
+    ) -> Vec<Range<usize>> {
+        match self.store.get_stroke_ref(key) {
+            Some(Stroke::TextStroke(textstroke)) => provider.misspelled_ranges(&textstroke.text),
+            _ => vec![],
+        }
+    }
+
+    /// Spellcheck the text stroke that is currently modified by the typewriter, so that misspelled words get underlined.
+    ///
+    /// Needs to be called again when the text has changed.
+    pub fn typewriter_spellcheck(&mut self, provider: &dyn SpellcheckProvider) -> WidgetFlags {
+        let Pen::Typewriter(typewriter) = self.penholder.current_pen_mut() else {
+            return WidgetFlags::default();
+        };
+        let misspelled_ranges = typewriter.modifying_stroke_key().and_then(|key| {
+            match self.store.get_stroke_ref(key) {
+                Some(Stroke::TextStroke(textstroke)) => {
+                    Some((key, provider.misspelled_ranges(&textstroke.text)))
+                }
+                _ => None,
+            }
+        });
+        typewriter.set_misspelled_ranges(misspelled_ranges)
+    }
+
+    /// Set the preedit text of an input method in the typewriter, which is shown inline while composing.
+    ///
+    /// `cursor` and the `emphasized_ranges` are byte positions relative to the preedit text, an empty text removes the preedit.
+    pub fn typewriter_set_preedit(
+        &mut self,
+        text: String,
+        cursor: usize,
+        emphasized_ranges: Vec<Range<usize>>,
+    ) -> WidgetFlags {
+        let Pen::Typewriter(typewriter) = self.penholder.current_pen_mut() else {
+            return WidgetFlags::default();
+        };
+        typewriter.set_preedit(
+            text,
+            cursor,
+            emphasized_ranges,
+            &mut EngineViewMut {
+                tasks_tx: self.tasks_tx.clone(),
+                pens_config: &mut self.pens_config,
+                doc: &mut self.document,
+                store: &mut self.store,
+                camera: &mut self.camera,
+                audioplayer: &mut self.audioplayer,
+            },
+        )
+    }
+
+    /// Remove the preedit text of an input method from the typewriter, if there is one.
+    pub fn typewriter_cancel_preedit(&mut self) -> WidgetFlags {
+        let Pen::Typewriter(typewriter) = self.penholder.current_pen_mut() else {
+            return WidgetFlags::default();
+        };
+        typewriter.cancel_preedit(&mut EngineViewMut {
+            tasks_tx: self.tasks_tx.clone(),
+            pens_config: &mut self.pens_config,
+            doc: &mut self.document,
+            store: &mut self.store,
+            camera: &mut self.camera,
+            audioplayer: &mut self.audioplayer,
+        })
+    }
+
+    /// Replace a misspelled word in the text stroke with the suggestion, as a single undoable change.
+    ///
+    /// Strokes rendering then needs to be updated.
+    pub fn text_apply_spellcheck_suggestion(
+        &mut self,
+        key: StrokeKey,
+        range: Range<usize>,
+        suggestion: &str,
+    ) -> anyhow::Result<WidgetFlags> {
+        let mut widget_flags = WidgetFlags::default();
+        let Some(Stroke::TextStroke(textstroke)) = self.store.get_stroke_mut(key) else {
+            return Err(anyhow::anyhow!(
+                "applying spellcheck suggestion failed, stroke for key {key:?} is not a text stroke"
+            ));
+        };
+        textstroke.replace_text_range(range, suggestion)?;
+        self.store.update_geometry_for_stroke(key);
+        self.store.set_rendering_dirty(key);
+
+        widget_flags.merge(self.store.record(Instant::now()));
+        widget_flags.merge(self.doc_resize_autoexpand());
+        // The typewriter cursors might be invalid now
+        widget_flags.merge(self.current_pen_update_state());
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        Ok(widget_flags)
+    }
+
+    /// Apply the cleanup filters to the bitmap image stroke, for photographed documents and whiteboards.
+    ///
+    /// The de-skew corners are expected in the pixel coordinates of the image,
+    /// see [BitmapImage::doc_coords_to_pixel_coords](crate::strokes::BitmapImage::doc_coords_to_pixel_coords).
+    /// Strokes rendering then needs to be updated.
+    pub fn bitmapimage_cleanup(
+        &mut self,
+        key: StrokeKey,
+        cleanup: render::ImageCleanup,
+    ) -> anyhow::Result<WidgetFlags> {
+        let mut widget_flags = self
+            .store
+            .modify_bitmapimage(key, |bitmapimage| bitmapimage.apply_cleanup(&cleanup))?;
+        widget_flags.merge(self.store.record(Instant::now()));
+        widget_flags.resize = true;
+        Ok(widget_flags)
+    }
+
+    /// Crop the bitmap image stroke to the bounds, given in the pixel coordinates of the image.
+    ///
+    /// The pixel data outside of the bounds is discarded. Strokes rendering then needs to be updated.
+    pub fn bitmapimage_crop(
+        &mut self,
+        key: StrokeKey,
+        pixel_bounds: Aabb,
+    ) -> anyhow::Result<WidgetFlags> {
+        let mut widget_flags = self
+            .store
+            .modify_bitmapimage(key, |bitmapimage| bitmapimage.crop(pixel_bounds))?;
+        widget_flags.merge(self.store.record(Instant::now()));
+        widget_flags.resize = true;
+        Ok(widget_flags)
+    }
+
+    /// Rotate the pixel data of the bitmap image stroke by 90 degrees.
+    ///
+    /// Strokes rendering then needs to be updated.
+    pub fn bitmapimage_rotate_90(
+        &mut self,
+        key: StrokeKey,
+        clockwise: bool,
+    ) -> anyhow::Result<WidgetFlags> {
+        let mut widget_flags = self
+            .store
+            .modify_bitmapimage(key, |bitmapimage| bitmapimage.rotate_90(clockwise))?;
+        widget_flags.merge(self.store.record(Instant::now()));
+        widget_flags.resize = true;
+        Ok(widget_flags)
+    }
+
+    /// Mirror the pixel data of the bitmap image stroke horizontally or vertically.
+    ///
+    /// Strokes rendering then needs to be updated.
+    pub fn bitmapimage_flip(
+        &mut self,
+        key: StrokeKey,
+        horizontal: bool,
+    ) -> anyhow::Result<WidgetFlags> {
+        let mut widget_flags = self
+            .store
+            .modify_bitmapimage(key, |bitmapimage| bitmapimage.flip(horizontal))?;
+        widget_flags.merge(self.store.record(Instant::now()));
+        Ok(widget_flags)
+    }
+
     /// Update the viewport offset of the camera, clamped to mins and maxs values depending on the document layout.
     ///
     /// Background and strokes rendering then need to be updated.
@@ -669,6 +1570,111 @@ impl RnoteEngine {
         self.camera.set_offset(offset, &self.document)
     }
 
+    /// Move the camera to the given total zoom and viewport center.
+    ///
+    /// When `animate` is true and camera animations are enabled in the navigation prefs, the transition is tweened
+    /// and the returned widget flags request the UI to drive the animation with `camera_animation_tick()`.
+    /// Else the target is applied immediately, e.g. when there is no UI.
+    pub fn camera_animate_to(
+        &mut self,
+        zoom: f64,
+        center: na::Vector2<f64>,
+        animate: bool,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let zoom = zoom.clamp(Camera::ZOOM_MIN, Camera::ZOOM_MAX);
+
+        if !animate || !self.navigation_prefs.animate_camera {
+            self.camera_animation = None;
+            return self.camera_apply_zoom_and_center(zoom, center);
+        }
+        self.camera_animation = Some(CameraAnimation::new(
+            (self.camera.total_zoom(), self.camera.viewport_center()),
+            (zoom, center),
+            Instant::now(),
+        ));
+        widget_flags.animate_camera = true;
+        widget_flags
+    }
+
+    /// Whether a camera animation is currently running.
+    pub fn camera_animation_running(&self) -> bool {
+        self.camera_animation.is_some()
+    }
+
+    /// Advance the running camera animation to the given time.
+    ///
+    /// Returns the widget flags and whether the animation is still running.
+    pub fn camera_animation_tick(&mut self, now: Instant) -> (WidgetFlags, bool) {
+        let Some(frame) = self.camera_animation.as_ref().map(|a| a.frame(now)) else {
+            return (WidgetFlags::default(), false);
+        };
+        if frame.finished {
+            self.camera_animation = None;
+            return (
+                self.camera_apply_zoom_and_center(frame.zoom, frame.center),
+                false,
+            );
+        }
+        // Only zoom temporarily while animating, the rendering is regenerated with the final zoom
+        let mut widget_flags = self
+            .camera
+            .zoom_temporarily_to(frame.zoom / self.camera.zoom());
+        widget_flags.merge(self.camera.set_viewport_center(frame.center));
+        widget_flags.merge(self.doc_expand_autoexpand());
+        widget_flags.redraw = true;
+        (widget_flags, true)
+    }
+
+    /// Stop a running camera animation, keeping the camera where it currently is.
+    ///
+    /// Should be called when the user starts to move the camera.
+    pub fn camera_cancel_animation(&mut self) -> WidgetFlags {
+        if self.camera_animation.take().is_none() {
+            return WidgetFlags::default();
+        }
+        self.camera_apply_zoom_and_center(self.camera.total_zoom(), self.camera.viewport_center())
+    }
+
+    /// Apply the zoom permanently, move the viewport center and regenerate the rendering.
+    fn camera_apply_zoom_and_center(&mut self, zoom: f64, center: na::Vector2<f64>) -> WidgetFlags {
+        let (mut widget_flags, _) = self.handle_engine_task(EngineTask::Zoom(zoom));
+        widget_flags.merge(self.camera.set_viewport_center(center));
+        widget_flags.merge(self.doc_resize_autoexpand());
+        self.update_rendering_current_viewport();
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Set the configuration of the overlays, like the selection handle size and hit tolerance.
+    pub fn camera_set_overlay_config(&mut self, overlay_config: OverlayConfig) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.camera.overlay_config = overlay_config.clamped();
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Rotate the view to the given angle in radians, keeping the viewport center.
+    ///
+    /// The rotation only affects the view, the document and its strokes are not modified.
+    pub fn camera_rotate_to(&mut self, rotation: f64) -> WidgetFlags {
+        let mut widget_flags = self.camera_cancel_animation();
+        widget_flags.merge(self.camera.rotate_to(rotation));
+        widget_flags.merge(self.doc_expand_autoexpand());
+        self.update_rendering_current_viewport();
+        widget_flags
+    }
+
+    /// Rotate the view by one rotation step, clockwise or counterclockwise.
+    pub fn camera_rotate_by_step(&mut self, clockwise: bool) -> WidgetFlags {
+        let step = if clockwise {
+            Camera::ROTATION_STEP
+        } else {
+            -Camera::ROTATION_STEP
+        };
+        self.camera_rotate_to(self.camera.rotation() + step)
+    }
+
     /// Update the viewport size of the camera.
     ///
     /// Background and strokes rendering then need to be updated.
@@ -0,0 +1,215 @@
+// Imports
+use super::RnoteEngine;
+use crate::store::StrokeKey;
+use crate::WidgetFlags;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rnote_compose::shapes::ShapeBehaviour;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// The direction of keyboard navigation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NavigationDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl NavigationDirection {
+    /// The unit vector of the direction in document coordinate space.
+    pub fn unit_vector(self) -> na::Vector2<f64> {
+        match self {
+            Self::Left => na::vector![-1.0, 0.0],
+            Self::Right => na::vector![1.0, 0.0],
+            Self::Up => na::vector![0.0, -1.0],
+            Self::Down => na::vector![0.0, 1.0],
+        }
+    }
+}
+
+/// Preferences for keyboard driven navigation and manipulation of strokes.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "navigation_prefs")]
+pub struct NavigationPrefs {
+    /// The distance strokes are nudged by with the arrow keys.
+    #[serde(rename = "nudge_step")]
+    pub nudge_step: f64,
+    /// The distance strokes are nudged by with the arrow keys while the large step modifier is pressed.
+    #[serde(rename = "nudge_step_large")]
+    pub nudge_step_large: f64,
+    /// Whether programmatic camera movements like zooming to fit or jumping to strokes are animated.
+    #[serde(rename = "animate_camera")]
+    pub animate_camera: bool,
+}
+
+impl Default for NavigationPrefs {
+    fn default() -> Self {
+        Self {
+            nudge_step: 1.0,
+            nudge_step_large: 10.0,
+            animate_camera: true,
+        }
+    }
+}
+
+impl NavigationPrefs {
+    /// The minimum nudge step.
+    pub const NUDGE_STEP_MIN: f64 = 0.1;
+    /// The maximum nudge step.
+    pub const NUDGE_STEP_MAX: f64 = 1000.0;
+}
+
+impl RnoteEngine {
+    /// The currently focused stroke, if it still exists and is not trashed.
+    pub fn focused_stroke(&self) -> Option<StrokeKey> {
+        self.focused_stroke.filter(|&key| {
+            self.store.get_stroke_ref(key).is_some() && self.store.trashed(key) == Some(false)
+        })
+    }
+
+    /// The bounds of the focused stroke in document coordinate space.
+    ///
+    /// Used to report the focus position to accessibility tooling.
+    pub fn focused_stroke_bounds(&self) -> Option<Aabb> {
+        self.focused_stroke()
+            .and_then(|key| self.store.get_stroke_ref(key))
+            .map(|stroke| stroke.bounds())
+    }
+
+    /// Set the focused stroke, or clear the focus when `None` is given.
+    pub fn set_focused_stroke(&mut self, key: Option<StrokeKey>) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.focused_stroke = key;
+        if let Some(bounds) = self.focused_stroke_bounds() {
+            widget_flags.merge(self.scroll_bounds_into_view(bounds));
+        }
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Move the focus to the next stroke in reading order (top to bottom, left to right).
+    ///
+    /// Wraps around at the end of the document.
+    pub fn focus_next_stroke(&mut self) -> WidgetFlags {
+        self.focus_stroke_in_reading_order(true)
+    }
+
+    /// Move the focus to the previous stroke in reading order (top to bottom, left to right).
+    ///
+    /// Wraps around at the start of the document.
+    pub fn focus_previous_stroke(&mut self) -> WidgetFlags {
+        self.focus_stroke_in_reading_order(false)
+    }
+
+    /// Move the focus to the nearest stroke in the given direction from the currently focused stroke.
+    ///
+    /// Without a focused stroke, the first stroke in reading order gets focused.
+    pub fn focus_stroke_in_direction(&mut self, direction: NavigationDirection) -> WidgetFlags {
+        let Some(focused_bounds) = self.focused_stroke_bounds() else {
+            return self.focus_stroke_in_reading_order(true);
+        };
+        let focused = self.focused_stroke();
+        let dir = direction.unit_vector();
+        let center = focused_bounds.center();
+
+        let nearest = self
+            .store
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter(|&key| Some(key) != focused)
+            .filter_map(|key| {
+                let offset = self.store.get_stroke_ref(key)?.bounds().center() - center;
+                let along = offset.dot(&dir);
+                if along <= 0.0 {
+                    return None;
+                }
+                // Penalize strokes that are offset perpendicular to the direction
+                let across = (offset - dir * along).magnitude();
+                Some((key, along + across * 2.0))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(key, _)| key);
+
+        match nearest {
+            Some(key) => self.set_focused_stroke(Some(key)),
+            None => WidgetFlags::default(),
+        }
+    }
+
+    /// Nudge the selected strokes, or the focused stroke when nothing is selected, in the given direction.
+    ///
+    /// The step is taken from the navigation prefs, `large` selects the large step.
+    pub fn nudge_strokes(&mut self, direction: NavigationDirection, large: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let mut keys = self.store.selection_keys_as_rendered();
+        if keys.is_empty() {
+            keys.extend(self.focused_stroke());
+        }
+        if keys.is_empty() {
+            return widget_flags;
+        }
+        let step = if large {
+            self.navigation_prefs.nudge_step_large
+        } else {
+            self.navigation_prefs.nudge_step
+        }
+        .clamp(
+            NavigationPrefs::NUDGE_STEP_MIN,
+            NavigationPrefs::NUDGE_STEP_MAX,
+        );
+        let offset = direction.unit_vector() * step;
+
+        self.store.translate_strokes(&keys, offset);
+        self.store.translate_strokes_images(&keys, offset);
+        self.store.update_geometry_for_strokes(&keys);
+        widget_flags.merge(self.doc_resize_autoexpand());
+        widget_flags.merge(self.current_pen_update_state());
+        widget_flags.merge(self.record(Instant::now()));
+        if let Some(bounds) = self.store.bounds_for_strokes(&keys) {
+            widget_flags.merge(self.scroll_bounds_into_view(bounds));
+        }
+
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
+    fn focus_stroke_in_reading_order(&mut self, forward: bool) -> WidgetFlags {
+        let mut keys_bounds = self
+            .store
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter_map(|key| Some((key, self.store.get_stroke_ref(key)?.bounds())))
+            .collect::<Vec<(StrokeKey, Aabb)>>();
+        if keys_bounds.is_empty() {
+            return self.set_focused_stroke(None);
+        }
+        keys_bounds.sort_by(|(_, a), (_, b)| {
+            a.mins[1]
+                .total_cmp(&b.mins[1])
+                .then(a.mins[0].total_cmp(&b.mins[0]))
+        });
+        let n_keys = keys_bounds.len();
+
+        let next_index = match self
+            .focused_stroke()
+            .and_then(|focused| keys_bounds.iter().position(|(key, _)| *key == focused))
+        {
+            Some(i) if forward => (i + 1) % n_keys,
+            Some(i) => (i + n_keys - 1) % n_keys,
+            None if forward => 0,
+            None => n_keys - 1,
+        };
+
+        self.set_focused_stroke(Some(keys_bounds[next_index].0))
+    }
+
+    /// Move the camera so that the bounds are visible, if they are not already entirely inside the viewport.
+    pub(super) fn scroll_bounds_into_view(&mut self, bounds: Aabb) -> WidgetFlags {
+        if self.camera.viewport().contains(&bounds) {
+            return WidgetFlags::default();
+        }
+        self.camera_animate_to(self.camera.total_zoom(), bounds.center().coords, true)
+    }
+}
@@ -0,0 +1,139 @@
+// Imports
+use super::RnoteEngine;
+use crate::ocr::OcrProvider;
+use crate::store::StrokeKey;
+use crate::strokes::{Stroke, StrokeBehaviour, TextLayer};
+use crate::{render, WidgetFlags};
+use futures::channel::oneshot;
+use p2d::bounding_volume::Aabb;
+use rnote_compose::shapes::{Rectangle, ShapeBehaviour};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// An image that is prepared for text recognition.
+#[derive(Debug, Clone)]
+enum OcrSource {
+    Bitmap(render::Image),
+    /// The Svg in its intrinsic size, with the upper left corner at the origin.
+    Vector(render::Svg),
+}
+
+impl OcrSource {
+    /// The scale in which vector images are rasterized for recognition.
+    const VECTOR_IMAGE_SCALE: f64 = 3.0;
+
+    fn recognize(self, provider: &dyn OcrProvider) -> anyhow::Result<TextLayer> {
+        match self {
+            Self::Bitmap(image) => provider.recognize(&image),
+            Self::Vector(svg) => {
+                let intrinsic_size = svg.bounds.extents();
+                let image = render::Image::gen_image_from_svg(
+                    svg.clone(),
+                    svg.bounds,
+                    Self::VECTOR_IMAGE_SCALE,
+                )?;
+                let mut text_layer = provider.recognize(&image)?;
+
+                // The rendered image might be slightly larger than the Svg,
+                // so the bounds need to be made relative to the intrinsic size.
+                let image_bounds = image.rect.bounds();
+                let image_size = image_bounds.extents();
+                for span in text_layer.spans.iter_mut() {
+                    let map = |p: na::Point2<f64>| -> na::Point2<f64> {
+                        (image_bounds.mins.coords + p.coords.component_mul(&image_size))
+                            .component_div(&intrinsic_size)
+                            .into()
+                    };
+                    span.bounds = Aabb::new(map(span.bounds.mins), map(span.bounds.maxs));
+                }
+                Ok(text_layer)
+            }
+        }
+    }
+}
+
+impl RnoteEngine {
+    /// Recognize the text of the images of the given keys through OCR, e.g. of scanned pages.
+    ///
+    /// Images that already have a text layer, e.g. imported Pdf pages with text, are skipped.
+    /// The recognized text layers are then applied with [RnoteEngine::ocr_apply_text_layers()].
+    pub fn ocr_recognize_images(
+        &self,
+        keys: &[StrokeKey],
+        provider: Arc<dyn OcrProvider>,
+    ) -> oneshot::Receiver<anyhow::Result<Vec<(StrokeKey, TextLayer)>>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<Vec<(StrokeKey, TextLayer)>>>();
+        let sources = keys
+            .iter()
+            .filter_map(|&key| match self.store.get_stroke_ref(key)? {
+                Stroke::BitmapImage(bitmapimage) if bitmapimage.text_layer.is_empty() => {
+                    Some((key, OcrSource::Bitmap(bitmapimage.image.clone())))
+                }
+                Stroke::VectorImage(vectorimage) if vectorimage.text_layer.is_empty() => {
+                    let mut vectorimage = vectorimage.clone();
+                    vectorimage.rectangle = Rectangle::from_p2d_aabb(Aabb::new(
+                        na::point![0.0, 0.0],
+                        vectorimage.intrinsic_size.into(),
+                    ));
+                    match vectorimage.gen_svg() {
+                        Ok(svg) => Some((key, OcrSource::Vector(svg))),
+                        Err(e) => {
+                            log::error!(
+                                "Generating Svg of vector image for OCR failed, Err: {e:?}"
+                            );
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            })
+            .collect::<Vec<(StrokeKey, OcrSource)>>();
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<(StrokeKey, TextLayer)>> {
+                sources
+                    .into_iter()
+                    .map(|(key, source)| Ok((key, source.recognize(provider.as_ref())?)))
+                    .collect()
+            };
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("Sending result to receiver failed. Receiver already dropped.");
+            }
+        });
+
+        oneshot_receiver
+    }
+
+    /// Recognize the text of all images of the document through OCR, see [RnoteEngine::ocr_recognize_images()].
+    pub fn ocr_recognize_all_images(
+        &self,
+        provider: Arc<dyn OcrProvider>,
+    ) -> oneshot::Receiver<anyhow::Result<Vec<(StrokeKey, TextLayer)>>> {
+        self.ocr_recognize_images(&self.store.stroke_keys_as_rendered(), provider)
+    }
+
+    /// Apply the recognized text layers to the images, as a single undoable change.
+    ///
+    /// The text can then be searched, selected with the select text tool and is exported as text layer in Pdf exports.
+    pub fn ocr_apply_text_layers(
+        &mut self,
+        text_layers: Vec<(StrokeKey, TextLayer)>,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let mut modified = false;
+
+        for (key, text_layer) in text_layers {
+            if text_layer.is_empty() {
+                continue;
+            }
+            modified |= self.store.set_stroke_text_layer(key, text_layer);
+        }
+        if !modified {
+            return widget_flags;
+        }
+        widget_flags.merge(self.record(Instant::now()));
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+}
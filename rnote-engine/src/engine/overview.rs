@@ -0,0 +1,114 @@
+// Imports
+use super::RnoteEngine;
+use crate::render;
+use crate::store::HistoryEntry;
+use p2d::bounding_volume::Aabb;
+use piet::RenderContext;
+use rnote_compose::helpers::AabbHelpers;
+use rnote_compose::Color;
+
+/// A low resolution rendering of the entire document, e.g. for a minimap.
+#[derive(Debug, Clone)]
+pub struct Overview {
+    /// The rendered image, its rectangle are the document bounds.
+    pub image: render::Image,
+    /// The document bounds at the time of rendering.
+    pub doc_bounds: Aabb,
+}
+
+/// Where the viewport is located in the document, to be overlaid on an [Overview].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OverviewViewport {
+    /// The document bounds.
+    pub doc_bounds: Aabb,
+    /// The current viewport in document coordinate space.
+    pub viewport: Aabb,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct OverviewCache {
+    overview: Overview,
+    max_size: na::Vector2<f64>,
+    /// The state of the store when the overview was rendered.
+    ///
+    /// Holding on to it makes every modification of the strokes allocate new components,
+    /// so edits are detected by comparing the pointers.
+    store_state: HistoryEntry,
+    background_color: Color,
+}
+
+impl RnoteEngine {
+    /// The overview of the entire document, rendered to fit into the given maximum size in pixels.
+    ///
+    /// The rendering is cached and only regenerated after the strokes, the document or the size were changed.
+    pub fn overview(&mut self, max_size: na::Vector2<f64>) -> anyhow::Result<Overview> {
+        let doc_bounds = self.document.bounds();
+        let background_color = self.document.background.color;
+
+        if let Some(cache) = &self.overview_cache {
+            if cache.max_size == max_size
+                && cache.overview.doc_bounds == doc_bounds
+                && cache.background_color == background_color
+                && self.store.eq_w_history_entry(&cache.store_state)
+            {
+                return Ok(cache.overview.clone());
+            }
+        }
+
+        let overview = self.gen_overview(doc_bounds, max_size)?;
+        self.overview_cache = Some(OverviewCache {
+            overview: overview.clone(),
+            max_size,
+            store_state: self.store.create_history_entry(),
+            background_color,
+        });
+        Ok(overview)
+    }
+
+    /// Where the current viewport is located in the document.
+    pub fn overview_viewport(&self) -> OverviewViewport {
+        OverviewViewport {
+            doc_bounds: self.document.bounds(),
+            viewport: self.camera.viewport(),
+        }
+    }
+
+    /// Drop the cached overview.
+    pub fn overview_clear_cache(&mut self) {
+        self.overview_cache = None;
+    }
+
+    fn gen_overview(
+        &self,
+        doc_bounds: Aabb,
+        max_size: na::Vector2<f64>,
+    ) -> anyhow::Result<Overview> {
+        let extents = doc_bounds.extents();
+        if extents[0] <= 0.0 || extents[1] <= 0.0 || max_size[0] < 1.0 || max_size[1] < 1.0 {
+            return Err(anyhow::anyhow!(
+                "generating overview failed, invalid document bounds {doc_bounds:?} or maximum size {max_size:?}"
+            ));
+        }
+        // Never render at a higher resolution than the document itself
+        let image_scale = (max_size[0] / extents[0])
+            .min(max_size[1] / extents[1])
+            .min(1.0);
+        let background_color = self.document.background.color;
+
+        let image = render::Image::gen_with_piet(
+            |piet_cx| {
+                piet_cx.fill(
+                    doc_bounds.to_kurbo_rect(),
+                    &piet::Color::from(background_color),
+                );
+                self.store
+                    .draw_strokes_immediate(piet_cx, doc_bounds, doc_bounds, image_scale);
+                Ok(())
+            },
+            doc_bounds,
+            image_scale,
+        )?;
+
+        Ok(Overview { image, doc_bounds })
+    }
+}
@@ -0,0 +1,420 @@
+// Imports
+use super::RnoteEngine;
+use crate::document::Layout;
+use crate::store::StrokeKey;
+use crate::WidgetFlags;
+use p2d::bounding_volume::Aabb;
+use p2d::query::PointQuery;
+use rnote_compose::helpers::{AabbHelpers, SplitOrder};
+use rnote_compose::shapes::ShapeBehaviour;
+use std::time::Instant;
+
+impl RnoteEngine {
+    /// The number of pages of the document when in fixed size layout.
+    ///
+    /// The pages are indexed in the default split order of the document.
+    pub fn doc_n_pages_fixed_size(&self) -> Option<usize> {
+        if self.document.layout != Layout::FixedSize {
+            return None;
+        }
+        let n_pages = self.document.pages_bounds(SplitOrder::default()).len();
+        (n_pages > 0).then_some(n_pages)
+    }
+
+    /// Insert a blank page at the given index when in fixed size layout.
+    ///
+    /// The strokes on the pages from the index onwards are moved by one page.
+    /// Background and strokes rendering then need to be updated.
+    pub fn doc_insert_page(&mut self, index: usize) -> WidgetFlags {
+        let Some(n_pages) = self.doc_n_pages_fixed_size() else {
+            return WidgetFlags::default();
+        };
+        let index = index.min(n_pages);
+        let doc_height_before = self.document.height;
+
+        let strokes_by_page = self.strokes_by_page();
+        self.doc_resize_to_n_pages(n_pages + 1);
+        let pages_bounds = self.document.pages_bounds(SplitOrder::default());
+        let moves = (index..n_pages)
+            .map(|page| (page, page + 1))
+            .collect::<Vec<(usize, usize)>>();
+        self.move_strokes_between_pages(&strokes_by_page, &moves, &pages_bounds);
+
+        self.finish_page_operation(doc_height_before)
+    }
+
+    /// Delete the page at the given index together with its strokes when in fixed size layout.
+    ///
+    /// The strokes on the following pages are moved back by one page. The last remaining page can't be deleted.
+    /// Background and strokes rendering then need to be updated.
+    pub fn doc_delete_page(&mut self, index: usize) -> WidgetFlags {
+        let Some(n_pages) = self.doc_n_pages_fixed_size() else {
+            return WidgetFlags::default();
+        };
+        if n_pages <= 1 || index >= n_pages {
+            return WidgetFlags::default();
+        }
+        let doc_height_before = self.document.height;
+
+        let strokes_by_page = self.strokes_by_page();
+        let pages_bounds = self.document.pages_bounds(SplitOrder::default());
+        let remove_keys = strokes_by_page
+            .iter()
+            .filter(|(_, page)| *page == index)
+            .map(|(key, _)| *key)
+            .collect::<Vec<StrokeKey>>();
+        let moves = (index + 1..n_pages)
+            .map(|page| (page, page - 1))
+            .collect::<Vec<(usize, usize)>>();
+        self.store.set_trashed_keys(&remove_keys, true);
+        self.move_strokes_between_pages(&strokes_by_page, &moves, &pages_bounds);
+        self.doc_resize_to_n_pages(n_pages - 1);
+
+        self.finish_page_operation(doc_height_before)
+    }
+
+    /// Duplicate the page at the given index together with its strokes when in fixed size layout.
+    ///
+    /// The copy is inserted right after the page.
+    /// Background and strokes rendering then need to be updated.
+    pub fn doc_duplicate_page(&mut self, index: usize) -> WidgetFlags {
+        let Some(n_pages) = self.doc_n_pages_fixed_size() else {
+            return WidgetFlags::default();
+        };
+        if index >= n_pages {
+            return WidgetFlags::default();
+        }
+        let doc_height_before = self.document.height;
+
+        let strokes_by_page = self.strokes_by_page();
+        self.doc_resize_to_n_pages(n_pages + 1);
+        let pages_bounds = self.document.pages_bounds(SplitOrder::default());
+        let duplicate_keys = strokes_by_page
+            .iter()
+            .filter(|(_, page)| *page == index)
+            .map(|(key, _)| *key)
+            .collect::<Vec<StrokeKey>>();
+        let moves = (index + 1..n_pages)
+            .map(|page| (page, page + 1))
+            .collect::<Vec<(usize, usize)>>();
+        self.move_strokes_between_pages(&strokes_by_page, &moves, &pages_bounds);
+        let new_keys = self
+            .store
+            .clone_strokes(&duplicate_keys)
+            .into_iter()
+            .map(|stroke| self.store.insert_stroke(stroke, None))
+            .collect::<Vec<StrokeKey>>();
+        self.translate_strokes_to_page(&new_keys, index, index + 1, &pages_bounds);
+
+        self.finish_page_operation(doc_height_before)
+    }
+
+    /// Move the page at index `from` to index `to` when in fixed size layout, shifting the pages in between.
+    ///
+    /// Background and strokes rendering then need to be updated.
+    pub fn doc_move_page(&mut self, from: usize, to: usize) -> WidgetFlags {
+        let Some(n_pages) = self.doc_n_pages_fixed_size() else {
+            return WidgetFlags::default();
+        };
+        if from >= n_pages || to >= n_pages || from == to {
+            return WidgetFlags::default();
+        }
+        let doc_height_before = self.document.height;
+        // The new order of the pages, containing the old page indices
+        let mut order = (0..n_pages).collect::<Vec<usize>>();
+        let page = order.remove(from);
+        order.insert(to, page);
+
+        let strokes_by_page = self.strokes_by_page();
+        let pages_bounds = self.document.pages_bounds(SplitOrder::default());
+        let moves = order
+            .into_iter()
+            .enumerate()
+            .filter(|(new_index, old_index)| new_index != old_index)
+            .map(|(new_index, old_index)| (old_index, new_index))
+            .collect::<Vec<(usize, usize)>>();
+        self.move_strokes_between_pages(&strokes_by_page, &moves, &pages_bounds);
+
+        self.finish_page_operation(doc_height_before)
+    }
+
+    /// Move the selected strokes to the page with the given index, keeping their position relative to the page.
+    ///
+    /// The pages are indexed in the default split order of the document. When `scroll` is true,
+    /// the camera is moved to the strokes at their new position.
+    /// Strokes rendering then needs to be updated.
+    pub fn move_selection_to_page(&mut self, index: usize, scroll: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let keys = self.store.selection_keys_as_rendered();
+        let Some(selection_bounds) = self.store.bounds_for_strokes(&keys) else {
+            return widget_flags;
+        };
+        let pages_bounds = self.document.pages_bounds(SplitOrder::default());
+        let Some(target_page) = pages_bounds.get(index) else {
+            return widget_flags;
+        };
+        let center = na::Point2::from(selection_bounds.center().coords);
+        let Some(source_page) = pages_bounds.iter().min_by(|a, b| {
+            a.distance_to_local_point(&center, true)
+                .total_cmp(&b.distance_to_local_point(&center, true))
+        }) else {
+            return widget_flags;
+        };
+        let offset = target_page.mins - source_page.mins;
+        if offset == na::Vector2::zeros() {
+            return widget_flags;
+        }
+
+        self.store.translate_strokes(&keys, offset);
+        self.store.translate_strokes_images(&keys, offset);
+        self.store.update_geometry_for_strokes(&keys);
+        widget_flags.merge(self.doc_resize_autoexpand());
+        widget_flags.merge(self.current_pen_update_state());
+        widget_flags.merge(self.record(Instant::now()));
+        if scroll {
+            widget_flags.merge(self.scroll_bounds_into_view(selection_bounds.translate(offset)));
+        }
+
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
+    /// Grow the document in fixed size layout so that it contains all strokes.
+    ///
+    /// Needed after undo and redo, where the document height is only restored for states recorded by page operations.
+    pub(super) fn doc_expand_fixed_size_to_fit_strokes(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if self.document.layout != Layout::FixedSize || self.document.format.height <= 0.0 {
+            return widget_flags;
+        }
+        let format_height = self.document.format.height;
+        let keys = self.store.stroke_keys_as_rendered();
+        let Some(bounds) = self.store.bounds_for_strokes(&keys) else {
+            return widget_flags;
+        };
+        let needed_height =
+            ((bounds.maxs[1] - self.document.y) / format_height).ceil() * format_height;
+        if needed_height > self.document.height {
+            self.document.height = needed_height;
+            widget_flags.resize = true;
+        }
+        widget_flags
+    }
+
+    /// Restore the document height that was recorded with the current history entry by a page operation.
+    pub(super) fn doc_restore_recorded_height(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if self.document.layout != Layout::FixedSize {
+            return widget_flags;
+        }
+        if let Some(doc_height) = self.store.live_doc_height() {
+            self.document.height = doc_height;
+            widget_flags.resize = true;
+        }
+        widget_flags
+    }
+
+    /// Resize the document in fixed size layout so that it has room for the given number of pages.
+    fn doc_resize_to_n_pages(&mut self, n_pages: usize) {
+        let columns = (self.document.width / self.document.format.width)
+            .ceil()
+            .max(1.0);
+        let rows = (n_pages.max(1) as f64 / columns).ceil();
+        self.document.height = rows * self.document.format.height;
+    }
+
+    /// The page index of every stroke that is not trashed, determined by the center of its bounds.
+    ///
+    /// The pages are indexed in the default split order. Strokes outside of all pages are assigned to the nearest page.
+    fn strokes_by_page(&self) -> Vec<(StrokeKey, usize)> {
+        let pages_bounds = self.document.pages_bounds(SplitOrder::default());
+        self.store
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter_map(|key| {
+                let center =
+                    na::Point2::from(self.store.get_stroke_ref(key)?.bounds().center().coords);
+                let (page, _) = pages_bounds.iter().enumerate().min_by(|(_, a), (_, b)| {
+                    a.distance_to_local_point(&center, true)
+                        .total_cmp(&b.distance_to_local_point(&center, true))
+                })?;
+                Some((key, page))
+            })
+            .collect()
+    }
+
+    /// Move the strokes of pages to other pages, given as pairs of old and new page indices.
+    ///
+    /// The strokes keep their position relative to the page. The page bounds must contain the old and the new pages.
+    fn move_strokes_between_pages(
+        &mut self,
+        strokes_by_page: &[(StrokeKey, usize)],
+        moves: &[(usize, usize)],
+        pages_bounds: &[Aabb],
+    ) {
+        for (from, to) in moves.iter().copied() {
+            let keys = strokes_by_page
+                .iter()
+                .filter(|(_, page)| *page == from)
+                .map(|(key, _)| *key)
+                .collect::<Vec<StrokeKey>>();
+            self.translate_strokes_to_page(&keys, from, to, pages_bounds);
+        }
+    }
+
+    fn translate_strokes_to_page(
+        &mut self,
+        keys: &[StrokeKey],
+        from: usize,
+        to: usize,
+        pages_bounds: &[Aabb],
+    ) {
+        let (Some(from_bounds), Some(to_bounds)) = (pages_bounds.get(from), pages_bounds.get(to))
+        else {
+            return;
+        };
+        let offset = to_bounds.mins - from_bounds.mins;
+        if keys.is_empty() || offset == na::Vector2::zeros() {
+            return;
+        }
+        self.store.translate_strokes(keys, offset);
+        self.store.translate_strokes_images(keys, offset);
+    }
+
+    fn finish_page_operation(&mut self, doc_height_before: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let all_strokes = self.store.stroke_keys_unordered();
+        self.store.update_geometry_for_strokes(&all_strokes);
+        widget_flags.merge(self.current_pen_update_state());
+        widget_flags.merge(
+            self.store
+                .record_w_doc_height(doc_height_before, self.document.height),
+        );
+        widget_flags.resize = true;
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strokes::{ShapeStroke, Stroke};
+    use approx::assert_relative_eq;
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::style::smooth::SmoothOptions;
+    use rnote_compose::Style;
+
+    /// A fixed size document with three pages and a stroke on each page.
+    fn engine_with_strokes_on_pages() -> (RnoteEngine, Vec<StrokeKey>) {
+        let mut engine = RnoteEngine::default();
+        engine.document.layout = Layout::FixedSize;
+        engine.document.height = engine.document.format.height * 3.0;
+        let keys = engine
+            .document
+            .pages_bounds(SplitOrder::default())
+            .into_iter()
+            .map(|page_bounds| {
+                let bounds = Aabb::new(
+                    page_bounds.mins + na::vector![50.0, 50.0],
+                    page_bounds.mins + na::vector![100.0, 100.0],
+                );
+                engine.store.insert_stroke(
+                    Stroke::ShapeStroke(ShapeStroke::new(
+                        Shape::Rectangle(Rectangle::from_p2d_aabb(bounds)),
+                        Style::Smooth(SmoothOptions::default()),
+                    )),
+                    None,
+                )
+            })
+            .collect::<Vec<StrokeKey>>();
+        engine.record(Instant::now());
+        (engine, keys)
+    }
+
+    /// The positions of the strokes that are not trashed.
+    fn stroke_positions(engine: &RnoteEngine) -> Vec<(StrokeKey, na::Point2<f64>)> {
+        let mut positions = engine
+            .store
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter_map(|key| Some((key, engine.store.get_stroke_ref(key)?.bounds().mins)))
+            .collect::<Vec<(StrokeKey, na::Point2<f64>)>>();
+        positions.sort_by(|(a, _), (b, _)| a.cmp(b));
+        positions
+    }
+
+    /// Applies the page operation, then checks that undo and redo restore the stroke positions and the document
+    /// height.
+    fn assert_undo_redo_roundtrip(
+        op: impl FnOnce(&mut RnoteEngine) -> WidgetFlags,
+        expected_n_pages: usize,
+    ) -> (RnoteEngine, Vec<StrokeKey>) {
+        let (mut engine, keys) = engine_with_strokes_on_pages();
+        let positions_before = stroke_positions(&engine);
+        let height_before = engine.document.height;
+
+        op(&mut engine);
+        let positions_after = stroke_positions(&engine);
+        let height_after = engine.document.height;
+        assert_eq!(engine.doc_n_pages_fixed_size(), Some(expected_n_pages));
+
+        engine.undo(Instant::now());
+        assert_eq!(stroke_positions(&engine), positions_before);
+        assert_eq!(engine.document.height, height_before);
+
+        engine.redo(Instant::now());
+        assert_eq!(stroke_positions(&engine), positions_after);
+        assert_eq!(engine.document.height, height_after);
+
+        (engine, keys)
+    }
+
+    fn stroke_y(engine: &RnoteEngine, key: StrokeKey) -> f64 {
+        engine.store.get_stroke_ref(key).unwrap().bounds().mins[1]
+    }
+
+    #[test]
+    fn insert_page_undo_redo() {
+        let (engine, keys) = assert_undo_redo_roundtrip(|engine| engine.doc_insert_page(1), 4);
+        let h = engine.document.format.height;
+        let offset = stroke_y(&engine, keys[0]);
+        assert_relative_eq!(stroke_y(&engine, keys[1]), 2.0 * h + offset);
+        assert_relative_eq!(stroke_y(&engine, keys[2]), 3.0 * h + offset);
+    }
+
+    #[test]
+    fn delete_page_undo_redo() {
+        let (engine, keys) = assert_undo_redo_roundtrip(|engine| engine.doc_delete_page(1), 2);
+        let h = engine.document.format.height;
+        let offset = stroke_y(&engine, keys[0]);
+        assert_eq!(stroke_positions(&engine).len(), 2);
+        assert_relative_eq!(stroke_y(&engine, keys[2]), h + offset);
+    }
+
+    #[test]
+    fn move_page_undo_redo() {
+        let (engine, keys) = assert_undo_redo_roundtrip(|engine| engine.doc_move_page(0, 2), 3);
+        let h = engine.document.format.height;
+        let offset = stroke_y(&engine, keys[1]);
+        assert_relative_eq!(stroke_y(&engine, keys[2]), h + offset);
+        assert_relative_eq!(stroke_y(&engine, keys[0]), 2.0 * h + offset);
+    }
+
+    #[test]
+    fn duplicate_page_undo_redo() {
+        let (engine, keys) = assert_undo_redo_roundtrip(|engine| engine.doc_duplicate_page(0), 4);
+        let h = engine.document.format.height;
+        let offset = stroke_y(&engine, keys[0]);
+        let copies = stroke_positions(&engine)
+            .into_iter()
+            .filter(|(key, _)| !keys.contains(key))
+            .collect::<Vec<(StrokeKey, na::Point2<f64>)>>();
+        assert_eq!(copies.len(), 1);
+        assert_relative_eq!(copies[0].1[1], h + offset);
+        assert_relative_eq!(stroke_y(&engine, keys[1]), 2.0 * h + offset);
+        assert_relative_eq!(stroke_y(&engine, keys[2]), 3.0 * h + offset);
+    }
+}
@@ -0,0 +1,82 @@
+// Imports
+use super::RnoteEngine;
+use crate::WidgetFlags;
+
+/// Reviewing how the document evolved over time, based on the creation timestamps of the strokes.
+///
+/// During playback only the strokes created at or before the playback time are drawn.
+/// The store is not altered, so ending the playback shows the current document again.
+/// Strokes that were trashed in the meantime are not shown, and strokes without a timestamp are always shown.
+impl RnoteEngine {
+    /// The time range of the creation of the strokes, in milliseconds since the unix epoch.
+    pub fn playback_time_range(&self) -> Option<(u64, u64)> {
+        let timestamps = self.store.created_timestamps();
+        Some((*timestamps.first()?, *timestamps.last()?))
+    }
+
+    /// The current playback time. None if the playback is not active.
+    pub fn playback_time(&self) -> Option<u64> {
+        self.store.playback_time
+    }
+
+    /// Whether the playback is active.
+    pub fn playback_active(&self) -> bool {
+        self.store.playback_time.is_some()
+    }
+
+    /// Set the playback time, showing the document as it looked at that time.
+    ///
+    /// None ends the playback.
+    pub fn set_playback_time(&mut self, playback_time: Option<u64>) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if self.store.playback_time != playback_time {
+            self.store.playback_time = playback_time;
+            self.lod_clear_cache();
+            self.update_content_rendering_current_viewport();
+            widget_flags.redraw = true;
+        }
+        widget_flags
+    }
+
+    /// Step the playback forward to the creation of the next stroke(s).
+    ///
+    /// Ends the playback when stepping past the newest stroke.
+    pub fn playback_step_forward(&mut self) -> WidgetFlags {
+        let Some(current) = self.store.playback_time else {
+            return WidgetFlags::default();
+        };
+        let next = self
+            .store
+            .created_timestamps()
+            .into_iter()
+            .find(|&t| t > current);
+        let newest = self.playback_time_range().map(|(_, newest)| newest);
+
+        // Showing the newest stroke equals the current document, so the playback can end
+        if next.is_none() || next == newest {
+            self.set_playback_time(None)
+        } else {
+            self.set_playback_time(next)
+        }
+    }
+
+    /// Step the playback backward, hiding the newest visible stroke(s).
+    ///
+    /// Starts the playback when it is not active. Stepping back from the oldest stroke shows the document
+    /// before any stroke with a timestamp was created.
+    pub fn playback_step_backward(&mut self) -> WidgetFlags {
+        let timestamps = self.store.created_timestamps();
+        let Some(&oldest) = timestamps.first() else {
+            return WidgetFlags::default();
+        };
+        let current = self.store.playback_time.unwrap_or(u64::MAX);
+        let previous = timestamps
+            .into_iter()
+            .rev()
+            .skip_while(|&t| t > current)
+            .nth(1)
+            .unwrap_or_else(|| oldest.saturating_sub(1).min(current));
+
+        self.set_playback_time(Some(previous))
+    }
+}
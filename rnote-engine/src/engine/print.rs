@@ -0,0 +1,447 @@
+// Imports
+use super::export::PageRange;
+use super::imposition::{self, PagesPerSheet, Sheet, SheetSlot};
+use super::{RnoteEngine, StrokeContent};
+use crate::document::PageMargins;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rnote_compose::helpers::SplitOrder;
+use rnote_compose::shapes::ShapeBehaviour;
+use serde::{Deserialize, Serialize};
+
+/// Print preferences.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "print_prefs")]
+pub struct PrintPrefs {
+    /// Whether the background should be printed.
+    #[serde(rename = "with_background")]
+    pub with_background: bool,
+    /// Whether the background pattern should be printed.
+    #[serde(rename = "with_pattern")]
+    pub with_pattern: bool,
+    /// The page order when documents with layouts that expand in horizontal and vertical directions are cut into pages.
+    #[serde(rename = "page_order")]
+    pub page_order: SplitOrder,
+    /// Whether pages that exceed the paper size are split into tiles printed at actual size,
+    /// instead of being scaled down to fit onto a single sheet.
+    #[serde(rename = "tile_oversized")]
+    pub tile_oversized: bool,
+    /// The overlap between neighbouring tiles, in document coordinate space.
+    #[serde(rename = "tile_overlap", with = "rnote_compose::serialize::f64_dp3")]
+    pub tile_overlap: f64,
+    /// Whether crop marks are drawn at the cut lines inside the overlap of neighbouring tiles.
+    #[serde(rename = "crop_marks")]
+    pub crop_marks: bool,
+    /// The number of pages placed onto every sheet of paper.
+    #[serde(rename = "pages_per_sheet")]
+    pub pages_per_sheet: PagesPerSheet,
+    /// The gutter between the pages on a sheet, in document coordinate space.
+    #[serde(rename = "n_up_gutter", with = "rnote_compose::serialize::f64_dp3")]
+    pub n_up_gutter: f64,
+}
+
+impl Default for PrintPrefs {
+    fn default() -> Self {
+        Self {
+            with_background: true,
+            with_pattern: true,
+            page_order: SplitOrder::default(),
+            tile_oversized: false,
+            tile_overlap: 24.0,
+            crop_marks: true,
+            pages_per_sheet: PagesPerSheet::default(),
+            n_up_gutter: 12.0,
+        }
+    }
+}
+
+impl PrintPrefs {
+    /// The length of the crop marks, in document coordinate space.
+    const CROP_MARK_LENGTH: f64 = 12.0;
+    /// The line width of the crop marks, in document coordinate space.
+    const CROP_MARK_WIDTH: f64 = 0.5;
+}
+
+/// A single printed sheet, either an entire document page or a tile of an oversized page.
+#[derive(Debug, Clone)]
+pub struct PrintTile {
+    /// The content printed on the sheet. Its bounds are the part of the page that is covered by the tile.
+    pub content: StrokeContent,
+    /// The bounds of the sheet in document coordinate space.
+    ///
+    /// For tiles of oversized pages these have the size of the paper and might extend beyond the page.
+    pub bounds: Aabb,
+    /// The index of the document page the tile belongs to.
+    pub page: usize,
+    /// The column and row of the tile inside the page.
+    pub position: (usize, usize),
+    /// The number of tile columns and rows the page is split into.
+    pub n_tiles: (usize, usize),
+    /// The overlap with the neighbouring tiles.
+    pub overlap: f64,
+}
+
+impl PrintTile {
+    /// Whether the tile is part of a page that is split into multiple tiles.
+    pub fn is_partial(&self) -> bool {
+        self.n_tiles != (1, 1)
+    }
+
+    /// The bounds inside the tile where it is cut apart from its neighbours,
+    /// which is in the middle of the overlap.
+    pub fn cut_bounds(&self) -> Aabb {
+        let half_overlap = self.overlap * 0.5;
+        let (col, row) = self.position;
+        let (n_cols, n_rows) = self.n_tiles;
+        let content_bounds = self.content.bounds.unwrap_or(self.bounds);
+        Aabb::new(
+            na::point![
+                if col > 0 {
+                    self.bounds.mins[0] + half_overlap
+                } else {
+                    self.bounds.mins[0]
+                },
+                if row > 0 {
+                    self.bounds.mins[1] + half_overlap
+                } else {
+                    self.bounds.mins[1]
+                }
+            ],
+            na::point![
+                if col + 1 < n_cols {
+                    self.bounds.maxs[0] - half_overlap
+                } else {
+                    content_bounds.maxs[0]
+                },
+                if row + 1 < n_rows {
+                    self.bounds.maxs[1] - half_overlap
+                } else {
+                    content_bounds.maxs[1]
+                }
+            ],
+        )
+    }
+
+    /// Draw the tile onto a cairo context, with its origin at the top left corner of the sheet.
+    ///
+    /// The context is expected to be scaled to document coordinate space. Entire pages are scaled to fit onto the paper,
+    /// and additionally into the printable area when margins are given. Tiles of oversized pages are drawn at actual size.
+    pub fn draw_to_cairo(
+        &self,
+        cairo_cx: &cairo::Context,
+        paper_size: na::Vector2<f64>,
+        print_prefs: &PrintPrefs,
+        printable_area_margins: Option<PageMargins>,
+        image_scale: f64,
+    ) -> anyhow::Result<()> {
+        let extents = self.bounds.extents();
+        let scale = if self.is_partial() {
+            1.0
+        } else {
+            (paper_size[0] / extents[0]).min(paper_size[1] / extents[1])
+        };
+
+        cairo_cx.save()?;
+        cairo_cx.scale(scale, scale);
+        if let Some(margins) = printable_area_margins.filter(|_| !self.is_partial()) {
+            let (scale, offset) = margins.fit_into_printable_area(extents);
+            cairo_cx.translate(offset[0], offset[1]);
+            cairo_cx.scale(scale, scale);
+        }
+        cairo_cx.translate(-self.bounds.mins[0], -self.bounds.mins[1]);
+        self.content.draw_to_cairo(
+            cairo_cx,
+            print_prefs.with_background,
+            print_prefs.with_pattern,
+            0.0,
+            image_scale,
+        )?;
+        if print_prefs.crop_marks && self.is_partial() {
+            self.draw_crop_marks(cairo_cx)?;
+        }
+        cairo_cx.restore()?;
+        Ok(())
+    }
+
+    /// Draws short marks along the cut lines at the edges of the tile.
+    fn draw_crop_marks(&self, cairo_cx: &cairo::Context) -> anyhow::Result<()> {
+        let cut_bounds = self.cut_bounds();
+        let (col, row) = self.position;
+        let (n_cols, n_rows) = self.n_tiles;
+        let length = PrintPrefs::CROP_MARK_LENGTH;
+
+        cairo_cx.save()?;
+        cairo_cx.set_source_rgba(0.0, 0.0, 0.0, 1.0);
+        cairo_cx.set_line_width(PrintPrefs::CROP_MARK_WIDTH);
+        let vertical_cut = |x: f64| {
+            cairo_cx.move_to(x, self.bounds.mins[1]);
+            cairo_cx.line_to(x, self.bounds.mins[1] + length);
+            cairo_cx.move_to(x, self.bounds.maxs[1] - length);
+            cairo_cx.line_to(x, self.bounds.maxs[1]);
+        };
+        if col > 0 {
+            vertical_cut(cut_bounds.mins[0]);
+        }
+        if col + 1 < n_cols {
+            vertical_cut(cut_bounds.maxs[0]);
+        }
+        let horizontal_cut = |y: f64| {
+            cairo_cx.move_to(self.bounds.mins[0], y);
+            cairo_cx.line_to(self.bounds.mins[0] + length, y);
+            cairo_cx.move_to(self.bounds.maxs[0] - length, y);
+            cairo_cx.line_to(self.bounds.maxs[0], y);
+        };
+        if row > 0 {
+            horizontal_cut(cut_bounds.mins[1]);
+        }
+        if row + 1 < n_rows {
+            horizontal_cut(cut_bounds.maxs[1]);
+        }
+        cairo_cx.stroke()?;
+        cairo_cx.restore()?;
+        Ok(())
+    }
+}
+
+/// The paper and resolution of an external print context.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrintTarget {
+    /// The size of the paper in device units of the print context.
+    pub paper_size: na::Vector2<f64>,
+    /// The horizontal and vertical resolution of the print context in device units per inch.
+    pub dpi: na::Vector2<f64>,
+}
+
+impl PrintTarget {
+    /// The paper size in document coordinate space.
+    pub fn paper_size_doc(&self, doc_dpi: f64) -> na::Vector2<f64> {
+        self.paper_size.component_div(&self.dpi) * doc_dpi
+    }
+}
+
+/// The sheets of a print job together with everything needed to draw them onto a print context.
+///
+/// Generating the sheets requires extracting the content of all pages, so the job should be kept around
+/// while printing instead of being regenerated for every page.
+#[derive(Debug, Clone)]
+pub struct PrintJob {
+    tiles: Vec<PrintTile>,
+    /// The placement of the tiles on the printed sheets.
+    sheets: Vec<Sheet>,
+    target: PrintTarget,
+    doc_dpi: f64,
+    print_prefs: PrintPrefs,
+    printable_area_margins: Option<PageMargins>,
+}
+
+impl PrintJob {
+    /// The upper limit of the image scale-factor for strokes that are drawn as bitmap images,
+    /// to keep the memory usage at high printer resolutions in check.
+    pub const IMAGE_SCALE_MAX: f64 = 6.0;
+
+    /// The number of printed sheets.
+    pub fn n_pages(&self) -> usize {
+        self.sheets.len()
+    }
+
+    pub fn tiles(&self) -> &[PrintTile] {
+        &self.tiles
+    }
+
+    /// Draw the sheets in the page range onto the cairo context of the print target.
+    ///
+    /// The content is drawn as vectors in device units at the resolution of the print target.
+    /// A new page is started between the sheets, but not after the last one,
+    /// so that print contexts that draw a single page at a time can be passed in as well.
+    pub fn draw_pages_to_cairo(
+        &self,
+        cairo_cx: &cairo::Context,
+        page_range: PageRange,
+    ) -> anyhow::Result<()> {
+        let n_pages = self.sheets.len();
+        let last = page_range.last.min(n_pages.saturating_sub(1));
+        if n_pages == 0 || page_range.first > last {
+            return Err(anyhow::anyhow!(
+                "The print page range {page_range:?} does not contain any of the {n_pages} pages."
+            ));
+        }
+        let device_scale = self.target.dpi / self.doc_dpi;
+        let image_scale = device_scale.max().clamp(1.0, Self::IMAGE_SCALE_MAX);
+        let paper_size = self.target.paper_size_doc(self.doc_dpi);
+
+        for (i, sheet) in self.sheets[page_range.first..=last].iter().enumerate() {
+            if i > 0 {
+                cairo_cx.show_page().map_err(|e| {
+                    anyhow::anyhow!("Showing page failed while printing, Err: {e:?}")
+                })?;
+            }
+            cairo_cx.save()?;
+            cairo_cx.scale(device_scale[0], device_scale[1]);
+            for slot in sheet.slots.iter() {
+                let tile = &self.tiles[slot.page];
+                if self.print_prefs.pages_per_sheet == PagesPerSheet::One {
+                    tile.draw_to_cairo(
+                        cairo_cx,
+                        paper_size,
+                        &self.print_prefs,
+                        self.printable_area_margins,
+                        image_scale,
+                    )?;
+                } else {
+                    cairo_cx.save()?;
+                    cairo_cx.translate(slot.offset[0], slot.offset[1]);
+                    cairo_cx.scale(slot.scale, slot.scale);
+                    tile.draw_to_cairo(
+                        cairo_cx,
+                        tile.bounds.extents(),
+                        &self.print_prefs,
+                        None,
+                        image_scale,
+                    )?;
+                    cairo_cx.restore()?;
+                }
+            }
+            cairo_cx.restore()?;
+        }
+        Ok(())
+    }
+}
+
+impl RnoteEngine {
+    /// Create a print job for the print target from the current document and the print preferences.
+    pub fn print_job(&self, target: PrintTarget) -> PrintJob {
+        let doc_dpi = self.document.format.dpi;
+        let margins = self.document.format.margins;
+        let printable_area_margins = (self.export_prefs.doc_export_prefs.fit_to_printable_area
+            && !margins.is_zero())
+        .then_some(margins);
+
+        let print_prefs = self.export_prefs.print_prefs;
+        let paper_size = target.paper_size_doc(doc_dpi);
+        let tiles = self.gen_print_tiles(paper_size);
+        let sheets = match print_prefs.pages_per_sheet {
+            PagesPerSheet::One => (0..tiles.len())
+                .map(|page| Sheet {
+                    size: paper_size,
+                    slots: vec![SheetSlot {
+                        page,
+                        scale: 1.0,
+                        offset: na::Vector2::zeros(),
+                    }],
+                })
+                .collect(),
+            // The orientation of the paper is given by the print target
+            pages_per_sheet => imposition::impose_n_up(
+                &tiles
+                    .iter()
+                    .map(|tile| tile.bounds.extents())
+                    .collect::<Vec<na::Vector2<f64>>>(),
+                &[paper_size],
+                pages_per_sheet,
+                print_prefs.n_up_gutter,
+            ),
+        };
+
+        PrintJob {
+            tiles,
+            sheets,
+            target,
+            doc_dpi,
+            print_prefs,
+            printable_area_margins,
+        }
+    }
+
+    /// Draw the sheets in the page range directly onto an external cairo print context.
+    ///
+    /// See [PrintJob::draw_pages_to_cairo()]. When drawing page by page, prefer creating a [PrintJob] once.
+    pub fn print_pages_to_cairo(
+        &self,
+        cairo_cx: &cairo::Context,
+        target: PrintTarget,
+        page_range: PageRange,
+    ) -> anyhow::Result<()> {
+        self.print_job(target)
+            .draw_pages_to_cairo(cairo_cx, page_range)
+    }
+
+    /// Split the document pages into the sheets that get printed onto paper with the given size,
+    /// in document coordinate space.
+    ///
+    /// Pages that exceed the paper size are split into tiles when enabled in the print preferences.
+    pub fn gen_print_tiles(&self, paper_size: na::Vector2<f64>) -> Vec<PrintTile> {
+        let print_prefs = self.export_prefs.print_prefs;
+
+        self.extract_pages_content(print_prefs.page_order)
+            .into_iter()
+            .enumerate()
+            .flat_map(|(page, page_content)| {
+                let Some(page_bounds) = page_content.bounds else {
+                    return vec![];
+                };
+                if !print_prefs.tile_oversized || fits_onto_paper(page_bounds, paper_size) {
+                    return vec![PrintTile {
+                        content: page_content,
+                        bounds: page_bounds,
+                        page,
+                        position: (0, 0),
+                        n_tiles: (1, 1),
+                        overlap: 0.0,
+                    }];
+                }
+                split_into_tiles(page, page_content, page_bounds, paper_size, &print_prefs)
+            })
+            .collect()
+    }
+}
+
+fn fits_onto_paper(bounds: Aabb, paper_size: na::Vector2<f64>) -> bool {
+    const TOLERANCE: f64 = 0.5;
+    let extents = bounds.extents();
+    extents[0] <= paper_size[0] + TOLERANCE && extents[1] <= paper_size[1] + TOLERANCE
+}
+
+fn split_into_tiles(
+    page: usize,
+    page_content: StrokeContent,
+    page_bounds: Aabb,
+    paper_size: na::Vector2<f64>,
+    print_prefs: &PrintPrefs,
+) -> Vec<PrintTile> {
+    // The overlap can't take up more than half of the paper, else the tiles would not advance
+    let overlap = print_prefs
+        .tile_overlap
+        .clamp(0.0, paper_size[0].min(paper_size[1]) * 0.5);
+    let step = paper_size.add_scalar(-overlap);
+    let extents = page_bounds.extents();
+    let n_cols = (((extents[0] - overlap) / step[0]).ceil() as usize).max(1);
+    let n_rows = (((extents[1] - overlap) / step[1]).ceil() as usize).max(1);
+
+    let mut tiles = Vec::with_capacity(n_cols * n_rows);
+    for row in 0..n_rows {
+        for col in 0..n_cols {
+            let mins = page_bounds.mins + na::vector![col as f64 * step[0], row as f64 * step[1]];
+            let bounds = Aabb::new(mins, mins + paper_size);
+            let Some(content_bounds) = bounds.intersection(&page_bounds) else {
+                continue;
+            };
+            let strokes = page_content
+                .strokes
+                .iter()
+                .filter(|stroke| stroke.bounds().intersects(&content_bounds))
+                .cloned()
+                .collect();
+            tiles.push(PrintTile {
+                content: StrokeContent::default()
+                    .with_strokes(strokes)
+                    .with_bounds(Some(content_bounds))
+                    .with_background(page_content.background),
+                bounds,
+                page,
+                position: (col, row),
+                n_tiles: (n_cols, n_rows),
+                overlap,
+            });
+        }
+    }
+    tiles
+}
@@ -0,0 +1,165 @@
+// Imports
+use super::RnoteEngine;
+use crate::store::StrokeKey;
+use crate::WidgetFlags;
+use p2d::bounding_volume::BoundingVolume;
+use rnote_compose::shapes::ShapeBehaviour;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Preferences for the memory that is used by the render caches.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "render_cache_prefs")]
+pub struct RenderCachePrefs {
+    /// Whether the memory budget is enforced.
+    #[serde(rename = "budget_enabled")]
+    pub budget_enabled: bool,
+    /// The memory budget in MiB.
+    #[serde(rename = "budget_mib")]
+    pub budget_mib: u32,
+}
+
+impl Default for RenderCachePrefs {
+    fn default() -> Self {
+        Self {
+            budget_enabled: true,
+            budget_mib: 512,
+        }
+    }
+}
+
+impl RenderCachePrefs {
+    /// The minimum memory budget in MiB.
+    pub const BUDGET_MIB_MIN: u32 = 32;
+    /// The maximum memory budget in MiB.
+    pub const BUDGET_MIB_MAX: u32 = 16384;
+
+    /// The memory budget in bytes.
+    pub fn budget_bytes(&self) -> usize {
+        self.budget_mib
+            .clamp(Self::BUDGET_MIB_MIN, Self::BUDGET_MIB_MAX) as usize
+            * 1024
+            * 1024
+    }
+}
+
+/// The memory held by the render caches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderCacheUsage {
+    /// The bytes held by the rendering images of the strokes.
+    pub stroke_images_bytes: usize,
+    /// The number of strokes that hold rendering images.
+    pub n_strokes_w_images: usize,
+    /// The bytes held by the composite images of the level of detail rendering.
+    pub lod_tiles_bytes: usize,
+}
+
+impl RenderCacheUsage {
+    /// The total bytes held by the render caches.
+    pub fn total_bytes(&self) -> usize {
+        self.stroke_images_bytes + self.lod_tiles_bytes
+    }
+}
+
+impl RnoteEngine {
+    /// The minimum interval between enforcing the budget when rendered images arrive.
+    const RENDER_CACHE_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+    /// The memory currently held by the render caches.
+    pub fn render_cache_usage(&self) -> RenderCacheUsage {
+        let keys = self.store.keys_w_rendering_images();
+        RenderCacheUsage {
+            stroke_images_bytes: keys
+                .iter()
+                .map(|&key| self.store.rendering_images_bytes(key))
+                .sum(),
+            n_strokes_w_images: keys.len(),
+            lod_tiles_bytes: self.lod_tiles_bytes(),
+        }
+    }
+
+    /// Enforce the memory budget of the render caches, if it was not checked recently.
+    pub(super) fn enforce_render_cache_budget_throttled(&mut self) -> WidgetFlags {
+        let now = Instant::now();
+        if self
+            .render_cache_checked
+            .map(|checked| now.duration_since(checked) < Self::RENDER_CACHE_CHECK_INTERVAL)
+            .unwrap_or(false)
+        {
+            return WidgetFlags::default();
+        }
+        self.enforce_render_cache_budget()
+    }
+
+    /// Enforce the memory budget of the render caches.
+    ///
+    /// When the budget is exceeded, the rendering images of the strokes are downgraded to a lower resolution,
+    /// starting with the strokes that are farthest away from the viewport center.
+    /// If that is not enough, the renderings outside of the viewport are dropped.
+    /// Downgraded images are replaced with full resolution ones when the strokes are rendered again, e.g. after zooming,
+    /// or once the budget has room for them again.
+    pub fn enforce_render_cache_budget(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.render_cache_checked = Some(Instant::now());
+        if !self.render_cache_prefs.budget_enabled {
+            self.store.set_rerender_downgraded(true);
+            return widget_flags;
+        }
+        let budget = self.render_cache_prefs.budget_bytes();
+        let mut usage = self.render_cache_usage().total_bytes();
+        // Rendering the downgraded images in full resolution again needs about four times their size
+        self.store
+            .set_rerender_downgraded(usage + 3 * self.store.downgraded_rendering_bytes() <= budget);
+        if usage <= budget {
+            return widget_flags;
+        }
+        let viewport = self.camera.viewport();
+        let viewport_center = viewport.center();
+
+        let mut keys = self.store.keys_w_rendering_images();
+        keys.sort_unstable_by(|&first, &second| {
+            let distance = |key: StrokeKey| {
+                self.store
+                    .get_stroke_ref(key)
+                    .map(|stroke| (stroke.bounds().center() - viewport_center).norm())
+                    .unwrap_or(f64::MAX)
+            };
+            // Farthest first
+            distance(second).total_cmp(&distance(first))
+        });
+
+        for &key in keys.iter() {
+            if usage <= budget {
+                break;
+            }
+            usage = usage.saturating_sub(self.store.downgrade_rendering(key));
+        }
+
+        if usage > budget {
+            usage = usage.saturating_sub(self.lod_evict_tiles_outside_viewport());
+        }
+        if usage > budget {
+            for &key in keys.iter() {
+                if usage <= budget {
+                    break;
+                }
+                let outside_viewport = self
+                    .store
+                    .get_stroke_ref(key)
+                    .map(|stroke| !stroke.bounds().intersects(&viewport))
+                    .unwrap_or(true);
+                if outside_viewport {
+                    usage = usage.saturating_sub(self.store.evict_rendering(key));
+                }
+            }
+        }
+        if usage > budget {
+            log::debug!(
+                "render caches exceed the budget of {budget} bytes with {usage} bytes after downgrading the renderings"
+            );
+        }
+
+        widget_flags.redraw = true;
+        widget_flags
+    }
+}
@@ -1,12 +1,13 @@
 // Imports
-use super::{visual_debug, EngineView};
+use super::{visual_debug, EngineView, StrokeContent};
+use crate::render;
 use crate::utils::{GdkRGBAHelpers, GrapheneRectHelpers};
-use crate::{Document, DrawOnDocBehaviour, RnoteEngine};
+use crate::{Camera, Document, DrawOnDocBehaviour, RnoteEngine};
 use gtk4::{gdk, graphene, gsk, prelude::*, Snapshot};
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use piet::RenderContext;
-use rnote_compose::color;
 use rnote_compose::helpers::{AabbHelpers, Affine2Helpers, SplitOrder};
+use rnote_compose::{color, Color};
 
 impl RnoteEngine {
     /// Update the background rendering for the current viewport.
@@ -46,16 +47,24 @@ impl RnoteEngine {
     }
 
     /// Update the content rendering for the current viewport.
+    ///
+    /// When zoomed out far, composite images of the regions are rendered instead of the individual strokes.
+    /// The render cache budget is enforced first, so downgraded renderings are only rendered again when it has room.
     pub fn update_content_rendering_current_viewport(&mut self) {
-        let viewport = self.camera.viewport();
-        let image_scale = self.camera.image_scale();
-
-        self.store.regenerate_rendering_in_viewport_threaded(
-            self.tasks_tx(),
-            false,
-            viewport,
-            image_scale,
-        );
+        // Is followed by a redraw, so the widget flags can be ignored
+        let _ = self.enforce_render_cache_budget();
+        self.update_lod_rendering_current_viewport();
+        if !self.lod_active() {
+            let viewport = self.camera.viewport();
+            let image_scale = self.camera.image_scale();
+
+            self.store.regenerate_rendering_in_viewport_threaded(
+                self.tasks_tx(),
+                false,
+                viewport,
+                image_scale,
+            );
+        }
     }
 
     /// Update the content and background rendering for the current viewport.
@@ -71,6 +80,9 @@ impl RnoteEngine {
         self.store.clear_rendering();
         self.background_tile_image.take();
         self.background_rendernodes.clear();
+        self.viewports_clear_rendering();
+        self.overview_clear_cache();
+        self.lod_clear_cache();
     }
 
     /// Regenerate the background tile image and updates the background rendering.
@@ -85,6 +97,41 @@ impl RnoteEngine {
         }
     }
 
+    /// Render the background and the strokes inside an arbitrary region of the document into a single image.
+    ///
+    /// Does not depend on GTK or the current camera and rendering state,
+    /// so it can be used by the CLI, thumbnailers and tests.
+    /// The image covers exactly the region, with a pixel size of the region extents multiplied by the image scale.
+    pub fn render_region(&self, region: Aabb, image_scale: f64) -> anyhow::Result<render::Image> {
+        if !image_scale.is_finite() || image_scale <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "rendering region failed, invalid image scale {image_scale}"
+            ));
+        }
+        region.assert_valid()?;
+        if region.volume() <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "rendering region failed, region {region:?} is empty"
+            ));
+        }
+        let content = StrokeContent::default()
+            .with_strokes(
+                self.store.get_strokes_arc(
+                    &self
+                        .store
+                        .stroke_keys_as_rendered_intersecting_bounds(region),
+                ),
+            )
+            .with_bounds(Some(region))
+            .with_background(Some(self.document.background));
+
+        render::Image::gen_with_cairo_exact(
+            |cairo_cx| content.draw_to_cairo(cairo_cx, true, true, 0.0, image_scale),
+            region,
+            image_scale,
+        )
+    }
+
     /// Draws the entire engine (doc, pens, strokes, selection, ..) to a GTK snapshot.
     pub fn draw_to_gtk_snapshot(
         &self,
@@ -98,15 +145,35 @@ impl RnoteEngine {
         snapshot.save();
         snapshot.transform(Some(&camera_transform));
         self.draw_document_shadow_to_gtk_snapshot(snapshot);
-        self.draw_background_to_gtk_snapshot(snapshot)?;
-        self.draw_format_borders_to_gtk_snapshot(snapshot)?;
+        self.draw_background_to_gtk_snapshot(snapshot, &self.background_rendernodes)?;
+        self.draw_format_borders_to_gtk_snapshot(snapshot, &self.camera)?;
         snapshot.restore();
         self.draw_origin_indicator_to_gtk_snapshot(snapshot)?;
         snapshot.save();
         snapshot.transform(Some(&camera_transform));
-        self.store
-            .draw_strokes_to_gtk_snapshot(snapshot, doc_bounds, viewport);
+        if self.lod_active() {
+            self.draw_lod_to_gtk_snapshot(snapshot, doc_bounds);
+        } else {
+            self.store.draw_strokes_to_gtk_snapshot(
+                snapshot,
+                doc_bounds,
+                viewport,
+                self.camera.image_scale(),
+            );
+        }
+        self.draw_heatmap_to_gtk_snapshot(snapshot);
         snapshot.restore();
+        let engine_view = EngineView {
+            tasks_tx: self.tasks_tx(),
+            pens_config: &self.pens_config,
+            doc: &self.document,
+            store: &self.store,
+            camera: &self.camera,
+            audioplayer: &self.audioplayer,
+        };
+        self.pens_config
+            .drawing_aids
+            .draw_on_doc_to_gtk_snapshot(snapshot, &engine_view)?;
         /*
                let cairo_cx = snapshot.append_cairo(&graphene::Rect::from_p2d_aabb(surface_bounds));
                let mut piet_cx = piet_cairo::CairoRenderContext::new(&cairo_cx);
@@ -118,17 +185,9 @@ impl RnoteEngine {
                    self.camera.image_scale(),
                );
         */
-        self.penholder.draw_on_doc_to_gtk_snapshot(
-            snapshot,
-            &EngineView {
-                tasks_tx: self.tasks_tx(),
-                pens_config: &self.pens_config,
-                doc: &self.document,
-                store: &self.store,
-                camera: &self.camera,
-                audioplayer: &self.audioplayer,
-            },
-        )?;
+        self.penholder
+            .draw_on_doc_to_gtk_snapshot(snapshot, &engine_view)?;
+        self.draw_focus_indicator_to_gtk_snapshot(snapshot)?;
 
         if self.visual_debug {
             snapshot.save();
@@ -142,7 +201,7 @@ impl RnoteEngine {
         Ok(())
     }
 
-    fn draw_document_shadow_to_gtk_snapshot(&self, snapshot: &Snapshot) {
+    pub(super) fn draw_document_shadow_to_gtk_snapshot(&self, snapshot: &Snapshot) {
         let shadow_width = Document::SHADOW_WIDTH;
         let shadow_offset = Document::SHADOW_OFFSET;
         let doc_bounds = self.document.bounds();
@@ -168,7 +227,11 @@ impl RnoteEngine {
         );
     }
 
-    fn draw_background_to_gtk_snapshot(&self, snapshot: &Snapshot) -> anyhow::Result<()> {
+    pub(super) fn draw_background_to_gtk_snapshot(
+        &self,
+        snapshot: &Snapshot,
+        background_rendernodes: &[gsk::RenderNode],
+    ) -> anyhow::Result<()> {
         let doc_bounds = self.document.bounds();
 
         snapshot.push_clip(&graphene::Rect::from_p2d_aabb(doc_bounds));
@@ -183,7 +246,7 @@ impl RnoteEngine {
             .upcast(),
         );
 
-        for r in self.background_rendernodes.iter() {
+        for r in background_rendernodes.iter() {
             snapshot.append_node(r);
         }
 
@@ -191,11 +254,15 @@ impl RnoteEngine {
         Ok(())
     }
 
-    fn draw_format_borders_to_gtk_snapshot(&self, snapshot: &Snapshot) -> anyhow::Result<()> {
+    pub(super) fn draw_format_borders_to_gtk_snapshot(
+        &self,
+        snapshot: &Snapshot,
+        camera: &Camera,
+    ) -> anyhow::Result<()> {
         if self.document.format.show_borders {
-            let total_zoom = self.camera.total_zoom();
+            let total_zoom = camera.total_zoom();
             let border_width = 1.0 / total_zoom;
-            let viewport = self.camera.viewport();
+            let viewport = camera.viewport();
             let doc_bounds = self.document.bounds();
 
             snapshot.push_clip(&graphene::Rect::from_p2d_aabb(doc_bounds.loosened(2.0)));
@@ -230,7 +297,29 @@ impl RnoteEngine {
                         gdk::RGBA::from_compose_color(self.document.format.border_color),
                         gdk::RGBA::from_compose_color(self.document.format.border_color),
                     ],
-                )
+                );
+
+                // The margins are drawn as subtle guides of the printable area
+                if !self.document.format.margins.is_zero() {
+                    let border_color = self.document.format.border_color;
+                    let guide_color = gdk::RGBA::from_compose_color(Color {
+                        a: border_color.a * 0.4,
+                        ..border_color
+                    });
+                    let printable_area = self.document.format.margins.printable_area(page_bounds);
+
+                    snapshot.append_border(
+                        &gsk::RoundedRect::new(
+                            graphene::Rect::from_p2d_aabb(printable_area),
+                            graphene::Size::zero(),
+                            graphene::Size::zero(),
+                            graphene::Size::zero(),
+                            graphene::Size::zero(),
+                        ),
+                        &[border_width as f32; 4],
+                        &[guide_color; 4],
+                    )
+                }
             }
 
             snapshot.pop();
@@ -239,6 +328,42 @@ impl RnoteEngine {
         Ok(())
     }
 
+    /// Draw the indicator around the stroke that has the keyboard focus.
+    ///
+    /// Expects that the snapshot is untransformed in surface coordinate space.
+    fn draw_focus_indicator_to_gtk_snapshot(&self, snapshot: &Snapshot) -> anyhow::Result<()> {
+        const FOCUS_COLOR: piet::Color = color::GNOME_BLUES[3];
+        const FOCUS_WIDTH: f64 = 2.0;
+        const FOCUS_MARGIN: f64 = 4.0;
+        let Some(bounds) = self.focused_stroke_bounds() else {
+            return Ok(());
+        };
+        let total_zoom = self.camera.total_zoom();
+        let bounds = bounds.loosened(FOCUS_MARGIN / total_zoom);
+        if !bounds.intersects(&self.camera.viewport()) {
+            return Ok(());
+        }
+        let bounds_on_surface = bounds
+            .extend_by(na::Vector2::repeat(FOCUS_WIDTH / total_zoom))
+            .scale(total_zoom)
+            .translate(-self.camera.offset());
+
+        let cairo_cx =
+            snapshot.append_cairo(&graphene::Rect::from_p2d_aabb(bounds_on_surface.ceil()));
+        let mut piet_cx = piet_cairo::CairoRenderContext::new(&cairo_cx);
+        let mut stroke_style = piet::StrokeStyle::new();
+        stroke_style.set_dash_pattern(vec![4.0 / total_zoom, 3.0 / total_zoom]);
+        piet_cx.transform(self.camera.transform().to_kurbo());
+        piet_cx.stroke_styled(
+            kurbo::RoundedRect::from_rect(bounds.to_kurbo_rect(), FOCUS_MARGIN / total_zoom),
+            &FOCUS_COLOR,
+            FOCUS_WIDTH / total_zoom,
+            &stroke_style,
+        );
+
+        Ok(())
+    }
+
     /// Draw the document origin indicator cross.
     ///
     /// Expects that the snapshot is untransformed in surface coordinate space.
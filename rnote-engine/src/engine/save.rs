@@ -0,0 +1,230 @@
+// Imports
+use super::export::rnote_bytes_from_snapshot;
+use super::RnoteEngine;
+use anyhow::Context;
+use futures::channel::oneshot;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Preferences for saving documents to files.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "save_prefs")]
+pub struct SavePrefs {
+    /// Whether the previous versions of the file are kept as backups when it is saved.
+    #[serde(rename = "backups_enabled")]
+    pub backups_enabled: bool,
+    /// The number of backups that are kept, named `<file>.bak1` (the most recent one) to `<file>.bak<count>`.
+    #[serde(rename = "backup_count")]
+    pub backup_count: u32,
+    /// Backups that are older than this number of days are removed. Zero keeps them regardless of their age.
+    #[serde(rename = "backup_max_age_days")]
+    pub backup_max_age_days: u32,
+}
+
+impl Default for SavePrefs {
+    fn default() -> Self {
+        Self {
+            backups_enabled: false,
+            backup_count: 3,
+            backup_max_age_days: 30,
+        }
+    }
+}
+
+impl SavePrefs {
+    /// The maximum number of backups.
+    pub const BACKUP_COUNT_MAX: u32 = 20;
+
+    fn backup_max_age(&self) -> Option<Duration> {
+        (self.backup_max_age_days > 0)
+            .then(|| Duration::from_secs(u64::from(self.backup_max_age_days) * 24 * 60 * 60))
+    }
+}
+
+impl RnoteEngine {
+    /// Save the current document as a .rnote file to the given path.
+    ///
+    /// The file is written atomically, so it either contains the previous or the new document,
+    /// even when the system crashes or loses power while saving.
+    /// When enabled in the save preferences, the previous file is kept as backup.
+    pub fn save_as_rnote_file(&self, path: PathBuf) -> oneshot::Receiver<anyhow::Result<()>> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<()>>();
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let engine_snapshot = self.take_snapshot_deferred();
+        let save_prefs = self.save_prefs;
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<()> {
+                let bytes = rnote_bytes_from_snapshot(engine_snapshot.finish(), &file_name)?;
+                if save_prefs.backups_enabled {
+                    // Failing backups should not prevent saving the document
+                    if let Err(e) = rotate_backups(&path, &save_prefs) {
+                        log::error!(
+                            "rotating backups of file `{}` failed, Err: {e:?}",
+                            path.display()
+                        );
+                    }
+                }
+                write_file_atomic(&path, &bytes)
+            };
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("Sending result to receiver in save_as_rnote_file() failed. Receiver was already dropped.");
+            }
+        });
+        oneshot_receiver
+    }
+}
+
+/// Write the bytes atomically to the file at the given path.
+///
+/// The bytes are written to a temporary file in the same directory and synced to disk, before the temporary file
+/// replaces the file at the path. Readers will never see a partially written file.
+pub fn write_file_atomic(path: impl AsRef<Path>, bytes: &[u8]) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let dir = parent_dir(path);
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("path `{}` has no file name", path.display()))?;
+    let tmp_path = dir.join(format!(
+        ".{}.tmp-{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let write_tmp = || -> std::io::Result<()> {
+        let mut tmp_file = std::fs::File::create(&tmp_path)?;
+        // Keep the permissions of the replaced file
+        if let Ok(metadata) = std::fs::metadata(path) {
+            tmp_file.set_permissions(metadata.permissions())?;
+        }
+        tmp_file.write_all(bytes)?;
+        tmp_file.sync_all()
+    };
+    if let Err(e) = write_tmp() {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(e)
+            .with_context(|| format!("writing temporary file `{}` failed", tmp_path.display()));
+    }
+    if let Err(e) = std::fs::rename(&tmp_path, path) {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(e).with_context(|| {
+            format!(
+                "replacing file `{}` with temporary file failed",
+                path.display()
+            )
+        });
+    }
+    sync_dir(dir);
+
+    Ok(())
+}
+
+/// The path of the backup with the given number, starting at 1 for the most recent backup.
+pub fn backup_path(path: impl AsRef<Path>, n: u32) -> PathBuf {
+    let mut backup = path.as_ref().as_os_str().to_owned();
+    backup.push(format!(".bak{n}"));
+    PathBuf::from(backup)
+}
+
+/// Shift the existing backups of the file by one and copy the current file to the most recent backup.
+///
+/// Backups beyond the configured count or older than the configured age are removed.
+fn rotate_backups(path: &Path, save_prefs: &SavePrefs) -> anyhow::Result<()> {
+    let backup_count = save_prefs.backup_count.min(SavePrefs::BACKUP_COUNT_MAX);
+    // Also cleans up backups left over from a previously higher count
+    for n in (backup_count.max(1)..=SavePrefs::BACKUP_COUNT_MAX).rev() {
+        let backup = backup_path(path, n);
+        if backup.exists() {
+            std::fs::remove_file(&backup)
+                .with_context(|| format!("removing backup `{}` failed", backup.display()))?;
+        }
+    }
+    if backup_count == 0 || !path.exists() {
+        return Ok(());
+    }
+    for n in (1..backup_count).rev() {
+        let backup = backup_path(path, n);
+        if backup.exists() {
+            std::fs::rename(&backup, backup_path(path, n + 1))
+                .with_context(|| format!("moving backup `{}` failed", backup.display()))?;
+        }
+    }
+    let latest_backup = backup_path(path, 1);
+    std::fs::copy(path, &latest_backup).with_context(|| {
+        format!(
+            "copying file `{}` to backup `{}` failed",
+            path.display(),
+            latest_backup.display()
+        )
+    })?;
+
+    if let Some(max_age) = save_prefs.backup_max_age() {
+        let now = SystemTime::now();
+        // The most recent backup is kept, it holds the version that is replaced now
+        for n in 2..=backup_count {
+            let backup = backup_path(path, n);
+            let Ok(modified) = std::fs::metadata(&backup).and_then(|m| m.modified()) else {
+                continue;
+            };
+            if now.duration_since(modified).unwrap_or_default() > max_age {
+                std::fs::remove_file(&backup).with_context(|| {
+                    format!("removing expired backup `{}` failed", backup.display())
+                })?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// Sync the directory entries, so the rename survives a crash.
+fn sync_dir(dir: &Path) {
+    #[cfg(unix)]
+    if let Err(e) = std::fs::File::open(dir).and_then(|d| d.sync_all()) {
+        log::warn!("syncing directory `{}` failed, Err: {e:?}", dir.display());
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atomic_save_rotates_backups() {
+        let dir = std::env::temp_dir().join(format!("rnote-save-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("doc.rnote");
+        let save_prefs = SavePrefs {
+            backups_enabled: true,
+            backup_count: 2,
+            backup_max_age_days: 0,
+        };
+
+        for version in 1..=4_u8 {
+            rotate_backups(&path, &save_prefs).unwrap();
+            write_file_atomic(&path, &[version]).unwrap();
+        }
+
+        assert_eq!(std::fs::read(&path).unwrap(), vec![4]);
+        assert_eq!(std::fs::read(backup_path(&path, 1)).unwrap(), vec![3]);
+        assert_eq!(std::fs::read(backup_path(&path, 2)).unwrap(), vec![2]);
+        assert!(!backup_path(&path, 3).exists());
+        // No temporary files are left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 3);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
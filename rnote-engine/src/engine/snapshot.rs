@@ -1,14 +1,22 @@
 // Imports
-use crate::document::background;
+use super::{Bookmark, ColorHistory};
+use crate::document::{background, DocumentMetadata};
 use crate::engine::import::XoppImportPrefs;
 use crate::fileformats::{rnoteformat, xoppformat, FileFormatLoader};
-use crate::store::{ChronoComponent, StrokeKey};
+use crate::fillpatterns::FillPattern;
+use crate::fonts::EmbeddedFont;
+use crate::pens::PenStyle;
+use crate::store::{ChronoComponent, InstanceComponent, StrokeKey, TrashComponent};
+use crate::strokes::strokebehaviour::StrokeBehaviour;
 use crate::strokes::Stroke;
-use crate::{Document, RnoteEngine};
+use crate::{Document, EngineError, Progress, RnoteEngine};
 use anyhow::Context;
 use futures::channel::oneshot;
+use rnote_compose::helpers::AabbHelpers;
+use rnote_compose::shapes::ShapeBehaviour;
 use serde::{Deserialize, Serialize};
-use slotmap::{HopSlotMap, SecondaryMap};
+use slotmap::{HopSlotMap, Key, SecondaryMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 
 // An engine snapshot, used when loading/saving the current document from/into a file.
@@ -21,8 +29,89 @@ pub struct EngineSnapshot {
     pub stroke_components: Arc<HopSlotMap<StrokeKey, Arc<Stroke>>>,
     #[serde(rename = "chrono_components")]
     pub chrono_components: Arc<SecondaryMap<StrokeKey, Arc<ChronoComponent>>>,
+    #[serde(rename = "instance_components")]
+    pub instance_components: Arc<SecondaryMap<StrokeKey, Arc<InstanceComponent>>>,
     #[serde(rename = "chrono_counter")]
     pub chrono_counter: u32,
+    /// The fonts used by the text strokes, embedded when saving.
+    #[serde(rename = "embedded_fonts")]
+    pub embedded_fonts: Vec<EmbeddedFont>,
+    /// The view state when the snapshot was taken, restored when reopening the document.
+    #[serde(rename = "view_state")]
+    pub view_state: Option<ViewState>,
+    /// The recently used stroke colors in the document.
+    #[serde(rename = "color_history")]
+    pub color_history: ColorHistory,
+    /// The metadata of the document.
+    #[serde(rename = "metadata")]
+    pub metadata: DocumentMetadata,
+    /// The bookmarks of the document, by their id.
+    #[serde(rename = "bookmarks")]
+    pub bookmarks: BTreeMap<String, Bookmark>,
+    /// The fill patterns of the document.
+    ///
+    /// Also contains the patterns the shapes are filled with, when they were taken from other documents.
+    #[serde(rename = "fill_patterns")]
+    pub fill_patterns: Vec<FillPattern>,
+}
+
+/// The state of the view onto a document, so that it can be reopened where it was left off.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "view_state")]
+pub struct ViewState {
+    /// The center of the viewport, in document coordinates.
+    #[serde(rename = "viewport_center")]
+    pub viewport_center: na::Vector2<f64>,
+    #[serde(rename = "zoom")]
+    pub zoom: f64,
+    /// The view rotation in radians.
+    #[serde(rename = "rotation")]
+    pub rotation: f64,
+    #[serde(rename = "pen_style")]
+    pub pen_style: PenStyle,
+    /// The last applied preset of the pen.
+    #[serde(rename = "pen_preset")]
+    pub pen_preset: Option<usize>,
+}
+
+impl Default for ViewState {
+    fn default() -> Self {
+        Self {
+            viewport_center: na::Vector2::zeros(),
+            zoom: 1.0,
+            rotation: 0.0,
+            pen_style: PenStyle::default(),
+            pen_preset: None,
+        }
+    }
+}
+
+impl ViewState {
+    /// Whether the values are finite and the zoom is positive.
+    pub fn is_valid(&self) -> bool {
+        self.viewport_center.iter().all(|v| v.is_finite())
+            && self.zoom.is_finite()
+            && self.zoom > 0.0
+            && self.rotation.is_finite()
+    }
+}
+
+/// A report about the problems found and the data that was dropped while repairing a damaged file.
+#[derive(Debug, Clone, Default)]
+pub struct RepairReport {
+    /// Messages describing the encountered problems.
+    pub issues: Vec<String>,
+    /// The amount of strokes that could be recovered.
+    pub n_strokes_recovered: usize,
+    /// The amount of strokes that were unreadable and were dropped.
+    pub n_strokes_dropped: usize,
+}
+
+impl RepairReport {
+    /// Whether no problems were found.
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty() && self.n_strokes_dropped == 0
+    }
 }
 
 impl Default for EngineSnapshot {
@@ -31,32 +120,444 @@ impl Default for EngineSnapshot {
             document: Document::default(),
             stroke_components: Arc::new(HopSlotMap::with_key()),
             chrono_components: Arc::new(SecondaryMap::new()),
+            instance_components: Arc::new(SecondaryMap::new()),
             chrono_counter: 0,
+            embedded_fonts: vec![],
+            view_state: None,
+            color_history: ColorHistory::default(),
+            metadata: DocumentMetadata::default(),
+            bookmarks: BTreeMap::default(),
+            fill_patterns: vec![],
+        }
+    }
+}
+
+/// A snapshot that still contains the trashed strokes.
+///
+/// Taking it only clones the `Arc`s of the store components, so it is cheap enough to be taken on the main thread.
+/// Removing the trashed strokes needs to copy the components and is deferred to [DeferredSnapshot::finish],
+/// which should be called on a worker thread.
+#[derive(Debug, Clone)]
+pub struct DeferredSnapshot {
+    pub(super) snapshot: EngineSnapshot,
+    pub(super) trash_components: Arc<SecondaryMap<StrokeKey, Arc<TrashComponent>>>,
+}
+
+impl DeferredSnapshot {
+    /// Remove the trashed strokes and the links to them, finishing the snapshot.
+    pub fn finish(self) -> EngineSnapshot {
+        let Self {
+            mut snapshot,
+            trash_components,
+        } = self;
+
+        let trashed_keys = trash_components
+            .iter()
+            .filter_map(|(key, trash_comp)| if trash_comp.trashed { Some(key) } else { None })
+            .collect::<Vec<StrokeKey>>();
+        if !trashed_keys.is_empty() {
+            let stroke_components = Arc::make_mut(&mut snapshot.stroke_components);
+            for key in trashed_keys {
+                stroke_components.remove(key);
+            }
+        }
+
+        // Only keep links between remaining strokes
+        let stroke_components = &snapshot.stroke_components;
+        if snapshot.instance_components.iter().any(|(key, comp)| {
+            !stroke_components.contains_key(key) || !stroke_components.contains_key(comp.master)
+        }) {
+            Arc::make_mut(&mut snapshot.instance_components).retain(|key, comp| {
+                stroke_components.contains_key(key) && stroke_components.contains_key(comp.master)
+            });
         }
+
+        snapshot
     }
 }
 
 impl EngineSnapshot {
+    /// Remove the strokes of the instances, only keeping the links to their masters and their transforms.
+    ///
+    /// Used when saving, the strokes are regenerated from the masters when loading.
+    pub(crate) fn strip_instance_strokes(&mut self) {
+        if self.instance_components.is_empty() {
+            return;
+        }
+        let stroke_components = Arc::make_mut(&mut self.stroke_components);
+        for key in self.instance_components.keys() {
+            stroke_components.remove(key);
+        }
+    }
+
+    /// Regenerate the strokes of the instances that were stripped when saving from their masters.
+    ///
+    /// The strokes are inserted with new keys, their chrono components are kept so that the order is preserved.
+    /// Instances whose master is missing are dropped.
+    fn regenerate_instance_strokes(&mut self) {
+        let stripped = self
+            .instance_components
+            .iter()
+            .filter(|(key, _)| !self.stroke_components.contains_key(*key))
+            .map(|(key, comp)| (key, **comp))
+            .collect::<Vec<(StrokeKey, InstanceComponent)>>();
+        if stripped.is_empty() {
+            return;
+        }
+        let stroke_components = Arc::make_mut(&mut self.stroke_components);
+        let chrono_components = Arc::make_mut(&mut self.chrono_components);
+        let instance_components = Arc::make_mut(&mut self.instance_components);
+        // Remove all stale entries first, as the new keys might reuse the slots of the stripped strokes
+        let stripped = stripped
+            .into_iter()
+            .map(|(key, comp)| {
+                instance_components.remove(key);
+                (comp, chrono_components.remove(key))
+            })
+            .collect::<Vec<(InstanceComponent, Option<Arc<ChronoComponent>>)>>();
+        for (comp, chrono_comp) in stripped {
+            let Some(master) = stroke_components.get(comp.master) else {
+                continue;
+            };
+            let key = stroke_components.insert(Arc::new(comp.gen_instance_stroke(master)));
+            instance_components.insert(key, Arc::new(comp));
+            if let Some(chrono_comp) = chrono_comp {
+                chrono_components.insert(key, chrono_comp);
+            }
+        }
+    }
+
+    /// Collects and embeds the fonts that are used by the text strokes, subset to the used glyphs.
+    pub fn embed_fonts(&mut self) {
+        let families = self
+            .stroke_components
+            .values()
+            .filter_map(|stroke| match stroke.as_ref() {
+                Stroke::TextStroke(textstroke) => Some(textstroke),
+                _ => None,
+            })
+            .flat_map(|textstroke| {
+                crate::fonts::text_font_families(&textstroke.text, &textstroke.text_style)
+            })
+            .collect::<Vec<(&str, &str)>>();
+        self.embedded_fonts = crate::fonts::collect_embedded_fonts(families);
+    }
+
+    /// Adds the registered fill patterns that the shapes are filled with, but are missing in the document.
+    ///
+    /// Happens when shapes are pasted from other documents.
+    pub fn collect_fill_patterns(&mut self) {
+        let missing = self
+            .stroke_components
+            .values()
+            .filter_map(|stroke| match stroke.as_ref() {
+                Stroke::ShapeStroke(shapestroke) => shapestroke.fill_pattern.as_deref(),
+                _ => None,
+            })
+            .filter(|id| !self.fill_patterns.iter().any(|p| p.id == *id))
+            .collect::<HashSet<&str>>()
+            .into_iter()
+            .filter_map(crate::fillpatterns::fill_pattern)
+            .collect::<Vec<FillPattern>>();
+        self.fill_patterns.extend(missing);
+    }
+
+    /// Checks the document and drops strokes with invalid geometry and an invalid view state,
+    /// which would otherwise corrupt the stroke key tree and the camera.
+    ///
+    /// Advances the progress with every checked stroke.
+    fn sanitize(&mut self, progress: &Progress) -> anyhow::Result<()> {
+        validate_document(&self.document)?;
+
+        let stage = progress.stage(0.0, 1.0, self.stroke_components.len());
+        let invalid_keys = self
+            .stroke_components
+            .iter()
+            .take_while(|_| !progress.is_cancelled())
+            .filter_map(|(key, stroke)| {
+                stage.step();
+                let mut stroke = stroke.as_ref().clone();
+                stroke.update_geometry();
+                match stroke.bounds().assert_valid() {
+                    Ok(()) => None,
+                    Err(e) => {
+                        log::warn!("dropping stroke with invalid geometry while loading snapshot, Err: {e:?}");
+                        Some(key)
+                    }
+                }
+            })
+            .collect::<Vec<StrokeKey>>();
+        stage.finish()?;
+        if !invalid_keys.is_empty() {
+            let stroke_components = Arc::make_mut(&mut self.stroke_components);
+            let chrono_components = Arc::make_mut(&mut self.chrono_components);
+            let instance_components = Arc::make_mut(&mut self.instance_components);
+            for &key in invalid_keys.iter() {
+                stroke_components.remove(key);
+                chrono_components.remove(key);
+                instance_components.remove(key);
+            }
+        }
+
+        if !self.view_state.as_ref().map_or(true, ViewState::is_valid) {
+            self.view_state = None;
+        }
+
+        Ok(())
+    }
+
     /// Loads a snapshot from the bytes of a .rnote file.
     ///
+    /// The progress is advanced while loading, and loading fails with [EngineError::Cancelled] when it gets cancelled.
     /// To import this snapshot into the current engine, use `import_snapshot()`.
-    pub async fn load_from_rnote_bytes(bytes: Vec<u8>) -> anyhow::Result<Self> {
+    pub async fn load_from_rnote_bytes(bytes: Vec<u8>, progress: Progress) -> anyhow::Result<Self> {
         let (snapshot_sender, snapshot_receiver) = oneshot::channel::<anyhow::Result<Self>>();
 
         rayon::spawn(move || {
-            let result = || -> anyhow::Result<Self> {
-                let rnote_file = rnoteformat::RnoteFile::load_from_bytes(&bytes)
-                    .context("loading RnoteFile from bytes failed.")?;
-                Ok(ijson::from_value(&rnote_file.engine_snapshot)?)
-            };
-
-            if let Err(_data) = snapshot_sender.send(result()) {
+            let result = Self::parse_rnote_bytes_w_progress(&bytes, &progress);
+            // Release the file bytes before handing over the snapshot
+            drop(bytes);
+            if let Err(_data) = snapshot_sender.send(result) {
                 log::error!("Sending result to receiver in open_from_rnote_bytes() failed. Receiver was already dropped.");
             }
         });
 
         snapshot_receiver.await?
     }
+
+    /// Parses a snapshot from the bytes of a .rnote file on the current thread.
+    ///
+    /// Does not need a running main loop, so it can be used in tools and fuzz targets.
+    /// Strokes with invalid geometry are dropped.
+    pub fn parse_rnote_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        Self::parse_rnote_bytes_w_progress(bytes, &Progress::default())
+    }
+
+    /// Parses a snapshot from the bytes of a .rnote file on the current thread, advancing the progress.
+    ///
+    /// See [EngineSnapshot::parse_rnote_bytes].
+    pub fn parse_rnote_bytes_w_progress(bytes: &[u8], progress: &Progress) -> anyhow::Result<Self> {
+        let rnote_file =
+            rnoteformat::RnoteFile::load_from_bytes_w_progress(bytes, &progress.scoped(0.0, 0.8))
+                .context("loading RnoteFile from bytes failed.")?;
+        let mut snapshot: Self = ijson::from_value(&rnote_file.engine_snapshot)
+            .map_err(|e| EngineError::Decode(format!("engine snapshot is invalid, {e}")))?;
+        drop(rnote_file);
+        progress.advance_to(0.9);
+        progress.check_cancelled()?;
+        snapshot.sanitize(&progress.scoped(0.9, 1.0))?;
+        snapshot.regenerate_instance_strokes();
+        Ok(snapshot)
+    }
+
+    /// Loads a snapshot from the bytes of a possibly damaged or truncated .rnote file.
+    ///
+    /// Parses as much as possible and drops unreadable strokes.
+    /// Returns the recovered snapshot together with a report about what was dropped.
+    pub async fn repair_from_rnote_bytes(bytes: Vec<u8>) -> anyhow::Result<(Self, RepairReport)> {
+        let (snapshot_sender, snapshot_receiver) =
+            oneshot::channel::<anyhow::Result<(Self, RepairReport)>>();
+
+        rayon::spawn(move || {
+            if let Err(_data) = snapshot_sender.send(Self::parse_rnote_bytes_repaired(&bytes)) {
+                log::error!("Sending result to receiver in repair_from_rnote_bytes() failed. Receiver was already dropped.");
+            }
+        });
+
+        snapshot_receiver.await?
+    }
+
+    /// Parses a snapshot from the bytes of a possibly damaged or truncated .rnote file on the current thread.
+    ///
+    /// See [EngineSnapshot::repair_from_rnote_bytes].
+    pub fn parse_rnote_bytes_repaired(bytes: &[u8]) -> anyhow::Result<(Self, RepairReport)> {
+        let (rnote_file, issues) = rnoteformat::RnoteFile::load_from_bytes_lenient(bytes)
+            .context("loading RnoteFile from bytes failed.")?;
+        let mut report = RepairReport {
+            issues,
+            ..Default::default()
+        };
+
+        let Some(snapshot_data) = rnote_file.engine_snapshot.as_object() else {
+            return Err(
+                EngineError::Decode(String::from("engine snapshot is not an object.")).into(),
+            );
+        };
+        let mut engine = RnoteEngine::default();
+
+        match snapshot_data
+            .get("document")
+            .map(ijson::from_value::<Document>)
+        {
+            Some(Ok(document)) => match validate_document(&document) {
+                Ok(()) => engine.document = document,
+                Err(e) => report.issues.push(format!(
+                    "document is invalid, using the default document. Err: {e}"
+                )),
+            },
+            Some(Err(e)) => report.issues.push(format!(
+                "document is unreadable, using the default document. Err: {e}"
+            )),
+            None => report.issues.push(String::from(
+                "document is missing, using the default document.",
+            )),
+        }
+
+        let mut chrono_components = snapshot_data
+            .get("chrono_components")
+            .map(|data| {
+                slots_from_value(data, "chrono component", &mut report.issues)
+                    .into_iter()
+                    .filter_map(
+                        |(key, value)| match ijson::from_value::<ChronoComponent>(value) {
+                            Ok(chrono) => Some((key, chrono)),
+                            Err(e) => {
+                                report.issues.push(format!(
+                                    "chrono component at index {} is unreadable. Err: {e}",
+                                    key.0
+                                ));
+                                None
+                            }
+                        },
+                    )
+                    .collect::<HashMap<(usize, u64), ChronoComponent>>()
+            })
+            .unwrap_or_default();
+
+        let mut strokes = snapshot_data
+            .get("stroke_components")
+            .map(|data| slots_from_value(data, "stroke", &mut report.issues))
+            .unwrap_or_else(|| {
+                report.issues.push(String::from("strokes are missing."));
+                vec![]
+            })
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let stroke = ijson::from_value::<Stroke>(value)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|mut stroke| {
+                        stroke.update_geometry();
+                        stroke.bounds().assert_valid()?;
+                        Ok(stroke)
+                    });
+                match stroke {
+                    Ok(stroke) => Some((key, chrono_components.remove(&key), stroke)),
+                    Err(e) => {
+                        report.issues.push(format!(
+                            "stroke at index {} is unreadable and was dropped. Err: {e}",
+                            key.0
+                        ));
+                        report.n_strokes_dropped += 1;
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<((usize, u64), Option<ChronoComponent>, Stroke)>>();
+
+        // Instances are saved without their strokes, they are regenerated from their masters as independent strokes
+        let recovered_keys = strokes
+            .iter()
+            .enumerate()
+            .map(|(i, (key, ..))| (*key, i))
+            .collect::<HashMap<(usize, u64), usize>>();
+        let instances = snapshot_data
+            .get("instance_components")
+            .map(|data| slots_from_value(data, "instance component", &mut report.issues))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(key, _)| !recovered_keys.contains_key(key))
+            .filter_map(|(key, value)| {
+                let comp = match ijson::from_value::<InstanceComponent>(value) {
+                    Ok(comp) => comp,
+                    Err(e) => {
+                        report.issues.push(format!(
+                            "instance component at index {} is unreadable. Err: {e}",
+                            key.0
+                        ));
+                        return None;
+                    }
+                };
+                let master = comp.master.data().as_ffi();
+                let master_key = ((master & 0xffff_ffff) as usize, master >> 32);
+                let (_, _, master) = &strokes[*recovered_keys.get(&master_key)?];
+                Some((
+                    key,
+                    chrono_components.remove(&key),
+                    comp.gen_instance_stroke(master),
+                ))
+            })
+            .collect::<Vec<((usize, u64), Option<ChronoComponent>, Stroke)>>();
+        strokes.extend(instances);
+
+        // Preserve the original order, strokes without chrono component are placed on top
+        strokes.sort_by(|(_, first, _), (_, second, _)| match (first, second) {
+            (Some(first), Some(second)) => first.cmp(second),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+        report.n_strokes_recovered = strokes.len();
+        for (_, chrono, stroke) in strokes {
+            engine
+                .store
+                .insert_stroke(stroke, chrono.map(|chrono| chrono.layer));
+        }
+        let _ = engine.doc_resize_autoexpand();
+
+        let mut snapshot = engine.take_snapshot();
+        match snapshot_data
+            .get("embedded_fonts")
+            .map(ijson::from_value::<Vec<EmbeddedFont>>)
+        {
+            Some(Ok(embedded_fonts)) => snapshot.embedded_fonts = embedded_fonts,
+            Some(Err(e)) => report.issues.push(format!(
+                "embedded fonts are unreadable and were dropped. Err: {e}"
+            )),
+            None => {}
+        }
+        // The view state and color history are not essential, so they are silently dropped when unreadable
+        snapshot.view_state = snapshot_data
+            .get("view_state")
+            .and_then(|data| ijson::from_value::<ViewState>(data).ok())
+            .filter(ViewState::is_valid);
+        snapshot.color_history = snapshot_data
+            .get("color_history")
+            .and_then(|data| ijson::from_value::<ColorHistory>(data).ok())
+            .unwrap_or_default();
+        match snapshot_data
+            .get("metadata")
+            .map(ijson::from_value::<DocumentMetadata>)
+        {
+            Some(Ok(metadata)) => snapshot.metadata = metadata,
+            Some(Err(e)) => report.issues.push(format!(
+                "document metadata is unreadable and was dropped. Err: {e}"
+            )),
+            None => {}
+        }
+        match snapshot_data
+            .get("bookmarks")
+            .map(ijson::from_value::<BTreeMap<String, Bookmark>>)
+        {
+            Some(Ok(bookmarks)) => snapshot.bookmarks = bookmarks,
+            Some(Err(e)) => report.issues.push(format!(
+                "bookmarks are unreadable and were dropped. Err: {e}"
+            )),
+            None => {}
+        }
+        match snapshot_data
+            .get("fill_patterns")
+            .map(ijson::from_value::<Vec<FillPattern>>)
+        {
+            Some(Ok(fill_patterns)) => snapshot.fill_patterns = fill_patterns,
+            Some(Err(e)) => report.issues.push(format!(
+                "fill patterns are unreadable and were dropped. Err: {e}"
+            )),
+            None => {}
+        }
+
+        Ok((snapshot, report))
+    }
+
     /// Loads from the bytes of a Xournal++ .xopp file.
     ///
     /// To import this snapshot into the current engine, use `import_snapshot()`.
@@ -67,121 +568,256 @@ impl EngineSnapshot {
         let (snapshot_sender, snapshot_receiver) = oneshot::channel::<anyhow::Result<Self>>();
 
         rayon::spawn(move || {
-            let result = || -> anyhow::Result<Self> {
-                let xopp_file = xoppformat::XoppFile::load_from_bytes(&bytes)?;
-
-                // Extract the largest width of all pages, add together all heights
-                let (doc_width, doc_height) = xopp_file
-                    .xopp_root
-                    .pages
-                    .iter()
-                    .map(|page| (page.width, page.height))
-                    .fold((0_f64, 0_f64), |prev, next| {
-                        // Max of width, sum heights
-                        (prev.0.max(next.0), prev.1 + next.1)
-                    });
-                let no_pages = xopp_file.xopp_root.pages.len() as u32;
-
-                let mut engine = RnoteEngine::default();
-
-                // We convert all values from the hardcoded 72 DPI of Xopp files to the preferred dpi
-                engine.document.format.dpi = xopp_import_prefs.dpi;
-
-                engine.document.x = 0.0;
-                engine.document.y = 0.0;
-                engine.document.width = crate::utils::convert_value_dpi(
-                    doc_width,
-                    xoppformat::XoppFile::DPI,
-                    xopp_import_prefs.dpi,
-                );
-                engine.document.height = crate::utils::convert_value_dpi(
-                    doc_height,
-                    xoppformat::XoppFile::DPI,
-                    xopp_import_prefs.dpi,
-                );
-
-                engine.document.format.width = crate::utils::convert_value_dpi(
-                    doc_width,
-                    xoppformat::XoppFile::DPI,
-                    xopp_import_prefs.dpi,
-                );
-                engine.document.format.height = crate::utils::convert_value_dpi(
-                    doc_height / (no_pages as f64),
-                    xoppformat::XoppFile::DPI,
-                    xopp_import_prefs.dpi,
-                );
-
-                if let Some(first_page) = xopp_file.xopp_root.pages.get(0) {
-                    if let xoppformat::XoppBackgroundType::Solid {
-                        color: _color,
-                        style: _style,
-                    } = &first_page.background.bg_type
-                    {
-                        // Xopp background styles are not compatible with Rnotes, so everything is plain for now
-                        engine.document.background.pattern = background::PatternStyle::None;
+            if let Err(_data) =
+                snapshot_sender.send(Self::parse_xopp_bytes(&bytes, xopp_import_prefs))
+            {
+                log::error!("sending result to receiver in open_from_xopp_bytes() failed. Receiver already dropped");
+            }
+        });
+
+        snapshot_receiver.await?
+    }
+
+    /// Parses a snapshot from the bytes of a Xournal++ .xopp file on the current thread.
+    ///
+    /// Does not need a running main loop, so it can be used in tools and fuzz targets.
+    pub fn parse_xopp_bytes(
+        bytes: &[u8],
+        xopp_import_prefs: XoppImportPrefs,
+    ) -> anyhow::Result<Self> {
+        let xopp_file = xoppformat::XoppFile::load_from_bytes(bytes)?;
+        if xopp_file.xopp_root.pages.is_empty() {
+            return Err(EngineError::Decode(String::from("xopp file has no pages.")).into());
+        }
+        if let Some(page) = xopp_file.xopp_root.pages.iter().find(|page| {
+            !(page.width.is_finite() && page.height.is_finite())
+                || page.width <= 0.0
+                || page.height <= 0.0
+        }) {
+            return Err(EngineError::InvalidGeometry(format!(
+                "xopp file has a page with invalid size {}x{}.",
+                page.width, page.height
+            ))
+            .into());
+        }
+
+        // Extract the largest width of all pages, add together all heights
+        let (doc_width, doc_height) = xopp_file
+            .xopp_root
+            .pages
+            .iter()
+            .map(|page| (page.width, page.height))
+            .fold((0_f64, 0_f64), |prev, next| {
+                // Max of width, sum heights
+                (prev.0.max(next.0), prev.1 + next.1)
+            });
+        let no_pages = xopp_file.xopp_root.pages.len() as u32;
+
+        let mut engine = RnoteEngine::default();
+
+        // We convert all values from the hardcoded 72 DPI of Xopp files to the preferred dpi
+        engine.document.format.dpi = xopp_import_prefs.dpi;
+
+        engine.document.x = 0.0;
+        engine.document.y = 0.0;
+        engine.document.width = crate::utils::convert_value_dpi(
+            doc_width,
+            xoppformat::XoppFile::DPI,
+            xopp_import_prefs.dpi,
+        );
+        engine.document.height = crate::utils::convert_value_dpi(
+            doc_height,
+            xoppformat::XoppFile::DPI,
+            xopp_import_prefs.dpi,
+        );
+
+        engine.document.format.width = crate::utils::convert_value_dpi(
+            doc_width,
+            xoppformat::XoppFile::DPI,
+            xopp_import_prefs.dpi,
+        );
+        engine.document.format.height = crate::utils::convert_value_dpi(
+            doc_height / (no_pages as f64),
+            xoppformat::XoppFile::DPI,
+            xopp_import_prefs.dpi,
+        );
+
+        if let Some(first_page) = xopp_file.xopp_root.pages.get(0) {
+            if let xoppformat::XoppBackgroundType::Solid {
+                color: _color,
+                style: _style,
+            } = &first_page.background.bg_type
+            {
+                // Xopp background styles are not compatible with Rnotes, so everything is plain for now
+                engine.document.background.pattern = background::PatternStyle::None;
+            }
+        }
+
+        // Offsetting as rnote has one global coordinate space
+        let mut offset = na::Vector2::<f64>::zeros();
+
+        for (_page_i, page) in xopp_file.xopp_root.pages.into_iter().enumerate() {
+            for layers in page.layers.into_iter() {
+                // import strokes
+                for new_xoppstroke in layers.strokes.into_iter() {
+                    match Stroke::from_xoppstroke(new_xoppstroke, offset, xopp_import_prefs.dpi) {
+                        Ok((new_stroke, layer)) => {
+                            engine.store.insert_stroke(new_stroke, Some(layer));
+                        }
+                        Err(e) => {
+                            log::error!(
+                                "from_xoppstroke() failed in open_from_xopp_bytes() with Err {:?}",
+                                e
+                            );
+                        }
                     }
                 }
 
-                // Offsetting as rnote has one global coordinate space
-                let mut offset = na::Vector2::<f64>::zeros();
-
-                for (_page_i, page) in xopp_file.xopp_root.pages.into_iter().enumerate() {
-                    for layers in page.layers.into_iter() {
-                        // import strokes
-                        for new_xoppstroke in layers.strokes.into_iter() {
-                            match Stroke::from_xoppstroke(
-                                new_xoppstroke,
-                                offset,
-                                xopp_import_prefs.dpi,
-                            ) {
-                                Ok((new_stroke, layer)) => {
-                                    engine.store.insert_stroke(new_stroke, Some(layer));
-                                }
-                                Err(e) => {
-                                    log::error!(
-                                        "from_xoppstroke() failed in open_from_xopp_bytes() with Err {:?}",
-                                        e
-                                    );
-                                }
-                            }
+                // import images
+                for new_xoppimage in layers.images.into_iter() {
+                    match Stroke::from_xoppimage(new_xoppimage, offset, xopp_import_prefs.dpi) {
+                        Ok(new_image) => {
+                            engine.store.insert_stroke(new_image, None);
                         }
-
-                        // import images
-                        for new_xoppimage in layers.images.into_iter() {
-                            match Stroke::from_xoppimage(
-                                new_xoppimage,
-                                offset,
-                                xopp_import_prefs.dpi,
-                            ) {
-                                Ok(new_image) => {
-                                    engine.store.insert_stroke(new_image, None);
-                                }
-                                Err(e) => {
-                                    log::error!(
-                                        "from_xoppimage() failed in open_from_xopp_bytes() with Err {:?}",
-                                        e
-                                    );
-                                }
-                            }
+                        Err(e) => {
+                            log::error!(
+                                "from_xoppimage() failed in open_from_xopp_bytes() with Err {:?}",
+                                e
+                            );
                         }
                     }
-
-                    // Only add to y offset, results in vertical pages
-                    offset[1] += crate::utils::convert_value_dpi(
-                        page.height,
-                        xoppformat::XoppFile::DPI,
-                        xopp_import_prefs.dpi,
-                    );
                 }
+            }
+
+            // Only add to y offset, results in vertical pages
+            offset[1] += crate::utils::convert_value_dpi(
+                page.height,
+                xoppformat::XoppFile::DPI,
+                xopp_import_prefs.dpi,
+            );
+        }
+
+        let mut snapshot = engine.take_snapshot();
+        snapshot.view_state = None;
+        Ok(snapshot)
+    }
+}
+
+/// Checks that the dimensions of the document are finite and positive.
+fn validate_document(document: &Document) -> anyhow::Result<()> {
+    let valid = [
+        document.x,
+        document.y,
+        document.width,
+        document.height,
+        document.format.width,
+        document.format.height,
+        document.format.dpi,
+    ]
+    .iter()
+    .all(|v| v.is_finite())
+        && document.width >= 0.0
+        && document.height >= 0.0
+        && document.format.width > 0.0
+        && document.format.height > 0.0
+        && document.format.dpi > 0.0;
+    if !valid {
+        return Err(EngineError::InvalidGeometry(format!(
+            "document has invalid dimensions, x: {}, y: {}, width: {}, height: {}, format: {}x{} at {} dpi",
+            document.x,
+            document.y,
+            document.width,
+            document.height,
+            document.format.width,
+            document.format.height,
+            document.format.dpi
+        ))
+        .into());
+    }
+    Ok(())
+}
 
-                Ok(engine.take_snapshot())
+/// Extracts the occupied slots of a serialized slotmap, keyed by their index and version.
+///
+/// Malformed slots are skipped and reported.
+fn slots_from_value<'a>(
+    data: &'a ijson::IValue,
+    name: &str,
+    issues: &mut Vec<String>,
+) -> Vec<((usize, u64), &'a ijson::IValue)> {
+    let Some(slots) = data.as_array() else {
+        issues.push(format!("{name} data is not an array."));
+        return vec![];
+    };
+
+    slots
+        .iter()
+        .enumerate()
+        .filter_map(|(i, slot)| {
+            let Some(slot) = slot.as_object() else {
+                issues.push(format!("{name} slot at index {i} is malformed."));
+                return None;
+            };
+            let value = slot.get("value").filter(|value| !value.is_null())?;
+            let Some(version) = slot
+                .get("version")
+                .and_then(|version| version.as_number())
+                .and_then(|version| version.to_u64())
+            else {
+                issues.push(format!("{name} slot at index {i} has no version."));
+                return None;
             };
+            Some(((i, version), value))
+        })
+        .collect()
+}
 
-            if let Err(_data) = snapshot_sender.send(result()) {
-                log::error!("sending result to receiver in open_from_xopp_bytes() failed. Receiver already dropped");
-            }
-        });
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::export::rnote_bytes_from_snapshot;
+    use crate::strokes::ShapeStroke;
+    use approx::assert_relative_eq;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::shapes::{Rectangle, Shape};
+    use rnote_compose::Style;
 
-        snapshot_receiver.await?
+    #[test]
+    fn instances_are_saved_as_links_and_regenerated() {
+        let mut engine = RnoteEngine::default();
+        let master = engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_p2d_aabb(Aabb::new(
+                    na::point![0.0, 0.0],
+                    na::point![50.0, 50.0],
+                ))),
+                Style::default(),
+            )),
+            None,
+        );
+        engine.store.set_selected_keys(&[master], true);
+        let instance = engine
+            .store
+            .duplicate_selection_linked(na::vector![100.0, 0.0])[0];
+        engine.store.update_geometry_for_strokes(&[instance]);
+        let instance_bounds = engine.store.get_stroke_ref(instance).unwrap().bounds();
+
+        let bytes = rnote_bytes_from_snapshot(engine.take_snapshot(), "instances.rnote").unwrap();
+        let rnote_file = rnoteformat::RnoteFile::load_from_bytes(&bytes).unwrap();
+        let saved_strokes = rnote_file
+            .engine_snapshot
+            .as_object()
+            .and_then(|snapshot_data| snapshot_data.get("stroke_components"))
+            .map(|data| slots_from_value(data, "stroke", &mut vec![]))
+            .unwrap();
+        assert_eq!(saved_strokes.len(), 1);
+
+        let snapshot = EngineSnapshot::parse_rnote_bytes(&bytes).unwrap();
+        assert_eq!(snapshot.stroke_components.len(), 2);
+        let (key, comp) = snapshot.instance_components.iter().next().unwrap();
+        assert_eq!(comp.master, master);
+        let bounds = snapshot.stroke_components[key].bounds();
+        assert_relative_eq!(bounds.mins, instance_bounds.mins);
+        assert_relative_eq!(bounds.maxs, instance_bounds.maxs);
     }
 }
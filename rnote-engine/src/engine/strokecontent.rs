@@ -1,13 +1,17 @@
 // Imports
+use super::{HeaderFooter, Watermark};
 use crate::document::Background;
 use crate::render::Svg;
-use crate::strokes::Stroke;
-use crate::{DrawBehaviour, RnoteEngine};
+use crate::strokes::{Stroke, StrokeBehaviour, TextLayer};
+use crate::{DrawBehaviour, EngineError, RnoteEngine};
+use anyhow::Context;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use piet::RenderContext;
 use rnote_compose::helpers::AabbHelpers;
 use rnote_compose::shapes::ShapeBehaviour;
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
 /// Stroke content.
@@ -22,11 +26,22 @@ pub struct StrokeContent {
     pub bounds: Option<Aabb>,
     #[serde(rename = "background")]
     pub background: Option<Background>,
+    /// Whether text is exported as glyph outlines when generating Svgs.
+    #[serde(skip)]
+    pub text_as_paths: bool,
+    /// The watermark that is overlaid on top of the strokes.
+    #[serde(skip)]
+    pub watermark: Option<Watermark>,
+    /// The header and footer that are placed on top of the strokes, with the placeholders already replaced.
+    #[serde(skip)]
+    pub header_footer: Option<HeaderFooter>,
 }
 
 impl StrokeContent {
     pub const MIME_TYPE: &str = "application/rnote-stroke-content";
     pub const CLIPBOARD_EXPORT_MARGIN: f64 = 6.0;
+    /// Cairo skips drawing fully transparent text, so the text layers are drawn with an unnoticeable alpha instead.
+    const TEXT_LAYER_ALPHA: f64 = 0.01;
 
     pub fn with_bounds(mut self, bounds: Option<Aabb>) -> Self {
         self.bounds = bounds;
@@ -38,6 +53,21 @@ impl StrokeContent {
         self
     }
 
+    pub fn with_text_as_paths(mut self, text_as_paths: bool) -> Self {
+        self.text_as_paths = text_as_paths;
+        self
+    }
+
+    pub fn with_watermark(mut self, watermark: Option<Watermark>) -> Self {
+        self.watermark = watermark;
+        self
+    }
+
+    pub fn with_header_footer(mut self, header_footer: Option<HeaderFooter>) -> Self {
+        self.header_footer = header_footer;
+        self
+    }
+
     pub fn with_background(mut self, background: Option<Background>) -> Self {
         self.background = background;
         self
@@ -58,10 +88,68 @@ impl StrokeContent {
         )
     }
 
+    /// Crop the bounds to the bounds of the strokes, extended by the margin.
+    ///
+    /// Apart from the margin the cropped bounds never extend beyond the current bounds.
+    /// The bounds are left unchanged if there are no strokes.
+    pub fn cropped_to_strokes(mut self, margin: f64) -> Self {
+        let Some(strokes_bounds) = self
+            .strokes
+            .iter()
+            .map(|s| s.bounds())
+            .reduce(|acc, x| acc.merged(&x))
+        else {
+            return self;
+        };
+        let cropped_bounds = match self.bounds {
+            Some(bounds) => bounds.intersection(&strokes_bounds),
+            None => Some(strokes_bounds),
+        };
+        if let Some(cropped_bounds) = cropped_bounds {
+            self.bounds = Some(cropped_bounds.loosened(margin));
+        }
+        self
+    }
+
+    /// Assign seeds to the strokes with randomized styles that don't have one yet.
+    ///
+    /// Without a seed the randomness changes every time the stroke is drawn. The seeds are derived from the stroke
+    /// bounds, so the same content is always drawn the same way.
+    pub fn with_deterministic_seeds(mut self) -> Self {
+        for stroke in self.strokes.iter_mut() {
+            let missing_seed = stroke
+                .style()
+                .map(|style| style.is_randomized() && style.seed().is_none())
+                .unwrap_or(false);
+            if !missing_seed {
+                continue;
+            }
+            let seed = seed_from_bounds(stroke.bounds());
+            if let Some(style) = Arc::make_mut(stroke).style_mut() {
+                style.set_seed(Some(seed));
+            }
+        }
+        self
+    }
+
     pub fn size(&self) -> Option<na::Vector2<f64>> {
         self.bounds().map(|b| b.extents())
     }
 
+    /// The bounds, accessible roles and descriptions of the strokes that have a description.
+    pub fn described_strokes(&self) -> Vec<(Aabb, &'static str, &str)> {
+        self.strokes
+            .iter()
+            .filter_map(|stroke| {
+                Some((
+                    stroke.bounds(),
+                    stroke.accessible_role(),
+                    stroke.description()?,
+                ))
+            })
+            .collect()
+    }
+
     /// Generate a Svg from the content.
     ///
     // Moves the bounds to mins: [0.0, 0.0], maxs: extents.
@@ -76,28 +164,72 @@ impl StrokeContent {
         };
         let bounds_loosened = bounds.loosened(margin);
         let mut svg = match (with_background, self.background) {
-            (true, Some(background)) => background.gen_svg(bounds_loosened, with_pattern)?,
+            (true, Some(background)) => background
+                .gen_svg(bounds_loosened, with_pattern)
+                .context(EngineError::Background)?,
             _ => Svg {
                 svg_data: String::new(),
                 bounds,
             },
         };
-        svg.merge([Svg::gen_with_piet_cairo_backend(
-            |piet_cx| {
-                piet_cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
-                piet_cx.clip(bounds.to_kurbo_rect());
-                for stroke in self.strokes.iter() {
-                    stroke.draw(piet_cx, RnoteEngine::STROKE_EXPORT_IMAGE_SCALE)?;
+        // Vector images are embedded with their Svg data to stay resolution independent,
+        // as is text when it is exported as outlines. All other strokes in between them are drawn in batches.
+        let mut batch = vec![];
+        for stroke in self.strokes.iter() {
+            let embedded_svg = match stroke.as_ref() {
+                Stroke::VectorImage(vectorimage) => Some(vectorimage.gen_svg()?),
+                Stroke::TextStroke(textstroke) if self.text_as_paths => {
+                    match textstroke.gen_outlines_svg() {
+                        Ok(textstroke_svg) => Some(textstroke_svg),
+                        Err(e) => {
+                            log::warn!("generating text outlines while exporting StrokeContent failed, drawing the text instead. Err: {e:?}");
+                            None
+                        }
+                    }
                 }
-                piet_cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
-                Ok(())
-            },
-            bounds,
-        )?]);
+                _ => None,
+            };
+            if let Some(mut embedded_svg) = embedded_svg {
+                if !batch.is_empty() {
+                    svg.merge([gen_strokes_svg(&batch, bounds)?]);
+                    batch.clear();
+                }
+                // Wrapping clips it to the bounds
+                embedded_svg.wrap_svg_root(Some(bounds), Some(bounds), false);
+                svg.merge([embedded_svg]);
+            } else {
+                batch.push(stroke.as_ref());
+            }
+        }
+        if !batch.is_empty() {
+            svg.merge([gen_strokes_svg(&batch, bounds)?]);
+        }
+        if let Some(watermark) = &self.watermark {
+            svg.merge([watermark.gen_svg(bounds)?]);
+        }
+        if let Some(header_footer) = &self.header_footer {
+            svg.merge([header_footer.gen_svg(bounds)?]);
+        }
+        let offset = -svg.bounds.mins.coords;
         // The simplification also moves the bounds to mins: [0.0, 0.0], maxs: extents
-        if let Err(e) = svg.simplify() {
-            log::warn!("simplifying Svg while exporting StrokeContent failed, Err: {e:?}");
-        };
+        match svg.simplify() {
+            Ok(()) => {
+                if let Err(e) = svg.optimize() {
+                    log::warn!("optimizing Svg while exporting StrokeContent failed, Err: {e:?}");
+                }
+            }
+            Err(e) => {
+                log::warn!("simplifying Svg while exporting StrokeContent failed, Err: {e:?}")
+            }
+        }
+        // Descriptions are added after the simplification, because it strips title and desc elements
+        let descriptions_svg_data = gen_descriptions_svg_data(&self.described_strokes(), offset);
+        if !descriptions_svg_data.is_empty() {
+            match svg.svg_data.rfind("</svg>") {
+                Some(pos) => svg.svg_data.insert_str(pos, &descriptions_svg_data),
+                None => svg.svg_data.push_str(&descriptions_svg_data),
+            }
+        }
         Ok(Some(svg))
     }
 
@@ -123,7 +255,9 @@ impl StrokeContent {
 
         if draw_background {
             if let Some(background) = &self.background {
-                background.draw_to_cairo(cairo_cx, bounds_loosened, draw_pattern)?;
+                background
+                    .draw_to_cairo(cairo_cx, bounds_loosened, draw_pattern)
+                    .context(EngineError::Background)?;
             }
         }
 
@@ -138,10 +272,134 @@ impl StrokeContent {
 
         let mut piet_cx = piet_cairo::CairoRenderContext::new(cairo_cx);
         for stroke in self.strokes.iter() {
-            stroke.draw(&mut piet_cx, image_scale)?;
+            // Described strokes are tagged as figures, which creates a tagged structure on Pdf surfaces
+            let described = stroke.description().is_some();
+            if described {
+                cairo_cx.tag_begin("Figure", "");
+            }
+            match stroke.as_ref() {
+                // Vector images are drawn directly to keep them resolution independent on vector surfaces
+                Stroke::VectorImage(vectorimage) => vectorimage.draw_to_cairo(cairo_cx)?,
+                stroke => stroke.draw(&mut piet_cx, image_scale)?,
+            }
+            if described {
+                cairo_cx.tag_end("Figure");
+            }
+        }
+        if let Some(watermark) = &self.watermark {
+            watermark.gen_svg(bounds)?.draw_to_cairo(cairo_cx)?;
         }
+        if let Some(header_footer) = &self.header_footer {
+            header_footer.gen_svg(bounds)?.draw_to_cairo(cairo_cx)?;
+        }
+        cairo_cx.restore()?;
         cairo_cx.restore()?;
+        Ok(())
+    }
+
+    /// Draw the text layers of the images as invisible text,
+    /// so that it can be selected, copied and searched on vector surfaces, e.g. in exported Pdf's.
+    pub fn draw_text_layers_to_cairo(&self, cairo_cx: &cairo::Context) -> anyhow::Result<()> {
+        cairo_cx.save()?;
+        cairo_cx.set_source_rgba(0.0, 0.0, 0.0, Self::TEXT_LAYER_ALPHA);
+        cairo_cx.select_font_face(
+            "sans-serif",
+            cairo::FontSlant::Normal,
+            cairo::FontWeight::Normal,
+        );
+
+        for stroke in self.strokes.iter() {
+            let Some((text_layer, rectangle)) = stroke.text_layer() else {
+                continue;
+            };
+            for span in text_layer.spans.iter() {
+                let bounds = TextLayer::span_bounds_on_doc(span, rectangle);
+                let extents = bounds.extents();
+                if extents[0] <= 0.0 || extents[1] <= 0.0 {
+                    continue;
+                }
+                cairo_cx.set_font_size(extents[1]);
+                let text_extents = cairo_cx.text_extents(&span.text)?;
+                let font_extents = cairo_cx.font_extents()?;
+                if text_extents.x_advance() <= 0.0 {
+                    continue;
+                }
+                let descent = extents[1] * font_extents.descent()
+                    / (font_extents.ascent() + font_extents.descent());
+
+                cairo_cx.save()?;
+                // Stretch the text horizontally so that it covers the word in the image
+                cairo_cx.translate(bounds.mins[0], bounds.maxs[1] - descent);
+                cairo_cx.scale(extents[0] / text_extents.x_advance(), 1.0);
+                cairo_cx.move_to(0.0, 0.0);
+                cairo_cx.show_text(&span.text)?;
+                cairo_cx.restore()?;
+            }
+        }
+
         cairo_cx.restore()?;
         Ok(())
     }
 }
+
+/// Generates invisible groups with `<title>` and `<desc>` elements covering the described strokes,
+/// so that screen readers can announce them.
+fn gen_descriptions_svg_data(
+    described_strokes: &[(Aabb, &'static str, &str)],
+    offset: na::Vector2<f64>,
+) -> String {
+    if described_strokes.is_empty() {
+        return String::new();
+    }
+    let mut w = xmlwriter::XmlWriter::new(xmlwriter::Options {
+        indent: xmlwriter::Indent::None,
+        ..Default::default()
+    });
+    w.start_element("g");
+    w.write_attribute("id", "rnote-descriptions");
+    for (bounds, role, description) in described_strokes {
+        let bounds = bounds.translate(offset);
+        w.start_element("g");
+        w.write_attribute("role", "img");
+        w.write_attribute("aria-label", description);
+        w.start_element("title");
+        w.write_text(role);
+        w.end_element();
+        w.start_element("desc");
+        w.write_text(description);
+        w.end_element();
+        w.start_element("rect");
+        w.write_attribute("x", &format!("{:.3}", bounds.mins[0]));
+        w.write_attribute("y", &format!("{:.3}", bounds.mins[1]));
+        w.write_attribute("width", &format!("{:.3}", bounds.extents()[0]));
+        w.write_attribute("height", &format!("{:.3}", bounds.extents()[1]));
+        w.write_attribute("fill", "none");
+        w.write_attribute("stroke", "none");
+        w.end_element();
+        w.end_element();
+    }
+    w.end_document()
+}
+
+fn seed_from_bounds(bounds: Aabb) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for v in bounds.mins.coords.iter().chain(bounds.maxs.coords.iter()) {
+        v.to_bits().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn gen_strokes_svg(strokes: &[&Stroke], bounds: Aabb) -> anyhow::Result<Svg> {
+    Svg::gen_with_piet_cairo_backend(
+        |piet_cx| {
+            piet_cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+            piet_cx.clip(bounds.to_kurbo_rect());
+            for stroke in strokes.iter() {
+                stroke.draw(piet_cx, RnoteEngine::STROKE_EXPORT_IMAGE_SCALE)?;
+            }
+            piet_cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+            Ok(())
+        },
+        bounds,
+    )
+}
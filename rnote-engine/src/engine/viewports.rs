@@ -0,0 +1,387 @@
+// Imports
+use super::{EngineTask, EngineTaskSender, RnoteEngine};
+use crate::store::render_comp::RENDER_IMAGE_SCALE_EQUALITY_TOLERANCE;
+use crate::store::StrokeKey;
+use crate::strokes::strokebehaviour::GeneratedStrokeImages;
+use crate::strokes::{Stroke, StrokeBehaviour};
+use crate::utils::{GdkRGBAHelpers, GrapheneRectHelpers};
+use crate::{render, Camera, StrokeStore, WidgetFlags};
+use gtk4::{gdk, graphene, gsk, prelude::*, Snapshot};
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rnote_compose::color;
+use rnote_compose::helpers::{AabbHelpers, SplitOrder};
+use rnote_compose::shapes::ShapeBehaviour;
+use slotmap::SecondaryMap;
+use std::sync::Arc;
+
+slotmap::new_key_type! {
+    /// The key of a secondary viewport.
+    pub struct ViewportKey;
+}
+
+#[derive(Debug, Clone)]
+struct ViewportStrokeRendering {
+    /// The stroke the rendering was generated from.
+    ///
+    /// Holding on to it makes every modification of the stroke in the store allocate a new one,
+    /// so an outdated rendering is detected by comparing the pointers.
+    stroke: Arc<Stroke>,
+    image_scale: f64,
+    /// The viewport the images were generated for, `None` when the entire stroke is rendered.
+    viewport: Option<Aabb>,
+    rendernodes: Vec<gsk::RenderNode>,
+}
+
+/// An additional viewport into the document, with its own camera and rendering cache.
+///
+/// Allows the UI to show multiple places of the same document at the same time, e.g. in a split view.
+/// The strokes are shared with the main viewport, only the rendering is separate.
+#[derive(Debug, Clone)]
+pub struct SecondaryViewport {
+    pub camera: Camera,
+    rendering: SecondaryMap<StrokeKey, ViewportStrokeRendering>,
+    /// The strokes that are currently rendered in a task.
+    pending: SecondaryMap<StrokeKey, Arc<Stroke>>,
+    background_rendernodes: Vec<gsk::RenderNode>,
+}
+
+impl SecondaryViewport {
+    fn new(camera: Camera) -> Self {
+        Self {
+            camera,
+            rendering: SecondaryMap::new(),
+            pending: SecondaryMap::new(),
+            background_rendernodes: vec![],
+        }
+    }
+
+    fn clear_rendering(&mut self) {
+        self.rendering.clear();
+        self.pending.clear();
+        self.background_rendernodes.clear();
+    }
+
+    fn update_background_rendering(
+        &mut self,
+        background_tile_image: Option<&render::Image>,
+        tile_size: na::Vector2<f64>,
+    ) {
+        self.background_rendernodes.clear();
+        let Some(image) = background_tile_image else {
+            return;
+        };
+        let texture = match image.to_memtexture() {
+            Ok(t) => t,
+            Err(e) => {
+                log::error!("failed to generate memory-texture of background tile image, {e:?}");
+                return;
+            }
+        };
+        for split_bounds in self
+            .camera
+            .viewport()
+            .split_extended_origin_aligned(tile_size, SplitOrder::default())
+        {
+            self.background_rendernodes.push(
+                gsk::TextureNode::new(&texture, &graphene::Rect::from_p2d_aabb(split_bounds))
+                    .upcast(),
+            );
+        }
+    }
+
+    /// Spawn render tasks for all strokes in the viewport that have no valid rendering.
+    fn update_content_rendering(
+        &mut self,
+        viewport_key: ViewportKey,
+        store: &StrokeStore,
+        tasks_tx: EngineTaskSender,
+    ) {
+        let image_scale = self.camera.image_scale();
+        let current_viewport = self.camera.viewport();
+        let viewport = current_viewport
+            .extend_by(current_viewport.extents() * render::VIEWPORT_EXTENTS_MARGIN_FACTOR);
+
+        // Drop the rendering of strokes that were removed or left the viewport
+        self.rendering.retain(|key, _| {
+            store
+                .get_stroke_ref(key)
+                .map(|stroke| stroke.bounds().intersects(&viewport))
+                .unwrap_or(false)
+        });
+
+        for key in store.stroke_keys_as_rendered_intersecting_bounds(viewport) {
+            let Some(stroke) = store.get_stroke_arc(key) else {
+                continue;
+            };
+            if self
+                .pending
+                .get(key)
+                .map(|pending| Arc::ptr_eq(pending, &stroke))
+                .unwrap_or(false)
+            {
+                continue;
+            }
+            if let Some(rendering) = self.rendering.get(key) {
+                if Arc::ptr_eq(&rendering.stroke, &stroke)
+                    && (rendering.image_scale - image_scale).abs()
+                        < RENDER_IMAGE_SCALE_EQUALITY_TOLERANCE
+                    && rendering
+                        .viewport
+                        .map(|v| v.contains(&current_viewport))
+                        .unwrap_or(true)
+                {
+                    continue;
+                }
+            }
+
+            self.pending.insert(key, Arc::clone(&stroke));
+            let tasks_tx = tasks_tx.clone();
+            rayon::spawn(move || match stroke.gen_images(viewport, image_scale) {
+                Ok(images) => {
+                    tasks_tx
+                        .unbounded_send(EngineTask::UpdateViewportStrokeWithImages {
+                            viewport_key,
+                            key,
+                            stroke,
+                            images,
+                            image_scale,
+                        })
+                        .unwrap_or_else(|e| {
+                            log::error!("tasks_tx.send() UpdateViewportStrokeWithImages failed for stroke with key {key:?}, with Err, {e:?}");
+                        });
+                }
+                Err(e) => {
+                    log::debug!("stroke.gen_image() failed in SecondaryViewport update_content_rendering() for stroke with key {key:?}, with Err: {e:?}");
+                }
+            });
+        }
+    }
+
+    /// Replace the rendering of the stroke, if the images were generated from the current stroke and image scale.
+    ///
+    /// Returns true if the rendering was replaced.
+    fn replace_rendering_with_images(
+        &mut self,
+        key: StrokeKey,
+        stroke: Arc<Stroke>,
+        images: GeneratedStrokeImages,
+        image_scale: f64,
+        store: &StrokeStore,
+    ) -> bool {
+        if self
+            .pending
+            .get(key)
+            .map(|pending| Arc::ptr_eq(pending, &stroke))
+            .unwrap_or(false)
+        {
+            self.pending.remove(key);
+        }
+        let current = store
+            .get_stroke_arc(key)
+            .map(|current| Arc::ptr_eq(&current, &stroke))
+            .unwrap_or(false);
+        if !current
+            || (self.camera.image_scale() - image_scale).abs()
+                >= RENDER_IMAGE_SCALE_EQUALITY_TOLERANCE
+        {
+            return false;
+        }
+        let (images, viewport) = match images {
+            GeneratedStrokeImages::Partial { images, viewport } => (images, Some(viewport)),
+            GeneratedStrokeImages::Full(images) => (images, None),
+        };
+        match render::Image::images_to_rendernodes(&images) {
+            Ok(rendernodes) => {
+                self.rendering.insert(
+                    key,
+                    ViewportStrokeRendering {
+                        stroke,
+                        image_scale,
+                        viewport,
+                        rendernodes,
+                    },
+                );
+                true
+            }
+            Err(e) => {
+                log::error!("failed to generate rendernodes in SecondaryViewport replace_rendering_with_images(), Err {e:?}");
+                false
+            }
+        }
+    }
+
+    fn draw_strokes_to_gtk_snapshot(
+        &self,
+        snapshot: &Snapshot,
+        store: &StrokeStore,
+        doc_bounds: Aabb,
+    ) {
+        snapshot.push_clip(&graphene::Rect::from_p2d_aabb(doc_bounds));
+
+        for key in store.stroke_keys_as_rendered_intersecting_bounds(self.camera.viewport()) {
+            if !store.visible_at_playback_time(key) {
+                continue;
+            }
+            match self.rendering.get(key) {
+                Some(rendering) => {
+                    for rendernode in rendering.rendernodes.iter() {
+                        snapshot.append_node(rendernode);
+                    }
+                }
+                None => {
+                    // draw a placeholder while the stroke does not have a rendering yet
+                    if let Some(stroke) = store.get_stroke_ref(key) {
+                        snapshot.append_color(
+                            &gdk::RGBA::from_piet_color(color::GNOME_BRIGHTS[1].with_alpha(0.13)),
+                            &graphene::Rect::from_p2d_aabb(stroke.bounds()),
+                        );
+                    }
+                }
+            }
+        }
+
+        snapshot.pop();
+    }
+}
+
+impl RnoteEngine {
+    /// Add a secondary viewport with the given size in surface coordinates.
+    ///
+    /// The camera of the new viewport starts out as a copy of the main camera.
+    pub fn viewport_add(&mut self, size: na::Vector2<f64>) -> ViewportKey {
+        let mut camera = self.camera.clone().with_size(size);
+        camera.zoom_task_handle = None;
+        let key = self
+            .secondary_viewports
+            .insert(SecondaryViewport::new(camera));
+        self.viewport_update_rendering(key);
+        key
+    }
+
+    /// Remove the secondary viewport and drop its rendering.
+    pub fn viewport_remove(&mut self, key: ViewportKey) {
+        self.secondary_viewports.remove(key);
+    }
+
+    /// The keys of all secondary viewports.
+    pub fn viewport_keys(&self) -> Vec<ViewportKey> {
+        self.secondary_viewports.keys().collect()
+    }
+
+    /// The camera of the secondary viewport.
+    pub fn viewport_camera(&self, key: ViewportKey) -> Option<&Camera> {
+        self.secondary_viewports.get(key).map(|v| &v.camera)
+    }
+
+    /// Update the offset of the secondary viewport, clamped to the document layout.
+    ///
+    /// The rendering of the viewport then needs to be updated.
+    pub fn viewport_set_offset(
+        &mut self,
+        key: ViewportKey,
+        offset: na::Vector2<f64>,
+    ) -> WidgetFlags {
+        let Some(viewport) = self.secondary_viewports.get_mut(key) else {
+            return WidgetFlags::default();
+        };
+        viewport.camera.set_offset(offset, &self.document)
+    }
+
+    /// Update the size of the secondary viewport.
+    ///
+    /// The rendering of the viewport then needs to be updated.
+    pub fn viewport_set_size(&mut self, key: ViewportKey, size: na::Vector2<f64>) -> WidgetFlags {
+        let Some(viewport) = self.secondary_viewports.get_mut(key) else {
+            return WidgetFlags::default();
+        };
+        viewport.camera.set_size(size)
+    }
+
+    /// Zoom the secondary viewport permanently, keeping its viewport center, and regenerate its rendering.
+    pub fn viewport_zoom_to(&mut self, key: ViewportKey, zoom: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let Some(viewport) = self.secondary_viewports.get_mut(key) else {
+            return widget_flags;
+        };
+        let center = viewport.camera.viewport_center();
+        widget_flags.merge(viewport.camera.zoom_temporarily_to(1.0));
+        widget_flags.merge(viewport.camera.zoom_to(zoom));
+        widget_flags.merge(viewport.camera.set_viewport_center(center));
+        self.viewport_update_rendering(key);
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Update the background and content rendering of the secondary viewport.
+    ///
+    /// Strokes that were modified since their last rendering are rendered again,
+    /// so this should be called when the viewport or the store has changed.
+    pub fn viewport_update_rendering(&mut self, key: ViewportKey) {
+        let tasks_tx = self.tasks_tx();
+        let Some(viewport) = self.secondary_viewports.get_mut(key) else {
+            return;
+        };
+        viewport.update_background_rendering(
+            self.background_tile_image.as_ref(),
+            self.document.background.tile_size(),
+        );
+        viewport.update_content_rendering(key, &self.store, tasks_tx);
+    }
+
+    /// Update the rendering of all secondary viewports.
+    pub fn viewports_update_rendering(&mut self) {
+        for key in self.viewport_keys() {
+            self.viewport_update_rendering(key);
+        }
+    }
+
+    /// Clear the rendering of all secondary viewports.
+    pub(super) fn viewports_clear_rendering(&mut self) {
+        for viewport in self.secondary_viewports.values_mut() {
+            viewport.clear_rendering();
+        }
+    }
+
+    /// Handle the images rendered for a stroke in a secondary viewport.
+    pub(super) fn viewport_handle_rendered_images(
+        &mut self,
+        viewport_key: ViewportKey,
+        key: StrokeKey,
+        stroke: Arc<Stroke>,
+        images: GeneratedStrokeImages,
+        image_scale: f64,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let Some(viewport) = self.secondary_viewports.get_mut(viewport_key) else {
+            return widget_flags;
+        };
+        if viewport.replace_rendering_with_images(key, stroke, images, image_scale, &self.store) {
+            widget_flags.redraw = true;
+        }
+        widget_flags
+    }
+
+    /// Draw the document and the strokes as seen through the secondary viewport to a GTK snapshot.
+    ///
+    /// Pens and their overlays are only drawn in the main viewport.
+    pub fn draw_viewport_to_gtk_snapshot(
+        &self,
+        key: ViewportKey,
+        snapshot: &Snapshot,
+    ) -> anyhow::Result<()> {
+        let Some(viewport) = self.secondary_viewports.get(key) else {
+            return Ok(());
+        };
+        let doc_bounds = self.document.bounds();
+
+        snapshot.save();
+        snapshot.transform(Some(&viewport.camera.transform_for_gtk_snapshot()));
+        self.draw_document_shadow_to_gtk_snapshot(snapshot);
+        self.draw_background_to_gtk_snapshot(snapshot, &viewport.background_rendernodes)?;
+        self.draw_format_borders_to_gtk_snapshot(snapshot, &viewport.camera)?;
+        viewport.draw_strokes_to_gtk_snapshot(snapshot, &self.store, doc_bounds);
+        snapshot.restore();
+
+        Ok(())
+    }
+}
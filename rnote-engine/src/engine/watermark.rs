@@ -0,0 +1,181 @@
+// Imports
+use crate::render;
+use crate::strokes::textoutlines;
+use crate::strokes::textstroke::TextStyle;
+use crate::strokes::{StrokeBehaviour, VectorImage};
+use p2d::bounding_volume::Aabb;
+use rnote_compose::helpers::Vector2Helpers;
+use rnote_compose::Color;
+use serde::{Deserialize, Serialize};
+
+/// The content of a watermark.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "watermark_content")]
+pub enum WatermarkContent {
+    #[serde(rename = "text")]
+    Text(String),
+    /// Svg data provided by the user.
+    #[serde(rename = "svg")]
+    Svg(String),
+}
+
+impl Default for WatermarkContent {
+    fn default() -> Self {
+        Self::Text(String::from("Draft"))
+    }
+}
+
+/// The position of a watermark on the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "watermark_position")]
+pub enum WatermarkPosition {
+    #[serde(rename = "center")]
+    Center,
+    #[serde(rename = "top_left")]
+    TopLeft,
+    #[serde(rename = "top_right")]
+    TopRight,
+    #[serde(rename = "bottom_left")]
+    BottomLeft,
+    #[serde(rename = "bottom_right")]
+    BottomRight,
+}
+
+impl Default for WatermarkPosition {
+    fn default() -> Self {
+        Self::Center
+    }
+}
+
+/// The pages a watermark is placed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "watermark_pages")]
+pub enum WatermarkPages {
+    #[serde(rename = "all")]
+    All,
+    #[serde(rename = "first")]
+    First,
+}
+
+impl Default for WatermarkPages {
+    fn default() -> Self {
+        Self::All
+    }
+}
+
+/// A watermark or stamp that is overlaid on the exported pages.
+///
+/// Text watermarks are converted to outlines, so they look the same in all export formats.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "watermark")]
+pub struct Watermark {
+    #[serde(rename = "content")]
+    pub content: WatermarkContent,
+    #[serde(rename = "position")]
+    pub position: WatermarkPosition,
+    #[serde(rename = "pages")]
+    pub pages: WatermarkPages,
+    /// The opacity, between 0.0 and 1.0.
+    #[serde(rename = "opacity")]
+    pub opacity: f64,
+    /// The font size of text watermarks.
+    #[serde(rename = "font_size")]
+    pub font_size: f64,
+    /// The color of text watermarks.
+    #[serde(rename = "color")]
+    pub color: Color,
+    /// The scale of Svg watermarks in relation to their intrinsic size.
+    #[serde(rename = "svg_scale")]
+    pub svg_scale: f64,
+    /// The distance to the edges of the page, when the watermark is not centered.
+    #[serde(rename = "margin")]
+    pub margin: f64,
+}
+
+impl Default for Watermark {
+    fn default() -> Self {
+        Self {
+            content: WatermarkContent::default(),
+            position: WatermarkPosition::default(),
+            pages: WatermarkPages::default(),
+            opacity: 0.2,
+            font_size: 96.0,
+            color: Color::BLACK,
+            svg_scale: 1.0,
+            margin: 24.0,
+        }
+    }
+}
+
+impl Watermark {
+    /// Whether the watermark is placed on the page with the index.
+    pub fn is_on_page(&self, page: usize) -> bool {
+        match self.pages {
+            WatermarkPages::All => true,
+            WatermarkPages::First => page == 0,
+        }
+    }
+
+    /// Generate the Svg of the watermark placed on the page with the bounds, clipped to them.
+    pub fn gen_svg(&self, page_bounds: Aabb) -> anyhow::Result<render::Svg> {
+        let (content_svg_data, content_bounds, scale) = match &self.content {
+            WatermarkContent::Text(text) => {
+                let text_style = TextStyle {
+                    font_size: self.font_size,
+                    color: self.color,
+                    ..TextStyle::default()
+                };
+                let outlines = textoutlines::text_outlines(text, &text_style)?;
+                let content_bounds = textoutlines::outlines_bounds(&outlines)
+                    .ok_or_else(|| anyhow::anyhow!("watermark text has no outlines"))?;
+                (
+                    rnote_compose::utils::svg_node_to_string(&textoutlines::outlines_svg_group(
+                        outlines,
+                    ))?,
+                    content_bounds,
+                    1.0,
+                )
+            }
+            WatermarkContent::Svg(svg_data) => {
+                let vectorimage =
+                    VectorImage::import_from_svg_data(svg_data, na::Vector2::zeros(), None)?;
+                let content_svg = vectorimage.gen_svg()?;
+                (content_svg.svg_data, content_svg.bounds, self.svg_scale)
+            }
+        };
+
+        let size = content_bounds.extents() * scale;
+        let free_space = page_bounds.extents() - size;
+        let offset = match self.position {
+            WatermarkPosition::Center => free_space * 0.5,
+            WatermarkPosition::TopLeft => na::vector![self.margin, self.margin],
+            WatermarkPosition::TopRight => {
+                na::vector![free_space[0] - self.margin, self.margin]
+            }
+            WatermarkPosition::BottomLeft => {
+                na::vector![self.margin, free_space[1] - self.margin]
+            }
+            WatermarkPosition::BottomRight => free_space - na::Vector2::repeat(self.margin),
+        };
+        let pos = page_bounds.mins.coords + offset;
+        let transform = kurbo::Affine::translate(pos.to_kurbo_vec())
+            * kurbo::Affine::scale(scale)
+            * kurbo::Affine::translate(-content_bounds.mins.coords.to_kurbo_vec());
+        let [a, b, c, d, e, f] = transform.as_coeffs();
+
+        let group = svg::node::element::Group::new()
+            .set("opacity", self.opacity.clamp(0.0, 1.0))
+            .set(
+                "transform",
+                format!("matrix({a:.6} {b:.6} {c:.6} {d:.6} {e:.3} {f:.3})"),
+            )
+            .add(svg::node::Text::new(content_svg_data));
+        let mut svg = render::Svg {
+            svg_data: rnote_compose::utils::svg_node_to_string(&group)?,
+            bounds: Aabb::new(pos.into(), (pos + size).into()),
+        };
+        // Wrapping clips it to the page
+        svg.wrap_svg_root(Some(page_bounds), Some(page_bounds), false);
+        Ok(svg)
+    }
+}
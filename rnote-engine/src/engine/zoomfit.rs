@@ -0,0 +1,79 @@
+// Imports
+use super::RnoteEngine;
+use crate::{Camera, WidgetFlags};
+use p2d::bounding_volume::Aabb;
+use p2d::query::PointQuery;
+use rnote_compose::helpers::SplitOrder;
+
+/// What the camera should be zoomed to fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZoomFitTarget {
+    /// All pages with content.
+    Document,
+    /// The page at the center of the viewport.
+    Page,
+    /// The bounds of the current selection.
+    Selection,
+}
+
+impl std::str::FromStr for ZoomFitTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "document" => Ok(Self::Document),
+            "page" => Ok(Self::Page),
+            "selection" => Ok(Self::Selection),
+            s => Err(anyhow::anyhow!(
+                "ZoomFitTarget from_string failed, invalid name: {s}"
+            )),
+        }
+    }
+}
+
+impl std::string::ToString for ZoomFitTarget {
+    fn to_string(&self) -> String {
+        match self {
+            Self::Document => String::from("document"),
+            Self::Page => String::from("page"),
+            Self::Selection => String::from("selection"),
+        }
+    }
+}
+
+impl RnoteEngine {
+    /// The bounds of the zoom fit target in document coordinate space, if there is anything to fit.
+    pub fn zoom_fit_target_bounds(&self, target: ZoomFitTarget) -> Option<Aabb> {
+        match target {
+            ZoomFitTarget::Document => self.bounds_w_content_extended(),
+            ZoomFitTarget::Page => {
+                let center = na::Point2::from(self.camera.viewport_center());
+                self.document
+                    .pages_bounds(SplitOrder::default())
+                    .into_iter()
+                    .min_by(|a, b| {
+                        a.distance_to_local_point(&center, true)
+                            .total_cmp(&b.distance_to_local_point(&center, true))
+                    })
+            }
+            ZoomFitTarget::Selection => self
+                .store
+                .bounds_for_strokes(&self.store.selection_keys_as_rendered()),
+        }
+    }
+
+    /// Zoom and move the camera so that the target fits into the viewport.
+    ///
+    /// See `camera_animate_to()` for when the transition is animated.
+    pub fn zoom_fit(&mut self, target: ZoomFitTarget, animate: bool) -> WidgetFlags {
+        let Some(bounds) = self.zoom_fit_target_bounds(target) else {
+            return WidgetFlags::default();
+        };
+        let to_zoom = self
+            .camera
+            .zoom_to_fit_bounds(bounds, Camera::ZOOM_FIT_MARGIN);
+        let to_center = bounds.center().coords;
+
+        self.camera_animate_to(to_zoom, to_center, animate)
+    }
+}
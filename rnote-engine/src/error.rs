@@ -0,0 +1,77 @@
+// Imports
+use thiserror::Error;
+
+/// The kind of failure of an engine operation.
+///
+/// The render, import, export and loading methods of the engine return `anyhow::Error`'s,
+/// which carry an [EngineError] as their cause or as context when the failure falls into one of these categories.
+/// Frontends can retrieve it with [EngineError::find] to react to the failure,
+/// instead of matching on the error messages.
+#[derive(Error, Debug)]
+pub enum EngineError {
+    /// Reading or writing data failed.
+    #[error("I/O operation failed, {0}")]
+    Io(#[from] std::io::Error),
+    /// The data is corrupted or could not be parsed.
+    #[error("decoding failed, {0}")]
+    Decode(String),
+    /// The format or the version of the data is not supported.
+    #[error("unsupported format, {0}")]
+    UnsupportedFormat(String),
+    /// The data is readable, but contains invalid sizes, bounds or coordinates.
+    #[error("invalid geometry, {0}")]
+    InvalidGeometry(String),
+    /// Rendering the document background failed.
+    ///
+    /// The operation might succeed when it is retried without the background.
+    #[error("rendering the background failed")]
+    Background,
+    /// The operation was cancelled before it finished.
+    #[error("operation was cancelled")]
+    Cancelled,
+}
+
+impl EngineError {
+    /// Find the engine error in the chain of causes and contexts of the error.
+    ///
+    /// Returns the outermost one, or None when the error does not carry an engine error.
+    pub fn find(error: &anyhow::Error) -> Option<&EngineError> {
+        error
+            .downcast_ref::<EngineError>()
+            .or_else(|| error.chain().find_map(|e| e.downcast_ref::<EngineError>()))
+    }
+
+    /// Whether the error was caused by the user cancelling the operation.
+    pub fn is_cancelled(error: &anyhow::Error) -> bool {
+        matches!(Self::find(error), Some(EngineError::Cancelled))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn find_through_context() {
+        let cause: anyhow::Result<()> =
+            Err(EngineError::Decode(String::from("invalid data")).into());
+        let e = cause.context("loading file failed").unwrap_err();
+        assert!(matches!(
+            EngineError::find(&e),
+            Some(EngineError::Decode(_))
+        ));
+
+        let cause: anyhow::Result<()> = Err(anyhow::anyhow!("drawing pattern failed"));
+        let e = cause
+            .context(EngineError::Background)
+            .context("exporting failed")
+            .unwrap_err();
+        assert!(matches!(
+            EngineError::find(&e),
+            Some(EngineError::Background)
+        ));
+
+        assert!(EngineError::find(&anyhow::anyhow!("other")).is_none());
+    }
+}
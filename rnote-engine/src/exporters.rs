@@ -0,0 +1,94 @@
+// Imports
+use crate::engine::export::DocExportPrefs;
+use crate::engine::EngineSnapshot;
+use once_cell::sync::Lazy;
+use std::sync::{Arc, RwLock};
+
+/// The file extensions of the built-in formats, which can't be used by custom exporters.
+pub const BUILTIN_FILE_EXTS: [&str; 6] = ["rnote", "svg", "pdf", "xopp", "tiff", "tif"];
+
+/// Exports the document snapshot with the export preferences to the bytes of the output file.
+///
+/// The string argument is the title of the document.
+pub type CustomExportFn =
+    Arc<dyn Fn(EngineSnapshot, DocExportPrefs, String) -> anyhow::Result<Vec<u8>> + Send + Sync>;
+
+/// A document export format provided by a plugin.
+///
+/// Registered custom exporters are available in the document export dialog and the cli, where they are
+/// selected by their file extension.
+#[derive(Clone)]
+pub struct CustomExporter {
+    /// The display name of the format.
+    pub name: String,
+    /// The MIME-Type of the output files.
+    pub mime_type: String,
+    /// The file extension of the output files, without the leading dot. Identifies the exporter.
+    pub file_ext: String,
+    /// The export function. Is called on a worker thread.
+    pub export: CustomExportFn,
+}
+
+impl std::fmt::Debug for CustomExporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CustomExporter")
+            .field("name", &self.name)
+            .field("mime_type", &self.mime_type)
+            .field("file_ext", &self.file_ext)
+            .finish()
+    }
+}
+
+static CUSTOM_EXPORTERS: Lazy<RwLock<Vec<CustomExporter>>> = Lazy::new(|| RwLock::new(vec![]));
+
+/// Register a custom exporter. Replaces an already registered exporter with the same file extension.
+///
+/// Should be done on application startup, before the export dialog is shown or the cli arguments are evaluated.
+pub fn register_custom_exporter(exporter: CustomExporter) -> anyhow::Result<()> {
+    let file_ext = exporter.file_ext.trim_start_matches('.').to_lowercase();
+    if file_ext.is_empty() || BUILTIN_FILE_EXTS.contains(&file_ext.as_str()) {
+        return Err(anyhow::anyhow!(
+            "Registering custom exporter `{}` failed, invalid or built-in file extension `{file_ext}`",
+            exporter.name
+        ));
+    }
+    let exporter = CustomExporter {
+        file_ext,
+        ..exporter
+    };
+
+    let mut exporters = CUSTOM_EXPORTERS.write().unwrap();
+    match exporters
+        .iter_mut()
+        .find(|e| e.file_ext == exporter.file_ext)
+    {
+        Some(registered) => *registered = exporter,
+        None => exporters.push(exporter),
+    }
+    Ok(())
+}
+
+/// Unregister the custom exporter for the file extension.
+///
+/// Returns false if no exporter is registered for it.
+pub fn unregister_custom_exporter(file_ext: &str) -> bool {
+    let mut exporters = CUSTOM_EXPORTERS.write().unwrap();
+    let len = exporters.len();
+    exporters.retain(|e| !e.file_ext.eq_ignore_ascii_case(file_ext));
+    exporters.len() != len
+}
+
+/// The registered custom exporters, in the order they were registered.
+pub fn custom_exporters() -> Vec<CustomExporter> {
+    CUSTOM_EXPORTERS.read().unwrap().clone()
+}
+
+/// The custom exporter registered for the file extension.
+pub fn custom_exporter_for_ext(file_ext: &str) -> Option<CustomExporter> {
+    CUSTOM_EXPORTERS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|e| e.file_ext.eq_ignore_ascii_case(file_ext))
+        .cloned()
+}
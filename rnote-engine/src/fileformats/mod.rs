@@ -5,6 +5,9 @@ pub mod xoppformat;
 // Imports
 use roxmltree::Node;
 
+/// The maximum size of the decompressed data of a file, so that crafted files can't exhaust the memory.
+pub const DECOMPRESSED_SIZE_MAX: u64 = 1024 * 1024 * 1024;
+
 /// The file format loader trait, implemented by `<Format>File` types.
 pub trait FileFormatLoader {
     /// load from bytes.
@@ -14,7 +14,10 @@ pub(crate) mod maj0min6;
 use self::maj0min5patch8::RnoteFileMaj0Min5Patch8;
 use self::maj0min5patch9::RnoteFileMaj0Min5Patch9;
 use self::maj0min6::RnoteFileMaj0Min6;
-use super::{FileFormatLoader, FileFormatSaver};
+use super::{FileFormatLoader, FileFormatSaver, DECOMPRESSED_SIZE_MAX};
+use crate::engine::StrokeContent;
+use crate::progress::ProgressStage;
+use crate::{EngineError, Progress};
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
 use std::io::{Read, Write};
@@ -27,15 +30,130 @@ fn compress_to_gzip(to_compress: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
     Ok(encoder.finish()?)
 }
 
-/// Decompress from gzip.
-fn decompress_from_gzip(compressed: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
-    let mut decoder = flate2::read::MultiGzDecoder::new(compressed);
+/// Decompress from gzip, advancing the progress with the compressed bytes that were read.
+///
+/// Fails when the decompressed data exceeds [DECOMPRESSED_SIZE_MAX].
+fn decompress_from_gzip(compressed: &[u8], progress: &Progress) -> Result<Vec<u8>, anyhow::Error> {
+    let stage = progress.stage(0.0, 1.0, compressed.len());
+    let reader = ProgressReader {
+        inner: compressed,
+        stage: &stage,
+    };
+    let mut decoder = flate2::read::MultiGzDecoder::new(reader).take(DECOMPRESSED_SIZE_MAX + 1);
     let mut bytes: Vec<u8> = Vec::new();
-    decoder.read_to_end(&mut bytes)?;
+    decoder.read_to_end(&mut bytes).map_err(|e| {
+        if progress.is_cancelled() {
+            EngineError::Cancelled
+        } else {
+            EngineError::Decode(format!("decompressing gzip data failed, {e}"))
+        }
+    })?;
+    stage.finish()?;
+    if bytes.len() as u64 > DECOMPRESSED_SIZE_MAX {
+        return Err(EngineError::Decode(format!(
+            "decompressed data exceeds the maximum size of {DECOMPRESSED_SIZE_MAX} bytes"
+        ))
+        .into());
+    }
 
     Ok(bytes)
 }
 
+/// Advances the progress stage with the bytes that are read, and stops reading when the progress is cancelled.
+struct ProgressReader<'a, 'p> {
+    inner: &'a [u8],
+    stage: &'a ProgressStage<'p>,
+}
+
+impl<'a, 'p> Read for ProgressReader<'a, 'p> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.stage.is_cancelled() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                EngineError::Cancelled,
+            ));
+        }
+        let n = self.inner.read(buf)?;
+        self.stage.advance(n);
+        Ok(n)
+    }
+}
+
+/// Decompress from gzip, returning the data decompressed until an error occurred or the maximum size is exceeded.
+///
+/// Used to salvage data from truncated files.
+fn decompress_from_gzip_lenient(compressed: &[u8]) -> (Vec<u8>, Option<std::io::Error>) {
+    let mut decoder = flate2::read::MultiGzDecoder::new(compressed).take(DECOMPRESSED_SIZE_MAX + 1);
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut buf = [0; 8192];
+
+    loop {
+        match decoder.read(&mut buf) {
+            Ok(0) => return (bytes, None),
+            Ok(n) if (bytes.len() + n) as u64 > DECOMPRESSED_SIZE_MAX => {
+                return (
+                    bytes,
+                    Some(std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        format!(
+                            "decompressed data exceeds the maximum size of {DECOMPRESSED_SIZE_MAX} bytes"
+                        ),
+                    )),
+                )
+            }
+            Ok(n) => bytes.extend_from_slice(&buf[..n]),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return (bytes, Some(e)),
+        }
+    }
+}
+
+/// Close a truncated Json string.
+///
+/// Cuts it after the last complete element and appends the missing closing brackets.
+/// Returns None if the string is malformed in other ways or nothing can be salvaged.
+fn close_truncated_json(json: &str) -> Option<String> {
+    let mut closing = Vec::<char>::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    // The position of the last separator outside of strings, together with the brackets that need closing at that point
+    let mut last_cut: Option<(usize, Vec<char>)> = None;
+
+    for (i, c) in json.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => closing.push('}'),
+            '[' => closing.push(']'),
+            '}' | ']' => {
+                if closing.pop() != Some(c) {
+                    return None;
+                }
+                if closing.is_empty() {
+                    // Not truncated
+                    return Some(json[..=i].to_string());
+                }
+            }
+            ',' => last_cut = Some((i, closing.clone())),
+            _ => {}
+        }
+    }
+
+    let (cut, closing) = last_cut?;
+    let mut closed = json[..cut].to_string();
+    closed.extend(closing.into_iter().rev());
+    Some(closed)
+}
+
 /// The rnote file wrapper.
 ///
 /// Used to extract and match the version up front, before deserializing the data.
@@ -53,31 +171,119 @@ struct RnotefileWrapper {
 /// This struct exists to allow for upgrading older versions before loading the file in.
 pub type RnoteFile = RnoteFileMaj0Min6;
 
+/// The rnote file wrapper, only containing the version.
+///
+/// Used to extract the version without deserializing the data.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename = "rnotefile_wrapper")]
+struct RnotefileWrapperVersion {
+    #[serde(rename = "version")]
+    version: semver::Version,
+}
+
 impl RnoteFile {
     pub const SEMVER: &str = "0.7.1";
+
+    /// Extract the file format version from the bytes of a `.rnote` file.
+    pub fn load_version_from_bytes(bytes: &[u8]) -> anyhow::Result<semver::Version> {
+        let wrapper = serde_json::from_slice::<RnotefileWrapperVersion>(
+            &decompress_from_gzip(bytes, &Progress::default())
+                .context("decompressing bytes failed.")?,
+        )
+        .context("deserializing RnotefileWrapperVersion from bytes failed.")?;
+
+        Ok(wrapper.version)
+    }
 }
 
 impl FileFormatLoader for RnoteFile {
     fn load_from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
-        let wrapper = serde_json::from_slice::<RnotefileWrapper>(
-            &decompress_from_gzip(bytes).context("decompressing bytes failed.")?,
-        )
-        .context("deserializing RnotefileWrapper from bytes failed.")?;
+        Self::load_from_bytes_w_progress(bytes, &Progress::default())
+    }
+}
+
+impl RnoteFile {
+    /// Load from the bytes of a `.rnote` file, advancing the progress while decompressing and deserializing.
+    ///
+    /// Fails with [EngineError::Cancelled] when the progress gets cancelled.
+    pub fn load_from_bytes_w_progress(bytes: &[u8], progress: &Progress) -> anyhow::Result<Self> {
+        let decompressed = decompress_from_gzip(bytes, &progress.scoped(0.0, 0.5))
+            .context("decompressing bytes failed.")?;
+        let wrapper = serde_json::from_slice::<RnotefileWrapper>(&decompressed).map_err(|e| {
+            EngineError::Decode(format!(
+                "deserializing RnotefileWrapper from bytes failed, {e}"
+            ))
+        })?;
+        drop(decompressed);
+        progress.advance_to(0.8);
+        progress.check_cancelled()?;
+
+        let rnote_file = Self::load_from_wrapper(wrapper)?;
+        progress.advance_to(1.0);
+        progress.check_cancelled()?;
+
+        Ok(rnote_file)
+    }
+}
+
+impl RnoteFile {
+    /// Load from the bytes of a possibly damaged or truncated `.rnote` file.
+    ///
+    /// Truncated compressed data and truncated Json are salvaged as far as possible.
+    /// Returns the file together with messages describing the problems that were encountered.
+    pub fn load_from_bytes_lenient(bytes: &[u8]) -> anyhow::Result<(Self, Vec<String>)> {
+        let mut issues = vec![];
+
+        let (decompressed, decompress_err) = decompress_from_gzip_lenient(bytes);
+        if let Some(e) = decompress_err {
+            issues.push(format!(
+                "decompressing failed after {} bytes, the file is likely truncated. Err: {e}",
+                decompressed.len()
+            ));
+        }
+        let wrapper = match serde_json::from_slice::<RnotefileWrapper>(&decompressed) {
+            Ok(wrapper) => wrapper,
+            Err(e) => {
+                issues.push(format!(
+                    "deserializing the file failed, salvaging the readable part. Err: {e}"
+                ));
+                let json = String::from_utf8_lossy(&decompressed);
+                let closed = close_truncated_json(&json).ok_or_else(|| {
+                    EngineError::Decode(String::from(
+                        "salvaging the readable part of the file failed.",
+                    ))
+                })?;
+                serde_json::from_str::<RnotefileWrapper>(&closed).map_err(|e| {
+                    EngineError::Decode(format!(
+                        "deserializing salvaged RnotefileWrapper failed, {e}"
+                    ))
+                })?
+            }
+        };
 
+        Ok((Self::load_from_wrapper(wrapper)?, issues))
+    }
+
+    /// Load from the wrapper, upgrading older file format versions.
+    fn load_from_wrapper(wrapper: RnotefileWrapper) -> anyhow::Result<Self> {
         // Conversions for older file format versions happen here
         if semver::VersionReq::parse(">=0.5.10")
             .unwrap()
             .matches(&wrapper.version)
         {
-            ijson::from_value::<RnoteFileMaj0Min6>(&wrapper.data)
-                .context("deserializing RnoteFileMaj0Min6 failed.")
+            ijson::from_value::<RnoteFileMaj0Min6>(&wrapper.data).map_err(|e| {
+                EngineError::Decode(format!("deserializing RnoteFileMaj0Min6 failed, {e}"))
+            })
         } else if semver::VersionReq::parse(">=0.5.9")
             .unwrap()
             .matches(&wrapper.version)
         {
             RnoteFileMaj0Min6::try_from(
-                ijson::from_value::<RnoteFileMaj0Min5Patch9>(&wrapper.data)
-                    .context("deserializing RnoteFileMaj0Min5Patch9 failed.")?,
+                ijson::from_value::<RnoteFileMaj0Min5Patch9>(&wrapper.data).map_err(|e| {
+                    EngineError::Decode(format!(
+                        "deserializing RnoteFileMaj0Min5Patch9 failed, {e}"
+                    ))
+                })?,
             )
             .context("converting RnoteFileMaj0Min5Patch9 to newest file version failed.")
         } else if semver::VersionReq::parse(">=0.5.0")
@@ -85,16 +291,20 @@ impl FileFormatLoader for RnoteFile {
             .matches(&wrapper.version)
         {
             RnoteFileMaj0Min5Patch9::try_from(
-                ijson::from_value::<RnoteFileMaj0Min5Patch8>(&wrapper.data)
-                    .context("deserializing RnoteFileMaj0Min5Patch8 failed")?,
+                ijson::from_value::<RnoteFileMaj0Min5Patch8>(&wrapper.data).map_err(|e| {
+                    EngineError::Decode(format!(
+                        "deserializing RnoteFileMaj0Min5Patch8 failed, {e}"
+                    ))
+                })?,
             )
             .and_then(RnoteFileMaj0Min6::try_from)
             .context("converting RnoteFileMaj0Min5Patch8 to newest file version failed.")
         } else {
-            Err(anyhow::anyhow!(
+            Err(EngineError::UnsupportedFormat(format!(
                 "failed to load rnote file from bytes, unsupported version: {}.",
                 wrapper.version
             ))
+            .into())
         }
     }
 }
@@ -115,3 +325,88 @@ impl FileFormatSaver for RnoteFile {
         Ok(compressed)
     }
 }
+
+/// A fragment of a `.rnote` file, holding strokes that are transferred between windows through the clipboard.
+///
+/// It is versioned and compressed the same way as the file, and preserves all stroke data without loss.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "rnote_fragment")]
+pub struct RnoteFragment {
+    #[serde(rename = "stroke_content")]
+    pub stroke_content: StrokeContent,
+}
+
+impl RnoteFragment {
+    pub const MIME_TYPE: &str = "application/x-rnote-fragment";
+
+    pub fn new(stroke_content: StrokeContent) -> Self {
+        Self { stroke_content }
+    }
+}
+
+impl FileFormatLoader for RnoteFragment {
+    fn load_from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let wrapper = serde_json::from_slice::<RnotefileWrapper>(
+            &decompress_from_gzip(bytes, &Progress::default())
+                .context("decompressing bytes failed.")?,
+        )
+        .map_err(|e| {
+            EngineError::Decode(format!(
+                "deserializing RnotefileWrapper from bytes failed, {e}"
+            ))
+        })?;
+        let current_version = semver::Version::parse(RnoteFile::SEMVER).unwrap();
+        if wrapper.version.major != current_version.major
+            || wrapper.version.minor != current_version.minor
+        {
+            return Err(EngineError::UnsupportedFormat(format!(
+                "failed to load rnote fragment from bytes, unsupported version: {}.",
+                wrapper.version
+            ))
+            .into());
+        }
+
+        ijson::from_value::<Self>(&wrapper.data).context("deserializing RnoteFragment failed.")
+    }
+}
+
+impl FileFormatSaver for RnoteFragment {
+    fn save_as_bytes(&self, _file_name: &str) -> anyhow::Result<Vec<u8>> {
+        let wrapper = RnotefileWrapper {
+            version: semver::Version::parse(RnoteFile::SEMVER).unwrap(),
+            data: ijson::to_value(self)
+                .context("converting RnoteFragment to JSON value failed.")?,
+        };
+        let compressed = compress_to_gzip(
+            serde_json::to_string(&wrapper)
+                .context("Serializing RnoteFileWrapper failed.")?
+                .as_bytes(),
+        )
+        .context("compressing bytes failed.")?;
+
+        Ok(compressed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::close_truncated_json;
+
+    #[test]
+    fn close_truncated() {
+        assert_eq!(
+            close_truncated_json(r#"{"a":[1,2,{"b":"x,y"#).as_deref(),
+            Some(r#"{"a":[1,2]}"#)
+        );
+        assert_eq!(
+            close_truncated_json(r#"{"a":{"b":1,"c":[3"#).as_deref(),
+            Some(r#"{"a":{"b":1}}"#)
+        );
+        assert_eq!(
+            close_truncated_json(r#"{"a":"\"}",}"#).as_deref(),
+            Some(r#"{"a":"\"}",}"#)
+        );
+        assert_eq!(close_truncated_json(r#"{"a":1]"#), None);
+        assert_eq!(close_truncated_json(r#"{"a":1"#), None);
+    }
+}
@@ -1,6 +1,11 @@
 // Imports
 use super::FromXmlAttributeValue;
-use super::{AsXmlAttributeValue, FileFormatLoader, FileFormatSaver, XmlLoadable, XmlWritable};
+use super::{
+    AsXmlAttributeValue, FileFormatLoader, FileFormatSaver, XmlLoadable, XmlWritable,
+    DECOMPRESSED_SIZE_MAX,
+};
+use crate::EngineError;
+use anyhow::Context;
 use roxmltree::{Node, NodeType};
 use serde::{Deserialize, Serialize};
 use std::io::{Read, Write};
@@ -17,10 +22,21 @@ fn compress_to_gzip(to_compress: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
 }
 
 /// Decompress from gzip.
+///
+/// Fails when the decompressed data exceeds [DECOMPRESSED_SIZE_MAX].
 fn decompress_from_gzip(compressed: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
-    let mut decoder = flate2::read::MultiGzDecoder::new(compressed);
+    let mut decoder = flate2::read::MultiGzDecoder::new(compressed).take(DECOMPRESSED_SIZE_MAX + 1);
     let mut bytes: Vec<u8> = Vec::new();
-    decoder.read_to_end(&mut bytes)?;
+    decoder
+        .read_to_end(&mut bytes)
+        .map_err(|e| EngineError::Decode(format!("decompressing gzip data failed, {e}")))?;
+    if bytes.len() as u64 > DECOMPRESSED_SIZE_MAX {
+        return Err(EngineError::Decode(format!(
+            "decompressed data exceeds the maximum size of {DECOMPRESSED_SIZE_MAX} bytes"
+        ))
+        .into());
+    }
+
     Ok(bytes)
 }
 
@@ -37,13 +53,19 @@ pub struct XoppFile {
 
 impl FileFormatLoader for XoppFile {
     fn load_from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
-        let decompressed = String::from_utf8(decompress_from_gzip(bytes)?)?;
+        let decompressed = String::from_utf8(decompress_from_gzip(bytes)?)
+            .map_err(|e| EngineError::Decode(format!("xopp file is not valid UTF-8, {e}")))?;
         let parsed_doc = roxmltree::Document::parse_with_options(
             decompressed.as_str(),
             roxmltree::ParsingOptions::default(),
-        )?;
+        )
+        .map_err(|e| EngineError::Decode(format!("parsing xopp Xml failed, {e}")))?;
         let mut xopp_root = XoppRoot::default();
-        xopp_root.load_from_xml(parsed_doc.root_element())?;
+        xopp_root
+            .load_from_xml(parsed_doc.root_element())
+            .context(EngineError::Decode(String::from(
+                "loading xopp root from Xml failed",
+            )))?;
         Ok(Self { xopp_root })
     }
 }
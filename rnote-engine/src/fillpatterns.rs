@@ -0,0 +1,137 @@
+// Imports
+use crate::render;
+use once_cell::sync::Lazy;
+use p2d::bounding_volume::Aabb;
+use piet::RenderContext;
+use rnote_compose::helpers::{AabbHelpers, Vector2Helpers};
+use rnote_compose::shapes::ShapeBehaviour;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::RwLock;
+
+/// Fill patterns are not drawn when filling the shape would need more tiles than this.
+const FILL_TILES_MAX: usize = 10_000;
+
+/// The fill patterns of all loaded documents, by their id.
+static FILL_PATTERNS: Lazy<RwLock<HashMap<String, FillPattern>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// A tileable pattern made from strokes, that shapes can be filled with.
+///
+/// Is stored in the document file, so that hand-drawn hatches and textures can be reused.
+/// The id is derived from the content, so the same pattern has the same id in every document.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "fill_pattern")]
+pub struct FillPattern {
+    #[serde(rename = "id")]
+    pub id: String,
+    /// The name shown to the user.
+    #[serde(rename = "name")]
+    pub name: String,
+    /// The size of a tile in document coordinate space.
+    #[serde(
+        rename = "tile_size",
+        with = "rnote_compose::serialize::na_vector2_f64_dp3"
+    )]
+    pub tile_size: na::Vector2<f64>,
+    /// The rendered tile, placed relative to the tile origin.
+    ///
+    /// Can extend beyond the tile, so that strokes at the edges of the tile overlap seamlessly into the next one.
+    #[serde(rename = "image")]
+    pub image: render::Image,
+}
+
+impl FillPattern {
+    /// The scale-factor the tile is rendered with, so that it stays sharp when zoomed in.
+    pub const IMAGE_SCALE: f64 = 2.0;
+    /// The minimum size of a tile.
+    pub const TILE_SIZE_MIN: f64 = 1.0;
+
+    /// Create a fill pattern from the rendered tile.
+    pub fn new(name: String, tile_size: na::Vector2<f64>, image: render::Image) -> Self {
+        let mut hasher = DefaultHasher::new();
+        image.data.as_ref().hash(&mut hasher);
+        image.pixel_width.hash(&mut hasher);
+        image.pixel_height.hash(&mut hasher);
+        tile_size[0].to_bits().hash(&mut hasher);
+        tile_size[1].to_bits().hash(&mut hasher);
+
+        Self {
+            id: format!("{:016x}", hasher.finish()),
+            name,
+            tile_size,
+            image,
+        }
+    }
+
+    /// Fill the path with the tiles of the pattern.
+    ///
+    /// The tiles are aligned to the document origin, so adjacent shapes filled with the same pattern line up.
+    pub(crate) fn draw_fill(
+        &self,
+        cx: &mut impl piet::RenderContext,
+        path: &kurbo::BezPath,
+        bounds: Aabb,
+    ) -> anyhow::Result<()> {
+        let tile_size = self
+            .tile_size
+            .maxs(&na::Vector2::repeat(Self::TILE_SIZE_MIN));
+        let first = na::vector![
+            (bounds.mins[0] / tile_size[0]).floor(),
+            (bounds.mins[1] / tile_size[1]).floor()
+        ];
+        let last = na::vector![
+            (bounds.maxs[0] / tile_size[0]).ceil(),
+            (bounds.maxs[1] / tile_size[1]).ceil()
+        ];
+        let n_tiles = (last - first).product();
+        if n_tiles > FILL_TILES_MAX as f64 {
+            log::warn!(
+                "not drawing fill pattern `{}`, it would need {n_tiles} tiles",
+                self.name
+            );
+            return Ok(());
+        }
+
+        let piet_image = cx
+            .make_image(
+                self.image.pixel_width as usize,
+                self.image.pixel_height as usize,
+                &self.image.data,
+                piet::ImageFormat::try_from(self.image.memory_format)?,
+            )
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        let image_bounds = self.image.rect.bounds();
+
+        cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        cx.clip(path.clone());
+        for y in (first[1] as i64)..(last[1] as i64) {
+            for x in (first[0] as i64)..(last[0] as i64) {
+                let tile_origin = na::vector![x as f64 * tile_size[0], y as f64 * tile_size[1]];
+                let dest_rect = image_bounds.translate(tile_origin).to_kurbo_rect();
+                cx.draw_image(&piet_image, dest_rect, piet::InterpolationMode::Bilinear);
+            }
+        }
+        cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        Ok(())
+    }
+}
+
+/// Registers fill patterns, so that the shapes that reference them by their id can be drawn.
+///
+/// Patterns stay registered when the document is closed, as the shapes might have been copied into other documents.
+pub fn register_fill_patterns<'a>(fill_patterns: impl IntoIterator<Item = &'a FillPattern>) {
+    let mut registered = FILL_PATTERNS.write().unwrap();
+    for fill_pattern in fill_patterns {
+        registered
+            .entry(fill_pattern.id.clone())
+            .or_insert_with(|| fill_pattern.clone());
+    }
+}
+
+/// The registered fill pattern with the id.
+pub fn fill_pattern(id: &str) -> Option<FillPattern> {
+    FILL_PATTERNS.read().unwrap().get(id).cloned()
+}
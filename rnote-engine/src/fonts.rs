@@ -0,0 +1,346 @@
+// Imports
+use crate::render::USVG_FONTDB;
+use crate::strokes::textstroke::{TextAttribute, TextStyle};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+
+/// The fallback chain for font families that are not available on the system.
+///
+/// The first available family is used, so that missing fonts are replaced the same way on every machine.
+pub const FONT_FAMILY_FALLBACKS: [&str; 5] = [
+    "Cantarell",
+    "Noto Sans",
+    "DejaVu Sans",
+    "Liberation Sans",
+    "Arial",
+];
+/// The generic font family that is used when none of the fallbacks are available.
+pub const FONT_FAMILY_FALLBACK_GENERIC: &str = "sans-serif";
+/// Generic font family names, which are always resolved by the text backend.
+const GENERIC_FONT_FAMILIES: [&str; 6] = [
+    "serif",
+    "sans-serif",
+    "sans",
+    "monospace",
+    "cursive",
+    "fantasy",
+];
+/// Font faces larger than this are not embedded.
+const EMBED_FACE_SIZE_MAX: usize = 16 * 1024 * 1024;
+
+/// The lowercase names of all font families that are installed on the system.
+///
+/// Embedded fonts are excluded, because the text backend can't use them.
+static SYSTEM_FONT_FAMILIES: Lazy<HashSet<String>> = Lazy::new(|| {
+    USVG_FONTDB
+        .read()
+        .unwrap()
+        .faces()
+        .filter(|face| !matches!(face.source, usvg::fontdb::Source::Binary(_)))
+        .flat_map(|face| face.families.iter().map(|(name, _)| name.to_lowercase()))
+        .collect()
+});
+
+/// The lowercase names of the embedded font families that were registered with the text backend.
+static REGISTERED_FONT_FAMILIES: Lazy<RwLock<HashSet<String>>> =
+    Lazy::new(|| RwLock::new(HashSet::new()));
+
+/// Is increased whenever embedded fonts are registered with the text backend.
+///
+/// The font maps of the text backend are per thread, each thread reloads its font map when it has not seen
+/// the current generation yet.
+static REGISTERED_FONTS_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static THREAD_FONTS_GENERATION: std::cell::Cell<usize> = std::cell::Cell::new(0);
+}
+
+/// Cache for resolved font families, as resolving is done whenever text layouts are built.
+static RESOLVED_FONT_FAMILIES: Lazy<RwLock<HashMap<String, String>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// A font face that is embedded in the document.
+///
+/// Is stored in the document file, so that it can be used on machines that don't have the font installed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "embedded_font")]
+pub struct EmbeddedFont {
+    /// The font family name.
+    #[serde(rename = "family")]
+    pub family: String,
+    /// The index of the face in the font data, for font collections.
+    #[serde(rename = "index")]
+    pub index: u32,
+    /// The font data.
+    ///
+    /// Is (de)serialized with base64 encoding.
+    #[serde(rename = "data", with = "crate::utils::glib_bytes_base64")]
+    pub data: glib::Bytes,
+}
+
+impl Default for EmbeddedFont {
+    fn default() -> Self {
+        Self {
+            family: String::default(),
+            index: 0,
+            data: glib::Bytes::from_owned(Vec::new()),
+        }
+    }
+}
+
+/// Whether the font family is a generic family, installed on the system or an embedded font registered with
+/// the text backend.
+pub fn is_font_family_available(family: &str) -> bool {
+    let family = family.to_lowercase();
+    GENERIC_FONT_FAMILIES.contains(&family.as_str())
+        || SYSTEM_FONT_FAMILIES.contains(&family)
+        || REGISTERED_FONT_FAMILIES.read().unwrap().contains(&family)
+}
+
+/// Resolves the font family to the family that is used for rendering.
+///
+/// Returns the family itself if it is available, else the first available family of [FONT_FAMILY_FALLBACKS].
+pub fn resolve_font_family(family: &str) -> String {
+    reload_text_backend_fonts_if_changed();
+
+    if let Some(resolved) = RESOLVED_FONT_FAMILIES.read().unwrap().get(family) {
+        return resolved.clone();
+    }
+    let resolved = if is_font_family_available(family) {
+        family.to_string()
+    } else {
+        let fallback = FONT_FAMILY_FALLBACKS
+            .iter()
+            .find(|fallback| is_font_family_available(fallback))
+            .copied()
+            .unwrap_or(FONT_FAMILY_FALLBACK_GENERIC)
+            .to_string();
+        log::debug!("font family `{family}` is not available, falling back to `{fallback}`");
+        fallback
+    };
+    RESOLVED_FONT_FAMILIES
+        .write()
+        .unwrap()
+        .insert(family.to_string(), resolved.clone());
+    resolved
+}
+
+/// The font families that are used by the text, together with the part of the text that is set in them.
+///
+/// Includes the font families of the ranged text attributes.
+pub fn text_font_families<'a>(
+    text: &'a str,
+    text_style: &'a TextStyle,
+) -> impl Iterator<Item = (&'a str, &'a str)> {
+    std::iter::once((text_style.font_family.as_str(), text)).chain(
+        text_style
+            .ranged_text_attributes
+            .iter()
+            .filter_map(|ranged_attr| match &ranged_attr.attribute {
+                TextAttribute::FontFamily(family) => {
+                    Some((family.as_str(), text.get(ranged_attr.range.clone())?))
+                }
+                _ => None,
+            }),
+    )
+}
+
+/// Collects the font faces of the given families for embedding, subset to the glyphs of the given texts.
+///
+/// Generic families, faces that don't permit embedding by their license and very large faces are skipped.
+/// Subsetting drops the layout tables, so ligatures and other advanced shaping features are not available when
+/// the text is rendered with an embedded font.
+pub fn collect_embedded_fonts<'a>(
+    families: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Vec<EmbeddedFont> {
+    let mut families_chars = HashMap::<String, HashSet<char>>::new();
+    for (family, text) in families {
+        let family = family.to_lowercase();
+        if GENERIC_FONT_FAMILIES.contains(&family.as_str()) {
+            continue;
+        }
+        families_chars
+            .entry(family)
+            .or_default()
+            .extend(text.chars());
+    }
+    if families_chars.is_empty() {
+        return vec![];
+    }
+    let fontdb = USVG_FONTDB.read().unwrap();
+
+    fontdb
+        .faces()
+        .filter_map(|face| {
+            let (family, chars) = face.families.iter().find_map(|(name, _)| {
+                families_chars
+                    .get(&name.to_lowercase())
+                    .map(|chars| (name.clone(), chars))
+            })?;
+            fontdb
+                .with_face_data(face.id, |data, index| {
+                    if data.len() > EMBED_FACE_SIZE_MAX {
+                        log::warn!("not embedding face of font family `{family}`, it is too large");
+                        return None;
+                    }
+                    let ttf_face = ttf_parser::Face::parse(data, index).ok()?;
+                    if matches!(
+                        ttf_face.permissions(),
+                        Some(ttf_parser::Permissions::Restricted)
+                    ) {
+                        log::warn!(
+                            "not embedding face of font family `{family}`, its license restricts embedding"
+                        );
+                        return None;
+                    }
+                    let glyphs = std::iter::once(0)
+                        .chain(
+                            chars
+                                .iter()
+                                .filter_map(|c| ttf_face.glyph_index(*c))
+                                .map(|glyph_id| glyph_id.0),
+                        )
+                        .collect::<Vec<u16>>();
+                    let subset =
+                        match subsetter::subset(data, index, subsetter::Profile::pdf(&glyphs)) {
+                            Ok(subset) => subset,
+                            Err(e) => {
+                                log::warn!(
+                                    "not embedding face of font family `{family}`, subsetting failed, Err: {e:?}"
+                                );
+                                return None;
+                            }
+                        };
+                    Some(EmbeddedFont {
+                        family: family.clone(),
+                        // The subset contains only the single face
+                        index: 0,
+                        data: glib::Bytes::from_owned(subset),
+                    })
+                })
+                .flatten()
+        })
+        .collect()
+}
+
+/// Registers embedded fonts whose family is not already available.
+///
+/// They are loaded into the font database, which is used when converting text to paths,
+/// and registered with the text backend, so that text strokes are drawn with them.
+/// Registering with the text backend is only supported with fontconfig, on other platforms text strokes
+/// are drawn with the fallback font.
+pub fn register_embedded_fonts(embedded_fonts: &[EmbeddedFont]) {
+    let mut fontdb = USVG_FONTDB.write().unwrap();
+    let available_families = fontdb
+        .faces()
+        .flat_map(|face| face.families.iter().map(|(name, _)| name.to_lowercase()))
+        .collect::<HashSet<String>>();
+    let mut registered_families = REGISTERED_FONT_FAMILIES.write().unwrap();
+    let mut registered_any = false;
+
+    for embedded_font in embedded_fonts
+        .iter()
+        .filter(|embedded_font| !available_families.contains(&embedded_font.family.to_lowercase()))
+    {
+        fontdb.load_font_data(embedded_font.data.to_vec());
+
+        match fontconfig::register_font_data(&embedded_font.data) {
+            Ok(()) => {
+                registered_families.insert(embedded_font.family.to_lowercase());
+                registered_any = true;
+            }
+            Err(e) => log::warn!(
+                "registering embedded font of family `{}` with the text backend failed, Err: {e:?}",
+                embedded_font.family
+            ),
+        }
+    }
+
+    if registered_any {
+        RESOLVED_FONT_FAMILIES.write().unwrap().clear();
+        REGISTERED_FONTS_GENERATION.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Reloads the font map of the text backend of the current thread, if embedded fonts were registered since it was
+/// last loaded.
+fn reload_text_backend_fonts_if_changed() {
+    let generation = REGISTERED_FONTS_GENERATION.load(Ordering::SeqCst);
+    THREAD_FONTS_GENERATION.with(|thread_generation| {
+        if thread_generation.get() != generation {
+            fontconfig::reload_font_map();
+            thread_generation.set(generation);
+        }
+    });
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod fontconfig {
+    use glib::prelude::*;
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    /// Writes the font data to the cache directory and adds it to the application fonts of fontconfig.
+    pub(super) fn register_font_data(data: &[u8]) -> anyhow::Result<()> {
+        let checksum = glib::compute_checksum_for_data(glib::ChecksumType::Sha256, data)
+            .ok_or_else(|| anyhow::anyhow!("computing the checksum of the font data failed"))?;
+        let dir = glib::user_cache_dir().join("rnote").join("embedded-fonts");
+        std::fs::create_dir_all(&dir)?;
+        let file = dir.join(format!("{checksum}.{}", font_file_extension(data)));
+        if !file.exists() {
+            std::fs::write(&file, data)?;
+        }
+
+        let file = CString::new(file.as_os_str().as_bytes())?;
+        // SAFETY: the file path is a valid nul-terminated string, a null config refers to the current config.
+        let added = unsafe {
+            fontconfig_sys::FcConfigAppFontAddFile(std::ptr::null_mut(), file.as_ptr() as *const u8)
+        };
+        if added == 0 {
+            return Err(anyhow::anyhow!("fontconfig could not add the font file"));
+        }
+        Ok(())
+    }
+
+    /// The file extension that matches the format of the font data.
+    fn font_file_extension(data: &[u8]) -> &'static str {
+        match data.get(..4) {
+            Some(b"OTTO") => "otf",
+            Some(b"ttcf") => "ttc",
+            _ => "ttf",
+        }
+    }
+
+    /// Replaces the default font map of the current thread with a new one, so that it picks up the added
+    /// application fonts.
+    pub(super) fn reload_font_map() {
+        if !pangocairo::FontMap::default().is::<pangocairo::FontMap>() {
+            log::warn!(
+                "reloading the font map failed, the default font map is not a pangocairo font map"
+            );
+            return;
+        }
+        match pangocairo::FontMap::new().downcast::<pangocairo::FontMap>() {
+            Ok(font_map) => pangocairo::FontMap::set_default(Some(&font_map)),
+            Err(_) => {
+                log::warn!(
+                    "reloading the font map failed, the new font map is not a pangocairo font map"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+mod fontconfig {
+    pub(super) fn register_font_data(_data: &[u8]) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!(
+            "registering fonts with the text backend is only supported with fontconfig"
+        ))
+    }
+
+    pub(super) fn reload_font_map() {}
+}
@@ -11,14 +11,26 @@
 // Modules
 pub mod audioplayer;
 pub mod camera;
+pub mod cameraanimation;
 pub mod document;
 mod drawbehaviour;
 pub mod engine;
+pub mod error;
+pub mod exporters;
 pub mod fileformats;
+pub mod fillpatterns;
+pub mod fonts;
+pub mod ocr;
 pub mod pens;
+pub mod progress;
 pub mod render;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod spellcheck;
 pub mod store;
 pub mod strokes;
+mod svgoptimize;
+pub mod symbols;
 pub mod tasks;
 pub mod utils;
 pub mod widgetflags;
@@ -30,7 +42,9 @@ pub use document::Document;
 pub use drawbehaviour::DrawBehaviour;
 pub use drawbehaviour::DrawOnDocBehaviour;
 pub use engine::RnoteEngine;
+pub use error::EngineError;
 pub use pens::PenHolder;
+pub use progress::Progress;
 pub use store::StrokeStore;
 pub use widgetflags::WidgetFlags;
 
@@ -0,0 +1,125 @@
+// Imports
+use crate::render;
+use crate::strokes::textlayer::{TextLayer, TextLayerSpan};
+use p2d::bounding_volume::Aabb;
+
+/// Provides optical character recognition (OCR) for images, e.g. of scanned pages.
+pub trait OcrProvider: std::fmt::Debug + Send + Sync {
+    /// The language of the recognized text, e.g. `eng`.
+    fn language(&self) -> String;
+
+    /// Recognize the text in the image.
+    ///
+    /// The bounds of the spans of the returned text layer are relative to the pixel size of the image.
+    fn recognize(&self, image: &render::Image) -> anyhow::Result<TextLayer>;
+}
+
+/// Parse the Tsv output of tesseract into a text layer, for an image with the given pixel size.
+///
+/// Only words are taken into account, the blocks, paragraphs and lines only determine the line breaks.
+pub fn text_layer_from_tesseract_tsv(tsv: &str, pixel_width: u32, pixel_height: u32) -> TextLayer {
+    if pixel_width == 0 || pixel_height == 0 {
+        return TextLayer::default();
+    }
+    let (width, height) = (f64::from(pixel_width), f64::from(pixel_height));
+    let mut spans = vec![];
+    let mut line = 0;
+    let mut prev_line_id = None;
+
+    // Columns: level, page_num, block_num, par_num, line_num, word_num, left, top, width, height, conf, text
+    for row in tsv.lines() {
+        let columns = row.splitn(12, '\t').collect::<Vec<&str>>();
+        if columns.len() < 12 || columns[0] != "5" {
+            continue;
+        }
+        let text = columns[11].trim();
+        let Ok(numbers) = columns[1..11]
+            .iter()
+            .map(|c| c.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+        else {
+            continue;
+        };
+        // Negative confidences mark non-text elements
+        if text.is_empty() || numbers[9] < 0.0 {
+            continue;
+        }
+        let line_id = (numbers[0], numbers[1], numbers[2], numbers[3]);
+        if matches!(prev_line_id, Some(prev_line_id) if prev_line_id != line_id) {
+            line += 1;
+        }
+        prev_line_id = Some(line_id);
+
+        let (left, top, w, h) = (numbers[5], numbers[6], numbers[7], numbers[8]);
+        spans.push(TextLayerSpan {
+            text: text.to_string(),
+            bounds: Aabb::new(
+                na::point![left / width, top / height],
+                na::point![(left + w) / width, (top + h) / height],
+            ),
+            line,
+        });
+    }
+
+    TextLayer { spans }
+}
+
+/// An OCR provider backed by tesseract.
+#[cfg(feature = "ocr")]
+#[derive(Debug, Clone)]
+pub struct TesseractOcr {
+    language: String,
+}
+
+#[cfg(feature = "ocr")]
+impl TesseractOcr {
+    /// Use the trained data for the language, e.g. `eng`. Multiple languages can be combined with `+`, e.g. `eng+deu`.
+    pub fn new(language: &str) -> anyhow::Result<Self> {
+        // Fail early if the trained data for the language is not installed
+        tesseract::Tesseract::new(None, Some(language)).map_err(|e| {
+            anyhow::anyhow!("Initializing tesseract for language `{language}` failed, Err: {e}")
+        })?;
+        Ok(Self {
+            language: language.to_string(),
+        })
+    }
+}
+
+#[cfg(feature = "ocr")]
+impl OcrProvider for TesseractOcr {
+    fn language(&self) -> String {
+        self.language.clone()
+    }
+
+    fn recognize(&self, image: &render::Image) -> anyhow::Result<TextLayer> {
+        image.assert_valid()?;
+        let (width, height) = (image.pixel_width, image.pixel_height);
+
+        // Tesseract works best with dark text on a light background, so transparent areas become white
+        let gray = match image.memory_format {
+            render::ImageMemoryFormat::R8g8b8a8Premultiplied => image
+                .data
+                .chunks_exact(4)
+                .map(|p| {
+                    let white = 255 - u32::from(p[3]);
+                    let luma = (299 * (u32::from(p[0]) + white)
+                        + 587 * (u32::from(p[1]) + white)
+                        + 114 * (u32::from(p[2]) + white))
+                        / 1000;
+                    luma.min(255) as u8
+                })
+                .collect::<Vec<u8>>(),
+        };
+
+        let tsv = tesseract::Tesseract::new(None, Some(&self.language))
+            .map_err(|e| anyhow::anyhow!("Initializing tesseract failed, Err: {e}"))?
+            .set_frame(&gray, width as i32, height as i32, 1, width as i32)
+            .map_err(|e| anyhow::anyhow!("Setting tesseract image failed, Err: {e}"))?
+            .recognize()
+            .map_err(|e| anyhow::anyhow!("Recognizing text with tesseract failed, Err: {e}"))?
+            .get_tsv_text(0)
+            .map_err(|e| anyhow::anyhow!("Getting text from tesseract failed, Err: {e}"))?;
+
+        Ok(text_layer_from_tesseract_tsv(&tsv, width, height))
+    }
+}
@@ -1,6 +1,11 @@
 // Imports
+use super::drawingaids::{DrawingAidGuide, DrawingAidHandle};
+use super::inputpredictor::InputPredictor;
 use super::penbehaviour::{PenBehaviour, PenProgress};
 use super::pensconfig::brushconfig::BrushStyle;
+use super::pensconfig::BrushConfig;
+use super::sprayemitter::SprayEmitter;
+use super::stabilizer::Stabilizer;
 use super::PenStyle;
 use crate::engine::{EngineView, EngineViewMut};
 use crate::store::StrokeKey;
@@ -14,10 +19,12 @@ use rnote_compose::builders::{
     PenPathBuilderBehaviour, PenPathBuilderCreator, PenPathBuilderProgress, PenPathModeledBuilder,
 };
 use rnote_compose::builders::{PenPathCurvedBuilder, PenPathSimpleBuilder};
-use rnote_compose::penevents::PenEvent;
-use rnote_compose::penpath::Element;
-use rnote_compose::Constraints;
-use std::time::Instant;
+use rnote_compose::helpers::AabbHelpers;
+use rnote_compose::penevents::{ModifierKey, PenEvent, PenState};
+use rnote_compose::penpath::{Element, Segment};
+use rnote_compose::style::{indicators, Composer};
+use rnote_compose::{Constraints, PenPath};
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 enum BrushState {
@@ -25,6 +32,17 @@ enum BrushState {
     Drawing {
         path_builder: Box<dyn PenPathBuilderBehaviour>,
         current_stroke_key: StrokeKey,
+        /// The guide the input is constrained to, either of a drawing aid or from the axis lock.
+        guide: Option<DrawingAidGuide>,
+        start_pos: na::Vector2<f64>,
+        predictor: InputPredictor,
+        stabilizer: Stabilizer,
+        /// Emits the elements of airbrush strokes over time.
+        spray_emitter: Option<SprayEmitter>,
+    },
+    ModifyingDrawingAid {
+        handle: DrawingAidHandle,
+        prev_pos: na::Vector2<f64>,
     },
 }
 
@@ -67,10 +85,30 @@ impl PenBehaviour for Brush {
         let mut widget_flags = WidgetFlags::default();
 
         let pen_progress = match (&mut self.state, event) {
-            (BrushState::Idle, PenEvent::Down { element, .. }) => {
-                if !element
+            (BrushState::Idle, PenEvent::Down { mut element, .. }) => {
+                let drawing_aid_handle = engine_view
+                    .pens_config
+                    .drawing_aids
+                    .handle_at(element.pos, engine_view.camera.total_zoom());
+
+                if let Some(handle) = drawing_aid_handle {
+                    self.state = BrushState::ModifyingDrawingAid {
+                        handle,
+                        prev_pos: element.pos,
+                    };
+
+                    PenProgress::InProgress
+                } else if !element
                     .filter_by_bounds(engine_view.doc.bounds().loosened(Self::INPUT_OVERSHOOT))
                 {
+                    let guide = engine_view
+                        .pens_config
+                        .drawing_aids
+                        .guide_at(element.pos, engine_view.camera.total_zoom());
+                    if let Some(guide) = guide {
+                        element.pos = guide.project(element.pos);
+                    }
+
                     if engine_view.pens_config.brush_config.style == BrushStyle::Marker {
                         play_marker_sound(engine_view);
                     } else {
@@ -96,19 +134,27 @@ impl PenBehaviour for Brush {
                         ),
                     );
 
-                    engine_view.store.regenerate_rendering_for_stroke(
-                        current_stroke_key,
-                        engine_view.camera.viewport(),
-                        engine_view.camera.image_scale(),
-                    );
+                    // The stroke is rendered directly while drawing, images are generated when it is finished
+                    engine_view.store.start_live_rendering(current_stroke_key);
+
+                    let airbrush =
+                        engine_view.pens_config.brush_config.style == BrushStyle::Airbrush;
+                    // The elements emitted by the airbrush must stay unaltered, so that the spray accumulates evenly
+                    let builder_type = if airbrush {
+                        PenPathBuilderType::Simple
+                    } else {
+                        engine_view.pens_config.brush_config.builder_type
+                    };
 
                     self.state = BrushState::Drawing {
-                        path_builder: new_builder(
-                            engine_view.pens_config.brush_config.builder_type,
-                            element,
-                            now,
-                        ),
+                        path_builder: new_builder(builder_type, element, now),
                         current_stroke_key,
+                        guide,
+                        start_pos: element.pos,
+                        predictor: InputPredictor::default(),
+                        stabilizer: Stabilizer::new(element),
+                        spray_emitter: airbrush
+                            .then(|| SprayEmitter::new(element, now, engine_view.tasks_tx.clone())),
                     };
 
                     PenProgress::InProgress
@@ -117,6 +163,31 @@ impl PenBehaviour for Brush {
                 }
             }
             (BrushState::Idle, _) => PenProgress::Idle,
+            (
+                BrushState::ModifyingDrawingAid { handle, prev_pos },
+                PenEvent::Down {
+                    element,
+                    modifier_keys,
+                },
+            ) => {
+                engine_view.pens_config.drawing_aids.drag_handle(
+                    *handle,
+                    *prev_pos,
+                    element.pos,
+                    modifier_keys.contains(&ModifierKey::KeyboardCtrl),
+                );
+                *prev_pos = element.pos;
+                widget_flags.redraw = true;
+
+                PenProgress::InProgress
+            }
+            (BrushState::ModifyingDrawingAid { .. }, PenEvent::Up { .. } | PenEvent::Cancel) => {
+                self.state = BrushState::Idle;
+                widget_flags.redraw = true;
+
+                PenProgress::Finished
+            }
+            (BrushState::ModifyingDrawingAid { .. }, _) => PenProgress::InProgress,
             (
                 BrushState::Drawing {
                     current_stroke_key, ..
@@ -127,7 +198,7 @@ impl PenBehaviour for Brush {
                 engine_view
                     .store
                     .update_geometry_for_stroke(*current_stroke_key);
-                engine_view.store.regenerate_rendering_for_stroke_threaded(
+                engine_view.store.finish_live_rendering(
                     engine_view.tasks_tx.clone(),
                     *current_stroke_key,
                     engine_view.camera.viewport(),
@@ -150,9 +221,92 @@ impl PenBehaviour for Brush {
                 BrushState::Drawing {
                     path_builder,
                     current_stroke_key,
+                    guide,
+                    start_pos,
+                    predictor,
+                    stabilizer,
+                    spray_emitter,
                 },
                 pen_event,
             ) => {
+                let rope_length = engine_view.pens_config.brush_config.stabilizer_rope_length
+                    / engine_view.camera.total_zoom();
+                let pen_event = match pen_event {
+                    PenEvent::Down {
+                        element,
+                        modifier_keys,
+                    } if rope_length > 0.0 => match stabilizer.pull(element, rope_length) {
+                        Some(element) => PenEvent::Down {
+                            element,
+                            modifier_keys,
+                        },
+                        // The brush doesn't move while the rope is slack
+                        None => return (PenProgress::InProgress, widget_flags),
+                    },
+                    PenEvent::Up {
+                        element,
+                        modifier_keys,
+                    } if rope_length > 0.0 => {
+                        // Catch up with the pointer before the stroke is finished
+                        extend_stroke_w_elements(
+                            path_builder.as_mut(),
+                            *current_stroke_key,
+                            stabilizer.catch_up(element, rope_length),
+                            guide.as_ref(),
+                            &modifier_keys,
+                            now,
+                            engine_view,
+                        );
+                        PenEvent::Up {
+                            element,
+                            modifier_keys,
+                        }
+                    }
+                    pen_event => pen_event,
+                };
+                // Lock to the nearest axis once the direction is known, while the modifier is pressed
+                if let PenEvent::Down {
+                    element,
+                    modifier_keys,
+                } = &pen_event
+                {
+                    if guide.is_none()
+                        && modifier_keys.contains(&ModifierKey::KeyboardShift)
+                        && (element.pos - *start_pos).magnitude()
+                            > Self::AXIS_LOCK_THRESHOLD / engine_view.camera.total_zoom()
+                    {
+                        *guide = Some(axis_lock_guide(
+                            *start_pos,
+                            element.pos,
+                            engine_view.pens_config.brush_config.axis_lock_angle,
+                        ));
+                    }
+                }
+                let pen_event = match guide {
+                    Some(guide) => constrain_event_to_guide(pen_event, guide),
+                    None => pen_event,
+                };
+                if let PenEvent::Down { element, .. } = &pen_event {
+                    predictor.push(*element, now);
+                }
+                // The airbrush only extends the stroke with the elements emitted in its intervals
+                let pen_event = match (spray_emitter.as_mut(), pen_event) {
+                    (Some(spray_emitter), PenEvent::Down { element, .. }) => {
+                        extend_stroke_w_elements(
+                            path_builder.as_mut(),
+                            *current_stroke_key,
+                            spray_emitter.emit(element, now),
+                            None,
+                            &[],
+                            now,
+                            engine_view,
+                        );
+                        widget_flags.store_modified = true;
+
+                        return (PenProgress::InProgress, widget_flags);
+                    }
+                    (_, pen_event) => pen_event,
+                };
                 match path_builder.handle_event(pen_event, now, Constraints::default()) {
                     PenPathBuilderProgress::InProgress => {
                         if engine_view.pens_config.brush_config.style != BrushStyle::Marker {
@@ -166,52 +320,40 @@ impl PenBehaviour for Brush {
                             trigger_brush_sound(engine_view);
                         }
 
-                        let n_segments = segments.len();
-
-                        if n_segments != 0 {
+                        if !segments.is_empty() {
                             if let Some(Stroke::BrushStroke(brushstroke)) =
                                 engine_view.store.get_stroke_mut(*current_stroke_key)
                             {
                                 brushstroke.extend_w_segments(segments);
                                 widget_flags.store_modified = true;
                             }
-
-                            engine_view.store.append_rendering_last_segments(
-                                engine_view.tasks_tx.clone(),
-                                *current_stroke_key,
-                                n_segments,
-                                engine_view.camera.viewport(),
-                                engine_view.camera.image_scale(),
-                            );
                         }
 
                         PenProgress::InProgress
                     }
                     PenPathBuilderProgress::Finished(segments) => {
-                        let n_segments = segments.len();
-
-                        if n_segments != 0 {
+                        if !segments.is_empty() {
                             if let Some(Stroke::BrushStroke(brushstroke)) =
                                 engine_view.store.get_stroke_mut(*current_stroke_key)
                             {
                                 brushstroke.extend_w_segments(segments);
                                 widget_flags.store_modified = true;
                             }
+                        }
 
-                            engine_view.store.append_rendering_last_segments(
-                                engine_view.tasks_tx.clone(),
-                                *current_stroke_key,
-                                n_segments,
-                                engine_view.camera.viewport(),
-                                engine_view.camera.image_scale(),
-                            );
+                        // Strokes along a guide are already exact, and airbrush strokes would lose their accumulated spray
+                        let smoothing = engine_view.pens_config.brush_config.smoothing;
+                        if smoothing > 0.0 && guide.is_none() && spray_emitter.is_none() {
+                            engine_view
+                                .store
+                                .smooth_brushstrokes(&[*current_stroke_key], smoothing);
                         }
 
                         // Finish up the last stroke
                         engine_view
                             .store
                             .update_geometry_for_stroke(*current_stroke_key);
-                        engine_view.store.regenerate_rendering_for_stroke_threaded(
+                        engine_view.store.finish_live_rendering(
                             engine_view.tasks_tx.clone(),
                             *current_stroke_key,
                             engine_view.camera.viewport(),
@@ -246,9 +388,36 @@ impl DrawOnDocBehaviour for Brush {
             .style_for_current_options();
 
         match &self.state {
-            BrushState::Idle => None,
-            BrushState::Drawing { path_builder, .. } => {
-                path_builder.bounds(&style, engine_view.camera.zoom())
+            BrushState::Idle | BrushState::ModifyingDrawingAid { .. } => None,
+            BrushState::Drawing {
+                path_builder,
+                guide,
+                predictor,
+                stabilizer,
+                ..
+            } => {
+                let bounds = path_builder.bounds(&style, engine_view.camera.zoom());
+                let bounds = match stabilizer.rope() {
+                    Some((brush, pointer)) => {
+                        let rope_bounds = Aabb::new_positive(brush.into(), pointer.into())
+                            .loosened(
+                                indicators::POS_INDICATOR_RADIUS / engine_view.camera.total_zoom(),
+                            );
+                        Some(bounds.map_or(rope_bounds, |bounds| bounds.merged(&rope_bounds)))
+                    }
+                    None => bounds,
+                };
+                match predicted_path(predictor, guide.as_ref(), engine_view) {
+                    Some(predicted) => {
+                        let predicted_bounds = predicted.composed_bounds(&style);
+                        Some(
+                            bounds.map_or(predicted_bounds, |bounds| {
+                                bounds.merged(&predicted_bounds)
+                            }),
+                        )
+                    }
+                    None => bounds,
+                }
             }
         }
     }
@@ -261,20 +430,43 @@ impl DrawOnDocBehaviour for Brush {
         cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
 
         match &self.state {
-            BrushState::Idle => {}
-            BrushState::Drawing { path_builder, .. } => {
+            BrushState::Idle | BrushState::ModifyingDrawingAid { .. } => {}
+            BrushState::Drawing {
+                path_builder,
+                guide,
+                predictor,
+                stabilizer,
+                ..
+            } => {
                 match engine_view.pens_config.brush_config.style {
                     BrushStyle::Marker => {
                         // Don't draw the marker, as the pen would render on top of other strokes, while the stroke itself would render underneath them.
                     }
-                    BrushStyle::Solid | BrushStyle::Textured => {
+                    BrushStyle::Airbrush => {
+                        // The spray is emitted over time, so it can't be predicted
+                    }
+                    BrushStyle::Solid | BrushStyle::Textured | BrushStyle::Grain => {
                         let style = engine_view
                             .pens_config
                             .brush_config
                             .style_for_current_options();
                         path_builder.draw_styled(cx, &style, engine_view.camera.total_zoom());
+
+                        // The predicted segment is only drawn provisionally and never becomes part of the stroke
+                        if let Some(predicted) =
+                            predicted_path(predictor, guide.as_ref(), engine_view)
+                        {
+                            predicted.draw_composed(cx, &style);
+                        }
                     }
                 }
+
+                // The rope of the stabilizer
+                if let Some((brush, pointer)) = stabilizer.rope() {
+                    let total_zoom = engine_view.camera.total_zoom();
+                    indicators::draw_vec_indicator(cx, PenState::Down, brush, pointer, total_zoom);
+                    indicators::draw_pos_indicator(cx, PenState::Up, pointer, total_zoom);
+                }
             }
         }
 
@@ -285,6 +477,42 @@ impl DrawOnDocBehaviour for Brush {
 
 impl Brush {
     const INPUT_OVERSHOOT: f64 = 30.0;
+    /// The distance in surface pixels the input needs to move before the axis lock direction is determined.
+    const AXIS_LOCK_THRESHOLD: f64 = 6.0;
+
+    /// Emit the airbrush spray for the time that has elapsed, while the pen is held down.
+    pub(crate) fn emit_airbrush_spray(
+        &mut self,
+        now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        if let BrushState::Drawing {
+            path_builder,
+            current_stroke_key,
+            spray_emitter: Some(spray_emitter),
+            ..
+        } = &mut self.state
+        {
+            let elements = spray_emitter.tick(now);
+            if !elements.is_empty() {
+                extend_stroke_w_elements(
+                    path_builder.as_mut(),
+                    *current_stroke_key,
+                    elements,
+                    None,
+                    &[],
+                    now,
+                    engine_view,
+                );
+                widget_flags.redraw = true;
+                widget_flags.store_modified = true;
+            }
+        }
+
+        widget_flags
+    }
 }
 
 fn play_marker_sound(engine_view: &mut EngineViewMut) {
@@ -299,6 +527,102 @@ fn trigger_brush_sound(engine_view: &mut EngineViewMut) {
     }
 }
 
+/// The path from the newest input to the predicted input, when input prediction is enabled.
+fn predicted_path(
+    predictor: &InputPredictor,
+    guide: Option<&DrawingAidGuide>,
+    engine_view: &EngineView,
+) -> Option<PenPath> {
+    let ahead = engine_view.pens_config.brush_config.input_prediction;
+    if ahead <= 0.0 {
+        return None;
+    }
+    let (last, mut predicted) = predictor.predict(Duration::from_secs_f64(ahead * 1e-3))?;
+    if let Some(guide) = guide {
+        predicted.pos = guide.project(predicted.pos);
+    }
+    Some(PenPath::new_w_segments(
+        last,
+        [Segment::LineTo { end: predicted }],
+    ))
+}
+
+/// The line through the start position, in the direction to the position snapped to multiples of the angle step (in degrees).
+fn axis_lock_guide(
+    start_pos: na::Vector2<f64>,
+    pos: na::Vector2<f64>,
+    angle_step: f64,
+) -> DrawingAidGuide {
+    let angle_step = angle_step
+        .clamp(
+            BrushConfig::AXIS_LOCK_ANGLE_MIN,
+            BrushConfig::AXIS_LOCK_ANGLE_MAX,
+        )
+        .to_radians();
+    let rel = pos - start_pos;
+    let angle = (rel[1].atan2(rel[0]) / angle_step).round() * angle_step;
+    DrawingAidGuide::Line {
+        point: start_pos,
+        direction: na::vector![angle.cos(), angle.sin()],
+    }
+}
+
+/// Feed the elements to the path builder as down events and extend the stroke with the built segments.
+fn extend_stroke_w_elements(
+    path_builder: &mut dyn PenPathBuilderBehaviour,
+    key: StrokeKey,
+    elements: impl IntoIterator<Item = Element>,
+    guide: Option<&DrawingAidGuide>,
+    modifier_keys: &[ModifierKey],
+    now: Instant,
+    engine_view: &mut EngineViewMut,
+) {
+    for element in elements {
+        let event = PenEvent::Down {
+            element,
+            modifier_keys: modifier_keys.to_vec(),
+        };
+        let event = match guide {
+            Some(guide) => constrain_event_to_guide(event, guide),
+            None => event,
+        };
+        if let PenPathBuilderProgress::EmitContinue(segments) =
+            path_builder.handle_event(event, now, Constraints::default())
+        {
+            if let Some(Stroke::BrushStroke(brushstroke)) = engine_view.store.get_stroke_mut(key) {
+                brushstroke.extend_w_segments(segments);
+            }
+        }
+    }
+}
+
+/// Project the positions of the event elements onto the guide.
+fn constrain_event_to_guide(event: PenEvent, guide: &DrawingAidGuide) -> PenEvent {
+    match event {
+        PenEvent::Down {
+            mut element,
+            modifier_keys,
+        } => {
+            element.pos = guide.project(element.pos);
+            PenEvent::Down {
+                element,
+                modifier_keys,
+            }
+        }
+        PenEvent::Up {
+            mut element,
+            modifier_keys,
+        } => {
+            element.pos = guide.project(element.pos);
+            PenEvent::Up {
+                element,
+                modifier_keys,
+            }
+        }
+        event => event,
+    }
+}
+
 fn new_builder(
     builder_type: PenPathBuilderType,
     element: Element,
@@ -0,0 +1,428 @@
+// Imports
+use crate::engine::EngineView;
+use crate::DrawOnDocBehaviour;
+use once_cell::sync::Lazy;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use piet::RenderContext;
+use rnote_compose::color;
+use rnote_compose::helpers::Vector2Helpers;
+use serde::{Deserialize, Serialize};
+
+static DRAWING_AID_FILL_COLOR: Lazy<piet::Color> =
+    Lazy::new(|| color::GNOME_BRIGHTS[2].with_alpha(0.35));
+static DRAWING_AID_OUTLINE_COLOR: Lazy<piet::Color> =
+    Lazy::new(|| color::GNOME_DARKS[3].with_alpha(0.8));
+static DRAWING_AID_SCALE_COLOR: Lazy<piet::Color> =
+    Lazy::new(|| color::GNOME_DARKS[3].with_alpha(0.6));
+const DRAWING_AID_HANDLE_COLOR: piet::Color = color::GNOME_BLUES[3];
+/// The stroke width in surface pixels.
+const DRAWING_AID_OUTLINE_WIDTH: f64 = 1.5;
+/// The handle radius in surface pixels.
+const DRAWING_AID_HANDLE_RADIUS: f64 = 8.0;
+
+/// A straightedge placed on the document.
+///
+/// Brush input that starts close to one of its long edges is constrained to the edge.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "ruler")]
+pub struct Ruler {
+    #[serde(rename = "enabled")]
+    pub enabled: bool,
+    #[serde(
+        rename = "center",
+        with = "rnote_compose::serialize::na_vector2_f64_dp3"
+    )]
+    pub center: na::Vector2<f64>,
+    /// The angle of the edges, in radians.
+    #[serde(rename = "angle", with = "rnote_compose::serialize::f64_dp3")]
+    pub angle: f64,
+}
+
+impl Default for Ruler {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            center: na::Vector2::zeros(),
+            angle: 0.0,
+        }
+    }
+}
+
+impl Ruler {
+    pub const LENGTH: f64 = 800.0;
+    pub const WIDTH: f64 = 80.0;
+    /// The distance between two ticks of the scale.
+    const TICK_DISTANCE: f64 = 10.0;
+
+    fn direction(&self) -> na::Vector2<f64> {
+        na::vector![self.angle.cos(), self.angle.sin()]
+    }
+
+    fn normal(&self) -> na::Vector2<f64> {
+        na::vector![-self.angle.sin(), self.angle.cos()]
+    }
+
+    /// The position in the local coordinates of the ruler, along and across the edges.
+    fn to_local(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        let rel = pos - self.center;
+        na::vector![rel.dot(&self.direction()), rel.dot(&self.normal())]
+    }
+
+    fn from_local(&self, local: na::Vector2<f64>) -> na::Vector2<f64> {
+        self.center + self.direction() * local[0] + self.normal() * local[1]
+    }
+
+    fn corners(&self) -> [na::Vector2<f64>; 4] {
+        let half = na::vector![Self::LENGTH * 0.5, Self::WIDTH * 0.5];
+        [
+            self.from_local(na::vector![-half[0], -half[1]]),
+            self.from_local(na::vector![half[0], -half[1]]),
+            self.from_local(na::vector![half[0], half[1]]),
+            self.from_local(na::vector![-half[0], half[1]]),
+        ]
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::from_points(&self.corners().map(na::Point2::from))
+    }
+
+    /// The edge guide at the position, when it is closer to an edge than the snap distance.
+    fn guide_at(
+        &self,
+        pos: na::Vector2<f64>,
+        snap_distance: f64,
+    ) -> Option<(f64, DrawingAidGuide)> {
+        let local = self.to_local(pos);
+        if local[0].abs() > Self::LENGTH * 0.5 + snap_distance {
+            return None;
+        }
+        let edge_offset = Self::WIDTH * 0.5 * local[1].signum();
+        let distance = (local[1] - edge_offset).abs();
+        (distance <= snap_distance).then(|| {
+            (
+                distance,
+                DrawingAidGuide::Line {
+                    point: self.from_local(na::vector![0.0, edge_offset]),
+                    direction: self.direction(),
+                },
+            )
+        })
+    }
+
+    fn draw(&self, cx: &mut impl RenderContext, total_zoom: f64) {
+        let corners = self.corners();
+        let outline = kurbo::BezPath::from_vec(vec![
+            kurbo::PathEl::MoveTo(corners[0].to_kurbo_point()),
+            kurbo::PathEl::LineTo(corners[1].to_kurbo_point()),
+            kurbo::PathEl::LineTo(corners[2].to_kurbo_point()),
+            kurbo::PathEl::LineTo(corners[3].to_kurbo_point()),
+            kurbo::PathEl::ClosePath,
+        ]);
+        cx.fill(outline.clone(), &*DRAWING_AID_FILL_COLOR);
+        cx.stroke(
+            outline,
+            &*DRAWING_AID_OUTLINE_COLOR,
+            DRAWING_AID_OUTLINE_WIDTH / total_zoom,
+        );
+
+        // The scale along both edges, with longer ticks every fifth tick
+        let n_ticks = (Self::LENGTH / Self::TICK_DISTANCE).floor() as i32;
+        let mut scale = kurbo::BezPath::new();
+        for i in 0..=n_ticks {
+            let along = -Self::LENGTH * 0.5 + i as f64 * Self::TICK_DISTANCE;
+            let tick_length = if i % 5 == 0 { 12.0 } else { 6.0 };
+            for edge in [-Self::WIDTH * 0.5, Self::WIDTH * 0.5] {
+                let inward = -edge.signum() * tick_length;
+                scale.move_to(self.from_local(na::vector![along, edge]).to_kurbo_point());
+                scale.line_to(
+                    self.from_local(na::vector![along, edge + inward])
+                        .to_kurbo_point(),
+                );
+            }
+        }
+        cx.stroke(
+            scale,
+            &*DRAWING_AID_SCALE_COLOR,
+            DRAWING_AID_OUTLINE_WIDTH / total_zoom,
+        );
+    }
+}
+
+/// A compass with a protractor scale placed on the document.
+///
+/// Brush input that starts close to its circle is constrained to arcs around its center.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "compass")]
+pub struct Compass {
+    #[serde(rename = "enabled")]
+    pub enabled: bool,
+    #[serde(
+        rename = "center",
+        with = "rnote_compose::serialize::na_vector2_f64_dp3"
+    )]
+    pub center: na::Vector2<f64>,
+    #[serde(rename = "radius", with = "rnote_compose::serialize::f64_dp3")]
+    pub radius: f64,
+}
+
+impl Default for Compass {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            center: na::Vector2::zeros(),
+            radius: 100.0,
+        }
+    }
+}
+
+impl Compass {
+    pub const RADIUS_MIN: f64 = 5.0;
+    /// The angle between two ticks of the protractor scale, in degrees.
+    const TICK_ANGLE: f64 = 5.0;
+
+    /// The handle for changing the radius.
+    fn radius_handle(&self) -> na::Vector2<f64> {
+        self.center + na::vector![self.radius, 0.0]
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::from_half_extents(self.center.into(), na::Vector2::repeat(self.radius))
+    }
+
+    /// The arc guide at the position, when it is closer to the circle than the snap distance.
+    fn guide_at(
+        &self,
+        pos: na::Vector2<f64>,
+        snap_distance: f64,
+    ) -> Option<(f64, DrawingAidGuide)> {
+        let distance = ((pos - self.center).magnitude() - self.radius).abs();
+        (distance <= snap_distance).then_some((
+            distance,
+            DrawingAidGuide::Arc {
+                center: self.center,
+                radius: self.radius,
+            },
+        ))
+    }
+
+    fn draw(&self, cx: &mut impl RenderContext, total_zoom: f64) {
+        let circle = kurbo::Circle::new(self.center.to_kurbo_point(), self.radius);
+        cx.fill(circle, &*DRAWING_AID_FILL_COLOR);
+        cx.stroke(
+            circle,
+            &*DRAWING_AID_OUTLINE_COLOR,
+            DRAWING_AID_OUTLINE_WIDTH / total_zoom,
+        );
+
+        // The protractor scale, with longer ticks every 45 degrees
+        let n_ticks = (360.0 / Self::TICK_ANGLE) as i32;
+        let mut scale = kurbo::BezPath::new();
+        for i in 0..n_ticks {
+            let angle = (i as f64 * Self::TICK_ANGLE).to_radians();
+            let direction = na::vector![angle.cos(), angle.sin()];
+            let tick_length = (if i % 9 == 0 { 12.0 } else { 6.0 }).min(self.radius * 0.5);
+            scale.move_to((self.center + direction * self.radius).to_kurbo_point());
+            scale.line_to((self.center + direction * (self.radius - tick_length)).to_kurbo_point());
+        }
+        cx.stroke(
+            scale,
+            &*DRAWING_AID_SCALE_COLOR,
+            DRAWING_AID_OUTLINE_WIDTH / total_zoom,
+        );
+
+        for handle in [self.center, self.radius_handle()] {
+            cx.fill(
+                kurbo::Circle::new(
+                    handle.to_kurbo_point(),
+                    DRAWING_AID_HANDLE_RADIUS * 0.5 / total_zoom,
+                ),
+                &DRAWING_AID_HANDLE_COLOR,
+            );
+        }
+    }
+}
+
+/// The guide that the input of a stroke is locked to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrawingAidGuide {
+    /// An infinite line through the point.
+    Line {
+        point: na::Vector2<f64>,
+        direction: na::Vector2<f64>,
+    },
+    /// A circle.
+    Arc {
+        center: na::Vector2<f64>,
+        radius: f64,
+    },
+}
+
+impl DrawingAidGuide {
+    /// Project the position onto the guide.
+    pub fn project(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        match self {
+            Self::Line { point, direction } => point + direction * (pos - point).dot(direction),
+            Self::Arc { center, radius } => {
+                let rel = pos - center;
+                if rel.magnitude() == 0.0 {
+                    center + na::vector![*radius, 0.0]
+                } else {
+                    center + rel.normalize() * *radius
+                }
+            }
+        }
+    }
+}
+
+/// The part of a drawing aid that is dragged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawingAidHandle {
+    /// The body of the ruler, moves or rotates it.
+    Ruler,
+    /// The center of the compass, moves it.
+    CompassCenter,
+    /// The radius handle of the compass, changes the radius.
+    CompassRadius,
+}
+
+/// The drawing aids placed on the document.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default, rename = "drawing_aids")]
+pub struct DrawingAids {
+    #[serde(rename = "ruler")]
+    pub ruler: Ruler,
+    #[serde(rename = "compass")]
+    pub compass: Compass,
+}
+
+impl DrawingAids {
+    /// The distance in surface pixels in which input snaps to a guide.
+    pub const SNAP_DISTANCE: f64 = 24.0;
+
+    pub fn any_enabled(&self) -> bool {
+        self.ruler.enabled || self.compass.enabled
+    }
+
+    /// Enable or disable the ruler. When it gets enabled, it is placed at the center of the viewport.
+    pub fn set_ruler_enabled(&mut self, enabled: bool, viewport: Aabb) {
+        if enabled && !self.ruler.enabled {
+            self.ruler.center = viewport.center().coords;
+            self.ruler.angle = 0.0;
+        }
+        self.ruler.enabled = enabled;
+    }
+
+    /// Enable or disable the compass. When it gets enabled, it is placed at the center of the viewport.
+    pub fn set_compass_enabled(&mut self, enabled: bool, viewport: Aabb) {
+        if enabled && !self.compass.enabled {
+            self.compass.center = viewport.center().coords;
+        }
+        self.compass.enabled = enabled;
+    }
+
+    /// The guide of the enabled drawing aids that is nearest to the position, when in snap distance.
+    pub fn guide_at(&self, pos: na::Vector2<f64>, total_zoom: f64) -> Option<DrawingAidGuide> {
+        let snap_distance = Self::SNAP_DISTANCE / total_zoom;
+        let mut guides = Vec::with_capacity(2);
+        if self.ruler.enabled {
+            guides.extend(self.ruler.guide_at(pos, snap_distance));
+        }
+        if self.compass.enabled {
+            guides.extend(self.compass.guide_at(pos, snap_distance));
+        }
+        guides
+            .into_iter()
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, guide)| guide)
+    }
+
+    /// The handle of the enabled drawing aids at the position.
+    ///
+    /// Positions that are in snap distance of a guide are not considered to be on the ruler body,
+    /// so that drawing along the edges is still possible.
+    pub fn handle_at(&self, pos: na::Vector2<f64>, total_zoom: f64) -> Option<DrawingAidHandle> {
+        let handle_radius = DRAWING_AID_HANDLE_RADIUS / total_zoom;
+        if self.compass.enabled {
+            if (pos - self.compass.radius_handle()).magnitude() <= handle_radius {
+                return Some(DrawingAidHandle::CompassRadius);
+            }
+            if (pos - self.compass.center).magnitude() <= handle_radius {
+                return Some(DrawingAidHandle::CompassCenter);
+            }
+        }
+        if self.ruler.enabled && self.guide_at(pos, total_zoom).is_none() {
+            let local = self.ruler.to_local(pos);
+            if local[0].abs() <= Ruler::LENGTH * 0.5 && local[1].abs() <= Ruler::WIDTH * 0.5 {
+                return Some(DrawingAidHandle::Ruler);
+            }
+        }
+        None
+    }
+
+    /// Drag the handle from the previous to the current position.
+    ///
+    /// When `rotate` is true, the ruler is rotated around its center instead of moved.
+    pub fn drag_handle(
+        &mut self,
+        handle: DrawingAidHandle,
+        prev_pos: na::Vector2<f64>,
+        pos: na::Vector2<f64>,
+        rotate: bool,
+    ) {
+        match handle {
+            DrawingAidHandle::Ruler => {
+                if rotate {
+                    let prev_rel = prev_pos - self.ruler.center;
+                    let rel = pos - self.ruler.center;
+                    if prev_rel.magnitude() > 0.0 && rel.magnitude() > 0.0 {
+                        self.ruler.angle += prev_rel.angle_ahead(&rel);
+                    }
+                } else {
+                    self.ruler.center += pos - prev_pos;
+                }
+            }
+            DrawingAidHandle::CompassCenter => {
+                self.compass.center += pos - prev_pos;
+            }
+            DrawingAidHandle::CompassRadius => {
+                self.compass.radius = (pos - self.compass.center)
+                    .magnitude()
+                    .max(Compass::RADIUS_MIN);
+            }
+        }
+    }
+}
+
+impl DrawOnDocBehaviour for DrawingAids {
+    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
+        let margin = (DRAWING_AID_HANDLE_RADIUS + DRAWING_AID_OUTLINE_WIDTH)
+            / engine_view.camera.total_zoom();
+        [
+            self.ruler.enabled.then(|| self.ruler.bounds()),
+            self.compass.enabled.then(|| self.compass.bounds()),
+        ]
+        .into_iter()
+        .flatten()
+        .reduce(|acc, bounds| acc.merged(&bounds))
+        .map(|bounds| bounds.loosened(margin))
+    }
+
+    fn draw_on_doc(
+        &self,
+        cx: &mut piet_cairo::CairoRenderContext,
+        engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        let total_zoom = engine_view.camera.total_zoom();
+
+        if self.ruler.enabled {
+            self.ruler.draw(cx, total_zoom);
+        }
+        if self.compass.enabled {
+            self.compass.draw(cx, total_zoom);
+        }
+
+        cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@ use super::penbehaviour::{PenBehaviour, PenProgress};
 use super::pensconfig::eraserconfig::EraserStyle;
 use super::PenStyle;
 use crate::engine::{EngineView, EngineViewMut};
+use crate::strokes::Stroke;
 use crate::{DrawOnDocBehaviour, WidgetFlags};
 use once_cell::sync::Lazy;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
@@ -11,6 +12,8 @@ use rnote_compose::color;
 use rnote_compose::helpers::AabbHelpers;
 use rnote_compose::penevents::PenEvent;
 use rnote_compose::penpath::Element;
+use rnote_compose::shapes::ShapeBehaviour;
+use rnote_compose::PenPath;
 use std::time::Instant;
 
 #[derive(Debug, Clone, Copy)]
@@ -20,15 +23,44 @@ pub enum EraserState {
     Down(Element),
 }
 
+/// The parts of the strokes that would be erased at the current eraser position.
+///
+/// Generated while the eraser is hovering in proximity, so that they can be highlighted before they are erased.
+#[derive(Clone, Debug, Default)]
+pub struct EraserPreview {
+    /// The paths of the brush strokes (or their colliding segments), with their stroke widths.
+    paths: Vec<(kurbo::BezPath, f64)>,
+    /// The colliding hitboxes of other strokes.
+    hitboxes: Vec<Aabb>,
+}
+
+impl EraserPreview {
+    fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.hitboxes.is_empty()
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.paths
+            .iter()
+            .map(|(path, width)| {
+                Aabb::from_kurbo_rect(kurbo::Shape::bounding_box(path)).loosened(width * 0.5)
+            })
+            .chain(self.hitboxes.iter().copied())
+            .reduce(|acc, bounds| acc.merged(&bounds))
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Eraser {
     pub(crate) state: EraserState,
+    preview: EraserPreview,
 }
 
 impl Default for Eraser {
     fn default() -> Self {
         Self {
             state: EraserState::Up,
+            preview: EraserPreview::default(),
         }
     }
 }
@@ -39,6 +71,7 @@ impl PenBehaviour for Eraser {
     }
 
     fn deinit(&mut self) -> WidgetFlags {
+        self.preview = EraserPreview::default();
         WidgetFlags::default()
     }
 
@@ -60,6 +93,7 @@ impl PenBehaviour for Eraser {
 
         let pen_progress = match (&mut self.state, event) {
             (EraserState::Up | EraserState::Proximity { .. }, PenEvent::Down { element, .. }) => {
+                self.preview = EraserPreview::default();
                 widget_flags.merge(erase(element, engine_view));
 
                 self.state = EraserState::Down(element);
@@ -67,6 +101,7 @@ impl PenBehaviour for Eraser {
                 PenProgress::InProgress
             }
             (EraserState::Up | EraserState::Down { .. }, PenEvent::Proximity { element, .. }) => {
+                self.preview = gen_preview(element, &engine_view.as_im());
                 self.state = EraserState::Proximity(element);
 
                 PenProgress::Idle
@@ -92,16 +127,19 @@ impl PenBehaviour for Eraser {
             }
             (EraserState::Down(_), PenEvent::KeyPressed { .. }) => PenProgress::InProgress,
             (EraserState::Proximity(_), PenEvent::Up { .. }) => {
+                self.preview = EraserPreview::default();
                 self.state = EraserState::Up;
 
                 PenProgress::Idle
             }
             (EraserState::Proximity(current_element), PenEvent::Proximity { element, .. }) => {
+                self.preview = gen_preview(element, &engine_view.as_im());
                 *current_element = element;
 
                 PenProgress::Idle
             }
             (EraserState::Proximity { .. } | EraserState::Down { .. }, PenEvent::Cancel) => {
+                self.preview = EraserPreview::default();
                 self.state = EraserState::Up;
 
                 widget_flags.merge(engine_view.store.record(Instant::now()));
@@ -122,7 +160,18 @@ impl DrawOnDocBehaviour for Eraser {
     fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
         match &self.state {
             EraserState::Up => None,
-            EraserState::Proximity(current_element) | EraserState::Down(current_element) => Some(
+            EraserState::Proximity(current_element) => {
+                let bounds = engine_view
+                    .pens_config
+                    .eraser_config
+                    .eraser_bounds(*current_element);
+                Some(
+                    self.preview
+                        .bounds()
+                        .map_or(bounds, |preview_bounds| preview_bounds.merged(&bounds)),
+                )
+            }
+            EraserState::Down(current_element) => Some(
                 engine_view
                     .pens_config
                     .eraser_config
@@ -143,6 +192,8 @@ impl DrawOnDocBehaviour for Eraser {
         static FILL_COLOR: Lazy<piet::Color> = Lazy::new(|| color::GNOME_REDS[0].with_alpha(0.627));
         static PROXIMITY_FILL_COLOR: Lazy<piet::Color> =
             Lazy::new(|| color::GNOME_REDS[0].with_alpha(0.2));
+        static PREVIEW_COLOR: Lazy<piet::Color> =
+            Lazy::new(|| color::GNOME_REDS[2].with_alpha(0.5));
         let outline_width = 2.0 / engine_view.camera.total_zoom();
 
         match &self.state {
@@ -156,6 +207,19 @@ impl DrawOnDocBehaviour for Eraser {
                 let fill_rect = bounds.to_kurbo_rect();
                 let outline_rect = bounds.tightened(outline_width * 0.5).to_kurbo_rect();
 
+                // Ghost the parts that would be erased
+                if !self.preview.is_empty() {
+                    let style = piet::StrokeStyle::new()
+                        .line_cap(piet::LineCap::Round)
+                        .line_join(piet::LineJoin::Round);
+                    for (path, width) in self.preview.paths.iter() {
+                        cx.stroke_styled(path, &*PREVIEW_COLOR, width.max(outline_width), &style);
+                    }
+                    for hitbox in self.preview.hitboxes.iter() {
+                        cx.fill(hitbox.to_kurbo_rect(), &*PREVIEW_COLOR);
+                    }
+                }
+
                 cx.fill(fill_rect, &*PROXIMITY_FILL_COLOR);
                 cx.stroke(outline_rect, &*OUTLINE_COLOR, outline_width);
             }
@@ -206,3 +270,61 @@ fn erase(element: Element, engine_view: &mut EngineViewMut) -> WidgetFlags {
 
     widget_flags
 }
+
+/// Generate the preview of the strokes, or with the split style the stroke segments, that collide with the eraser.
+fn gen_preview(element: Element, engine_view: &EngineView) -> EraserPreview {
+    let eraser_bounds = engine_view.pens_config.eraser_config.eraser_bounds(element);
+    let split = matches!(
+        engine_view.pens_config.eraser_config.style,
+        EraserStyle::SplitCollidingStrokes
+    );
+    let mut preview = EraserPreview::default();
+
+    for key in engine_view
+        .store
+        .stroke_keys_as_rendered_intersecting_bounds(eraser_bounds)
+    {
+        let Some(stroke) = engine_view.store.get_stroke_ref(key) else {
+            continue;
+        };
+        match stroke {
+            Stroke::BrushStroke(brushstroke) => {
+                let stroke_width = brushstroke.style.stroke_width();
+                let hits = brushstroke.path.hittest(&eraser_bounds, stroke_width * 0.5);
+                if hits.is_empty() {
+                    continue;
+                }
+                if !split {
+                    preview
+                        .paths
+                        .push((brushstroke.path.to_kurbo(), stroke_width));
+                    continue;
+                }
+                let mut path = kurbo::BezPath::new();
+                for i in hits {
+                    let start = if i == 0 {
+                        brushstroke.path.start
+                    } else {
+                        brushstroke.path.segments[i - 1].end()
+                    };
+                    let segment = brushstroke.path.segments[i];
+                    path.extend(PenPath::new_w_segments(start, [segment]).to_kurbo());
+                }
+                preview.paths.push((path, stroke_width));
+            }
+            Stroke::ShapeStroke(_) => {
+                let hitboxes = stroke.hitboxes();
+                if hitboxes
+                    .iter()
+                    .any(|hitbox| eraser_bounds.intersects(hitbox))
+                {
+                    preview.hitboxes.extend(hitboxes);
+                }
+            }
+            // Other strokes are ignored by the eraser
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => {}
+        }
+    }
+
+    preview
+}
@@ -0,0 +1,63 @@
+// Imports
+use crate::render;
+use crate::store::StrokeStore;
+use crate::{Document, DrawBehaviour};
+use p2d::bounding_volume::Aabb;
+use rnote_compose::Color;
+
+/// Sample the composited color of the document at the position.
+///
+/// The strokes in a square with the given half extents around the position are rendered with the image scale,
+/// and their average color is composited over the document background color.
+/// Pages and other overlays are not taken into account.
+pub(crate) fn sample_color(
+    store: &StrokeStore,
+    doc: &Document,
+    pos: na::Vector2<f64>,
+    half_extents: f64,
+    image_scale: f64,
+) -> anyhow::Result<Color> {
+    let bounds = Aabb::from_half_extents(pos.into(), na::Vector2::repeat(half_extents));
+    let keys = store.stroke_keys_as_rendered_intersecting_bounds(bounds);
+    let background = doc.background.color;
+
+    if keys.is_empty() {
+        return Ok(background);
+    }
+
+    let image = render::Image::gen_with_piet(
+        |piet_cx| {
+            for key in keys {
+                if let Some(stroke) = store.get_stroke_ref(key) {
+                    stroke.draw(piet_cx, image_scale)?;
+                }
+            }
+            Ok(())
+        },
+        bounds,
+        image_scale,
+    )?;
+
+    let n_pixels = (image.data.len() / 4).max(1) as f64;
+    // The pixels are premultiplied rgba, so they can be averaged directly
+    let sum = image.data.chunks_exact(4).fold([0.0; 4], |mut sum, pixel| {
+        for (channel, value) in sum.iter_mut().zip(pixel) {
+            *channel += f64::from(*value) / 255.0;
+        }
+        sum
+    });
+    let [r, g, b, a] = sum.map(|channel| channel / n_pixels);
+
+    // Composite over the background and unpremultiply
+    let background_weight = background.a * (1.0 - a);
+    let alpha = a + background_weight;
+    if alpha <= 0.0 {
+        return Ok(Color::TRANSPARENT);
+    }
+    Ok(Color::new(
+        (r + background.r * background_weight) / alpha,
+        (g + background.g * background_weight) / alpha,
+        (b + background.b * background_weight) / alpha,
+        alpha,
+    ))
+}
@@ -0,0 +1,234 @@
+// Imports
+use crate::render;
+use crate::store::chrono_comp::StrokeLayer;
+use crate::store::StrokeStore;
+use crate::DrawBehaviour;
+use p2d::bounding_volume::Aabb;
+use rnote_compose::penpath::Element;
+use rnote_compose::shapes::ShapeBehaviour;
+use rnote_compose::PenPath;
+use std::collections::VecDeque;
+
+/// The maximum extent of the rasterized area in pixels, limiting the memory usage and duration of the fill.
+const RASTER_EXTENT_MAX: f64 = 2048.0;
+
+/// The eight neighbour directions, ordered clockwise (with the y-axis pointing down) starting east.
+const NEIGHBOURS: [(i64, i64); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+/// Trace the outline of the region around `pos` that is enclosed by strokes.
+///
+/// The strokes on the user layers inside `bounds` are rasterized with the given image scale,
+/// pixels with an opacity above `tolerance` bound the region. Gaps in the bounding strokes up to `gap_closing`
+/// (in document coordinates) are closed, so that sketchy strokes can be filled.
+///
+/// Returns None when the position is on a stroke, or the region is not enclosed inside the bounds.
+pub(crate) fn trace_region(
+    store: &StrokeStore,
+    pos: na::Vector2<f64>,
+    bounds: Aabb,
+    image_scale: f64,
+    tolerance: f64,
+    gap_closing: f64,
+) -> anyhow::Result<Option<Vec<na::Vector2<f64>>>> {
+    if !bounds.contains_local_point(&pos.into()) {
+        return Ok(None);
+    }
+    let image_scale = image_scale.min(RASTER_EXTENT_MAX / bounds.extents().max());
+    let keys = store
+        .stroke_keys_as_rendered_intersecting_bounds(bounds)
+        .into_iter()
+        .filter(|&key| {
+            store
+                .chrono_components
+                .get(key)
+                .map_or(false, |chrono_comp| {
+                    matches!(chrono_comp.layer, StrokeLayer::UserLayer(_))
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let image = render::Image::gen_with_piet(
+        |piet_cx| {
+            for key in keys {
+                if let Some(stroke) = store.get_stroke_ref(key) {
+                    stroke.draw(piet_cx, image_scale)?;
+                }
+            }
+            Ok(())
+        },
+        bounds,
+        image_scale,
+    )?;
+    let width = image.pixel_width as usize;
+    let height = image.pixel_height as usize;
+    let image_bounds = image.rect.bounds();
+    let pixel_scale = na::vector![
+        width as f64 / image_bounds.extents()[0],
+        height as f64 / image_bounds.extents()[1]
+    ];
+
+    let seed = (pos - image_bounds.mins.coords).component_mul(&pixel_scale);
+    if seed[0] < 0.0 || seed[1] < 0.0 || seed[0] >= width as f64 || seed[1] >= height as f64 {
+        return Ok(None);
+    }
+    let seed = (seed[0] as usize, seed[1] as usize);
+
+    // The alpha is the last byte of the premultiplied rgba pixels
+    let alpha_threshold = (tolerance.clamp(0.0, 1.0) * 255.0) as u8;
+    let boundary = image
+        .data
+        .chunks_exact(4)
+        .map(|pixel| pixel[3] > alpha_threshold)
+        .collect::<Vec<bool>>();
+    let gap_radius = (gap_closing * 0.5 * pixel_scale[0]).ceil() as usize;
+    let boundary = dilate(&boundary, width, height, gap_radius);
+
+    let Some(region) = flood(&boundary, width, height, seed) else {
+        return Ok(None);
+    };
+    // Grow the region back under the closed gaps and the antialiased stroke edges
+    let region = dilate(&region, width, height, gap_radius + 1);
+
+    let outline = trace_outline(&region, width, height)
+        .into_iter()
+        .map(|(x, y)| {
+            Element::new(
+                image_bounds.mins.coords
+                    + na::vector![x as f64 + 0.5, y as f64 + 0.5].component_div(&pixel_scale),
+                1.0,
+            )
+        });
+    let Some(path) = PenPath::try_from_elements(outline) else {
+        return Ok(None);
+    };
+    let points = path
+        .smoothed(0.5, 2)
+        .simplified(0.5 / pixel_scale[0])
+        .into_elements()
+        .into_iter()
+        .map(|el| el.pos)
+        .collect::<Vec<na::Vector2<f64>>>();
+
+    Ok((points.len() >= 3).then_some(points))
+}
+
+/// Grow the set pixels of the mask by the radius, using a square kernel.
+fn dilate(mask: &[bool], width: usize, height: usize, radius: usize) -> Vec<bool> {
+    if radius == 0 {
+        return mask.to_vec();
+    }
+
+    // The square kernel is separable, so the rows and columns are dilated in two passes
+    let dilate_line = |get: &dyn Fn(usize) -> bool, len: usize| -> Vec<bool> {
+        let mut prefix = vec![0usize; len + 1];
+        for i in 0..len {
+            prefix[i + 1] = prefix[i] + get(i) as usize;
+        }
+        (0..len)
+            .map(|i| prefix[(i + radius + 1).min(len)] > prefix[i.saturating_sub(radius)])
+            .collect()
+    };
+
+    let mut rows = vec![false; mask.len()];
+    for y in 0..height {
+        let row = dilate_line(&|x| mask[y * width + x], width);
+        rows[y * width..(y + 1) * width].copy_from_slice(&row);
+    }
+    let mut dilated = vec![false; mask.len()];
+    for x in 0..width {
+        let column = dilate_line(&|y| rows[y * width + x], height);
+        for (y, set) in column.into_iter().enumerate() {
+            dilated[y * width + x] = set;
+        }
+    }
+    dilated
+}
+
+/// Flood the four-connected region that is not bounded, starting from the seed.
+///
+/// Returns None when the seed is on the boundary or the region reaches the edges of the mask.
+fn flood(
+    boundary: &[bool],
+    width: usize,
+    height: usize,
+    seed: (usize, usize),
+) -> Option<Vec<bool>> {
+    if boundary[seed.1 * width + seed.0] {
+        return None;
+    }
+    let mut region = vec![false; boundary.len()];
+    let mut queue = VecDeque::from([seed]);
+    region[seed.1 * width + seed.0] = true;
+
+    while let Some((x, y)) = queue.pop_front() {
+        if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+            return None;
+        }
+        for (nx, ny) in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+            let i = ny * width + nx;
+            if !boundary[i] && !region[i] {
+                region[i] = true;
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    Some(region)
+}
+
+/// Trace the outer contour of the region with Moore-neighbour tracing, returning the pixels along it clockwise.
+fn trace_outline(region: &[bool], width: usize, height: usize) -> Vec<(usize, usize)> {
+    let is_set = |(x, y): (i64, i64)| {
+        x >= 0
+            && y >= 0
+            && (x as usize) < width
+            && (y as usize) < height
+            && region[y as usize * width + x as usize]
+    };
+    let Some(start) = region.iter().position(|&set| set) else {
+        return vec![];
+    };
+    let start = ((start % width) as i64, (start / width) as i64);
+
+    // The start is the topmost, leftmost pixel, so the western neighbour is outside the region
+    let mut current = start;
+    let mut backtrack = 4;
+    let mut first_dir = None;
+    let mut outline = vec![];
+
+    // Every pixel is visited at most four times by the tracing
+    for _ in 0..region.len() * 4 {
+        let Some(dir) = (1..=8)
+            .map(|i| (backtrack + i) % 8)
+            .find(|&dir| is_set((current.0 + NEIGHBOURS[dir].0, current.1 + NEIGHBOURS[dir].1)))
+        else {
+            // A single isolated pixel
+            break;
+        };
+        // Stop when the start pixel is left in the same direction as at the beginning
+        if current == start {
+            if first_dir == Some(dir) {
+                break;
+            }
+            first_dir = first_dir.or(Some(dir));
+        }
+        outline.push((current.0 as usize, current.1 as usize));
+
+        current = (current.0 + NEIGHBOURS[dir].0, current.1 + NEIGHBOURS[dir].1);
+        // Point back to the last examined neighbour outside the region
+        backtrack = if dir % 2 == 0 {
+            (dir + 6) % 8
+        } else {
+            (dir + 5) % 8
+        };
+    }
+    outline
+}
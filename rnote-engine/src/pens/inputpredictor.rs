@@ -0,0 +1,87 @@
+// Imports
+use rnote_compose::penpath::Element;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Predicts where the pen input will be shortly, to compensate for input and rendering latency.
+///
+/// The prediction extrapolates the most recent input samples with their velocity and acceleration,
+/// so that curves are followed as well.
+#[derive(Debug, Clone, Default)]
+pub struct InputPredictor {
+    samples: VecDeque<(Element, Instant)>,
+}
+
+impl InputPredictor {
+    const SAMPLES_MAX: usize = 3;
+    /// Samples older than this (relative to the newest sample) are not used for the prediction.
+    const SAMPLE_AGE_MAX: Duration = Duration::from_millis(80);
+    /// The maximum factor the acceleration may extend or shorten the linear prediction.
+    const ACCELERATION_INFLUENCE_MAX: f64 = 0.5;
+
+    /// Discard all samples.
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Add an input sample.
+    pub fn push(&mut self, element: Element, now: Instant) {
+        // Samples with the same timestamp can't be used to calculate velocities
+        if let Some((last, last_time)) = self.samples.back_mut() {
+            if now <= *last_time {
+                *last = element;
+                return;
+            }
+        }
+        self.samples.push_back((element, now));
+        while self.samples.len() > Self::SAMPLES_MAX {
+            self.samples.pop_front();
+        }
+        while self.samples.front().map_or(false, |(_, time)| {
+            now.duration_since(*time) > Self::SAMPLE_AGE_MAX
+        }) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// The newest sample together with the predicted element the given duration ahead of it.
+    ///
+    /// Returns None when there are not enough recent samples.
+    pub fn predict(&self, ahead: Duration) -> Option<(Element, Element)> {
+        let n = self.samples.len();
+        if n < 2 || ahead.is_zero() {
+            return None;
+        }
+        let (last, last_time) = self.samples[n - 1];
+        let (prev, prev_time) = self.samples[n - 2];
+        let dt = last_time.duration_since(prev_time).as_secs_f64();
+        let velocity = (last.pos - prev.pos) / dt;
+        let t = ahead.as_secs_f64();
+        let linear = velocity * t;
+
+        let curved = if n >= 3 {
+            let (prev_prev, prev_prev_time) = self.samples[n - 3];
+            let prev_dt = prev_time.duration_since(prev_prev_time).as_secs_f64();
+            let prev_velocity = (prev.pos - prev_prev.pos) / prev_dt;
+            let acceleration = (velocity - prev_velocity) / ((dt + prev_dt) * 0.5);
+            let acceleration_offset = acceleration * (0.5 * t * t);
+
+            // Limit the influence of the acceleration, because it is very sensitive to noisy input
+            let max_norm = linear.norm() * Self::ACCELERATION_INFLUENCE_MAX;
+            if acceleration_offset.norm() > max_norm {
+                acceleration_offset.normalize() * max_norm
+            } else {
+                acceleration_offset
+            }
+        } else {
+            na::Vector2::zeros()
+        };
+
+        let offset = linear + curved;
+        if !offset.iter().all(|v| v.is_finite()) {
+            return None;
+        }
+
+        Some((last, Element::new(last.pos + offset, last.pressure)))
+    }
+}
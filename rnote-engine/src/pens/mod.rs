@@ -1,26 +1,38 @@
 // Modules
 pub mod brush;
+pub mod drawingaids;
 pub mod eraser;
+pub mod eyedropper;
+pub mod floodfill;
+pub mod inputpredictor;
 pub mod penbehaviour;
 pub mod penholder;
 pub mod penmode;
 pub mod pensconfig;
+pub mod plugin;
 pub mod selector;
 pub mod shaper;
 pub mod shortcuts;
+pub mod sprayemitter;
+pub mod stabilizer;
 pub mod tools;
 pub mod typewriter;
 
 // Re-exports
 pub use brush::Brush;
+pub use drawingaids::DrawingAids;
 pub use eraser::Eraser;
+pub use inputpredictor::InputPredictor;
 pub use penbehaviour::PenBehaviour;
 pub use penholder::PenHolder;
 pub use penmode::PenMode;
 pub use pensconfig::PensConfig;
+pub use plugin::{PenPlugin, PenPluginInfo, PenPluginRegistry, PluginPen};
 pub use selector::Selector;
 pub use shaper::Shaper;
 pub use shortcuts::Shortcuts;
+pub use sprayemitter::SprayEmitter;
+pub use stabilizer::Stabilizer;
 pub use tools::Tools;
 pub use typewriter::Typewriter;
 
@@ -42,6 +54,7 @@ pub enum Pen {
     Eraser(Eraser),
     Selector(Selector),
     Tools(Tools),
+    Plugin(PluginPen),
 }
 
 impl Default for Pen {
@@ -59,6 +72,7 @@ impl PenBehaviour for Pen {
             Pen::Eraser(eraser) => eraser.init(engine_view),
             Pen::Selector(selector) => selector.init(engine_view),
             Pen::Tools(tools) => tools.init(engine_view),
+            Pen::Plugin(plugin) => plugin.init(engine_view),
         }
     }
 
@@ -70,6 +84,7 @@ impl PenBehaviour for Pen {
             Pen::Eraser(eraser) => eraser.deinit(),
             Pen::Selector(selector) => selector.deinit(),
             Pen::Tools(tools) => tools.deinit(),
+            Pen::Plugin(plugin) => plugin.deinit(),
         }
     }
 
@@ -81,6 +96,7 @@ impl PenBehaviour for Pen {
             Pen::Eraser(eraser) => eraser.style(),
             Pen::Selector(selector) => selector.style(),
             Pen::Tools(tools) => tools.style(),
+            Pen::Plugin(plugin) => plugin.style(),
         }
     }
 
@@ -92,6 +108,7 @@ impl PenBehaviour for Pen {
             Pen::Eraser(eraser) => eraser.update_state(engine_view),
             Pen::Selector(selector) => selector.update_state(engine_view),
             Pen::Tools(tools) => tools.update_state(engine_view),
+            Pen::Plugin(plugin) => plugin.update_state(engine_view),
         }
     }
 
@@ -108,6 +125,7 @@ impl PenBehaviour for Pen {
             Pen::Eraser(eraser) => eraser.handle_event(event, now, engine_view),
             Pen::Selector(selector) => selector.handle_event(event, now, engine_view),
             Pen::Tools(tools) => tools.handle_event(event, now, engine_view),
+            Pen::Plugin(plugin) => plugin.handle_event(event, now, engine_view),
         }
     }
 
@@ -122,6 +140,7 @@ impl PenBehaviour for Pen {
             Pen::Eraser(eraser) => eraser.fetch_clipboard_content(engine_view),
             Pen::Selector(selector) => selector.fetch_clipboard_content(engine_view),
             Pen::Tools(tools) => tools.fetch_clipboard_content(engine_view),
+            Pen::Plugin(plugin) => plugin.fetch_clipboard_content(engine_view),
         }
     }
 
@@ -136,6 +155,7 @@ impl PenBehaviour for Pen {
             Pen::Eraser(eraser) => eraser.cut_clipboard_content(engine_view),
             Pen::Selector(selector) => selector.cut_clipboard_content(engine_view),
             Pen::Tools(tools) => tools.cut_clipboard_content(engine_view),
+            Pen::Plugin(plugin) => plugin.cut_clipboard_content(engine_view),
         }
     }
 }
@@ -149,6 +169,7 @@ impl DrawOnDocBehaviour for Pen {
             Pen::Eraser(eraser) => eraser.bounds_on_doc(engine_view),
             Pen::Selector(selector) => selector.bounds_on_doc(engine_view),
             Pen::Tools(tools) => tools.bounds_on_doc(engine_view),
+            Pen::Plugin(plugin) => plugin.bounds_on_doc(engine_view),
         }
     }
 
@@ -164,6 +185,7 @@ impl DrawOnDocBehaviour for Pen {
             Pen::Eraser(eraser) => eraser.draw_on_doc(cx, engine_view),
             Pen::Selector(selector) => selector.draw_on_doc(cx, engine_view),
             Pen::Tools(tools) => tools.draw_on_doc(cx, engine_view),
+            Pen::Plugin(plugin) => plugin.draw_on_doc(cx, engine_view),
         }
     }
 }
@@ -196,6 +218,8 @@ pub enum PenStyle {
     Selector,
     #[serde(rename = "tools")]
     Tools,
+    #[serde(rename = "plugin")]
+    Plugin,
 }
 
 impl Default for PenStyle {
@@ -224,6 +248,7 @@ impl std::str::FromStr for PenStyle {
             "eraser" => Ok(Self::Eraser),
             "selector" => Ok(Self::Selector),
             "tools" => Ok(Self::Tools),
+            "plugin" => Ok(Self::Plugin),
             s => Err(anyhow::anyhow!(
                 "PenStyle::from_str() failed, invalid name {s}"
             )),
@@ -240,6 +265,7 @@ impl std::string::ToString for PenStyle {
             PenStyle::Eraser => String::from("eraser"),
             PenStyle::Selector => String::from("selector"),
             PenStyle::Tools => String::from("tools"),
+            PenStyle::Plugin => String::from("plugin"),
         }
     }
 }
@@ -253,6 +279,7 @@ impl PenStyle {
             Self::Eraser => String::from("pen-eraser-symbolic"),
             Self::Selector => String::from("pen-selector-symbolic"),
             Self::Tools => String::from("pen-tools-symbolic"),
+            Self::Plugin => String::from("application-x-addon-symbolic"),
         }
     }
 }
@@ -3,10 +3,11 @@ use super::penbehaviour::PenProgress;
 use super::penmode::PenModeState;
 use super::shortcuts::ShortcutMode;
 use super::{
-    Brush, Eraser, Pen, PenBehaviour, PenMode, PenStyle, Selector, Shaper, Shortcuts, Tools,
-    Typewriter,
+    Brush, Eraser, Pen, PenBehaviour, PenMode, PenPluginRegistry, PenStyle, PensConfig, PluginPen,
+    Selector, Shaper, Shortcuts, Tools, Typewriter,
 };
 use crate::engine::{EngineView, EngineViewMut};
+use crate::pens::pensconfig::toolsconfig::ToolStyle;
 use crate::pens::shortcuts::ShortcutAction;
 use crate::widgetflags::WidgetFlags;
 use crate::DrawOnDocBehaviour;
@@ -35,6 +36,9 @@ pub struct PenHolder {
     /// The policy for the retrieval of input event backlogs.
     #[serde(skip)]
     pub backlog_policy: BacklogPolicy,
+    /// The registered pen plugins. They are registered by the application and not part of the config.
+    #[serde(skip)]
+    pub pen_plugins: PenPluginRegistry,
 
     #[serde(skip)]
     pub(super) current_pen: Pen,
@@ -44,6 +48,9 @@ pub struct PenHolder {
     toggle_pen_style: Option<PenStyle>,
     #[serde(skip)]
     prev_shortcut_key: Option<ShortcutKey>,
+    /// The tools style before it was overridden by the pan shortcut action.
+    #[serde(skip)]
+    prev_tools_style: Option<ToolStyle>,
 }
 
 impl Default for PenHolder {
@@ -52,11 +59,13 @@ impl Default for PenHolder {
             shortcuts: Shortcuts::default(),
             pen_mode_state: PenModeState::default(),
             backlog_policy: BacklogPolicy::NoLimit,
+            pen_plugins: PenPluginRegistry::default(),
 
             current_pen: Pen::default(),
             pen_progress: PenProgress::Idle,
             toggle_pen_style: None,
             prev_shortcut_key: None,
+            prev_tools_style: None,
         }
     }
 }
@@ -67,6 +76,7 @@ impl PenHolder {
             shortcuts: self.shortcuts.clone(),
             pen_mode_state: self.pen_mode_state.clone_config(),
             backlog_policy: self.backlog_policy,
+            pen_plugins: self.pen_plugins.clone(),
             ..Default::default()
         }
     }
@@ -154,6 +164,28 @@ impl PenHolder {
         widget_flags
     }
 
+    /// Select the pen plugin with the given id and change to the plugin pen style.
+    pub fn change_pen_plugin(
+        &mut self,
+        id: String,
+        engine_view: &mut EngineViewMut,
+    ) -> WidgetFlags {
+        if self.pen_plugins.info(&id).is_none() {
+            log::warn!("Changing to pen plugin `{id}` failed, the plugin is not registered.");
+            return WidgetFlags::default();
+        }
+        engine_view.pens_config.plugins_config.current = Some(id);
+
+        if self.current_pen_style() == PenStyle::Plugin {
+            // The style does not change, but the pen needs to be reinstalled for the selected plugin
+            let mut widget_flags = self.reinstall_pen_current_style(engine_view);
+            widget_flags.refresh_ui = true;
+            widget_flags
+        } else {
+            self.change_style(PenStyle::Plugin, engine_view)
+        }
+    }
+
     /// Change the style override.
     pub fn change_style_override(
         &mut self,
@@ -162,6 +194,9 @@ impl PenHolder {
     ) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
 
+        if new_style_override != Some(PenStyle::Tools) {
+            self.restore_tools_style(engine_view);
+        }
         if self.pen_mode_state.style_override() != new_style_override {
             // Deselecting when changing the style override
             let all_strokes = engine_view.store.selection_keys_as_rendered();
@@ -206,7 +241,11 @@ impl PenHolder {
                 .handle_event(PenEvent::Cancel, Instant::now(), engine_view);
 
         // then reinstall a new pen instance
-        let mut new_pen = new_pen(self.current_pen_style_w_override());
+        let mut new_pen = new_pen(
+            self.current_pen_style_w_override(),
+            &self.pen_plugins,
+            engine_view.pens_config,
+        );
         widget_flags.merge(new_pen.init(&engine_view.as_im()));
         widget_flags.merge(new_pen.update_state(engine_view));
         self.current_pen = new_pen;
@@ -260,6 +299,7 @@ impl PenHolder {
             PenProgress::Finished => {
                 // take the style override when pen is finished
                 if self.pen_mode_state.take_style_override().is_some() {
+                    self.restore_tools_style(engine_view);
                     widget_flags.refresh_ui = true;
                 }
 
@@ -283,6 +323,7 @@ impl PenHolder {
             PenStyle::Eraser => BacklogPolicy::Limit(Duration::from_millis(33)),
             PenStyle::Selector => BacklogPolicy::Limit(Duration::from_millis(33)),
             PenStyle::Tools => BacklogPolicy::DisableBacklog,
+            PenStyle::Plugin => BacklogPolicy::Limit(Duration::from_millis(8)),
         };
 
         // Enable text preprocessing for typewriter
@@ -331,6 +372,28 @@ impl PenHolder {
                         }
                     }
                 },
+                ShortcutAction::Pan => {
+                    // Temporarily switch to the tools pen with the offset camera tool
+                    if self.prev_tools_style.is_none() {
+                        self.prev_tools_style = Some(engine_view.pens_config.tools_config.style);
+                    }
+                    engine_view.pens_config.tools_config.style = ToolStyle::OffsetCamera;
+                    widget_flags
+                        .merge(self.change_style_override(Some(PenStyle::Tools), engine_view));
+                    widget_flags.refresh_ui = true;
+                }
+                ShortcutAction::CyclePreset => {
+                    if engine_view
+                        .pens_config
+                        .cycle_preset(self.current_pen_style_w_override())
+                        .is_some()
+                    {
+                        widget_flags.merge(self.current_pen_update_state(engine_view));
+                        widget_flags.refresh_ui = true;
+                    }
+                }
+                // Undo and redo are handled by the engine, because they need access to the entire engine state
+                ShortcutAction::Undo | ShortcutAction::Redo => {}
             }
         }
 
@@ -340,6 +403,13 @@ impl PenHolder {
         widget_flags
     }
 
+    /// Restore the tools style that was overridden by the pan shortcut action.
+    fn restore_tools_style(&mut self, engine_view: &mut EngineViewMut) {
+        if let Some(prev_tools_style) = self.prev_tools_style.take() {
+            engine_view.pens_config.tools_config.style = prev_tools_style;
+        }
+    }
+
     /// Fetch clipboard content from the current pen.
     #[allow(clippy::type_complexity)]
     pub fn fetch_clipboard_content(
@@ -377,7 +447,7 @@ impl DrawOnDocBehaviour for PenHolder {
     }
 }
 
-fn new_pen(pen_style: PenStyle) -> Pen {
+fn new_pen(pen_style: PenStyle, pen_plugins: &PenPluginRegistry, pens_config: &PensConfig) -> Pen {
     match pen_style {
         PenStyle::Brush => Pen::Brush(Brush::default()),
         PenStyle::Shaper => Pen::Shaper(Shaper::default()),
@@ -385,5 +455,12 @@ fn new_pen(pen_style: PenStyle) -> Pen {
         PenStyle::Eraser => Pen::Eraser(Eraser::default()),
         PenStyle::Selector => Pen::Selector(Selector::default()),
         PenStyle::Tools => Pen::Tools(Tools::default()),
+        PenStyle::Plugin => Pen::Plugin(PluginPen::new(
+            pens_config
+                .plugins_config
+                .current
+                .as_ref()
+                .and_then(|id| pen_plugins.create(id)),
+        )),
     }
 }
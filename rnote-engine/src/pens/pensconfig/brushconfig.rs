@@ -2,6 +2,8 @@
 use crate::store::chrono_comp::StrokeLayer;
 use rand::{Rng, SeedableRng};
 use rnote_compose::builders::PenPathBuilderType;
+use rnote_compose::style::airbrush::AirbrushOptions;
+use rnote_compose::style::grain::GrainOptions;
 use rnote_compose::style::smooth::SmoothOptions;
 use rnote_compose::style::textured::TexturedOptions;
 use rnote_compose::style::PressureCurve;
@@ -27,6 +29,10 @@ pub enum BrushStyle {
     Solid,
     #[serde(rename = "textured")]
     Textured,
+    #[serde(rename = "grain")]
+    Grain,
+    #[serde(rename = "airbrush")]
+    Airbrush,
 }
 
 impl Default for BrushStyle {
@@ -97,7 +103,7 @@ impl std::ops::DerefMut for SolidOptions {
     }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default, rename = "brush_config")]
 pub struct BrushConfig {
     #[serde(rename = "builder_type")]
@@ -110,16 +116,67 @@ pub struct BrushConfig {
     pub solid_options: SolidOptions,
     #[serde(rename = "textured_options")]
     pub textured_options: TexturedOptions,
+    #[serde(rename = "grain_options")]
+    pub grain_options: GrainOptions,
+    #[serde(rename = "airbrush_options")]
+    pub airbrush_options: AirbrushOptions,
+    /// The angle step in degrees that strokes are locked to while the axis lock modifier is pressed.
+    #[serde(rename = "axis_lock_angle", with = "rnote_compose::serialize::f64_dp3")]
+    pub axis_lock_angle: f64,
+    /// The strength that finished strokes get smoothed and simplified with. 0.0 disables smoothing.
+    #[serde(rename = "smoothing", with = "rnote_compose::serialize::f64_dp3")]
+    pub smoothing: f64,
+    /// How far ahead (in milliseconds) the stroke tip is predicted and provisionally drawn. 0.0 disables the prediction.
+    #[serde(
+        rename = "input_prediction",
+        with = "rnote_compose::serialize::f64_dp3"
+    )]
+    pub input_prediction: f64,
+    /// The rope length (in surface pixels) of the stabilizer, that the brush is pulled behind the pointer with. 0.0 disables the stabilizer.
+    #[serde(
+        rename = "stabilizer_rope_length",
+        with = "rnote_compose::serialize::f64_dp3"
+    )]
+    pub stabilizer_rope_length: f64,
+}
+
+impl Default for BrushConfig {
+    fn default() -> Self {
+        Self {
+            builder_type: PenPathBuilderType::default(),
+            style: BrushStyle::default(),
+            marker_options: MarkerOptions::default(),
+            solid_options: SolidOptions::default(),
+            textured_options: TexturedOptions::default(),
+            grain_options: GrainOptions::default(),
+            airbrush_options: AirbrushOptions::default(),
+            axis_lock_angle: Self::AXIS_LOCK_ANGLE_DEFAULT,
+            smoothing: 0.0,
+            input_prediction: 0.0,
+            stabilizer_rope_length: 0.0,
+        }
+    }
 }
 
 impl BrushConfig {
     pub const STROKE_WIDTH_MIN: f64 = 0.1;
     pub const STROKE_WIDTH_MAX: f64 = 500.0;
+    pub const AXIS_LOCK_ANGLE_MIN: f64 = 1.0;
+    pub const AXIS_LOCK_ANGLE_MAX: f64 = 90.0;
+    pub const AXIS_LOCK_ANGLE_DEFAULT: f64 = 45.0;
+    pub const SMOOTHING_MIN: f64 = 0.0;
+    pub const SMOOTHING_MAX: f64 = 1.0;
+    pub const INPUT_PREDICTION_MIN: f64 = 0.0;
+    pub const INPUT_PREDICTION_MAX: f64 = 50.0;
+    pub const STABILIZER_ROPE_LENGTH_MIN: f64 = 0.0;
+    pub const STABILIZER_ROPE_LENGTH_MAX: f64 = 200.0;
 
     pub(crate) fn layer_for_current_options(&self) -> StrokeLayer {
         match &self.style {
             BrushStyle::Marker => StrokeLayer::Highlighter,
-            BrushStyle::Solid | BrushStyle::Textured => StrokeLayer::UserLayer(0),
+            BrushStyle::Solid | BrushStyle::Textured | BrushStyle::Grain | BrushStyle::Airbrush => {
+                StrokeLayer::UserLayer(0)
+            }
         }
     }
 
@@ -127,6 +184,7 @@ impl BrushConfig {
     pub(crate) fn new_style_seeds(&mut self) {
         let seed = Some(rand_pcg::Pcg64::from_entropy().gen());
         self.textured_options.seed = seed;
+        self.airbrush_options.seed = seed;
     }
 
     pub(crate) fn style_for_current_options(&self) -> Style {
@@ -146,6 +204,16 @@ impl BrushConfig {
 
                 Style::Textured(options)
             }
+            BrushStyle::Grain => {
+                let options = self.grain_options.clone();
+
+                Style::Grain(options)
+            }
+            BrushStyle::Airbrush => {
+                let options = self.airbrush_options.clone();
+
+                Style::Airbrush(options)
+            }
         }
     }
 }
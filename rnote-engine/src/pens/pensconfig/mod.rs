@@ -1,6 +1,8 @@
 // Modules
 pub mod brushconfig;
 pub mod eraserconfig;
+pub mod penpresets;
+pub mod pluginsconfig;
 pub mod selectorconfig;
 pub mod shaperconfig;
 pub mod toolsconfig;
@@ -9,12 +11,16 @@ pub mod typewriterconfig;
 // Re-exports
 pub use brushconfig::BrushConfig;
 pub use eraserconfig::EraserConfig;
+pub use penpresets::PenPresets;
+pub use pluginsconfig::PluginsConfig;
 pub use selectorconfig::SelectorConfig;
 pub use shaperconfig::ShaperConfig;
 pub use toolsconfig::ToolsConfig;
 pub use typewriterconfig::TypewriterConfig;
 
 // Imports
+use super::{DrawingAids, PenStyle};
+use rnote_compose::Color;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -32,4 +38,117 @@ pub struct PensConfig {
     pub selector_config: SelectorConfig,
     #[serde(default, rename = "tools_config")]
     pub tools_config: ToolsConfig,
+    #[serde(default, rename = "plugins_config")]
+    pub plugins_config: PluginsConfig,
+    #[serde(default, rename = "drawing_aids")]
+    pub drawing_aids: DrawingAids,
+    #[serde(default, rename = "brush_presets")]
+    pub brush_presets: PenPresets<BrushConfig>,
+    #[serde(default, rename = "shaper_presets")]
+    pub shaper_presets: PenPresets<ShaperConfig>,
+    #[serde(default, rename = "typewriter_presets")]
+    pub typewriter_presets: PenPresets<TypewriterConfig>,
+    #[serde(default, rename = "eraser_presets")]
+    pub eraser_presets: PenPresets<EraserConfig>,
+}
+
+impl PensConfig {
+    /// The color the pen draws its strokes with.
+    pub fn stroke_color(&self, style: PenStyle) -> Option<Color> {
+        match style {
+            PenStyle::Brush => self.brush_config.style_for_current_options().stroke_color(),
+            PenStyle::Shaper => self
+                .shaper_config
+                .gen_style_for_current_options()
+                .stroke_color(),
+            PenStyle::Typewriter => Some(self.typewriter_config.text_style.color),
+            PenStyle::Eraser | PenStyle::Selector | PenStyle::Tools | PenStyle::Plugin => None,
+        }
+    }
+
+    /// Set the stroke color of all pens and styles.
+    pub fn set_stroke_color(&mut self, color: Color) {
+        self.brush_config.marker_options.stroke_color = Some(color);
+        self.brush_config.solid_options.stroke_color = Some(color);
+        self.brush_config.textured_options.stroke_color = Some(color);
+        self.brush_config.grain_options.stroke_color = Some(color);
+        self.brush_config.airbrush_options.stroke_color = Some(color);
+        self.shaper_config.smooth_options.stroke_color = Some(color);
+        self.shaper_config.rough_options.stroke_color = Some(color);
+        self.typewriter_config.text_style.color = color;
+    }
+
+    /// Save the current config of the pen as new preset.
+    ///
+    /// Returns the index of the new preset, or None when the pen has no presets or the maximum number is reached.
+    pub fn save_preset(&mut self, style: PenStyle) -> Option<usize> {
+        match style {
+            PenStyle::Brush => self.brush_presets.save(&self.brush_config),
+            PenStyle::Shaper => self.shaper_presets.save(&self.shaper_config),
+            PenStyle::Typewriter => self.typewriter_presets.save(&self.typewriter_config),
+            PenStyle::Eraser => self.eraser_presets.save(&self.eraser_config),
+            PenStyle::Selector | PenStyle::Tools | PenStyle::Plugin => None,
+        }
+    }
+
+    /// Apply the next saved preset of the pen.
+    ///
+    /// Returns the index of the applied preset, or None when the pen has no presets.
+    pub fn cycle_preset(&mut self, style: PenStyle) -> Option<usize> {
+        match style {
+            PenStyle::Brush => self.brush_presets.cycle(&mut self.brush_config),
+            PenStyle::Shaper => self.shaper_presets.cycle(&mut self.shaper_config),
+            PenStyle::Typewriter => self.typewriter_presets.cycle(&mut self.typewriter_config),
+            PenStyle::Eraser => self.eraser_presets.cycle(&mut self.eraser_config),
+            PenStyle::Selector | PenStyle::Tools | PenStyle::Plugin => None,
+        }
+    }
+
+    /// The index of the last applied preset of the pen.
+    pub fn current_preset(&self, style: PenStyle) -> Option<usize> {
+        match style {
+            PenStyle::Brush => self.brush_presets.current(),
+            PenStyle::Shaper => self.shaper_presets.current(),
+            PenStyle::Typewriter => self.typewriter_presets.current(),
+            PenStyle::Eraser => self.eraser_presets.current(),
+            PenStyle::Selector | PenStyle::Tools | PenStyle::Plugin => None,
+        }
+    }
+
+    /// Apply the saved preset at the index of the pen.
+    ///
+    /// Returns false when the pen has no preset at the index.
+    pub fn apply_preset(&mut self, style: PenStyle, i: usize) -> bool {
+        match style {
+            PenStyle::Brush => self.brush_presets.apply(i, &mut self.brush_config),
+            PenStyle::Shaper => self.shaper_presets.apply(i, &mut self.shaper_config),
+            PenStyle::Typewriter => self
+                .typewriter_presets
+                .apply(i, &mut self.typewriter_config),
+            PenStyle::Eraser => self.eraser_presets.apply(i, &mut self.eraser_config),
+            PenStyle::Selector | PenStyle::Tools | PenStyle::Plugin => false,
+        }
+    }
+
+    /// The number of saved presets of the pen.
+    pub fn n_presets(&self, style: PenStyle) -> usize {
+        match style {
+            PenStyle::Brush => self.brush_presets.presets().len(),
+            PenStyle::Shaper => self.shaper_presets.presets().len(),
+            PenStyle::Typewriter => self.typewriter_presets.presets().len(),
+            PenStyle::Eraser => self.eraser_presets.presets().len(),
+            PenStyle::Selector | PenStyle::Tools | PenStyle::Plugin => 0,
+        }
+    }
+
+    /// Remove all saved presets of the pen.
+    pub fn clear_presets(&mut self, style: PenStyle) {
+        match style {
+            PenStyle::Brush => self.brush_presets = PenPresets::default(),
+            PenStyle::Shaper => self.shaper_presets = PenPresets::default(),
+            PenStyle::Typewriter => self.typewriter_presets = PenPresets::default(),
+            PenStyle::Eraser => self.eraser_presets = PenPresets::default(),
+            PenStyle::Selector | PenStyle::Tools | PenStyle::Plugin => {}
+        }
+    }
 }
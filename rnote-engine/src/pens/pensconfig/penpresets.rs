@@ -0,0 +1,103 @@
+// Imports
+use serde::{Deserialize, Serialize};
+
+/// Saved configurations of a pen, that can be cycled through to quickly switch between favorite pens.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "pen_presets")]
+pub struct PenPresets<T> {
+    #[serde(rename = "presets")]
+    presets: Vec<T>,
+    /// The index of the last applied preset.
+    #[serde(rename = "current")]
+    current: Option<usize>,
+}
+
+impl<T> Default for PenPresets<T> {
+    fn default() -> Self {
+        Self {
+            presets: vec![],
+            current: None,
+        }
+    }
+}
+
+impl<T> PenPresets<T>
+where
+    T: Clone,
+{
+    /// The maximum number of presets per pen.
+    pub const PRESETS_MAX: usize = 8;
+
+    /// The saved presets.
+    pub fn presets(&self) -> &[T] {
+        &self.presets
+    }
+
+    /// The index of the last applied preset.
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// Save the config as new preset, which then is the current one.
+    ///
+    /// Returns the index of the new preset, or None when the maximum number of presets is reached.
+    pub fn save(&mut self, config: &T) -> Option<usize> {
+        if self.presets.len() >= Self::PRESETS_MAX {
+            return None;
+        }
+        self.presets.push(config.clone());
+        self.current = Some(self.presets.len() - 1);
+        self.current
+    }
+
+    /// Overwrite the preset at the index with the config.
+    ///
+    /// Returns false when there is no preset at the index.
+    pub fn replace(&mut self, i: usize, config: &T) -> bool {
+        let Some(preset) = self.presets.get_mut(i) else {
+            return false;
+        };
+        *preset = config.clone();
+        true
+    }
+
+    /// Remove the preset at the index.
+    pub fn remove(&mut self, i: usize) -> Option<T> {
+        if i >= self.presets.len() {
+            return None;
+        }
+        let removed = self.presets.remove(i);
+        self.current = match self.current {
+            Some(current) if current == i => None,
+            Some(current) if current > i => Some(current - 1),
+            current => current,
+        };
+        Some(removed)
+    }
+
+    /// Apply the preset at the index to the config.
+    ///
+    /// Returns false when there is no preset at the index.
+    pub fn apply(&mut self, i: usize, config: &mut T) -> bool {
+        let Some(preset) = self.presets.get(i) else {
+            return false;
+        };
+        *config = preset.clone();
+        self.current = Some(i);
+        true
+    }
+
+    /// Apply the next preset to the config, wrapping around after the last one.
+    ///
+    /// Returns the index of the applied preset, or None when no presets are saved.
+    pub fn cycle(&mut self, config: &mut T) -> Option<usize> {
+        if self.presets.is_empty() {
+            return None;
+        }
+        let next = self
+            .current
+            .map_or(0, |current| (current + 1) % self.presets.len());
+        self.apply(next, config);
+        Some(next)
+    }
+}
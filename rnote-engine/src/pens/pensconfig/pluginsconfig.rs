@@ -0,0 +1,50 @@
+// Imports
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The config of the pen plugins.
+///
+/// The configs of the individual plugins are stored by their id as opaque values,
+/// so they are persisted even when the plugin is currently not registered.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, rename = "plugins_config")]
+pub struct PluginsConfig {
+    /// The id of the selected pen plugin.
+    #[serde(rename = "current")]
+    pub current: Option<String>,
+    #[serde(rename = "configs")]
+    configs: HashMap<String, serde_json::Value>,
+}
+
+impl PluginsConfig {
+    /// The config of the plugin with the given id.
+    ///
+    /// Returns the default when no config is stored or it can't be deserialized into the type.
+    pub fn config<T: DeserializeOwned + Default>(&self, id: &str) -> T {
+        self.configs
+            .get(id)
+            .and_then(|value| match serde_json::from_value(value.clone()) {
+                Ok(config) => Some(config),
+                Err(e) => {
+                    log::warn!("Deserializing config of pen plugin `{id}` failed, Err: {e:?}");
+                    None
+                }
+            })
+            .unwrap_or_default()
+    }
+
+    /// Store the config of the plugin with the given id.
+    pub fn set_config<T: Serialize>(&mut self, id: &str, config: &T) -> anyhow::Result<()> {
+        let value = serde_json::to_value(config).map_err(|e| {
+            anyhow::anyhow!("Serializing config of pen plugin `{id}` failed, Err: {e:?}")
+        })?;
+        self.configs.insert(id.to_string(), value);
+        Ok(())
+    }
+
+    /// Remove the stored config of the plugin with the given id.
+    pub fn remove_config(&mut self, id: &str) {
+        self.configs.remove(id);
+    }
+}
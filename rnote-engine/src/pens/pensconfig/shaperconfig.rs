@@ -48,6 +48,15 @@ pub struct ShaperConfig {
     pub rough_options: RoughOptions,
     #[serde(rename = "constraints")]
     pub constraints: Constraints,
+    /// Whether holding the pen still at the end of a shape snaps it to perfect proportions
+    #[serde(rename = "perfect_shape_hold")]
+    pub perfect_shape_hold: bool,
+    /// The time in milliseconds the pen needs to be held still for the shape to snap
+    #[serde(
+        rename = "perfect_shape_hold_duration",
+        with = "rnote_compose::serialize::f64_dp3"
+    )]
+    pub perfect_shape_hold_duration: f64,
 }
 
 impl Default for ShaperConfig {
@@ -63,6 +72,8 @@ impl Default for ShaperConfig {
             smooth_options: SmoothOptions::default(),
             rough_options: RoughOptions::default(),
             constraints,
+            perfect_shape_hold: true,
+            perfect_shape_hold_duration: Self::PERFECT_SHAPE_HOLD_DURATION_DEFAULT,
         }
     }
 }
@@ -70,6 +81,9 @@ impl Default for ShaperConfig {
 impl ShaperConfig {
     pub const STROKE_WIDTH_MIN: f64 = 0.1;
     pub const STROKE_WIDTH_MAX: f64 = 500.0;
+    pub const PERFECT_SHAPE_HOLD_DURATION_MIN: f64 = 100.0;
+    pub const PERFECT_SHAPE_HOLD_DURATION_MAX: f64 = 3000.0;
+    pub const PERFECT_SHAPE_HOLD_DURATION_DEFAULT: f64 = 600.0;
 
     /// A new seed for new shapes
     pub(crate) fn new_style_seeds(&mut self) {
@@ -77,6 +91,28 @@ impl ShaperConfig {
         self.rough_options.seed = seed;
     }
 
+    /// The constraints that snap a shape of the current builder type to perfect proportions.
+    ///
+    /// Lines and curves snap to the axes and diagonals, all other shapes to a 1:1 ratio.
+    pub(crate) fn perfect_shape_constraints(&self) -> Constraints {
+        let mut constraints = Constraints {
+            enabled: true,
+            ..Default::default()
+        };
+        constraints.ratios.insert(ConstraintRatio::OneToOne);
+        if matches!(
+            self.builder_type,
+            ShapeBuilderType::Line
+                | ShapeBuilderType::Arrow
+                | ShapeBuilderType::QuadBez
+                | ShapeBuilderType::CubBez
+        ) {
+            constraints.ratios.insert(ConstraintRatio::Horizontal);
+            constraints.ratios.insert(ConstraintRatio::Vertical);
+        }
+        constraints
+    }
+
     pub(crate) fn gen_style_for_current_options(&self) -> Style {
         match &self.style {
             ShaperStyle::Smooth => {
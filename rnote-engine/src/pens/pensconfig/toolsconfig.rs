@@ -22,6 +22,16 @@ pub enum ToolStyle {
     OffsetCamera,
     #[serde(rename = "zoom")]
     Zoom,
+    #[serde(rename = "editnodes")]
+    EditNodes,
+    #[serde(rename = "fill")]
+    Fill,
+    #[serde(rename = "eyedropper")]
+    Eyedropper,
+    #[serde(rename = "measure")]
+    Measure,
+    #[serde(rename = "select_text")]
+    SelectText,
 }
 
 impl Default for ToolStyle {
@@ -40,9 +50,37 @@ impl TryFrom<u32> for ToolStyle {
     }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default, rename = "tools_config")]
 pub struct ToolsConfig {
     #[serde(rename = "style")]
     pub style: ToolStyle,
+    /// The opacity up to which strokes are filled over instead of bounding the filled region, ranging [0.0, 1.0].
+    #[serde(rename = "fill_tolerance", with = "rnote_compose::serialize::f64_dp3")]
+    pub fill_tolerance: f64,
+    /// Gaps in the strokes bounding the filled region are closed up to this width (in surface pixels).
+    #[serde(
+        rename = "fill_gap_closing",
+        with = "rnote_compose::serialize::f64_dp3"
+    )]
+    pub fill_gap_closing: f64,
+}
+
+impl Default for ToolsConfig {
+    fn default() -> Self {
+        Self {
+            style: ToolStyle::default(),
+            fill_tolerance: Self::FILL_TOLERANCE_DEFAULT,
+            fill_gap_closing: Self::FILL_GAP_CLOSING_DEFAULT,
+        }
+    }
+}
+
+impl ToolsConfig {
+    pub const FILL_TOLERANCE_MIN: f64 = 0.0;
+    pub const FILL_TOLERANCE_MAX: f64 = 0.95;
+    pub const FILL_TOLERANCE_DEFAULT: f64 = 0.2;
+    pub const FILL_GAP_CLOSING_MIN: f64 = 0.0;
+    pub const FILL_GAP_CLOSING_MAX: f64 = 40.0;
+    pub const FILL_GAP_CLOSING_DEFAULT: f64 = 6.0;
 }
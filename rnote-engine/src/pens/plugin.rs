@@ -0,0 +1,211 @@
+// Imports
+use super::penbehaviour::{PenBehaviour, PenProgress};
+use super::PenStyle;
+use crate::engine::{EngineView, EngineViewMut};
+use crate::{DrawOnDocBehaviour, WidgetFlags};
+use p2d::bounding_volume::Aabb;
+use piet_cairo::CairoRenderContext;
+use rnote_compose::penevents::PenEvent;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// A pen behaviour provided by a plugin, e.g. a custom brush or a domain specific annotation tool.
+///
+/// Plugins get a mutable view into the engine when handling events, so they can add and modify strokes through the store.
+/// Their config is persisted in [PluginsConfig](super::pensconfig::PluginsConfig) by their id.
+/// The store history is recorded when the plugin reports [PenProgress::Finished],
+/// so the changes of the finished operation can be undone as a single step.
+pub trait PenPlugin: std::fmt::Debug {
+    /// Init the plugin pen. Called right after the pen instance was created.
+    fn init(&mut self, _engine_view: &EngineView) -> WidgetFlags {
+        WidgetFlags::default()
+    }
+
+    /// Deinit the plugin pen.
+    fn deinit(&mut self) -> WidgetFlags {
+        WidgetFlags::default()
+    }
+
+    /// Update the pen and its config state with the state from the engine.
+    fn update_state(&mut self, _engine_view: &mut EngineViewMut) -> WidgetFlags {
+        WidgetFlags::default()
+    }
+
+    /// Handle a pen event.
+    ///
+    /// The pen instance is replaced by a new one after it reports [PenProgress::Finished].
+    fn handle_event(
+        &mut self,
+        event: PenEvent,
+        now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> (PenProgress, WidgetFlags);
+
+    /// The bounds of what the pen draws on the document.
+    fn bounds_on_doc(&self, _engine_view: &EngineView) -> Option<Aabb> {
+        None
+    }
+
+    /// Draw the pen state on the document, e.g. a preview of the stroke that is currently drawn.
+    fn draw_on_doc(
+        &self,
+        _cx: &mut CairoRenderContext,
+        _engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Creates new pen instances of a plugin.
+pub type PenPluginFactory = Arc<dyn Fn() -> Box<dyn PenPlugin> + Send + Sync>;
+
+/// Describes a registered pen plugin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PenPluginInfo {
+    /// The unique id, e.g. `org.example.calligraphy`. Used to persist the plugin config and selection.
+    pub id: String,
+    /// The display name.
+    pub name: String,
+    /// The name of the icon, if the plugin provides one.
+    pub icon_name: Option<String>,
+}
+
+/// The registry of the available pen plugins.
+///
+/// Plugins are registered by the application at runtime, e.g. from external crates or
+/// after loading them dynamically.
+#[derive(Clone, Default)]
+pub struct PenPluginRegistry {
+    plugins: Vec<(PenPluginInfo, PenPluginFactory)>,
+}
+
+impl std::fmt::Debug for PenPluginRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PenPluginRegistry")
+            .field(
+                "plugins",
+                &self
+                    .plugins
+                    .iter()
+                    .map(|(info, _)| info)
+                    .collect::<Vec<&PenPluginInfo>>(),
+            )
+            .finish()
+    }
+}
+
+impl PenPluginRegistry {
+    /// Register a pen plugin. Replaces an already registered plugin with the same id.
+    pub fn register(&mut self, info: PenPluginInfo, factory: PenPluginFactory) {
+        match self.plugins.iter_mut().find(|(i, _)| i.id == info.id) {
+            Some(plugin) => *plugin = (info, factory),
+            None => self.plugins.push((info, factory)),
+        }
+    }
+
+    /// Unregister the pen plugin with the given id.
+    ///
+    /// Returns false if no plugin with the id is registered.
+    pub fn unregister(&mut self, id: &str) -> bool {
+        let len = self.plugins.len();
+        self.plugins.retain(|(info, _)| info.id != id);
+        self.plugins.len() != len
+    }
+
+    /// The infos of the registered plugins, in the order they were registered.
+    pub fn list(&self) -> Vec<PenPluginInfo> {
+        self.plugins.iter().map(|(info, _)| info.clone()).collect()
+    }
+
+    /// The info of the plugin with the given id.
+    pub fn info(&self, id: &str) -> Option<&PenPluginInfo> {
+        self.plugins
+            .iter()
+            .map(|(info, _)| info)
+            .find(|info| info.id == id)
+    }
+
+    /// Create a new pen instance of the plugin with the given id.
+    pub(super) fn create(&self, id: &str) -> Option<Box<dyn PenPlugin>> {
+        self.plugins
+            .iter()
+            .find(|(info, _)| info.id == id)
+            .map(|(_, factory)| factory())
+    }
+}
+
+/// The pen wrapping the instance of the selected pen plugin.
+///
+/// Inert if no plugin is selected or the selected plugin is not registered.
+#[derive(Debug, Default)]
+pub struct PluginPen {
+    plugin: Option<Box<dyn PenPlugin>>,
+}
+
+impl PluginPen {
+    pub fn new(plugin: Option<Box<dyn PenPlugin>>) -> Self {
+        Self { plugin }
+    }
+}
+
+impl PenBehaviour for PluginPen {
+    fn init(&mut self, engine_view: &EngineView) -> WidgetFlags {
+        self.plugin
+            .as_mut()
+            .map(|plugin| plugin.init(engine_view))
+            .unwrap_or_default()
+    }
+
+    fn deinit(&mut self) -> WidgetFlags {
+        self.plugin
+            .as_mut()
+            .map(|plugin| plugin.deinit())
+            .unwrap_or_default()
+    }
+
+    fn style(&self) -> PenStyle {
+        PenStyle::Plugin
+    }
+
+    fn update_state(&mut self, engine_view: &mut EngineViewMut) -> WidgetFlags {
+        self.plugin
+            .as_mut()
+            .map(|plugin| plugin.update_state(engine_view))
+            .unwrap_or_default()
+    }
+
+    fn handle_event(
+        &mut self,
+        event: PenEvent,
+        now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> (PenProgress, WidgetFlags) {
+        let Some(plugin) = self.plugin.as_mut() else {
+            return (PenProgress::Idle, WidgetFlags::default());
+        };
+        let (pen_progress, mut widget_flags) = plugin.handle_event(event, now, engine_view);
+
+        if pen_progress == PenProgress::Finished {
+            widget_flags.merge(engine_view.store.record(now));
+        }
+
+        (pen_progress, widget_flags)
+    }
+}
+
+impl DrawOnDocBehaviour for PluginPen {
+    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
+        self.plugin.as_ref()?.bounds_on_doc(engine_view)
+    }
+
+    fn draw_on_doc(
+        &self,
+        cx: &mut CairoRenderContext,
+        engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        match self.plugin.as_ref() {
+            Some(plugin) => plugin.draw_on_doc(cx, engine_view),
+            None => Ok(()),
+        }
+    }
+}
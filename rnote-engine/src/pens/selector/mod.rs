@@ -6,13 +6,14 @@ use super::penbehaviour::{PenBehaviour, PenProgress};
 use super::pensconfig::selectorconfig::SelectorStyle;
 use super::PenStyle;
 use crate::engine::{EngineView, EngineViewMut, StrokeContent};
+use crate::fileformats::rnoteformat::RnoteFragment;
+use crate::fileformats::FileFormatSaver;
 use crate::render::{self, Svg};
 use crate::store::StrokeKey;
 use crate::strokes::StrokeBehaviour;
 use crate::{Camera, DrawOnDocBehaviour, RnoteEngine, WidgetFlags};
 use futures::channel::oneshot;
 use kurbo::Shape;
-use once_cell::sync::Lazy;
 use p2d::bounding_volume::{Aabb, BoundingSphere, BoundingVolume};
 use p2d::query::PointQuery;
 use piet::RenderContext;
@@ -20,7 +21,7 @@ use rnote_compose::helpers::{AabbHelpers, Vector2Helpers};
 use rnote_compose::penevents::{ModifierKey, PenEvent, PenState};
 use rnote_compose::penpath::Element;
 use rnote_compose::style::indicators;
-use rnote_compose::{color, Color};
+use rnote_compose::Color;
 use std::time::Instant;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -31,6 +32,16 @@ pub(super) enum ResizeCorner {
     BottomRight,
 }
 
+/// Whether translating the selection moves a duplicate and leaves the original in place.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Stamp {
+    Off,
+    /// The selection gets duplicated as soon as it is actually moved.
+    Pending,
+    /// The selection is the duplicate.
+    Duplicated,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub(super) enum ModifyState {
     Up,
@@ -38,6 +49,7 @@ pub(super) enum ModifyState {
     Translate {
         start_pos: na::Vector2<f64>,
         current_pos: na::Vector2<f64>,
+        stamp: Stamp,
     },
     Rotate {
         rotation_center: na::Point2<f64>,
@@ -175,6 +187,11 @@ impl PenBehaviour for Selector {
                         StrokeContent::CLIPBOARD_EXPORT_MARGIN,
                     )?;
 
+                    // Add the lossless rnote fragment
+                    clipboard_content.push((
+                        RnoteFragment::new(stroke_content.clone()).save_as_bytes("")?,
+                        RnoteFragment::MIME_TYPE.to_string(),
+                    ));
                     // Add StrokeContent
                     clipboard_content.push((
                         serde_json::to_string(&stroke_content)?.into_bytes(),
@@ -238,6 +255,11 @@ impl PenBehaviour for Selector {
                         StrokeContent::CLIPBOARD_EXPORT_MARGIN,
                     )?;
 
+                    // Add the lossless rnote fragment
+                    clipboard_content.push((
+                        RnoteFragment::new(stroke_content.clone()).save_as_bytes("")?,
+                        RnoteFragment::MIME_TYPE.to_string(),
+                    ));
                     // Add StrokeContent
                     clipboard_content.push((
                         serde_json::to_string(&stroke_content)?.into_bytes(),
@@ -303,7 +325,10 @@ impl DrawOnDocBehaviour for Selector {
             }
             SelectorState::ModifySelection {
                 selection_bounds, ..
-            } => Some(selection_bounds.extend_by(Self::RESIZE_NODE_SIZE / total_zoom)),
+            } => Some(selection_bounds.extend_by(na::Vector2::repeat(
+                engine_view.camera.overlay_handle_size()
+                    + engine_view.camera.overlay_hit_tolerance(),
+            ))),
         }
     }
 
@@ -314,6 +339,10 @@ impl DrawOnDocBehaviour for Selector {
     ) -> anyhow::Result<()> {
         cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
         let total_zoom = engine_view.camera.total_zoom();
+        let selection_outline_color =
+            piet::Color::from(engine_view.camera.overlay_config.selection_outline_color);
+        let selection_fill_color =
+            piet::Color::from(engine_view.camera.overlay_config.selection_fill_color);
 
         match &self.state {
             SelectorState::Idle => {}
@@ -340,10 +369,10 @@ impl DrawOnDocBehaviour for Selector {
                                     .collect::<Vec<f64>>(),
                             );
 
-                            cx.fill(bez_path.clone(), &*SELECTION_FILL_COLOR);
+                            cx.fill(bez_path.clone(), &selection_fill_color);
                             cx.stroke_styled(
                                 bez_path,
-                                &*SELECTION_OUTLINE_COLOR,
+                                &selection_outline_color,
                                 Self::OUTLINE_STROKE_WIDTH / total_zoom,
                                 &stroke_style,
                             );
@@ -364,10 +393,10 @@ impl DrawOnDocBehaviour for Selector {
                                     .collect::<Vec<f64>>(),
                             );
 
-                            cx.fill(select_rect, &*SELECTION_FILL_COLOR);
+                            cx.fill(select_rect, &selection_fill_color);
                             cx.stroke_styled(
                                 select_rect,
-                                &*SELECTION_OUTLINE_COLOR,
+                                &selection_outline_color,
                                 Self::OUTLINE_STROKE_WIDTH / total_zoom,
                                 &stroke_style,
                             );
@@ -380,7 +409,7 @@ impl DrawOnDocBehaviour for Selector {
                                     last.pos.to_kurbo_point(),
                                     Self::SELECTING_SINGLE_CIRCLE_RADIUS / total_zoom,
                                 ),
-                                &*SELECTION_OUTLINE_COLOR,
+                                &selection_outline_color,
                                 Self::OUTLINE_STROKE_WIDTH / total_zoom,
                             );
                         }
@@ -406,7 +435,7 @@ impl DrawOnDocBehaviour for Selector {
 
                             cx.stroke_styled(
                                 bez_path,
-                                &*SELECTION_OUTLINE_COLOR,
+                                &selection_outline_color,
                                 Self::OUTLINE_STROKE_WIDTH / total_zoom,
                                 &stroke_style,
                             );
@@ -457,13 +486,6 @@ impl DrawOnDocBehaviour for Selector {
     }
 }
 
-/// The outline color when drawing a selection
-static SELECTION_OUTLINE_COLOR: Lazy<piet::Color> =
-    Lazy::new(|| color::GNOME_BRIGHTS[4].with_alpha(0.941));
-/// The fill color when drawing a selection
-static SELECTION_FILL_COLOR: Lazy<piet::Color> =
-    Lazy::new(|| color::GNOME_BRIGHTS[2].with_alpha(0.050));
-
 impl Selector {
     /// The threshold magnitude where above it the translation is applied. In surface coordinates.
     const TRANSLATE_MAGNITUDE_THRESHOLD: f64 = 1.414;
@@ -475,10 +497,6 @@ impl Selector {
     const SELECTING_DASH_PATTERN: [f64; 2] = [12.0, 6.0];
     /// The radius of the circle when selecting in single mode.
     const SELECTING_SINGLE_CIRCLE_RADIUS: f64 = 4.0;
-    /// Resize node size, in surface coordinates.
-    const RESIZE_NODE_SIZE: na::Vector2<f64> = na::vector![18.0, 18.0];
-    /// Rotate node size, in surface coordinates.
-    const ROTATE_NODE_SIZE: f64 = 18.0;
 
     fn add_to_select_path(style: SelectorStyle, path: &mut Vec<Element>, element: Element) {
         match style {
@@ -496,35 +514,55 @@ impl Selector {
         }
     }
 
+    /// The bounds of the resize node at the given corner of the selection.
     fn resize_node_bounds(position: ResizeCorner, selection_bounds: Aabb, camera: &Camera) -> Aabb {
-        let total_zoom = camera.total_zoom();
+        let half_extents = na::Vector2::repeat(camera.overlay_handle_size() * 0.5);
         match position {
             ResizeCorner::TopLeft => Aabb::from_half_extents(
                 na::point![selection_bounds.mins[0], selection_bounds.mins[1]],
-                Self::RESIZE_NODE_SIZE * 0.5 / total_zoom,
+                half_extents,
             ),
             ResizeCorner::TopRight => Aabb::from_half_extents(
                 na::point![selection_bounds.maxs[0], selection_bounds.mins[1]],
-                Self::RESIZE_NODE_SIZE * 0.5 / total_zoom,
+                half_extents,
             ),
             ResizeCorner::BottomLeft => Aabb::from_half_extents(
                 na::point![selection_bounds.mins[0], selection_bounds.maxs[1]],
-                Self::RESIZE_NODE_SIZE * 0.5 / total_zoom,
+                half_extents,
             ),
             ResizeCorner::BottomRight => Aabb::from_half_extents(
                 na::point![selection_bounds.maxs[0], selection_bounds.maxs[1]],
-                Self::RESIZE_NODE_SIZE * 0.5 / total_zoom,
+                half_extents,
             ),
         }
     }
 
+    /// Whether the position hits the resize node at the given corner, including the hit tolerance.
+    fn resize_node_hit(
+        position: ResizeCorner,
+        selection_bounds: Aabb,
+        pos: na::Vector2<f64>,
+        camera: &Camera,
+    ) -> bool {
+        Self::resize_node_bounds(position, selection_bounds, camera)
+            .loosened(camera.overlay_hit_tolerance())
+            .contains_local_point(&pos.into())
+    }
+
+    /// The sphere of the rotate node.
     fn rotate_node_sphere(selection_bounds: Aabb, camera: &Camera) -> BoundingSphere {
-        let total_zoom = camera.total_zoom();
         let pos = na::point![
             selection_bounds.maxs[0],
             (selection_bounds.maxs[1] + selection_bounds.mins[1]) * 0.5
         ];
-        BoundingSphere::new(pos, Self::ROTATE_NODE_SIZE * 0.5 / total_zoom)
+        BoundingSphere::new(pos, camera.overlay_handle_size() * 0.5)
+    }
+
+    /// Whether the position hits the rotate node, including the hit tolerance.
+    fn rotate_node_hit(selection_bounds: Aabb, pos: na::Vector2<f64>, camera: &Camera) -> bool {
+        Self::rotate_node_sphere(selection_bounds, camera)
+            .loosened(camera.overlay_hit_tolerance())
+            .contains_local_point(&pos.into())
     }
 
     fn draw_selection_overlay(
@@ -534,13 +572,16 @@ impl Selector {
         camera: &Camera,
     ) -> anyhow::Result<()> {
         piet_cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
-        let total_zoom = camera.total_zoom();
+        let total_zoom = camera.overlay_zoom();
+        let selection_outline_color =
+            piet::Color::from(camera.overlay_config.selection_outline_color);
+        let selection_fill_color = piet::Color::from(camera.overlay_config.selection_fill_color);
 
         let rotate_node_sphere = Self::rotate_node_sphere(selection_bounds, camera);
         let rotate_node_state = match modify_state {
             ModifyState::Rotate { .. } => PenState::Down,
             ModifyState::Hover(pos) => {
-                if rotate_node_sphere.contains_local_point(&(*pos).into()) {
+                if Self::rotate_node_hit(selection_bounds, *pos, camera) {
                     PenState::Proximity
                 } else {
                     PenState::Up
@@ -557,7 +598,7 @@ impl Selector {
                 ..
             } => PenState::Down,
             ModifyState::Hover(pos) => {
-                if resize_tl_node_bounds.contains_local_point(&(*pos).into()) {
+                if Self::resize_node_hit(ResizeCorner::TopLeft, selection_bounds, *pos, camera) {
                     PenState::Proximity
                 } else {
                     PenState::Up
@@ -574,7 +615,7 @@ impl Selector {
                 ..
             } => PenState::Down,
             ModifyState::Hover(pos) => {
-                if resize_tr_node_bounds.contains_local_point(&(*pos).into()) {
+                if Self::resize_node_hit(ResizeCorner::TopRight, selection_bounds, *pos, camera) {
                     PenState::Proximity
                 } else {
                     PenState::Up
@@ -591,7 +632,7 @@ impl Selector {
                 ..
             } => PenState::Down,
             ModifyState::Hover(pos) => {
-                if resize_bl_node_bounds.contains_local_point(&(*pos).into()) {
+                if Self::resize_node_hit(ResizeCorner::BottomLeft, selection_bounds, *pos, camera) {
                     PenState::Proximity
                 } else {
                     PenState::Up
@@ -608,7 +649,8 @@ impl Selector {
                 ..
             } => PenState::Down,
             ModifyState::Hover(pos) => {
-                if resize_br_node_bounds.contains_local_point(&(*pos).into()) {
+                if Self::resize_node_hit(ResizeCorner::BottomRight, selection_bounds, *pos, camera)
+                {
                     PenState::Proximity
                 } else {
                     PenState::Up
@@ -674,10 +716,10 @@ impl Selector {
 
         piet_cx.clip(clip_path);
 
-        piet_cx.fill(selection_rect, &*SELECTION_FILL_COLOR);
+        piet_cx.fill(selection_rect, &selection_fill_color);
         piet_cx.stroke(
             selection_rect,
-            &*SELECTION_OUTLINE_COLOR,
+            &selection_outline_color,
             Selector::OUTLINE_STROKE_WIDTH / total_zoom,
         );
 
@@ -1,8 +1,9 @@
 // Imports
-use super::{ModifyState, ResizeCorner, Selector, SelectorState};
+use super::{ModifyState, ResizeCorner, Selector, SelectorState, Stamp};
 use crate::engine::EngineViewMut;
 use crate::pens::penbehaviour::PenProgress;
 use crate::pens::pensconfig::selectorconfig::SelectorStyle;
+use crate::strokes::Stroke;
 use crate::{DrawOnDocBehaviour, WidgetFlags};
 use p2d::bounding_volume::Aabb;
 use p2d::query::PointQuery;
@@ -78,9 +79,11 @@ impl Selector {
                             {
                                 *selection_bounds = new_bounds;
                             }
-                        } else if Self::rotate_node_sphere(*selection_bounds, engine_view.camera)
-                            .contains_local_point(&element.pos.into())
-                        {
+                        } else if Self::rotate_node_hit(
+                            *selection_bounds,
+                            element.pos,
+                            engine_view.camera,
+                        ) {
                             // clicking on the rotate node
                             let rotation_angle = {
                                 let vec = element.pos - selection_bounds.center().coords;
@@ -93,59 +96,61 @@ impl Selector {
                                 current_rotation_angle: rotation_angle,
                             };
                             // clicking on one of the resize nodes at the corners
-                        } else if Self::resize_node_bounds(
+                        } else if Self::resize_node_hit(
                             ResizeCorner::TopLeft,
                             *selection_bounds,
+                            element.pos,
                             engine_view.camera,
-                        )
-                        .contains_local_point(&element.pos.into())
-                        {
+                        ) {
                             *modify_state = ModifyState::Resize {
                                 from_corner: ResizeCorner::TopLeft,
                                 start_bounds: *selection_bounds,
                                 start_pos: element.pos,
                             }
-                        } else if Self::resize_node_bounds(
+                        } else if Self::resize_node_hit(
                             ResizeCorner::TopRight,
                             *selection_bounds,
+                            element.pos,
                             engine_view.camera,
-                        )
-                        .contains_local_point(&element.pos.into())
-                        {
+                        ) {
                             *modify_state = ModifyState::Resize {
                                 from_corner: ResizeCorner::TopRight,
                                 start_bounds: *selection_bounds,
                                 start_pos: element.pos,
                             }
-                        } else if Self::resize_node_bounds(
+                        } else if Self::resize_node_hit(
                             ResizeCorner::BottomLeft,
                             *selection_bounds,
+                            element.pos,
                             engine_view.camera,
-                        )
-                        .contains_local_point(&element.pos.into())
-                        {
+                        ) {
                             *modify_state = ModifyState::Resize {
                                 from_corner: ResizeCorner::BottomLeft,
                                 start_bounds: *selection_bounds,
                                 start_pos: element.pos,
                             }
-                        } else if Self::resize_node_bounds(
+                        } else if Self::resize_node_hit(
                             ResizeCorner::BottomRight,
                             *selection_bounds,
+                            element.pos,
                             engine_view.camera,
-                        )
-                        .contains_local_point(&element.pos.into())
-                        {
+                        ) {
                             *modify_state = ModifyState::Resize {
                                 from_corner: ResizeCorner::BottomRight,
                                 start_bounds: *selection_bounds,
                                 start_pos: element.pos,
                             }
                         } else if selection_bounds.contains_local_point(&element.pos.into()) {
-                            // clicking inside the selection bounds, triggering translation
+                            // clicking inside the selection bounds, triggering translation.
+                            // While pressing Alt a duplicate is moved and the original is left in place
                             *modify_state = ModifyState::Translate {
                                 start_pos: element.pos,
                                 current_pos: element.pos,
+                                stamp: if modifier_keys.contains(&ModifierKey::KeyboardAlt) {
+                                    Stamp::Pending
+                                } else {
+                                    Stamp::Off
+                                },
                             };
                         } else {
                             // when clicking outside the selection bounds, reset
@@ -158,12 +163,20 @@ impl Selector {
                     ModifyState::Translate {
                         start_pos: _,
                         current_pos,
+                        stamp,
                     } => {
                         let offset = element.pos - *current_pos;
 
                         if offset.magnitude()
                             > Self::TRANSLATE_MAGNITUDE_THRESHOLD / engine_view.camera.total_zoom()
                         {
+                            if *stamp == Stamp::Pending {
+                                // Duplicate in place, the duplicate becomes the selection that is moved
+                                *selection = engine_view
+                                    .store
+                                    .duplicate_selection_w_offset(na::Vector2::zeros());
+                                *stamp = Stamp::Duplicated;
+                            }
                             engine_view.store.translate_strokes(selection, offset);
                             engine_view
                                 .store
@@ -372,6 +385,17 @@ impl Selector {
                 selection,
                 selection_bounds,
             } => {
+                if let ModifyState::Translate {
+                    start_pos,
+                    current_pos,
+                    stamp: Stamp::Duplicated,
+                } = modify_state
+                {
+                    engine_view
+                        .store
+                        .set_last_duplicate_offset(*current_pos - *start_pos);
+                }
+
                 match modify_state {
                     ModifyState::Translate { .. }
                     | ModifyState::Rotate { .. }
@@ -471,10 +495,20 @@ impl Selector {
                     KeyboardKey::Unicode('a') => {
                         self.select_all(modifier_keys, engine_view, &mut widget_flags)
                     }
-                    KeyboardKey::Unicode('d') => {
-                        //Duplicate selection
+                    KeyboardKey::Unicode('d') | KeyboardKey::Unicode('D') => {
+                        //Duplicate selection, repeating the last duplication offset while pressing Shift
+                        // and duplicating as linked instances while pressing Alt
                         if modifier_keys.contains(&ModifierKey::KeyboardCtrl) {
-                            let duplicated = engine_view.store.duplicate_selection();
+                            let duplicated = if modifier_keys.contains(&ModifierKey::KeyboardShift)
+                            {
+                                engine_view.store.duplicate_selection_repeat()
+                            } else if modifier_keys.contains(&ModifierKey::KeyboardAlt) {
+                                engine_view
+                                    .store
+                                    .duplicate_selection_linked(Stroke::IMPORT_OFFSET_DEFAULT)
+                            } else {
+                                engine_view.store.duplicate_selection()
+                            };
                             engine_view.store.update_geometry_for_strokes(&duplicated);
                             engine_view.store.regenerate_rendering_for_strokes_threaded(
                                 engine_view.tasks_tx.clone(),
@@ -16,16 +16,52 @@ use rnote_compose::builders::{CubBezBuilder, QuadBezBuilder, ShapeBuilderType};
 use rnote_compose::builders::{ShapeBuilderCreator, ShapeBuilderProgress};
 use rnote_compose::penevents::{KeyboardKey, ModifierKey, PenEvent};
 use rnote_compose::penpath::Element;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 enum ShaperState {
     Idle,
     BuildShape {
         builder: Box<dyn ShapeBuilderBehaviour>,
+        hold: ShapeHold,
     },
 }
 
+/// Tracks whether the pen is held still while building a shape.
+#[derive(Debug, Clone, Copy)]
+struct ShapeHold {
+    /// The last element where the pen moved beyond the hold tolerance.
+    anchor: Element,
+    /// The time since the pen rests at the anchor.
+    since: Instant,
+    /// Whether the shape is currently snapped to perfect proportions.
+    perfect: bool,
+}
+
+impl ShapeHold {
+    /// The distance (in surface coords) the pen may move while still being considered held.
+    const TOLERANCE: f64 = 4.0;
+
+    fn new(element: Element, now: Instant) -> Self {
+        Self {
+            anchor: element,
+            since: now,
+            perfect: false,
+        }
+    }
+
+    /// Update with a new pen position.
+    ///
+    /// Moving away releases the snap, resting for the hold duration engages it.
+    fn update(&mut self, element: Element, now: Instant, duration: Duration, total_zoom: f64) {
+        if (element.pos - self.anchor.pos).norm() > Self::TOLERANCE / total_zoom {
+            *self = Self::new(element, now);
+        } else if now.duration_since(self.since) >= duration {
+            self.perfect = true;
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Shaper {
     state: ShaperState,
@@ -74,6 +110,7 @@ impl PenBehaviour for Shaper {
                         element,
                         now,
                     ),
+                    hold: ShapeHold::new(element, now),
                 };
 
                 PenProgress::InProgress
@@ -84,7 +121,7 @@ impl PenBehaviour for Shaper {
 
                 PenProgress::Finished
             }
-            (ShaperState::BuildShape { builder }, event) => {
+            (ShaperState::BuildShape { builder, hold }, event) => {
                 // Use Ctrl to temporarily enable/disable constraints when the switch is off/on
                 let mut constraints = engine_view.pens_config.shaper_config.constraints.clone();
                 constraints.enabled = match event {
@@ -103,6 +140,43 @@ impl PenBehaviour for Shaper {
                     PenEvent::Text { .. } | PenEvent::Cancel => false,
                 };
 
+                // Holding the pen still snaps the shape to perfect proportions
+                let shaper_config = &engine_view.pens_config.shaper_config;
+                if shaper_config.perfect_shape_hold {
+                    let hold_duration =
+                        Duration::from_secs_f64(shaper_config.perfect_shape_hold_duration * 1e-3);
+                    let total_zoom = engine_view.camera.total_zoom();
+
+                    match &event {
+                        PenEvent::Down { element, .. } => {
+                            hold.update(*element, now, hold_duration, total_zoom);
+                        }
+                        PenEvent::Up {
+                            element,
+                            modifier_keys,
+                        } => {
+                            // Motion events might not be emitted while the pen rests,
+                            // so the hold is also checked when the pen is lifted.
+                            hold.update(*element, now, hold_duration, total_zoom);
+                            if hold.perfect {
+                                // Let the builder update to the snapped position before finishing
+                                builder.handle_event(
+                                    PenEvent::Down {
+                                        element: hold.anchor,
+                                        modifier_keys: modifier_keys.clone(),
+                                    },
+                                    now,
+                                    shaper_config.perfect_shape_constraints(),
+                                );
+                            }
+                        }
+                        _ => {}
+                    }
+                    if hold.perfect {
+                        constraints = shaper_config.perfect_shape_constraints();
+                    }
+                }
+
                 let mut pen_progress = match builder.handle_event(event.clone(), now, constraints) {
                     ShapeBuilderProgress::InProgress => PenProgress::InProgress,
                     ShapeBuilderProgress::EmitContinue(shapes) => {
@@ -197,7 +271,7 @@ impl DrawOnDocBehaviour for Shaper {
 
         match &self.state {
             ShaperState::Idle => None,
-            ShaperState::BuildShape { builder } => {
+            ShaperState::BuildShape { builder, .. } => {
                 builder.bounds(&style, engine_view.camera.total_zoom())
             }
         }
@@ -216,7 +290,7 @@ impl DrawOnDocBehaviour for Shaper {
 
         match &self.state {
             ShaperState::Idle => {}
-            ShaperState::BuildShape { builder } => {
+            ShaperState::BuildShape { builder, .. } => {
                 builder.draw_styled(cx, &style, engine_view.camera.total_zoom())
             }
         }
@@ -47,6 +47,18 @@ pub enum ShortcutAction {
         #[serde(rename = "mode")]
         mode: ShortcutMode,
     },
+    /// Temporarily pan the canvas by dragging.
+    #[serde(rename = "pan")]
+    Pan,
+    /// Undo the latest changes.
+    #[serde(rename = "undo")]
+    Undo,
+    /// Redo the latest undone changes.
+    #[serde(rename = "redo")]
+    Redo,
+    /// Apply the next saved preset of the current pen.
+    #[serde(rename = "cycle_preset")]
+    CyclePreset,
 }
 
 /// The registered shortcut actions for the given shortcut keys.
@@ -0,0 +1,87 @@
+// Imports
+use crate::engine::{EngineTask, EngineTaskSender};
+use crate::tasks::{PeriodicTaskHandle, PeriodicTaskResult};
+use rnote_compose::penpath::Element;
+use rnote_compose::style::airbrush::AirbrushOptions;
+use std::time::{Duration, Instant};
+
+/// Emits the elements of airbrush strokes in constant intervals while the pen is held down.
+///
+/// Every element sprays the same amount of particles, so the spray accumulates over time,
+/// also while the pen is held still and no pen events arrive.
+#[derive(Debug)]
+pub struct SprayEmitter {
+    /// The last emitted element.
+    last: Element,
+    /// The element of the pointer, towards which the next elements are emitted.
+    pointer: Element,
+    last_emission: Instant,
+    /// Requests emitting on every interval, quits when the emitter is dropped.
+    _tick_task_handle: PeriodicTaskHandle,
+}
+
+impl SprayEmitter {
+    /// The maximum number of elements that are emitted at once, when the emission was stalled.
+    const EMISSION_STEPS_MAX: usize = 30;
+
+    /// A new emitter starting at the element. Emitting is requested through the engine tasks.
+    pub fn new(start: Element, now: Instant, tasks_tx: EngineTaskSender) -> Self {
+        let tick_task = move || -> PeriodicTaskResult {
+            if let Err(e) = tasks_tx.unbounded_send(EngineTask::EmitAirbrushSpray) {
+                log::error!(
+                    "Failed to send EmitAirbrushSpray task from spray emitter tick task, {e:?}"
+                );
+                PeriodicTaskResult::Quit
+            } else {
+                PeriodicTaskResult::Continue
+            }
+        };
+
+        Self {
+            last: start,
+            pointer: start,
+            last_emission: now,
+            _tick_task_handle: PeriodicTaskHandle::new(
+                tick_task,
+                Duration::from_secs_f64(AirbrushOptions::EMISSION_INTERVAL),
+            ),
+        }
+    }
+
+    /// Move the pointer and emit the elements for the intervals that have elapsed since the last emission.
+    pub fn emit(&mut self, pointer: Element, now: Instant) -> Vec<Element> {
+        self.pointer = pointer;
+        self.tick(now)
+    }
+
+    /// Emit the elements for the intervals that have elapsed since the last emission,
+    /// interpolated from the last emitted element to the pointer.
+    pub fn tick(&mut self, now: Instant) -> Vec<Element> {
+        let elapsed = now
+            .saturating_duration_since(self.last_emission)
+            .as_secs_f64();
+        let n_steps = (elapsed / AirbrushOptions::EMISSION_INTERVAL).floor() as usize;
+        if n_steps == 0 {
+            return vec![];
+        }
+        if n_steps > Self::EMISSION_STEPS_MAX {
+            self.last_emission = now;
+        } else {
+            self.last_emission +=
+                Duration::from_secs_f64(n_steps as f64 * AirbrushOptions::EMISSION_INTERVAL);
+        }
+        let n_steps = n_steps.min(Self::EMISSION_STEPS_MAX);
+        let start = self.last;
+        self.last = self.pointer;
+
+        (1..=n_steps)
+            .map(|i| {
+                let t = i as f64 / n_steps as f64;
+                Element::new(
+                    start.pos.lerp(&self.pointer.pos, t),
+                    start.pressure + (self.pointer.pressure - start.pressure) * t,
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,73 @@
+// Imports
+use rnote_compose::penpath::Element;
+
+/// Stabilizes the pen input with a "lazy brush".
+///
+/// The brush is pulled behind the pointer on a rope and only moves once the rope is taut,
+/// which irons out jitter and small deviations, so that precise curves can be inked.
+#[derive(Debug, Clone)]
+pub struct Stabilizer {
+    brush: Element,
+    pointer: na::Vector2<f64>,
+}
+
+impl Stabilizer {
+    /// The maximum number of elements that are emitted when catching up with the pointer.
+    const CATCH_UP_STEPS_MAX: usize = 16;
+
+    /// A new stabilizer, with the brush and pointer at the start element.
+    pub fn new(start: Element) -> Self {
+        Self {
+            brush: start,
+            pointer: start.pos,
+        }
+    }
+
+    /// The brush and pointer positions, when the rope between them has a length.
+    pub fn rope(&self) -> Option<(na::Vector2<f64>, na::Vector2<f64>)> {
+        (self.brush.pos != self.pointer).then_some((self.brush.pos, self.pointer))
+    }
+
+    /// Move the pointer, pulling the brush behind it.
+    ///
+    /// Returns the new brush element when the brush has moved, None while the rope is slack.
+    pub fn pull(&mut self, pointer: Element, rope_length: f64) -> Option<Element> {
+        self.pointer = pointer.pos;
+        self.brush.pressure = pointer.pressure;
+
+        let offset = pointer.pos - self.brush.pos;
+        let dist = offset.magnitude();
+        if dist <= rope_length {
+            return None;
+        }
+        self.brush.pos += offset * ((dist - rope_length) / dist);
+        Some(self.brush)
+    }
+
+    /// Let the brush catch up with the pointer, used when the pen is lifted.
+    ///
+    /// Returns the elements along the way, excluding the pointer element itself.
+    pub fn catch_up(&mut self, pointer: Element, rope_length: f64) -> Vec<Element> {
+        let start = self.brush;
+        let dist = (pointer.pos - start.pos).magnitude();
+        self.brush = pointer;
+        self.pointer = pointer.pos;
+
+        if dist <= 0.0 || rope_length <= 0.0 {
+            return vec![];
+        }
+        // Quarter rope lengths are fine enough for the stroke to follow the rope direction
+        let n_steps =
+            ((dist / (rope_length * 0.25)).ceil() as usize).clamp(1, Self::CATCH_UP_STEPS_MAX);
+
+        (1..n_steps)
+            .map(|i| {
+                let t = i as f64 / n_steps as f64;
+                Element::new(
+                    start.pos.lerp(&pointer.pos, t),
+                    start.pressure + (pointer.pressure - start.pressure) * t,
+                )
+            })
+            .collect()
+    }
+}
@@ -2,15 +2,23 @@
 use super::penbehaviour::{PenBehaviour, PenProgress};
 use super::pensconfig::toolsconfig::ToolStyle;
 use super::PenStyle;
+use super::{eyedropper, floodfill};
+use crate::document::Document;
 use crate::engine::{EngineView, EngineViewMut};
-use crate::store::StrokeKey;
+use crate::store::chrono_comp::StrokeLayer;
+use crate::store::{StrokeKey, StrokeStore};
+use crate::strokes::{ShapeStroke, Stroke, StrokeNodeKind};
 use crate::{Camera, DrawOnDocBehaviour, WidgetFlags};
+use futures::channel::oneshot;
 use once_cell::sync::Lazy;
-use p2d::bounding_volume::Aabb;
-use piet::RenderContext;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use piet::{RenderContext, Text, TextLayout, TextLayoutBuilder};
 use rnote_compose::color;
 use rnote_compose::helpers::{AabbHelpers, Vector2Helpers};
-use rnote_compose::penevents::PenEvent;
+use rnote_compose::penevents::{ModifierKey, PenEvent};
+use rnote_compose::shapes::{Polygon, Shape};
+use rnote_compose::style::smooth::SmoothOptions;
+use rnote_compose::Style;
 use std::time::Instant;
 
 #[derive(Clone, Debug)]
@@ -256,6 +264,477 @@ impl DrawOnDocBehaviour for ZoomTool {
     }
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct EditNodesTool {
+    /// The stroke whose nodes are edited.
+    pub target: Option<StrokeKey>,
+    /// The index of the node that is currently dragged.
+    dragged: Option<usize>,
+    /// Whether the target was modified since the pen went down.
+    modified: bool,
+}
+
+static EDITNODESTOOL_DARK_COLOR: Lazy<piet::Color> =
+    Lazy::new(|| color::GNOME_DARKS[3].with_alpha(0.941));
+static EDITNODESTOOL_LIGHT_COLOR: Lazy<piet::Color> =
+    Lazy::new(|| color::GNOME_BRIGHTS[1].with_alpha(0.941));
+
+impl EditNodesTool {
+    const ANCHOR_RADIUS: f64 = 5.0;
+    const CONTROL_RADIUS: f64 = 3.5;
+    const HIT_RADIUS: f64 = 8.0;
+    const OUTLINE_WIDTH: f64 = 1.5;
+    const HANDLE_LINE_WIDTH: f64 = 1.0;
+    const DRAGGED_COLOR: piet::Color = color::GNOME_BLUES[3];
+
+    /// The index of the target node nearest to the position, if it is inside the hit radius.
+    fn node_at(
+        &self,
+        store: &StrokeStore,
+        pos: na::Vector2<f64>,
+        total_zoom: f64,
+    ) -> Option<usize> {
+        let stroke = store.get_stroke_ref(self.target?)?;
+
+        stroke
+            .nodes()
+            .into_iter()
+            .enumerate()
+            .map(|(i, node)| (i, (node.pos - pos).norm()))
+            .filter(|(_, dist)| *dist <= Self::HIT_RADIUS / total_zoom)
+            .min_by(|(_, first), (_, second)| first.total_cmp(second))
+            .map(|(i, _)| i)
+    }
+
+    /// The topmost stroke at the position that has editable nodes.
+    fn stroke_at(engine_view: &EngineViewMut, pos: na::Vector2<f64>) -> Option<StrokeKey> {
+        engine_view
+            .store
+            .stroke_hitboxes_contain_coord(engine_view.camera.viewport(), pos)
+            .into_iter()
+            .rev()
+            .find(|&key| {
+                engine_view
+                    .store
+                    .get_stroke_ref(key)
+                    .map_or(false, |stroke| !stroke.nodes().is_empty())
+            })
+    }
+
+    /// Modify the target stroke, then update its geometry and rendering.
+    ///
+    /// Instances are unlinked from their master, because they would otherwise lose the modification.
+    fn modify_target<T>(
+        &mut self,
+        engine_view: &mut EngineViewMut,
+        modify: impl FnOnce(&mut Stroke) -> T,
+    ) -> Option<T> {
+        let key = self.target?;
+        if engine_view.store.instance_master(key).is_some() {
+            engine_view.store.unlink_instances(&[key]);
+        }
+        let res = modify(engine_view.store.get_stroke_mut(key)?);
+
+        engine_view.store.update_geometry_for_stroke(key);
+        engine_view.store.regenerate_rendering_for_stroke(
+            key,
+            engine_view.camera.viewport(),
+            engine_view.camera.image_scale(),
+        );
+        self.modified = true;
+        Some(res)
+    }
+}
+
+impl DrawOnDocBehaviour for EditNodesTool {
+    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
+        let node_positions = engine_view
+            .store
+            .get_stroke_ref(self.target?)?
+            .nodes()
+            .into_iter()
+            .map(|node| node.pos.into())
+            .collect::<Vec<na::Point2<f64>>>();
+        if node_positions.is_empty() {
+            return None;
+        }
+
+        Some(Aabb::from_points(&node_positions).loosened(
+            (Self::ANCHOR_RADIUS + Self::OUTLINE_WIDTH) / engine_view.camera.total_zoom(),
+        ))
+    }
+
+    fn draw_on_doc(
+        &self,
+        cx: &mut piet_cairo::CairoRenderContext,
+        engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        let Some(stroke) = self
+            .target
+            .and_then(|key| engine_view.store.get_stroke_ref(key))
+        else {
+            return Ok(());
+        };
+        cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        let total_zoom = engine_view.camera.total_zoom();
+        let nodes = stroke.nodes();
+
+        // Handle lines from the control points to their anchors
+        for node in nodes.iter() {
+            if let StrokeNodeKind::Control { anchor } = node.kind {
+                if let Some(anchor) = nodes.get(anchor) {
+                    cx.stroke(
+                        kurbo::Line::new(anchor.pos.to_kurbo_point(), node.pos.to_kurbo_point()),
+                        &*EDITNODESTOOL_DARK_COLOR,
+                        Self::HANDLE_LINE_WIDTH / total_zoom,
+                    );
+                }
+            }
+        }
+
+        for (i, node) in nodes.iter().enumerate() {
+            let radius = match node.kind {
+                StrokeNodeKind::Anchor => Self::ANCHOR_RADIUS,
+                StrokeNodeKind::Control { .. } => Self::CONTROL_RADIUS,
+            };
+            let circle = kurbo::Circle::new(node.pos.to_kurbo_point(), radius / total_zoom);
+            let fill_color = if self.dragged == Some(i) {
+                Self::DRAGGED_COLOR
+            } else {
+                *EDITNODESTOOL_LIGHT_COLOR
+            };
+
+            cx.fill(circle, &fill_color);
+            cx.stroke(
+                circle,
+                &*EDITNODESTOOL_DARK_COLOR,
+                Self::OUTLINE_WIDTH / total_zoom,
+            );
+        }
+
+        cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MeasureTool {
+    /// The points of the measured polyline.
+    pub points: Vec<na::Vector2<f64>>,
+}
+
+static MEASURETOOL_LINE_COLOR: Lazy<piet::Color> =
+    Lazy::new(|| color::GNOME_BLUES[4].with_alpha(0.941));
+static MEASURETOOL_LABEL_BG_COLOR: Lazy<piet::Color> =
+    Lazy::new(|| color::GNOME_DARKS[3].with_alpha(0.878));
+static MEASURETOOL_LABEL_TEXT_COLOR: Lazy<piet::Color> =
+    Lazy::new(|| color::GNOME_BRIGHTS[1].with_alpha(1.0));
+
+impl MeasureTool {
+    const LINE_WIDTH: f64 = 1.5;
+    const CLOSING_LINE_DASH_PATTERN: [f64; 2] = [6.0, 4.0];
+    const POINT_RADIUS: f64 = 3.0;
+    const LABEL_FONT_SIZE: f64 = 12.0;
+    const LABEL_PADDING: f64 = 4.0;
+    /// The extents that are reserved for the labels around the points, in surface coordinates.
+    const LABEL_EXTENTS: na::Vector2<f64> = na::vector![160.0, 48.0];
+    /// Segments shorter than this (in surface coordinates) don't get a label.
+    const LABEL_SEGMENT_LEN_MIN: f64 = 4.0;
+
+    /// The area of the polygon that is enclosed by the points, when there are at least three.
+    fn area(&self) -> Option<f64> {
+        if self.points.len() < 3 {
+            return None;
+        }
+        let twice_area = self
+            .points
+            .iter()
+            .zip(self.points.iter().cycle().skip(1))
+            .map(|(first, second)| first[0] * second[1] - second[0] * first[1])
+            .sum::<f64>();
+
+        Some(twice_area.abs() * 0.5)
+    }
+
+    /// The total length of the polyline.
+    fn length(&self) -> f64 {
+        self.points
+            .windows(2)
+            .map(|segment| (segment[1] - segment[0]).norm())
+            .sum()
+    }
+
+    /// The angle of the segment against the horizontal in degrees, counter-clockwise as displayed.
+    fn segment_angle(start: na::Vector2<f64>, end: na::Vector2<f64>) -> f64 {
+        let angle = (-(end[1] - start[1])).atan2(end[0] - start[0]).to_degrees();
+        if angle < 0.0 {
+            angle + 360.0
+        } else {
+            angle
+        }
+    }
+
+    fn format_length(doc: &Document, length: f64) -> String {
+        format!(
+            "{:.2} {}",
+            doc.measured_length(length),
+            doc.measure_unit.abbreviation()
+        )
+    }
+
+    fn format_area(doc: &Document, area: f64) -> String {
+        format!(
+            "{:.2} {}²",
+            doc.measured_length(1.0).powi(2) * area,
+            doc.measure_unit.abbreviation()
+        )
+    }
+
+    /// Draw a label centered on the position, with a size independent of the zoom.
+    fn draw_label(
+        cx: &mut piet_cairo::CairoRenderContext,
+        text: String,
+        pos: na::Vector2<f64>,
+        total_zoom: f64,
+    ) -> anyhow::Result<()> {
+        let text_layout = cx
+            .text()
+            .new_text_layout(text)
+            .text_color(*MEASURETOOL_LABEL_TEXT_COLOR)
+            .alignment(piet::TextAlignment::Center)
+            .font(piet::FontFamily::SANS_SERIF, Self::LABEL_FONT_SIZE)
+            .build()
+            .map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        let size = text_layout.size();
+        let label_rect = kurbo::Rect::new(
+            -size.width * 0.5 - Self::LABEL_PADDING,
+            -size.height * 0.5 - Self::LABEL_PADDING,
+            size.width * 0.5 + Self::LABEL_PADDING,
+            size.height * 0.5 + Self::LABEL_PADDING,
+        );
+
+        cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        cx.transform(
+            kurbo::Affine::translate(pos.to_kurbo_vec()) * kurbo::Affine::scale(1.0 / total_zoom),
+        );
+        cx.fill(
+            label_rect.to_rounded_rect(Self::LABEL_PADDING),
+            &*MEASURETOOL_LABEL_BG_COLOR,
+        );
+        cx.draw_text(
+            &text_layout,
+            kurbo::Point::new(-size.width * 0.5, -size.height * 0.5),
+        );
+        cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        Ok(())
+    }
+}
+
+impl DrawOnDocBehaviour for MeasureTool {
+    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
+        if self.points.is_empty() {
+            return None;
+        }
+        let points = self
+            .points
+            .iter()
+            .map(|&pos| pos.into())
+            .collect::<Vec<na::Point2<f64>>>();
+
+        Some(
+            Aabb::from_points(&points)
+                .extend_by(Self::LABEL_EXTENTS / engine_view.camera.total_zoom()),
+        )
+    }
+
+    fn draw_on_doc(
+        &self,
+        cx: &mut piet_cairo::CairoRenderContext,
+        engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        if self.points.is_empty() {
+            return Ok(());
+        }
+        cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        let total_zoom = engine_view.camera.total_zoom();
+        let doc = engine_view.doc;
+
+        let mut polyline = kurbo::BezPath::new();
+        polyline.move_to(self.points[0].to_kurbo_point());
+        for point in self.points.iter().skip(1) {
+            polyline.line_to(point.to_kurbo_point());
+        }
+        cx.stroke(
+            polyline,
+            &*MEASURETOOL_LINE_COLOR,
+            Self::LINE_WIDTH / total_zoom,
+        );
+
+        let area = self.area();
+        if area.is_some() {
+            let closing_line = kurbo::Line::new(
+                self.points[self.points.len() - 1].to_kurbo_point(),
+                self.points[0].to_kurbo_point(),
+            );
+            cx.stroke_styled(
+                closing_line,
+                &*MEASURETOOL_LINE_COLOR,
+                Self::LINE_WIDTH / total_zoom,
+                &piet::StrokeStyle::new().dash_pattern(&Self::CLOSING_LINE_DASH_PATTERN),
+            );
+        }
+
+        for point in self.points.iter() {
+            cx.fill(
+                kurbo::Circle::new(point.to_kurbo_point(), Self::POINT_RADIUS / total_zoom),
+                &*MEASURETOOL_LINE_COLOR,
+            );
+        }
+
+        // The length and angle of each segment
+        for segment in self.points.windows(2) {
+            let length = (segment[1] - segment[0]).norm();
+            if length * total_zoom < Self::LABEL_SEGMENT_LEN_MIN {
+                continue;
+            }
+            Self::draw_label(
+                cx,
+                format!(
+                    "{}  {:.1}°",
+                    Self::format_length(doc, length),
+                    Self::segment_angle(segment[0], segment[1])
+                ),
+                (segment[0] + segment[1]) * 0.5,
+                total_zoom,
+            )?;
+        }
+
+        // The angles between adjacent segments
+        for vertex in self.points.windows(3) {
+            let (first, second) = (vertex[0] - vertex[1], vertex[2] - vertex[1]);
+            if first.norm() * total_zoom < Self::LABEL_SEGMENT_LEN_MIN
+                || second.norm() * total_zoom < Self::LABEL_SEGMENT_LEN_MIN
+            {
+                continue;
+            }
+            Self::draw_label(
+                cx,
+                format!("{:.1}°", first.angle(&second).to_degrees()),
+                vertex[1],
+                total_zoom,
+            )?;
+        }
+
+        if let Some(area) = area {
+            let centroid = self.points.iter().sum::<na::Vector2<f64>>() / self.points.len() as f64;
+            Self::draw_label(
+                cx,
+                format!(
+                    "{}\n{}",
+                    Self::format_length(doc, self.length()),
+                    Self::format_area(doc, area)
+                ),
+                centroid,
+                total_zoom,
+            )?;
+        }
+
+        cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SelectTextTool {
+    /// The start position of the selection rectangle.
+    pub start: na::Vector2<f64>,
+    /// The current position of the selection rectangle.
+    pub current: na::Vector2<f64>,
+    /// The selected text of the text layers of images.
+    pub text: String,
+    /// The bounds of the selected words.
+    pub word_bounds: Vec<Aabb>,
+}
+
+impl Default for SelectTextTool {
+    fn default() -> Self {
+        Self {
+            start: na::Vector2::zeros(),
+            current: na::Vector2::zeros(),
+            text: String::default(),
+            word_bounds: vec![],
+        }
+    }
+}
+
+static SELECTTEXTTOOL_FILL_COLOR: Lazy<piet::Color> =
+    Lazy::new(|| color::GNOME_BRIGHTS[2].with_alpha(0.090));
+static SELECTTEXTTOOL_OUTLINE_COLOR: Lazy<piet::Color> =
+    Lazy::new(|| color::GNOME_BLUES[3].with_alpha(0.941));
+static SELECTTEXTTOOL_HIGHLIGHT_COLOR: Lazy<piet::Color> =
+    Lazy::new(|| color::GNOME_BLUES[2].with_alpha(0.314));
+
+impl SelectTextTool {
+    const OUTLINE_WIDTH: f64 = 1.5;
+
+    fn selection_rect(&self) -> Aabb {
+        Aabb::new_positive(self.start.into(), self.current.into())
+    }
+
+    fn update_selection(&mut self, store: &StrokeStore) {
+        (self.text, self.word_bounds) = store.text_layer_text_in_bounds(self.selection_rect());
+    }
+
+    fn clear(&mut self) {
+        self.text.clear();
+        self.word_bounds.clear();
+    }
+
+    fn draw_selection_rect(
+        &self,
+        cx: &mut piet_cairo::CairoRenderContext,
+        engine_view: &EngineView,
+    ) {
+        let selection_rect = self.selection_rect().to_kurbo_rect();
+
+        cx.fill(selection_rect, &*SELECTTEXTTOOL_FILL_COLOR);
+        cx.stroke(
+            selection_rect,
+            &*SELECTTEXTTOOL_OUTLINE_COLOR,
+            Self::OUTLINE_WIDTH / engine_view.camera.total_zoom(),
+        );
+    }
+}
+
+impl DrawOnDocBehaviour for SelectTextTool {
+    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
+        let selection_rect = self
+            .selection_rect()
+            .loosened(Self::OUTLINE_WIDTH / engine_view.camera.total_zoom());
+
+        Some(
+            self.word_bounds
+                .iter()
+                .fold(selection_rect, |acc, bounds| acc.merged(bounds)),
+        )
+    }
+
+    fn draw_on_doc(
+        &self,
+        cx: &mut piet_cairo::CairoRenderContext,
+        engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+        for bounds in self.word_bounds.iter() {
+            cx.fill(bounds.to_kurbo_rect(), &*SELECTTEXTTOOL_HIGHLIGHT_COLOR);
+        }
+
+        cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum ToolsState {
     Idle,
@@ -273,6 +752,9 @@ pub struct Tools {
     pub verticalspace_tool: VerticalSpaceTool,
     pub offsetcamera_tool: OffsetCameraTool,
     pub zoom_tool: ZoomTool,
+    pub editnodes_tool: EditNodesTool,
+    pub measure_tool: MeasureTool,
+    pub selecttext_tool: SelectTextTool,
     state: ToolsState,
 }
 
@@ -282,6 +764,9 @@ impl PenBehaviour for Tools {
     }
 
     fn deinit(&mut self) -> WidgetFlags {
+        self.editnodes_tool.target = None;
+        self.measure_tool.points.clear();
+        self.selecttext_tool.clear();
         WidgetFlags::default()
     }
 
@@ -293,6 +778,30 @@ impl PenBehaviour for Tools {
         WidgetFlags::default()
     }
 
+    fn fetch_clipboard_content(
+        &self,
+        engine_view: &EngineView,
+    ) -> oneshot::Receiver<anyhow::Result<(Vec<(Vec<u8>, String)>, WidgetFlags)>> {
+        let (sender, receiver) =
+            oneshot::channel::<anyhow::Result<(Vec<(Vec<u8>, String)>, WidgetFlags)>>();
+        let mut clipboard_content = Vec::with_capacity(1);
+
+        // The text that is selected in the text layers of images
+        if engine_view.pens_config.tools_config.style == ToolStyle::SelectText
+            && !self.selecttext_tool.text.is_empty()
+        {
+            clipboard_content.push((
+                self.selecttext_tool.text.clone().into_bytes(),
+                String::from("text/plain;charset=utf-8"),
+            ));
+        }
+
+        if let Err(e) = sender.send(Ok((clipboard_content, WidgetFlags::default()))) {
+            log::error!("sending fetched tools clipboard content failed, Err: {e:?}");
+        }
+        receiver
+    }
+
     fn handle_event(
         &mut self,
         event: PenEvent,
@@ -302,7 +811,13 @@ impl PenBehaviour for Tools {
         let mut widget_flags = WidgetFlags::default();
 
         let pen_progress = match (&mut self.state, event) {
-            (ToolsState::Idle, PenEvent::Down { element, .. }) => {
+            (
+                ToolsState::Idle,
+                PenEvent::Down {
+                    element,
+                    modifier_keys,
+                },
+            ) => {
                 match engine_view.pens_config.tools_config.style {
                     ToolStyle::VerticalSpace => {
                         self.verticalspace_tool.start_pos_y = element.pos[1];
@@ -327,6 +842,57 @@ impl PenBehaviour for Tools {
                             .transform_point(&element.pos.into())
                             .coords;
                     }
+                    ToolStyle::EditNodes => {
+                        // Ctrl + click removes a node, or adds one when clicking on the outline
+                        let tool = &mut self.editnodes_tool;
+                        let total_zoom = engine_view.camera.total_zoom();
+                        let ctrl = modifier_keys.contains(&ModifierKey::KeyboardCtrl);
+                        tool.dragged = None;
+                        tool.modified = false;
+
+                        match tool.node_at(engine_view.store, element.pos, total_zoom) {
+                            Some(index) if ctrl => {
+                                tool.modify_target(engine_view, |stroke| stroke.remove_node(index));
+                            }
+                            Some(index) => {
+                                tool.dragged = Some(index);
+                            }
+                            None => {
+                                let hit = EditNodesTool::stroke_at(engine_view, element.pos);
+                                if ctrl && hit.is_some() && hit == tool.target {
+                                    tool.dragged = tool
+                                        .modify_target(engine_view, |stroke| {
+                                            stroke.insert_node(element.pos)
+                                        })
+                                        .flatten();
+                                } else {
+                                    tool.target = hit;
+                                }
+                            }
+                        }
+                    }
+                    ToolStyle::Fill => {
+                        widget_flags.merge(Self::fill_region(engine_view, element.pos));
+                    }
+                    ToolStyle::Eyedropper => {
+                        widget_flags.merge(Self::pick_color(engine_view, element.pos));
+                    }
+                    ToolStyle::Measure => {
+                        // Ctrl + click continues the current measurement with another segment
+                        let points = &mut self.measure_tool.points;
+                        if !(modifier_keys.contains(&ModifierKey::KeyboardCtrl)
+                            && !points.is_empty())
+                        {
+                            points.clear();
+                            points.push(element.pos);
+                        }
+                        points.push(element.pos);
+                    }
+                    ToolStyle::SelectText => {
+                        self.selecttext_tool.start = element.pos;
+                        self.selecttext_tool.current = element.pos;
+                        self.selecttext_tool.clear();
+                    }
                 }
                 widget_flags.merge(
                     engine_view
@@ -409,6 +975,26 @@ impl PenBehaviour for Tools {
                         }
                         self.zoom_tool.current_surface_coord = new_surface_coord;
                     }
+                    ToolStyle::EditNodes => {
+                        if let Some(index) = self.editnodes_tool.dragged {
+                            self.editnodes_tool.modify_target(engine_view, |stroke| {
+                                stroke.move_node(index, element.pos)
+                            });
+                        }
+                    }
+                    ToolStyle::Fill => {}
+                    ToolStyle::Eyedropper => {
+                        widget_flags.merge(Self::pick_color(engine_view, element.pos));
+                    }
+                    ToolStyle::Measure => {
+                        if let Some(last) = self.measure_tool.points.last_mut() {
+                            *last = element.pos;
+                        }
+                    }
+                    ToolStyle::SelectText => {
+                        self.selecttext_tool.current = element.pos;
+                        self.selecttext_tool.update_selection(engine_view.store);
+                    }
                 }
 
                 PenProgress::InProgress
@@ -423,7 +1009,18 @@ impl PenBehaviour for Tools {
                         widget_flags.merge(engine_view.store.record(Instant::now()));
                         widget_flags.store_modified = true;
                     }
-                    ToolStyle::OffsetCamera | ToolStyle::Zoom => {}
+                    ToolStyle::EditNodes => {
+                        if self.editnodes_tool.modified {
+                            widget_flags.merge(engine_view.store.record(Instant::now()));
+                            widget_flags.store_modified = true;
+                        }
+                    }
+                    ToolStyle::OffsetCamera
+                    | ToolStyle::Zoom
+                    | ToolStyle::Fill
+                    | ToolStyle::Eyedropper
+                    | ToolStyle::Measure
+                    | ToolStyle::SelectText => {}
                 }
 
                 widget_flags.merge(
@@ -445,6 +1042,8 @@ impl PenBehaviour for Tools {
             (ToolsState::Active, PenEvent::Proximity { .. }) => PenProgress::InProgress,
             (ToolsState::Active, PenEvent::KeyPressed { .. }) => PenProgress::InProgress,
             (ToolsState::Active, PenEvent::Cancel) => {
+                self.measure_tool.points.clear();
+                self.selecttext_tool.clear();
                 widget_flags.merge(
                     engine_view
                         .doc
@@ -475,8 +1074,20 @@ impl DrawOnDocBehaviour for Tools {
                 ToolStyle::VerticalSpace => self.verticalspace_tool.bounds_on_doc(engine_view),
                 ToolStyle::OffsetCamera => self.offsetcamera_tool.bounds_on_doc(engine_view),
                 ToolStyle::Zoom => self.zoom_tool.bounds_on_doc(engine_view),
+                ToolStyle::EditNodes => self.editnodes_tool.bounds_on_doc(engine_view),
+                ToolStyle::Measure => self.measure_tool.bounds_on_doc(engine_view),
+                ToolStyle::SelectText => self.selecttext_tool.bounds_on_doc(engine_view),
+                ToolStyle::Fill | ToolStyle::Eyedropper => None,
+            },
+            // The nodes of the edited stroke, the last measurement and the selected text stay visible while idle
+            ToolsState::Idle => match engine_view.pens_config.tools_config.style {
+                ToolStyle::EditNodes => self.editnodes_tool.bounds_on_doc(engine_view),
+                ToolStyle::Measure => self.measure_tool.bounds_on_doc(engine_view),
+                ToolStyle::SelectText if !self.selecttext_tool.word_bounds.is_empty() => {
+                    self.selecttext_tool.bounds_on_doc(engine_view)
+                }
+                _ => None,
             },
-            ToolsState::Idle => None,
         }
     }
 
@@ -497,6 +1108,19 @@ impl DrawOnDocBehaviour for Tools {
             ToolStyle::Zoom => {
                 self.zoom_tool.draw_on_doc(cx, engine_view)?;
             }
+            ToolStyle::EditNodes => {
+                self.editnodes_tool.draw_on_doc(cx, engine_view)?;
+            }
+            ToolStyle::Measure => {
+                self.measure_tool.draw_on_doc(cx, engine_view)?;
+            }
+            ToolStyle::SelectText => {
+                self.selecttext_tool.draw_on_doc(cx, engine_view)?;
+                if let ToolsState::Active = self.state {
+                    self.selecttext_tool.draw_selection_rect(cx, engine_view);
+                }
+            }
+            ToolStyle::Fill | ToolStyle::Eyedropper => {}
         }
 
         cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
@@ -518,7 +1142,87 @@ impl Tools {
                 self.zoom_tool.start_surface_coord = na::Vector2::zeros();
                 self.zoom_tool.current_surface_coord = na::Vector2::zeros();
             }
+            ToolStyle::EditNodes => {
+                self.editnodes_tool.dragged = None;
+                self.editnodes_tool.modified = false;
+            }
+            ToolStyle::Fill
+            | ToolStyle::Eyedropper
+            | ToolStyle::Measure
+            | ToolStyle::SelectText => {}
         }
         self.state = ToolsState::Idle;
     }
+
+    /// Fill the region around the position that is enclosed by strokes with the fill color of the shaper.
+    ///
+    /// The fill is inserted below the strokes, so that it does not cover the bounding strokes.
+    fn fill_region(engine_view: &mut EngineViewMut, pos: na::Vector2<f64>) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let total_zoom = engine_view.camera.total_zoom();
+        let tools_config = &engine_view.pens_config.tools_config;
+        let shaper_options = &engine_view.pens_config.shaper_config.smooth_options;
+
+        let points = match floodfill::trace_region(
+            engine_view.store,
+            pos,
+            engine_view.camera.viewport(),
+            engine_view.camera.image_scale(),
+            tools_config.fill_tolerance,
+            tools_config.fill_gap_closing / total_zoom,
+        ) {
+            Ok(Some(points)) => points,
+            Ok(None) => return widget_flags,
+            Err(e) => {
+                log::error!("tracing the fill region failed, Err: {e:?}");
+                return widget_flags;
+            }
+        };
+        let style = Style::Smooth(SmoothOptions {
+            stroke_color: None,
+            fill_color: shaper_options.fill_color.or(shaper_options.stroke_color),
+            ..SmoothOptions::default()
+        });
+        let stroke = Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Polygon(Polygon::new(points)),
+            style,
+        ));
+
+        let key = engine_view
+            .store
+            .insert_stroke(stroke, Some(StrokeLayer::Highlighter));
+        engine_view.store.regenerate_rendering_for_stroke(
+            key,
+            engine_view.camera.viewport(),
+            engine_view.camera.image_scale(),
+        );
+
+        widget_flags.merge(engine_view.store.record(Instant::now()));
+        widget_flags.store_modified = true;
+        widget_flags
+    }
+
+    /// Pick the color of the document at the position and set it as the stroke color of the pens.
+    fn pick_color(engine_view: &mut EngineViewMut, pos: na::Vector2<f64>) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        // Sample an area of about two by two pixels on the screen
+        let half_extents = 1.0 / engine_view.camera.total_zoom();
+
+        match eyedropper::sample_color(
+            engine_view.store,
+            engine_view.doc,
+            pos,
+            half_extents,
+            engine_view.camera.image_scale(),
+        ) {
+            Ok(color) => {
+                engine_view.pens_config.set_stroke_color(color);
+                widget_flags.refresh_ui = true;
+            }
+            Err(e) => {
+                log::error!("sampling the color at position {pos:?} failed, Err: {e:?}");
+            }
+        }
+        widget_flags
+    }
 }
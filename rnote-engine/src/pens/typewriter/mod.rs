@@ -11,14 +11,13 @@ use crate::strokes::textstroke::{RangedTextAttribute, TextAttribute, TextStyle};
 use crate::strokes::{Stroke, TextStroke};
 use crate::{AudioPlayer, Camera, DrawOnDocBehaviour, WidgetFlags};
 use futures::channel::oneshot;
-use once_cell::sync::Lazy;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use piet::RenderContext;
 use rnote_compose::helpers::{AabbHelpers, Vector2Helpers};
 use rnote_compose::penevents::{KeyboardKey, PenEvent, PenState};
 use rnote_compose::shapes::ShapeBehaviour;
 use rnote_compose::style::indicators;
-use rnote_compose::{color, Transform};
+use rnote_compose::Transform;
 use std::ops::Range;
 use std::time::{Duration, Instant};
 use unicode_segmentation::GraphemeCursor;
@@ -56,11 +55,27 @@ pub(super) enum TypewriterState {
     },
 }
 
+/// The preedit (composition) text of an input method, which is inserted inline while composing.
+#[derive(Debug, Clone)]
+struct Preedit {
+    /// The key of the text stroke the preedit text is inserted into.
+    stroke_key: StrokeKey,
+    /// The byte range of the preedit text in the text stroke.
+    range: Range<usize>,
+    /// The cursor position, relative to the start of the preedit text.
+    cursor: usize,
+    /// Emphasized ranges relative to the start of the preedit text, e.g. the currently converted segment.
+    emphasized_ranges: Vec<Range<usize>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Typewriter {
     state: TypewriterState,
     blink_task_handle: Option<crate::tasks::PeriodicTaskHandle>,
     cursor_visible: bool,
+    /// The misspelled ranges of the text stroke with the key, underlined while it is modified.
+    misspelled_ranges: Option<(StrokeKey, Vec<Range<usize>>)>,
+    preedit: Option<Preedit>,
 }
 
 impl Default for Typewriter {
@@ -69,14 +84,14 @@ impl Default for Typewriter {
             state: TypewriterState::Idle,
             blink_task_handle: None,
             cursor_visible: true,
+            misspelled_ranges: None,
+            preedit: None,
         }
     }
 }
 
 impl DrawOnDocBehaviour for Typewriter {
     fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
-        let total_zoom = engine_view.camera.total_zoom();
-
         match &self.state {
             TypewriterState::Idle => None,
             TypewriterState::Start(pos) => Some(Aabb::new(
@@ -99,10 +114,9 @@ impl DrawOnDocBehaviour for Typewriter {
                         engine_view.pens_config.typewriter_config.text_width,
                         textstroke,
                     );
-                    let typewriter_bounds = text_rect.extend_by(
-                        Self::TRANSLATE_NODE_SIZE.maxs(&Self::ADJUST_TEXT_WIDTH_NODE_SIZE)
-                            / total_zoom,
-                    );
+                    let typewriter_bounds = text_rect.extend_by(na::Vector2::repeat(
+                        engine_view.camera.overlay_handle_size(),
+                    ));
 
                     Some(typewriter_bounds)
                 } else {
@@ -118,14 +132,16 @@ impl DrawOnDocBehaviour for Typewriter {
         engine_view: &EngineView,
     ) -> anyhow::Result<()> {
         cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
-        let total_zoom = engine_view.camera.total_zoom();
+        let overlay_zoom = engine_view.camera.overlay_zoom();
+        let text_outline_color =
+            piet::Color::from(engine_view.camera.overlay_config.selection_outline_color);
 
         let draw_text_outline = |cx: &mut piet_cairo::CairoRenderContext, bounds: Aabb| {
-            let stroke_width = Self::TEXT_OUTLINE_STROKE_WIDTH / total_zoom;
+            let stroke_width = Self::TEXT_OUTLINE_STROKE_WIDTH / overlay_zoom;
 
             cx.stroke(
                 bounds.tightened(stroke_width * 0.5).to_kurbo_rect(),
-                &*TEXT_OUTLINE_COLOR,
+                &text_outline_color,
                 stroke_width,
             );
         };
@@ -170,6 +186,21 @@ impl DrawOnDocBehaviour for Typewriter {
                     // Draw text outline
                     draw_text_outline(cx, text_bounds);
 
+                    // Underline misspelled words
+                    if let Some((_, ranges)) = self
+                        .misspelled_ranges
+                        .as_ref()
+                        .filter(|(key, _)| key == stroke_key)
+                    {
+                        textstroke.text_style.draw_misspelled_underlines(
+                            cx,
+                            textstroke.text.clone(),
+                            ranges,
+                            &textstroke.transform,
+                            engine_view.camera,
+                        );
+                    }
+
                     // Draw the text selection
                     if let ModifyState::Selecting {
                         selection_cursor, ..
@@ -185,12 +216,43 @@ impl DrawOnDocBehaviour for Typewriter {
                         );
                     }
 
+                    // Draw the preedit underlines and the cursor inside the preedit text
+                    let preedit = self
+                        .preedit
+                        .as_ref()
+                        .filter(|preedit| preedit.stroke_key == *stroke_key);
+                    if let Some(preedit) = preedit {
+                        textstroke.text_style.draw_preedit_underlines(
+                            cx,
+                            textstroke.text.clone(),
+                            preedit.range.clone(),
+                            &preedit
+                                .emphasized_ranges
+                                .iter()
+                                .map(|range| {
+                                    preedit.range.start + range.start
+                                        ..preedit.range.start + range.end
+                                })
+                                .collect::<Vec<Range<usize>>>(),
+                            &textstroke.transform,
+                            engine_view.camera,
+                        );
+                    }
+
                     // Draw the cursor
                     if self.cursor_visible {
+                        let cursor = match preedit {
+                            Some(preedit) => GraphemeCursor::new(
+                                preedit.range.start + preedit.cursor,
+                                textstroke.text.len(),
+                                true,
+                            ),
+                            None => cursor.clone(),
+                        };
                         textstroke.text_style.draw_cursor(
                             cx,
                             textstroke.text.clone(),
-                            cursor,
+                            &cursor,
                             &textstroke.transform,
                             engine_view.camera,
                         )?;
@@ -205,7 +267,10 @@ impl DrawOnDocBehaviour for Typewriter {
                     let adjust_text_width_node_state = match modify_state {
                         ModifyState::AdjustTextWidth { .. } => PenState::Down,
                         ModifyState::Hover(pos) => {
-                            if adjust_text_width_node_bounds.contains_local_point(&(*pos).into()) {
+                            if adjust_text_width_node_bounds
+                                .loosened(engine_view.camera.overlay_hit_tolerance())
+                                .contains_local_point(&(*pos).into())
+                            {
                                 PenState::Proximity
                             } else {
                                 PenState::Up
@@ -221,8 +286,8 @@ impl DrawOnDocBehaviour for Typewriter {
                             text_width,
                             engine_view.camera,
                         ),
-                        Self::ADJUST_TEXT_WIDTH_NODE_SIZE / total_zoom,
-                        total_zoom,
+                        na::Vector2::repeat(engine_view.camera.overlay_handle_size()),
+                        overlay_zoom,
                     );
 
                     // Draw the translate Node
@@ -232,7 +297,10 @@ impl DrawOnDocBehaviour for Typewriter {
                         let translate_node_state = match modify_state {
                             ModifyState::Translating { .. } => PenState::Down,
                             ModifyState::Hover(pos) => {
-                                if translate_node_bounds.contains_local_point(&(*pos).into()) {
+                                if translate_node_bounds
+                                    .loosened(engine_view.camera.overlay_hit_tolerance())
+                                    .contains_local_point(&(*pos).into())
+                                {
                                     PenState::Proximity
                                 } else {
                                     PenState::Up
@@ -244,7 +312,7 @@ impl DrawOnDocBehaviour for Typewriter {
                             cx,
                             translate_node_state,
                             translate_node_bounds,
-                            total_zoom,
+                            overlay_zoom,
                         );
                     }
                 }
@@ -353,7 +421,14 @@ impl PenBehaviour for Typewriter {
         now: Instant,
         engine_view: &mut EngineViewMut,
     ) -> (PenProgress, WidgetFlags) {
-        let (pen_progress, widget_flags) = match event {
+        // Input methods reset the preedit on all events except hovering, so it is removed before handling them.
+        // On text events the committed text then gets inserted regularly.
+        let mut widget_flags = WidgetFlags::default();
+        if !matches!(event, PenEvent::Proximity { .. }) {
+            widget_flags.merge(self.cancel_preedit(engine_view));
+        }
+
+        let (pen_progress, event_widget_flags) = match event {
             PenEvent::Down {
                 element,
                 modifier_keys,
@@ -374,6 +449,7 @@ impl PenBehaviour for Typewriter {
             PenEvent::Cancel => self.handle_pen_event_cancel(now, engine_view),
         };
 
+        widget_flags.merge(event_widget_flags);
         (pen_progress, widget_flags)
     }
 
@@ -531,19 +607,11 @@ fn update_cursors_for_textstroke(
     }
 }
 
-/// The outline color when drawing a text box outline
-static TEXT_OUTLINE_COLOR: Lazy<piet::Color> =
-    Lazy::new(|| color::GNOME_BRIGHTS[4].with_alpha(0.941));
-
 impl Typewriter {
-    // The size of the translate node, located in the upper left corner.
-    const TRANSLATE_NODE_SIZE: na::Vector2<f64> = na::vector![18.0, 18.0];
     /// The threshold magniuted where above it the translation is applied. In surface coordinates.
     const TRANSLATE_MAGNITUDE_THRESHOLD: f64 = 1.414;
     /// The threshold in x-axis direction where above it adjustments to the text width are applied. In surface coordinates.
     const ADJ_TEXT_WIDTH_THRESHOLD: f64 = 1.0;
-    /// The size of the translate node, located in the upper right corner.
-    const ADJUST_TEXT_WIDTH_NODE_SIZE: na::Vector2<f64> = na::vector![18.0, 18.0];
     /// The text width when the typewriter is in `Start` state.
     const STATE_START_TEXT_WIDTH: f64 = 10.0;
     /// The outline stroke width when drawing a text box outline
@@ -564,10 +632,10 @@ impl Typewriter {
 
     /// The bounds of the translate node.
     fn translate_node_bounds(typewriter_bounds: Aabb, camera: &Camera) -> Aabb {
-        let total_zoom = camera.total_zoom();
+        let half_extents = na::Vector2::repeat(camera.overlay_handle_size() * 0.5);
         Aabb::from_half_extents(
-            (typewriter_bounds.mins.coords + Self::TRANSLATE_NODE_SIZE * 0.5 / total_zoom).into(),
-            Self::TRANSLATE_NODE_SIZE * 0.5 / total_zoom,
+            (typewriter_bounds.mins.coords + half_extents).into(),
+            half_extents,
         )
     }
 
@@ -577,10 +645,9 @@ impl Typewriter {
         text_width: f64,
         camera: &Camera,
     ) -> na::Vector2<f64> {
-        let total_zoom = camera.total_zoom();
         na::vector![
             text_rect_origin[0] + text_width,
-            text_rect_origin[1] - Self::ADJUST_TEXT_WIDTH_NODE_SIZE[1] * 0.5 / total_zoom
+            text_rect_origin[1] - camera.overlay_handle_size() * 0.5
         ]
     }
 
@@ -590,14 +657,130 @@ impl Typewriter {
         text_width: f64,
         camera: &Camera,
     ) -> Aabb {
-        let total_zoom = camera.total_zoom();
         let center = Self::adjust_text_width_node_center(text_rect_origin, text_width, camera);
         Aabb::from_half_extents(
             center.into(),
-            Self::ADJUST_TEXT_WIDTH_NODE_SIZE * 0.5 / total_zoom,
+            na::Vector2::repeat(camera.overlay_handle_size() * 0.5),
         )
     }
 
+    /// The key of the text stroke that is currently modified, if available.
+    pub fn modifying_stroke_key(&self) -> Option<StrokeKey> {
+        if let TypewriterState::Modifying { stroke_key, .. } = &self.state {
+            Some(*stroke_key)
+        } else {
+            None
+        }
+    }
+
+    /// Set the misspelled ranges of the text stroke, which are underlined while it is modified.
+    pub fn set_misspelled_ranges(
+        &mut self,
+        misspelled_ranges: Option<(StrokeKey, Vec<Range<usize>>)>,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.misspelled_ranges = misspelled_ranges;
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Set the preedit text of an input method, which is inserted inline at the cursor while composing.
+    ///
+    /// `cursor` and the `emphasized_ranges` are byte positions relative to the preedit text.
+    /// An empty text removes the preedit. When the composition is committed,
+    /// the text arrives as regular text event which replaces the preedit.
+    pub fn set_preedit(
+        &mut self,
+        text: String,
+        cursor: usize,
+        emphasized_ranges: Vec<Range<usize>>,
+        engine_view: &mut EngineViewMut,
+    ) -> WidgetFlags {
+        let mut widget_flags = self.cancel_preedit(engine_view);
+        if text.is_empty() {
+            return widget_flags;
+        }
+        if !matches!(self.state, TypewriterState::Modifying { .. }) {
+            // Start a new text stroke, the preedit gets inserted into it
+            widget_flags.merge(self.insert_text(String::new(), None, engine_view));
+        }
+        let TypewriterState::Modifying {
+            stroke_key,
+            cursor: text_cursor,
+            ..
+        } = &self.state
+        else {
+            return widget_flags;
+        };
+        let Some(Stroke::TextStroke(textstroke)) = engine_view.store.get_stroke_mut(*stroke_key)
+        else {
+            return widget_flags;
+        };
+        let pos = text_cursor.cur_cursor();
+        if let Err(e) = textstroke.replace_text_range(pos..pos, &text) {
+            log::error!("Inserting preedit text failed, Err: {e:?}");
+            return widget_flags;
+        }
+        engine_view.store.update_geometry_for_stroke(*stroke_key);
+        engine_view.store.regenerate_rendering_for_stroke(
+            *stroke_key,
+            engine_view.camera.viewport(),
+            engine_view.camera.image_scale(),
+        );
+        widget_flags.merge(
+            engine_view
+                .doc
+                .resize_autoexpand(engine_view.store, engine_view.camera),
+        );
+        self.preedit = Some(Preedit {
+            stroke_key: *stroke_key,
+            range: pos..pos + text.len(),
+            cursor: cursor.min(text.len()),
+            emphasized_ranges: emphasized_ranges
+                .into_iter()
+                .filter(|range| range.start < range.end && range.end <= text.len())
+                .collect(),
+        });
+
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Remove the preedit text of an input method, if there is one.
+    ///
+    /// The preedit text is not part of the history, so no history entry is recorded.
+    pub fn cancel_preedit(&mut self, engine_view: &mut EngineViewMut) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let Some(preedit) = self.preedit.take() else {
+            return widget_flags;
+        };
+        let Some(Stroke::TextStroke(textstroke)) =
+            engine_view.store.get_stroke_mut(preedit.stroke_key)
+        else {
+            return widget_flags;
+        };
+        if let Err(e) = textstroke.replace_text_range(preedit.range, "") {
+            log::error!("Removing preedit text failed, Err: {e:?}");
+            return widget_flags;
+        }
+        engine_view
+            .store
+            .update_geometry_for_stroke(preedit.stroke_key);
+        engine_view.store.regenerate_rendering_for_stroke(
+            preedit.stroke_key,
+            engine_view.camera.viewport(),
+            engine_view.camera.image_scale(),
+        );
+        widget_flags.merge(
+            engine_view
+                .doc
+                .resize_autoexpand(engine_view.store, engine_view.camera),
+        );
+
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
     /// The range of the current selection, if available.
     pub fn selection_range(&self) -> Option<(Range<usize>, StrokeKey)> {
         if let TypewriterState::Modifying {
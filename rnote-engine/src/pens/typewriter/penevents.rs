@@ -5,6 +5,7 @@ use crate::pens::penbehaviour::PenProgress;
 use crate::pens::PenBehaviour;
 use crate::strokes::{Stroke, TextStroke};
 use crate::{DrawOnDocBehaviour, StrokeStore, WidgetFlags};
+use p2d::bounding_volume::BoundingVolume;
 use rnote_compose::penevents::{KeyboardKey, ModifierKey};
 use rnote_compose::penpath::Element;
 use std::time::Instant;
@@ -84,6 +85,7 @@ impl Typewriter {
                             engine_view.store.get_stroke_ref(*stroke_key),
                         ) {
                             if Self::translate_node_bounds(typewriter_bounds, engine_view.camera)
+                                .loosened(engine_view.camera.overlay_hit_tolerance())
                                 .contains_local_point(&element.pos.into())
                             {
                                 // switch to translating state
@@ -100,6 +102,7 @@ impl Typewriter {
                                 text_width,
                                 engine_view.camera,
                             )
+                            .loosened(engine_view.camera.overlay_hit_tolerance())
                             .contains_local_point(&element.pos.into())
                             {
                                 // switch to adjust text width
@@ -156,6 +159,7 @@ impl Typewriter {
                         if let Some(typewriter_bounds) = typewriter_bounds {
                             // Clicking on the translate node
                             if Self::translate_node_bounds(typewriter_bounds, engine_view.camera)
+                                .loosened(engine_view.camera.overlay_hit_tolerance())
                                 .contains_local_point(&element.pos.into())
                             {
                                 self.state = TypewriterState::Modifying {
@@ -538,9 +542,9 @@ impl Typewriter {
                                     if modifier_keys.contains(&ModifierKey::KeyboardShift) {
                                         let old_cursor = cursor.clone();
                                         if modifier_keys.contains(&ModifierKey::KeyboardCtrl) {
-                                            textstroke.move_cursor_word_back(cursor);
+                                            textstroke.move_cursor_word_left(cursor);
                                         } else {
-                                            textstroke.move_cursor_back(cursor);
+                                            textstroke.move_cursor_left(cursor);
                                         }
 
                                         *modify_state = ModifyState::Selecting {
@@ -550,9 +554,9 @@ impl Typewriter {
                                     } else {
                                         #[allow(clippy::collapsible_else_if)]
                                         if modifier_keys.contains(&ModifierKey::KeyboardCtrl) {
-                                            textstroke.move_cursor_word_back(cursor);
+                                            textstroke.move_cursor_word_left(cursor);
                                         } else {
-                                            textstroke.move_cursor_back(cursor);
+                                            textstroke.move_cursor_left(cursor);
                                         }
                                     }
                                 }
@@ -560,9 +564,9 @@ impl Typewriter {
                                     if modifier_keys.contains(&ModifierKey::KeyboardShift) {
                                         let old_cursor = cursor.clone();
                                         if modifier_keys.contains(&ModifierKey::KeyboardCtrl) {
-                                            textstroke.move_cursor_word_forward(cursor);
+                                            textstroke.move_cursor_word_right(cursor);
                                         } else {
-                                            textstroke.move_cursor_forward(cursor);
+                                            textstroke.move_cursor_right(cursor);
                                         }
 
                                         *modify_state = ModifyState::Selecting {
@@ -572,9 +576,9 @@ impl Typewriter {
                                     } else {
                                         #[allow(clippy::collapsible_else_if)]
                                         if modifier_keys.contains(&ModifierKey::KeyboardCtrl) {
-                                            textstroke.move_cursor_word_forward(cursor);
+                                            textstroke.move_cursor_word_right(cursor);
                                         } else {
-                                            textstroke.move_cursor_forward(cursor);
+                                            textstroke.move_cursor_right(cursor);
                                         }
                                     }
                                 }
@@ -703,9 +707,9 @@ impl Typewriter {
                                 KeyboardKey::NavLeft => {
                                     if modifier_keys.contains(&ModifierKey::KeyboardShift) {
                                         if modifier_keys.contains(&ModifierKey::KeyboardCtrl) {
-                                            textstroke.move_cursor_word_back(cursor);
+                                            textstroke.move_cursor_word_left(cursor);
                                         } else {
-                                            textstroke.move_cursor_back(cursor);
+                                            textstroke.move_cursor_left(cursor);
                                         }
                                         false
                                     } else {
@@ -718,9 +722,9 @@ impl Typewriter {
                                 KeyboardKey::NavRight => {
                                     if modifier_keys.contains(&ModifierKey::KeyboardShift) {
                                         if modifier_keys.contains(&ModifierKey::KeyboardCtrl) {
-                                            textstroke.move_cursor_word_forward(cursor);
+                                            textstroke.move_cursor_word_right(cursor);
                                         } else {
-                                            textstroke.move_cursor_forward(cursor);
+                                            textstroke.move_cursor_right(cursor);
                                         }
                                         false
                                     } else {
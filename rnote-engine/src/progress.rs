@@ -0,0 +1,181 @@
+// Imports
+use crate::EngineError;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Reports the progress of a long running operation like a Pdf import, a document export or loading a large file,
+/// and allows cancelling it.
+///
+/// The operation updates the progress on its worker thread. Frontends either poll [Progress::fraction],
+/// or receive the updates through the callback, which is called on the worker thread.
+/// Clones share the same state, so a clone can be passed to the operation while the original is kept to cancel it.
+///
+/// A cancelled operation stops at its next step, drops everything it has allocated so far
+/// and fails with [EngineError::Cancelled].
+#[derive(Clone)]
+pub struct Progress {
+    state: Arc<ProgressState>,
+    /// The range of the whole fraction that is covered by this handle, see [Progress::scoped].
+    scope: (f64, f64),
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self {
+            state: Arc::default(),
+            scope: (0.0, 1.0),
+        }
+    }
+}
+
+#[derive(Default)]
+struct ProgressState {
+    /// The bits of the completed fraction. The fraction is never negative, so the bits compare like the values.
+    fraction_bits: AtomicU64,
+    cancelled: AtomicBool,
+    callback: Option<Box<dyn Fn(f64) + Send + Sync>>,
+}
+
+impl std::fmt::Debug for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Progress")
+            .field("fraction", &self.fraction())
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Progress that calls the callback with the completed fraction every time the operation advances.
+    ///
+    /// The callback is called on the thread of the operation.
+    pub fn with_callback<F>(callback: F) -> Self
+    where
+        F: Fn(f64) + Send + Sync + 'static,
+    {
+        Self {
+            state: Arc::new(ProgressState {
+                callback: Some(Box::new(callback)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    /// The completed fraction of the whole operation, from 0.0 to 1.0.
+    pub fn fraction(&self) -> f64 {
+        f64::from_bits(self.state.fraction_bits.load(Ordering::Relaxed))
+    }
+
+    /// Request the operation to be cancelled.
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Returns [EngineError::Cancelled] when the operation was requested to be cancelled.
+    pub fn check_cancelled(&self) -> Result<(), EngineError> {
+        if self.is_cancelled() {
+            Err(EngineError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// A handle for a part of the operation, that maps its fraction from 0.0 to 1.0 into the range from start to end
+    /// of the scope of this handle.
+    ///
+    /// Lets nested operations report their progress without knowing about the operation they are part of.
+    pub fn scoped(&self, start: f64, end: f64) -> Self {
+        let (scope_start, scope_end) = self.scope;
+        let map = |f: f64| scope_start + (scope_end - scope_start) * f;
+        Self {
+            state: Arc::clone(&self.state),
+            scope: (map(start), map(end)),
+        }
+    }
+
+    /// Advance the completed fraction of the scope. Never moves backwards, so it can be called from multiple threads.
+    pub(crate) fn advance_to(&self, fraction: f64) {
+        let (scope_start, scope_end) = self.scope;
+        let fraction = scope_start + (scope_end - scope_start) * fraction;
+        // Also maps NaN and negative zero to zero
+        let fraction = if fraction > 0.0 {
+            fraction.min(1.0)
+        } else {
+            0.0
+        };
+        let prev_bits = self
+            .state
+            .fraction_bits
+            .fetch_max(fraction.to_bits(), Ordering::Relaxed);
+        if prev_bits < fraction.to_bits() {
+            if let Some(callback) = &self.state.callback {
+                callback(fraction);
+            }
+        }
+    }
+
+    /// A stage of the operation with the given number of steps, spanning the range from start to end of the scope.
+    pub(crate) fn stage(&self, start: f64, end: f64, n_steps: usize) -> ProgressStage<'_> {
+        self.advance_to(start);
+        ProgressStage {
+            progress: self,
+            start,
+            end,
+            n_steps,
+            done: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// A stage of an operation, made up of steps that can be completed in parallel.
+#[derive(Debug)]
+pub(crate) struct ProgressStage<'p> {
+    progress: &'p Progress,
+    start: f64,
+    end: f64,
+    n_steps: usize,
+    done: AtomicUsize,
+}
+
+impl<'p> ProgressStage<'p> {
+    /// Mark a step as completed.
+    ///
+    /// Steps should check [Progress::is_cancelled] before they start, and skip their work when it returns true.
+    pub(crate) fn step(&self) {
+        self.advance(1);
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.progress.is_cancelled()
+    }
+
+    /// Mark the given number of steps as completed.
+    pub(crate) fn advance(&self, n_steps: usize) {
+        let done = self.done.fetch_add(n_steps, Ordering::Relaxed) + n_steps;
+        let stage_fraction = if self.n_steps == 0 {
+            1.0
+        } else {
+            (done as f64 / self.n_steps as f64).min(1.0)
+        };
+        self.progress
+            .advance_to(self.start + (self.end - self.start) * stage_fraction);
+    }
+
+    /// Mark the stage as completed.
+    ///
+    /// Returns [EngineError::Cancelled] when the operation was requested to be cancelled during the stage,
+    /// because then some of its steps were skipped.
+    pub(crate) fn finish(self) -> Result<(), EngineError> {
+        self.progress.advance_to(self.end);
+        self.progress.check_cancelled()
+    }
+}
@@ -1,6 +1,6 @@
 // Imports
 use crate::utils::GrapheneRectHelpers;
-use crate::DrawBehaviour;
+use crate::{DrawBehaviour, EngineError};
 use anyhow::Context;
 use gtk4::{gdk, gio, graphene, gsk, prelude::*};
 use image::io::Reader;
@@ -12,14 +12,17 @@ use rnote_compose::shapes::{Rectangle, ShapeBehaviour};
 use rnote_compose::transform::TransformBehaviour;
 use serde::{Deserialize, Serialize};
 use std::io::{self, Cursor};
+use std::sync::RwLock;
 use svg::Node;
 use usvg::{TreeParsing, TreeTextToPath, TreeWriting};
 
 /// Usvg font database
-pub static USVG_FONTDB: Lazy<usvg::fontdb::Database> = Lazy::new(|| {
+///
+/// Holds the system fonts and the registered embedded fonts, see [crate::fonts].
+pub static USVG_FONTDB: Lazy<RwLock<usvg::fontdb::Database>> = Lazy::new(|| {
     let mut db = usvg::fontdb::Database::new();
     db.load_system_fonts();
-    db
+    RwLock::new(db)
 });
 
 /// Px unit (96 DPI ) to Point unit ( 72 DPI ) conversion factor.
@@ -74,6 +77,95 @@ impl From<ImageMemoryFormat> for piet::ImageFormat {
     }
 }
 
+/// The filter used when downscaling supersampled images.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    num_derive::FromPrimitive,
+    num_derive::ToPrimitive,
+)]
+#[serde(rename = "downscale_filter")]
+pub enum DownscaleFilter {
+    #[serde(rename = "triangle")]
+    Triangle,
+    #[serde(rename = "catmull_rom")]
+    CatmullRom,
+    #[serde(rename = "gaussian")]
+    Gaussian,
+    #[serde(rename = "lanczos3")]
+    Lanczos3,
+}
+
+impl Default for DownscaleFilter {
+    fn default() -> Self {
+        Self::Lanczos3
+    }
+}
+
+impl TryFrom<u32> for DownscaleFilter {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        num_traits::FromPrimitive::from_u32(value).ok_or_else(|| {
+            anyhow::anyhow!(
+                "DownscaleFilter try_from::<u32>() for value {} failed",
+                value
+            )
+        })
+    }
+}
+
+impl From<DownscaleFilter> for image::imageops::FilterType {
+    fn from(value: DownscaleFilter) -> Self {
+        match value {
+            DownscaleFilter::Triangle => image::imageops::FilterType::Triangle,
+            DownscaleFilter::CatmullRom => image::imageops::FilterType::CatmullRom,
+            DownscaleFilter::Gaussian => image::imageops::FilterType::Gaussian,
+            DownscaleFilter::Lanczos3 => image::imageops::FilterType::Lanczos3,
+        }
+    }
+}
+
+/// Cleanup filters for photographed or scanned documents, applied on the image buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "image_cleanup")]
+pub struct ImageCleanup {
+    /// The four corners of the document in the image in pixel coordinates,
+    /// ordered top-left, top-right, bottom-right, bottom-left.
+    ///
+    /// When set, the perspective is corrected so that the document fills the entire image.
+    #[serde(rename = "deskew_corners")]
+    pub deskew_corners: Option<[na::Point2<f64>; 4]>,
+    /// Stretches the contrast and white balance so that the paper becomes white and the ink dark.
+    #[serde(rename = "scan_filter")]
+    pub scan_filter: bool,
+    /// Converts the image to grayscale.
+    #[serde(rename = "grayscale")]
+    pub grayscale: bool,
+}
+
+impl Default for ImageCleanup {
+    fn default() -> Self {
+        Self {
+            deskew_corners: None,
+            scan_filter: false,
+            grayscale: false,
+        }
+    }
+}
+
+impl ImageCleanup {
+    /// Whether any of the filters are enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.deskew_corners.is_some() || self.scan_filter || self.grayscale
+    }
+}
+
 /// A bitmap image.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "image")]
@@ -177,6 +269,11 @@ impl TransformBehaviour for Image {
 }
 
 impl Image {
+    /// The maximum supersampling factor. The size of the rendered image grows quadratically with it.
+    pub const SUPERSAMPLING_MAX: u32 = 4;
+    /// The maximum pixel width and height of images created by the cleanup filters, the limit of cairo image surfaces.
+    pub const PIXEL_SIZE_MAX: u32 = 32767;
+
     pub fn assert_valid(&self) -> anyhow::Result<()> {
         self.rect.bounds().assert_valid()?;
 
@@ -193,8 +290,78 @@ impl Image {
     }
 
     pub fn try_from_encoded_bytes(bytes: &[u8]) -> Result<Self, anyhow::Error> {
+        if is_heif(bytes) {
+            return Self::try_from_heif_bytes(bytes);
+        }
         let reader = Reader::new(io::Cursor::new(bytes)).with_guessed_format()?;
-        Ok(Image::from(reader.decode()?))
+        let image = reader.decode().map_err(|e| match e {
+            image::ImageError::Unsupported(e) => EngineError::UnsupportedFormat(e.to_string()),
+            e => EngineError::Decode(format!("decoding image failed, {e}")),
+        })?;
+        Ok(Image::from(image))
+    }
+
+    /// Decode Heif/Heic image bytes.
+    #[cfg(feature = "heif")]
+    fn try_from_heif_bytes(bytes: &[u8]) -> Result<Self, anyhow::Error> {
+        use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+        let lib_heif = LibHeif::new();
+        let ctx = HeifContext::read_from_bytes(bytes).context("reading Heif context failed")?;
+        let handle = ctx
+            .primary_image_handle()
+            .context("getting Heif primary image handle failed")?;
+        let heif_image = lib_heif
+            .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)
+            .context("decoding Heif image failed")?;
+        let planes = heif_image.planes();
+        let interleaved = planes
+            .interleaved
+            .ok_or_else(|| anyhow::anyhow!("decoded Heif image has no interleaved plane"))?;
+
+        // Rows might be padded
+        let row_len = interleaved.width as usize * 4;
+        if interleaved.stride < row_len.max(1) {
+            return Err(anyhow::anyhow!(
+                "decoded Heif image has invalid stride {}",
+                interleaved.stride
+            ));
+        }
+        let data = interleaved
+            .data
+            .chunks(interleaved.stride)
+            .take(interleaved.height as usize)
+            .map(|row| row.get(..row_len))
+            .collect::<Option<Vec<&[u8]>>>()
+            .ok_or_else(|| anyhow::anyhow!("decoded Heif image has truncated rows"))?
+            .concat();
+        let rgba_image = image::RgbaImage::from_raw(interleaved.width, interleaved.height, data)
+            .ok_or_else(|| anyhow::anyhow!("creating image from Heif data failed"))?;
+
+        Ok(Image::from(image::DynamicImage::ImageRgba8(rgba_image)))
+    }
+
+    #[cfg(not(feature = "heif"))]
+    fn try_from_heif_bytes(_bytes: &[u8]) -> Result<Self, anyhow::Error> {
+        Err(EngineError::UnsupportedFormat(String::from(
+            "Heif images are not supported, built without the `heif` feature",
+        ))
+        .into())
+    }
+
+    /// Read the DPI from the metadata of the encoded image bytes.
+    ///
+    /// Supports the `pHYs` chunk in Png's and the JFIF and Exif resolution in Jpeg's.
+    /// Returns None when the image does not contain a (valid) resolution.
+    pub fn read_dpi_from_encoded_bytes(bytes: &[u8]) -> Option<f64> {
+        let dpi = if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            png_read_phys_dpi(bytes)
+        } else if bytes.starts_with(&[0xFF, 0xD8]) {
+            jpeg_read_dpi(bytes)
+        } else {
+            None
+        }?;
+        (dpi.is_finite() && dpi > 0.0).then_some(dpi)
     }
 
     pub fn try_from_cairo_surface(
@@ -249,6 +416,315 @@ impl Image {
         Ok(bytes_buf.into_inner())
     }
 
+    /// Encode the images as the pages of a multi-page Tiff.
+    pub fn encode_images_as_multipage_tiff(
+        images: impl IntoIterator<Item = Self>,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        let mut bytes_buf: Cursor<Vec<u8>> = Cursor::new(Vec::new());
+
+        // Encoder in new scope, because it borrows the buffer
+        {
+            let mut encoder = tiff::encoder::TiffEncoder::new(&mut bytes_buf)
+                .context("creating Tiff encoder failed")?;
+            for (i, image) in images.into_iter().enumerate() {
+                image.assert_valid()?;
+                encoder
+                    .write_image::<tiff::encoder::colortype::RGBA8>(
+                        image.pixel_width,
+                        image.pixel_height,
+                        &image.data,
+                    )
+                    .with_context(|| format!("writing page {i} to Tiff failed"))?;
+            }
+        }
+
+        Ok(bytes_buf.into_inner())
+    }
+
+    /// Encode into the format and embed the DPI as metadata.
+    ///
+    /// The DPI is currently only written for Png, as `pHYs` chunk.
+    pub fn into_encoded_bytes_with_dpi(
+        self,
+        format: image::ImageOutputFormat,
+        dpi: f64,
+    ) -> Result<Vec<u8>, anyhow::Error> {
+        let is_png = matches!(format, image::ImageOutputFormat::Png);
+        let bytes = self.into_encoded_bytes(format)?;
+        if is_png {
+            png_insert_phys_chunk(bytes, dpi)
+        } else {
+            Ok(bytes)
+        }
+    }
+
+    /// Downscale the image to the given pixel size with the filter.
+    ///
+    /// The target rect stays the same.
+    pub fn downscaled(
+        self,
+        pixel_width: u32,
+        pixel_height: u32,
+        filter: DownscaleFilter,
+    ) -> anyhow::Result<Self> {
+        let rect = self.rect.clone();
+        let imgbuf = self.to_imgbuf()?;
+        let resized = image::imageops::resize(&imgbuf, pixel_width, pixel_height, filter.into());
+
+        Ok(Self {
+            data: glib::Bytes::from_owned(resized.into_raw()),
+            rect,
+            pixel_width,
+            pixel_height,
+            memory_format: ImageMemoryFormat::R8g8b8a8Premultiplied,
+        })
+    }
+
+    /// Crop the image to the bounds in pixel coordinates, which are rounded and clamped to the image size.
+    ///
+    /// The target rect is set to the bounds of the new pixel size at the origin.
+    pub fn cropped(self, pixel_bounds: Aabb) -> anyhow::Result<Self> {
+        let x = pixel_bounds.mins[0].round().max(0.0) as u32;
+        let y = pixel_bounds.mins[1].round().max(0.0) as u32;
+        let width = (pixel_bounds.maxs[0].round().max(0.0) as u32)
+            .min(self.pixel_width)
+            .saturating_sub(x);
+        let height = (pixel_bounds.maxs[1].round().max(0.0) as u32)
+            .min(self.pixel_height)
+            .saturating_sub(y);
+        if width == 0 || height == 0 {
+            return Err(anyhow::anyhow!(
+                "cropping image failed, bounds {pixel_bounds:?} do not intersect the image"
+            ));
+        }
+        let imgbuf = self.to_imgbuf()?;
+        let cropped = image::imageops::crop_imm(&imgbuf, x, y, width, height).to_image();
+
+        Ok(Self::from_rgba_imgbuf(cropped))
+    }
+
+    /// Rotate the image data by 90 degrees, clockwise or counter-clockwise.
+    ///
+    /// The target rect is set to the bounds of the new pixel size at the origin.
+    pub fn rotated_90(self, clockwise: bool) -> anyhow::Result<Self> {
+        let imgbuf = self.to_imgbuf()?;
+        let rotated = if clockwise {
+            image::imageops::rotate90(&imgbuf)
+        } else {
+            image::imageops::rotate270(&imgbuf)
+        };
+
+        Ok(Self::from_rgba_imgbuf(rotated))
+    }
+
+    /// Mirror the image data horizontally or vertically.
+    ///
+    /// The target rect stays the same.
+    pub fn flipped(self, horizontal: bool) -> anyhow::Result<Self> {
+        let rect = self.rect.clone();
+        let imgbuf = self.to_imgbuf()?;
+        let flipped = if horizontal {
+            image::imageops::flip_horizontal(&imgbuf)
+        } else {
+            image::imageops::flip_vertical(&imgbuf)
+        };
+
+        Ok(Self {
+            rect,
+            ..Self::from_rgba_imgbuf(flipped)
+        })
+    }
+
+    /// Expects the image buffer to contain premultiplied rgba8 data.
+    fn from_rgba_imgbuf(imgbuf: image::RgbaImage) -> Self {
+        let (pixel_width, pixel_height) = imgbuf.dimensions();
+
+        Self {
+            data: glib::Bytes::from_owned(imgbuf.into_raw()),
+            rect: Rectangle::from_p2d_aabb(Aabb::new(
+                na::point![0.0, 0.0],
+                na::point![f64::from(pixel_width), f64::from(pixel_height)],
+            )),
+            pixel_width,
+            pixel_height,
+            memory_format: ImageMemoryFormat::R8g8b8a8Premultiplied,
+        }
+    }
+
+    /// Apply the cleanup filters in the order: perspective de-skew, scan filter, grayscale.
+    ///
+    /// The target rect stays the same when no de-skew is applied.
+    /// Otherwise it is set to the bounds of the new pixel size at the origin.
+    pub fn cleaned_up(self, cleanup: &ImageCleanup) -> anyhow::Result<Self> {
+        self.assert_valid()?;
+        let mut image = match cleanup.deskew_corners {
+            Some(corners) => self.perspective_deskewed(corners)?,
+            None => self,
+        };
+        if cleanup.scan_filter {
+            image.apply_scan_filter();
+        }
+        if cleanup.grayscale {
+            image.convert_to_grayscale();
+        }
+        Ok(image)
+    }
+
+    /// Correct the perspective of the quad spanned by the corners (in pixel coordinates,
+    /// ordered top-left, top-right, bottom-right, bottom-left), so that it fills the entire returned image.
+    ///
+    /// The corners are clamped to the image. The new pixel size is determined by the longer of the opposing edges
+    /// of the quad and fails when it exceeds [Image::PIXEL_SIZE_MAX].
+    pub fn perspective_deskewed(&self, corners: [na::Point2<f64>; 4]) -> anyhow::Result<Self> {
+        self.assert_valid()?;
+        let corners = corners.map(|corner| {
+            na::point![
+                corner[0].clamp(0.0, f64::from(self.pixel_width)),
+                corner[1].clamp(0.0, f64::from(self.pixel_height))
+            ]
+        });
+        let width = (corners[1] - corners[0])
+            .magnitude()
+            .max((corners[2] - corners[3]).magnitude())
+            .round()
+            .max(1.0);
+        let height = (corners[3] - corners[0])
+            .magnitude()
+            .max((corners[2] - corners[1]).magnitude())
+            .round()
+            .max(1.0);
+        if width > f64::from(Self::PIXEL_SIZE_MAX) || height > f64::from(Self::PIXEL_SIZE_MAX) {
+            return Err(anyhow::anyhow!(
+                "de-skewed image size ({width}, {height}) exceeds the maximum pixel size {}",
+                Self::PIXEL_SIZE_MAX
+            ));
+        }
+        let (pixel_width, pixel_height) = (width as u32, height as u32);
+        let homography = homography_from_quad(
+            [
+                na::point![0.0, 0.0],
+                na::point![width, 0.0],
+                na::point![width, height],
+                na::point![0.0, height],
+            ],
+            corners,
+        )
+        .ok_or_else(|| {
+            anyhow::anyhow!("calculating homography failed, corners {corners:?} are degenerate")
+        })?;
+
+        let src = &*self.data;
+        let (src_width, src_height) = (self.pixel_width as usize, self.pixel_height as usize);
+        let mut data = vec![0_u8; 4 * pixel_width as usize * pixel_height as usize];
+
+        for (i, dst) in data.chunks_exact_mut(4).enumerate() {
+            let (x, y) = (
+                (i % pixel_width as usize) as f64 + 0.5,
+                (i / pixel_width as usize) as f64 + 0.5,
+            );
+            let p = homography * na::vector![x, y, 1.0];
+            if p[2].abs() < f64::EPSILON {
+                continue;
+            }
+            // Bilinear sampling, the data is premultiplied so the channels can be interpolated independently.
+            let (sx, sy) = (p[0] / p[2] - 0.5, p[1] / p[2] - 0.5);
+            if sx < -0.5 || sy < -0.5 || sx > src_width as f64 - 0.5 || sy > src_height as f64 - 0.5
+            {
+                continue;
+            }
+            let (x0, y0) = (sx.floor(), sy.floor());
+            let (fx, fy) = (sx - x0, sy - y0);
+            let clamp_x = |x: f64| (x.max(0.0) as usize).min(src_width - 1);
+            let clamp_y = |y: f64| (y.max(0.0) as usize).min(src_height - 1);
+            let (x0i, x1i, y0i, y1i) = (
+                clamp_x(x0),
+                clamp_x(x0 + 1.0),
+                clamp_y(y0),
+                clamp_y(y0 + 1.0),
+            );
+
+            for c in 0..4 {
+                let px = |x: usize, y: usize| f64::from(src[4 * (y * src_width + x) + c]);
+                let top = px(x0i, y0i) * (1.0 - fx) + px(x1i, y0i) * fx;
+                let bottom = px(x0i, y1i) * (1.0 - fx) + px(x1i, y1i) * fx;
+                dst[c] = (top * (1.0 - fy) + bottom * fy).round().clamp(0.0, 255.0) as u8;
+            }
+        }
+
+        Ok(Self {
+            data: glib::Bytes::from_owned(data),
+            rect: Rectangle::from_p2d_aabb(Aabb::new(
+                na::point![0.0, 0.0],
+                na::point![width, height],
+            )),
+            pixel_width,
+            pixel_height,
+            memory_format: ImageMemoryFormat::R8g8b8a8Premultiplied,
+        })
+    }
+
+    /// Stretches the levels of every color channel independently, so that the paper becomes white and the ink dark.
+    ///
+    /// This corrects both contrast and white balance of photographed documents.
+    pub fn apply_scan_filter(&mut self) {
+        // The percentiles of the histograms that are mapped to black and white.
+        // The white point is chosen lower, because paper usually covers most of the image.
+        const BLACK_PERC: usize = 2;
+        const WHITE_PERC: usize = 90;
+        let mut data = self.data.to_vec();
+        let n_pixels = data.len() / 4;
+        if n_pixels == 0 {
+            return;
+        }
+
+        let mut histograms = [[0_usize; 256]; 3];
+        for px in data.chunks_exact(4) {
+            for c in 0..3 {
+                histograms[c][unpremultiply(px[c], px[3]) as usize] += 1;
+            }
+        }
+        let percentile = |histogram: &[usize; 256], perc: usize| -> u8 {
+            let target = n_pixels * perc / 100;
+            let mut acc = 0;
+            for (value, count) in histogram.iter().enumerate() {
+                acc += count;
+                if acc > target {
+                    return value as u8;
+                }
+            }
+            255
+        };
+        let levels = histograms.map(|histogram| {
+            let lo = f64::from(percentile(&histogram, BLACK_PERC));
+            let hi = f64::from(percentile(&histogram, WHITE_PERC)).max(lo + 1.0);
+            (lo, hi)
+        });
+
+        for px in data.chunks_exact_mut(4) {
+            let alpha = px[3];
+            for (c, (lo, hi)) in levels.iter().enumerate() {
+                let value = f64::from(unpremultiply(px[c], alpha));
+                let stretched = ((value - lo) / (hi - lo) * 255.0).clamp(0.0, 255.0);
+                px[c] = (stretched * f64::from(alpha) / 255.0).round() as u8;
+            }
+        }
+        self.data = glib::Bytes::from_owned(data);
+    }
+
+    /// Convert the image to grayscale, using the Rec. 709 luma coefficients.
+    pub fn convert_to_grayscale(&mut self) {
+        let mut data = self.data.to_vec();
+        for px in data.chunks_exact_mut(4) {
+            let luma =
+                0.2126 * f64::from(px[0]) + 0.7152 * f64::from(px[1]) + 0.0722 * f64::from(px[2]);
+            let luma = luma.round().clamp(0.0, 255.0) as u8;
+            px[0] = luma;
+            px[1] = luma;
+            px[2] = luma;
+        }
+        self.data = glib::Bytes::from_owned(data);
+    }
+
     pub fn to_memtexture(&self) -> Result<gdk::MemoryTexture, anyhow::Error> {
         self.assert_valid()?;
 
@@ -383,6 +859,28 @@ impl Image {
         })
     }
 
+    /// Generate an image from an Svg, rendered at `supersampling` times the image scale
+    /// and downscaled with the filter to reduce aliasing.
+    ///
+    /// A supersampling factor of 1 renders without supersampling, it is clamped to [Image::SUPERSAMPLING_MAX].
+    pub fn gen_image_from_svg_supersampled(
+        svg: Svg,
+        bounds: Aabb,
+        image_scale: f64,
+        supersampling: u32,
+        filter: DownscaleFilter,
+    ) -> Result<Self, anyhow::Error> {
+        let supersampling = supersampling.min(Self::SUPERSAMPLING_MAX);
+        if supersampling <= 1 {
+            return Self::gen_image_from_svg(svg, bounds, image_scale);
+        }
+        let image = Self::gen_image_from_svg(svg, bounds, image_scale * f64::from(supersampling))?;
+        let pixel_width = (image.pixel_width / supersampling).max(1);
+        let pixel_height = (image.pixel_height / supersampling).max(1);
+
+        image.downscaled(pixel_width, pixel_height, filter)
+    }
+
     /// Generates an image with a provided closure that draws onto a [cairo::Context].
     pub fn gen_with_cairo<F>(
         draw_func: F,
@@ -394,6 +892,21 @@ impl Image {
     {
         bounds.ensure_positive();
         bounds = bounds.ceil().loosened(1.0);
+        Self::gen_with_cairo_exact(draw_func, bounds, image_scale)
+    }
+
+    /// Generates an image with a provided closure that draws onto a [cairo::Context].
+    ///
+    /// Unlike [Image::gen_with_cairo], the bounds are not aligned or extended,
+    /// so the image covers exactly the given bounds.
+    pub fn gen_with_cairo_exact<F>(
+        draw_func: F,
+        bounds: Aabb,
+        image_scale: f64,
+    ) -> anyhow::Result<Self>
+    where
+        F: FnOnce(&cairo::Context) -> anyhow::Result<()>,
+    {
         bounds.assert_valid()?;
 
         let width_scaled = ((bounds.extents()[0]) * image_scale).round() as u32;
@@ -600,10 +1113,22 @@ impl Svg {
         Ok(())
     }
 
+    /// Reduce the size of the Svg by merging repeated styles into classes, reusing repeated paths
+    /// and rounding coordinates.
+    ///
+    /// Expects the Svg data to have a root element, as produced by [Svg::simplify].
+    pub fn optimize(&mut self) -> anyhow::Result<()> {
+        self.svg_data = crate::svgoptimize::optimize_svg_data(&self.svg_data)?;
+        Ok(())
+    }
+
     /// Simplify the Svg by passing it through [usvg].
     pub fn simplify(&mut self) -> anyhow::Result<()> {
         let xml_options = usvg::XmlOptions {
-            id_prefix: Some(rnote_compose::utils::svg_random_id_prefix()),
+            // Derived from the data, so that simplifying the same Svg always produces the same output
+            id_prefix: Some(rnote_compose::utils::svg_id_prefix_from_data(
+                self.svg_data.as_bytes(),
+            )),
             transforms_precision: 4,
             coordinates_precision: 3,
             writer_opts: xmlwriter::Options {
@@ -620,7 +1145,7 @@ impl Svg {
             false,
         );
         let mut usvg_tree = usvg::Tree::from_str(&wrapped_svg_data, &usvg::Options::default())?;
-        usvg_tree.convert_text(&USVG_FONTDB);
+        usvg_tree.convert_text(&USVG_FONTDB.read().unwrap());
         self.svg_data = rnote_compose::utils::remove_xml_header(&usvg_tree.to_string(&xml_options));
         self.bounds = simplified_bounds;
 
@@ -637,6 +1162,205 @@ impl Svg {
     }
 }
 
+/// Insert a `pHYs` chunk containing the DPI into encoded Png bytes, right after the `IHDR` chunk.
+fn png_insert_phys_chunk(mut png_bytes: Vec<u8>, dpi: f64) -> anyhow::Result<Vec<u8>> {
+    // Png signature (8 bytes) followed by the IHDR chunk (4 length, 4 type, 13 data, 4 crc bytes)
+    const IHDR_END: usize = 8 + 4 + 4 + 13 + 4;
+    if png_bytes.len() < IHDR_END || &png_bytes[12..16] != b"IHDR" {
+        return Err(anyhow::anyhow!(
+            "Inserting pHYs chunk failed, bytes are not a valid Png."
+        ));
+    }
+    let pixels_per_meter = (dpi / 0.0254).round() as u32;
+
+    let mut chunk_type_data = Vec::with_capacity(13);
+    chunk_type_data.extend_from_slice(b"pHYs");
+    chunk_type_data.extend_from_slice(&pixels_per_meter.to_be_bytes());
+    chunk_type_data.extend_from_slice(&pixels_per_meter.to_be_bytes());
+    // Unit is meter
+    chunk_type_data.push(1);
+    let mut crc = flate2::Crc::new();
+    crc.update(&chunk_type_data);
+
+    let mut chunk = Vec::with_capacity(21);
+    chunk.extend_from_slice(&9_u32.to_be_bytes());
+    chunk.extend_from_slice(&chunk_type_data);
+    chunk.extend_from_slice(&crc.sum().to_be_bytes());
+    png_bytes.splice(IHDR_END..IHDR_END, chunk);
+
+    Ok(png_bytes)
+}
+
+/// Whether the bytes are a Heif/Heic image, determined by the brand of the `ftyp` box.
+fn is_heif(bytes: &[u8]) -> bool {
+    const HEIF_BRANDS: [&[u8; 4]; 7] = [
+        b"heic", b"heix", b"heim", b"heis", b"hevc", b"hevx", b"mif1",
+    ];
+    bytes.get(4..8) == Some(b"ftyp")
+        && bytes
+            .get(8..12)
+            .map_or(false, |brand| HEIF_BRANDS.iter().any(|b| brand == *b))
+}
+
+/// Read the DPI from the `pHYs` chunk of encoded Png bytes.
+fn png_read_phys_dpi(png_bytes: &[u8]) -> Option<f64> {
+    // Skip the Png signature
+    let mut pos = 8;
+    while let Some(chunk_header) = png_bytes.get(pos..pos + 8) {
+        let len = u32::from_be_bytes(chunk_header[0..4].try_into().ok()?) as usize;
+        match &chunk_header[4..8] {
+            b"pHYs" => {
+                let data = png_bytes.get(pos + 8..pos + 8 + 9)?;
+                let pixels_per_unit_x = u32::from_be_bytes(data[0..4].try_into().ok()?);
+                // Unit 1 is meter, 0 means the unit is unknown and only the aspect ratio is specified
+                return (data[8] == 1).then_some(f64::from(pixels_per_unit_x) * 0.0254);
+            }
+            // pHYs must appear before the first IDAT chunk
+            b"IDAT" | b"IEND" => return None,
+            _ => {}
+        }
+        // length, type, data, crc
+        pos += 4 + 4 + len + 4;
+    }
+    None
+}
+
+/// Read the DPI from encoded Jpeg bytes.
+///
+/// The Exif resolution is preferred over the JFIF density.
+fn jpeg_read_dpi(jpeg_bytes: &[u8]) -> Option<f64> {
+    let mut jfif_dpi = None;
+    // Skip the SOI marker
+    let mut pos = 2;
+    while let Some(&[0xFF, marker]) = jpeg_bytes.get(pos..pos + 2) {
+        // Fill bytes
+        if marker == 0xFF {
+            pos += 1;
+            continue;
+        }
+        // Image data starts with SOS, EOI ends the image
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+        let len = u16::from_be_bytes(jpeg_bytes.get(pos + 2..pos + 4)?.try_into().ok()?) as usize;
+        let segment = jpeg_bytes.get(pos + 4..pos + 2 + len)?;
+        match marker {
+            // APP0
+            0xE0 if segment.starts_with(b"JFIF\0") && segment.len() >= 12 => {
+                let density_x = f64::from(u16::from_be_bytes([segment[8], segment[9]]));
+                jfif_dpi = match segment[7] {
+                    1 => Some(density_x),
+                    2 => Some(density_x * 2.54),
+                    _ => None,
+                };
+            }
+            // APP1
+            0xE1 if segment.starts_with(b"Exif\0\0") => {
+                if let Some(dpi) = exif_read_dpi(&segment[6..]) {
+                    return Some(dpi);
+                }
+            }
+            _ => {}
+        }
+        pos += 2 + len;
+    }
+    jfif_dpi
+}
+
+/// Read the resolution from the first IFD of the Tiff structure of Exif data.
+fn exif_read_dpi(tiff: &[u8]) -> Option<f64> {
+    const TAG_X_RESOLUTION: u16 = 0x011A;
+    const TAG_RESOLUTION_UNIT: u16 = 0x0128;
+
+    let little_endian = match tiff.get(0..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let read_u16 = |pos: usize| -> Option<u16> {
+        let b: [u8; 2] = tiff.get(pos..pos + 2)?.try_into().ok()?;
+        Some(if little_endian {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        })
+    };
+    let read_u32 = |pos: usize| -> Option<u32> {
+        let b: [u8; 4] = tiff.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if little_endian {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    };
+
+    let ifd = read_u32(4)? as usize;
+    let n_entries = read_u16(ifd)? as usize;
+    let mut x_resolution = None;
+    // Inch is the default unit
+    let mut resolution_unit = 2;
+    for i in 0..n_entries {
+        let entry = ifd + 2 + i * 12;
+        match read_u16(entry)? {
+            TAG_X_RESOLUTION => {
+                // Rational, stored at the offset
+                let offset = read_u32(entry + 8)? as usize;
+                let (num, den) = (read_u32(offset)?, read_u32(offset + 4)?);
+                if den != 0 {
+                    x_resolution = Some(f64::from(num) / f64::from(den));
+                }
+            }
+            TAG_RESOLUTION_UNIT => resolution_unit = read_u16(entry + 8)?,
+            _ => {}
+        }
+    }
+
+    match resolution_unit {
+        2 => x_resolution,
+        3 => x_resolution.map(|r| r * 2.54),
+        _ => None,
+    }
+}
+
+/// Calculates the homography that maps the `from` quad onto the `to` quad.
+///
+/// Returns None if the quads are degenerate.
+fn homography_from_quad(
+    from: [na::Point2<f64>; 4],
+    to: [na::Point2<f64>; 4],
+) -> Option<na::Matrix3<f64>> {
+    let mut a = na::SMatrix::<f64, 8, 8>::zeros();
+    let mut b = na::SVector::<f64, 8>::zeros();
+    for (i, (p, q)) in from.iter().zip(to.iter()).enumerate() {
+        let (x, y, u, v) = (p[0], p[1], q[0], q[1]);
+        a.set_row(
+            2 * i,
+            &na::RowSVector::<f64, 8>::from_row_slice(&[x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y]),
+        );
+        a.set_row(
+            2 * i + 1,
+            &na::RowSVector::<f64, 8>::from_row_slice(&[0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y]),
+        );
+        b[2 * i] = u;
+        b[2 * i + 1] = v;
+    }
+    let h = a.lu().solve(&b)?;
+    if h.iter().any(|v| !v.is_finite()) {
+        return None;
+    }
+    Some(na::Matrix3::new(
+        h[0], h[1], h[2], h[3], h[4], h[5], h[6], h[7], 1.0,
+    ))
+}
+
+fn unpremultiply(value: u8, alpha: u8) -> u8 {
+    if alpha == 0 {
+        0
+    } else {
+        (u32::from(value) * 255 / u32::from(alpha)).min(255) as u8
+    }
+}
+
 fn convert_image_bgra_to_rgba(_width: u32, _height: u32, mut bytes: Vec<u8>) -> Vec<u8> {
     for src in bytes.chunks_exact_mut(4) {
         let (blue, green, red, alpha) = (src[0], src[1], src[2], src[3]);
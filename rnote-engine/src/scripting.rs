@@ -0,0 +1,322 @@
+// Imports
+use crate::store::StrokeKey;
+use crate::strokes::textstroke::TextStyle;
+use crate::strokes::{ShapeStroke, Stroke, TextStroke};
+use crate::{RnoteEngine, WidgetFlags};
+use p2d::bounding_volume::Aabb;
+use p2d::query::PointQuery;
+use rhai::{Array, Dynamic, EvalAltResult, Map};
+use rnote_compose::helpers::SplitOrder;
+use rnote_compose::shapes::{Ellipse, Line, Rectangle, Shape, ShapeBehaviour};
+use rnote_compose::transform::TransformBehaviour;
+use rnote_compose::{Color, Style, Transform};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Instant;
+
+/// The outcome of a script that was run on the document.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptOutcome {
+    /// The lines printed by the script.
+    pub output: Vec<String>,
+    /// The file names the script requested the document to be exported to, after its changes were applied.
+    ///
+    /// The export itself is left to the caller, because scripts are not allowed to access the file system.
+    pub export_requests: Vec<String>,
+}
+
+/// A document modification requested by a script. They are applied after the script finished successfully.
+#[derive(Debug, Clone)]
+enum ScriptOp {
+    Insert(Stroke),
+    Recolor(StrokeKey, Color),
+    Delete(StrokeKey),
+}
+
+#[derive(Debug, Default)]
+struct ScriptState {
+    /// The keys of the strokes, indexed by the ids that are exposed to the script.
+    keys: Vec<StrokeKey>,
+    strokes: Array,
+    pages_bounds: Vec<Aabb>,
+    text_style: TextStyle,
+    shape_style: Style,
+    ops: Vec<ScriptOp>,
+    outcome: ScriptOutcome,
+}
+
+impl ScriptState {
+    fn page_offset(&self, page: i64) -> Result<na::Vector2<f64>, Box<EvalAltResult>> {
+        usize::try_from(page)
+            .ok()
+            .and_then(|page| self.pages_bounds.get(page))
+            .map(|bounds| bounds.mins.coords)
+            .ok_or_else(|| format!("page {page} does not exist").into())
+    }
+
+    fn key(&self, id: i64) -> Result<StrokeKey, Box<EvalAltResult>> {
+        usize::try_from(id)
+            .ok()
+            .and_then(|id| self.keys.get(id))
+            .copied()
+            .ok_or_else(|| format!("stroke with id {id} does not exist").into())
+    }
+
+    fn insert_shape(&mut self, page: i64, shape: Shape) -> Result<(), Box<EvalAltResult>> {
+        let mut stroke = Stroke::ShapeStroke(ShapeStroke::new(shape, self.shape_style.clone()));
+        stroke.translate(self.page_offset(page)?);
+        self.ops.push(ScriptOp::Insert(stroke));
+        Ok(())
+    }
+}
+
+/// Converts script integers and floats to f64.
+fn to_f64(value: Dynamic) -> Result<f64, Box<EvalAltResult>> {
+    value
+        .as_float()
+        .or_else(|_| value.as_int().map(|v| v as f64))
+        .map_err(|type_name| format!("expected a number, got `{type_name}`").into())
+}
+
+/// Parses colors in the hex form `#rrggbb` or `#rrggbbaa`.
+fn parse_color(color: &str) -> Result<Color, Box<EvalAltResult>> {
+    piet::Color::from_hex_str(color)
+        .map(Color::from)
+        .map_err(|e| format!("invalid color `{color}`, Err: {e}").into())
+}
+
+impl RnoteEngine {
+    /// The maximum number of operations a script may execute, to stop runaway scripts.
+    pub const SCRIPT_MAX_OPERATIONS: u64 = 10_000_000;
+    /// The maximum length of strings created by a script, in bytes.
+    pub const SCRIPT_MAX_STRING_SIZE: usize = 1024 * 1024;
+    /// The maximum number of elements of arrays created by a script.
+    ///
+    /// Raised to the number of strokes for large documents, because the strokes are queried as array.
+    pub const SCRIPT_MAX_ARRAY_SIZE: usize = 100_000;
+    /// The maximum number of properties of object maps created by a script.
+    pub const SCRIPT_MAX_MAP_SIZE: usize = 10_000;
+
+    /// Run a [rhai](https://rhai.rs) script that automates document edits.
+    ///
+    /// The script can query the pages and strokes, insert text and shapes with the current pen styles,
+    /// recolor and delete strokes and request exports. Positions are relative to the upper left corner of the given page.
+    /// The modifications are applied as a single undoable change only when the script runs successfully.
+    ///
+    /// Available functions:
+    /// - `n_pages()`, `page_width()`, `page_height()`
+    /// - `strokes()`: array of maps with `id`, `kind`, `page`, `x`, `y`, `width`, `height` and `text` for text strokes
+    /// - `insert_text(page, x, y, text)`
+    /// - `insert_line(page, x1, y1, x2, y2)`, `insert_rect(page, x, y, width, height)`, `insert_ellipse(page, cx, cy, rx, ry)`
+    /// - `recolor(id, "#rrggbb")`, `delete(id)`
+    /// - `export_doc(file_name)`
+    ///
+    /// Strokes rendering then needs to be updated.
+    pub fn run_script(&mut self, script: &str) -> anyhow::Result<(ScriptOutcome, WidgetFlags)> {
+        let pages_bounds = self.document.pages_bounds(SplitOrder::default());
+        let keys = self.store.stroke_keys_as_rendered();
+        let strokes = keys
+            .iter()
+            .enumerate()
+            .filter_map(|(id, &key)| {
+                let stroke = self.store.get_stroke_ref(key)?;
+                let bounds = stroke.bounds();
+                let page = pages_bounds
+                    .iter()
+                    .position(|page_bounds| page_bounds.contains_local_point(&bounds.center()))
+                    .map_or(-1, |page| page as i64);
+                let page_offset = usize::try_from(page)
+                    .ok()
+                    .map_or(na::Vector2::zeros(), |page| pages_bounds[page].mins.coords);
+                let kind = match stroke {
+                    Stroke::BrushStroke(_) => "drawing",
+                    Stroke::ShapeStroke(_) => "shape",
+                    Stroke::TextStroke(_) => "text",
+                    Stroke::VectorImage(_) | Stroke::BitmapImage(_) => "image",
+                };
+
+                let mut map = Map::new();
+                map.insert("id".into(), Dynamic::from(id as i64));
+                map.insert("kind".into(), Dynamic::from(kind.to_string()));
+                map.insert("page".into(), Dynamic::from(page));
+                map.insert("x".into(), Dynamic::from(bounds.mins[0] - page_offset[0]));
+                map.insert("y".into(), Dynamic::from(bounds.mins[1] - page_offset[1]));
+                map.insert("width".into(), Dynamic::from(bounds.extents()[0]));
+                map.insert("height".into(), Dynamic::from(bounds.extents()[1]));
+                if let Stroke::TextStroke(textstroke) = stroke {
+                    map.insert("text".into(), Dynamic::from(textstroke.text.clone()));
+                }
+                Some(Dynamic::from(map))
+            })
+            .collect::<Array>();
+        let n_strokes = strokes.len();
+        let state = Rc::new(RefCell::new(ScriptState {
+            keys,
+            strokes,
+            pages_bounds,
+            text_style: self.pens_config.typewriter_config.text_style.clone(),
+            shape_style: self
+                .pens_config
+                .shaper_config
+                .gen_style_for_current_options(),
+            ..Default::default()
+        }));
+        let format_size = na::vector![self.document.format.width, self.document.format.height];
+
+        let mut rhai_engine = rhai::Engine::new();
+        rhai_engine.set_max_operations(Self::SCRIPT_MAX_OPERATIONS);
+        rhai_engine.set_max_call_levels(64);
+        rhai_engine.set_max_expr_depths(64, 64);
+        rhai_engine.set_max_string_size(Self::SCRIPT_MAX_STRING_SIZE);
+        rhai_engine.set_max_array_size(Self::SCRIPT_MAX_ARRAY_SIZE.max(n_strokes));
+        rhai_engine.set_max_map_size(Self::SCRIPT_MAX_MAP_SIZE);
+
+        let s = Rc::clone(&state);
+        rhai_engine.on_print(move |text| s.borrow_mut().outcome.output.push(text.to_string()));
+        let s = Rc::clone(&state);
+        rhai_engine
+            .on_debug(move |text, _, _| s.borrow_mut().outcome.output.push(text.to_string()));
+
+        let s = Rc::clone(&state);
+        rhai_engine.register_fn("n_pages", move || s.borrow().pages_bounds.len() as i64);
+        rhai_engine.register_fn("page_width", move || format_size[0]);
+        rhai_engine.register_fn("page_height", move || format_size[1]);
+        let s = Rc::clone(&state);
+        rhai_engine.register_fn("strokes", move || s.borrow().strokes.clone());
+
+        let s = Rc::clone(&state);
+        rhai_engine.register_fn(
+            "insert_text",
+            move |page: i64,
+                  x: Dynamic,
+                  y: Dynamic,
+                  text: &str|
+                  -> Result<(), Box<EvalAltResult>> {
+                let mut state = s.borrow_mut();
+                let pos = state.page_offset(page)? + na::vector![to_f64(x)?, to_f64(y)?];
+                let stroke = Stroke::TextStroke(TextStroke::new(
+                    text.to_string(),
+                    pos,
+                    state.text_style.clone(),
+                ));
+                state.ops.push(ScriptOp::Insert(stroke));
+                Ok(())
+            },
+        );
+        let s = Rc::clone(&state);
+        rhai_engine.register_fn(
+            "insert_line",
+            move |page: i64,
+                  x1: Dynamic,
+                  y1: Dynamic,
+                  x2: Dynamic,
+                  y2: Dynamic|
+                  -> Result<(), Box<EvalAltResult>> {
+                let line = Line {
+                    start: na::vector![to_f64(x1)?, to_f64(y1)?],
+                    end: na::vector![to_f64(x2)?, to_f64(y2)?],
+                };
+                s.borrow_mut().insert_shape(page, Shape::Line(line))
+            },
+        );
+        let s = Rc::clone(&state);
+        rhai_engine.register_fn(
+            "insert_rect",
+            move |page: i64,
+                  x: Dynamic,
+                  y: Dynamic,
+                  width: Dynamic,
+                  height: Dynamic|
+                  -> Result<(), Box<EvalAltResult>> {
+                let mins = na::point![to_f64(x)?, to_f64(y)?];
+                let extents = na::vector![to_f64(width)?, to_f64(height)?];
+                let rectangle = Rectangle::from_p2d_aabb(Aabb::new_positive(mins, mins + extents));
+                s.borrow_mut()
+                    .insert_shape(page, Shape::Rectangle(rectangle))
+            },
+        );
+        let s = Rc::clone(&state);
+        rhai_engine.register_fn(
+            "insert_ellipse",
+            move |page: i64,
+                  cx: Dynamic,
+                  cy: Dynamic,
+                  rx: Dynamic,
+                  ry: Dynamic|
+                  -> Result<(), Box<EvalAltResult>> {
+                let ellipse = Ellipse {
+                    radii: na::vector![to_f64(rx)?.abs(), to_f64(ry)?.abs()],
+                    transform: Transform::new_w_isometry(na::Isometry2::new(
+                        na::vector![to_f64(cx)?, to_f64(cy)?],
+                        0.0,
+                    )),
+                };
+                s.borrow_mut().insert_shape(page, Shape::Ellipse(ellipse))
+            },
+        );
+        let s = Rc::clone(&state);
+        rhai_engine.register_fn(
+            "recolor",
+            move |id: i64, color: &str| -> Result<(), Box<EvalAltResult>> {
+                let mut state = s.borrow_mut();
+                let op = ScriptOp::Recolor(state.key(id)?, parse_color(color)?);
+                state.ops.push(op);
+                Ok(())
+            },
+        );
+        let s = Rc::clone(&state);
+        rhai_engine.register_fn("delete", move |id: i64| -> Result<(), Box<EvalAltResult>> {
+            let mut state = s.borrow_mut();
+            let op = ScriptOp::Delete(state.key(id)?);
+            state.ops.push(op);
+            Ok(())
+        });
+        let s = Rc::clone(&state);
+        rhai_engine.register_fn("export_doc", move |file_name: &str| {
+            s.borrow_mut()
+                .outcome
+                .export_requests
+                .push(file_name.to_string())
+        });
+
+        rhai_engine
+            .run(script)
+            .map_err(|e| anyhow::anyhow!("Running script failed, Err: {e}"))?;
+        // The engine holds clones of the state in the registered functions
+        drop(rhai_engine);
+        let state = Rc::try_unwrap(state)
+            .map_err(|_| anyhow::anyhow!("Script state is still referenced after running"))?
+            .into_inner();
+
+        let mut widget_flags = WidgetFlags::default();
+        if state.ops.is_empty() {
+            return Ok((state.outcome, widget_flags));
+        }
+        for op in state.ops {
+            match op {
+                ScriptOp::Insert(stroke) => {
+                    let key = self.store.insert_stroke(stroke, None);
+                    self.store.regenerate_rendering_for_stroke(
+                        key,
+                        self.camera.viewport(),
+                        self.camera.image_scale(),
+                    );
+                }
+                ScriptOp::Recolor(key, color) => {
+                    widget_flags.merge(self.store.change_stroke_colors(&[key], color));
+                }
+                ScriptOp::Delete(key) => {
+                    self.store.set_trashed_keys(&[key], true);
+                }
+            }
+        }
+        widget_flags.merge(self.current_pen_update_state());
+        widget_flags.merge(self.doc_resize_autoexpand());
+        widget_flags.merge(self.record(Instant::now()));
+        widget_flags.redraw = true;
+        widget_flags.resize = true;
+        widget_flags.store_modified = true;
+
+        Ok((state.outcome, widget_flags))
+    }
+}
@@ -0,0 +1,86 @@
+// Imports
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Provides spellchecking for the text of text strokes.
+pub trait SpellcheckProvider: std::fmt::Debug {
+    /// The language of the used dictionary, e.g. `en_US`.
+    fn language(&self) -> String;
+
+    /// Whether the word is spelled correctly.
+    fn check_word(&self, word: &str) -> bool;
+
+    /// Suggestions for the misspelled word, ordered with the best ones first.
+    fn suggestions(&self, word: &str) -> Vec<String>;
+
+    /// The byte ranges of all misspelled words in the text.
+    ///
+    /// Words without any alphabetic characters or containing digits are skipped.
+    fn misspelled_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        text.split_word_bound_indices()
+            .filter(|(_, word)| {
+                word.chars().any(|c| c.is_alphabetic()) && !word.chars().any(|c| c.is_numeric())
+            })
+            .filter(|(_, word)| !self.check_word(word))
+            .map(|(i, word)| i..i + word.len())
+            .collect()
+    }
+}
+
+/// A spellcheck provider backed by enchant, which in turn uses hunspell, aspell, etc. .
+#[cfg(feature = "spellcheck")]
+pub struct EnchantSpellcheck {
+    dict: enchant::Dict,
+}
+
+#[cfg(feature = "spellcheck")]
+impl std::fmt::Debug for EnchantSpellcheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EnchantSpellcheck")
+            .field("language", &self.dict.get_lang())
+            .finish()
+    }
+}
+
+#[cfg(feature = "spellcheck")]
+impl EnchantSpellcheck {
+    /// Request the dictionary for the language, e.g. `en_US`.
+    pub fn new(language: &str) -> anyhow::Result<Self> {
+        let mut broker = enchant::Broker::new();
+        let dict = broker.request_dict(language).map_err(|e| {
+            anyhow::anyhow!(
+                "Requesting enchant dictionary for language `{language}` failed, Err: {e}"
+            )
+        })?;
+        Ok(Self { dict })
+    }
+
+    /// The languages for which dictionaries are installed.
+    pub fn available_languages() -> Vec<String> {
+        let mut broker = enchant::Broker::new();
+        let mut languages = broker
+            .list_dicts()
+            .into_iter()
+            .map(|dict_info| dict_info.lang)
+            .collect::<Vec<String>>();
+        languages.sort();
+        languages.dedup();
+        languages
+    }
+}
+
+#[cfg(feature = "spellcheck")]
+impl SpellcheckProvider for EnchantSpellcheck {
+    fn language(&self) -> String {
+        self.dict.get_lang().to_string()
+    }
+
+    fn check_word(&self, word: &str) -> bool {
+        // Treat words the dictionary can't handle as correct, to avoid false underlines
+        self.dict.check(word).unwrap_or(true)
+    }
+
+    fn suggestions(&self, word: &str) -> Vec<String> {
+        self.dict.suggest(word)
+    }
+}
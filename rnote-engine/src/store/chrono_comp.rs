@@ -5,6 +5,7 @@ use rayon::slice::ParallelSliceMut;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq)]
 #[serde(rename = "stroke_layer")]
@@ -68,6 +69,11 @@ pub struct ChronoComponent {
     t: u32,
     #[serde(rename = "layer")]
     pub layer: StrokeLayer,
+    /// The time the stroke was created, in milliseconds since the unix epoch.
+    ///
+    /// None for strokes from documents saved before the timestamps were introduced.
+    #[serde(rename = "created")]
+    pub created: Option<u64>,
 }
 
 impl Default for ChronoComponent {
@@ -75,22 +81,36 @@ impl Default for ChronoComponent {
         Self {
             t: 0,
             layer: StrokeLayer::default(),
+            created: None,
         }
     }
 }
 
 impl ChronoComponent {
     pub fn new(t: u32, layer: StrokeLayer) -> Self {
-        Self { t, layer }
+        Self {
+            t,
+            layer,
+            created: Some(timestamp_now()),
+        }
     }
 }
 
+/// The current time in milliseconds since the unix epoch.
+pub fn timestamp_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
 /// Systems that are related to their chronological ordering.
 impl StrokeStore {
     pub fn update_chrono_to_last(&mut self, key: StrokeKey) {
         if let Some(chrono_comp) = Arc::make_mut(&mut self.chrono_components).get_mut(key) {
             self.chrono_counter += 1;
             Arc::make_mut(chrono_comp).t = self.chrono_counter;
+            self.mark_stroke_changed(key);
         } else {
             log::debug!(
                 "get chrono_comp in set_chrono_to_last() returned None for stroke with key {:?}",
@@ -99,60 +119,64 @@ impl StrokeStore {
         }
     }
 
-    /// Returns the keys in chronological order, as in first: gets drawn first, last: gets drawn last.
-    pub fn keys_sorted_chrono(&self) -> Vec<StrokeKey> {
-        let chrono_components = &self.chrono_components;
-
-        let mut keys = self.stroke_components.keys().collect::<Vec<StrokeKey>>();
+    /// The creation timestamp of the stroke, in milliseconds since the unix epoch.
+    pub fn created(&self, key: StrokeKey) -> Option<u64> {
+        self.chrono_components.get(key)?.created
+    }
 
-        keys.par_sort_unstable_by(|&first, &second| {
-            if let (Some(first_chrono), Some(second_chrono)) =
-                (chrono_components.get(first), chrono_components.get(second))
-            {
-                let layer_order = first_chrono.layer.cmp(&second_chrono.layer);
+    /// The sorted and deduplicated creation timestamps of all strokes that are not trashed.
+    pub fn created_timestamps(&self) -> Vec<u64> {
+        let mut timestamps = self
+            .stroke_components
+            .keys()
+            .filter(|&key| !(self.trashed(key).unwrap_or(false)))
+            .filter_map(|key| self.created(key))
+            .collect::<Vec<u64>>();
+        timestamps.sort_unstable();
+        timestamps.dedup();
+        timestamps
+    }
 
-                if layer_order != std::cmp::Ordering::Equal {
-                    layer_order
-                } else {
-                    first_chrono.t.cmp(&second_chrono.t)
-                }
-            } else {
-                std::cmp::Ordering::Equal
-            }
-        });
+    /// Whether the stroke is visible at the current playback time.
+    ///
+    /// Strokes without a creation timestamp are always visible.
+    pub fn visible_at_playback_time(&self, key: StrokeKey) -> bool {
+        match (self.playback_time, self.created(key)) {
+            (Some(playback_time), Some(created)) => created <= playback_time,
+            _ => true,
+        }
+    }
 
+    /// Returns the keys in chronological order, as in first: gets drawn first, last: gets drawn last.
+    pub fn keys_sorted_chrono(&self) -> Vec<StrokeKey> {
+        let mut keys = self.stroke_components.keys().collect::<Vec<StrokeKey>>();
+        self.sort_keys_chrono(&mut keys);
         keys
     }
 
     pub fn keys_sorted_chrono_intersecting_bounds(&self, bounds: Aabb) -> Vec<StrokeKey> {
-        let chrono_components = &self.chrono_components;
-
         let mut keys = self.key_tree.keys_intersecting_bounds(bounds);
+        self.sort_keys_chrono(&mut keys);
+        keys
+    }
 
-        keys.par_sort_unstable_by(|&first, &second| {
-            if let (Some(first_chrono), Some(second_chrono)) =
-                (chrono_components.get(first), chrono_components.get(second))
-            {
-                let layer_order = first_chrono.layer.cmp(&second_chrono.layer);
-
-                if layer_order != std::cmp::Ordering::Equal {
-                    layer_order
-                } else {
-                    first_chrono.t.cmp(&second_chrono.t)
-                }
-            } else {
-                std::cmp::Ordering::Equal
-            }
-        });
+    pub fn keys_sorted_chrono_in_bounds(&self, bounds: Aabb) -> Vec<StrokeKey> {
+        let mut keys = self.key_tree.keys_in_bounds(bounds);
+        self.sort_keys_chrono(&mut keys);
+        keys
+    }
 
+    /// The keys of the strokes whose bounds contain the point, in chronological order.
+    pub fn keys_sorted_chrono_containing_point(&self, point: na::Point2<f64>) -> Vec<StrokeKey> {
+        let mut keys = self.key_tree.keys_containing_point(point);
+        self.sort_keys_chrono(&mut keys);
         keys
     }
 
-    pub fn keys_sorted_chrono_in_bounds(&self, bounds: Aabb) -> Vec<StrokeKey> {
+    /// Sort the keys by their layer first, then by their chrono order.
+    fn sort_keys_chrono(&self, keys: &mut [StrokeKey]) {
         let chrono_components = &self.chrono_components;
 
-        let mut keys = self.key_tree.keys_in_bounds(bounds);
-
         keys.par_sort_unstable_by(|&first, &second| {
             if let (Some(first_chrono), Some(second_chrono)) =
                 (chrono_components.get(first), chrono_components.get(second))
@@ -168,7 +192,5 @@ impl StrokeStore {
                 std::cmp::Ordering::Equal
             }
         });
-
-        keys
     }
 }
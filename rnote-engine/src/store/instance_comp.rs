@@ -0,0 +1,247 @@
+// Imports
+use super::{StrokeKey, StrokeStore};
+use crate::strokes::{Stroke, StrokeBehaviour};
+use rnote_compose::shapes::ShapeBehaviour;
+use rnote_compose::transform::TransformBehaviour;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// The transform of an instance in relation to its master stroke.
+///
+/// It is applied to the master in the order: scale, rotate (both around the origin), translate.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "instance_transform")]
+pub struct InstanceTransform {
+    #[serde(rename = "scale")]
+    pub scale: na::Vector2<f64>,
+    #[serde(rename = "rotation")]
+    pub rotation: f64,
+    #[serde(rename = "translation")]
+    pub translation: na::Vector2<f64>,
+}
+
+impl Default for InstanceTransform {
+    fn default() -> Self {
+        Self {
+            scale: na::Vector2::repeat(1.0),
+            rotation: 0.0,
+            translation: na::Vector2::zeros(),
+        }
+    }
+}
+
+impl InstanceTransform {
+    const EPSILON: f64 = 1e-9;
+
+    /// The transform as homogeneous matrix.
+    pub fn to_matrix(&self) -> na::Matrix3<f64> {
+        na::Matrix3::new_translation(&self.translation)
+            * na::Rotation2::new(self.rotation).to_homogeneous()
+            * na::Matrix3::new_nonuniform_scaling(&self.scale)
+    }
+
+    /// Decompose a homogeneous matrix into a transform.
+    ///
+    /// Returns None when the matrix is degenerate or contains shearing,
+    /// which can't be represented by stroke transformations.
+    pub fn from_matrix(matrix: &na::Matrix3<f64>) -> Option<Self> {
+        let col0 = na::vector![matrix[(0, 0)], matrix[(1, 0)]];
+        let col1 = na::vector![matrix[(0, 1)], matrix[(1, 1)]];
+        let scale_x = col0.norm();
+        if scale_x < Self::EPSILON
+            || col0.dot(&col1).abs() > Self::EPSILON.sqrt() * scale_x * col1.norm()
+        {
+            return None;
+        }
+        // A negative determinant means the instance is mirrored
+        let scale_y = (col0[0] * col1[1] - col0[1] * col1[0]) / scale_x;
+        if scale_y.abs() < Self::EPSILON {
+            return None;
+        }
+        Some(Self {
+            scale: na::vector![scale_x, scale_y],
+            rotation: col0[1].atan2(col0[0]),
+            translation: na::vector![matrix[(0, 2)], matrix[(1, 2)]],
+        })
+    }
+
+    /// Apply the transform to a stroke.
+    pub fn apply(&self, stroke: &mut Stroke) {
+        stroke.scale(self.scale);
+        stroke.rotate(self.rotation, na::Point2::origin());
+        stroke.translate(self.translation);
+    }
+}
+
+/// Links a stroke as instance to a master stroke.
+///
+/// The stroke of an instance is derived from its master and regenerated every time the geometry of the master is updated.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default, rename = "instance_component")]
+pub struct InstanceComponent {
+    #[serde(rename = "master")]
+    pub master: StrokeKey,
+    #[serde(rename = "transform")]
+    pub transform: InstanceTransform,
+}
+
+impl InstanceComponent {
+    /// Generate the stroke of the instance from the stroke of its master.
+    pub fn gen_instance_stroke(&self, master: &Stroke) -> Stroke {
+        let mut stroke = master.clone();
+        self.transform.apply(&mut stroke);
+        stroke.update_geometry();
+        stroke
+    }
+}
+
+impl StrokeStore {
+    /// The master of the instance with the given key.
+    pub fn instance_master(&self, key: StrokeKey) -> Option<StrokeKey> {
+        self.instance_components.get(key).map(|comp| comp.master)
+    }
+
+    /// The keys of the instances linked to the master.
+    pub fn instances_of(&self, master: StrokeKey) -> Vec<StrokeKey> {
+        self.instance_components
+            .iter()
+            .filter_map(|(key, comp)| (comp.master == master).then_some(key))
+            .collect()
+    }
+
+    /// Duplicate the selected keys as instances that are linked to the originals, and offset them.
+    ///
+    /// Duplicated instances are linked to the same master. The duplicates become the selection.
+    /// The returned, duplicated strokes then need to update their geometry and rendering.
+    pub fn duplicate_selection_linked(&mut self, offset: na::Vector2<f64>) -> Vec<StrokeKey> {
+        let old_selected = self.selection_keys_as_rendered();
+        let new_selected = self.duplicate_selection_w_offset(offset);
+
+        for (&old_key, &new_key) in old_selected.iter().zip(new_selected.iter()) {
+            let mut instance_comp = match self.instance_components.get(old_key) {
+                Some(old_instance_comp) => **old_instance_comp,
+                None => InstanceComponent {
+                    master: old_key,
+                    transform: InstanceTransform::default(),
+                },
+            };
+            instance_comp.transform.translation += offset;
+            Arc::make_mut(&mut self.instance_components).insert(new_key, Arc::new(instance_comp));
+        }
+
+        new_selected
+    }
+
+    /// Unlink the strokes with the given keys, so that they become independent strokes.
+    ///
+    /// When a key is a master, all its instances are unlinked.
+    pub fn unlink_instances(&mut self, keys: &[StrokeKey]) {
+        if self.instance_components.is_empty() {
+            return;
+        }
+        let instance_comps = Arc::make_mut(&mut self.instance_components);
+        instance_comps.retain(|key, comp| !keys.contains(&key) && !keys.contains(&comp.master));
+    }
+
+    /// Regenerate the instances of the given keys that are masters from the current master strokes.
+    ///
+    /// The instances then need to update their rendering.
+    pub(super) fn update_instances_of_masters(&mut self, keys: &[StrokeKey]) {
+        if self.instance_components.is_empty() {
+            return;
+        }
+        let mut instances_by_master =
+            HashMap::<StrokeKey, Vec<(StrokeKey, InstanceComponent)>>::new();
+        for (key, comp) in self.instance_components.iter() {
+            instances_by_master
+                .entry(comp.master)
+                .or_default()
+                .push((key, **comp));
+        }
+
+        for master in keys {
+            let (Some(instances), Some(master_stroke)) = (
+                instances_by_master.get(master),
+                self.stroke_components.get(*master).cloned(),
+            ) else {
+                continue;
+            };
+            for (instance_key, comp) in instances {
+                let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
+                    .get_mut(*instance_key)
+                    .map(Arc::make_mut)
+                else {
+                    continue;
+                };
+                *stroke = comp.gen_instance_stroke(&master_stroke);
+                self.key_tree
+                    .update_with_key(*instance_key, stroke.bounds());
+                self.set_rendering_dirty(*instance_key);
+            }
+        }
+    }
+
+    /// Track the translation of strokes in the transforms of affected instances.
+    pub(super) fn instances_translated(&mut self, keys: &[StrokeKey], offset: na::Vector2<f64>) {
+        let matrix = na::Matrix3::new_translation(&offset);
+        self.track_instance_transforms(keys, |_| matrix);
+    }
+
+    /// Track the rotation of strokes in the transforms of affected instances.
+    pub(super) fn instances_rotated(
+        &mut self,
+        keys: &[StrokeKey],
+        angle: f64,
+        center: na::Point2<f64>,
+    ) {
+        let matrix = na::Matrix3::new_translation(&center.coords)
+            * na::Rotation2::new(angle).to_homogeneous()
+            * na::Matrix3::new_translation(&-center.coords);
+        self.track_instance_transforms(keys, |_| matrix);
+    }
+
+    /// Track the scaling (around the origin) of strokes in the transforms of affected instances.
+    pub(super) fn instances_scaled(&mut self, keys: &[StrokeKey], scale: na::Vector2<f64>) {
+        let matrix = na::Matrix3::new_nonuniform_scaling(&scale);
+        self.track_instance_transforms(keys, |_| matrix);
+    }
+
+    /// Track the transformation of strokes, given as a homogeneous matrix per key,
+    /// in the transforms of affected instances.
+    ///
+    /// When the master of an instance is transformed as well, its transformation is factored out,
+    /// so that regenerating the instance from the transformed master results in the transformed instance.
+    /// Instances where the resulting transform can't be represented get unlinked.
+    pub(super) fn track_instance_transforms(
+        &mut self,
+        keys: &[StrokeKey],
+        matrix: impl Fn(StrokeKey) -> na::Matrix3<f64>,
+    ) {
+        if self.instance_components.is_empty() {
+            return;
+        }
+        let keys_set = keys.iter().copied().collect::<HashSet<StrokeKey>>();
+        let instance_comps = Arc::make_mut(&mut self.instance_components);
+        for &key in keys {
+            let Some(comp) = instance_comps.get_mut(key).map(Arc::make_mut) else {
+                continue;
+            };
+            let master_inverse = if keys_set.contains(&comp.master) {
+                matrix(comp.master).try_inverse()
+            } else {
+                Some(na::Matrix3::identity())
+            };
+            match master_inverse.and_then(|master_inverse| {
+                InstanceTransform::from_matrix(
+                    &(matrix(key) * comp.transform.to_matrix() * master_inverse),
+                )
+            }) {
+                Some(transform) => comp.transform = transform,
+                None => {
+                    instance_comps.remove(key);
+                }
+            }
+        }
+    }
+}
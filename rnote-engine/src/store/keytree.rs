@@ -1,7 +1,8 @@
 // Imports
 use super::StrokeKey;
-use p2d::bounding_volume::Aabb;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rstar::primitives::GeomWithData;
+use slotmap::SecondaryMap;
 
 /// The rtree object that holds the bounds and [StrokeKey].
 type KeyTreeObject = GeomWithData<rstar::primitives::Rectangle<[f64; 2]>, StrokeKey>;
@@ -9,33 +10,47 @@ type KeyTreeObject = GeomWithData<rstar::primitives::Rectangle<[f64; 2]>, Stroke
 #[derive(Debug, Default)]
 /// A Rtree with [StrokeKey]'s as associated data.
 ///
-/// Used for faster spatial queries.
-pub(super) struct KeyTree(rstar::RTree<KeyTreeObject, rstar::DefaultParams>);
+/// Used for faster spatial queries. The bounds of the inserted objects are tracked by their key,
+/// so updating and removing objects only needs to descend the tree instead of searching through all objects.
+pub(super) struct KeyTree {
+    tree: rstar::RTree<KeyTreeObject, rstar::DefaultParams>,
+    bounds: SecondaryMap<StrokeKey, Aabb>,
+    /// The union of the bounds of the objects that were inserted or removed since it was last taken.
+    changed_bounds: Option<Aabb>,
+}
 
 impl KeyTree {
     /// Insert a new tree object with the given [StrokeKey] and bounds.
+    ///
+    /// Replaces the object if one with the key is already present.
     pub fn insert_with_key(&mut self, key: StrokeKey, bounds: Aabb) {
-        self.0.insert(new_keytree_object(key, bounds));
+        self.remove_with_key(key);
+        self.tree.insert(new_keytree_object(key, bounds));
+        self.bounds.insert(key, bounds);
+        self.mark_changed(bounds);
     }
 
     /// Removes the [KeyTreeObject] for the given key.
     pub fn remove_with_key(&mut self, key: StrokeKey) -> Option<KeyTreeObject> {
-        let object_to_remove = self.0.iter().find(|&object| object.data == key)?.to_owned();
+        let bounds = self.bounds.remove(key)?;
+        self.mark_changed(bounds);
 
-        self.0.remove(&object_to_remove)
+        self.tree.remove(&new_keytree_object(key, bounds))
     }
 
     /// Update the Tree with new bounds for the given key.
     ///
     /// Has to be called when the geometry of the stroke has changed.
     pub fn update_with_key(&mut self, key: StrokeKey, new_bounds: Aabb) {
-        self.remove_with_key(key);
+        if self.bounds.get(key) == Some(&new_bounds) {
+            return;
+        }
         self.insert_with_key(key, new_bounds);
     }
 
     /// Return the keys that intersect with the given bounds.
     pub fn keys_intersecting_bounds(&self, bounds: Aabb) -> Vec<StrokeKey> {
-        self.0
+        self.tree
             .locate_in_envelope_intersecting(&rstar::AABB::from_corners(
                 [bounds.mins[0], bounds.mins[1]],
                 [bounds.maxs[0], bounds.maxs[1]],
@@ -46,7 +61,7 @@ impl KeyTree {
 
     /// Return the keys that are completely contained in the given bounds.
     pub fn keys_in_bounds(&self, bounds: Aabb) -> Vec<StrokeKey> {
-        self.0
+        self.tree
             .locate_in_envelope(&rstar::AABB::from_corners(
                 [bounds.mins[0], bounds.mins[1]],
                 [bounds.maxs[0], bounds.maxs[1]],
@@ -55,19 +70,64 @@ impl KeyTree {
             .collect()
     }
 
+    /// Return the keys whose bounds contain the given point.
+    pub fn keys_containing_point(&self, point: na::Point2<f64>) -> Vec<StrokeKey> {
+        self.tree
+            .locate_all_at_point(&[point[0], point[1]])
+            .map(|object| object.data)
+            .collect()
+    }
+
     /// Rebuild the entire rtree from the given Vec of (key, bounds).
     pub fn rebuild_from_vec(&mut self, strokes: Vec<(StrokeKey, Aabb)>) {
+        let changed = self
+            .bounds
+            .values()
+            .chain(strokes.iter().map(|(_, bounds)| bounds))
+            .copied()
+            .reduce(|acc, bounds| acc.merged(&bounds));
+        if let Some(changed) = changed {
+            self.mark_changed(changed);
+        }
+        self.bounds = strokes.iter().copied().collect();
         let objects = strokes
             .into_iter()
             .map(|(key, bounds)| new_keytree_object(key, bounds))
             .collect();
 
-        self.0 = rstar::RTree::bulk_load(objects);
+        self.tree = rstar::RTree::bulk_load(objects);
+    }
+
+    /// The union of the bounds of the objects that were inserted or removed since they were last taken.
+    pub fn changed_bounds(&self) -> Option<Aabb> {
+        self.changed_bounds
+    }
+
+    /// Take the union of the bounds of the objects that were inserted or removed.
+    pub fn take_changed_bounds(&mut self) -> Option<Aabb> {
+        self.changed_bounds.take()
     }
 
     ///  Clear the entire tree.
     pub fn clear(&mut self) {
-        *self = Self::default()
+        let changed_bounds = self.tree_bounds();
+        *self = Self::default();
+        self.changed_bounds = changed_bounds;
+    }
+
+    fn tree_bounds(&self) -> Option<Aabb> {
+        self.bounds
+            .values()
+            .copied()
+            .chain(self.changed_bounds)
+            .reduce(|acc, bounds| acc.merged(&bounds))
+    }
+
+    fn mark_changed(&mut self, bounds: Aabb) {
+        self.changed_bounds = Some(match self.changed_bounds {
+            Some(changed) => changed.merged(&bounds),
+            None => bounds,
+        });
     }
 }
 
@@ -1,5 +1,6 @@
 // Modules
 pub mod chrono_comp;
+pub mod instance_comp;
 pub mod keytree;
 pub mod render_comp;
 pub mod selection_comp;
@@ -8,6 +9,7 @@ pub mod trash_comp;
 
 // Re-exports
 pub use chrono_comp::ChronoComponent;
+pub use instance_comp::{InstanceComponent, InstanceTransform};
 use keytree::KeyTree;
 pub use render_comp::RenderComponent;
 pub use selection_comp::SelectionComponent;
@@ -18,6 +20,7 @@ use self::chrono_comp::StrokeLayer;
 use crate::engine::EngineSnapshot;
 use crate::strokes::Stroke;
 use crate::WidgetFlags;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::shapes::ShapeBehaviour;
 use serde::{Deserialize, Serialize};
 use slotmap::{HopSlotMap, SecondaryMap};
@@ -38,8 +41,13 @@ pub struct HistoryEntry {
     pub trash_components: Arc<SecondaryMap<StrokeKey, Arc<TrashComponent>>>,
     #[serde(rename = "chrono_components")]
     pub chrono_components: Arc<SecondaryMap<StrokeKey, Arc<ChronoComponent>>>,
+    #[serde(rename = "instance_components")]
+    pub instance_components: Arc<SecondaryMap<StrokeKey, Arc<InstanceComponent>>>,
     #[serde(rename = "chrono_counter")]
     pub chrono_counter: u32,
+    /// The document height, only recorded around page operations that change it.
+    #[serde(rename = "doc_height")]
+    pub doc_height: Option<f64>,
 }
 
 impl Default for HistoryEntry {
@@ -48,8 +56,10 @@ impl Default for HistoryEntry {
             stroke_components: Arc::new(HopSlotMap::with_key()),
             trash_components: Arc::new(SecondaryMap::new()),
             chrono_components: Arc::new(SecondaryMap::new()),
+            instance_components: Arc::new(SecondaryMap::new()),
 
             chrono_counter: 0,
+            doc_height: None,
         }
     }
 }
@@ -61,6 +71,7 @@ impl Default for HistoryEntry {
 ///     * 'trash_components': Holds state whether the strokes are trashed
 ///     * 'selection_components': Holds state whether the strokes are selected
 ///     * 'chrono_components': Holds state about the chronological ordering
+///     * 'instance_components': Holds the links of instances to their master strokes, only present for instances
 ///     * 'render_components': Holds state about the rendering.
 ///
 /// The systems are implemented as methods on StrokesStore, loosely categorized to the different components (but often modify others as well).
@@ -81,6 +92,8 @@ pub struct StrokeStore {
     selection_components: Arc<SecondaryMap<StrokeKey, Arc<SelectionComponent>>>,
     #[serde(rename = "chrono_components")]
     chrono_components: Arc<SecondaryMap<StrokeKey, Arc<ChronoComponent>>>,
+    #[serde(rename = "instance_components")]
+    instance_components: Arc<SecondaryMap<StrokeKey, Arc<InstanceComponent>>>,
     /// Incrementing counter for chrono_components.
     ///
     /// Value must be kept equal to the [ChronoComponent] of the newest inserted or modified stroke.
@@ -98,6 +111,23 @@ pub struct StrokeStore {
     /// Needs to be updated with `update_with_key()` when strokes changed their geometry or position!
     #[serde(skip)]
     key_tree: KeyTree,
+    /// The offset of the last duplication, re-applied when repeating it.
+    #[serde(skip)]
+    last_duplicate_offset: na::Vector2<f64>,
+    /// When set, only strokes created at or before this time (in milliseconds since the unix epoch) are drawn.
+    ///
+    /// Used to review how the document evolved, without altering the store.
+    #[serde(skip)]
+    pub(crate) playback_time: Option<u64>,
+    /// The union of the bounds of strokes whose rendering changed, in addition to the changes tracked by the key tree.
+    ///
+    /// Used to invalidate renderings that are composed from multiple strokes.
+    #[serde(skip)]
+    changed_bounds: Option<Aabb>,
+    /// Whether downgraded renderings are rendered again in full resolution, set when the render cache budget has room
+    /// for them.
+    #[serde(skip)]
+    rerender_downgraded: bool,
 }
 
 impl Default for StrokeStore {
@@ -107,6 +137,7 @@ impl Default for StrokeStore {
             trash_components: Arc::new(SecondaryMap::new()),
             selection_components: Arc::new(SecondaryMap::new()),
             chrono_components: Arc::new(SecondaryMap::new()),
+            instance_components: Arc::new(SecondaryMap::new()),
             render_components: SecondaryMap::new(),
 
             // Start off with state in the history
@@ -114,6 +145,10 @@ impl Default for StrokeStore {
             live_index: 0,
 
             key_tree: KeyTree::default(),
+            last_duplicate_offset: Stroke::IMPORT_OFFSET_DEFAULT,
+            playback_time: None,
+            changed_bounds: None,
+            rerender_downgraded: true,
 
             chrono_counter: 0,
         }
@@ -124,6 +159,37 @@ impl StrokeStore {
     /// Max length of the history.
     pub(crate) const HISTORY_MAX_LEN: usize = 100;
 
+    /// The union of the bounds of the strokes that were changed since the bounds were last taken.
+    pub(crate) fn changed_bounds(&self) -> Option<Aabb> {
+        match (self.changed_bounds, self.key_tree.changed_bounds()) {
+            (Some(a), Some(b)) => Some(a.merged(&b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Take the union of the bounds of the strokes that were changed.
+    pub(crate) fn take_changed_bounds(&mut self) -> Option<Aabb> {
+        let changed_bounds = self.changed_bounds();
+        self.changed_bounds = None;
+        self.key_tree.take_changed_bounds();
+        changed_bounds
+    }
+
+    /// Mark the current bounds of the stroke as changed.
+    pub(crate) fn mark_stroke_changed(&mut self, key: StrokeKey) {
+        let Some(bounds) = self
+            .stroke_components
+            .get(key)
+            .map(|stroke| stroke.bounds())
+        else {
+            return;
+        };
+        self.changed_bounds = Some(match self.changed_bounds {
+            Some(changed) => changed.merged(&bounds),
+            None => bounds,
+        });
+    }
+
     /// Import from a engine snapshot. A loaded strokes store should always be imported with this method.
     ///
     /// The store then needs to update its rendering.
@@ -133,6 +199,7 @@ impl StrokeStore {
         widget_flags.merge(self.clear());
         self.stroke_components = Arc::clone(&snapshot.stroke_components);
         self.chrono_components = Arc::clone(&snapshot.chrono_components);
+        self.instance_components = Arc::clone(&snapshot.instance_components);
         self.chrono_counter = snapshot.chrono_counter;
 
         self.update_geometry_for_strokes(&self.keys_unordered());
@@ -157,10 +224,14 @@ impl StrokeStore {
 
     /// Checks the equality of current state to all fields of the given history entry,
     /// doing pointer compares when they are wrapped inside Arc's.
-    fn eq_w_history_entry(&self, history_entry: &HistoryEntry) -> bool {
+    pub(crate) fn eq_w_history_entry(&self, history_entry: &HistoryEntry) -> bool {
         Arc::ptr_eq(&self.stroke_components, &history_entry.stroke_components)
             && Arc::ptr_eq(&self.trash_components, &history_entry.trash_components)
             && Arc::ptr_eq(&self.chrono_components, &history_entry.chrono_components)
+            && Arc::ptr_eq(
+                &self.instance_components,
+                &history_entry.instance_components,
+            )
             && self.chrono_counter == history_entry.chrono_counter
     }
 
@@ -170,7 +241,9 @@ impl StrokeStore {
             stroke_components: Arc::clone(&self.stroke_components),
             trash_components: Arc::clone(&self.trash_components),
             chrono_components: Arc::clone(&self.chrono_components),
+            instance_components: Arc::clone(&self.instance_components),
             chrono_counter: self.chrono_counter,
+            doc_height: None,
         }
     }
 
@@ -179,6 +252,7 @@ impl StrokeStore {
         self.stroke_components = Arc::clone(&history_entry.stroke_components);
         self.trash_components = Arc::clone(&history_entry.trash_components);
         self.chrono_components = Arc::clone(&history_entry.chrono_components);
+        self.instance_components = Arc::clone(&history_entry.instance_components);
         self.chrono_counter = history_entry.chrono_counter;
 
         // Since we don't store the rtree in the history, we need to rebuild it.
@@ -202,18 +276,8 @@ impl StrokeStore {
             .map(|last| !self.eq_w_history_entry(last))
             .unwrap_or(true)
         {
-            // as soon as the current state is recorded, remove the future
-            self.history.truncate(self.live_index + 1);
-
             let current = self.create_history_entry();
-            self.history.push_back(current);
-            self.live_index += 1;
-
-            // truncate history if necessary
-            while self.history.len() > Self::HISTORY_MAX_LEN {
-                self.history.pop_front();
-                self.live_index -= 1;
-            }
+            self.push_history_entry(current);
         } else {
             log::debug!("state has not changed, no need to record");
         }
@@ -224,6 +288,59 @@ impl StrokeStore {
         widget_flags
     }
 
+    /// Record the current state together with the document height, for operations that change the height.
+    ///
+    /// The height before the operation is saved in the live history entry, so undo and redo can restore both heights.
+    /// The state is recorded even when only the height has changed.
+    pub(crate) fn record_w_doc_height(
+        &mut self,
+        doc_height_before: f64,
+        doc_height: f64,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        if let Some(live) = self.history.get_mut(self.live_index) {
+            live.doc_height = Some(doc_height_before);
+        }
+        if doc_height != doc_height_before
+            || self
+                .history
+                .back()
+                .map(|last| !self.eq_w_history_entry(last))
+                .unwrap_or(true)
+        {
+            let mut current = self.create_history_entry();
+            current.doc_height = Some(doc_height);
+            self.push_history_entry(current);
+        } else {
+            log::debug!("state and document height have not changed, no need to record");
+        }
+
+        widget_flags.hide_undo = Some(!self.can_undo());
+        widget_flags.hide_redo = Some(!self.can_redo());
+
+        widget_flags
+    }
+
+    fn push_history_entry(&mut self, history_entry: HistoryEntry) {
+        // as soon as the current state is recorded, remove the future
+        self.history.truncate(self.live_index + 1);
+
+        self.history.push_back(history_entry);
+        self.live_index += 1;
+
+        // truncate history if necessary
+        while self.history.len() > Self::HISTORY_MAX_LEN {
+            self.history.pop_front();
+            self.live_index -= 1;
+        }
+    }
+
+    /// The document height saved in the live history entry, if it was recorded around a page operation.
+    pub(crate) fn live_doc_height(&self) -> Option<f64> {
+        self.history.get(self.live_index)?.doc_height
+    }
+
     /// Update the state of the latest history entry with the current document state.
     pub(crate) fn update_latest_history_entry(&mut self, _now: Instant) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
@@ -237,7 +354,8 @@ impl StrokeStore {
             // as soon as the current state is recorded, remove the future
             self.history.truncate(self.live_index + 1);
 
-            let current = self.create_history_entry();
+            let mut current = self.create_history_entry();
+            current.doc_height = self.history[self.live_index].doc_height;
             self.history[self.live_index] = current;
         } else {
             log::debug!("state has not changed, no need to update current state to history");
@@ -341,6 +459,7 @@ impl StrokeStore {
         Arc::make_mut(&mut self.trash_components).remove(key);
         Arc::make_mut(&mut self.selection_components).remove(key);
         Arc::make_mut(&mut self.chrono_components).remove(key);
+        self.unlink_instances(&[key]);
         self.render_components.remove(key);
 
         self.key_tree.remove_with_key(key);
@@ -355,8 +474,10 @@ impl StrokeStore {
         Arc::make_mut(&mut self.trash_components).clear();
         Arc::make_mut(&mut self.selection_components).clear();
         Arc::make_mut(&mut self.chrono_components).clear();
+        Arc::make_mut(&mut self.instance_components).clear();
 
         self.chrono_counter = 0;
+        self.playback_time = None;
         let widget_flags = self.clear_history(HistoryEntry::default());
 
         self.render_components.clear();
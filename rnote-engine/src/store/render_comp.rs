@@ -21,6 +21,10 @@ pub enum RenderCompState {
     ForViewport(Aabb),
     BusyRenderingInTask,
     Dirty,
+    /// The images were downgraded to a lower resolution to stay within the render cache budget.
+    ///
+    /// Holds the viewport when the rendering was only generated for it.
+    Downgraded(Option<Aabb>),
 }
 
 impl Default for RenderCompState {
@@ -29,11 +33,30 @@ impl Default for RenderCompState {
     }
 }
 
+/// The state of rendering a stroke directly on the snapshot, bypassing its cached images.
+///
+/// Used for strokes that are currently drawn, where regenerating images for every new segment would be too slow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveRenderState {
+    Inactive,
+    /// The stroke is currently drawn.
+    Drawing,
+    /// The stroke is finished and is rendered directly until its images are regenerated.
+    AwaitingImages,
+}
+
+impl Default for LiveRenderState {
+    fn default() -> Self {
+        Self::Inactive
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RenderComponent {
     pub(super) images: Vec<render::Image>,
     pub(super) rendernodes: Vec<gsk::RenderNode>,
     pub(super) state: RenderCompState,
+    pub(super) live: LiveRenderState,
 }
 
 impl Default for RenderComponent {
@@ -42,6 +65,16 @@ impl Default for RenderComponent {
             state: RenderCompState::default(),
             images: vec![],
             rendernodes: vec![],
+            live: LiveRenderState::default(),
+        }
+    }
+}
+
+impl RenderComponent {
+    /// Stops rendering directly once the images of a finished live stroke are regenerated.
+    fn end_live_rendering(&mut self) {
+        if self.live == LiveRenderState::AwaitingImages {
+            self.live = LiveRenderState::Inactive;
         }
     }
 }
@@ -82,6 +115,7 @@ impl StrokeStore {
         if let Some(render_comp) = self.render_components.get_mut(key) {
             render_comp.state = RenderCompState::Dirty;
         }
+        self.mark_stroke_changed(key);
     }
 
     pub fn set_rendering_dirty_for_strokes(&mut self, keys: &[StrokeKey]) {
@@ -153,6 +187,7 @@ impl StrokeStore {
                             render_comp.rendernodes = rendernodes;
                             render_comp.images = images;
                             render_comp.state = RenderCompState::ForViewport(viewport);
+                            render_comp.end_live_rendering();
                         }
                         Err(e) => {
                             render_comp.state = RenderCompState::Dirty;
@@ -166,6 +201,7 @@ impl StrokeStore {
                             render_comp.rendernodes = rendernodes;
                             render_comp.images = images;
                             render_comp.state = RenderCompState::Complete;
+                            render_comp.end_live_rendering();
                         }
                         Err(e) => {
                             render_comp.state = RenderCompState::Dirty;
@@ -260,6 +296,7 @@ impl StrokeStore {
         image_scale: f64,
     ) {
         let keys = self.render_components.keys().collect::<Vec<StrokeKey>>();
+        let rerender_downgraded = self.rerender_downgraded;
 
         for key in keys {
             if let (Some(stroke), Some(render_comp)) = (
@@ -283,24 +320,32 @@ impl StrokeStore {
 
                 // only check if rerendering is not forced
                 if !force_regenerate {
+                    // We don't skip if we pass the threshold in relation to the margin, so the stroke gets rerendered in time. between 0.0 and 1.0
+                    const SKIP_RERENDER_MARGIN_THRESHOLD: f64 = 0.7;
+                    let within_margin_threshold = |old_viewport: Aabb| {
+                        let diff = (old_viewport.center().coords - viewport.center().coords).abs();
+                        diff[0] < viewport_render_margins[0] * SKIP_RERENDER_MARGIN_THRESHOLD
+                            && diff[1] < viewport_render_margins[1] * SKIP_RERENDER_MARGIN_THRESHOLD
+                    };
                     match render_comp.state {
                         RenderCompState::Complete | RenderCompState::BusyRenderingInTask => {
                             continue;
                         }
                         RenderCompState::ForViewport(old_viewport) => {
-                            // We don't skip if we pass the threshold in relation to the margin, so the stroke gets rerendered in time. between 0.0 and 1.0
-                            const SKIP_RERENDER_MARGIN_THRESHOLD: f64 = 0.7;
-                            let diff =
-                                (old_viewport.center().coords - viewport.center().coords).abs();
-                            if diff[0] < viewport_render_margins[0] * SKIP_RERENDER_MARGIN_THRESHOLD
-                                && diff[1]
-                                    < viewport_render_margins[1] * SKIP_RERENDER_MARGIN_THRESHOLD
-                            {
+                            if within_margin_threshold(old_viewport) {
                                 // We don't update the state, to have the old bounds on the next call
                                 // so the rendering is only updated after it crossed the margin threshold
                                 continue;
                             }
                         }
+                        RenderCompState::Downgraded(old_viewport) => {
+                            // Downgraded renderings are kept until the render cache budget has room again
+                            if !rerender_downgraded
+                                && old_viewport.map_or(true, within_margin_threshold)
+                            {
+                                continue;
+                            }
+                        }
                         RenderCompState::Dirty => {}
                     }
                 }
@@ -329,6 +374,128 @@ impl StrokeStore {
         }
     }
 
+    /// The keys of the strokes that hold rendering images.
+    pub fn keys_w_rendering_images(&self) -> Vec<StrokeKey> {
+        self.render_components
+            .iter()
+            .filter_map(|(key, render_comp)| (!render_comp.images.is_empty()).then_some(key))
+            .collect()
+    }
+
+    /// The number of bytes held by the rendering images of the stroke.
+    pub fn rendering_images_bytes(&self, key: StrokeKey) -> usize {
+        self.render_components
+            .get(key)
+            .map(|render_comp| {
+                render_comp
+                    .images
+                    .iter()
+                    .map(|image| image.data.len())
+                    .sum()
+            })
+            .unwrap_or(0)
+    }
+
+    /// The number of bytes held by the downgraded rendering images.
+    pub fn downgraded_rendering_bytes(&self) -> usize {
+        self.render_components
+            .values()
+            .filter(|render_comp| matches!(render_comp.state, RenderCompState::Downgraded(_)))
+            .flat_map(|render_comp| render_comp.images.iter())
+            .map(|image| image.data.len())
+            .sum()
+    }
+
+    /// Set whether downgraded renderings are rendered again in full resolution
+    /// when the rendering in the viewport is regenerated.
+    pub(crate) fn set_rerender_downgraded(&mut self, rerender_downgraded: bool) {
+        self.rerender_downgraded = rerender_downgraded;
+    }
+
+    /// Halve the resolution of the rendering images of the stroke.
+    ///
+    /// Only complete renderings are downgraded, and only once. They are marked as downgraded,
+    /// so they are rendered again in full resolution when the render cache budget has room for them.
+    /// Returns the number of freed bytes.
+    pub fn downgrade_rendering(&mut self, key: StrokeKey) -> usize {
+        /// Images are not downgraded below this size in pixels.
+        const DOWNGRADE_MIN_PIXEL_SIZE: u32 = 16;
+
+        let Some(render_comp) = self.render_components.get_mut(key) else {
+            return 0;
+        };
+        let downgraded_state = match render_comp.state {
+            RenderCompState::Complete => RenderCompState::Downgraded(None),
+            RenderCompState::ForViewport(viewport) => RenderCompState::Downgraded(Some(viewport)),
+            RenderCompState::BusyRenderingInTask
+            | RenderCompState::Dirty
+            | RenderCompState::Downgraded(_) => return 0,
+        };
+        let bytes_before = render_comp
+            .images
+            .iter()
+            .map(|image| image.data.len())
+            .sum::<usize>();
+        let images = std::mem::take(&mut render_comp.images)
+            .into_iter()
+            .map(|image| {
+                if image.pixel_width.max(image.pixel_height) < DOWNGRADE_MIN_PIXEL_SIZE * 2 {
+                    return image;
+                }
+                let (pixel_width, pixel_height) = (
+                    (image.pixel_width / 2).max(1),
+                    (image.pixel_height / 2).max(1),
+                );
+                match image.clone().downscaled(
+                    pixel_width,
+                    pixel_height,
+                    render::DownscaleFilter::Triangle,
+                ) {
+                    Ok(downscaled) => downscaled,
+                    Err(e) => {
+                        log::error!("downscaling rendering image failed, Err: {e:?}");
+                        image
+                    }
+                }
+            })
+            .collect::<Vec<render::Image>>();
+
+        match render::Image::images_to_rendernodes(&images) {
+            Ok(rendernodes) => {
+                render_comp.rendernodes = rendernodes;
+                render_comp.images = images;
+                render_comp.state = downgraded_state;
+            }
+            Err(e) => {
+                log::error!("failed to generate rendernodes in downgrade_rendering(), Err: {e:?}");
+                render_comp.rendernodes = vec![];
+                render_comp.images = vec![];
+                render_comp.state = RenderCompState::Dirty;
+            }
+        }
+
+        bytes_before.saturating_sub(
+            render_comp
+                .images
+                .iter()
+                .map(|image| image.data.len())
+                .sum::<usize>(),
+        )
+    }
+
+    /// Drop the rendering of the stroke, so that it is rendered again when needed.
+    ///
+    /// Returns the number of freed bytes.
+    pub fn evict_rendering(&mut self, key: StrokeKey) -> usize {
+        let bytes = self.rendering_images_bytes(key);
+        if let Some(render_comp) = self.render_components.get_mut(key) {
+            render_comp.rendernodes = vec![];
+            render_comp.images = vec![];
+            render_comp.state = RenderCompState::Dirty;
+        }
+        bytes
+    }
+
     /// Clear all rendering for all strokes.
     pub fn clear_rendering(&mut self) {
         for (_key, render_comp) in self.render_components.iter_mut() {
@@ -338,57 +505,32 @@ impl StrokeStore {
         }
     }
 
-    /// Generate images and appends them to the render component for the last segments of brushstrokes.
+    /// Render the stroke directly on the snapshot while it is being drawn, instead of through its images.
+    ///
+    /// Must be followed by `finish_live_rendering()` when the stroke is finished.
+    pub fn start_live_rendering(&mut self, key: StrokeKey) {
+        if let Some(render_comp) = self.render_components.get_mut(key) {
+            render_comp.live = LiveRenderState::Drawing;
+        }
+    }
+
+    /// Regenerate the images of the finished live stroke in a task.
     ///
-    /// For other strokes the rendering is regenerated completely.
-    pub fn append_rendering_last_segments(
+    /// The stroke is rendered directly until the images are regenerated.
+    pub fn finish_live_rendering(
         &mut self,
         tasks_tx: EngineTaskSender,
         key: StrokeKey,
-        n_last_segments: usize,
         viewport: Aabb,
         image_scale: f64,
     ) {
-        if let (Some(stroke), Some(render_comp)) = (
-            self.stroke_components.get(key),
-            self.render_components.get_mut(key),
-        ) {
-            match stroke.as_ref() {
-                Stroke::BrushStroke(brushstroke) => {
-                    match brushstroke.gen_image_for_last_segments(n_last_segments, image_scale) {
-                        Ok(Some(image)) => match render::Image::images_to_rendernodes([&image]) {
-                            Ok(mut rendernodes) => {
-                                render_comp.rendernodes.append(&mut rendernodes);
-                                render_comp.images.push(image);
-                            }
-                            Err(e) => {
-                                render_comp.state = RenderCompState::Dirty;
-                                log::error!("failed to generated rendernodes in append_rendering_last_segments(), Err: {e:?}");
-                            }
-                        },
-                        Ok(None) => {}
-                        Err(e) => {
-                            render_comp.state = RenderCompState::Dirty;
-                            log::error!(
-                                "failed to generated image in append_rendering_last_segments(), Err: {e:?}"
-                            );
-                        }
-                    }
-                }
-                // regenerate everything for strokes that don't support generating svgs for the last added elements
-                Stroke::ShapeStroke(_)
-                | Stroke::TextStroke(_)
-                | Stroke::VectorImage(_)
-                | Stroke::BitmapImage(_) => {
-                    self.regenerate_rendering_for_stroke_threaded(
-                        tasks_tx,
-                        key,
-                        viewport,
-                        image_scale,
-                    );
-                }
+        if let Some(render_comp) = self.render_components.get_mut(key) {
+            if render_comp.live == LiveRenderState::Drawing {
+                render_comp.live = LiveRenderState::AwaitingImages;
             }
         }
+        self.set_rendering_dirty(key);
+        self.regenerate_rendering_for_stroke_threaded(tasks_tx, key, viewport, image_scale);
     }
 
     /// Replace the entire current rendering with the given new images.
@@ -403,6 +545,7 @@ impl StrokeStore {
                             render_comp.rendernodes = rendernodes;
                             render_comp.images = images;
                             render_comp.state = RenderCompState::ForViewport(viewport);
+                            render_comp.end_live_rendering();
                         }
                         Err(e) => {
                             log::error!("failed to generate rendernodes in replace_rendering_with_images(), Err {e:?}");
@@ -416,6 +559,7 @@ impl StrokeStore {
                             render_comp.rendernodes = rendernodes;
                             render_comp.images = images;
                             render_comp.state = RenderCompState::Complete;
+                            render_comp.end_live_rendering();
                         }
                         Err(e) => {
                             log::error!("failed to generate rendernodes in replace_rendering_with_images(), Err {e:?}");
@@ -459,14 +603,31 @@ impl StrokeStore {
         snapshot: &Snapshot,
         doc_bounds: Aabb,
         viewport: Aabb,
+        image_scale: f64,
     ) {
         snapshot.push_clip(&graphene::Rect::from_p2d_aabb(doc_bounds));
 
         for key in self.stroke_keys_as_rendered_intersecting_bounds(viewport) {
+            if !self.visible_at_playback_time(key) {
+                continue;
+            }
             if let (Some(stroke), Some(render_comp)) = (
                 self.stroke_components.get(key),
                 self.render_components.get(key),
             ) {
+                if render_comp.live != LiveRenderState::Inactive {
+                    // Restrict to the viewport, else cairo is very unperformant for large bounds
+                    if let Some(bounds) = stroke.bounds().intersection(&viewport) {
+                        let cairo_cx =
+                            snapshot.append_cairo(&graphene::Rect::from_p2d_aabb(bounds));
+                        let mut piet_cx = piet_cairo::CairoRenderContext::new(&cairo_cx);
+                        if let Err(e) = stroke.draw(&mut piet_cx, image_scale) {
+                            log::error!("drawing live stroke with key {key:?} failed, Err: {e:?}");
+                        }
+                    }
+                    continue;
+                }
+
                 // if the stroke currently does not have a rendering and is will create one,
                 // draw a placeholder filled rect
                 if render_comp.rendernodes.is_empty()
@@ -519,6 +680,9 @@ impl StrokeStore {
         image_scale: f64,
     ) {
         for key in self.stroke_keys_as_rendered_intersecting_bounds(viewport) {
+            if !self.visible_at_playback_time(key) {
+                continue;
+            }
             if let Some(stroke) = self.stroke_components.get(key) {
                 if let Err(e) = stroke.draw(piet_cx, image_scale) {
                     log::error!(
@@ -112,6 +112,23 @@ impl StrokeStore {
     ///
     /// The returned, duplicated strokes then need to update their geometry and rendering.
     pub fn duplicate_selection(&mut self) -> Vec<StrokeKey> {
+        // Offsetting the new selected stroke to make the duplication apparent
+        self.duplicate_selection_w_offset(Stroke::IMPORT_OFFSET_DEFAULT)
+    }
+
+    /// Duplicate the selected keys, offset by the offset of the last duplication.
+    ///
+    /// Repeating this on the duplicates builds up patterns.
+    /// The returned, duplicated strokes then need to update their geometry and rendering.
+    pub fn duplicate_selection_repeat(&mut self) -> Vec<StrokeKey> {
+        self.duplicate_selection_w_offset(self.last_duplicate_offset)
+    }
+
+    /// Duplicate the selected keys and offset the duplicates.
+    ///
+    /// The duplicates become the selection. The offset is remembered for repeating the duplication.
+    /// The returned, duplicated strokes then need to update their geometry and rendering.
+    pub fn duplicate_selection_w_offset(&mut self, offset: na::Vector2<f64>) -> Vec<StrokeKey> {
         let old_selected = self.selection_keys_as_rendered();
         self.set_selected_keys(&old_selected, false);
 
@@ -141,10 +158,15 @@ impl StrokeStore {
             })
             .collect::<Vec<StrokeKey>>();
 
-        // Offsetting the new selected stroke to make the duplication apparent
-        self.translate_strokes(&new_selected, Stroke::IMPORT_OFFSET_DEFAULT);
-        self.translate_strokes_images(&new_selected, Stroke::IMPORT_OFFSET_DEFAULT);
+        self.translate_strokes(&new_selected, offset);
+        self.translate_strokes_images(&new_selected, offset);
+        self.last_duplicate_offset = offset;
 
         new_selected
     }
+
+    /// Set the offset that is re-applied when repeating the duplication.
+    pub fn set_last_duplicate_offset(&mut self, offset: na::Vector2<f64>) {
+        self.last_duplicate_offset = offset;
+    }
 }
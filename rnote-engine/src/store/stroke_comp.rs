@@ -2,7 +2,8 @@
 use super::render_comp::RenderCompState;
 use super::StrokeKey;
 use crate::engine::StrokeContent;
-use crate::strokes::{Stroke, StrokeBehaviour};
+use crate::strokes::textstroke::TextSearchPattern;
+use crate::strokes::{BitmapImage, Stroke, StrokeBehaviour, TextLayer};
 use crate::{render, StrokeStore, WidgetFlags};
 use geo::intersects::Intersects;
 use geo::prelude::Contains;
@@ -11,6 +12,8 @@ use rnote_compose::penpath::{Element, Segment};
 use rnote_compose::shapes::ShapeBehaviour;
 use rnote_compose::transform::TransformBehaviour;
 use rnote_compose::{helpers, Color};
+use std::collections::HashMap;
+use std::ops::Range;
 use std::sync::Arc;
 
 /// Systems that are related to the stroke components.
@@ -122,6 +125,7 @@ impl StrokeStore {
             self.key_tree.update_with_key(key, stroke.bounds());
             self.set_rendering_dirty(key);
         }
+        self.update_instances_of_masters(&[key]);
     }
 
     /// Updates the strokes geometries.
@@ -129,8 +133,16 @@ impl StrokeStore {
     /// The strokes then need to update their rendering.
     pub fn update_geometry_for_strokes(&mut self, keys: &[StrokeKey]) {
         keys.iter().for_each(|&key| {
-            self.update_geometry_for_stroke(key);
+            if let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
+                .get_mut(key)
+                .map(Arc::make_mut)
+            {
+                stroke.update_geometry();
+                self.key_tree.update_with_key(key, stroke.bounds());
+                self.set_rendering_dirty(key);
+            }
         });
+        self.update_instances_of_masters(keys);
     }
 
     /// Calculate the height needed to fit all strokes.
@@ -207,6 +219,7 @@ impl StrokeStore {
                 }
             }
         });
+        self.instances_translated(keys, offset);
     }
 
     /// Translate the stroke rendering images.
@@ -247,6 +260,7 @@ impl StrokeStore {
                 }
             }
         });
+        self.instances_rotated(keys, angle, center);
     }
 
     /// Change the stroke and text color for the given keys.
@@ -327,6 +341,239 @@ impl StrokeStore {
         widget_flags
     }
 
+    /// Change the fill pattern of the shapes of the given keys, or remove it when `None` is given.
+    ///
+    /// The strokes then need to update their rendering.
+    pub fn change_fill_patterns(&mut self, keys: &[StrokeKey], id: Option<String>) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        for &key in keys {
+            let is_filled_shape = matches!(
+                self.stroke_components.get(key).map(|stroke| stroke.as_ref()),
+                Some(Stroke::ShapeStroke(shapestroke))
+                    if shapestroke.fill_path().is_some() && shapestroke.fill_pattern != id
+            );
+            if !is_filled_shape {
+                continue;
+            }
+            if let Some(Stroke::ShapeStroke(shapestroke)) =
+                Arc::make_mut(&mut self.stroke_components)
+                    .get_mut(key)
+                    .map(Arc::make_mut)
+            {
+                shapestroke.fill_pattern = id.clone();
+                self.set_rendering_dirty(key);
+                widget_flags.redraw = true;
+                widget_flags.store_modified = true;
+            }
+        }
+
+        widget_flags
+    }
+
+    /// Replace the stroke and fill colors of the given keys that are equal to `from` with `to`.
+    ///
+    /// The strokes then need to update their rendering.
+    pub fn replace_colors(&mut self, keys: &[StrokeKey], from: Color, to: Color) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        if keys.is_empty() {
+            return widget_flags;
+        }
+
+        keys.iter().for_each(|&key| {
+            if let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
+                .get_mut(key)
+                .map(Arc::make_mut)
+            {
+                let replaced = match stroke {
+                    Stroke::BrushStroke(brush_stroke) => brush_stroke.style.replace_color(from, to),
+                    Stroke::ShapeStroke(shape_stroke) => shape_stroke.style.replace_color(from, to),
+                    Stroke::TextStroke(text_stroke) => {
+                        text_stroke.text_style.replace_color(from, to)
+                    }
+                    _ => false,
+                };
+                if replaced {
+                    self.set_rendering_dirty(key);
+                    widget_flags.redraw = true;
+                    widget_flags.store_modified = true;
+                }
+            }
+        });
+
+        widget_flags
+    }
+
+    /// Find the matches of the pattern in the text strokes and the text layers of the images of the given keys.
+    ///
+    /// Returns the keys of the strokes that contain matches, together with the byte ranges of the matches.
+    /// For images the ranges are in the text returned by [TextLayer::text()].
+    pub fn find_text(
+        &self,
+        keys: &[StrokeKey],
+        pattern: &TextSearchPattern,
+    ) -> Vec<(StrokeKey, Vec<Range<usize>>)> {
+        keys.iter()
+            .filter_map(|&key| {
+                let matches = match self.get_stroke_ref(key)? {
+                    Stroke::TextStroke(textstroke) => pattern.find_matches(&textstroke.text),
+                    stroke => {
+                        let (text_layer, _) = stroke.text_layer()?;
+                        pattern.find_matches(&text_layer.text())
+                    }
+                };
+                (!matches.is_empty()).then_some((key, matches))
+            })
+            .collect()
+    }
+
+    /// The bounds on the document of the words of the image text layer that are in the byte ranges,
+    /// e.g. to highlight the matches returned by [StrokeStore::find_text()].
+    pub fn text_layer_ranges_bounds(&self, key: StrokeKey, ranges: &[Range<usize>]) -> Vec<Aabb> {
+        let Some((text_layer, rectangle)) = self
+            .get_stroke_ref(key)
+            .and_then(|stroke| stroke.text_layer())
+        else {
+            return vec![];
+        };
+        ranges
+            .iter()
+            .flat_map(|range| text_layer.spans_in_text_range(range.clone()))
+            .map(|span| TextLayer::span_bounds_on_doc(span, rectangle))
+            .collect()
+    }
+
+    /// The text of the text layers of the images that intersect the bounds, e.g. of imported Pdf pages.
+    ///
+    /// Returns the selected text, with the text of separate images on separate lines,
+    /// together with the bounds of the selected words.
+    pub fn text_layer_text_in_bounds(&self, bounds: Aabb) -> (String, Vec<Aabb>) {
+        let mut texts = vec![];
+        let mut span_bounds = vec![];
+
+        for key in self.stroke_keys_as_rendered_intersecting_bounds(bounds) {
+            let Some((text_layer, rectangle)) = self
+                .get_stroke_ref(key)
+                .and_then(|stroke| stroke.text_layer())
+            else {
+                continue;
+            };
+            let spans = text_layer.spans_intersecting_bounds(rectangle, bounds);
+            if spans.is_empty() {
+                continue;
+            }
+            texts.push(TextLayer::join_spans(spans.iter().map(|(span, _)| *span)));
+            span_bounds.extend(spans.into_iter().map(|(_, bounds)| bounds));
+        }
+
+        (texts.join("\n"), span_bounds)
+    }
+
+    /// Replace the matches of the pattern in the text strokes of the given keys.
+    ///
+    /// Returns the keys of the modified text strokes. The strokes then need to update their rendering.
+    pub fn replace_text(
+        &mut self,
+        keys: &[StrokeKey],
+        pattern: &TextSearchPattern,
+        replacement: &str,
+    ) -> (Vec<StrokeKey>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+        let mut modified_keys = vec![];
+
+        for &key in keys {
+            // Check first, to avoid cloning strokes without matches
+            if !matches!(self.get_stroke_ref(key), Some(Stroke::TextStroke(_)))
+                || self.find_text(&[key], pattern).is_empty()
+            {
+                continue;
+            }
+            if let Some(Stroke::TextStroke(textstroke)) = Arc::make_mut(&mut self.stroke_components)
+                .get_mut(key)
+                .map(Arc::make_mut)
+            {
+                if textstroke.replace_matches(pattern, replacement) > 0 {
+                    modified_keys.push(key);
+                }
+            }
+        }
+        if modified_keys.is_empty() {
+            return (modified_keys, widget_flags);
+        }
+        self.update_geometry_for_strokes(&modified_keys);
+        self.set_rendering_dirty_for_strokes(&modified_keys);
+
+        widget_flags.redraw = true;
+        widget_flags.resize = true;
+        widget_flags.store_modified = true;
+        (modified_keys, widget_flags)
+    }
+
+    /// Set the description (alt-text) of the stroke.
+    ///
+    /// Returns false if the stroke does not exist or does not support descriptions.
+    pub fn set_stroke_description(&mut self, key: StrokeKey, description: Option<String>) -> bool {
+        // Check first, to avoid cloning strokes that are left unmodified
+        if self
+            .get_stroke_ref(key)
+            .map_or(true, |stroke| matches!(stroke, Stroke::TextStroke(_)))
+        {
+            return false;
+        }
+        Arc::make_mut(&mut self.stroke_components)
+            .get_mut(key)
+            .map(Arc::make_mut)
+            .map_or(false, |stroke| stroke.set_description(description))
+    }
+
+    /// Set the text layer of the image stroke, e.g. with text recognized through OCR.
+    ///
+    /// Returns false if the stroke is not an image.
+    pub fn set_stroke_text_layer(&mut self, key: StrokeKey, text_layer: TextLayer) -> bool {
+        match Arc::make_mut(&mut self.stroke_components)
+            .get_mut(key)
+            .map(Arc::make_mut)
+        {
+            Some(Stroke::VectorImage(vectorimage)) => vectorimage.text_layer = text_layer,
+            Some(Stroke::BitmapImage(bitmapimage)) => bitmapimage.text_layer = text_layer,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Modify the bitmap image stroke with the closure, for operations that change its pixel data.
+    ///
+    /// The text layer is cleared, because it might not match the modified image anymore.
+    /// The stroke then needs to update its rendering.
+    pub fn modify_bitmapimage<F>(
+        &mut self,
+        key: StrokeKey,
+        modify: F,
+    ) -> anyhow::Result<WidgetFlags>
+    where
+        F: FnOnce(&mut BitmapImage) -> anyhow::Result<()>,
+    {
+        let mut widget_flags = WidgetFlags::default();
+
+        let Some(Stroke::BitmapImage(bitmapimage)) = Arc::make_mut(&mut self.stroke_components)
+            .get_mut(key)
+            .map(Arc::make_mut)
+        else {
+            return Err(anyhow::anyhow!(
+                "modify_bitmapimage() failed, stroke for key {key:?} is not a bitmap image"
+            ));
+        };
+        modify(bitmapimage)?;
+        bitmapimage.text_layer = TextLayer::default();
+        self.update_geometry_for_stroke(key);
+        self.set_rendering_dirty(key);
+
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+        Ok(widget_flags)
+    }
+
     /// Rotate the stroke rendering images.
     ///
     /// The strokes then need to update their rendering.
@@ -372,6 +619,50 @@ impl StrokeStore {
                 }
             }
         });
+        self.instances_scaled(keys, scale);
+    }
+
+    /// Scale the stroke widths with the factor.
+    ///
+    /// The strokes then need to update their geometry and rendering.
+    pub fn scale_stroke_widths(&mut self, keys: &[StrokeKey], factor: f64) {
+        keys.iter().for_each(|&key| {
+            if let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
+                .get_mut(key)
+                .map(Arc::make_mut)
+            {
+                match stroke {
+                    Stroke::BrushStroke(brush_stroke) => brush_stroke
+                        .style
+                        .set_stroke_width(brush_stroke.style.stroke_width() * factor),
+                    Stroke::ShapeStroke(shape_stroke) => shape_stroke
+                        .style
+                        .set_stroke_width(shape_stroke.style.stroke_width() * factor),
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    /// Smooth and simplify the paths of the brush strokes with the strength, ranging from 0.0 to 1.0.
+    ///
+    /// Returns the keys of the brush strokes that were smoothed.
+    /// The strokes then need to update their geometry and rendering.
+    pub fn smooth_brushstrokes(&mut self, keys: &[StrokeKey], strength: f64) -> Vec<StrokeKey> {
+        keys.iter()
+            .filter_map(|&key| {
+                match Arc::make_mut(&mut self.stroke_components)
+                    .get_mut(key)
+                    .map(Arc::make_mut)
+                {
+                    Some(Stroke::BrushStroke(brushstroke)) => {
+                        brushstroke.smooth(strength);
+                        Some(key)
+                    }
+                    _ => None,
+                }
+            })
+            .collect()
     }
 
     /// Scale the stroke rendering images.
@@ -434,6 +725,7 @@ impl StrokeStore {
             Some(old_bounds) => old_bounds,
             None => return,
         };
+        let mut transforms = HashMap::with_capacity(keys.len());
 
         keys.iter().for_each(|&key| {
             if let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
@@ -459,9 +751,23 @@ impl StrokeStore {
                     stroke.translate(old_stroke_bounds.center().coords);
 
                     self.key_tree.update_with_key(key, stroke.bounds());
+                    transforms.insert(
+                        key,
+                        na::Matrix3::new_translation(&old_stroke_bounds.center().coords)
+                            * na::Matrix3::new_nonuniform_scaling(&scale)
+                            * na::Matrix3::new_translation(
+                                &(rel_offset - old_stroke_bounds.center().coords),
+                            ),
+                    );
                 }
             }
         });
+        self.track_instance_transforms(keys, |key| {
+            transforms
+                .get(&key)
+                .copied()
+                .unwrap_or_else(na::Matrix3::identity)
+        });
     }
 
     /// Resize the strokes rendering images to new bounds.
@@ -639,14 +945,23 @@ impl StrokeStore {
     }
 
     /// Return the keys for strokes where the given coord is inside at least one of their hitboxes.
+    ///
+    /// Only coordinates inside the viewport are hit.
     pub fn stroke_hitboxes_contain_coord(
         &self,
         viewport: Aabb,
         coord: na::Vector2<f64>,
     ) -> Vec<StrokeKey> {
-        self.stroke_keys_as_rendered_intersecting_bounds(viewport)
+        if !viewport.contains_local_point(&coord.into()) {
+            return vec![];
+        }
+
+        self.keys_sorted_chrono_containing_point(coord.into())
             .into_iter()
             .filter(|&key| {
+                if self.trashed(key).unwrap_or(false) {
+                    return false;
+                }
                 if let Some(stroke) = self.stroke_components.get(key) {
                     stroke
                         .hitboxes()
@@ -661,14 +976,16 @@ impl StrokeStore {
 
     /// Return all keys below the given `y`.
     pub fn keys_below_y(&self, y: f64) -> Vec<StrokeKey> {
-        self.stroke_components
-            .iter()
-            .filter_map(|(key, stroke)| {
-                if stroke.bounds().mins[1] > y {
-                    Some(key)
-                } else {
-                    None
-                }
+        let below_bounds = Aabb::new(na::point![f64::MIN, y], na::point![f64::MAX, f64::MAX]);
+
+        self.key_tree
+            .keys_in_bounds(below_bounds)
+            .into_iter()
+            .filter(|&key| {
+                self.stroke_components
+                    .get(key)
+                    .map(|stroke| stroke.bounds().mins[1] > y)
+                    .unwrap_or(false)
             })
             .collect::<Vec<StrokeKey>>()
     }
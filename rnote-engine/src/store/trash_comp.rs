@@ -1,11 +1,12 @@
 // Imports
 use super::chrono_comp::StrokeLayer;
 use super::{StrokeKey, StrokeStore};
+use crate::pens::PenStyle;
 use crate::strokes::{BrushStroke, Stroke};
 use crate::WidgetFlags;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::shapes::ShapeBehaviour;
-use rnote_compose::PenPath;
+use rnote_compose::{Color, PenPath};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -92,8 +93,12 @@ impl StrokeStore {
     /// Trash strokes that collide with the given bounds.
     pub fn trash_colliding_strokes(&mut self, eraser_bounds: Aabb, viewport: Aabb) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
+        // Only strokes that intersect the part of the eraser inside the viewport can collide
+        let Some(query_bounds) = eraser_bounds.intersection(&viewport) else {
+            return widget_flags;
+        };
 
-        self.stroke_keys_as_rendered_intersecting_bounds(viewport)
+        self.stroke_keys_as_rendered_intersecting_bounds(query_bounds)
             .into_iter()
             .for_each(|key| {
                 let mut trash_current_stroke = false;
@@ -128,6 +133,64 @@ impl StrokeStore {
         widget_flags
     }
 
+    /// Trash the strokes that are fully contained in the given bounds.
+    ///
+    /// Returns the keys of the trashed strokes.
+    pub fn trash_strokes_in_bounds(&mut self, bounds: Aabb) -> (Vec<StrokeKey>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+        let keys = self.stroke_keys_as_rendered_in_bounds(bounds);
+
+        if !keys.is_empty() {
+            self.set_trashed_keys(&keys, true);
+            widget_flags.store_modified = true;
+            widget_flags.resize = true;
+        }
+
+        (keys, widget_flags)
+    }
+
+    /// Trash the strokes of the given keys that have a stroke, fill or text color equal to `color`.
+    ///
+    /// When `pen_style` is set, only the strokes that are drawn by this pen are trashed.
+    /// Colors are compared with 8 bit precision. Returns the keys of the trashed strokes.
+    pub fn trash_strokes_w_color(
+        &mut self,
+        keys: &[StrokeKey],
+        color: Color,
+        pen_style: Option<PenStyle>,
+    ) -> (Vec<StrokeKey>, WidgetFlags) {
+        let mut widget_flags = WidgetFlags::default();
+        let matching = keys
+            .iter()
+            .copied()
+            .filter(|&key| {
+                let Some(stroke) = self.stroke_components.get(key) else {
+                    return false;
+                };
+                match (stroke.as_ref(), pen_style) {
+                    (Stroke::BrushStroke(brushstroke), None | Some(PenStyle::Brush)) => {
+                        brushstroke.style.has_color(color)
+                    }
+                    (Stroke::ShapeStroke(shapestroke), None | Some(PenStyle::Shaper)) => {
+                        shapestroke.style.has_color(color)
+                    }
+                    (Stroke::TextStroke(textstroke), None | Some(PenStyle::Typewriter)) => {
+                        textstroke.text_style.has_color(color)
+                    }
+                    _ => false,
+                }
+            })
+            .collect::<Vec<StrokeKey>>();
+
+        if !matching.is_empty() {
+            self.set_trashed_keys(&matching, true);
+            widget_flags.store_modified = true;
+            widget_flags.resize = true;
+        }
+
+        (matching, widget_flags)
+    }
+
     /// Remove colliding stroke segments with the given bounds.
     /// The stroke is then split. Strokes that don't have segments are trashed completely.
     ///
@@ -141,9 +204,13 @@ impl StrokeStore {
     ) -> (Vec<StrokeKey>, WidgetFlags) {
         let mut widget_flags = WidgetFlags::default();
         let mut modified_keys = vec![];
+        // Only strokes that intersect the part of the eraser inside the viewport can collide
+        let Some(query_bounds) = eraser_bounds.intersection(&viewport) else {
+            return (modified_keys, widget_flags);
+        };
 
         let new_strokes = self
-            .stroke_keys_as_rendered_intersecting_bounds(viewport)
+            .stroke_keys_as_rendered_intersecting_bounds(query_bounds)
             .into_iter()
             .flat_map(|key| {
                 let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
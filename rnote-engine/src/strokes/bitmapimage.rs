@@ -1,10 +1,12 @@
 // Imports
 use super::strokebehaviour::{self, GeneratedStrokeImages};
-use super::{Stroke, StrokeBehaviour};
+use super::{Stroke, StrokeBehaviour, TextLayer};
 use crate::document::Format;
-use crate::engine::import::{PdfImportPageSpacing, PdfImportPrefs};
+use crate::engine::import::{
+    BitmapImportPrefs, PdfImportPageSpacing, PdfImportPrefs, PDF_IMPORT_RENDER_PROGRESS,
+};
 use crate::render;
-use crate::DrawBehaviour;
+use crate::{DrawBehaviour, EngineError, Progress};
 use anyhow::Context;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use piet::RenderContext;
@@ -29,6 +31,12 @@ pub struct BitmapImage {
     pub image: render::Image,
     #[serde(rename = "rectangle")]
     pub rectangle: Rectangle,
+    /// A description of the stroke for screen reader users (alt-text).
+    #[serde(default, rename = "description")]
+    pub description: Option<String>,
+    /// The invisible text of the image, e.g. of an imported Pdf page.
+    #[serde(default, rename = "text_layer")]
+    pub text_layer: TextLayer,
 }
 
 impl Default for BitmapImage {
@@ -36,6 +44,8 @@ impl Default for BitmapImage {
         Self {
             image: render::Image::default(),
             rectangle: Rectangle::default(),
+            description: None,
+            text_layer: TextLayer::default(),
         }
     }
 }
@@ -168,7 +178,126 @@ impl BitmapImage {
             transform: Transform::new_w_isometry(na::Isometry2::new(pos + size * 0.5, 0.0)),
         };
 
-        Ok(Self { image, rectangle })
+        Ok(Self {
+            image,
+            rectangle,
+            description: None,
+            text_layer: TextLayer::default(),
+        })
+    }
+
+    /// Import from the image bytes, sized by the DPI stored in its metadata and the bitmap import preferences.
+    pub fn import_from_image_bytes_w_prefs(
+        bytes: &[u8],
+        pos: na::Vector2<f64>,
+        bitmap_import_prefs: BitmapImportPrefs,
+        format: &Format,
+    ) -> Result<Self, anyhow::Error> {
+        let image =
+            render::Image::try_from_encoded_bytes(bytes)?.cleaned_up(&render::ImageCleanup {
+                deskew_corners: None,
+                scan_filter: bitmap_import_prefs.scan_filter,
+                grayscale: bitmap_import_prefs.grayscale,
+            })?;
+        let size = bitmap_import_prefs.calc_image_size(
+            na::vector![f64::from(image.pixel_width), f64::from(image.pixel_height)],
+            render::Image::read_dpi_from_encoded_bytes(bytes),
+            format,
+        );
+
+        let rectangle = Rectangle {
+            cuboid: p2d::shape::Cuboid::new(size * 0.5),
+            transform: Transform::new_w_isometry(na::Isometry2::new(pos + size * 0.5, 0.0)),
+        };
+
+        Ok(Self {
+            image,
+            rectangle,
+            description: None,
+            text_layer: TextLayer::default(),
+        })
+    }
+
+    /// Converts a point in the coordinate space of the document to the pixel coordinates of the image.
+    pub fn doc_coords_to_pixel_coords(&self, point: na::Point2<f64>) -> na::Point2<f64> {
+        let half_extents = self.rectangle.cuboid.half_extents;
+        let local = self.rectangle.transform.affine.inverse() * point;
+        na::point![
+            (local[0] + half_extents[0]) * f64::from(self.image.pixel_width)
+                / (2.0 * half_extents[0]),
+            (local[1] + half_extents[1]) * f64::from(self.image.pixel_height)
+                / (2.0 * half_extents[1])
+        ]
+    }
+
+    /// Apply the cleanup filters to the image.
+    ///
+    /// When the perspective is de-skewed, the rectangle is shrunk to the de-skewed region, keeping its center.
+    pub fn apply_cleanup(&mut self, cleanup: &render::ImageCleanup) -> anyhow::Result<()> {
+        let image = self.image.clone().cleaned_up(cleanup)?;
+
+        if let Some(corners) = cleanup.deskew_corners {
+            let center = corners.iter().map(|c| c.coords).sum::<na::Vector2<f64>>() / 4.0;
+            self.fit_rectangle_to_pixel_region(
+                center.into(),
+                na::vector![f64::from(image.pixel_width), f64::from(image.pixel_height)],
+            );
+        }
+
+        self.image = image;
+        Ok(())
+    }
+
+    /// Crop the image to the bounds in pixel coordinates, discarding the pixel data outside of it.
+    ///
+    /// The rectangle is shrunk to the cropped region.
+    pub fn crop(&mut self, pixel_bounds: Aabb) -> anyhow::Result<()> {
+        let image = self.image.clone().cropped(pixel_bounds)?;
+        let x = pixel_bounds.mins[0].round().max(0.0);
+        let y = pixel_bounds.mins[1].round().max(0.0);
+        let new_pixel_size =
+            na::vector![f64::from(image.pixel_width), f64::from(image.pixel_height)];
+
+        self.fit_rectangle_to_pixel_region(na::point![x, y] + new_pixel_size * 0.5, new_pixel_size);
+        self.image = image;
+        Ok(())
+    }
+
+    /// Rotate the image by 90 degrees, clockwise or counter-clockwise.
+    ///
+    /// The pixel data is rotated and the extents of the rectangle are swapped, keeping its center.
+    pub fn rotate_90(&mut self, clockwise: bool) -> anyhow::Result<()> {
+        self.image = self.image.clone().rotated_90(clockwise)?;
+        let half_extents = self.rectangle.cuboid.half_extents;
+        self.rectangle.cuboid.half_extents = na::vector![half_extents[1], half_extents[0]];
+        Ok(())
+    }
+
+    /// Mirror the pixel data of the image horizontally or vertically.
+    pub fn flip(&mut self, horizontal: bool) -> anyhow::Result<()> {
+        self.image = self.image.clone().flipped(horizontal)?;
+        Ok(())
+    }
+
+    /// Fits the rectangle to a region of the current image, given its center and size in pixel coordinates.
+    ///
+    /// The scale between pixels and the rectangle extents is kept.
+    fn fit_rectangle_to_pixel_region(
+        &mut self,
+        center: na::Point2<f64>,
+        pixel_size: na::Vector2<f64>,
+    ) {
+        let old_pixel_size = na::vector![
+            f64::from(self.image.pixel_width),
+            f64::from(self.image.pixel_height)
+        ];
+        let old_half_extents = self.rectangle.cuboid.half_extents;
+        let pixel_scale = (old_half_extents * 2.0).component_div(&old_pixel_size);
+        let local_center = center.coords.component_mul(&pixel_scale) - old_half_extents;
+
+        self.rectangle.cuboid.half_extents = pixel_size.component_mul(&pixel_scale) * 0.5;
+        let offset = self.rectangle.transform.transform_vec(local_center);
+        self.rectangle.transform.append_translation_mut(offset);
     }
 
     pub fn import_from_pdf_bytes(
@@ -177,8 +306,10 @@ impl BitmapImage {
         insert_pos: na::Vector2<f64>,
         page_range: Option<Range<u32>>,
         format: &Format,
+        progress: &Progress,
     ) -> Result<Vec<Self>, anyhow::Error> {
-        let doc = poppler::Document::from_bytes(&glib::Bytes::from(to_be_read), None)?;
+        let doc = poppler::Document::from_bytes(&glib::Bytes::from(to_be_read), None)
+            .map_err(|e| EngineError::Decode(format!("loading Pdf document failed, {e}")))?;
         let page_range = page_range.unwrap_or(0..doc.n_pages() as u32);
 
         let page_width = format.width * (pdf_import_prefs.page_width_perc / 100.0);
@@ -190,13 +321,18 @@ impl BitmapImage {
         };
         let x = insert_pos[0];
         let mut y = insert_pos[1];
+        let render_stage = progress.stage(0.0, PDF_IMPORT_RENDER_PROGRESS, page_range.len());
 
         let pngs = page_range
+            // Poppler renders sequentially, so the remaining pages are skipped when cancelled
+            .take_while(|_| !progress.is_cancelled())
             .filter_map(|page_i| {
+                render_stage.step();
                 let page = doc.page(page_i as i32)?;
                 let intrinsic_size = page.size();
                 let width = intrinsic_size.0 * page_zoom;
                 let height = intrinsic_size.1 * page_zoom;
+                let text_layer = TextLayer::from_pdf_page(&page);
 
                 let res =
                     move || -> anyhow::Result<(Vec<u8>, na::Vector2<f64>, na::Vector2<f64>)> {
@@ -232,23 +368,25 @@ impl BitmapImage {
 
                             page.render_for_printing(&cx);
 
-                            // Draw outline around page
-                            cx.set_source_rgba(
-                                color::GNOME_REDS[4].as_rgba().0,
-                                color::GNOME_REDS[4].as_rgba().1,
-                                color::GNOME_REDS[4].as_rgba().2,
-                                1.0,
-                            );
-
-                            let line_width = 1.0;
-                            cx.set_line_width(line_width);
-                            cx.rectangle(
-                                line_width * 0.5,
-                                line_width * 0.5,
-                                intrinsic_size.0 - line_width,
-                                intrinsic_size.1 - line_width,
-                            );
-                            cx.stroke()?;
+                            if pdf_import_prefs.page_borders {
+                                // Draw outline around page
+                                cx.set_source_rgba(
+                                    color::GNOME_REDS[4].as_rgba().0,
+                                    color::GNOME_REDS[4].as_rgba().1,
+                                    color::GNOME_REDS[4].as_rgba().2,
+                                    1.0,
+                                );
+
+                                let line_width = 1.0;
+                                cx.set_line_width(line_width);
+                                cx.rectangle(
+                                    line_width * 0.5,
+                                    line_width * 0.5,
+                                    intrinsic_size.0 - line_width,
+                                    intrinsic_size.1 - line_width,
+                                );
+                                cx.stroke()?;
+                            }
                         }
 
                         let mut png_data: Vec<u8> = Vec::new();
@@ -268,30 +406,39 @@ impl BitmapImage {
                 };
 
                 match res() {
-                    Ok(ret) => Some(ret),
+                    Ok((png_data, pos, size)) => Some((png_data, pos, size, text_layer)),
                     Err(e) => {
                         log::error!("bitmapimage import_from_pdf_bytes() failed with Err: {e:?}");
                         None
                     }
                 }
             })
-            .collect::<Vec<(Vec<u8>, na::Vector2<f64>, na::Vector2<f64>)>>();
+            .collect::<Vec<(Vec<u8>, na::Vector2<f64>, na::Vector2<f64>, TextLayer)>>();
+        render_stage.finish()?;
+        let import_stage = progress.stage(PDF_IMPORT_RENDER_PROGRESS, 1.0, pngs.len());
 
-        Ok(pngs
+        let bitmapimages = pngs
             .into_par_iter()
-            .filter_map(|(png_data, pos, size)| {
-                match Self::import_from_image_bytes(
-                    &png_data,
-                    pos,
-                    Some(size),
-                ) {
-                    Ok(bitmapimage) => Some(bitmapimage),
+            .filter_map(|(png_data, pos, size, text_layer)| {
+                if progress.is_cancelled() {
+                    return None;
+                }
+                let res = Self::import_from_image_bytes(&png_data, pos, Some(size));
+                import_stage.step();
+                match res {
+                    Ok(bitmapimage) => Some(Self {
+                        text_layer,
+                        ..bitmapimage
+                    }),
                     Err(e) => {
                         log::error!("import_from_image_bytes() failed in bitmapimage import_from_pdf_bytes() with Err: {e:?}");
                         None
                     }
                 }
             })
-            .collect())
+            .collect::<Vec<Self>>();
+        import_stage.finish()?;
+
+        Ok(bitmapimages)
     }
 }
@@ -23,6 +23,9 @@ pub struct BrushStroke {
     pub path: PenPath,
     #[serde(default, rename = "style")]
     pub style: Style,
+    /// A description of the stroke for screen reader users (alt-text).
+    #[serde(default, rename = "description")]
+    pub description: Option<String>,
     // since the path can have many hitboxes, we store them here and update them when the stroke geometry changes
     #[serde(skip)]
     hitboxes: Vec<Aabb>,
@@ -56,7 +59,10 @@ impl StrokeBehaviour for BrushStroke {
         let bounds = self.bounds();
         let partial = !viewport.contains(&bounds);
         let Some(bounds) = viewport.intersection(&bounds) else {
-            return Ok(GeneratedStrokeImages::Partial { images: vec![], viewport });
+            return Ok(GeneratedStrokeImages::Partial {
+                images: vec![],
+                viewport,
+            });
         };
         let bounds_extents = bounds.extents();
 
@@ -104,6 +110,42 @@ impl StrokeBehaviour for BrushStroke {
                         image_scale,
                     );
 
+                    match image {
+                        Ok(image) => vec![image],
+                        Err(e) => {
+                            log::error!("gen_images() in brushstroke failed with Err: {e:?}");
+                            vec![]
+                        }
+                    }
+                }
+                Style::Grain(options) => {
+                    let image = render::Image::gen_with_piet(
+                        |piet_cx| {
+                            self.path.draw_composed(piet_cx, options);
+                            Ok(())
+                        },
+                        bounds,
+                        image_scale,
+                    );
+
+                    match image {
+                        Ok(image) => vec![image],
+                        Err(e) => {
+                            log::error!("gen_images() in brushstroke failed with Err: {e:?}");
+                            vec![]
+                        }
+                    }
+                }
+                Style::Airbrush(options) => {
+                    let image = render::Image::gen_with_piet(
+                        |piet_cx| {
+                            self.path.draw_composed(piet_cx, options);
+                            Ok(())
+                        },
+                        bounds,
+                        image_scale,
+                    );
+
                     match image {
                         Ok(image) => vec![image],
                         Err(e) => {
@@ -174,6 +216,44 @@ impl StrokeBehaviour for BrushStroke {
 
                     images
                 }
+                Style::Grain(options) => {
+                    // Rendered as a single image, because overlapping segments would add up in opacity
+                    let image = render::Image::gen_with_piet(
+                        |piet_cx| {
+                            self.path.draw_composed(piet_cx, options);
+                            Ok(())
+                        },
+                        bounds,
+                        image_scale,
+                    );
+
+                    match image {
+                        Ok(image) => vec![image],
+                        Err(e) => {
+                            log::error!("gen_images() in brushstroke failed with Err: {e:?}");
+                            vec![]
+                        }
+                    }
+                }
+                Style::Airbrush(options) => {
+                    // Rendered as a single image, because the particles of a segment can land far outside of its bounds
+                    let image = render::Image::gen_with_piet(
+                        |piet_cx| {
+                            self.path.draw_composed(piet_cx, options);
+                            Ok(())
+                        },
+                        bounds,
+                        image_scale,
+                    );
+
+                    match image {
+                        Ok(image) => vec![image],
+                        Err(e) => {
+                            log::error!("gen_images() in brushstroke failed with Err: {e:?}");
+                            vec![]
+                        }
+                    }
+                }
             }
         };
 
@@ -226,9 +306,10 @@ impl DrawBehaviour for BrushStroke {
             Style::Smooth(options) => self.path.draw_composed(cx, options),
             Style::Rough(_) => {
                 // Rough style currently unsupported for pen paths
-                unimplemented!()
             }
             Style::Textured(options) => self.path.draw_composed(cx, options),
+            Style::Grain(options) => self.path.draw_composed(cx, options),
+            Style::Airbrush(options) => self.path.draw_composed(cx, options),
         };
 
         cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
@@ -240,8 +321,11 @@ impl ShapeBehaviour for BrushStroke {
     fn bounds(&self) -> Aabb {
         match &self.style {
             Style::Smooth(options) => self.path.composed_bounds(options),
-            Style::Rough(_options) => unimplemented!(),
+            // Rough style currently unsupported for pen paths, but can appear in corrupted or crafted files
+            Style::Rough(_options) => self.path.composed_bounds(&self.style.smooth_fallback()),
             Style::Textured(options) => self.path.composed_bounds(options),
+            Style::Grain(options) => self.path.composed_bounds(options),
+            Style::Airbrush(options) => self.path.composed_bounds(options),
         }
     }
 
@@ -263,6 +347,11 @@ impl TransformBehaviour for BrushStroke {
 }
 
 impl BrushStroke {
+    /// The maximum number of smoothing iterations, at full strength.
+    const SMOOTH_ITERATIONS_MAX: usize = 8;
+    /// The maximum tolerance when simplifying the path, at full strength.
+    const SIMPLIFY_EPSILON_MAX: f64 = 2.0;
+
     pub fn new(start: Element, style: Style) -> Self {
         let path = PenPath::new(start);
 
@@ -274,6 +363,7 @@ impl BrushStroke {
         let mut new_brushstroke = Self {
             path,
             style,
+            description: None,
             hitboxes: vec![],
         };
         new_brushstroke.update_geometry();
@@ -295,6 +385,26 @@ impl BrushStroke {
         self.update_geometry();
     }
 
+    /// Smooth and simplify the path with the strength, ranging from 0.0 (unchanged) to 1.0.
+    ///
+    /// Jitter gets smoothed out first, then redundant elements are removed
+    /// and the remaining elements are connected with curves.
+    pub fn smooth(&mut self, strength: f64) {
+        let strength = strength.clamp(0.0, 1.0);
+        if strength <= 0.0 || self.path.segments.len() < 2 {
+            return;
+        }
+        let iterations = (strength * Self::SMOOTH_ITERATIONS_MAX as f64).ceil() as usize;
+        let epsilon = strength * Self::SIMPLIFY_EPSILON_MAX;
+
+        let path = self
+            .path
+            .smoothed(strength, iterations)
+            .simplified(epsilon)
+            .curved();
+        self.replace_path(path);
+    }
+
     // internal method generating the current hitboxes.
     fn gen_hitboxes_int(&self) -> Vec<Aabb> {
         let stroke_width = self.style.stroke_width();
@@ -341,6 +451,67 @@ impl BrushStroke {
                 Some(image)
             }
             Style::Rough(_) => None,
+            Style::Grain(options) => {
+                let path_len = self.path.segments.len();
+
+                let start_el = self
+                    .path
+                    .segments
+                    .get(path_len.saturating_sub(n_last_segments).saturating_sub(1))
+                    .map(|s| s.end())
+                    .unwrap_or(self.path.start);
+
+                let range_path = PenPath::new_w_segments(
+                    start_el,
+                    self.path.segments[path_len.saturating_sub(n_last_segments)..]
+                        .iter()
+                        .copied(),
+                );
+
+                let image = render::Image::gen_with_piet(
+                    |piet_cx| {
+                        range_path.draw_composed(piet_cx, options);
+                        Ok(())
+                    },
+                    range_path.composed_bounds(options),
+                    image_scale,
+                )?;
+
+                Some(image)
+            }
+            Style::Airbrush(options) => {
+                let mut options = options.clone();
+                let path_len = self.path.segments.len();
+
+                (0..path_len.saturating_sub(n_last_segments)).for_each(|_| {
+                    options.advance_seed();
+                });
+
+                let start_el = self
+                    .path
+                    .segments
+                    .get(path_len.saturating_sub(n_last_segments).saturating_sub(1))
+                    .map(|s| s.end())
+                    .unwrap_or(self.path.start);
+
+                let range_path = PenPath::new_w_segments(
+                    start_el,
+                    self.path.segments[path_len.saturating_sub(n_last_segments)..]
+                        .iter()
+                        .copied(),
+                );
+
+                let image = render::Image::gen_with_piet(
+                    |piet_cx| {
+                        range_path.draw_composed(piet_cx, &options);
+                        Ok(())
+                    },
+                    range_path.composed_bounds(&options),
+                    image_scale,
+                )?;
+
+                Some(image)
+            }
             Style::Textured(options) => {
                 let mut options = options.clone();
                 let path_len = self.path.segments.len();
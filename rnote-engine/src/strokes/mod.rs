@@ -4,6 +4,10 @@ pub mod brushstroke;
 pub mod shapestroke;
 pub mod stroke;
 pub mod strokebehaviour;
+pub mod strokenodes;
+pub mod svgconvert;
+pub mod textlayer;
+pub mod textoutlines;
 pub mod textstroke;
 pub mod vectorimage;
 
@@ -13,5 +17,7 @@ pub use brushstroke::BrushStroke;
 pub use shapestroke::ShapeStroke;
 pub use stroke::Stroke;
 pub use strokebehaviour::StrokeBehaviour;
+pub use strokenodes::{StrokeNode, StrokeNodeKind};
+pub use textlayer::TextLayer;
 pub use textstroke::TextStroke;
 pub use vectorimage::VectorImage;
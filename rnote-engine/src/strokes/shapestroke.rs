@@ -19,6 +19,14 @@ pub struct ShapeStroke {
     pub shape: Shape,
     #[serde(rename = "style")]
     pub style: Style,
+    /// A description of the stroke for screen reader users (alt-text).
+    #[serde(default, rename = "description")]
+    pub description: Option<String>,
+    /// The id of the fill pattern the shape is filled with, drawn on top of the fill color.
+    ///
+    /// Only closed shapes can be filled.
+    #[serde(default, rename = "fill_pattern")]
+    pub fill_pattern: Option<String>,
     #[serde(skip)]
     // since the shape can have many hitboxes, we store them and update them when the stroke geometry changes
     hitboxes: Vec<Aabb>,
@@ -93,6 +101,15 @@ impl DrawBehaviour for ShapeStroke {
         cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
 
         self.shape.draw_composed(cx, &self.style);
+        if let Some(fill_pattern) = self
+            .fill_pattern
+            .as_deref()
+            .and_then(crate::fillpatterns::fill_pattern)
+        {
+            if let Some(fill_path) = self.fill_path() {
+                fill_pattern.draw_fill(cx, &fill_path, self.shape.bounds())?;
+            }
+        }
 
         cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
         Ok(())
@@ -104,7 +121,7 @@ impl ShapeBehaviour for ShapeStroke {
         match &self.style {
             Style::Smooth(options) => self.shape.composed_bounds(options),
             Style::Rough(options) => self.shape.composed_bounds(options),
-            Style::Textured(_) => self.shape.bounds(),
+            Style::Textured(_) | Style::Grain(_) | Style::Airbrush(_) => self.shape.bounds(),
         }
     }
 
@@ -130,6 +147,8 @@ impl ShapeStroke {
         let mut shapestroke = Self {
             shape,
             style,
+            description: None,
+            fill_pattern: None,
             hitboxes: vec![],
         };
         shapestroke.update_geometry();
@@ -137,6 +156,19 @@ impl ShapeStroke {
         shapestroke
     }
 
+    /// The path of the area that is filled, None when the shape is not closed.
+    pub fn fill_path(&self) -> Option<kurbo::BezPath> {
+        match &self.shape {
+            Shape::Rectangle(rectangle) => Some(rectangle.to_kurbo()),
+            Shape::Ellipse(ellipse) => Some(kurbo::Shape::to_path(&ellipse.to_kurbo(), 0.1)),
+            Shape::Polygon(polygon) => Some(polygon.to_kurbo()),
+            Shape::Line(_)
+            | Shape::Arrow(_)
+            | Shape::QuadraticBezier(_)
+            | Shape::CubicBezier(_) => None,
+        }
+    }
+
     fn gen_hitboxes_int(&self) -> Vec<Aabb> {
         let width = self.style.stroke_width();
 
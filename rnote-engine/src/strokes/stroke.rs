@@ -4,13 +4,13 @@ use super::brushstroke::BrushStroke;
 use super::shapestroke::ShapeStroke;
 use super::strokebehaviour::GeneratedStrokeImages;
 use super::vectorimage::VectorImage;
-use super::{StrokeBehaviour, TextStroke};
+use super::{StrokeBehaviour, TextLayer, TextStroke};
 use crate::fileformats::xoppformat::{self, XoppColor};
 use crate::store::chrono_comp::StrokeLayer;
 use crate::{render, RnoteEngine};
 use crate::{utils, DrawBehaviour};
 use base64::Engine;
-use p2d::bounding_volume::Aabb;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::helpers::AabbHelpers;
 use rnote_compose::penpath::Element;
 use rnote_compose::shapes::{Rectangle, ShapeBehaviour};
@@ -193,6 +193,97 @@ impl Stroke {
             Stroke::VectorImage(_) | Stroke::BitmapImage(_) => StrokeLayer::Image,
         }
     }
+
+    /// A short, human readable name of the kind of stroke, used as accessible role.
+    pub fn accessible_role(&self) -> &'static str {
+        match self {
+            Stroke::BrushStroke(_) => "Drawing",
+            Stroke::ShapeStroke(_) => "Shape",
+            Stroke::TextStroke(_) => "Text",
+            Stroke::VectorImage(_) | Stroke::BitmapImage(_) => "Image",
+        }
+    }
+
+    /// The description (alt-text) of the stroke for screen reader users.
+    ///
+    /// Text strokes don't have a separate description, their text is accessible directly.
+    pub fn description(&self) -> Option<&str> {
+        match self {
+            Stroke::BrushStroke(brushstroke) => brushstroke.description.as_deref(),
+            Stroke::ShapeStroke(shapestroke) => shapestroke.description.as_deref(),
+            Stroke::TextStroke(_) => None,
+            Stroke::VectorImage(vectorimage) => vectorimage.description.as_deref(),
+            Stroke::BitmapImage(bitmapimage) => bitmapimage.description.as_deref(),
+        }
+    }
+
+    /// The style of brush and shape strokes.
+    pub fn style(&self) -> Option<&Style> {
+        match self {
+            Stroke::BrushStroke(brushstroke) => Some(&brushstroke.style),
+            Stroke::ShapeStroke(shapestroke) => Some(&shapestroke.style),
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => None,
+        }
+    }
+
+    /// The mutable style of brush and shape strokes.
+    pub fn style_mut(&mut self) -> Option<&mut Style> {
+        match self {
+            Stroke::BrushStroke(brushstroke) => Some(&mut brushstroke.style),
+            Stroke::ShapeStroke(shapestroke) => Some(&mut shapestroke.style),
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => None,
+        }
+    }
+
+    /// The text layer of image strokes, together with the rectangle the image occupies.
+    pub fn text_layer(&self) -> Option<(&TextLayer, &Rectangle)> {
+        match self {
+            Stroke::VectorImage(vectorimage) => {
+                Some((&vectorimage.text_layer, &vectorimage.rectangle))
+            }
+            Stroke::BitmapImage(bitmapimage) => {
+                Some((&bitmapimage.text_layer, &bitmapimage.rectangle))
+            }
+            Stroke::BrushStroke(_) | Stroke::ShapeStroke(_) | Stroke::TextStroke(_) => None,
+        }
+    }
+
+    /// Generate a standalone Svg document of the stroke, with a viewBox tightly fitting its bounds extended by the margin.
+    ///
+    /// Text is converted to paths, so the Svg does not depend on the installed fonts.
+    pub fn gen_standalone_svg(&self, margin: f64) -> anyhow::Result<String> {
+        let mut svg = self.gen_svg()?;
+        svg.bounds = self.bounds().loosened(margin);
+        // The simplification also moves the bounds to mins: [0.0, 0.0], maxs: extents
+        svg.simplify()?;
+
+        Ok(rnote_compose::utils::add_xml_header(
+            rnote_compose::utils::wrap_svg_root(
+                svg.svg_data.as_str(),
+                Some(svg.bounds),
+                Some(svg.bounds),
+                false,
+            )
+            .as_str(),
+        ))
+    }
+
+    /// Set the description (alt-text) of the stroke. Empty descriptions are removed.
+    ///
+    /// Returns false if the stroke does not support descriptions.
+    pub fn set_description(&mut self, description: Option<String>) -> bool {
+        let description = description
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty());
+        match self {
+            Stroke::BrushStroke(brushstroke) => brushstroke.description = description,
+            Stroke::ShapeStroke(shapestroke) => shapestroke.description = description,
+            Stroke::TextStroke(_) => return false,
+            Stroke::VectorImage(vectorimage) => vectorimage.description = description,
+            Stroke::BitmapImage(bitmapimage) => bitmapimage.description = description,
+        }
+        true
+    }
     pub fn from_xoppstroke(
         stroke: xoppformat::XoppStroke,
         offset: na::Vector2<f64>,
@@ -220,6 +311,15 @@ impl Stroke {
                 "from_xoppstroke() failed, stroke has empty widths vector"
             ));
         }
+        if !widths
+            .iter()
+            .chain(coords.iter().flat_map(|c| c.iter()))
+            .all(|v| v.is_finite())
+        {
+            return Err(anyhow::anyhow!(
+                "from_xoppstroke() failed, stroke has non-finite widths or coordinates"
+            ));
+        }
 
         let mut smooth_options = SmoothOptions::default();
 
@@ -246,7 +346,11 @@ impl Stroke {
         let mut stroke_width = widths.remove(0);
 
         // extract the maximum width ( the widths in xournal++'s format are not relative to the stroke width).
-        let max_width = widths.iter().cloned().reduce(f64::max);
+        let max_width = widths
+            .iter()
+            .cloned()
+            .reduce(f64::max)
+            .filter(|max_width| *max_width > 0.0);
 
         if let Some(max_width) = max_width {
             // the stroke width in rnote needs to be the maximum of all widths
@@ -261,6 +365,11 @@ impl Stroke {
             widths = (0..coords.len()).map(|_| 1.0).collect();
         };
 
+        if stroke_width <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "from_xoppstroke() failed, stroke has non-positive width {stroke_width}"
+            ));
+        }
         smooth_options.stroke_width = stroke_width;
 
         let penpath = PenPath::try_from_elements(
@@ -308,6 +417,7 @@ impl Stroke {
             ],
         )
         .translate(offset);
+        bounds.assert_valid()?;
 
         let bytes = base64::engine::general_purpose::STANDARD.decode(&xopp_image.data)?;
 
@@ -317,7 +427,12 @@ impl Stroke {
         };
         let image = render::Image::try_from_encoded_bytes(&bytes)?;
 
-        Ok(Stroke::BitmapImage(BitmapImage { image, rectangle }))
+        Ok(Stroke::BitmapImage(BitmapImage {
+            image,
+            rectangle,
+            description: None,
+            text_layer: TextLayer::default(),
+        }))
     }
 
     pub fn into_xopp(self, current_dpi: f64) -> Option<xoppformat::XoppStrokeType> {
@@ -337,6 +452,14 @@ impl Stroke {
                         options.stroke_width,
                         crate::utils::xoppcolor_from_color(options.stroke_color?),
                     ),
+                    Style::Grain(options) => (
+                        options.stroke_width,
+                        crate::utils::xoppcolor_from_color(options.stroke_color?),
+                    ),
+                    Style::Airbrush(options) => (
+                        options.stroke_width,
+                        crate::utils::xoppcolor_from_color(options.stroke_color?),
+                    ),
                 };
 
                 let tool = xoppformat::XoppTool::Pen;
@@ -355,7 +478,12 @@ impl Stroke {
                         Style::Smooth(options) => {
                             options.pressure_curve.apply(stroke_width, element.pressure)
                         }
-                        Style::Rough(_) | Style::Textured(_) => stroke_width * element.pressure,
+                        Style::Grain(options) => {
+                            options.pressure_curve.apply(stroke_width, element.pressure)
+                        }
+                        Style::Rough(_) | Style::Textured(_) | Style::Airbrush(_) => {
+                            stroke_width * element.pressure
+                        }
                     })
                     .collect();
                 width_vec.append(&mut pressures);
@@ -0,0 +1,469 @@
+// Imports
+use super::Stroke;
+use kurbo::ParamCurveNearest;
+use rnote_compose::penpath::{Element, Segment};
+use rnote_compose::shapes::{CubicBezier, QuadraticBezier};
+use rnote_compose::{PenPath, Shape};
+
+/// The kind of a stroke node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrokeNodeKind {
+    /// A node the stroke outline passes through.
+    Anchor,
+    /// A curve control point, belonging to the anchor with the given node index.
+    Control { anchor: usize },
+}
+
+/// An editable node of a stroke.
+#[derive(Debug, Clone, Copy)]
+pub struct StrokeNode {
+    pub pos: na::Vector2<f64>,
+    pub kind: StrokeNodeKind,
+}
+
+impl StrokeNode {
+    fn anchor(pos: na::Vector2<f64>) -> Self {
+        Self {
+            pos,
+            kind: StrokeNodeKind::Anchor,
+        }
+    }
+
+    fn control(pos: na::Vector2<f64>, anchor: usize) -> Self {
+        Self {
+            pos,
+            kind: StrokeNodeKind::Control { anchor },
+        }
+    }
+}
+
+impl Stroke {
+    /// The editable nodes of the stroke.
+    ///
+    /// Empty for strokes that don't support editing their nodes.
+    pub fn nodes(&self) -> Vec<StrokeNode> {
+        match self {
+            Stroke::BrushStroke(brushstroke) => penpath_nodes(&brushstroke.path),
+            Stroke::ShapeStroke(shapestroke) => shape_nodes(&shapestroke.shape),
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => vec![],
+        }
+    }
+
+    /// Move the node with the given index to a new position.
+    ///
+    /// Returns false when the node does not exist.
+    /// The stroke then needs to update its geometry and rendering.
+    pub fn move_node(&mut self, index: usize, pos: na::Vector2<f64>) -> bool {
+        match self {
+            Stroke::BrushStroke(brushstroke) => {
+                penpath_move_node(&mut brushstroke.path, index, pos)
+            }
+            Stroke::ShapeStroke(shapestroke) => shape_move_node(&mut shapestroke.shape, index, pos),
+            Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => false,
+        }
+    }
+
+    /// Insert a new anchor node on the stroke outline where it is nearest to the given position.
+    ///
+    /// Returns the index of the inserted node, or None when the stroke does not support inserting nodes.
+    /// The stroke then needs to update its geometry and rendering.
+    pub fn insert_node(&mut self, pos: na::Vector2<f64>) -> Option<usize> {
+        match self {
+            Stroke::BrushStroke(brushstroke) => penpath_insert_node(&mut brushstroke.path, pos),
+            _ => None,
+        }
+    }
+
+    /// Remove the anchor node with the given index.
+    ///
+    /// Returns false when the node can't be removed.
+    /// The stroke then needs to update its geometry and rendering.
+    pub fn remove_node(&mut self, index: usize) -> bool {
+        match self {
+            Stroke::BrushStroke(brushstroke) => penpath_remove_node(&mut brushstroke.path, index),
+            _ => false,
+        }
+    }
+}
+
+/// Identifies a node inside a pen path.
+#[derive(Debug, Clone, Copy)]
+enum PenPathNode {
+    Start,
+    Cp { seg: usize },
+    Cp1 { seg: usize },
+    Cp2 { seg: usize },
+    End { seg: usize },
+}
+
+fn penpath_nodes(path: &PenPath) -> Vec<StrokeNode> {
+    let mut nodes = vec![StrokeNode::anchor(path.start.pos)];
+    let mut prev_anchor = 0;
+
+    for seg in path.segments.iter() {
+        match seg {
+            Segment::LineTo { end } => {
+                nodes.push(StrokeNode::anchor(end.pos));
+            }
+            Segment::QuadBezTo { cp, end } => {
+                nodes.push(StrokeNode::control(*cp, prev_anchor));
+                nodes.push(StrokeNode::anchor(end.pos));
+            }
+            Segment::CubBezTo { cp1, cp2, end } => {
+                nodes.push(StrokeNode::control(*cp1, prev_anchor));
+                nodes.push(StrokeNode::control(*cp2, nodes.len() + 1));
+                nodes.push(StrokeNode::anchor(end.pos));
+            }
+        }
+        prev_anchor = nodes.len() - 1;
+    }
+
+    nodes
+}
+
+fn penpath_node(path: &PenPath, index: usize) -> Option<PenPathNode> {
+    if index == 0 {
+        return Some(PenPathNode::Start);
+    }
+    let mut i = 1;
+    for (seg_i, seg) in path.segments.iter().enumerate() {
+        let seg_nodes: &[PenPathNode] = match seg {
+            Segment::LineTo { .. } => &[PenPathNode::End { seg: seg_i }],
+            Segment::QuadBezTo { .. } => &[
+                PenPathNode::Cp { seg: seg_i },
+                PenPathNode::End { seg: seg_i },
+            ],
+            Segment::CubBezTo { .. } => &[
+                PenPathNode::Cp1 { seg: seg_i },
+                PenPathNode::Cp2 { seg: seg_i },
+                PenPathNode::End { seg: seg_i },
+            ],
+        };
+        if index < i + seg_nodes.len() {
+            return Some(seg_nodes[index - i]);
+        }
+        i += seg_nodes.len();
+    }
+    None
+}
+
+/// The node index of the end anchor of the segment.
+fn penpath_segment_end_index(path: &PenPath, seg_i: usize) -> usize {
+    path.segments[..=seg_i]
+        .iter()
+        .map(|seg| match seg {
+            Segment::LineTo { .. } => 1,
+            Segment::QuadBezTo { .. } => 2,
+            Segment::CubBezTo { .. } => 3,
+        })
+        .sum()
+}
+
+fn segment_end_mut(seg: &mut Segment) -> &mut Element {
+    match seg {
+        Segment::LineTo { end }
+        | Segment::QuadBezTo { end, .. }
+        | Segment::CubBezTo { end, .. } => end,
+    }
+}
+
+fn penpath_move_node(path: &mut PenPath, index: usize, pos: na::Vector2<f64>) -> bool {
+    let Some(node) = penpath_node(path, index) else {
+        return false;
+    };
+
+    // Control points attached to a moved anchor are moved along with it
+    let move_attached_controls = |path: &mut PenPath, offset: na::Vector2<f64>, next_seg: usize| {
+        if let Some(Segment::CubBezTo { cp1, .. }) = path.segments.get_mut(next_seg) {
+            *cp1 += offset;
+        }
+    };
+
+    match node {
+        PenPathNode::Start => {
+            let offset = pos - path.start.pos;
+            path.start.pos = pos;
+            move_attached_controls(path, offset, 0);
+        }
+        PenPathNode::Cp { seg } => {
+            if let Segment::QuadBezTo { cp, .. } = &mut path.segments[seg] {
+                *cp = pos;
+            }
+        }
+        PenPathNode::Cp1 { seg } => {
+            if let Segment::CubBezTo { cp1, .. } = &mut path.segments[seg] {
+                *cp1 = pos;
+            }
+        }
+        PenPathNode::Cp2 { seg } => {
+            if let Segment::CubBezTo { cp2, .. } = &mut path.segments[seg] {
+                *cp2 = pos;
+            }
+        }
+        PenPathNode::End { seg } => {
+            let end = segment_end_mut(&mut path.segments[seg]);
+            let offset = pos - end.pos;
+            end.pos = pos;
+            if let Segment::CubBezTo { cp2, .. } = &mut path.segments[seg] {
+                *cp2 += offset;
+            }
+            move_attached_controls(path, offset, seg + 1);
+        }
+    }
+
+    true
+}
+
+fn penpath_insert_node(path: &mut PenPath, pos: na::Vector2<f64>) -> Option<usize> {
+    let point = kurbo::Point::new(pos[0], pos[1]);
+
+    // Find the segment and the curve parameter nearest to the position
+    let mut prev = path.start;
+    let mut nearest: Option<(usize, f64, f64)> = None;
+    for (seg_i, seg) in path.segments.iter().enumerate() {
+        let seg_nearest = match seg {
+            Segment::LineTo { end } => kurbo::Line::new(
+                kurbo::Point::new(prev.pos[0], prev.pos[1]),
+                kurbo::Point::new(end.pos[0], end.pos[1]),
+            )
+            .nearest(point, 1e-3),
+            Segment::QuadBezTo { cp, end } => QuadraticBezier {
+                start: prev.pos,
+                cp: *cp,
+                end: end.pos,
+            }
+            .to_kurbo()
+            .nearest(point, 1e-3),
+            Segment::CubBezTo { cp1, cp2, end } => CubicBezier {
+                start: prev.pos,
+                cp1: *cp1,
+                cp2: *cp2,
+                end: end.pos,
+            }
+            .to_kurbo()
+            .nearest(point, 1e-3),
+        };
+        if nearest.map_or(true, |(_, _, distance_sq)| {
+            seg_nearest.distance_sq < distance_sq
+        }) {
+            nearest = Some((seg_i, seg_nearest.t, seg_nearest.distance_sq));
+        }
+        prev = seg.end();
+    }
+
+    let (seg_i, t, _) = nearest?;
+    let start = if seg_i == 0 {
+        path.start
+    } else {
+        path.segments[seg_i - 1].end()
+    };
+    let end = path.segments[seg_i].end();
+    let split_pressure = start.pressure + (end.pressure - start.pressure) * t;
+
+    let (first, second) = match path.segments[seg_i] {
+        Segment::LineTo { end } => {
+            let split = Element::new(start.pos.lerp(&end.pos, t), split_pressure);
+            (Segment::LineTo { end: split }, Segment::LineTo { end })
+        }
+        Segment::QuadBezTo { cp, end } => {
+            let (first, second) = QuadraticBezier {
+                start: start.pos,
+                cp,
+                end: end.pos,
+            }
+            .split(t);
+            (
+                Segment::QuadBezTo {
+                    cp: first.cp,
+                    end: Element::new(first.end, split_pressure),
+                },
+                Segment::QuadBezTo { cp: second.cp, end },
+            )
+        }
+        Segment::CubBezTo { cp1, cp2, end } => {
+            let (first, second) = CubicBezier {
+                start: start.pos,
+                cp1,
+                cp2,
+                end: end.pos,
+            }
+            .split(t);
+            (
+                Segment::CubBezTo {
+                    cp1: first.cp1,
+                    cp2: first.cp2,
+                    end: Element::new(first.end, split_pressure),
+                },
+                Segment::CubBezTo {
+                    cp1: second.cp1,
+                    cp2: second.cp2,
+                    end,
+                },
+            )
+        }
+    };
+    path.segments[seg_i] = first;
+    path.segments.insert(seg_i + 1, second);
+
+    Some(penpath_segment_end_index(path, seg_i))
+}
+
+fn penpath_remove_node(path: &mut PenPath, index: usize) -> bool {
+    // Keep at least a single segment
+    if path.segments.len() < 2 {
+        return false;
+    }
+
+    match penpath_node(path, index) {
+        Some(PenPathNode::Start) => {
+            let first = path.segments.remove(0);
+            path.start = first.end();
+            true
+        }
+        Some(PenPathNode::End { seg }) => {
+            // The following segment is joined to the previous anchor
+            let removed = path.segments.remove(seg);
+            if seg == path.segments.len() {
+                return true;
+            }
+            if let (Segment::CubBezTo { cp1, .. }, Segment::CubBezTo { cp1: next_cp1, .. }) =
+                (removed, &mut path.segments[seg])
+            {
+                *next_cp1 = cp1;
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+fn shape_nodes(shape: &Shape) -> Vec<StrokeNode> {
+    match shape {
+        Shape::Line(line) => vec![StrokeNode::anchor(line.start), StrokeNode::anchor(line.end)],
+        Shape::Arrow(arrow) => vec![
+            StrokeNode::anchor(arrow.start),
+            StrokeNode::anchor(arrow.tip),
+        ],
+        Shape::Rectangle(rectangle) => {
+            let half_extents = rectangle.cuboid.half_extents;
+            rectangle_corners_local(half_extents)
+                .into_iter()
+                .map(|corner| {
+                    StrokeNode::anchor(rectangle.transform.transform_point(corner.into()).coords)
+                })
+                .collect()
+        }
+        Shape::Ellipse(ellipse) => [
+            na::vector![ellipse.radii[0], 0.0],
+            na::vector![0.0, ellipse.radii[1]],
+        ]
+        .into_iter()
+        .map(|radius| StrokeNode::anchor(ellipse.transform.transform_point(radius.into()).coords))
+        .collect(),
+        Shape::QuadraticBezier(quadbez) => vec![
+            StrokeNode::anchor(quadbez.start),
+            StrokeNode::control(quadbez.cp, 0),
+            StrokeNode::anchor(quadbez.end),
+        ],
+        Shape::CubicBezier(cubbez) => vec![
+            StrokeNode::anchor(cubbez.start),
+            StrokeNode::control(cubbez.cp1, 0),
+            StrokeNode::control(cubbez.cp2, 3),
+            StrokeNode::anchor(cubbez.end),
+        ],
+        Shape::Polygon(polygon) => polygon
+            .points
+            .iter()
+            .map(|point| StrokeNode::anchor(*point))
+            .collect(),
+    }
+}
+
+fn rectangle_corners_local(half_extents: na::Vector2<f64>) -> [na::Vector2<f64>; 4] {
+    [
+        na::vector![-half_extents[0], -half_extents[1]],
+        na::vector![half_extents[0], -half_extents[1]],
+        na::vector![half_extents[0], half_extents[1]],
+        na::vector![-half_extents[0], half_extents[1]],
+    ]
+}
+
+fn shape_move_node(shape: &mut Shape, index: usize, pos: na::Vector2<f64>) -> bool {
+    match (shape, index) {
+        (Shape::Line(line), 0) => line.start = pos,
+        (Shape::Line(line), 1) => line.end = pos,
+        (Shape::Arrow(arrow), 0) => arrow.start = pos,
+        (Shape::Arrow(arrow), 1) => arrow.tip = pos,
+        (Shape::Rectangle(rectangle), 0..=3) => {
+            // The opposite corner stays in place
+            let Some(inverse) = rectangle.transform.affine.try_inverse() else {
+                return false;
+            };
+            let corners = rectangle_corners_local(rectangle.cuboid.half_extents);
+            let opposite = corners[(index + 2) % 4];
+            let moved = (inverse * na::Point2::from(pos)).coords;
+            let center = (opposite + moved) * 0.5;
+
+            rectangle.cuboid.half_extents = ((moved - opposite) * 0.5).abs();
+            rectangle.transform.affine *= na::Translation2::from(center);
+        }
+        (Shape::Ellipse(ellipse), 0..=1) => {
+            let Some(inverse) = ellipse.transform.affine.try_inverse() else {
+                return false;
+            };
+            let local = (inverse * na::Point2::from(pos)).coords;
+            ellipse.radii[index] = local[index].abs();
+        }
+        (Shape::QuadraticBezier(quadbez), 0) => quadbez.start = pos,
+        (Shape::QuadraticBezier(quadbez), 1) => quadbez.cp = pos,
+        (Shape::QuadraticBezier(quadbez), 2) => quadbez.end = pos,
+        (Shape::CubicBezier(cubbez), 0) => cubbez.start = pos,
+        (Shape::CubicBezier(cubbez), 1) => cubbez.cp1 = pos,
+        (Shape::CubicBezier(cubbez), 2) => cubbez.cp2 = pos,
+        (Shape::CubicBezier(cubbez), 3) => cubbez.end = pos,
+        (Shape::Polygon(polygon), index) if index < polygon.points.len() => {
+            polygon.points[index] = pos
+        }
+        _ => return false,
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_and_remove_penpath_nodes() {
+        let mut path = PenPath::new_w_segments(
+            Element::new(na::vector![0.0, 0.0], 0.5),
+            [
+                Segment::LineTo {
+                    end: Element::new(na::vector![10.0, 0.0], 0.5),
+                },
+                Segment::CubBezTo {
+                    cp1: na::vector![12.0, 0.0],
+                    cp2: na::vector![20.0, 8.0],
+                    end: Element::new(na::vector![20.0, 10.0], 0.5),
+                },
+            ],
+        );
+        assert_eq!(penpath_nodes(&path).len(), 5);
+
+        let index = penpath_insert_node(&mut path, na::vector![5.0, 1.0]).unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(penpath_nodes(&path)[index].pos, na::vector![5.0, 0.0]);
+
+        assert!(penpath_move_node(&mut path, 2, na::vector![10.0, 2.0]));
+        assert_eq!(path.segments[2].end().pos, na::vector![20.0, 10.0]);
+        assert!(matches!(
+            path.segments[2],
+            Segment::CubBezTo { cp1, .. } if cp1 == na::vector![12.0, 2.0]
+        ));
+
+        // Control points can't be removed
+        assert!(!penpath_remove_node(&mut path, 3));
+        assert!(penpath_remove_node(&mut path, 1));
+        assert_eq!(path.segments.len(), 2);
+    }
+}
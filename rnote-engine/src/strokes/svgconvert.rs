@@ -0,0 +1,368 @@
+// Imports
+use super::textstroke::{FontStyle, TextStyle};
+use super::{ShapeStroke, Stroke, TextStroke, VectorImage};
+use crate::EngineError;
+use rnote_compose::helpers::Vector2Helpers;
+use rnote_compose::shapes::{CubicBezier, Ellipse, Line, QuadraticBezier, Rectangle};
+use rnote_compose::style::smooth::SmoothOptions;
+use rnote_compose::transform::Transform;
+use rnote_compose::{Color, Shape, Style};
+use usvg::TreeParsing;
+
+/// Paths with more segments are kept in the vector image, because they would be split into too many shape strokes.
+const PATH_MAX_SEGMENTS: usize = 32;
+/// The tolerance when recognizing rectangles and ellipses, relative to their size.
+const RECOGNIZE_TOLERANCE: f64 = 1e-3;
+
+/// Converts the primitive elements of the Svg into editable strokes.
+///
+/// Rectangles and ellipses become the matching shape strokes, other stroked paths become line and bezier curve shape strokes
+/// and text becomes text strokes. Everything that can't be represented (e.g. gradients, filled paths, clipped or masked groups)
+/// is kept in the returned vector image, which is None when everything was converted.
+pub fn import_svg_as_strokes(
+    svg_data: &str,
+    pos: na::Vector2<f64>,
+    size: Option<na::Vector2<f64>>,
+) -> anyhow::Result<(Option<VectorImage>, Vec<Stroke>)> {
+    let svg_tree = usvg::Tree::from_str(svg_data, &usvg::Options::default())
+        .map_err(|e| EngineError::Decode(format!("parsing Svg failed, {e}")))?;
+    let size = size.unwrap_or_else(|| {
+        na::vector![
+            f64::from(svg_tree.size.width()),
+            f64::from(svg_tree.size.height())
+        ]
+    });
+    let view_box = svg_tree.view_box.rect;
+    // Maps Svg user units to document coordinates
+    let to_doc = kurbo::Affine::translate(pos.to_kurbo_vec())
+        * kurbo::Affine::scale_non_uniform(
+            size[0] / f64::from(view_box.width()),
+            size[1] / f64::from(view_box.height()),
+        )
+        * kurbo::Affine::translate((-f64::from(view_box.x()), -f64::from(view_box.y())));
+
+    let mut strokes = vec![];
+    let mut converted_nodes = vec![];
+    convert_node(&svg_tree.root, to_doc, &mut strokes, &mut converted_nodes);
+    for node in converted_nodes {
+        node.detach();
+    }
+
+    let vectorimage = if svg_tree.root.has_children() {
+        let remaining_svg_data =
+            usvg::TreeWriting::to_string(&svg_tree, &usvg::XmlOptions::default());
+        Some(VectorImage::import_from_svg_data(
+            &remaining_svg_data,
+            pos,
+            Some(size),
+        )?)
+    } else {
+        None
+    };
+
+    Ok((vectorimage, strokes))
+}
+
+/// Converts a usvg transform into a kurbo affine.
+pub(crate) fn usvg_transform_to_kurbo(ts: usvg::Transform) -> kurbo::Affine {
+    kurbo::Affine::new([
+        f64::from(ts.sx),
+        f64::from(ts.ky),
+        f64::from(ts.kx),
+        f64::from(ts.sy),
+        f64::from(ts.tx),
+        f64::from(ts.ty),
+    ])
+}
+
+/// Converts the data of a usvg path into a kurbo bezier path.
+pub(crate) fn usvg_path_to_bezpath(path: &usvg::Path) -> kurbo::BezPath {
+    let mut bez_path = kurbo::BezPath::new();
+    for segment in path.data.segments() {
+        match segment {
+            usvg::tiny_skia_path::PathSegment::MoveTo(p) => {
+                bez_path.move_to((f64::from(p.x), f64::from(p.y)))
+            }
+            usvg::tiny_skia_path::PathSegment::LineTo(p) => {
+                bez_path.line_to((f64::from(p.x), f64::from(p.y)))
+            }
+            usvg::tiny_skia_path::PathSegment::QuadTo(p1, p) => bez_path.quad_to(
+                (f64::from(p1.x), f64::from(p1.y)),
+                (f64::from(p.x), f64::from(p.y)),
+            ),
+            usvg::tiny_skia_path::PathSegment::CubicTo(p1, p2, p) => bez_path.curve_to(
+                (f64::from(p1.x), f64::from(p1.y)),
+                (f64::from(p2.x), f64::from(p2.y)),
+                (f64::from(p.x), f64::from(p.y)),
+            ),
+            usvg::tiny_skia_path::PathSegment::Close => bez_path.close_path(),
+        }
+    }
+    bez_path
+}
+
+/// The color of a paint, if it is a solid color.
+pub(crate) fn usvg_paint_color(paint: &usvg::Paint, opacity: f32) -> Option<Color> {
+    match paint {
+        usvg::Paint::Color(color) => Some(Color::new(
+            f64::from(color.red) / 255.0,
+            f64::from(color.green) / 255.0,
+            f64::from(color.blue) / 255.0,
+            f64::from(opacity),
+        )),
+        _ => None,
+    }
+}
+
+fn convert_node(
+    node: &usvg::Node,
+    parent_ts: kurbo::Affine,
+    strokes: &mut Vec<Stroke>,
+    converted_nodes: &mut Vec<usvg::Node>,
+) {
+    let ts = match *node.borrow() {
+        usvg::NodeKind::Group(ref group) => {
+            // The effects of these can't be represented by strokes
+            if group.opacity.get() < 1.0
+                || group.clip_path.is_some()
+                || group.mask.is_some()
+                || !group.filters.is_empty()
+            {
+                return;
+            }
+            parent_ts * usvg_transform_to_kurbo(group.transform)
+        }
+        usvg::NodeKind::Path(ref path) => {
+            if let Some(mut path_strokes) =
+                convert_path(path, parent_ts * usvg_transform_to_kurbo(path.transform))
+            {
+                strokes.append(&mut path_strokes);
+                converted_nodes.push(node.clone());
+            }
+            return;
+        }
+        usvg::NodeKind::Text(ref text) => {
+            if let Some(mut text_strokes) =
+                convert_text(text, parent_ts * usvg_transform_to_kurbo(text.transform))
+            {
+                strokes.append(&mut text_strokes);
+                converted_nodes.push(node.clone());
+            }
+            return;
+        }
+        _ => return,
+    };
+
+    for child in node.children() {
+        convert_node(&child, ts, strokes, converted_nodes);
+    }
+}
+
+fn convert_path(path: &usvg::Path, ts: kurbo::Affine) -> Option<Vec<Stroke>> {
+    if path.visibility != usvg::Visibility::Visible {
+        return None;
+    }
+    let scale = ts.determinant().abs().sqrt();
+    let mut smooth_options = SmoothOptions::default();
+    match &path.stroke {
+        Some(stroke) => {
+            smooth_options.stroke_color =
+                Some(usvg_paint_color(&stroke.paint, stroke.opacity.get())?);
+            smooth_options.stroke_width = f64::from(stroke.width.get()) * scale;
+        }
+        None => smooth_options.stroke_color = None,
+    }
+    smooth_options.fill_color = match &path.fill {
+        Some(fill) => Some(usvg_paint_color(&fill.paint, fill.opacity.get())?),
+        None => None,
+    };
+    if smooth_options.stroke_color.is_none() && smooth_options.fill_color.is_none() {
+        return None;
+    }
+    let style = Style::Smooth(smooth_options);
+
+    let mut bez_path = usvg_path_to_bezpath(path);
+    bez_path.apply_affine(ts);
+
+    if let Some(rectangle) = recognize_rectangle(&bez_path) {
+        return Some(vec![Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Rectangle(rectangle),
+            style,
+        ))]);
+    }
+    if let Some(ellipse) = recognize_ellipse(&bez_path) {
+        return Some(vec![Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Ellipse(ellipse),
+            style,
+        ))]);
+    }
+    // Only closed rectangles and ellipses can be filled
+    if path.fill.is_some() || path.stroke.is_none() {
+        return None;
+    }
+
+    let segments = bez_path.segments().collect::<Vec<kurbo::PathSeg>>();
+    if segments.is_empty() || segments.len() > PATH_MAX_SEGMENTS {
+        return None;
+    }
+    let v = |p: kurbo::Point| na::vector![p.x, p.y];
+
+    Some(
+        segments
+            .into_iter()
+            .map(|segment| {
+                let shape = match segment {
+                    kurbo::PathSeg::Line(line) => Shape::Line(Line {
+                        start: v(line.p0),
+                        end: v(line.p1),
+                    }),
+                    kurbo::PathSeg::Quad(quad) => Shape::QuadraticBezier(QuadraticBezier {
+                        start: v(quad.p0),
+                        cp: v(quad.p1),
+                        end: v(quad.p2),
+                    }),
+                    kurbo::PathSeg::Cubic(cubic) => Shape::CubicBezier(CubicBezier {
+                        start: v(cubic.p0),
+                        cp1: v(cubic.p1),
+                        cp2: v(cubic.p2),
+                        end: v(cubic.p3),
+                    }),
+                };
+                Stroke::ShapeStroke(ShapeStroke::new(shape, style.clone()))
+            })
+            .collect(),
+    )
+}
+
+/// Recognizes a closed path consisting of four lines with right angles.
+fn recognize_rectangle(bez_path: &kurbo::BezPath) -> Option<Rectangle> {
+    let els = bez_path.elements();
+    let mut points = vec![];
+    for (i, el) in els.iter().enumerate() {
+        match el {
+            kurbo::PathEl::MoveTo(p) if i == 0 => points.push(na::vector![p.x, p.y]),
+            kurbo::PathEl::LineTo(p) if i > 0 => points.push(na::vector![p.x, p.y]),
+            kurbo::PathEl::ClosePath if i == els.len() - 1 => {}
+            _ => return None,
+        }
+    }
+    // The closing line might be explicit
+    if points.len() == 5 && (points[4] - points[0]).norm() <= tolerance(&points) {
+        points.pop();
+    }
+    if points.len() != 4 {
+        return None;
+    }
+
+    let tol = tolerance(&points);
+    let (e0, e1) = (points[1] - points[0], points[2] - points[1]);
+    if e0.norm() <= tol
+        || e1.norm() <= tol
+        || e0.dot(&e1).abs() > tol * (e0.norm() + e1.norm())
+        || (points[0] + e1 - points[3]).norm() > tol
+    {
+        return None;
+    }
+
+    let center = (points[0] + points[2]) * 0.5;
+    let angle = e0[1].atan2(e0[0]);
+    Some(Rectangle {
+        cuboid: p2d::shape::Cuboid::new(na::vector![e0.norm() * 0.5, e1.norm() * 0.5]),
+        transform: Transform::new_w_isometry(na::Isometry2::new(center, angle)),
+    })
+}
+
+/// Recognizes a closed path consisting of four cubic curves, the way ellipses are represented in Svg paths.
+fn recognize_ellipse(bez_path: &kurbo::BezPath) -> Option<Ellipse> {
+    let els = bez_path.elements();
+    let mut points = vec![];
+    for (i, el) in els.iter().enumerate() {
+        match el {
+            kurbo::PathEl::MoveTo(p) if i == 0 => points.push(na::vector![p.x, p.y]),
+            kurbo::PathEl::CurveTo(_, _, p) if i > 0 => points.push(na::vector![p.x, p.y]),
+            kurbo::PathEl::ClosePath if i == els.len() - 1 => {}
+            _ => return None,
+        }
+    }
+    if points.len() != 5 {
+        return None;
+    }
+
+    let tol = tolerance(&points);
+    let center = (points[0] + points[2]) * 0.5;
+    let (a, b) = (points[0] - center, points[1] - center);
+    if (points[4] - points[0]).norm() > tol
+        || ((points[1] + points[3]) * 0.5 - center).norm() > tol
+        || a.norm() <= tol
+        || b.norm() <= tol
+        || a.dot(&b).abs() > tol * (a.norm() + b.norm())
+    {
+        return None;
+    }
+    let angle = a[1].atan2(a[0]);
+    let radii = na::vector![a.norm(), b.norm()];
+    // The curves must lie on the ellipse, else it is a different shape
+    let on_ellipse = bez_path.segments().all(|seg| {
+        let mid = kurbo::ParamCurve::eval(&seg, 0.5);
+        let local = na::Rotation2::new(-angle) * (na::vector![mid.x, mid.y] - center);
+        ((local[0] / radii[0]).powi(2) + (local[1] / radii[1]).powi(2) - 1.0).abs() < 0.01
+    });
+    if !on_ellipse {
+        return None;
+    }
+    let ellipse = Ellipse {
+        radii,
+        transform: Transform::new_w_isometry(na::Isometry2::new(center, angle)),
+    };
+
+    Some(ellipse)
+}
+
+fn tolerance(points: &[na::Vector2<f64>]) -> f64 {
+    let extent = points
+        .iter()
+        .flat_map(|p| points.iter().map(move |q| (p - q).norm()))
+        .fold(0.0, f64::max);
+    (extent * RECOGNIZE_TOLERANCE).max(1e-6)
+}
+
+fn convert_text(text: &usvg::Text, ts: kurbo::Affine) -> Option<Vec<TextStroke>> {
+    let scale = ts.determinant().abs().sqrt();
+
+    text.chunks
+        .iter()
+        .map(|chunk| {
+            let span = chunk.spans.first()?;
+            let color = match &span.fill {
+                Some(fill) => usvg_paint_color(&fill.paint, fill.opacity.get())?,
+                None => return None,
+            };
+            let font_size = f64::from(span.font_size.get()) * scale;
+            let baseline = ts
+                * kurbo::Point::new(
+                    f64::from(chunk.x.unwrap_or(0.0)),
+                    f64::from(chunk.y.unwrap_or(0.0)),
+                );
+            let text_style = TextStyle {
+                font_family: span
+                    .font
+                    .families
+                    .first()
+                    .cloned()
+                    .unwrap_or_else(|| String::from(TextStyle::FONT_FAMILY_DEFAULT)),
+                font_size,
+                font_weight: span.font.weight,
+                font_style: match span.font.style {
+                    usvg::FontStyle::Normal => FontStyle::Regular,
+                    usvg::FontStyle::Italic | usvg::FontStyle::Oblique => FontStyle::Italic,
+                },
+                color,
+                ..Default::default()
+            };
+            // Text strokes are positioned by their upper left corner, approximate it from the baseline
+            let upper_left = na::vector![baseline.x, baseline.y - font_size * 0.8];
+
+            Some(TextStroke::new(chunk.text.clone(), upper_left, text_style))
+        })
+        .collect()
+}
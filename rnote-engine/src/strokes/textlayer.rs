@@ -0,0 +1,167 @@
+// Imports
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rnote_compose::shapes::Rectangle;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// A word of the text layer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "text_layer_span")]
+pub struct TextLayerSpan {
+    #[serde(rename = "text")]
+    pub text: String,
+    /// The bounds of the word, relative to the size of the image, ranging [0.0, 1.0].
+    #[serde(rename = "bounds")]
+    pub bounds: Aabb,
+    /// The index of the line the word is on.
+    #[serde(rename = "line")]
+    pub line: usize,
+}
+
+impl Default for TextLayerSpan {
+    fn default() -> Self {
+        Self {
+            text: String::default(),
+            bounds: Aabb::new_invalid(),
+            line: 0,
+        }
+    }
+}
+
+/// The invisible text of an image, e.g. the text of an imported Pdf page.
+///
+/// It can be selected and copied, but is not rendered.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "text_layer")]
+pub struct TextLayer {
+    /// The words, in reading order.
+    #[serde(rename = "spans")]
+    pub spans: Vec<TextLayerSpan>,
+}
+
+impl TextLayer {
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Create the text layer from the characters of the text and their bounds.
+    ///
+    /// The characters are grouped into words, separated by whitespace. The bounds must be relative to the image size.
+    pub fn from_chars(text: &str, char_bounds: &[Aabb]) -> Self {
+        let mut spans = vec![];
+        let mut current: Option<TextLayerSpan> = None;
+        let mut line = 0;
+
+        for (c, bounds) in text.chars().zip(char_bounds.iter()) {
+            if c.is_whitespace() {
+                spans.extend(current.take());
+                if c == '\n' {
+                    line += 1;
+                }
+                continue;
+            }
+            match current.as_mut() {
+                Some(span) => {
+                    span.text.push(c);
+                    span.bounds.merge(bounds);
+                }
+                None => {
+                    current = Some(TextLayerSpan {
+                        text: c.to_string(),
+                        bounds: *bounds,
+                        line,
+                    })
+                }
+            }
+        }
+        spans.extend(current);
+
+        Self { spans }
+    }
+
+    /// Extract the text layer of the Pdf page.
+    pub fn from_pdf_page(page: &poppler::Page) -> Self {
+        let (width, height) = page.size();
+        let (Some(text), Some(rects)) = (page.text(), page.text_layout()) else {
+            return Self::default();
+        };
+        if width <= 0.0 || height <= 0.0 {
+            return Self::default();
+        }
+        let char_bounds = rects
+            .iter()
+            .map(|rect| {
+                Aabb::new_positive(
+                    na::point![rect.x1() / width, rect.y1() / height],
+                    na::point![rect.x2() / width, rect.y2() / height],
+                )
+            })
+            .collect::<Vec<Aabb>>();
+
+        Self::from_chars(text.as_str(), &char_bounds)
+    }
+
+    /// The bounds of the span on the document, for the text layer of an image that occupies the rectangle.
+    pub fn span_bounds_on_doc(span: &TextLayerSpan, rectangle: &Rectangle) -> Aabb {
+        let size = rectangle.cuboid.half_extents * 2.0;
+        let local = Aabb::new(
+            (span.bounds.mins.coords.component_mul(&size) - rectangle.cuboid.half_extents).into(),
+            (span.bounds.maxs.coords.component_mul(&size) - rectangle.cuboid.half_extents).into(),
+        );
+        rectangle.transform.transform_aabb(local)
+    }
+
+    /// The spans that intersect the bounds on the document, together with their bounds on the document.
+    pub fn spans_intersecting_bounds(
+        &self,
+        rectangle: &Rectangle,
+        bounds: Aabb,
+    ) -> Vec<(&TextLayerSpan, Aabb)> {
+        self.spans
+            .iter()
+            .map(|span| (span, Self::span_bounds_on_doc(span, rectangle)))
+            .filter(|(_, span_bounds)| span_bounds.intersects(&bounds))
+            .collect()
+    }
+
+    /// Join the text of the spans, with words separated by spaces and lines by newlines.
+    pub fn join_spans<'a>(spans: impl IntoIterator<Item = &'a TextLayerSpan>) -> String {
+        let mut text = String::new();
+        let mut prev_line = None;
+
+        for span in spans {
+            match prev_line {
+                Some(prev_line) if prev_line != span.line => text.push('\n'),
+                Some(_) => text.push(' '),
+                None => {}
+            }
+            text.push_str(&span.text);
+            prev_line = Some(span.line);
+        }
+        text
+    }
+
+    /// The entire text.
+    pub fn text(&self) -> String {
+        Self::join_spans(&self.spans)
+    }
+
+    /// The spans that overlap the byte range of the entire text returned by [TextLayer::text()].
+    pub fn spans_in_text_range(&self, range: Range<usize>) -> Vec<&TextLayerSpan> {
+        let mut spans = vec![];
+        let mut start = 0;
+
+        for (i, span) in self.spans.iter().enumerate() {
+            if i > 0 {
+                // The separating space or newline
+                start += 1;
+            }
+            let end = start + span.text.len();
+            if start < range.end && range.start < end {
+                spans.push(span);
+            }
+            start = end;
+        }
+        spans
+    }
+}
@@ -0,0 +1,511 @@
+// Imports
+use super::textstroke::{FontStyle, TextAlignment, TextAttribute, TextStyle};
+use crate::render::USVG_FONTDB;
+use kurbo::Shape;
+use p2d::bounding_volume::Aabb;
+use rnote_compose::Color;
+use rustybuzz::ttf_parser;
+use std::ops::Range;
+use usvg::fontdb;
+
+/// A filled outline of laid out text.
+#[derive(Debug, Clone)]
+pub struct TextOutline {
+    /// The outline, in the coordinate space of the text layout.
+    pub path: kurbo::BezPath,
+    pub color: Color,
+}
+
+/// The attributes of a run of text in which they don't change.
+#[derive(Debug, Clone)]
+struct RunStyle {
+    font_family: String,
+    font_size: f64,
+    font_weight: u16,
+    font_style: FontStyle,
+    color: Color,
+    underline: bool,
+    strikethrough: bool,
+}
+
+impl RunStyle {
+    /// The style at the byte index of the text, with the ranged attributes applied.
+    fn at(text_style: &TextStyle, index: usize) -> Self {
+        let mut style = Self {
+            font_family: text_style.font_family.clone(),
+            font_size: text_style.font_size,
+            font_weight: text_style.font_weight,
+            font_style: text_style.font_style,
+            color: text_style.color,
+            underline: false,
+            strikethrough: false,
+        };
+        // Later attributes override earlier ones, like when building the text layout
+        let mut ranged_text_attributes =
+            text_style.ranged_text_attributes.iter().collect::<Vec<_>>();
+        ranged_text_attributes.sort_by_key(|ranged_attr| ranged_attr.range.start);
+        for ranged_attr in ranged_text_attributes
+            .into_iter()
+            .filter(|ranged_attr| ranged_attr.range.contains(&index))
+        {
+            match &ranged_attr.attribute {
+                TextAttribute::FontFamily(family) => style.font_family = family.clone(),
+                TextAttribute::FontSize(size) => style.font_size = *size,
+                TextAttribute::FontWeight(weight) => style.font_weight = *weight,
+                TextAttribute::TextColor(color) => style.color = *color,
+                TextAttribute::Style(font_style) => style.font_style = *font_style,
+                TextAttribute::Underline(underline) => style.underline = *underline,
+                TextAttribute::Strikethrough(strikethrough) => style.strikethrough = *strikethrough,
+            }
+        }
+        style.font_family = crate::fonts::resolve_font_family(&style.font_family);
+        style
+    }
+}
+
+/// Vertical font metrics, scaled to the font size.
+#[derive(Debug, Clone, Copy)]
+struct FontMetrics {
+    ascent: f64,
+    /// Positive below the baseline.
+    descent: f64,
+    /// The offset of the underline from the baseline, positive below it, and its thickness.
+    underline: (f64, f64),
+    /// The offset of the strikethrough from the baseline, positive below it, and its thickness.
+    strikethrough: (f64, f64),
+}
+
+#[derive(Debug, Clone)]
+struct ShapedGlyph {
+    /// The index of the run the glyph belongs to.
+    run: usize,
+    offset: kurbo::Vec2,
+    advance: f64,
+    /// The outline relative to the glyph origin on the baseline.
+    outline: kurbo::BezPath,
+    /// Lines can be broken after whitespace.
+    is_whitespace: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    glyphs: Range<usize>,
+    /// The width without trailing whitespace.
+    width: f64,
+    ascent: f64,
+    descent: f64,
+    /// Whether the line was broken because it was too long, and not at a hard line break.
+    wrapped: bool,
+}
+
+/// Lay out the text with the style and convert the glyphs to outlines, without the cairo and pango text stack.
+///
+/// The fonts are looked up in the font database and the text is shaped with rustybuzz, so the outlines only depend on
+/// the available fonts. Lines are broken at hard line breaks and, when the style has a max width, greedily at whitespace.
+/// The outlines are in the coordinate space of the text layout, with the origin at its upper left corner.
+///
+/// The layout is simpler than the one of pango: runs are always laid out in logical order,
+/// so mixed text directions inside a line are not reordered.
+pub fn text_outlines(text: &str, text_style: &TextStyle) -> anyhow::Result<Vec<TextOutline>> {
+    let fontdb = USVG_FONTDB.read().unwrap();
+    let base_metrics = shape_run(&fontdb, "", &RunStyle::at(text_style, 0))?.0;
+
+    let mut run_styles = vec![];
+    let mut run_metrics = vec![];
+    let mut glyphs = vec![];
+    let mut lines = vec![];
+    let mut paragraph_start = 0;
+    for paragraph in text.split('\n') {
+        let paragraph_range = paragraph_start..paragraph_start + paragraph.len();
+        paragraph_start = paragraph_range.end + 1;
+
+        let first_glyph = glyphs.len();
+        for run_range in run_ranges(text, paragraph_range, text_style) {
+            let style = RunStyle::at(text_style, run_range.start);
+            let (metrics, run_glyphs) = shape_run(&fontdb, &text[run_range], &style)?;
+            let run = run_styles.len();
+            glyphs.extend(
+                run_glyphs
+                    .into_iter()
+                    .map(|glyph| ShapedGlyph { run, ..glyph }),
+            );
+            run_styles.push(style);
+            run_metrics.push(metrics);
+        }
+
+        let paragraph_lines = break_lines(&glyphs[first_glyph..], text_style.max_width);
+        let n_lines = paragraph_lines.len();
+        for (i, line_glyphs) in paragraph_lines.into_iter().enumerate() {
+            let line_glyphs = first_glyph + line_glyphs.start..first_glyph + line_glyphs.end;
+            let (ascent, descent) = glyphs[line_glyphs.clone()]
+                .iter()
+                .map(|glyph| &run_metrics[glyph.run])
+                .fold(None, |acc: Option<(f64, f64)>, metrics: &FontMetrics| {
+                    Some(match acc {
+                        Some((ascent, descent)) => {
+                            (ascent.max(metrics.ascent), descent.max(metrics.descent))
+                        }
+                        None => (metrics.ascent, metrics.descent),
+                    })
+                })
+                .unwrap_or((base_metrics.ascent, base_metrics.descent));
+            let trailing_whitespace = glyphs[line_glyphs.clone()]
+                .iter()
+                .rev()
+                .take_while(|glyph| glyph.is_whitespace)
+                .map(|glyph| glyph.advance)
+                .sum::<f64>();
+            let width = glyphs[line_glyphs.clone()]
+                .iter()
+                .map(|glyph| glyph.advance)
+                .sum::<f64>()
+                - trailing_whitespace;
+            lines.push(Line {
+                glyphs: line_glyphs,
+                width,
+                ascent,
+                descent,
+                wrapped: i + 1 < n_lines,
+            });
+        }
+    }
+
+    let layout_width = text_style
+        .max_width
+        .unwrap_or_else(|| lines.iter().map(|line| line.width).fold(0.0, f64::max));
+    let mut outlines = vec![];
+    let mut line_top = 0.0;
+    for line in lines.iter() {
+        let baseline = line_top + line.ascent;
+        let free_space = (layout_width - line.width).max(0.0);
+        let mut x = match text_style.alignment {
+            TextAlignment::Start | TextAlignment::Fill => 0.0,
+            TextAlignment::Center => free_space * 0.5,
+            TextAlignment::End => free_space,
+        };
+        // Justified lines distribute the free space on the inner whitespace
+        let line_glyphs = &glyphs[line.glyphs.clone()];
+        let n_trailing = line_glyphs
+            .iter()
+            .rev()
+            .take_while(|glyph| glyph.is_whitespace)
+            .count();
+        let inner_glyphs = &line_glyphs[..line_glyphs.len() - n_trailing];
+        let n_spaces = inner_glyphs
+            .iter()
+            .filter(|glyph| glyph.is_whitespace)
+            .count();
+        let space_extra = match text_style.alignment {
+            TextAlignment::Fill if line.wrapped && n_spaces > 0 => free_space / n_spaces as f64,
+            _ => 0.0,
+        };
+
+        let mut current: Option<(usize, kurbo::BezPath, kurbo::BezPath)> = None;
+        for glyph in line_glyphs.iter() {
+            if current.as_ref().map(|(run, ..)| *run) != Some(glyph.run) {
+                if let Some((run, path, decorations)) = current.take() {
+                    push_outlines(&mut outlines, path, decorations, run_styles[run].color);
+                }
+                current = Some((glyph.run, kurbo::BezPath::new(), kurbo::BezPath::new()));
+            }
+            let Some((_, path, decorations)) = current.as_mut() else {
+                continue;
+            };
+            let origin = kurbo::Vec2::new(x, baseline) + glyph.offset;
+            for el in (kurbo::Affine::translate(origin) * glyph.outline.clone()).elements() {
+                path.push(*el);
+            }
+
+            let mut advance = glyph.advance;
+            if glyph.is_whitespace {
+                advance += space_extra;
+            }
+            let style = &run_styles[glyph.run];
+            let metrics = &run_metrics[glyph.run];
+            for (enabled, (offset, thickness)) in [
+                (style.underline, metrics.underline),
+                (style.strikethrough, metrics.strikethrough),
+            ] {
+                if enabled {
+                    let top = baseline + offset - thickness * 0.5;
+                    for el in
+                        kurbo::Rect::new(x, top, x + advance, top + thickness).path_elements(0.1)
+                    {
+                        decorations.push(el);
+                    }
+                }
+            }
+            x += advance;
+        }
+        if let Some((run, path, decorations)) = current.take() {
+            push_outlines(&mut outlines, path, decorations, run_styles[run].color);
+        }
+        line_top = baseline + line.descent;
+    }
+    Ok(outlines)
+}
+
+/// A Svg group with the outlines as filled paths.
+pub fn outlines_svg_group(outlines: Vec<TextOutline>) -> svg::node::element::Group {
+    outlines
+        .into_iter()
+        .fold(svg::node::element::Group::new(), |group, outline| {
+            group.add(
+                svg::node::element::Path::new()
+                    .set("fill", outline.color.to_css_color_attr())
+                    .set("d", outline.path.to_svg()),
+            )
+        })
+}
+
+/// The bounds of the outlines. None when there are no outlines, e.g. for whitespace-only text.
+pub fn outlines_bounds(outlines: &[TextOutline]) -> Option<Aabb> {
+    outlines
+        .iter()
+        .map(|outline| outline.path.bounding_box())
+        .reduce(|acc, bbox| acc.union(bbox))
+        .map(|bbox| Aabb::new(na::point![bbox.x0, bbox.y0], na::point![bbox.x1, bbox.y1]))
+}
+
+fn push_outlines(
+    outlines: &mut Vec<TextOutline>,
+    path: kurbo::BezPath,
+    decorations: kurbo::BezPath,
+    color: Color,
+) {
+    for path in [path, decorations] {
+        if !path.elements().is_empty() {
+            outlines.push(TextOutline { path, color });
+        }
+    }
+}
+
+/// Split the paragraph into ranges at the boundaries of the ranged text attributes.
+fn run_ranges(text: &str, paragraph: Range<usize>, text_style: &TextStyle) -> Vec<Range<usize>> {
+    let mut boundaries = text_style
+        .ranged_text_attributes
+        .iter()
+        .flat_map(|ranged_attr| [ranged_attr.range.start, ranged_attr.range.end])
+        .filter(|&i| i > paragraph.start && i < paragraph.end && text.is_char_boundary(i))
+        .chain([paragraph.start, paragraph.end])
+        .collect::<Vec<usize>>();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+    boundaries
+        .windows(2)
+        .map(|w| w[0]..w[1])
+        .filter(|range| !range.is_empty())
+        .collect()
+}
+
+/// Break the glyphs of a paragraph into lines, returning the glyph ranges of the lines.
+///
+/// Lines are broken after the last whitespace that fits, or before the overflowing glyph if there is none.
+fn break_lines(glyphs: &[ShapedGlyph], max_width: Option<f64>) -> Vec<Range<usize>> {
+    let Some(max_width) = max_width else {
+        return vec![0..glyphs.len()];
+    };
+    let mut lines = vec![];
+    let mut start = 0;
+    let mut width = 0.0;
+    let mut last_break: Option<usize> = None;
+    let mut i = 0;
+    while i < glyphs.len() {
+        let glyph = &glyphs[i];
+        if !glyph.is_whitespace && width + glyph.advance > max_width && i > start {
+            let end = last_break.map(|b| b + 1).unwrap_or(i);
+            lines.push(start..end);
+            start = end;
+            width = glyphs[start..i].iter().map(|g| g.advance).sum();
+            last_break = (start..i).rev().find(|&j| glyphs[j].is_whitespace);
+            continue;
+        }
+        width += glyph.advance;
+        if glyph.is_whitespace {
+            last_break = Some(i);
+        }
+        i += 1;
+    }
+    lines.push(start..glyphs.len());
+    lines
+}
+
+/// Shape the text with the font that matches the style, returning the font metrics and the glyphs.
+fn shape_run(
+    fontdb: &fontdb::Database,
+    text: &str,
+    style: &RunStyle,
+) -> anyhow::Result<(FontMetrics, Vec<ShapedGlyph>)> {
+    let generic_family = match style.font_family.to_lowercase().as_str() {
+        "serif" => Some(fontdb::Family::Serif),
+        "sans-serif" | "sans" => Some(fontdb::Family::SansSerif),
+        "monospace" => Some(fontdb::Family::Monospace),
+        "cursive" => Some(fontdb::Family::Cursive),
+        "fantasy" => Some(fontdb::Family::Fantasy),
+        _ => None,
+    };
+    let families =
+        std::iter::once(generic_family.unwrap_or(fontdb::Family::Name(&style.font_family)))
+            .chain(
+                crate::fonts::FONT_FAMILY_FALLBACKS
+                    .iter()
+                    .map(|family| fontdb::Family::Name(family)),
+            )
+            .collect::<Vec<fontdb::Family>>();
+    let face_id = fontdb
+        .query(&fontdb::Query {
+            families: &families,
+            weight: fontdb::Weight(style.font_weight),
+            stretch: fontdb::Stretch::Normal,
+            style: match style.font_style {
+                FontStyle::Regular => fontdb::Style::Normal,
+                FontStyle::Italic => fontdb::Style::Italic,
+            },
+        })
+        .ok_or_else(|| {
+            anyhow::anyhow!("no font face found for font family `{}`", style.font_family)
+        })?;
+
+    fontdb
+        .with_face_data(face_id, |data, index| {
+            let face = rustybuzz::Face::from_slice(data, index).ok_or_else(|| {
+                anyhow::anyhow!("parsing face of font family `{}` failed", style.font_family)
+            })?;
+            let scale = style.font_size / f64::from(face.units_per_em());
+            let line_metrics = |metrics: Option<ttf_parser::LineMetrics>, fallback: (f64, f64)| {
+                metrics
+                    .map(|m| {
+                        (
+                            -f64::from(m.position) * scale,
+                            f64::from(m.thickness) * scale,
+                        )
+                    })
+                    .unwrap_or(fallback)
+            };
+            let ascent = f64::from(face.ascender()) * scale;
+            let thickness_fallback = style.font_size / 14.0;
+            let metrics = FontMetrics {
+                ascent,
+                descent: -f64::from(face.descender()) * scale,
+                underline: line_metrics(
+                    face.underline_metrics(),
+                    (thickness_fallback, thickness_fallback),
+                ),
+                strikethrough: line_metrics(
+                    face.strikeout_metrics(),
+                    (-ascent * 0.3, thickness_fallback),
+                ),
+            };
+
+            let mut buffer = rustybuzz::UnicodeBuffer::new();
+            buffer.push_str(text);
+            buffer.guess_segment_properties();
+            let glyph_buffer = rustybuzz::shape(&face, &[], buffer);
+            let glyphs = glyph_buffer
+                .glyph_infos()
+                .iter()
+                .zip(glyph_buffer.glyph_positions())
+                .map(|(info, pos)| {
+                    let mut builder = OutlineBuilder {
+                        path: kurbo::BezPath::new(),
+                        scale,
+                    };
+                    face.outline_glyph(ttf_parser::GlyphId(info.glyph_id as u16), &mut builder);
+                    ShapedGlyph {
+                        run: 0,
+                        offset: kurbo::Vec2::new(
+                            f64::from(pos.x_offset) * scale,
+                            -f64::from(pos.y_offset) * scale,
+                        ),
+                        advance: f64::from(pos.x_advance) * scale,
+                        outline: builder.path,
+                        is_whitespace: text
+                            .get(info.cluster as usize..)
+                            .and_then(|s| s.chars().next())
+                            .map(char::is_whitespace)
+                            .unwrap_or(false),
+                    }
+                })
+                .collect::<Vec<ShapedGlyph>>();
+            Ok((metrics, glyphs))
+        })
+        .ok_or_else(|| {
+            anyhow::anyhow!("loading face of font family `{}` failed", style.font_family)
+        })?
+}
+
+/// Builds the glyph outline in the coordinate space of the text layout, where y points down.
+struct OutlineBuilder {
+    path: kurbo::BezPath,
+    scale: f64,
+}
+
+impl OutlineBuilder {
+    fn point(&self, x: f32, y: f32) -> kurbo::Point {
+        kurbo::Point::new(f64::from(x) * self.scale, -f64::from(y) * self.scale)
+    }
+}
+
+impl ttf_parser::OutlineBuilder for OutlineBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+        self.path.move_to(p);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+        self.path.line_to(p);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (p1, p) = (self.point(x1, y1), self.point(x, y));
+        self.path.quad_to(p1, p);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (p1, p2, p) = (self.point(x1, y1), self.point(x2, y2), self.point(x, y));
+        self.path.curve_to(p1, p2, p);
+    }
+
+    fn close(&mut self) {
+        self.path.close_path();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glyph(advance: f64, is_whitespace: bool) -> ShapedGlyph {
+        ShapedGlyph {
+            run: 0,
+            offset: kurbo::Vec2::ZERO,
+            advance,
+            outline: kurbo::BezPath::new(),
+            is_whitespace,
+        }
+    }
+
+    #[test]
+    fn break_lines_at_whitespace() {
+        // "aa aa aaaa"
+        let glyphs = [
+            glyph(1.0, false),
+            glyph(1.0, false),
+            glyph(1.0, true),
+            glyph(1.0, false),
+            glyph(1.0, false),
+            glyph(1.0, true),
+            glyph(1.0, false),
+            glyph(1.0, false),
+            glyph(1.0, false),
+            glyph(1.0, false),
+        ];
+        assert_eq!(break_lines(&glyphs, None), vec![0..10]);
+        assert_eq!(break_lines(&glyphs, Some(5.0)), vec![0..6, 6..10]);
+        assert_eq!(
+            break_lines(&glyphs, Some(3.0)),
+            vec![0..3, 3..6, 6..9, 9..10]
+        );
+    }
+}
@@ -1,6 +1,8 @@
 // Imports
 use super::strokebehaviour::GeneratedStrokeImages;
+use super::textoutlines;
 use super::StrokeBehaviour;
+use crate::document::{Document, Layout};
 use crate::{render, strokes::strokebehaviour, Camera, DrawBehaviour};
 use kurbo::Shape;
 use once_cell::sync::Lazy;
@@ -130,9 +132,15 @@ impl TextAttribute {
         T: piet::Text,
     {
         match self {
-            TextAttribute::FontFamily(font_family) => piet_text.font_family(font_family.as_str()).map(
-                piet::TextAttribute::FontFamily)
-                    .ok_or_else(|| anyhow::anyhow!("piet font_family() failed in textattribute try_into_piet() with font family name: {}", font_family)),
+            TextAttribute::FontFamily(font_family) => piet_text
+                .font_family(&crate::fonts::resolve_font_family(&font_family))
+                .map(piet::TextAttribute::FontFamily)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "piet font_family() failed in textattribute try_into_piet() with font family name: {}",
+                        font_family
+                    )
+                }),
             TextAttribute::FontSize(font_size) => Ok(piet::TextAttribute::FontSize(font_size)),
             TextAttribute::FontWeight(font_weight) => Ok(piet::TextAttribute::Weight(piet::FontWeight::new(font_weight))),
             TextAttribute::TextColor(color) => Ok(piet::TextAttribute::TextColor(piet::Color::from(color))),
@@ -197,6 +205,36 @@ impl TextStyle {
     pub const FONT_WEIGHT_DEFAULT: u16 = 500;
     pub const FONT_COLOR_DEFAULT: Color = Color::BLACK;
 
+    /// Whether the text color or one of the ranged text colors is equal to the given color.
+    ///
+    /// Colors are compared with 8 bit precision.
+    pub fn has_color(&self, color: Color) -> bool {
+        u32::from(self.color) == u32::from(color)
+            || self.ranged_text_attributes.iter().any(|ranged_attr| {
+                matches!(ranged_attr.attribute, TextAttribute::TextColor(other) if u32::from(other) == u32::from(color))
+            })
+    }
+
+    /// Replace the text color and the ranged text colors that are equal to `from` with `to`.
+    ///
+    /// Colors are compared with 8 bit precision. Returns true when a color was replaced.
+    pub fn replace_color(&mut self, from: Color, to: Color) -> bool {
+        let mut replaced = false;
+        if u32::from(self.color) == u32::from(from) {
+            self.color = to;
+            replaced = true;
+        }
+        for ranged_attr in self.ranged_text_attributes.iter_mut() {
+            if let TextAttribute::TextColor(color) = &mut ranged_attr.attribute {
+                if u32::from(*color) == u32::from(from) {
+                    *color = to;
+                    replaced = true;
+                }
+            }
+        }
+        replaced
+    }
+
     pub fn build_text_layout<T>(
         &self,
         piet_text: &mut T,
@@ -206,7 +244,7 @@ impl TextStyle {
         T: piet::Text,
     {
         let font_family = piet_text
-            .font_family(&self.font_family)
+            .font_family(&crate::fonts::resolve_font_family(&self.font_family))
             .unwrap_or(piet::FontFamily::SERIF);
 
         let mut text_layout_builder = piet_text
@@ -319,7 +357,98 @@ impl TextStyle {
             selection_cursor.cur_cursor()..cursor.cur_cursor()
         };
 
-        Ok(text_layout.rects_for_range(range))
+        Ok(Self::bidi_rects_for_range(&text_layout, &text, range))
+    }
+
+    /// The grapheme boundaries of the line together with the horizontal positions of the cursor at them.
+    ///
+    /// The end of the line is only included when it is the end of the text, else it belongs to the next line.
+    fn line_cursor_positions(
+        text_layout: &impl TextLayout,
+        text: &str,
+        line: usize,
+    ) -> Vec<(usize, f64)> {
+        let Some(line_metric) = text_layout.line_metric(line) else {
+            return vec![];
+        };
+        let (start, end) = (line_metric.start_offset, line_metric.end_offset);
+
+        text[start..end]
+            .grapheme_indices(true)
+            .map(|(i, _)| start + i)
+            .chain((end == text.len()).then_some(end))
+            .map(|index| (index, text_layout.hit_test_text_position(index).point.x))
+            .collect()
+    }
+
+    /// The rects covering the text in the range, separately for every run of left-to-right and right-to-left text.
+    ///
+    /// [TextLayout::rects_for_range] spans from the position of the range start to the position of the range end on
+    /// every line, which covers the wrong text when directions are mixed on a line.
+    /// Here every grapheme spans from its leading edge to the next cursor position in its direction.
+    fn bidi_rects_for_range(
+        text_layout: &impl TextLayout,
+        text: &str,
+        range: Range<usize>,
+    ) -> Vec<kurbo::Rect> {
+        let levels = unicode_bidi::BidiInfo::new(text, None).levels;
+        let mut rects = vec![];
+
+        for line in 0..text_layout.line_count() {
+            let Some(line_metric) = text_layout.line_metric(line) else {
+                continue;
+            };
+            if range.start >= line_metric.end_offset || range.end <= line_metric.start_offset {
+                continue;
+            }
+            let positions = Self::line_cursor_positions(text_layout, text, line);
+            // The visual ends of the line are edges as well, the cursor position at the end of a wrapped line is on the next line
+            let mut edges = positions
+                .iter()
+                .map(|(_, x)| *x)
+                .chain(
+                    text_layout
+                        .rects_for_range(line_metric.start_offset..line_metric.end_offset)
+                        .into_iter()
+                        .flat_map(|rect| [rect.x0, rect.x1]),
+                )
+                .collect::<Vec<f64>>();
+            edges.sort_by(|a, b| a.total_cmp(b));
+
+            let mut spans = positions
+                .iter()
+                .filter(|(index, _)| range.contains(index))
+                .filter_map(|&(index, x)| {
+                    let rtl = levels.get(index).map_or(false, |level| level.is_rtl());
+                    let other = if rtl {
+                        edges.iter().rev().find(|edge| **edge < x)
+                    } else {
+                        edges.iter().find(|edge| **edge > x)
+                    }?;
+                    Some((x.min(*other), x.max(*other)))
+                })
+                .collect::<Vec<(f64, f64)>>();
+            spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            // Merge the spans of adjacent graphemes
+            let mut merged: Vec<(f64, f64)> = vec![];
+            for (x0, x1) in spans {
+                match merged.last_mut() {
+                    Some(last) if x0 <= last.1 => last.1 = last.1.max(x1),
+                    _ => merged.push((x0, x1)),
+                }
+            }
+            rects.extend(merged.into_iter().map(|(x0, x1)| {
+                kurbo::Rect::new(
+                    x0,
+                    line_metric.y_offset,
+                    x1,
+                    line_metric.y_offset + line_metric.height,
+                )
+            }));
+        }
+
+        rects
     }
 
     /// Draw the cursor.
@@ -393,6 +522,89 @@ impl TextStyle {
             }
         }
     }
+
+    /// Draw the underlines of the preedit text of an input method.
+    ///
+    /// The emphasized ranges get a thicker underline.
+    pub fn draw_preedit_underlines(
+        &self,
+        cx: &mut impl piet::RenderContext,
+        text: String,
+        range: Range<usize>,
+        emphasized_ranges: &[Range<usize>],
+        transform: &Transform,
+        camera: &Camera,
+    ) {
+        const UNDERLINE_COLOR: piet::Color = color::GNOME_DARKS[2];
+        let underline_width = 1.0 / camera.total_zoom();
+        let text_len = text.len();
+
+        let Ok(text_layout) = self.build_text_layout(&mut piet_cairo::CairoText::new(), text)
+        else {
+            return;
+        };
+        let ranges = std::iter::once((range, underline_width)).chain(
+            emphasized_ranges
+                .iter()
+                .map(|range| (range.clone(), underline_width * 2.5)),
+        );
+
+        for (range, width) in ranges.filter(|(range, _)| range.end <= text_len) {
+            for rect in text_layout.rects_for_range(range) {
+                let underline = kurbo::Line::new(
+                    kurbo::Point::new(rect.x0, rect.y1 - width * 0.5),
+                    kurbo::Point::new(rect.x1, rect.y1 - width * 0.5),
+                );
+                cx.stroke(transform.to_kurbo() * underline, &UNDERLINE_COLOR, width);
+            }
+        }
+    }
+
+    /// Draw wavy underlines below the given ranges of misspelled words.
+    pub fn draw_misspelled_underlines(
+        &self,
+        cx: &mut impl piet::RenderContext,
+        text: String,
+        ranges: &[Range<usize>],
+        transform: &Transform,
+        camera: &Camera,
+    ) {
+        const UNDERLINE_COLOR: piet::Color = color::GNOME_REDS[3];
+        let total_zoom = camera.total_zoom();
+        let underline_width = 1.5 / total_zoom;
+        let wave_length = 4.0 / total_zoom;
+        let text_len = text.len();
+
+        let Ok(text_layout) = self.build_text_layout(&mut piet_cairo::CairoText::new(), text)
+        else {
+            return;
+        };
+
+        for range in ranges.iter().filter(|range| range.end <= text_len) {
+            for rect in text_layout.rects_for_range(range.clone()) {
+                let mut underline = kurbo::BezPath::new();
+                let mut x = rect.x0;
+                let mut up = true;
+                underline.move_to(kurbo::Point::new(x, rect.y1));
+                while x < rect.x1 {
+                    x = (x + wave_length * 0.5).min(rect.x1);
+                    let y = if up {
+                        rect.y1 - wave_length * 0.5
+                    } else {
+                        rect.y1
+                    };
+                    underline.line_to(kurbo::Point::new(x, y));
+                    up = !up;
+                }
+
+                cx.stroke(
+                    transform.to_kurbo() * underline,
+                    &UNDERLINE_COLOR,
+                    underline_width,
+                );
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -563,6 +775,47 @@ impl DrawBehaviour for TextStroke {
     }
 }
 
+/// A pattern for searching the text of text strokes.
+#[derive(Debug, Clone)]
+pub struct TextSearchPattern {
+    regex: regex::Regex,
+    is_regex: bool,
+}
+
+impl TextSearchPattern {
+    /// A pattern matching the text literally.
+    pub fn new_plain(text: &str, case_sensitive: bool) -> anyhow::Result<Self> {
+        Ok(Self {
+            regex: regex::RegexBuilder::new(&regex::escape(text))
+                .case_insensitive(!case_sensitive)
+                .build()?,
+            is_regex: false,
+        })
+    }
+
+    /// A pattern matching the regular expression.
+    ///
+    /// Replacements can then reference capture groups with `$name` or `${n}`.
+    pub fn new_regex(pattern: &str, case_sensitive: bool) -> anyhow::Result<Self> {
+        Ok(Self {
+            regex: regex::RegexBuilder::new(pattern)
+                .case_insensitive(!case_sensitive)
+                .multi_line(true)
+                .build()?,
+            is_regex: true,
+        })
+    }
+
+    /// The byte ranges of all non-empty, non-overlapping matches in the text.
+    pub fn find_matches(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex
+            .find_iter(text)
+            .filter(|m| !m.is_empty())
+            .map(|m| m.range())
+            .collect()
+    }
+}
+
 impl TextStroke {
     pub fn new(text: String, upper_left_pos: na::Vector2<f64>, text_style: TextStyle) -> Self {
         Self {
@@ -572,6 +825,34 @@ impl TextStroke {
         }
     }
 
+    /// Generate a Svg with the text converted to glyph outlines.
+    ///
+    /// Does not depend on cairo and pango, so the text renders the same in every viewer and in headless builds.
+    /// The layout can slightly differ from the one drawn on the canvas, see [textoutlines::text_outlines].
+    pub fn gen_outlines_svg(&self) -> anyhow::Result<render::Svg> {
+        let outlines = textoutlines::text_outlines(&self.text, &self.text_style)?;
+        let group = textoutlines::outlines_svg_group(outlines)
+            .set("transform", self.transform.to_svg_transform_attr_str());
+
+        Ok(render::Svg {
+            svg_data: rnote_compose::utils::svg_node_to_string(&group)?,
+            bounds: self.bounds(),
+        })
+    }
+
+    /// Whether the paragraph containing the byte index has a right-to-left base direction.
+    ///
+    /// The direction is determined by the first strong character of the paragraph.
+    pub fn is_rtl_at(&self, index: usize) -> bool {
+        let bidi_info = unicode_bidi::BidiInfo::new(&self.text, None);
+        bidi_info
+            .paragraphs
+            .iter()
+            .find(|paragraph| paragraph.range.contains(&index) || paragraph.range.end == index)
+            .map(|paragraph| paragraph.level.is_rtl())
+            .unwrap_or(false)
+    }
+
     pub fn get_text_slice_for_range(&self, range: Range<usize>) -> &str {
         &self.text[range]
     }
@@ -720,6 +1001,62 @@ impl TextStroke {
         );
     }
 
+    /// Replace all matches of the pattern with the replacement.
+    ///
+    /// Returns the number of replaced matches.
+    pub fn replace_matches(&mut self, pattern: &TextSearchPattern, replacement: &str) -> usize {
+        let captures = pattern
+            .regex
+            .captures_iter(&self.text)
+            .filter(|captures| captures.get(0).map(|m| !m.is_empty()).unwrap_or(false))
+            .map(|captures| {
+                let range = captures.get(0).unwrap().range();
+                let mut replace_text = String::new();
+                if pattern.is_regex {
+                    captures.expand(replacement, &mut replace_text);
+                } else {
+                    replace_text.push_str(replacement);
+                }
+                (range, replace_text)
+            })
+            .collect::<Vec<(Range<usize>, String)>>();
+
+        // Replace back to front, so that the ranges of the remaining matches stay valid
+        for (range, replace_text) in captures.iter().rev() {
+            self.text.replace_range(range.clone(), replace_text);
+            self.translate_attrs_after_cursor(
+                range.start + replace_text.len(),
+                replace_text.len() as i32 - range.len() as i32,
+            );
+        }
+
+        captures.len()
+    }
+
+    /// Replace the text in the byte range, which must lie on char boundaries.
+    pub fn replace_text_range(
+        &mut self,
+        range: Range<usize>,
+        replace_text: &str,
+    ) -> anyhow::Result<()> {
+        if range.start > range.end
+            || range.end > self.text.len()
+            || !self.text.is_char_boundary(range.start)
+            || !self.text.is_char_boundary(range.end)
+        {
+            return Err(anyhow::anyhow!(
+                "replacing text failed, range {range:?} is invalid for text with length {}",
+                self.text.len()
+            ));
+        }
+        self.text.replace_range(range.clone(), replace_text);
+        self.translate_attrs_after_cursor(
+            range.start + replace_text.len(),
+            replace_text.len() as i32 - range.len() as i32,
+        );
+        Ok(())
+    }
+
     /// Translate the ranged text attributes after the given cursor.
     ///
     /// Overlapping ranges are extended / shrunk
@@ -851,6 +1188,74 @@ impl TextStroke {
         cursor.next_boundary(&self.text, 0).unwrap();
     }
 
+    /// Move the cursor visually to the left on its line, following the layout of bidirectional text.
+    ///
+    /// At the visual start of the line the cursor moves on to the adjacent line in the direction of the paragraph.
+    pub fn move_cursor_left(&self, cursor: &mut GraphemeCursor) {
+        self.move_cursor_visually(cursor, false);
+    }
+
+    /// Move the cursor visually to the right on its line, following the layout of bidirectional text.
+    ///
+    /// At the visual end of the line the cursor moves on to the adjacent line in the direction of the paragraph.
+    pub fn move_cursor_right(&self, cursor: &mut GraphemeCursor) {
+        self.move_cursor_visually(cursor, true);
+    }
+
+    fn move_cursor_visually(&self, cursor: &mut GraphemeCursor, right: bool) {
+        let Ok(text_layout) = self
+            .text_style
+            .build_text_layout(&mut piet_cairo::CairoText::new(), self.text.clone())
+        else {
+            return;
+        };
+        let current = text_layout.hit_test_text_position(cursor.cur_cursor());
+        let target = TextStyle::line_cursor_positions(&text_layout, &self.text, current.line)
+            .into_iter()
+            .filter(|(_, x)| {
+                if right {
+                    *x > current.point.x
+                } else {
+                    *x < current.point.x
+                }
+            })
+            .min_by(|(_, a), (_, b)| {
+                if right {
+                    a.total_cmp(b)
+                } else {
+                    b.total_cmp(a)
+                }
+            });
+
+        match target {
+            Some((index, _)) => cursor.set_cursor(index),
+            None if right != self.is_rtl_at(cursor.cur_cursor()) => {
+                self.move_cursor_forward(cursor)
+            }
+            None => self.move_cursor_back(cursor),
+        }
+    }
+
+    /// Move the cursor to the word on the left, which is the previous word in left-to-right paragraphs
+    /// and the next word in right-to-left paragraphs.
+    pub fn move_cursor_word_left(&self, cursor: &mut GraphemeCursor) {
+        if self.is_rtl_at(cursor.cur_cursor()) {
+            self.move_cursor_word_forward(cursor);
+        } else {
+            self.move_cursor_word_back(cursor);
+        }
+    }
+
+    /// Move the cursor to the word on the right, which is the next word in left-to-right paragraphs
+    /// and the previous word in right-to-left paragraphs.
+    pub fn move_cursor_word_right(&self, cursor: &mut GraphemeCursor) {
+        if self.is_rtl_at(cursor.cur_cursor()) {
+            self.move_cursor_word_back(cursor);
+        } else {
+            self.move_cursor_word_forward(cursor);
+        }
+    }
+
     pub fn move_cursor_word_back(&self, cursor: &mut GraphemeCursor) {
         cursor.set_cursor(self.get_prev_word_start_index(cursor.cur_cursor()));
     }
@@ -971,3 +1376,287 @@ impl TextStroke {
         }
     }
 }
+
+/// A block of imported text, laid out as a separate text stroke.
+#[derive(Debug, Clone)]
+enum ImportTextBlock {
+    Paragraph(String),
+    Heading { level: usize, text: String },
+    ListItem { indent: usize, text: String },
+    Code(String),
+}
+
+impl TextStroke {
+    /// The spacing between imported text blocks, relative to the font size.
+    const IMPORT_BLOCK_SPACING: f64 = 0.5;
+    /// The indentation width of list items, relative to the font size.
+    const IMPORT_LIST_INDENT: f64 = 1.5;
+    /// The font weight of headings and bold text.
+    const IMPORT_BOLD_WEIGHT: u16 = 700;
+
+    /// Import plain text or Markdown as text strokes, one for every paragraph, heading, list item or code block.
+    ///
+    /// In Markdown, headings get larger font sizes and list items are indented.
+    /// Bold (`**`) and italic (`*`) inline formatting is applied as ranged text attributes.
+    /// When the document has a fixed size or continuous vertical layout, blocks that would cross the end of a page
+    /// are moved to the next one.
+    pub fn import_from_text(
+        text: &str,
+        markdown: bool,
+        insert_pos: na::Vector2<f64>,
+        text_style: &TextStyle,
+        document: &Document,
+    ) -> Vec<Self> {
+        let blocks = if markdown {
+            parse_markdown_blocks(text)
+        } else {
+            parse_plain_text_blocks(text)
+        };
+        let format = document.format;
+        let paginate = matches!(
+            document.layout,
+            Layout::FixedSize | Layout::ContinuousVertical
+        );
+        let page_margin = text_style.font_size * 2.0;
+        // The right border of the page column the text is inserted in
+        let page_col_end =
+            document.x + ((insert_pos[0] - document.x) / format.width + 1.0).floor() * format.width;
+        let max_width =
+            (page_col_end - page_margin - insert_pos[0]).max(text_style.font_size * 4.0);
+
+        let mut y = insert_pos[1];
+        let mut textstrokes = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            let mut block_style = TextStyle {
+                max_width: Some(max_width),
+                ranged_text_attributes: vec![],
+                ..text_style.clone()
+            };
+            let mut x = insert_pos[0];
+
+            let text = match block {
+                ImportTextBlock::Paragraph(text) => {
+                    if markdown {
+                        let (text, attrs) = parse_markdown_inline(&text);
+                        block_style.ranged_text_attributes = attrs;
+                        text
+                    } else {
+                        text
+                    }
+                }
+                ImportTextBlock::Heading { level, text } => {
+                    let scale = match level {
+                        1 => 2.0,
+                        2 => 1.6,
+                        3 => 1.3,
+                        _ => 1.1,
+                    };
+                    block_style.font_size = (text_style.font_size * scale)
+                        .clamp(TextStyle::FONT_SIZE_MIN, TextStyle::FONT_SIZE_MAX);
+                    block_style.font_weight = Self::IMPORT_BOLD_WEIGHT;
+                    let (text, attrs) = parse_markdown_inline(&text);
+                    block_style.ranged_text_attributes = attrs;
+                    text
+                }
+                ImportTextBlock::ListItem { indent, text } => {
+                    let indent_width =
+                        text_style.font_size * Self::IMPORT_LIST_INDENT * indent as f64;
+                    x += indent_width;
+                    block_style.max_width =
+                        Some((max_width - indent_width).max(text_style.font_size * 4.0));
+                    let (text, attrs) = parse_markdown_inline(&text);
+                    block_style.ranged_text_attributes = attrs;
+                    text
+                }
+                ImportTextBlock::Code(text) => {
+                    block_style.font_family = String::from("monospace");
+                    text
+                }
+            };
+
+            let mut textstroke = Self::new(text, na::vector![x, y], block_style);
+            let height = textstroke.bounds().extents()[1];
+
+            if paginate && height < format.height - page_margin * 2.0 {
+                let page_i = ((y - document.y) / format.height).floor();
+                let page_end = document.y + (page_i + 1.0) * format.height - page_margin;
+                if y + height > page_end {
+                    y = document.y + (page_i + 1.0) * format.height + page_margin;
+                    textstroke.transform =
+                        Transform::new_w_isometry(na::Isometry2::new(na::vector![x, y], 0.0));
+                }
+            }
+
+            y += height + text_style.font_size * Self::IMPORT_BLOCK_SPACING;
+            textstrokes.push(textstroke);
+        }
+
+        textstrokes
+    }
+}
+
+/// Splits plain text into paragraphs separated by empty lines. Line breaks inside paragraphs are kept.
+fn parse_plain_text_blocks(text: &str) -> Vec<ImportTextBlock> {
+    let mut blocks = vec![];
+    let mut paragraph: Vec<&str> = vec![];
+
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !paragraph.is_empty() {
+                blocks.push(ImportTextBlock::Paragraph(paragraph.join("\n")));
+                paragraph.clear();
+            }
+        } else {
+            paragraph.push(line.trim_end());
+        }
+    }
+    if !paragraph.is_empty() {
+        blocks.push(ImportTextBlock::Paragraph(paragraph.join("\n")));
+    }
+
+    blocks
+}
+
+/// Splits Markdown into headings, list items, code blocks and paragraphs.
+fn parse_markdown_blocks(text: &str) -> Vec<ImportTextBlock> {
+    let mut blocks = vec![];
+    let mut paragraph: Option<String> = None;
+    let mut code: Option<Vec<&str>> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with("```") {
+            match code.take() {
+                Some(code_lines) => blocks.push(ImportTextBlock::Code(code_lines.join("\n"))),
+                None => {
+                    if let Some(p) = paragraph.take() {
+                        blocks.push(ImportTextBlock::Paragraph(p));
+                    }
+                    code = Some(vec![]);
+                }
+            }
+            continue;
+        }
+        if let Some(code_lines) = code.as_mut() {
+            code_lines.push(line);
+            continue;
+        }
+
+        let heading_level = trimmed.chars().take_while(|c| *c == '#').count();
+        let leading_spaces = line
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum::<usize>();
+
+        let block = if trimmed.is_empty() || is_markdown_rule(trimmed) {
+            None
+        } else if (1..=6).contains(&heading_level) && trimmed[heading_level..].starts_with(' ') {
+            Some(ImportTextBlock::Heading {
+                level: heading_level,
+                text: trimmed[heading_level..].trim().to_string(),
+            })
+        } else if let Some(item) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|marker| trimmed.strip_prefix(marker))
+        {
+            Some(ImportTextBlock::ListItem {
+                indent: leading_spaces / 2 + 1,
+                text: format!("• {}", item.trim()),
+            })
+        } else if is_markdown_ordered_item(trimmed) {
+            Some(ImportTextBlock::ListItem {
+                indent: leading_spaces / 2 + 1,
+                text: trimmed.to_string(),
+            })
+        } else {
+            let trimmed = trimmed.trim_start_matches('>').trim();
+            match paragraph.as_mut() {
+                Some(p) => {
+                    p.push(' ');
+                    p.push_str(trimmed);
+                }
+                None => paragraph = Some(trimmed.to_string()),
+            }
+            continue;
+        };
+
+        if let Some(p) = paragraph.take() {
+            blocks.push(ImportTextBlock::Paragraph(p));
+        }
+        if let Some(block) = block {
+            blocks.push(block);
+        }
+    }
+    if let Some(p) = paragraph.take() {
+        blocks.push(ImportTextBlock::Paragraph(p));
+    }
+    if let Some(code_lines) = code.take() {
+        blocks.push(ImportTextBlock::Code(code_lines.join("\n")));
+    }
+
+    blocks
+}
+
+/// Thematic breaks like `---`, `***` or `___`.
+fn is_markdown_rule(line: &str) -> bool {
+    let line = line
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+    line.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|m| line.chars().all(|c| c == *m))
+}
+
+/// Ordered list items like `1. item` or `2) item`.
+fn is_markdown_ordered_item(line: &str) -> bool {
+    let n_digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    n_digits > 0 && (line[n_digits..].starts_with(". ") || line[n_digits..].starts_with(") "))
+}
+
+/// Strips the inline Markdown markers for bold, italic and code and returns the matching ranged text attributes.
+fn parse_markdown_inline(text: &str) -> (String, Vec<RangedTextAttribute>) {
+    let mut out = String::with_capacity(text.len());
+    let mut attrs = vec![];
+    let mut bold_start: Option<usize> = None;
+    let mut italic_start: Option<usize> = None;
+    let mut in_code = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '`' => in_code = !in_code,
+            '*' if !in_code && chars.peek() == Some(&'*') => {
+                chars.next();
+                match bold_start.take() {
+                    Some(start) if start < out.len() => attrs.push(RangedTextAttribute {
+                        range: start..out.len(),
+                        attribute: TextAttribute::FontWeight(TextStroke::IMPORT_BOLD_WEIGHT),
+                    }),
+                    Some(_) => {}
+                    None => bold_start = Some(out.len()),
+                }
+            }
+            // A single `*` followed by whitespace is not a marker, e.g. in `5 * 3`
+            '*' if !in_code
+                && (italic_start.is_some()
+                    || chars.peek().map(|n| !n.is_whitespace()).unwrap_or(false)) =>
+            {
+                match italic_start.take() {
+                    Some(start) if start < out.len() => attrs.push(RangedTextAttribute {
+                        range: start..out.len(),
+                        attribute: TextAttribute::Style(FontStyle::Italic),
+                    }),
+                    Some(_) => {}
+                    None => italic_start = Some(out.len()),
+                }
+            }
+            c => out.push(c),
+        }
+    }
+
+    (out, attrs)
+}
@@ -1,17 +1,20 @@
 // Imports
 use super::strokebehaviour::GeneratedStrokeImages;
-use super::{Stroke, StrokeBehaviour};
-use crate::engine::import::{PdfImportPageSpacing, PdfImportPrefs};
+use super::{svgconvert, BrushStroke, Stroke, StrokeBehaviour, TextLayer};
+use crate::engine::import::{PdfImportPageSpacing, PdfImportPrefs, PDF_IMPORT_RENDER_PROGRESS};
 use crate::{document::Format, strokes::strokebehaviour};
-use crate::{render, DrawBehaviour};
+use crate::{render, DrawBehaviour, EngineError, Progress};
 use p2d::bounding_volume::Aabb;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use rnote_compose::color;
-use rnote_compose::helpers::AabbHelpers;
+use rnote_compose::helpers::{AabbHelpers, Vector2Helpers};
+use rnote_compose::penpath::Element;
 use rnote_compose::shapes::Rectangle;
 use rnote_compose::shapes::ShapeBehaviour;
+use rnote_compose::style::smooth::SmoothOptions;
 use rnote_compose::transform::Transform;
 use rnote_compose::transform::TransformBehaviour;
+use rnote_compose::{PenPath, Style};
 use serde::{Deserialize, Serialize};
 use std::ops::Range;
 use usvg::{TreeParsing, TreeTextToPath, TreeWriting};
@@ -28,6 +31,12 @@ pub struct VectorImage {
     pub intrinsic_size: na::Vector2<f64>,
     #[serde(rename = "rectangle")]
     pub rectangle: Rectangle,
+    /// A description of the stroke for screen reader users (alt-text).
+    #[serde(default, rename = "description")]
+    pub description: Option<String>,
+    /// The invisible text of the image, e.g. of an imported Pdf page.
+    #[serde(default, rename = "text_layer")]
+    pub text_layer: TextLayer,
 }
 
 impl Default for VectorImage {
@@ -36,6 +45,8 @@ impl Default for VectorImage {
             svg_data: String::default(),
             intrinsic_size: na::Vector2::zeros(),
             rectangle: Rectangle::default(),
+            description: None,
+            text_layer: TextLayer::default(),
         }
     }
 }
@@ -149,6 +160,13 @@ impl TransformBehaviour for VectorImage {
 }
 
 impl VectorImage {
+    /// Draw the Svg data directly to the cairo context, without rasterizing it first.
+    ///
+    /// Preferable when drawing to vector surfaces, e.g. when exporting as Pdf.
+    pub fn draw_to_cairo(&self, cx: &cairo::Context) -> anyhow::Result<()> {
+        self.gen_svg()?.draw_to_cairo(cx)
+    }
+
     pub fn import_from_svg_data(
         svg_data: &str,
         pos: na::Vector2<f64>,
@@ -165,8 +183,9 @@ impl VectorImage {
             },
         };
 
-        let mut svg_tree = usvg::Tree::from_str(svg_data, &usvg::Options::default())?;
-        svg_tree.convert_text(&render::USVG_FONTDB);
+        let mut svg_tree = usvg::Tree::from_str(svg_data, &usvg::Options::default())
+            .map_err(|e| EngineError::Decode(format!("parsing Svg failed, {e}")))?;
+        svg_tree.convert_text(&render::USVG_FONTDB.read().unwrap());
         let svg_data = svg_tree.to_string(&xml_options);
         let intrinsic_size =
             na::vector![svg_tree.size.width() as f64, svg_tree.size.height() as f64];
@@ -190,6 +209,8 @@ impl VectorImage {
             svg_data,
             intrinsic_size,
             rectangle,
+            description: None,
+            text_layer: TextLayer::default(),
         })
     }
 
@@ -199,8 +220,118 @@ impl VectorImage {
         insert_pos: na::Vector2<f64>,
         page_range: Option<Range<u32>>,
         format: &Format,
+        progress: &Progress,
     ) -> Result<Vec<Self>, anyhow::Error> {
-        let doc = poppler::Document::from_bytes(&glib::Bytes::from(to_be_read), None)?;
+        let svgs = Self::render_pdf_pages_to_svgs(
+            to_be_read,
+            pdf_import_prefs,
+            insert_pos,
+            page_range,
+            format,
+            progress,
+        )?;
+        let import_stage = progress.stage(PDF_IMPORT_RENDER_PROGRESS, 1.0, svgs.len());
+
+        let vectorimages = svgs
+            .into_par_iter()
+            .filter_map(|(svg, text_layer)| {
+                if progress.is_cancelled() {
+                    return None;
+                }
+                let res = Self::import_from_svg_data(
+                    svg.svg_data.as_str(),
+                    svg.bounds.mins.coords,
+                    Some(svg.bounds.extents()),
+                );
+                import_stage.step();
+                match res {
+                    Ok(vectorimage) => Some(Self {
+                        text_layer,
+                        ..vectorimage
+                    }),
+                    Err(e) => {
+                        log::error!("import_from_svg_data() failed failed in vectorimage import_from_pdf_bytes() with Err: {e:?}");
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<Self>>();
+        import_stage.finish()?;
+
+        Ok(vectorimages)
+    }
+
+    /// Import the pdf pages as vector images, with the handwriting ink converted to brush strokes.
+    ///
+    /// Stroked paths with a solid color that contain curves or many segments, like the handwriting in Pdf's exported
+    /// from GoodNotes, Notability or OneNote, are removed from the page and returned as brush strokes.
+    /// Ink that is exported as filled outlines stays part of the page.
+    pub fn import_from_pdf_bytes_w_ink(
+        to_be_read: &[u8],
+        pdf_import_prefs: PdfImportPrefs,
+        insert_pos: na::Vector2<f64>,
+        page_range: Option<Range<u32>>,
+        format: &Format,
+        progress: &Progress,
+    ) -> Result<Vec<(Self, Vec<BrushStroke>)>, anyhow::Error> {
+        let svgs = Self::render_pdf_pages_to_svgs(
+            to_be_read,
+            pdf_import_prefs,
+            insert_pos,
+            page_range,
+            format,
+            progress,
+        )?;
+        let import_stage = progress.stage(PDF_IMPORT_RENDER_PROGRESS, 1.0, svgs.len());
+
+        let pages = svgs
+            .into_par_iter()
+            .filter_map(|(svg, text_layer)| {
+                if progress.is_cancelled() {
+                    return None;
+                }
+                let res = || -> anyhow::Result<(Self, Vec<BrushStroke>)> {
+                    let (page_svg_data, brushstrokes) = extract_ink_from_svg(&svg)?;
+                    let vectorimage = Self::import_from_svg_data(
+                        page_svg_data.as_str(),
+                        svg.bounds.mins.coords,
+                        Some(svg.bounds.extents()),
+                    )?;
+                    Ok((
+                        Self {
+                            text_layer,
+                            ..vectorimage
+                        },
+                        brushstrokes,
+                    ))
+                };
+                let res = res();
+                import_stage.step();
+                match res {
+                    Ok(page) => Some(page),
+                    Err(e) => {
+                        log::error!("importing page with ink failed in vectorimage import_from_pdf_bytes_w_ink() with Err: {e:?}");
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<(Self, Vec<BrushStroke>)>>();
+        import_stage.finish()?;
+
+        Ok(pages)
+    }
+
+    /// Render the pdf pages into Svg's, positioned according to the import preferences, together with their text layer.
+    fn render_pdf_pages_to_svgs(
+        to_be_read: &[u8],
+        pdf_import_prefs: PdfImportPrefs,
+        insert_pos: na::Vector2<f64>,
+        page_range: Option<Range<u32>>,
+        format: &Format,
+        progress: &Progress,
+    ) -> Result<Vec<(render::Svg, TextLayer)>, anyhow::Error> {
+        let doc = poppler::Document::from_bytes(&glib::Bytes::from(to_be_read), None)
+            .map_err(|e| EngineError::Decode(format!("loading Pdf document failed, {e}")))?;
         let page_range = page_range.unwrap_or(0..doc.n_pages() as u32);
 
         let page_width = format.width * (pdf_import_prefs.page_width_perc / 100.0);
@@ -212,12 +343,18 @@ impl VectorImage {
         };
         let x = insert_pos[0];
         let mut y = insert_pos[1];
+        let render_stage = progress.stage(0.0, PDF_IMPORT_RENDER_PROGRESS, page_range.len());
 
-        let svgs = page_range.filter_map(|page_i| {
+        let svgs = page_range
+            // Poppler renders sequentially, so the remaining pages are skipped when cancelled
+            .take_while(|_| !progress.is_cancelled())
+            .filter_map(|page_i| {
+            render_stage.step();
             let page = doc.page(page_i as i32)?;
             let intrinsic_size = page.size();
             let width = intrinsic_size.0 * page_zoom;
             let height = intrinsic_size.1 * page_zoom;
+            let text_layer = TextLayer::from_pdf_page(&page);
 
             let res = move || -> anyhow::Result<String> {
                 let svg_stream: Vec<u8> = vec![];
@@ -249,18 +386,20 @@ impl VectorImage {
                     // Render the poppler page
                     page.render_for_printing(&cx);
 
-                    // Draw outline around page
-                    cx.set_source_rgba(color::GNOME_REDS[4].as_rgba().0, color::GNOME_REDS[4].as_rgba().1, color::GNOME_REDS[4].as_rgba().2, 1.0);
-
-                    let line_width = 1.0;
-                    cx.set_line_width(line_width);
-                    cx.rectangle(
-                        line_width * 0.5,
-                        line_width * 0.5,
-                        intrinsic_size.0 - line_width,
-                        intrinsic_size.1 - line_width,
-                    );
-                    cx.stroke()?;
+                    if pdf_import_prefs.page_borders {
+                        // Draw outline around page
+                        cx.set_source_rgba(color::GNOME_REDS[4].as_rgba().0, color::GNOME_REDS[4].as_rgba().1, color::GNOME_REDS[4].as_rgba().2, 1.0);
+
+                        let line_width = 1.0;
+                        cx.set_line_width(line_width);
+                        cx.rectangle(
+                            line_width * 0.5,
+                            line_width * 0.5,
+                            intrinsic_size.0 - line_width,
+                            intrinsic_size.1 - line_width,
+                        );
+                        cx.stroke()?;
+                    }
                 }
 
                 let svg_content = String::from_utf8(
@@ -281,33 +420,19 @@ impl VectorImage {
             };
 
             match res() {
-                Ok(svg_data) => Some(render::Svg {
+                Ok(svg_data) => Some((render::Svg {
                     svg_data,
                     bounds,
-                }),
+                }, text_layer)),
                 Err(e) => {
                     log::error!("importing page {page_i} from pdf failed with Err: {e:?}");
                     None
                 }
             }
-        }).collect::<Vec<render::Svg>>();
+        }).collect::<Vec<(render::Svg, TextLayer)>>();
+        render_stage.finish()?;
 
-        Ok(svgs
-            .into_par_iter()
-            .filter_map(|svg| {
-                match Self::import_from_svg_data(
-                    svg.svg_data.as_str(),
-                    svg.bounds.mins.coords,
-                    Some(svg.bounds.extents()),
-                ) {
-                    Ok(vectorimage) => Some(vectorimage),
-                    Err(e) => {
-                        log::error!("import_from_svg_data() failed failed in vectorimage import_from_pdf_bytes() with Err: {e:?}");
-                        None
-                    }
-                }
-            })
-            .collect())
+        Ok(svgs)
     }
 
     pub fn export_as_svg(&self) -> Result<String, anyhow::Error> {
@@ -325,3 +450,153 @@ impl VectorImage {
         Ok(export_svg_data)
     }
 }
+
+/// Removes the paths that are considered handwriting ink from the Svg and converts them into brush strokes.
+///
+/// Returns the remaining Svg data and the brush strokes in the coordinate space of the Svg bounds.
+fn extract_ink_from_svg(svg: &render::Svg) -> anyhow::Result<(String, Vec<BrushStroke>)> {
+    // Tolerance when flattening curves, in document coordinates
+    const FLATTEN_TOLERANCE: f64 = 0.1;
+
+    let svg_tree = usvg::Tree::from_str(&svg.svg_data, &usvg::Options::default())?;
+    let view_box = svg_tree.view_box.rect;
+    let scale = na::vector![
+        svg.bounds.extents()[0] / f64::from(view_box.width()),
+        svg.bounds.extents()[1] / f64::from(view_box.height())
+    ];
+    // Maps Svg user units to document coordinates
+    let to_doc = kurbo::Affine::translate(svg.bounds.mins.coords.to_kurbo_vec())
+        * kurbo::Affine::scale_non_uniform(scale[0], scale[1])
+        * kurbo::Affine::translate((-f64::from(view_box.x()), -f64::from(view_box.y())));
+
+    let mut ink_paths = vec![];
+    collect_ink_paths(&svg_tree.root, usvg::Transform::default(), &mut ink_paths);
+
+    let mut brushstrokes = vec![];
+    for (node, ts) in ink_paths {
+        if let usvg::NodeKind::Path(ref path) = *node.borrow() {
+            let Some(stroke) = path.stroke.as_ref() else {
+                continue;
+            };
+            let Some(stroke_color) =
+                svgconvert::usvg_paint_color(&stroke.paint, stroke.opacity.get())
+            else {
+                continue;
+            };
+            let transform = to_doc * svgconvert::usvg_transform_to_kurbo(ts);
+
+            let mut smooth_options = SmoothOptions::default();
+            smooth_options.stroke_width =
+                f64::from(stroke.width.get()) * transform.determinant().abs().sqrt();
+            smooth_options.stroke_color = Some(stroke_color);
+
+            let mut bez_path = svgconvert::usvg_path_to_bezpath(path);
+            bez_path.apply_affine(transform);
+
+            // Every sub path becomes a separate brush stroke
+            let mut sub_paths: Vec<Vec<na::Vector2<f64>>> = vec![];
+            kurbo::flatten(bez_path, FLATTEN_TOLERANCE, |el| match el {
+                kurbo::PathEl::MoveTo(p) => sub_paths.push(vec![na::vector![p.x, p.y]]),
+                kurbo::PathEl::LineTo(p) => {
+                    if let Some(sub_path) = sub_paths.last_mut() {
+                        sub_path.push(na::vector![p.x, p.y]);
+                    }
+                }
+                kurbo::PathEl::ClosePath => {
+                    if let Some(sub_path) = sub_paths.last_mut() {
+                        if let Some(first) = sub_path.first().copied() {
+                            sub_path.push(first);
+                        }
+                    }
+                }
+                _ => {}
+            });
+
+            brushstrokes.extend(sub_paths.into_iter().filter_map(|sub_path| {
+                let penpath = PenPath::try_from_elements(
+                    sub_path.into_iter().map(|pos| Element::new(pos, 1.0)),
+                )?;
+                Some(BrushStroke::from_penpath(
+                    penpath,
+                    Style::Smooth(smooth_options.clone()),
+                ))
+            }));
+        }
+        node.detach();
+    }
+
+    Ok((
+        svg_tree.to_string(&usvg::XmlOptions::default()),
+        brushstrokes,
+    ))
+}
+
+/// Collects the paths that are considered handwriting ink together with their absolute transform.
+fn collect_ink_paths(
+    node: &usvg::Node,
+    parent_ts: usvg::Transform,
+    ink_paths: &mut Vec<(usvg::Node, usvg::Transform)>,
+) {
+    // Paths with only a few straight segments are likely lines or borders of the page content
+    const INK_MIN_LINE_SEGMENTS: usize = 6;
+
+    let ts = match *node.borrow() {
+        usvg::NodeKind::Group(ref group) => parent_ts.pre_concat(group.transform),
+        usvg::NodeKind::Path(ref path) => {
+            let solid_stroke = path.stroke.as_ref().map_or(false, |stroke| {
+                matches!(stroke.paint, usvg::Paint::Color(_)) && stroke.dasharray.is_none()
+            });
+            let has_curves = path.data.segments().any(|segment| {
+                matches!(
+                    segment,
+                    usvg::tiny_skia_path::PathSegment::QuadTo(..)
+                        | usvg::tiny_skia_path::PathSegment::CubicTo(..)
+                )
+            });
+            if path.fill.is_none()
+                && solid_stroke
+                && (has_curves || path.data.len() >= INK_MIN_LINE_SEGMENTS)
+            {
+                ink_paths.push((node.clone(), parent_ts.pre_concat(path.transform)));
+            }
+            return;
+        }
+        _ => return,
+    };
+
+    for child in node.children() {
+        collect_ink_paths(&child, ts, ink_paths);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_ink_only_takes_curved_or_long_stroked_paths() {
+        let svg_data = r##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100" viewBox="0 0 100 100">
+<path fill="none" stroke="#000000" stroke-width="2" d="M 10 10 C 20 0 30 20 40 10"/>
+<path fill="none" stroke="#000000" stroke-width="2" d="M 0 50 L 10 52 L 20 50 L 30 52 L 40 50 L 50 52 L 60 50"/>
+<path fill="none" stroke="#000000" stroke-width="2" d="M 0 80 L 100 80"/>
+<path fill="none" stroke="#000000" stroke-width="2" d="M 0 90 L 10 90 L 20 92 L 30 90 L 40 90"/>
+<path fill="none" stroke="#000000" stroke-width="2" stroke-dasharray="2 2" d="M 60 10 C 70 0 80 20 90 10"/>
+<path fill="#000000" stroke="#000000" stroke-width="2" d="M 60 60 C 70 50 80 70 90 60 Z"/>
+</svg>"##;
+        let svg = render::Svg {
+            svg_data: svg_data.to_string(),
+            bounds: Aabb::new(na::point![0.0, 0.0], na::point![100.0, 100.0]),
+        };
+
+        let (page_svg_data, brushstrokes) = extract_ink_from_svg(&svg).unwrap();
+
+        // The curve and the path with many segments are ink
+        assert_eq!(brushstrokes.len(), 2);
+        // The straight line, the path with few segments, the dashed and the filled path stay on the page
+        let doc = roxmltree::Document::parse(&page_svg_data).unwrap();
+        assert_eq!(
+            doc.descendants().filter(|n| n.has_tag_name("path")).count(),
+            4
+        );
+    }
+}
@@ -0,0 +1,449 @@
+// Imports
+use once_cell::sync::Lazy;
+use regex::Regex;
+use roxmltree::{Node, NodeId};
+use std::collections::{BTreeMap, HashMap};
+
+/// The presentation attributes that are merged into style classes.
+const STYLE_ATTRIBUTES: [&str; 13] = [
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "stroke",
+    "stroke-width",
+    "stroke-opacity",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "opacity",
+    "clip-rule",
+];
+/// The attributes with coordinates or lengths that are rounded.
+const COORDINATE_ATTRIBUTES: [&str; 15] = [
+    "d",
+    "x",
+    "y",
+    "width",
+    "height",
+    "cx",
+    "cy",
+    "r",
+    "rx",
+    "ry",
+    "x1",
+    "y1",
+    "x2",
+    "y2",
+    "stroke-width",
+];
+/// Elements that establish a new coordinate system which is not tracked. Their content is not rounded.
+const UNTRACKED_COORDINATE_ELEMENTS: [&str; 4] = ["svg", "symbol", "pattern", "marker"];
+/// The decimal places coordinates are rounded to, when they are not scaled up by a transform.
+const COORDINATES_PRECISION: f64 = 2.0;
+const COORDINATES_PRECISION_MAX: f64 = 6.0;
+/// Paths with shorter data are not worth replacing with references.
+const REUSE_PATH_DATA_LEN_MIN: usize = 32;
+const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+
+static NUMBER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"-?(?:\d+\.?\d*|\.\d+)(?:[eE][-+]?\d+)?").unwrap());
+
+/// Reduces the size of the Svg data without changing how it is rendered.
+///
+/// Repeated combinations of presentation attributes are merged into style classes, paths with identical data
+/// are defined once and reused, and coordinates are rounded to a precision that is not visible, respecting transforms.
+/// Expects the data to have a single `svg` root element, like the output of [usvg].
+pub(crate) fn optimize_svg_data(svg_data: &str) -> anyhow::Result<String> {
+    let doc = roxmltree::Document::parse(svg_data)?;
+    let root = doc.root_element();
+    if !root.has_tag_name("svg") {
+        return Err(anyhow::anyhow!(
+            "optimizing Svg failed, root element is not `svg`"
+        ));
+    }
+    let prefix = rnote_compose::utils::svg_id_prefix_from_data(svg_data.as_bytes());
+
+    let mut collected = Collected::default();
+    collect_element(root, root_scale(root), true, &mut collected);
+
+    let classes = collected
+        .style_counts
+        .into_iter()
+        .filter(|(style, count)| *count >= 2 && class_is_smaller(style))
+        .enumerate()
+        .map(|(i, (style, _))| (style, format!("s-{prefix}-{i}")))
+        .collect::<BTreeMap<Vec<(String, String)>, String>>();
+    let reused_paths = collected
+        .path_counts
+        .into_iter()
+        .filter(|(_, count)| *count >= 2)
+        .enumerate()
+        .map(|(i, (data, _))| (data, format!("p-{prefix}-{i}")))
+        .collect::<BTreeMap<String, String>>();
+
+    let xlink_prefix = root.lookup_prefix(XLINK_NAMESPACE).unwrap_or("xlink");
+    let optimizer = Optimizer {
+        attributes: collected.attributes,
+        path_data: collected.path_data,
+        classes,
+        reused_paths,
+        href_attribute: format!("{xlink_prefix}:href"),
+    };
+    let mut w = xmlwriter::XmlWriter::new(xmlwriter::Options {
+        use_single_quote: false,
+        indent: xmlwriter::Indent::None,
+        attributes_indent: xmlwriter::Indent::None,
+    });
+    optimizer.write_element(&mut w, root, None);
+    Ok(w.end_document())
+}
+
+#[derive(Debug, Default)]
+struct Collected {
+    /// The attributes of the elements with rounded values, by the element node id.
+    attributes: HashMap<NodeId, Vec<(String, String)>>,
+    /// The data of the paths that can be replaced with references, by the element node id.
+    path_data: HashMap<NodeId, String>,
+    style_counts: BTreeMap<Vec<(String, String)>, usize>,
+    path_counts: BTreeMap<String, usize>,
+}
+
+/// Collects the attributes of the element and its descendants, rounds them and counts repeated styles and paths.
+///
+/// `scale` is the accumulated scale of the coordinate system, None when it is unknown.
+/// Paths are only reusable when all their ancestors are plain groups.
+fn collect_element(node: Node, scale: Option<f64>, reusable: bool, collected: &mut Collected) {
+    let tag_name = node.tag_name().name();
+    let scale =
+        if node.parent_element().is_some() && UNTRACKED_COORDINATE_ELEMENTS.contains(&tag_name) {
+            None
+        } else {
+            match node.attribute("transform") {
+                Some(transform) => scale.zip(transform_scale(transform)).map(|(s, t)| s * t),
+                None => scale,
+            }
+        };
+    let precision = scale.and_then(precision_for_scale);
+
+    let attributes = node
+        .attributes()
+        .map(|attr| {
+            let name = qualified_name(node, attr.namespace(), attr.name());
+            let value = match precision {
+                Some(precision)
+                    if attr.namespace().is_none()
+                        && COORDINATE_ATTRIBUTES.contains(&attr.name()) =>
+                {
+                    round_numbers(attr.value(), precision)
+                }
+                _ => attr.value().to_string(),
+            };
+            (name, value)
+        })
+        .collect::<Vec<(String, String)>>();
+
+    if let Some(style) = style_of(&attributes) {
+        *collected.style_counts.entry(style).or_insert(0) += 1;
+    }
+    if let Some(data) = reusable_path_data(node, &attributes, reusable) {
+        *collected.path_counts.entry(data.to_string()).or_insert(0) += 1;
+        collected.path_data.insert(node.id(), data.to_string());
+    }
+    collected.attributes.insert(node.id(), attributes);
+
+    let children_reusable = reusable && (tag_name == "g" || node.parent_element().is_none());
+    for child in node.children().filter(|n| n.is_element()) {
+        collect_element(child, scale, children_reusable, collected);
+    }
+}
+
+#[derive(Debug)]
+struct Optimizer {
+    attributes: HashMap<NodeId, Vec<(String, String)>>,
+    path_data: HashMap<NodeId, String>,
+    classes: BTreeMap<Vec<(String, String)>, String>,
+    reused_paths: BTreeMap<String, String>,
+    href_attribute: String,
+}
+
+impl Optimizer {
+    fn write_element(&self, w: &mut xmlwriter::XmlWriter, node: Node, parent: Option<Node>) {
+        let no_attributes = vec![];
+        let attributes = self.attributes.get(&node.id()).unwrap_or(&no_attributes);
+        let class = style_of(attributes).and_then(|style| self.classes.get(&style));
+        let reused_path = self
+            .path_data
+            .get(&node.id())
+            .and_then(|data| self.reused_paths.get(data));
+
+        if reused_path.is_some() {
+            w.start_element("use");
+        } else {
+            w.start_element(&qualified_name(
+                node,
+                node.tag_name().namespace(),
+                node.tag_name().name(),
+            ));
+        }
+
+        // Namespace declarations
+        for ns in node.namespaces() {
+            if ns.name() == Some("xml") {
+                continue;
+            }
+            let declared_by_parent = parent
+                .map(|p| {
+                    p.namespaces()
+                        .any(|p_ns| p_ns.name() == ns.name() && p_ns.uri() == ns.uri())
+                })
+                .unwrap_or(false);
+            if !declared_by_parent {
+                match ns.name() {
+                    Some(name) => w.write_attribute(&format!("xmlns:{name}"), ns.uri()),
+                    None => w.write_attribute("xmlns", ns.uri()),
+                }
+            }
+        }
+        if parent.is_none()
+            && !self.reused_paths.is_empty()
+            && node.lookup_prefix(XLINK_NAMESPACE).is_none()
+        {
+            w.write_attribute("xmlns:xlink", XLINK_NAMESPACE);
+        }
+
+        let mut existing_class = None;
+        for (name, value) in attributes.iter() {
+            if class.is_some() && STYLE_ATTRIBUTES.contains(&name.as_str()) {
+                continue;
+            }
+            if reused_path.is_some() && name == "d" {
+                continue;
+            }
+            if name == "class" {
+                existing_class = Some(value);
+                continue;
+            }
+            w.write_attribute(name, value);
+        }
+        match (existing_class, class) {
+            (Some(existing), Some(class)) => {
+                w.write_attribute("class", &format!("{existing} {class}"))
+            }
+            (Some(existing), None) => w.write_attribute("class", existing),
+            (None, Some(class)) => w.write_attribute("class", class),
+            (None, None) => {}
+        }
+        if let Some(id) = reused_path {
+            w.write_attribute(&self.href_attribute, &format!("#{id}"));
+        }
+
+        if parent.is_none() {
+            self.write_definitions(w);
+        }
+        for child in node.children() {
+            if child.is_element() {
+                self.write_element(w, child, Some(node));
+            } else if child.is_text() {
+                w.write_text(child.text().unwrap_or_default());
+            }
+        }
+        w.end_element();
+    }
+
+    /// Write the style classes and the reused paths.
+    fn write_definitions(&self, w: &mut xmlwriter::XmlWriter) {
+        if !self.classes.is_empty() {
+            let css = self
+                .classes
+                .iter()
+                .map(|(style, class)| {
+                    let declarations = style
+                        .iter()
+                        .map(|(name, value)| format!("{name}:{value}"))
+                        .collect::<Vec<String>>()
+                        .join(";");
+                    format!(".{class}{{{declarations}}}")
+                })
+                .collect::<String>();
+            w.start_element("style");
+            w.write_text(&css);
+            w.end_element();
+        }
+        if !self.reused_paths.is_empty() {
+            w.start_element("defs");
+            for (data, id) in self.reused_paths.iter() {
+                w.start_element("path");
+                w.write_attribute("id", id);
+                w.write_attribute("d", data);
+                w.end_element();
+            }
+            w.end_element();
+        }
+    }
+}
+
+fn qualified_name(node: Node, namespace: Option<&str>, name: &str) -> String {
+    match namespace.and_then(|ns| node.lookup_prefix(ns)) {
+        Some(prefix) if !prefix.is_empty() => format!("{prefix}:{name}"),
+        _ => name.to_string(),
+    }
+}
+
+/// The style attributes of the element, None when it has none.
+fn style_of(attributes: &[(String, String)]) -> Option<Vec<(String, String)>> {
+    let style = attributes
+        .iter()
+        .filter(|(name, _)| STYLE_ATTRIBUTES.contains(&name.as_str()))
+        .cloned()
+        .collect::<Vec<(String, String)>>();
+    (!style.is_empty()).then_some(style)
+}
+
+/// Whether referencing a class is shorter than the attributes, and the values can be used in Css.
+fn class_is_smaller(style: &[(String, String)]) -> bool {
+    // `class="s-xxxxxxxx-nn"` and the declarations in the style element
+    const CLASS_ATTRIBUTE_LEN: usize = 22;
+    let attributes_len = style
+        .iter()
+        .map(|(name, value)| name.len() + value.len() + 4)
+        .sum::<usize>();
+    attributes_len > CLASS_ATTRIBUTE_LEN
+        && style
+            .iter()
+            .all(|(_, value)| !value.contains(['{', '}', ';', '<']))
+}
+
+fn reusable_path_data<'a>(
+    node: Node,
+    attributes: &'a [(String, String)],
+    reusable: bool,
+) -> Option<&'a str> {
+    if !reusable
+        || !node.has_tag_name("path")
+        || node.parent_element().is_none()
+        || attributes.iter().any(|(name, _)| name == "id")
+    {
+        return None;
+    }
+    attributes
+        .iter()
+        .find(|(name, _)| name == "d")
+        .map(|(_, data)| data.as_str())
+        .filter(|data| data.len() >= REUSE_PATH_DATA_LEN_MIN)
+}
+
+/// The scale of the root coordinate system, from its size and view box.
+fn root_scale(root: Node) -> Option<f64> {
+    let Some(view_box) = root.attribute("viewBox") else {
+        return Some(1.0);
+    };
+    let view_box = parse_numbers(view_box)?;
+    let (Some(width), Some(height)) = (root.attribute("width"), root.attribute("height")) else {
+        return Some(1.0);
+    };
+    let (width, height) = (width.parse::<f64>().ok()?, height.parse::<f64>().ok()?);
+    match view_box[..] {
+        [_, _, vb_width, vb_height] if vb_width > 0.0 && vb_height > 0.0 => {
+            Some((width / vb_width).max(height / vb_height))
+        }
+        _ => None,
+    }
+}
+
+/// The maximum scale of the transform, None when it can't be parsed.
+fn transform_scale(transform: &str) -> Option<f64> {
+    let transform = transform.trim();
+    let (kind, args) = transform.strip_suffix(')')?.split_once('(')?;
+    let args = parse_numbers(args)?;
+    match (kind.trim(), &args[..]) {
+        ("matrix", [a, b, c, d, _, _]) => Some(a.hypot(*b).max(c.hypot(*d))),
+        ("translate", _) => Some(1.0),
+        ("scale", [s]) => Some(s.abs()),
+        ("scale", [sx, sy]) => Some(sx.abs().max(sy.abs())),
+        _ => None,
+    }
+}
+
+fn parse_numbers(s: &str) -> Option<Vec<f64>> {
+    s.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|v| !v.is_empty())
+        .map(|v| v.parse::<f64>().ok())
+        .collect()
+}
+
+/// The decimal places that keep the rounding error invisible in a coordinate system with the scale.
+fn precision_for_scale(scale: f64) -> Option<usize> {
+    if !scale.is_finite() || scale <= 0.0 {
+        return None;
+    }
+    let precision = COORDINATES_PRECISION + scale.log10().ceil().max(0.0);
+    (precision <= COORDINATES_PRECISION_MAX).then_some(precision as usize)
+}
+
+/// Round all numbers in the value to the decimal places.
+fn round_numbers(value: &str, precision: usize) -> String {
+    let mut rounded = String::with_capacity(value.len());
+    let mut last = 0;
+    for m in NUMBER_REGEX.find_iter(value) {
+        rounded.push_str(&value[last..m.start()]);
+        last = m.end();
+        let Ok(number) = m.as_str().parse::<f64>() else {
+            rounded.push_str(m.as_str());
+            continue;
+        };
+        let formatted = format_number(number, precision);
+        rounded.push_str(&formatted);
+        // Keep the numbers separated when the decimal point was removed and the next number starts with one
+        if !formatted.contains('.') && value[last..].starts_with('.') {
+            rounded.push(' ');
+        }
+    }
+    rounded.push_str(&value[last..]);
+    rounded
+}
+
+/// Format the number with at most the decimal places, without trailing zeros and the leading zero.
+fn format_number(number: f64, precision: usize) -> String {
+    let mut formatted = format!("{number:.precision$}");
+    if formatted.contains('.') {
+        let len = formatted.trim_end_matches('0').trim_end_matches('.').len();
+        formatted.truncate(len);
+    }
+    if formatted == "-0" {
+        return String::from("0");
+    }
+    if let Some(fraction) = formatted.strip_prefix("0.") {
+        format!(".{fraction}")
+    } else if let Some(fraction) = formatted.strip_prefix("-0.") {
+        format!("-.{fraction}")
+    } else {
+        formatted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn optimize_merges_styles_and_reuses_paths() {
+        let path_data = "M 10.12345 20.5 L 30.004 40 L 50.5 -60.2549 Z";
+        let svg_data = format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100" viewBox="0 0 100 100"><path fill="#ff0000" stroke="#000000" stroke-width="2" d="{path_data}"/><path fill="#ff0000" stroke="#000000" stroke-width="2" d="{path_data}"/><g transform="matrix(10 0 0 10 0 0)"><path fill="none" d="M 1.234567 2"/></g></svg>"##
+        );
+        let optimized = optimize_svg_data(&svg_data).unwrap();
+        let doc = roxmltree::Document::parse(&optimized).unwrap();
+
+        assert_eq!(
+            doc.descendants().filter(|n| n.has_tag_name("use")).count(),
+            2
+        );
+        assert!(optimized.contains("{fill:#ff0000;stroke:#000000;stroke-width:2}"));
+        assert!(optimized.contains(r#"d="M 10.12 20.5 L 30 40 L 50.5 -60.25 Z""#));
+        // Scaled up coordinates keep more decimal places
+        assert!(optimized.contains(r#"d="M 1.235 2""#));
+        assert_eq!(round_numbers("1.0.5 0.26", 1), "1 .5 .3");
+    }
+}
@@ -0,0 +1,462 @@
+// Imports
+use crate::engine::{PastePlacement, StrokeContent};
+use crate::fileformats::rnoteformat::RnoteFragment;
+use crate::fileformats::{FileFormatLoader, FileFormatSaver};
+use crate::strokes::{ShapeStroke, Stroke, StrokeBehaviour};
+use crate::{render, DrawBehaviour, RnoteEngine, WidgetFlags};
+use anyhow::Context;
+use p2d::bounding_volume::BoundingVolume;
+use rnote_compose::shapes::{Arrow, CubicBezier, Ellipse, Line, Rectangle, Shape};
+use rnote_compose::style::smooth::SmoothOptions;
+use rnote_compose::transform::TransformBehaviour;
+use rnote_compose::{Style, Transform};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The name of the set holding the symbols saved by the user.
+pub const USER_SET_NAME: &str = "user";
+/// The file extension of saved user symbols.
+pub const USER_SYMBOL_FILE_EXT: &str = "rnote";
+
+/// A reusable symbol that can be inserted into documents.
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    /// The name, unique inside its set.
+    pub name: String,
+    /// The strokes of the symbol.
+    pub content: StrokeContent,
+}
+
+impl Symbol {
+    /// Margin around the symbol in previews.
+    const PREVIEW_MARGIN: f64 = 4.0;
+
+    /// A new symbol. The content is stripped from its background and bounds.
+    pub fn new(name: impl Into<String>, content: StrokeContent) -> Self {
+        Self {
+            name: name.into(),
+            content: content.with_background(None).with_bounds(None),
+        }
+    }
+
+    /// The size of the symbol at scale 1.
+    pub fn size(&self) -> Option<na::Vector2<f64>> {
+        self.content.size()
+    }
+
+    /// The content of the symbol, scaled by the factor.
+    pub fn scaled_content(&self, scale: f64) -> StrokeContent {
+        if (scale - 1.0).abs() < f64::EPSILON {
+            return self.content.clone();
+        }
+        let strokes = self
+            .content
+            .strokes
+            .iter()
+            .map(|stroke| {
+                let mut stroke = (**stroke).clone();
+                stroke.scale(na::Vector2::repeat(scale));
+                stroke.update_geometry();
+                Arc::new(stroke)
+            })
+            .collect();
+        StrokeContent::default().with_strokes(strokes)
+    }
+
+    /// Render a preview of the symbol, fitted into the maximum size in pixels.
+    pub fn gen_preview(&self, max_size: na::Vector2<f64>) -> anyhow::Result<render::Image> {
+        let bounds = self
+            .content
+            .bounds()
+            .context("symbol has no content")?
+            .loosened(Self::PREVIEW_MARGIN);
+        let extents = bounds.extents();
+        let image_scale = (max_size[0] / extents[0]).min(max_size[1] / extents[1]);
+        render::Image::gen_with_piet(
+            |piet_cx| {
+                for stroke in self.content.strokes.iter() {
+                    stroke.draw(piet_cx, image_scale)?;
+                }
+                Ok(())
+            },
+            bounds,
+            image_scale,
+        )
+    }
+}
+
+/// A named set of symbols.
+#[derive(Debug, Clone)]
+pub struct SymbolSet {
+    /// The name, unique inside the library.
+    pub name: String,
+    /// Whether the set is bundled with the application and therefore read-only.
+    pub bundled: bool,
+    pub symbols: Vec<Symbol>,
+}
+
+impl SymbolSet {
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|symbol| symbol.name == name)
+    }
+}
+
+/// The symbol library, holding the bundled sets and the symbols saved by the user.
+///
+/// User symbols are stored as `.rnote` fragment files in the user symbols directory, named after the symbol.
+#[derive(Debug, Clone)]
+pub struct SymbolLibrary {
+    bundled: Vec<SymbolSet>,
+    user: SymbolSet,
+    user_dir: Option<PathBuf>,
+}
+
+impl Default for SymbolLibrary {
+    fn default() -> Self {
+        Self {
+            bundled: vec![arrows_set(), flowchart_set(), electrical_set()],
+            user: SymbolSet {
+                name: USER_SET_NAME.to_string(),
+                bundled: false,
+                symbols: vec![],
+            },
+            user_dir: None,
+        }
+    }
+}
+
+impl SymbolLibrary {
+    /// All sets, the bundled sets first.
+    pub fn sets(&self) -> impl Iterator<Item = &SymbolSet> {
+        self.bundled.iter().chain(std::iter::once(&self.user))
+    }
+
+    /// List all symbols as pairs of set name and symbol name.
+    pub fn list(&self) -> Vec<(&str, &str)> {
+        self.sets()
+            .flat_map(|set| {
+                set.symbols
+                    .iter()
+                    .map(|symbol| (set.name.as_str(), symbol.name.as_str()))
+            })
+            .collect()
+    }
+
+    pub fn get(&self, set: &str, name: &str) -> Option<&Symbol> {
+        self.sets().find(|s| s.name == set)?.get(name)
+    }
+
+    /// Load the user symbols from the directory, replacing the current user symbols.
+    ///
+    /// The directory is remembered and used when saving or removing symbols.
+    /// Files that fail to load are skipped.
+    pub fn load_user_symbols(&mut self, dir: impl AsRef<Path>) -> anyhow::Result<()> {
+        let dir = dir.as_ref();
+        self.user_dir = Some(dir.to_path_buf());
+        self.user.symbols.clear();
+        if !dir.exists() {
+            return Ok(());
+        }
+
+        for entry in std::fs::read_dir(dir)
+            .with_context(|| format!("reading user symbols directory {dir:?} failed"))?
+        {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(USER_SYMBOL_FILE_EXT) {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            match std::fs::read(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|bytes| RnoteFragment::load_from_bytes(&bytes))
+            {
+                Ok(fragment) => self
+                    .user
+                    .symbols
+                    .push(Symbol::new(name, fragment.stroke_content)),
+                Err(e) => log::warn!("loading user symbol from file {path:?} failed, Err: {e:?}"),
+            }
+        }
+        self.user.symbols.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(())
+    }
+
+    /// Save the content as user symbol, replacing an existing one with the same name.
+    pub fn save_user_symbol(&mut self, name: &str, content: StrokeContent) -> anyhow::Result<()> {
+        let path = self.user_symbol_path(name)?;
+        let symbol = Symbol::new(name, content);
+        if symbol.content.strokes.is_empty() {
+            return Err(anyhow::anyhow!("symbol has no content"));
+        }
+        let bytes = RnoteFragment::new(symbol.content.clone()).save_as_bytes(name)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, bytes)
+            .with_context(|| format!("writing user symbol to file {path:?} failed"))?;
+
+        self.user.symbols.retain(|s| s.name != name);
+        self.user.symbols.push(symbol);
+        self.user.symbols.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(())
+    }
+
+    /// Remove the user symbol together with its file.
+    pub fn remove_user_symbol(&mut self, name: &str) -> anyhow::Result<()> {
+        let path = self.user_symbol_path(name)?;
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("removing user symbol file {path:?} failed"))?;
+        }
+        self.user.symbols.retain(|s| s.name != name);
+        Ok(())
+    }
+
+    fn user_symbol_path(&self, name: &str) -> anyhow::Result<PathBuf> {
+        let dir = self
+            .user_dir
+            .as_ref()
+            .context("user symbols directory is not set")?;
+        if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+            return Err(anyhow::anyhow!("invalid symbol name `{name}`"));
+        }
+        Ok(dir.join(format!("{name}.{USER_SYMBOL_FILE_EXT}")))
+    }
+}
+
+impl RnoteEngine {
+    /// Insert the symbol centered at the position (in document coordinate space), scaled by the factor.
+    ///
+    /// The inserted strokes become the selection.
+    pub fn insert_symbol(
+        &mut self,
+        symbol: &Symbol,
+        pos: na::Vector2<f64>,
+        scale: f64,
+    ) -> WidgetFlags {
+        self.insert_stroke_content(
+            symbol.scaled_content(scale),
+            PastePlacement::CenteredAt(pos),
+        )
+    }
+}
+
+fn shapes_symbol(name: &str, shapes: Vec<Shape>) -> Symbol {
+    let strokes = shapes
+        .into_iter()
+        .map(|shape| {
+            Arc::new(Stroke::ShapeStroke(ShapeStroke::new(
+                shape,
+                Style::Smooth(SmoothOptions::default()),
+            )))
+        })
+        .collect();
+    Symbol::new(name, StrokeContent::default().with_strokes(strokes))
+}
+
+fn line(start: (f64, f64), end: (f64, f64)) -> Shape {
+    Shape::Line(Line {
+        start: na::vector![start.0, start.1],
+        end: na::vector![end.0, end.1],
+    })
+}
+
+fn polyline(points: &[(f64, f64)]) -> Vec<Shape> {
+    points.windows(2).map(|w| line(w[0], w[1])).collect()
+}
+
+fn arrow(start: (f64, f64), tip: (f64, f64)) -> Shape {
+    Shape::Arrow(Arrow::new(
+        na::vector![start.0, start.1],
+        na::vector![tip.0, tip.1],
+    ))
+}
+
+fn circle(center: (f64, f64), radius: f64) -> Shape {
+    Shape::Ellipse(Ellipse {
+        radii: na::Vector2::repeat(radius),
+        transform: Transform::new_w_isometry(na::Isometry2::new(
+            na::vector![center.0, center.1],
+            0.0,
+        )),
+    })
+}
+
+/// A quarter circle arc starting at the angle (in radians), approximated with a cubic bezier curve.
+fn quarter_arc(center: (f64, f64), radius: f64, start_angle: f64) -> Shape {
+    // The distance of the control points for an optimal approximation
+    const K: f64 = 0.552_284_75;
+    let center = na::vector![center.0, center.1];
+    let end_angle = start_angle + std::f64::consts::FRAC_PI_2;
+    let dir = |angle: f64| na::vector![angle.cos(), angle.sin()];
+    let tangent = |angle: f64| na::vector![-angle.sin(), angle.cos()];
+    let start = center + dir(start_angle) * radius;
+    let end = center + dir(end_angle) * radius;
+    Shape::CubicBezier(CubicBezier {
+        start,
+        cp1: start + tangent(start_angle) * radius * K,
+        cp2: end - tangent(end_angle) * radius * K,
+        end,
+    })
+}
+
+fn arrows_set() -> SymbolSet {
+    SymbolSet {
+        name: "arrows".to_string(),
+        bundled: true,
+        symbols: vec![
+            shapes_symbol("arrow-right", vec![arrow((0.0, 20.0), (60.0, 20.0))]),
+            shapes_symbol("arrow-left", vec![arrow((60.0, 20.0), (0.0, 20.0))]),
+            shapes_symbol("arrow-up", vec![arrow((20.0, 60.0), (20.0, 0.0))]),
+            shapes_symbol("arrow-down", vec![arrow((20.0, 0.0), (20.0, 60.0))]),
+            shapes_symbol("arrow-diagonal", vec![arrow((0.0, 60.0), (60.0, 0.0))]),
+            shapes_symbol(
+                "arrow-double",
+                vec![
+                    arrow((30.0, 20.0), (0.0, 20.0)),
+                    arrow((30.0, 20.0), (60.0, 20.0)),
+                ],
+            ),
+        ],
+    }
+}
+
+fn flowchart_set() -> SymbolSet {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    SymbolSet {
+        name: "flowchart".to_string(),
+        bundled: true,
+        symbols: vec![
+            shapes_symbol(
+                "process",
+                vec![Shape::Rectangle(Rectangle::from_corners(
+                    na::vector![0.0, 0.0],
+                    na::vector![80.0, 40.0],
+                ))],
+            ),
+            shapes_symbol(
+                "terminator",
+                vec![
+                    line((20.0, 0.0), (60.0, 0.0)),
+                    quarter_arc((60.0, 20.0), 20.0, -FRAC_PI_2),
+                    quarter_arc((60.0, 20.0), 20.0, 0.0),
+                    line((60.0, 40.0), (20.0, 40.0)),
+                    quarter_arc((20.0, 20.0), 20.0, FRAC_PI_2),
+                    quarter_arc((20.0, 20.0), 20.0, PI),
+                ],
+            ),
+            shapes_symbol(
+                "decision",
+                polyline(&[
+                    (40.0, 0.0),
+                    (80.0, 30.0),
+                    (40.0, 60.0),
+                    (0.0, 30.0),
+                    (40.0, 0.0),
+                ]),
+            ),
+            shapes_symbol(
+                "data",
+                polyline(&[
+                    (15.0, 0.0),
+                    (80.0, 0.0),
+                    (65.0, 40.0),
+                    (0.0, 40.0),
+                    (15.0, 0.0),
+                ]),
+            ),
+            shapes_symbol("document", {
+                let mut shapes = polyline(&[(0.0, 40.0), (0.0, 0.0), (80.0, 0.0), (80.0, 40.0)]);
+                shapes.push(Shape::CubicBezier(CubicBezier {
+                    start: na::vector![80.0, 40.0],
+                    cp1: na::vector![60.0, 28.0],
+                    cp2: na::vector![20.0, 52.0],
+                    end: na::vector![0.0, 40.0],
+                }));
+                shapes
+            }),
+            shapes_symbol("connector", vec![circle((10.0, 10.0), 10.0)]),
+        ],
+    }
+}
+
+fn electrical_set() -> SymbolSet {
+    SymbolSet {
+        name: "electrical".to_string(),
+        bundled: true,
+        symbols: vec![
+            shapes_symbol(
+                "resistor",
+                polyline(&[
+                    (0.0, 20.0),
+                    (15.0, 20.0),
+                    (18.0, 12.0),
+                    (24.0, 28.0),
+                    (30.0, 12.0),
+                    (36.0, 28.0),
+                    (42.0, 12.0),
+                    (48.0, 28.0),
+                    (51.0, 20.0),
+                    (66.0, 20.0),
+                ]),
+            ),
+            shapes_symbol(
+                "capacitor",
+                vec![
+                    line((0.0, 20.0), (28.0, 20.0)),
+                    line((28.0, 4.0), (28.0, 36.0)),
+                    line((36.0, 4.0), (36.0, 36.0)),
+                    line((36.0, 20.0), (64.0, 20.0)),
+                ],
+            ),
+            shapes_symbol(
+                "battery",
+                vec![
+                    line((0.0, 20.0), (28.0, 20.0)),
+                    line((28.0, 2.0), (28.0, 38.0)),
+                    line((36.0, 10.0), (36.0, 30.0)),
+                    line((36.0, 20.0), (64.0, 20.0)),
+                ],
+            ),
+            shapes_symbol("diode", {
+                let mut shapes = polyline(&[(20.0, 6.0), (20.0, 34.0), (44.0, 20.0), (20.0, 6.0)]);
+                shapes.extend([
+                    line((0.0, 20.0), (20.0, 20.0)),
+                    line((44.0, 6.0), (44.0, 34.0)),
+                    line((44.0, 20.0), (64.0, 20.0)),
+                ]);
+                shapes
+            }),
+            shapes_symbol(
+                "switch",
+                vec![
+                    line((0.0, 20.0), (20.0, 20.0)),
+                    line((20.0, 20.0), (44.0, 6.0)),
+                    line((44.0, 20.0), (64.0, 20.0)),
+                ],
+            ),
+            shapes_symbol(
+                "lamp",
+                vec![
+                    line((0.0, 20.0), (18.0, 20.0)),
+                    circle((32.0, 20.0), 14.0),
+                    line((22.1, 10.1), (41.9, 29.9)),
+                    line((22.1, 29.9), (41.9, 10.1)),
+                    line((46.0, 20.0), (64.0, 20.0)),
+                ],
+            ),
+            shapes_symbol(
+                "ground",
+                vec![
+                    line((20.0, 0.0), (20.0, 20.0)),
+                    line((4.0, 20.0), (36.0, 20.0)),
+                    line((10.0, 27.0), (30.0, 27.0)),
+                    line((16.0, 34.0), (24.0, 34.0)),
+                ],
+            ),
+        ],
+    }
+}
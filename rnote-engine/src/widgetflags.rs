@@ -26,6 +26,9 @@ pub struct WidgetFlags {
     /// Meaning, when enabled instead of key events, text events are then emitted
     /// for regular unicode text. Used when writing text with the typewriter.
     pub enable_text_preprocessing: Option<bool>,
+    /// Start driving the camera animation with the frame clock of the widget,
+    /// by repeatedly calling `camera_animation_tick()` until it reports that the animation has finished.
+    pub animate_camera: bool,
 }
 
 impl Default for WidgetFlags {
@@ -42,6 +45,7 @@ impl Default for WidgetFlags {
             hide_undo: None,
             hide_redo: None,
             enable_text_preprocessing: None,
+            animate_camera: false,
         }
     }
 }
@@ -57,6 +61,7 @@ impl WidgetFlags {
         self.zoomed_temporarily |= other.zoomed_temporarily;
         self.zoomed |= other.zoomed;
         self.deselect_color_setters |= other.deselect_color_setters;
+        self.animate_camera |= other.animate_camera;
         if other.hide_undo.is_some() {
             self.hide_undo = other.hide_undo
         }
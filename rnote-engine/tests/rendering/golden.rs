@@ -0,0 +1,170 @@
+// Imports
+use image::{Rgba, RgbaImage};
+use rnote_engine::RnoteEngine;
+use std::path::PathBuf;
+
+/// When this environment variable is set, the golden images are (re)written from the current renderings.
+pub(crate) const UPDATE_GOLDEN_ENV_VAR: &str = "RNOTE_UPDATE_GOLDEN";
+/// The maximum YIQ delta between two colors, used to normalize the pixel differences.
+const YIQ_DELTA_MAX: f64 = 35215.0;
+
+/// How much a rendering is allowed to differ from its golden image.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Tolerance {
+    /// The perceptual difference (0.0 to 1.0) above which two pixels are considered different.
+    pub(crate) pixel_threshold: f64,
+    /// The ratio of different pixels (0.0 to 1.0) that is still accepted, e.g. for changed anti-aliasing.
+    pub(crate) max_diff_ratio: f64,
+}
+
+impl Tolerance {
+    /// For strokes that are rendered by our own code paths.
+    pub(crate) const STROKES: Self = Self {
+        pixel_threshold: 0.1,
+        max_diff_ratio: 0.002,
+    };
+    /// For text, which also depends on the installed fonts and the font rendering of the system.
+    pub(crate) const TEXT: Self = Self {
+        pixel_threshold: 0.2,
+        max_diff_ratio: 0.02,
+    };
+}
+
+/// Render the document of the engine through the cairo export path, including the background.
+pub(crate) fn render_document(engine: &RnoteEngine, image_scale: f64) -> anyhow::Result<RgbaImage> {
+    let content = engine.extract_document_content();
+    let bounds = content
+        .bounds()
+        .ok_or_else(|| anyhow::anyhow!("document content has no bounds"))?;
+    let width = (bounds.extents()[0] * image_scale).ceil() as i32;
+    let height = (bounds.extents()[1] * image_scale).ceil() as i32;
+
+    let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width, height)?;
+    {
+        let cairo_cx = cairo::Context::new(&surface)?;
+        cairo_cx.scale(image_scale, image_scale);
+        cairo_cx.translate(-bounds.mins[0], -bounds.mins[1]);
+        content.draw_to_cairo(&cairo_cx, true, true, 0.0, image_scale)?;
+    }
+    let mut png_bytes = vec![];
+    surface.write_to_png(&mut png_bytes)?;
+
+    Ok(image::load_from_memory_with_format(&png_bytes, image::ImageFormat::Png)?.into_rgba8())
+}
+
+/// Compare the rendering against the golden image with the given name and panic if they differ beyond the tolerance.
+///
+/// On failure the rendering and an image highlighting the differences are written to the test output directory.
+/// Missing golden images fail the test, they are only created when [UPDATE_GOLDEN_ENV_VAR] is set.
+pub(crate) fn assert_golden(name: &str, rendering: &RgbaImage, tolerance: Tolerance) {
+    let golden_path = golden_dir().join(format!("{name}.png"));
+
+    if std::env::var_os(UPDATE_GOLDEN_ENV_VAR).is_some() {
+        std::fs::create_dir_all(golden_dir()).unwrap();
+        rendering.save(&golden_path).unwrap();
+        eprintln!(
+            "wrote golden image `{}`, review and commit it",
+            golden_path.display()
+        );
+        return;
+    }
+    if !golden_path.exists() {
+        let actual_path = write_output(name, "actual", rendering);
+        panic!(
+            "golden image `{}` is missing. Rendering written to `{}`. Create the golden images \
+            by running the tests with `{UPDATE_GOLDEN_ENV_VAR}=1`, then review and commit them",
+            golden_path.display(),
+            actual_path.display()
+        );
+    }
+    let golden = image::open(&golden_path)
+        .unwrap_or_else(|e| {
+            panic!(
+                "opening golden image `{}` failed, Err: {e:?}",
+                golden_path.display()
+            )
+        })
+        .into_rgba8();
+
+    if golden.dimensions() != rendering.dimensions() {
+        let actual_path = write_output(name, "actual", rendering);
+        panic!(
+            "rendering `{name}` has size {:?}, but golden image has size {:?}. Rendering written to `{}`",
+            rendering.dimensions(),
+            golden.dimensions(),
+            actual_path.display()
+        );
+    }
+
+    let (diff_image, n_diff_pixels) = diff(&golden, rendering, tolerance.pixel_threshold);
+    let diff_ratio = n_diff_pixels as f64 / (golden.width() as f64 * golden.height() as f64);
+    if diff_ratio > tolerance.max_diff_ratio {
+        let actual_path = write_output(name, "actual", rendering);
+        let diff_path = write_output(name, "diff", &diff_image);
+        panic!(
+            "rendering `{name}` differs from its golden image in {n_diff_pixels} pixels ({:.3}%, accepted: {:.3}%). \
+            Rendering written to `{}`, differences to `{}`. If the change is intended, update the golden images \
+            by running the tests with `{UPDATE_GOLDEN_ENV_VAR}=1`",
+            diff_ratio * 100.0,
+            tolerance.max_diff_ratio * 100.0,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/rendering/golden")
+}
+
+fn write_output(name: &str, suffix: &str, image: &RgbaImage) -> PathBuf {
+    let output_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&output_dir).unwrap();
+    let path = output_dir.join(format!("{name}-{suffix}.png"));
+    image.save(&path).unwrap();
+    path
+}
+
+/// Returns an image with the different pixels marked in red over the faded golden image, and the number of them.
+fn diff(golden: &RgbaImage, rendering: &RgbaImage, pixel_threshold: f64) -> (RgbaImage, usize) {
+    let mut n_diff_pixels = 0;
+    let diff_image = RgbaImage::from_fn(golden.width(), golden.height(), |x, y| {
+        let golden_px = *golden.get_pixel(x, y);
+        if yiq_delta(golden_px, *rendering.get_pixel(x, y)) > pixel_threshold {
+            n_diff_pixels += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let [luma, _, _] = rgb_to_yiq(blend_on_white(golden_px));
+            let faded = (255.0 - (255.0 - luma) * 0.1) as u8;
+            Rgba([faded, faded, faded, 255])
+        }
+    });
+    (diff_image, n_diff_pixels)
+}
+
+/// The perceptual difference between two pixels, from 0.0 to 1.0.
+///
+/// Uses the weighted YIQ color difference from "Measuring perceived color difference using YIQ NTSC transmission color
+/// space in mobile applications" by Y. Kotsarenko and F. Ramos.
+fn yiq_delta(first: Rgba<u8>, second: Rgba<u8>) -> f64 {
+    if first == second {
+        return 0.0;
+    }
+    let [y1, i1, q1] = rgb_to_yiq(blend_on_white(first));
+    let [y2, i2, q2] = rgb_to_yiq(blend_on_white(second));
+    let delta = 0.5053 * (y1 - y2).powi(2) + 0.299 * (i1 - i2).powi(2) + 0.1957 * (q1 - q2).powi(2);
+    (delta / YIQ_DELTA_MAX).sqrt()
+}
+
+fn blend_on_white(px: Rgba<u8>) -> [f64; 3] {
+    let alpha = px[3] as f64 / 255.0;
+    [0, 1, 2].map(|i| 255.0 + (px[i] as f64 - 255.0) * alpha)
+}
+
+fn rgb_to_yiq([r, g, b]: [f64; 3]) -> [f64; 3] {
+    [
+        r * 0.29889531 + g * 0.58662247 + b * 0.11448223,
+        r * 0.59597799 - g * 0.2741761 - b * 0.32180189,
+        r * 0.21147017 - g * 0.52261711 + b * 0.31114694,
+    ]
+}
@@ -0,0 +1,193 @@
+//! Golden image tests for the rendering of the strokes.
+//!
+//! The fixture documents are rendered through the cairo export path and compared against the golden images in
+//! `tests/rendering/golden`. After intended rendering changes, the golden images are updated by running
+//! `RNOTE_UPDATE_GOLDEN=1 cargo test -p rnote-engine --test rendering`.
+
+// Modules
+mod golden;
+
+// Imports
+use golden::{assert_golden, render_document, Tolerance};
+use rnote_compose::penpath::Element;
+use rnote_compose::shapes::{CubicBezier, Ellipse, Line, Rectangle, Shape};
+use rnote_compose::style::airbrush::AirbrushOptions;
+use rnote_compose::style::grain::GrainOptions;
+use rnote_compose::style::rough::roughoptions::FillStyle;
+use rnote_compose::style::rough::RoughOptions;
+use rnote_compose::style::smooth::SmoothOptions;
+use rnote_compose::style::textured::TexturedOptions;
+use rnote_compose::{Color, Style};
+use rnote_engine::document::Format;
+use rnote_engine::engine::DocumentBuilder;
+use rnote_engine::strokes::textstroke::{TextAlignment, TextStyle};
+
+// Renames
+extern crate nalgebra as na;
+
+/// The seed for the styles that are randomized, so the renderings are reproducible.
+const SEED: u64 = 42;
+const IMAGE_SCALE: f64 = 1.0;
+
+fn doc_builder() -> DocumentBuilder {
+    DocumentBuilder::new().with_format(Format {
+        width: 600.0,
+        height: 400.0,
+        ..Format::default()
+    })
+}
+
+/// A wavy pen stroke from left to right with increasing pressure, starting at the y position.
+fn wavy_elements(y: f64) -> Vec<Element> {
+    (0..=200)
+        .map(|i| {
+            let t = i as f64 / 200.0;
+            Element::new(
+                na::vector![
+                    50.0 + t * 500.0,
+                    y + (t * std::f64::consts::TAU * 2.0).sin() * 20.0
+                ],
+                0.1 + t * 0.9,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn brush_styles() -> anyhow::Result<()> {
+    let engine = doc_builder()
+        .with_pen_stroke(
+            wavy_elements(50.0),
+            Style::Smooth(SmoothOptions {
+                stroke_width: 6.0,
+                ..SmoothOptions::default()
+            }),
+        )?
+        .with_pen_stroke(
+            wavy_elements(130.0),
+            Style::Textured(TexturedOptions {
+                stroke_width: 8.0,
+                seed: Some(SEED),
+                ..TexturedOptions::default()
+            }),
+        )?
+        .with_pen_stroke(
+            wavy_elements(210.0),
+            Style::Grain(GrainOptions {
+                stroke_width: 10.0,
+                ..GrainOptions::default()
+            }),
+        )?
+        .with_pen_stroke(
+            wavy_elements(290.0),
+            Style::Airbrush(AirbrushOptions {
+                seed: Some(SEED),
+                ..AirbrushOptions::default()
+            }),
+        )?
+        .build();
+
+    let rendering = render_document(&engine, IMAGE_SCALE)?;
+    assert_golden("brush_styles", &rendering, Tolerance::STROKES);
+    Ok(())
+}
+
+#[test]
+fn rough_shapes() -> anyhow::Result<()> {
+    let rough = |fill_color: Option<Color>, fill_style: FillStyle| {
+        Style::Rough(RoughOptions {
+            fill_color,
+            fill_style,
+            seed: Some(SEED),
+            ..RoughOptions::default()
+        })
+    };
+    let engine = doc_builder()
+        .with_shape(
+            Shape::Rectangle(Rectangle::from_corners(
+                na::vector![40.0, 40.0],
+                na::vector![200.0, 160.0],
+            )),
+            rough(Some(Color::RED), FillStyle::Hachure),
+        )?
+        .with_shape(
+            Shape::Ellipse(Ellipse::from_foci_and_point(
+                [na::vector![280.0, 100.0], na::vector![420.0, 100.0]],
+                na::vector![350.0, 150.0],
+            )),
+            rough(Some(Color::BLUE), FillStyle::ZigZag),
+        )?
+        .with_shape(
+            Shape::Line(Line {
+                start: na::vector![40.0, 220.0],
+                end: na::vector![560.0, 260.0],
+            }),
+            rough(None, FillStyle::Solid),
+        )?
+        .with_shape(
+            Shape::CubicBezier(CubicBezier {
+                start: na::vector![40.0, 360.0],
+                cp1: na::vector![200.0, 240.0],
+                cp2: na::vector![400.0, 400.0],
+                end: na::vector![560.0, 300.0],
+            }),
+            rough(None, FillStyle::Solid),
+        )?
+        .build();
+
+    let rendering = render_document(&engine, IMAGE_SCALE)?;
+    assert_golden("rough_shapes", &rendering, Tolerance::STROKES);
+    Ok(())
+}
+
+#[test]
+fn text_layout() {
+    let text = "The quick brown fox jumps over the lazy dog.\nSecond paragraph, with a wrapped line that is long enough to wrap.";
+    let text_style = |alignment: TextAlignment| TextStyle {
+        max_width: Some(240.0),
+        alignment,
+        ..TextStyle::default()
+    };
+    let engine = doc_builder()
+        .with_text(
+            text,
+            na::vector![40.0, 40.0],
+            text_style(TextAlignment::Start),
+        )
+        .with_text(
+            text,
+            na::vector![320.0, 40.0],
+            text_style(TextAlignment::End),
+        )
+        .with_text(
+            text,
+            na::vector![40.0, 220.0],
+            text_style(TextAlignment::Center),
+        )
+        .with_text(
+            text,
+            na::vector![320.0, 220.0],
+            text_style(TextAlignment::Fill),
+        )
+        .build();
+
+    let rendering = render_document(&engine, IMAGE_SCALE).unwrap();
+    assert_golden("text_layout", &rendering, Tolerance::TEXT);
+}
+
+#[test]
+fn zoomed_rendering() -> anyhow::Result<()> {
+    let engine = doc_builder()
+        .with_pen_stroke(
+            wavy_elements(200.0),
+            Style::Textured(TexturedOptions {
+                seed: Some(SEED),
+                ..TexturedOptions::default()
+            }),
+        )?
+        .build();
+
+    let rendering = render_document(&engine, 2.5)?;
+    assert_golden("zoomed_rendering", &rendering, Tolerance::STROKES);
+    Ok(())
+}
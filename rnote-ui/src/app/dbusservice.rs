@@ -0,0 +1,253 @@
+// Imports
+use crate::canvas::{self, RnCanvas};
+use crate::{RnApp, RnAppWindow};
+use gtk4::{gio, glib, glib::clone, prelude::*};
+use rnote_compose::helpers::SplitOrder;
+use rnote_engine::engine::export::{DocExportFormat, DocExportPrefs};
+use rnote_engine::{exporters, Progress};
+
+/// The name of the D-Bus interface for controlling the documents of a running instance.
+pub(crate) const DBUS_INTERFACE_NAME: &str = "com.github.flxzt.rnote.Document";
+
+/// Positions are in the coordinate space of the document.
+/// Methods without a position insert the content at the default import position of the current view.
+const DBUS_INTERFACE_XML: &str = r#"
+<node>
+  <interface name="com.github.flxzt.rnote.Document">
+    <method name="OpenFile">
+      <arg type="s" name="path" direction="in"/>
+    </method>
+    <method name="InsertImage">
+      <arg type="s" name="path" direction="in"/>
+    </method>
+    <method name="InsertImageAt">
+      <arg type="s" name="path" direction="in"/>
+      <arg type="d" name="x" direction="in"/>
+      <arg type="d" name="y" direction="in"/>
+    </method>
+    <method name="InsertText">
+      <arg type="s" name="text" direction="in"/>
+    </method>
+    <method name="InsertTextAt">
+      <arg type="s" name="text" direction="in"/>
+      <arg type="d" name="x" direction="in"/>
+      <arg type="d" name="y" direction="in"/>
+    </method>
+    <method name="Export">
+      <arg type="s" name="path" direction="in"/>
+    </method>
+    <method name="GetDocumentState">
+      <arg type="a{sv}" name="state" direction="out"/>
+    </method>
+  </interface>
+</node>
+"#;
+
+impl RnApp {
+    /// Register the D-Bus service on the object path of the application.
+    ///
+    /// External tools can use it to drive the active document,
+    /// e.g. to insert a screenshot with `gdbus call --session --dest com.github.flxzt.rnote
+    /// --object-path /com/github/flxzt/rnote --method com.github.flxzt.rnote.Document.InsertImage /tmp/screenshot.png`.
+    pub(crate) fn register_dbus_service(
+        &self,
+        connection: &gio::DBusConnection,
+        object_path: &str,
+    ) -> Result<gio::RegistrationId, glib::Error> {
+        let node_info = gio::DBusNodeInfo::for_xml(DBUS_INTERFACE_XML)?;
+        let interface_info = node_info
+            .lookup_interface(DBUS_INTERFACE_NAME)
+            .ok_or_else(|| {
+                glib::Error::new(
+                    gio::IOErrorEnum::NotFound,
+                    "D-Bus interface info not found in introspection data",
+                )
+            })?;
+
+        connection.register_object(
+            object_path,
+            &interface_info,
+            clone!(@weak self as app => move |_, _, _, _, method_name, parameters, invocation| {
+                app.handle_dbus_method_call(method_name.to_string(), parameters, invocation);
+            }),
+            // The interface has no properties
+            |_, _, _, _, _| ().to_variant(),
+            |_, _, _, _, _, _| false,
+        )
+    }
+
+    fn handle_dbus_method_call(
+        &self,
+        method_name: String,
+        parameters: glib::Variant,
+        invocation: gio::DBusMethodInvocation,
+    ) {
+        glib::MainContext::default().spawn_local(clone!(@weak self as app => async move {
+            match app.dbus_method_call(&method_name, parameters).await {
+                Ok(ret) => invocation.return_value(ret.as_ref()),
+                Err(e) => {
+                    log::error!("D-Bus method call `{method_name}` failed, Err: {e:?}");
+                    invocation.return_gerror(glib::Error::new(
+                        gio::DBusError::Failed,
+                        &format!("{e:?}"),
+                    ));
+                }
+            }
+        }));
+    }
+
+    async fn dbus_method_call(
+        &self,
+        method_name: &str,
+        parameters: glib::Variant,
+    ) -> anyhow::Result<Option<glib::Variant>> {
+        let invalid_parameters = || {
+            anyhow::anyhow!(
+                "Invalid parameters of type `{}` for method `{method_name}`",
+                parameters.type_()
+            )
+        };
+
+        match method_name {
+            "OpenFile" => {
+                let (path,) = parameters
+                    .get::<(String,)>()
+                    .ok_or_else(invalid_parameters)?;
+                self.open(&[gio::File::for_path(path)], "");
+                Ok(None)
+            }
+            "InsertImage" | "InsertImageAt" => {
+                let (path, target_pos) = if method_name == "InsertImageAt" {
+                    let (path, x, y) = parameters
+                        .get::<(String, f64, f64)>()
+                        .ok_or_else(invalid_parameters)?;
+                    (path, Some(na::vector![x, y]))
+                } else {
+                    let (path,) = parameters
+                        .get::<(String,)>()
+                        .ok_or_else(invalid_parameters)?;
+                    (path, None)
+                };
+                let canvas = self.dbus_active_canvas()?;
+                let file = gio::File::for_path(path);
+                let (bytes, _) = file.load_bytes_future().await?;
+
+                match crate::utils::FileType::lookup_file_type(&file) {
+                    crate::utils::FileType::VectorImageFile => {
+                        canvas
+                            .load_in_vectorimage_bytes(bytes.to_vec(), target_pos)
+                            .await?
+                    }
+                    crate::utils::FileType::BitmapImageFile => {
+                        canvas
+                            .load_in_bitmapimage_bytes(bytes.to_vec(), target_pos)
+                            .await?
+                    }
+                    _ => return Err(anyhow::anyhow!("File is not a supported image")),
+                }
+                Ok(None)
+            }
+            "InsertText" | "InsertTextAt" => {
+                let (text, target_pos) = if method_name == "InsertTextAt" {
+                    let (text, x, y) = parameters
+                        .get::<(String, f64, f64)>()
+                        .ok_or_else(invalid_parameters)?;
+                    (text, Some(na::vector![x, y]))
+                } else {
+                    let (text,) = parameters
+                        .get::<(String,)>()
+                        .ok_or_else(invalid_parameters)?;
+                    (text, None)
+                };
+                self.dbus_active_canvas()?.load_in_text(text, target_pos)?;
+                Ok(None)
+            }
+            "Export" => {
+                let (path,) = parameters
+                    .get::<(String,)>()
+                    .ok_or_else(invalid_parameters)?;
+                let canvas = self.dbus_active_canvas()?;
+                let file = gio::File::for_path(path);
+                let file_title = crate::utils::default_file_title_for_export(
+                    Some(file.clone()),
+                    Some(&canvas::OUTPUT_FILE_NEW_TITLE),
+                    None,
+                );
+                let file_ext = file
+                    .path()
+                    .and_then(|p| {
+                        p.extension()
+                            .map(|ext| ext.to_string_lossy().to_lowercase())
+                    })
+                    .unwrap_or_default();
+
+                let export_format = match file_ext.as_str() {
+                    "svg" => DocExportFormat::Svg,
+                    "pdf" => DocExportFormat::Pdf,
+                    "xopp" => DocExportFormat::Xopp,
+                    "tiff" | "tif" => DocExportFormat::Tiff,
+                    ext if exporters::custom_exporter_for_ext(ext).is_some() => {
+                        return canvas
+                            .export_doc_w_custom_exporter(&file, file_title, ext)
+                            .await
+                            .map(|_| None);
+                    }
+                    ext => {
+                        return Err(anyhow::anyhow!("Unsupported export file extension `{ext}`"))
+                    }
+                };
+                let doc_export_prefs = DocExportPrefs {
+                    export_format,
+                    ..canvas.engine_ref().export_prefs.doc_export_prefs
+                };
+                canvas
+                    .export_doc(
+                        &file,
+                        file_title,
+                        Some(doc_export_prefs),
+                        Progress::default(),
+                    )
+                    .await?;
+                Ok(None)
+            }
+            "GetDocumentState" => {
+                let canvas = self.dbus_active_canvas()?;
+                let engine = canvas.engine_ref();
+                let state = glib::VariantDict::new(None);
+                state.insert_value("title", &canvas.doc_title_display().to_variant());
+                state.insert_value(
+                    "path",
+                    &canvas
+                        .output_file()
+                        .and_then(|f| f.path())
+                        .map(|p| p.to_string_lossy().to_string())
+                        .unwrap_or_default()
+                        .to_variant(),
+                );
+                state.insert_value("unsaved_changes", &canvas.unsaved_changes().to_variant());
+                state.insert_value(
+                    "n_pages",
+                    &(engine.document.pages_bounds(SplitOrder::default()).len() as u32)
+                        .to_variant(),
+                );
+                state.insert_value(
+                    "n_strokes",
+                    &(engine.store.stroke_keys_as_rendered().len() as u32).to_variant(),
+                );
+                state.insert_value("width", &engine.document.width.to_variant());
+                state.insert_value("height", &engine.document.height.to_variant());
+
+                Ok(Some(glib::Variant::tuple_from_iter([state.end()])))
+            }
+            _ => Err(anyhow::anyhow!("Unknown method `{method_name}`")),
+        }
+    }
+
+    fn dbus_active_canvas(&self) -> anyhow::Result<RnCanvas> {
+        let appwindow = self
+            .active_window()
+            .and_then(|w| w.downcast::<RnAppWindow>().ok())
+            .ok_or_else(|| anyhow::anyhow!("No application window is open"))?;
+        Ok(appwindow.active_tab_wrapper().canvas())
+    }
+}
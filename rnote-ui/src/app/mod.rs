@@ -1,5 +1,6 @@
 // Modules
 mod appactions;
+mod dbusservice;
 
 // Imports
 use crate::{
@@ -15,12 +16,16 @@ use crate::{
 };
 use adw::subclass::prelude::AdwApplicationImpl;
 use gtk4::{gio, glib, glib::clone, prelude::*, subclass::prelude::*};
+use rnote_engine::engine::DeepLink;
+use std::cell::RefCell;
 
 mod imp {
     use super::*;
 
     #[derive(Debug, Default)]
-    pub(crate) struct RnApp {}
+    pub(crate) struct RnApp {
+        pub(crate) dbus_registration: RefCell<Option<gio::RegistrationId>>,
+    }
 
     #[glib::object_subclass]
     impl ObjectSubclass for RnApp {
@@ -49,6 +54,28 @@ mod imp {
             self.parent_open(files, hint);
 
             let input_file = files.first().cloned();
+            // Deep links from other applications, like `rnote:///path/to/file.rnote#page-2`
+            if let Some(link_file) = input_file
+                .as_ref()
+                .filter(|file| file.has_uri_scheme(DeepLink::SCHEME))
+            {
+                let deep_link = match DeepLink::parse(&link_file.uri()) {
+                    Ok(deep_link) => deep_link,
+                    Err(e) => {
+                        log::error!("Opening deep link failed, Err: {e:?}");
+                        return;
+                    }
+                };
+                // A new window opens the file in its first tab
+                let (appwindow, new_tab) = match self.obj().active_window() {
+                    Some(w) => (w.downcast::<RnAppWindow>().unwrap(), true),
+                    None => (self.new_appwindow_init_show(None), false),
+                };
+                glib::MainContext::default().spawn_local(clone!(@weak appwindow => async move {
+                    appwindow.open_deep_link(deep_link, new_tab).await;
+                }));
+                return;
+            }
             if let Some(appwindow) = self
                 .obj()
                 .active_window()
@@ -65,6 +92,33 @@ mod imp {
                 self.new_appwindow_init_show(input_file);
             }
         }
+
+        fn dbus_register(
+            &self,
+            connection: &gio::DBusConnection,
+            object_path: &str,
+        ) -> Result<(), glib::Error> {
+            self.parent_dbus_register(connection, object_path)?;
+
+            // Failing to register the service should not prevent the app from starting
+            match self.obj().register_dbus_service(connection, object_path) {
+                Ok(registration) => {
+                    self.dbus_registration.replace(Some(registration));
+                }
+                Err(e) => log::error!("registering D-Bus service failed, Err: {e:?}"),
+            }
+            Ok(())
+        }
+
+        fn dbus_unregister(&self, connection: &gio::DBusConnection, object_path: &str) {
+            if let Some(registration) = self.dbus_registration.take() {
+                if let Err(e) = connection.unregister_object(registration) {
+                    log::error!("unregistering D-Bus service failed, Err: {e:?}");
+                }
+            }
+
+            self.parent_dbus_unregister(connection, object_path);
+        }
     }
 
     impl GtkApplicationImpl for RnApp {}
@@ -81,7 +135,7 @@ mod imp {
         }
 
         /// Initializes and shows a new app window
-        fn new_appwindow_init_show(&self, input_file: Option<gio::File>) {
+        fn new_appwindow_init_show(&self, input_file: Option<gio::File>) -> RnAppWindow {
             let appwindow = RnAppWindow::new(self.obj().upcast_ref::<gtk4::Application>());
             appwindow.init();
             appwindow.present();
@@ -92,6 +146,7 @@ mod imp {
                     appwindow.open_file_w_dialogs(input_file, None, false).await;
                 }));
             }
+            appwindow
         }
 
         fn setup_gresources(&self) {
@@ -5,14 +5,21 @@ use gtk4::{
     gdk, gio, glib, glib::clone, prelude::*, PrintOperation, PrintOperationAction, Unit,
     UriLauncher, Window,
 };
-use p2d::bounding_volume::BoundingVolume;
-use rnote_compose::helpers::SplitOrder;
 use rnote_compose::penevents::ShortcutKey;
-use rnote_engine::document::Layout;
-use rnote_engine::engine::StrokeContent;
+use rnote_engine::document::{Layout, TouchDrawingPolicy};
+use rnote_engine::engine::export::PageRange;
+use rnote_engine::engine::{
+    DeepLink, HeatmapMode, PageFitMode, PasteMode, PrintJob, PrintTarget, StrokeContent,
+    ZoomFitTarget,
+};
+use rnote_engine::fileformats::rnoteformat::RnoteFragment;
 use rnote_engine::pens::PenStyle;
-use rnote_engine::{Camera, RnoteEngine, WidgetFlags};
+use rnote_engine::strokes::Stroke;
+use rnote_engine::utils::GdkRGBAHelpers;
+use rnote_engine::{Camera, WidgetFlags};
+use std::cell::RefCell;
 use std::path::PathBuf;
+use std::rc::Rc;
 use std::str::FromStr;
 use std::time::Instant;
 
@@ -64,6 +71,12 @@ impl RnAppWindow {
         let action_pen_sounds =
             gio::SimpleAction::new_stateful("pen-sounds", None, false.to_variant());
         self.add_action(&action_pen_sounds);
+        let action_drawing_aid_ruler =
+            gio::SimpleAction::new_stateful("drawing-aid-ruler", None, false.to_variant());
+        self.add_action(&action_drawing_aid_ruler);
+        let action_drawing_aid_compass =
+            gio::SimpleAction::new_stateful("drawing-aid-compass", None, false.to_variant());
+        self.add_action(&action_drawing_aid_compass);
         let action_format_borders =
             gio::SimpleAction::new_stateful("format-borders", None, true.to_variant());
         self.add_action(&action_format_borders);
@@ -77,12 +90,40 @@ impl RnAppWindow {
             String::from("infinite").to_variant(),
         );
         self.add_action(&action_doc_layout);
+        let action_doc_touch_drawing_policy = gio::SimpleAction::new_stateful(
+            "doc-touch-drawing-policy",
+            Some(&String::static_variant_type()),
+            String::from("follow-app").to_variant(),
+        );
+        self.add_action(&action_doc_touch_drawing_policy);
+        let action_paste_mode = gio::SimpleAction::new_stateful(
+            "paste-mode",
+            Some(&String::static_variant_type()),
+            String::from("viewport").to_variant(),
+        );
+        self.add_action(&action_paste_mode);
+        let action_heatmap_mode = gio::SimpleAction::new_stateful(
+            "heatmap-mode",
+            Some(&String::static_variant_type()),
+            String::from("off").to_variant(),
+        );
+        self.add_action(&action_heatmap_mode);
+        let action_page_fit_mode = gio::SimpleAction::new_stateful(
+            "page-fit-mode",
+            Some(&String::static_variant_type()),
+            String::from("actual-size").to_variant(),
+        );
+        self.add_action(&action_page_fit_mode);
         let action_pen_style = gio::SimpleAction::new_stateful(
             "pen-style",
             Some(&String::static_variant_type()),
             String::from("brush").to_variant(),
         );
         self.add_action(&action_pen_style);
+        let action_pen_preset_save = gio::SimpleAction::new("pen-preset-save", None);
+        self.add_action(&action_pen_preset_save);
+        let action_pen_preset_cycle = gio::SimpleAction::new("pen-preset-cycle", None);
+        self.add_action(&action_pen_preset_cycle);
         let action_undo_stroke = gio::SimpleAction::new("undo", None);
         self.add_action(&action_undo_stroke);
         let action_redo_stroke = gio::SimpleAction::new("redo", None);
@@ -91,6 +132,16 @@ impl RnAppWindow {
         self.add_action(&action_zoom_reset);
         let action_zoom_fit_width = gio::SimpleAction::new("zoom-fit-width", None);
         self.add_action(&action_zoom_fit_width);
+        let action_zoom_fit =
+            gio::SimpleAction::new("zoom-fit", Some(&String::static_variant_type()));
+        self.add_action(&action_zoom_fit);
+        let action_rotate_view_clockwise = gio::SimpleAction::new("rotate-view-clockwise", None);
+        self.add_action(&action_rotate_view_clockwise);
+        let action_rotate_view_counterclockwise =
+            gio::SimpleAction::new("rotate-view-counterclockwise", None);
+        self.add_action(&action_rotate_view_counterclockwise);
+        let action_rotate_view_reset = gio::SimpleAction::new("rotate-view-reset", None);
+        self.add_action(&action_rotate_view_reset);
         let action_zoomin = gio::SimpleAction::new("zoom-in", None);
         self.add_action(&action_zoomin);
         let action_zoomout = gio::SimpleAction::new("zoom-out", None);
@@ -103,10 +154,40 @@ impl RnAppWindow {
         self.add_action(&action_resize_to_fit_strokes);
         let action_return_origin_page = gio::SimpleAction::new("return-origin-page", None);
         self.add_action(&action_return_origin_page);
+        let action_copy_deep_link = gio::SimpleAction::new("copy-deep-link", None);
+        self.add_action(&action_copy_deep_link);
+        let action_reanchor_origin = gio::SimpleAction::new("reanchor-origin", None);
+        self.add_action(&action_reanchor_origin);
         let action_selection_trash = gio::SimpleAction::new("selection-trash", None);
         self.add_action(&action_selection_trash);
         let action_selection_duplicate = gio::SimpleAction::new("selection-duplicate", None);
         self.add_action(&action_selection_duplicate);
+        let action_selection_duplicate_repeat =
+            gio::SimpleAction::new("selection-duplicate-repeat", None);
+        self.add_action(&action_selection_duplicate_repeat);
+        let action_selection_duplicate_linked =
+            gio::SimpleAction::new("selection-duplicate-linked", None);
+        self.add_action(&action_selection_duplicate_linked);
+        let action_selection_unlink_instances =
+            gio::SimpleAction::new("selection-unlink-instances", None);
+        self.add_action(&action_selection_unlink_instances);
+        let action_selection_smooth =
+            gio::SimpleAction::new("selection-smooth", Some(&f64::static_variant_type()));
+        self.add_action(&action_selection_smooth);
+        let action_selection_to_fill_pattern =
+            gio::SimpleAction::new("selection-to-fill-pattern", None);
+        self.add_action(&action_selection_to_fill_pattern);
+        let action_selection_apply_fill_pattern =
+            gio::SimpleAction::new("selection-apply-fill-pattern", None);
+        self.add_action(&action_selection_apply_fill_pattern);
+        let action_selection_remove_fill_pattern =
+            gio::SimpleAction::new("selection-remove-fill-pattern", None);
+        self.add_action(&action_selection_remove_fill_pattern);
+        let action_erase_in_selection = gio::SimpleAction::new("erase-in-selection", None);
+        self.add_action(&action_erase_in_selection);
+        let action_erase_by_color =
+            gio::SimpleAction::new("erase-by-color", Some(&String::static_variant_type()));
+        self.add_action(&action_erase_by_color);
         let action_selection_select_all = gio::SimpleAction::new("selection-select-all", None);
         self.add_action(&action_selection_select_all);
         let action_selection_deselect_all = gio::SimpleAction::new("selection-deselect-all", None);
@@ -127,6 +208,8 @@ impl RnAppWindow {
         self.add_action(&action_print_doc);
         let action_import_file = gio::SimpleAction::new("import-file", None);
         self.add_action(&action_import_file);
+        let action_insert_screenshot = gio::SimpleAction::new("insert-screenshot", None);
+        self.add_action(&action_insert_screenshot);
         let action_export_doc = gio::SimpleAction::new("export-doc", None);
         self.add_action(&action_export_doc);
         let action_export_doc_pages = gio::SimpleAction::new("export-doc-pages", None);
@@ -287,6 +370,84 @@ impl RnAppWindow {
             }),
         );
 
+        // Per-document touch drawing policy
+        action_doc_touch_drawing_policy.connect_activate(
+            clone!(@weak self as appwindow => move |action_doc_touch_drawing_policy, target| {
+                let policy_str = target.unwrap().str().unwrap();
+                let policy = match TouchDrawingPolicy::from_str(policy_str) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        log::error!("doc-touch-drawing-policy action activated with invalid target, Err: {e:}");
+                        return;
+                    }
+                };
+                action_doc_touch_drawing_policy.set_state(policy_str.to_variant());
+
+                let canvas = appwindow.active_tab_wrapper().canvas();
+                let mut widget_flags = WidgetFlags::default();
+                if canvas.engine_ref().document.touch_drawing_policy != policy {
+                    canvas.engine_mut().document.touch_drawing_policy = policy;
+                    // The policy is saved with the document
+                    widget_flags.store_modified = true;
+                }
+                canvas.set_touch_drawing(policy.finger_draws(appwindow.touch_drawing()));
+                appwindow.handle_widget_flags(widget_flags, &canvas);
+            }),
+        );
+
+        // Paste mode
+        action_paste_mode.connect_activate(
+            clone!(@weak self as appwindow => move |action_paste_mode, target| {
+                let paste_mode_str = target.unwrap().str().unwrap();
+                let paste_mode = match PasteMode::from_str(paste_mode_str) {
+                    Ok(m) => m,
+                    Err(e) => {
+                        log::error!("paste-mode action activated with invalid target, Err: {e:}");
+                        return;
+                    }
+                };
+                action_paste_mode.set_state(paste_mode_str.to_variant());
+
+                appwindow.active_tab_wrapper().canvas().engine_mut().import_prefs.paste_mode = paste_mode;
+            }),
+        );
+
+        // Heatmap mode
+        action_heatmap_mode.connect_activate(
+            clone!(@weak self as appwindow => move |action_heatmap_mode, target| {
+                let heatmap_mode_str = target.unwrap().str().unwrap();
+                let heatmap_mode = match HeatmapMode::from_str(heatmap_mode_str) {
+                    Ok(m) => m,
+                    Err(e) => {
+                        log::error!("heatmap-mode action activated with invalid target, Err: {e:}");
+                        return;
+                    }
+                };
+                action_heatmap_mode.set_state(heatmap_mode_str.to_variant());
+
+                let canvas = appwindow.active_tab_wrapper().canvas();
+                canvas.engine_mut().heatmap_mode = heatmap_mode;
+                canvas.queue_draw();
+            }),
+        );
+
+        // Page fit mode
+        action_page_fit_mode.connect_activate(
+            clone!(@weak self as appwindow => move |action_page_fit_mode, target| {
+                let page_fit_mode_str = target.unwrap().str().unwrap();
+                let page_fit_mode = match PageFitMode::from_str(page_fit_mode_str) {
+                    Ok(m) => m,
+                    Err(e) => {
+                        log::error!("page-fit-mode action activated with invalid target, Err: {e:}");
+                        return;
+                    }
+                };
+                action_page_fit_mode.set_state(page_fit_mode_str.to_variant());
+
+                appwindow.active_tab_wrapper().canvas().engine_mut().import_prefs.page_fit_mode = page_fit_mode;
+            }),
+        );
+
         // Pen sounds
         action_pen_sounds.connect_change_state(
             clone!(@weak self as appwindow => move |action_pen_sounds, state_request| {
@@ -298,6 +459,32 @@ impl RnAppWindow {
             }),
         );
 
+        // Ruler drawing aid
+        action_drawing_aid_ruler.connect_change_state(
+            clone!(@weak self as appwindow => move |action_drawing_aid_ruler, state_request| {
+                let enabled = state_request.unwrap().get::<bool>().unwrap();
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let widget_flags = canvas.engine_mut().set_ruler_enabled(enabled);
+                appwindow.handle_widget_flags(widget_flags, &canvas);
+
+                action_drawing_aid_ruler.set_state(enabled.to_variant());
+            }),
+        );
+
+        // Compass drawing aid
+        action_drawing_aid_compass.connect_change_state(
+            clone!(@weak self as appwindow => move |action_drawing_aid_compass, state_request| {
+                let enabled = state_request.unwrap().get::<bool>().unwrap();
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let widget_flags = canvas.engine_mut().set_compass_enabled(enabled);
+                appwindow.handle_widget_flags(widget_flags, &canvas);
+
+                action_drawing_aid_compass.set_state(enabled.to_variant());
+            }),
+        );
+
         // Format borders
         action_format_borders.connect_change_state(
             clone!(@weak self as appwindow => move |action_format_borders, state_request| {
@@ -336,6 +523,32 @@ impl RnAppWindow {
             }),
         );
 
+        // Save the config of the current pen as preset
+        action_pen_preset_save.connect_activate(
+            clone!(@weak self as appwindow => move |_action_pen_preset_save, _| {
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let text = match canvas.engine_mut().save_pen_preset() {
+                    Some(i) => format!("{} {}", gettext("Saved Pen Preset"), i + 1),
+                    None => gettext("Saving a preset for this pen is not possible"),
+                };
+                appwindow.overlays().dispatch_toast_text(
+                    &text,
+                    crate::overlays::TEXT_TOAST_TIMEOUT_DEFAULT,
+                );
+            }),
+        );
+
+        // Apply the next saved preset of the current pen
+        action_pen_preset_cycle.connect_activate(
+            clone!(@weak self as appwindow => move |_action_pen_preset_cycle, _| {
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let widget_flags = canvas.engine_mut().cycle_pen_preset();
+                appwindow.handle_widget_flags(widget_flags, &canvas);
+            }),
+        );
+
         // Tab actions
         action_active_tab_move_left.connect_activate(
             clone!(@weak self as appwindow => move |_, _| {
@@ -430,6 +643,178 @@ impl RnAppWindow {
             }),
         );
 
+        // Duplicate Selection again with the offset of the last duplication
+        action_selection_duplicate_repeat.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_duplicate_repeat, _| {
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let mut widget_flags = WidgetFlags::default();
+                let new_selected = canvas.engine_mut().store.duplicate_selection_repeat();
+                canvas.engine_mut().store.update_geometry_for_strokes(&new_selected);
+                widget_flags.merge(canvas.engine_mut().current_pen_update_state());
+                widget_flags.merge(canvas.engine_mut().doc_resize_autoexpand());
+                widget_flags.merge(canvas.engine_mut().record(Instant::now()));
+                canvas.update_rendering_current_viewport();
+
+                appwindow.handle_widget_flags(widget_flags, &canvas);
+            }),
+        );
+
+        // Duplicate Selection as instances that are linked to the originals
+        action_selection_duplicate_linked.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_duplicate_linked, _| {
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let mut widget_flags = WidgetFlags::default();
+                let new_selected = canvas
+                    .engine_mut()
+                    .store
+                    .duplicate_selection_linked(Stroke::IMPORT_OFFSET_DEFAULT);
+                canvas.engine_mut().store.update_geometry_for_strokes(&new_selected);
+                widget_flags.merge(canvas.engine_mut().current_pen_update_state());
+                widget_flags.merge(canvas.engine_mut().doc_resize_autoexpand());
+                widget_flags.merge(canvas.engine_mut().record(Instant::now()));
+                canvas.update_rendering_current_viewport();
+
+                appwindow.handle_widget_flags(widget_flags, &canvas);
+            }),
+        );
+
+        // Unlink the selected instances and the instances of selected masters
+        action_selection_unlink_instances.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_unlink_instances, _| {
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let mut widget_flags = WidgetFlags::default();
+                let selected = canvas.engine_ref().store.selection_keys_as_rendered();
+                canvas.engine_mut().store.unlink_instances(&selected);
+                widget_flags.merge(canvas.engine_mut().record(Instant::now()));
+
+                appwindow.handle_widget_flags(widget_flags, &canvas);
+            }),
+        );
+
+        // Smooth and simplify the selected brush strokes with the strength given as target
+        action_selection_smooth.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_smooth, target| {
+                let Some(strength) = target.and_then(|target| target.get::<f64>()) else {
+                    log::error!("selection-smooth action activated with invalid target");
+                    return;
+                };
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let widget_flags = canvas.engine_mut().smooth_selection(strength);
+                canvas.update_rendering_current_viewport();
+
+                appwindow.handle_widget_flags(widget_flags, &canvas);
+            }),
+        );
+
+        // Save the selection as fill pattern in the document
+        action_selection_to_fill_pattern.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_to_fill_pattern, _| {
+                glib::MainContext::default().spawn_local(clone!(@weak appwindow => async move {
+                    let canvas = appwindow.active_tab_wrapper().canvas();
+                    let name = format!(
+                        "{} {}",
+                        gettext("Pattern"),
+                        canvas.engine_ref().doc_fill_patterns.len() + 1
+                    );
+                    let receiver = canvas.engine_ref().export_selection_as_fill_pattern(name);
+                    match receiver.await {
+                        Ok(Ok(Some(fill_pattern))) => {
+                            canvas.engine_mut().add_doc_fill_pattern(fill_pattern);
+                            canvas.set_unsaved_changes(true);
+                            appwindow.overlays().dispatch_toast_text(
+                                &gettext("Saved selection as fill pattern"),
+                                crate::overlays::TEXT_TOAST_TIMEOUT_DEFAULT,
+                            );
+                        }
+                        Ok(Ok(None)) => {}
+                        Ok(Err(e)) => {
+                            log::error!("exporting selection as fill pattern failed, Err: {e:?}");
+                            appwindow
+                                .overlays()
+                                .dispatch_toast_error(&gettext("Saving selection as fill pattern failed"));
+                        }
+                        Err(e) => {
+                            log::error!("awaiting exported fill pattern failed, Err: {e:?}");
+                        }
+                    }
+                }));
+            }),
+        );
+
+        // Fill the selected shapes with the most recently saved fill pattern
+        action_selection_apply_fill_pattern.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_apply_fill_pattern, _| {
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let Some(id) = canvas.engine_ref().doc_fill_patterns.last().map(|p| p.id.clone()) else {
+                    appwindow.overlays().dispatch_toast_text(
+                        &gettext("Save a selection as fill pattern first"),
+                        crate::overlays::TEXT_TOAST_TIMEOUT_DEFAULT,
+                    );
+                    return;
+                };
+                let widget_flags = canvas.engine_mut().set_selection_fill_pattern(Some(id));
+                canvas.update_rendering_current_viewport();
+
+                appwindow.handle_widget_flags(widget_flags, &canvas);
+            }),
+        );
+
+        // Remove the fill pattern of the selected shapes
+        action_selection_remove_fill_pattern.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_remove_fill_pattern, _| {
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let widget_flags = canvas.engine_mut().set_selection_fill_pattern(None);
+                canvas.update_rendering_current_viewport();
+
+                appwindow.handle_widget_flags(widget_flags, &canvas);
+            }),
+        );
+
+        // Erase all strokes inside the selection bounds
+        action_erase_in_selection.connect_activate(
+            clone!(@weak self as appwindow => move |_action_erase_in_selection, _| {
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let widget_flags = canvas.engine_mut().erase_in_selection_bounds();
+                canvas.update_rendering_current_viewport();
+
+                appwindow.handle_widget_flags(widget_flags, &canvas);
+            }),
+        );
+
+        // Erase all strokes with the current stroke color. The target is the pen style the strokes were drawn with, or "all"
+        action_erase_by_color.connect_activate(
+            clone!(@weak self as appwindow => move |_action_erase_by_color, target| {
+                let Some(pen_style_str) = target.and_then(|target| target.str()) else {
+                    log::error!("erase-by-color action activated with invalid target");
+                    return;
+                };
+                let pen_style = match pen_style_str {
+                    "all" => None,
+                    pen_style_str => match PenStyle::from_str(pen_style_str) {
+                        Ok(s) => Some(s),
+                        Err(e) => {
+                            log::error!("erase-by-color action activated with invalid target, Err: {e:}");
+                            return;
+                        }
+                    },
+                };
+                let color = appwindow.overlays().colorpicker().stroke_color().into_compose_color();
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let widget_flags = canvas.engine_mut().erase_by_color(color, pen_style);
+                canvas.update_rendering_current_viewport();
+
+                appwindow.handle_widget_flags(widget_flags, &canvas);
+            }),
+        );
+
         // select all strokes
         action_selection_select_all.connect_activate(
             clone!(@weak self as appwindow => move |_action_selection_select_all, _| {
@@ -516,6 +901,50 @@ impl RnAppWindow {
             appwindow.handle_widget_flags(widget_flags, &canvas)
         }));
 
+        // Zoom to fit the document, the current page or the selection
+        action_zoom_fit.connect_activate(clone!(@weak self as appwindow => move |_, target| {
+            let target_str = target.unwrap().str().unwrap();
+            let target = match ZoomFitTarget::from_str(target_str) {
+                Ok(t) => t,
+                Err(e) => {
+                    log::error!("zoom-fit action activated with invalid target, Err: {e:}");
+                    return;
+                }
+            };
+            let canvas = appwindow.active_tab_wrapper().canvas();
+
+            let widget_flags = canvas.engine_mut().zoom_fit(target, true);
+            appwindow.handle_widget_flags(widget_flags, &canvas)
+        }));
+
+        // Rotate the view clockwise
+        action_rotate_view_clockwise.connect_activate(
+            clone!(@weak self as appwindow => move |_,_| {
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let widget_flags = canvas.engine_mut().camera_rotate_by_step(true);
+                appwindow.handle_widget_flags(widget_flags, &canvas)
+            }),
+        );
+
+        // Rotate the view counterclockwise
+        action_rotate_view_counterclockwise.connect_activate(
+            clone!(@weak self as appwindow => move |_,_| {
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let widget_flags = canvas.engine_mut().camera_rotate_by_step(false);
+                appwindow.handle_widget_flags(widget_flags, &canvas)
+            }),
+        );
+
+        // Reset the view rotation
+        action_rotate_view_reset.connect_activate(clone!(@weak self as appwindow => move |_,_| {
+            let canvas = appwindow.active_tab_wrapper().canvas();
+
+            let widget_flags = canvas.engine_mut().camera_rotate_to(0.0);
+            appwindow.handle_widget_flags(widget_flags, &canvas)
+        }));
+
         // Zoom in
         action_zoomin.connect_activate(clone!(@weak self as appwindow => move |_,_| {
             let canvas = appwindow.active_tab_wrapper().canvas();
@@ -578,12 +1007,40 @@ impl RnAppWindow {
         action_return_origin_page.connect_activate(clone!(@weak self as appwindow => move |_,_| {
             let canvas = appwindow.active_tab_wrapper().canvas();
 
-            canvas.return_to_origin_page();
+            canvas.return_to_origin_page(true);
             let widget_flags = canvas.engine_mut().doc_resize_autoexpand();
             canvas.update_rendering_current_viewport();
             appwindow.handle_widget_flags(widget_flags, &canvas);
         }));
 
+        // Copy a link to the focused or selected stroke or the current page, for linking to it from other applications
+        action_copy_deep_link.connect_activate(clone!(@weak self as appwindow => move |_,_| {
+            let canvas = appwindow.active_tab_wrapper().canvas();
+
+            let Some(file_path) = canvas.output_file().and_then(|f| f.path()) else {
+                appwindow.overlays().dispatch_toast_text(
+                    &gettext("The document needs to be saved before it can be linked to"),
+                    crate::overlays::TEXT_TOAST_TIMEOUT_DEFAULT,
+                );
+                return;
+            };
+            let anchor = canvas.engine_ref().anchor_for_current_view();
+            appwindow.clipboard().set_text(&DeepLink::new(file_path, anchor).to_uri());
+            appwindow.overlays().dispatch_toast_text(
+                &gettext("Copied link to location"),
+                crate::overlays::TEXT_TOAST_TIMEOUT_DEFAULT,
+            );
+        }));
+
+        // Re-anchor the origin to the start of the content
+        action_reanchor_origin.connect_activate(clone!(@weak self as appwindow => move |_,_| {
+            let canvas = appwindow.active_tab_wrapper().canvas();
+
+            let widget_flags = canvas.engine_mut().doc_reanchor_origin();
+            canvas.update_rendering_current_viewport();
+            appwindow.handle_widget_flags(widget_flags, &canvas);
+        }));
+
         // New doc
         action_new_doc.connect_activate(clone!(@weak self as appwindow => move |_, _| {
             glib::MainContext::default().spawn_local(clone!(@weak appwindow => async move {
@@ -631,15 +1088,9 @@ impl RnAppWindow {
 
         // Print doc
         action_print_doc.connect_activate(clone!(@weak self as appwindow => move |_, _| {
-            // TODO: Expose these variables as options in the print dialog
-            let draw_background = true;
-            let draw_pattern = true;
-            let page_order = SplitOrder::default();
-            let margin = 0.0;
-
             let canvas = appwindow.active_tab_wrapper().canvas();
-            let pages_content = canvas.engine_ref().extract_pages_content(page_order);
-            let n_pages = pages_content.len();
+            // The sheets depend on the paper size, so the job is created when the print dialog was confirmed
+            let print_job = Rc::new(RefCell::new(Option::<PrintJob>::None));
 
             appwindow.overlays().progressbar_start_pulsing();
 
@@ -647,19 +1098,18 @@ impl RnAppWindow {
                 .unit(Unit::None)
                 .build();
 
-            print_op.connect_begin_print(clone!(@weak appwindow => move |print_op, _print_cx| {
-                print_op.set_n_pages(n_pages as i32);
+            print_op.connect_begin_print(clone!(@weak appwindow, @weak canvas, @strong print_job => move |print_op, print_cx| {
+                let job = canvas.engine_ref().print_job(print_target(print_cx));
+                print_op.set_n_pages(job.n_pages() as i32);
+                *print_job.borrow_mut() = Some(job);
             }));
 
-            print_op.connect_draw_page(clone!(@weak appwindow, @weak canvas => move |_print_op, print_cx, page_no| {
-                let page_content = &pages_content[page_no as usize];
-                let page_bounds = page_content.bounds.unwrap().loosened(margin);
-                let print_scale = (print_cx.width() / page_bounds.extents()[0]).min(print_cx.height() / page_bounds.extents()[1]);
-                let cairo_cx = print_cx.cairo_context();
-
-                cairo_cx.scale(print_scale, print_scale);
-                cairo_cx.translate(-page_bounds.mins[0], -page_bounds.mins[1]);
-                if let Err(e) = page_content.draw_to_cairo(&cairo_cx, draw_background, draw_pattern, margin, RnoteEngine::STROKE_EXPORT_IMAGE_SCALE) {
+            print_op.connect_draw_page(clone!(@weak appwindow, @strong print_job => move |_print_op, print_cx, page_no| {
+                let Some(print_job) = &*print_job.borrow() else {
+                    return;
+                };
+                let page_range = PageRange { first: page_no as usize, last: page_no as usize };
+                if let Err(e) = print_job.draw_pages_to_cairo(&print_cx.cairo_context(), page_range) {
                     log::error!("drawing page no: {page_no} while printing failed, Err: {e:?}");
                 }
             }));
@@ -685,6 +1135,25 @@ impl RnAppWindow {
             }));
         }));
 
+        // Insert screenshot
+        action_insert_screenshot.connect_activate(clone!(@weak self as appwindow => move |_,_| {
+            glib::MainContext::default().spawn_local(clone!(@weak appwindow => async move {
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let result = async {
+                    let Some(file) = crate::screenshot::capture_screenshot_w_portal().await? else {
+                        return Ok(());
+                    };
+                    let (bytes, _) = file.load_bytes_future().await?;
+                    canvas.load_in_bitmapimage_bytes_viewport_centered(bytes.to_vec()).await
+                }.await;
+                if let Err(e) = result {
+                    log::error!("inserting screenshot failed, Err: {e:?}");
+                    appwindow.overlays().dispatch_toast_error(&gettext("Inserting screenshot failed"));
+                }
+            }));
+        }));
+
         // Export document
         action_export_doc.connect_activate(clone!(@weak self as appwindow => move |_,_| {
             glib::MainContext::default().spawn_local(clone!(@weak appwindow => async move {
@@ -771,6 +1240,7 @@ impl RnAppWindow {
         // Clipboard paste
         action_clipboard_paste.connect_activate(clone!(@weak self as appwindow => move |_, _| {
             let canvas = appwindow.active_tab_wrapper().canvas();
+            let placement = appwindow.active_tab_wrapper().paste_placement();
             let content_formats = appwindow.clipboard().formats();
 
             // Order matters here, we want to go from specific -> generic, mostly because `text/plain` is contained in other text based formats
@@ -806,6 +1276,41 @@ impl RnAppWindow {
                         }
                     }
                 }));
+            } else if content_formats.contain_mime_type(RnoteFragment::MIME_TYPE) {
+                glib::MainContext::default().spawn_local(clone!(@weak canvas, @weak appwindow => async move {
+                    log::debug!("recognized clipboard content format: {}", RnoteFragment::MIME_TYPE);
+
+                    match appwindow.clipboard().read_future(&[RnoteFragment::MIME_TYPE], glib::PRIORITY_DEFAULT).await {
+                        Ok((input_stream, _)) => {
+                            let mut acc = Vec::new();
+                            loop {
+                                match input_stream.read_future(vec![0; CLIPBOARD_INPUT_STREAM_BUFSIZE], glib::PRIORITY_DEFAULT).await {
+                                    Ok((mut bytes, n)) => {
+                                        if n == 0 {
+                                            break;
+                                        }
+                                        bytes.truncate(n);
+                                        acc.append(&mut bytes);
+                                    }
+                                    Err(e) => {
+                                        log::error!("failed to read clipboard input stream, Err: {e:?}");
+                                        acc.clear();
+                                        break;
+                                    }
+                                }
+                            }
+
+                            if !acc.is_empty() {
+                                if let Err(e) = canvas.insert_rnote_fragment(acc, placement).await {
+                                    log::error!("failed to paste clipboard, Err: {e:?}");
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            log::error!("failed to paste clipboard as {}, read_future() failed with Err: {e:?}", RnoteFragment::MIME_TYPE);
+                        }
+                    };
+                }));
             } else if content_formats.contain_mime_type(StrokeContent::MIME_TYPE) {
                 glib::MainContext::default().spawn_local(clone!(@weak canvas, @weak appwindow => async move {
                     log::debug!("recognized clipboard content format: {}", StrokeContent::MIME_TYPE);
@@ -832,7 +1337,7 @@ impl RnAppWindow {
                             if !acc.is_empty() {
                                 match crate::utils::str_from_u8_nul_utf8(&acc) {
                                     Ok(json_string) => {
-                                        if let Err(e) = canvas.insert_stroke_content(json_string.to_string()).await {
+                                        if let Err(e) = canvas.insert_stroke_content(json_string.to_string(), placement).await {
                                             log::error!("failed to paste clipboard, Err: {e:?}");
                                         }
                                     }
@@ -949,6 +1454,12 @@ impl RnAppWindow {
         app.set_accels_for_action("win.print-doc", &["<Ctrl>p"]);
         app.set_accels_for_action("win.zoom-in", &["<Ctrl>plus"]);
         app.set_accels_for_action("win.zoom-out", &["<Ctrl>minus"]);
+        app.set_accels_for_action("win.zoom-fit::document", &["<Ctrl><Alt>0"]);
+        app.set_accels_for_action("win.zoom-fit::page", &["<Ctrl><Alt>1"]);
+        app.set_accels_for_action("win.zoom-fit::selection", &["<Ctrl><Alt>2"]);
+        app.set_accels_for_action("win.rotate-view-clockwise", &["<Ctrl>bracketright"]);
+        app.set_accels_for_action("win.rotate-view-counterclockwise", &["<Ctrl>bracketleft"]);
+        app.set_accels_for_action("win.rotate-view-reset", &["<Ctrl><Alt>r"]);
         app.set_accels_for_action("win.import-file", &["<Ctrl>i"]);
         app.set_accels_for_action("win.undo", &["<Ctrl>z"]);
         app.set_accels_for_action("win.redo", &["<Ctrl><Shift>z"]);
@@ -961,6 +1472,8 @@ impl RnAppWindow {
         app.set_accels_for_action("win.pen-style::eraser", &["<Ctrl>4"]);
         app.set_accels_for_action("win.pen-style::selector", &["<Ctrl>5"]);
         app.set_accels_for_action("win.pen-style::tools", &["<Ctrl>6"]);
+        app.set_accels_for_action("win.pen-preset-cycle", &["<Ctrl>period"]);
+        app.set_accels_for_action("win.pen-preset-save", &["<Ctrl><Alt>period"]);
 
         // shortcuts for devel builds
         if config::PROFILE.to_lowercase().as_str() == "devel" {
@@ -968,3 +1481,10 @@ impl RnAppWindow {
         }
     }
 }
+
+fn print_target(print_cx: &gtk4::PrintContext) -> PrintTarget {
+    PrintTarget {
+        paper_size: na::vector![print_cx.width(), print_cx.height()],
+        dpi: na::vector![print_cx.dpi_x(), print_cx.dpi_y()],
+    }
+}
@@ -626,6 +626,11 @@ impl RnAppWindow {
                 .shaper_page()
                 .constraint_menubutton()
                 .set_direction(ArrowType::Right);
+            obj.overlays()
+                .penssidebar()
+                .tools_page()
+                .fillconfig_menubutton()
+                .set_direction(ArrowType::Right);
             obj.overlays()
                 .penssidebar()
                 .shaper_page()
@@ -732,6 +737,11 @@ impl RnAppWindow {
                 .shaper_page()
                 .constraint_menubutton()
                 .set_direction(ArrowType::Left);
+            obj.overlays()
+                .penssidebar()
+                .tools_page()
+                .fillconfig_menubutton()
+                .set_direction(ArrowType::Left);
             obj.overlays()
                 .penssidebar()
                 .shaper_page()
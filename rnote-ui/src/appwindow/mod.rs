@@ -12,11 +12,13 @@ use adw::{prelude::*, subclass::prelude::*};
 use gettextrs::gettext;
 use gtk4::{gdk, gio, glib, glib::clone, Application, Box, Button, IconTheme};
 use rnote_compose::Color;
+use rnote_engine::engine::DeepLink;
 use rnote_engine::pens::pensconfig::brushconfig::BrushStyle;
 use rnote_engine::pens::pensconfig::shaperconfig::ShaperStyle;
+use rnote_engine::pens::pensconfig::toolsconfig::ToolStyle;
 use rnote_engine::pens::PenStyle;
 use rnote_engine::utils::GdkRGBAHelpers;
-use rnote_engine::{engine::EngineTask, WidgetFlags};
+use rnote_engine::{engine::EngineTask, EngineError, Progress, WidgetFlags};
 use std::path::Path;
 
 glib::wrapper! {
@@ -259,6 +261,9 @@ impl RnAppWindow {
         if let Some(enable_text_preprocessing) = widget_flags.enable_text_preprocessing {
             canvas.set_text_preprocessing(enable_text_preprocessing);
         }
+        if widget_flags.animate_camera {
+            canvas.drive_camera_animation();
+        }
     }
 
     /// Get the active (selected) tab page.
@@ -480,10 +485,16 @@ impl RnAppWindow {
                             appwindow.active_tab_wrapper()
                         };
                         let (bytes, _) = input_file.load_bytes_future().await?;
-                        wrapper
+                        let progress = Progress::new();
+                        let progress_toast = appwindow
+                            .overlays()
+                            .progressbar_track(&progress, &gettext("Opening file…"));
+                        let result = wrapper
                             .canvas()
-                            .load_in_rnote_bytes(bytes.to_vec(), input_file.path())
-                            .await?;
+                            .load_in_rnote_bytes(bytes.to_vec(), input_file.path(), progress)
+                            .await;
+                        progress_toast.dismiss();
+                        result?;
                         if rnote_file_new_tab {
                             appwindow.append_wrapper_new_tab(&wrapper);
                         }
@@ -526,6 +537,16 @@ impl RnAppWindow {
                     )
                     .await?
                 }
+                file_type @ (crate::utils::FileType::PlainTextFile
+                | crate::utils::FileType::MarkdownFile) => {
+                    let markdown = matches!(file_type, crate::utils::FileType::MarkdownFile);
+                    let canvas = appwindow.active_tab_wrapper().canvas();
+                    let (bytes, _) = input_file.load_bytes_future().await?;
+                    canvas
+                        .load_in_text_file_bytes(bytes.to_vec(), target_pos, markdown)
+                        .await?;
+                    true
+                }
                 crate::utils::FileType::Folder => {
                     if let Some(dir) = input_file.path() {
                         appwindow
@@ -550,6 +571,13 @@ impl RnAppWindow {
             Ok(false) => {
                 self.overlays().progressbar_abort();
             }
+            Err(e) if EngineError::is_cancelled(&e) => {
+                self.overlays().progressbar_abort();
+                self.overlays().dispatch_toast_text(
+                    &gettext("Opening file cancelled"),
+                    crate::overlays::TEXT_TOAST_TIMEOUT_DEFAULT,
+                );
+            }
             Err(e) => {
                 self.overlays().progressbar_abort();
                 log::error!("Opening file with dialogs failed, Err: {e:?}");
@@ -559,6 +587,37 @@ impl RnAppWindow {
         }
     }
 
+    /// Open the file of the deep link and move the view to its anchor.
+    ///
+    /// See [RnAppWindow::open_file_w_dialogs] for `rnote_file_new_tab`.
+    pub(crate) async fn open_deep_link(&self, deep_link: DeepLink, rnote_file_new_tab: bool) {
+        self.open_file_w_dialogs(
+            gio::File::for_path(&deep_link.file),
+            None,
+            rnote_file_new_tab,
+        )
+        .await;
+        let Some(anchor) = deep_link.anchor else {
+            return;
+        };
+        let canvas = self.active_tab_wrapper().canvas();
+        // Opening the file might have failed
+        if canvas.output_file().and_then(|f| f.path()).as_deref() != Some(deep_link.file.as_path())
+        {
+            return;
+        }
+        let res = canvas.engine_mut().jump_to_anchor(&anchor);
+        match res {
+            Ok(widget_flags) => self.handle_widget_flags(widget_flags, &canvas),
+            Err(e) => {
+                log::error!("Jumping to anchor of deep link failed, Err: {e:?}");
+                self.overlays().dispatch_toast_error(&gettext(
+                    "The linked location does not exist in the document anymore",
+                ));
+            }
+        }
+    }
+
     /// Refresh the UI from the engine state from the given tab page.
     pub(crate) fn refresh_ui_from_engine(&self, active_tab: &RnCanvasWrapper) {
         let canvas = active_tab.canvas();
@@ -566,7 +625,12 @@ impl RnAppWindow {
         // Avoids already borrowed
         let format = canvas.engine_ref().document.format;
         let doc_layout = canvas.engine_ref().document.layout;
+        let touch_drawing_policy = canvas.engine_ref().document.touch_drawing_policy;
+        let paste_mode = canvas.engine_ref().import_prefs.paste_mode;
+        let page_fit_mode = canvas.engine_ref().import_prefs.page_fit_mode;
+        let heatmap_mode = canvas.engine_ref().heatmap_mode;
         let pen_sounds = canvas.engine_ref().pen_sounds();
+        let drawing_aids = canvas.engine_ref().pens_config.drawing_aids;
         let pen_style = canvas.engine_ref().penholder.current_pen_style_w_override();
 
         // Undo / redo
@@ -582,11 +646,41 @@ impl RnAppWindow {
             "doc-layout",
             Some(&doc_layout.to_string().to_variant()),
         );
+        adw::prelude::ActionGroupExt::activate_action(
+            self,
+            "doc-touch-drawing-policy",
+            Some(&touch_drawing_policy.to_string().to_variant()),
+        );
+        adw::prelude::ActionGroupExt::activate_action(
+            self,
+            "paste-mode",
+            Some(&paste_mode.to_string().to_variant()),
+        );
+        adw::prelude::ActionGroupExt::activate_action(
+            self,
+            "page-fit-mode",
+            Some(&page_fit_mode.to_string().to_variant()),
+        );
+        adw::prelude::ActionGroupExt::activate_action(
+            self,
+            "heatmap-mode",
+            Some(&heatmap_mode.to_string().to_variant()),
+        );
         adw::prelude::ActionGroupExt::change_action_state(
             self,
             "pen-sounds",
             &pen_sounds.to_variant(),
         );
+        adw::prelude::ActionGroupExt::change_action_state(
+            self,
+            "drawing-aid-ruler",
+            &drawing_aids.ruler.enabled.to_variant(),
+        );
+        adw::prelude::ActionGroupExt::change_action_state(
+            self,
+            "drawing-aid-compass",
+            &drawing_aids.compass.enabled.to_variant(),
+        );
         adw::prelude::ActionGroupExt::change_action_state(
             self,
             "format-borders",
@@ -665,6 +759,30 @@ impl RnAppWindow {
                             .colorpicker()
                             .set_stroke_color(gdk::RGBA::from_compose_color(stroke_color));
                     }
+                    BrushStyle::Grain => {
+                        let stroke_color = canvas
+                            .engine_ref()
+                            .pens_config
+                            .brush_config
+                            .grain_options
+                            .stroke_color
+                            .unwrap_or(Color::TRANSPARENT);
+                        self.overlays()
+                            .colorpicker()
+                            .set_stroke_color(gdk::RGBA::from_compose_color(stroke_color));
+                    }
+                    BrushStyle::Airbrush => {
+                        let stroke_color = canvas
+                            .engine_ref()
+                            .pens_config
+                            .brush_config
+                            .airbrush_options
+                            .stroke_color
+                            .unwrap_or(Color::TRANSPARENT);
+                        self.overlays()
+                            .colorpicker()
+                            .set_stroke_color(gdk::RGBA::from_compose_color(stroke_color));
+                    }
                 }
             }
             PenStyle::Shaper => {
@@ -759,6 +877,22 @@ impl RnAppWindow {
                     .penssidebar()
                     .sidebar_stack()
                     .set_visible_child_name("tools_page");
+
+                // Show the color that was picked with the eyedropper
+                if canvas.engine_ref().pens_config.tools_config.style == ToolStyle::Eyedropper {
+                    let stroke_color = canvas
+                        .engine_ref()
+                        .pens_config
+                        .typewriter_config
+                        .text_style
+                        .color;
+                    self.overlays()
+                        .colorpicker()
+                        .set_stroke_color(gdk::RGBA::from_compose_color(stroke_color));
+                }
+            }
+            PenStyle::Plugin => {
+                // Pen plugins don't have a toggle and a page in the pens sidebar
             }
         }
 
@@ -4,8 +4,11 @@ use futures::channel::oneshot;
 use gtk4::{gio, prelude::*};
 use rnote_compose::helpers::Vector2Helpers;
 use rnote_engine::engine::export::{DocExportPrefs, DocPagesExportPrefs, SelectionExportPrefs};
-use rnote_engine::engine::{EngineSnapshot, StrokeContent};
+use rnote_engine::engine::{EngineSnapshot, PastePlacement, StrokeContent};
+use rnote_engine::fileformats::rnoteformat::RnoteFragment;
+use rnote_engine::fileformats::FileFormatLoader;
 use rnote_engine::strokes::Stroke;
+use rnote_engine::Progress;
 use std::ops::Range;
 use std::path::Path;
 
@@ -14,11 +17,13 @@ impl RnCanvas {
         &self,
         bytes: Vec<u8>,
         file_path: Option<P>,
+        progress: Progress,
     ) -> anyhow::Result<()>
     where
         P: AsRef<Path>,
     {
-        let engine_snapshot = EngineSnapshot::load_from_rnote_bytes(bytes).await?;
+        let engine_snapshot = EngineSnapshot::load_from_rnote_bytes(bytes, progress).await?;
+        let view_state = engine_snapshot.view_state;
 
         let mut widget_flags = self.engine_mut().load_snapshot(engine_snapshot);
 
@@ -30,7 +35,12 @@ impl RnCanvas {
 
         self.set_unsaved_changes(false);
         self.set_empty(false);
-        self.return_to_origin_page();
+        // Put the view back where the document was left off
+        if let Some(view_state) = view_state {
+            widget_flags.merge(self.engine_mut().restore_view_state(&view_state));
+        } else {
+            self.return_to_origin_page(false);
+        }
         self.background_regenerate_pattern();
         widget_flags.merge(self.engine_mut().doc_resize_autoexpand());
         self.update_rendering_current_viewport();
@@ -45,7 +55,7 @@ impl RnCanvas {
         if let Some(output_file) = self.output_file() {
             let (bytes, _) = output_file.load_bytes_future().await?;
 
-            self.load_in_rnote_bytes(bytes.to_vec(), output_file.path())
+            self.load_in_rnote_bytes(bytes.to_vec(), output_file.path(), Progress::default())
                 .await?;
         }
 
@@ -71,16 +81,14 @@ impl RnCanvas {
                 ])
         });
 
-        // we need the split the import operation between generate_vectorimage_from_bytes() which returns a receiver and import_generated_strokes(),
-        // to avoid borrowing the entire engine refcell while awaiting the stroke
-        let vectorimage_receiver = self
+        // we need the split the import operation between generate_svg_strokes_from_bytes() which returns a receiver and import_generated_strokes(),
+        // to avoid borrowing the entire engine refcell while awaiting the strokes
+        let svg_strokes_receiver = self
             .engine_mut()
-            .generate_vectorimage_from_bytes(pos, bytes);
-        let vectorimage = vectorimage_receiver.await??;
+            .generate_svg_strokes_from_bytes(pos, bytes);
+        let svg_strokes = svg_strokes_receiver.await??;
 
-        let widget_flags = self
-            .engine_mut()
-            .import_generated_strokes(vec![(Stroke::VectorImage(vectorimage), None)]);
+        let widget_flags = self.engine_mut().import_generated_strokes(svg_strokes);
 
         self.emit_handle_widget_flags(widget_flags);
         Ok(())
@@ -119,6 +127,131 @@ impl RnCanvas {
         Ok(())
     }
 
+    /// Loads in the bytes of a bitmap image centered in the current viewport, e.g. a captured screenshot.
+    pub(crate) async fn load_in_bitmapimage_bytes_viewport_centered(
+        &self,
+        bytes: Vec<u8>,
+    ) -> anyhow::Result<()> {
+        let bitmapimage_receiver = self
+            .engine_mut()
+            .generate_bitmapimage_from_bytes_viewport_centered(bytes);
+        let bitmapimage = bitmapimage_receiver.await??;
+
+        let widget_flags = self
+            .engine_mut()
+            .import_generated_strokes(vec![(Stroke::BitmapImage(bitmapimage), None)]);
+
+        self.emit_handle_widget_flags(widget_flags);
+        Ok(())
+    }
+
+    /// Loads in the bytes of a plain text or Markdown file as text strokes.
+    ///
+    /// Target position is in the coordinate space of the doc.
+    pub(crate) async fn load_in_text_file_bytes(
+        &self,
+        bytes: Vec<u8>,
+        target_pos: Option<na::Vector2<f64>>,
+        markdown: bool,
+    ) -> anyhow::Result<()> {
+        let pos = target_pos.unwrap_or_else(|| {
+            self.engine_ref()
+                .camera
+                .transform()
+                .inverse()
+                .transform_point(&na::Point2::from(Stroke::IMPORT_OFFSET_DEFAULT))
+                .coords
+                .maxs(&na::vector![
+                    self.engine_ref().document.x,
+                    self.engine_ref().document.y
+                ])
+        });
+
+        let textstrokes_receiver = self
+            .engine_mut()
+            .generate_textstrokes_from_bytes(pos, bytes, markdown);
+        let textstrokes = textstrokes_receiver.await??;
+
+        let widget_flags = self.engine_mut().import_generated_strokes(textstrokes);
+
+        self.emit_handle_widget_flags(widget_flags);
+        Ok(())
+    }
+
+    /// Loads in multiple image and Pdf files and places them next to each other, starting at the target position.
+    ///
+    /// Every image and every Pdf page is placed as a separate item. Unsupported files are skipped.
+    pub(crate) async fn load_in_multiple_files(
+        &self,
+        files: Vec<gio::File>,
+        // In the coordinate space of the doc
+        target_pos: Option<na::Vector2<f64>>,
+    ) -> anyhow::Result<()> {
+        let pos = target_pos.unwrap_or_else(|| {
+            self.engine_ref()
+                .camera
+                .transform()
+                .inverse()
+                .transform_point(&na::Point2::from(Stroke::IMPORT_OFFSET_DEFAULT))
+                .coords
+                .maxs(&na::vector![
+                    self.engine_ref().document.x,
+                    self.engine_ref().document.y
+                ])
+        });
+
+        let mut items = vec![];
+        for file in files {
+            match crate::utils::FileType::lookup_file_type(&file) {
+                crate::utils::FileType::VectorImageFile => {
+                    let (bytes, _) = file.load_bytes_future().await?;
+                    let svg_strokes_receiver = self
+                        .engine_mut()
+                        .generate_svg_strokes_from_bytes(pos, bytes.to_vec());
+                    items.push(svg_strokes_receiver.await??);
+                }
+                crate::utils::FileType::BitmapImageFile => {
+                    let (bytes, _) = file.load_bytes_future().await?;
+                    let bitmapimage_receiver = self
+                        .engine_mut()
+                        .generate_bitmapimage_from_bytes(pos, bytes.to_vec());
+                    items.push(vec![(
+                        Stroke::BitmapImage(bitmapimage_receiver.await??),
+                        None,
+                    )]);
+                }
+                crate::utils::FileType::PdfFile => {
+                    let (bytes, _) = file.load_bytes_future().await?;
+                    let pdf_pages_receiver = self.engine_mut().generate_pdf_pages_from_bytes(
+                        bytes.to_vec(),
+                        pos,
+                        None,
+                        Progress::default(),
+                    );
+                    items.extend(
+                        pdf_pages_receiver
+                            .await??
+                            .into_iter()
+                            .map(|page| vec![page]),
+                    );
+                }
+                _ => {
+                    log::warn!(
+                        "skipping file {:?} while loading in multiple files, unsupported file type",
+                        file.path()
+                    );
+                }
+            }
+        }
+
+        let widget_flags = self
+            .engine_mut()
+            .import_generated_strokes_w_placement(items, pos);
+
+        self.emit_handle_widget_flags(widget_flags);
+        Ok(())
+    }
+
     pub(crate) async fn load_in_xopp_bytes(&self, bytes: Vec<u8>) -> anyhow::Result<()> {
         let xopp_import_prefs = self.engine_mut().import_prefs.xopp_import_prefs;
 
@@ -130,7 +263,7 @@ impl RnCanvas {
         self.set_output_file(None);
         self.set_unsaved_changes(true);
         self.set_empty(false);
-        self.return_to_origin_page();
+        self.return_to_origin_page(false);
         self.background_regenerate_pattern();
         widget_flags.merge(self.engine_mut().doc_resize_autoexpand());
         self.update_rendering_current_viewport();
@@ -147,6 +280,7 @@ impl RnCanvas {
         bytes: Vec<u8>,
         target_pos: Option<na::Vector2<f64>>,
         page_range: Option<Range<u32>>,
+        progress: Progress,
     ) -> anyhow::Result<()> {
         let pos = target_pos.unwrap_or_else(|| {
             self.engine_ref()
@@ -163,7 +297,7 @@ impl RnCanvas {
 
         let strokes_receiver = self
             .engine_mut()
-            .generate_pdf_pages_from_bytes(bytes, pos, page_range);
+            .generate_pdf_pages_from_bytes(bytes, pos, page_range, progress);
         let strokes = strokes_receiver.await??;
 
         let widget_flags = self.engine_mut().import_generated_strokes(strokes);
@@ -198,7 +332,11 @@ impl RnCanvas {
     }
 
     /// Deserializes the stroke content and inserts it into the engine. The data is usually coming from the clipboard, drop source, etc.
-    pub(crate) async fn insert_stroke_content(&self, json_string: String) -> anyhow::Result<()> {
+    pub(crate) async fn insert_stroke_content(
+        &self,
+        json_string: String,
+        placement: PastePlacement,
+    ) -> anyhow::Result<()> {
         let (oneshot_sender, oneshot_receiver) =
             oneshot::channel::<anyhow::Result<StrokeContent>>();
 
@@ -211,19 +349,32 @@ impl RnCanvas {
             }
         });
         let content = oneshot_receiver.await??;
-        let pos = self
-            .engine_ref()
-            .camera
-            .transform()
-            .inverse()
-            .transform_point(&na::Point2::from(Stroke::IMPORT_OFFSET_DEFAULT))
-            .coords
-            .maxs(&na::vector![
-                self.engine_ref().document.x,
-                self.engine_ref().document.y
-            ]);
-
-        let widget_flags = self.engine_mut().insert_stroke_content(content, pos);
+        let widget_flags = self.engine_mut().insert_stroke_content(content, placement);
+
+        self.emit_handle_widget_flags(widget_flags);
+        Ok(())
+    }
+
+    /// Loads the stroke content from the bytes of a rnote fragment and inserts it into the engine.
+    /// The data is usually coming from the clipboard of another window.
+    pub(crate) async fn insert_rnote_fragment(
+        &self,
+        bytes: Vec<u8>,
+        placement: PastePlacement,
+    ) -> anyhow::Result<()> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<StrokeContent>>();
+
+        rayon::spawn(move || {
+            let result = || -> Result<StrokeContent, anyhow::Error> {
+                Ok(RnoteFragment::load_from_bytes(&bytes)?.stroke_content)
+            };
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in insert_rnote_fragment() failed. Receiver already dropped");
+            }
+        });
+        let content = oneshot_receiver.await??;
+        let widget_flags = self.engine_mut().insert_stroke_content(content, placement);
 
         self.emit_handle_widget_flags(widget_flags);
         Ok(())
@@ -246,21 +397,11 @@ impl RnCanvas {
             )
         })?;
 
-        let basename = file.basename().ok_or_else(|| {
-            anyhow::anyhow!(
-                "save_document_to_file() failed, could not retrieve basename for file: {file:?}"
-            )
-        })?;
-
         self.set_save_in_progress(true);
 
-        let rnote_bytes_receiver = self
-            .engine_ref()
-            .save_as_rnote_bytes(basename.to_string_lossy().to_string());
-
         let mut skip_set_output_file = false;
         if let Some(current_file_path) = self.output_file().and_then(|f| f.path()) {
-            if same_file::is_same_file(current_file_path, file_path).unwrap_or(false) {
+            if same_file::is_same_file(current_file_path, &file_path).unwrap_or(false) {
                 skip_set_output_file = true;
             }
         }
@@ -274,10 +415,9 @@ impl RnCanvas {
         self.dismiss_output_file_modified_toast();
         self.set_output_file_expect_write(true);
 
-        let res = async move {
-            crate::utils::create_replace_file_future(rnote_bytes_receiver.await??, file).await
-        }
-        .await;
+        // The engine writes the file atomically and rotates the backups
+        let save_receiver = self.engine_ref().save_as_rnote_file(file_path);
+        let res = async move { save_receiver.await? }.await;
 
         if let Err(e) = res {
             self.set_save_in_progress(false);
@@ -299,8 +439,27 @@ impl RnCanvas {
         file: &gio::File,
         title: String,
         export_prefs_override: Option<DocExportPrefs>,
+        progress: Progress,
     ) -> anyhow::Result<()> {
-        let export_bytes = self.engine_ref().export_doc(title, export_prefs_override);
+        let export_bytes = self
+            .engine_ref()
+            .export_doc(title, export_prefs_override, progress);
+
+        crate::utils::create_replace_file_future(export_bytes.await??, file).await?;
+
+        Ok(())
+    }
+
+    /// Exports the document with the custom exporter registered for the file extension.
+    pub(crate) async fn export_doc_w_custom_exporter(
+        &self,
+        file: &gio::File,
+        title: String,
+        file_ext: &str,
+    ) -> anyhow::Result<()> {
+        let export_bytes = self
+            .engine_ref()
+            .export_doc_w_custom_exporter(file_ext, title, None);
 
         crate::utils::create_replace_file_future(export_bytes.await??, file).await?;
 
@@ -1,6 +1,6 @@
 // Imports
 use super::RnCanvas;
-use gtk4::{gdk, prelude::*, Inhibit, Native};
+use gtk4::{gdk, pango, prelude::*, IMMulticontext, Inhibit, Native};
 use rnote_compose::penevents::ShortcutKey;
 use rnote_compose::penevents::{KeyboardKey, PenState};
 use rnote_compose::penevents::{ModifierKey, PenEvent};
@@ -82,7 +82,11 @@ pub(crate) fn handle_pointer_controller_event(
                 }
             }
 
-            let shortcut_key = retrieve_button_shortcut_key(gdk_button, is_stylus);
+            let shortcut_key = retrieve_button_shortcut_key(
+                gdk_button,
+                is_stylus,
+                retrieve_pen_mode(event) == Some(PenMode::Eraser),
+            );
 
             if let Some(shortcut_key) = shortcut_key {
                 widget_flags.merge(
@@ -250,6 +254,28 @@ pub(crate) fn handle_imcontext_text_commit(canvas: &RnCanvas, text: &str) {
     canvas.emit_handle_widget_flags(widget_flags);
 }
 
+pub(crate) fn handle_imcontext_preedit_changed(canvas: &RnCanvas, cx: &IMMulticontext) {
+    let (text, attrs, cursor_pos) = cx.preedit_string();
+    // The cursor position is reported in chars, the engine expects byte indices
+    let cursor = text
+        .char_indices()
+        .nth(cursor_pos.max(0) as usize)
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+    // Input methods usually highlight the currently converted segment with a background
+    let emphasized_ranges = attrs
+        .attributes()
+        .into_iter()
+        .filter(|attr| attr.type_() == pango::AttrType::Background)
+        .map(|attr| attr.start_index() as usize..(attr.end_index() as usize).min(text.len()))
+        .collect();
+    let widget_flags =
+        canvas
+            .engine_mut()
+            .typewriter_set_preedit(text.to_string(), cursor, emphasized_ranges);
+    canvas.emit_handle_widget_flags(widget_flags);
+}
+
 #[allow(unused)]
 fn debug_gdk_event(event: &gdk::Event) {
     let pos = event
@@ -379,8 +405,10 @@ fn retrieve_pointer_elements(
 pub(crate) fn retrieve_button_shortcut_key(
     gdk_button: u32,
     is_stylus: bool,
+    is_eraser_end: bool,
 ) -> Option<ShortcutKey> {
     match (is_stylus, gdk_button) {
+        (true, gdk::BUTTON_PRIMARY) if is_eraser_end => Some(ShortcutKey::StylusEraserEnd),
         (_, gdk::BUTTON_PRIMARY) => None,
         (false, gdk::BUTTON_SECONDARY) => Some(ShortcutKey::MouseSecondaryButton),
         (true, gdk::BUTTON_SECONDARY) => Some(ShortcutKey::StylusPrimaryButton),
@@ -23,6 +23,7 @@ use rnote_engine::utils::GrapheneRectHelpers;
 use rnote_engine::Document;
 use rnote_engine::{RnoteEngine, WidgetFlags};
 use std::cell::{Cell, Ref, RefCell, RefMut};
+use std::time::Instant;
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, glib::Boxed)]
 #[boxed_type(name = "WidgetFlagsBoxed")]
@@ -69,6 +70,7 @@ mod imp {
 
         pub(crate) engine: RefCell<RnoteEngine>,
         pub(crate) engine_task_handler_handle: RefCell<Option<glib::JoinHandle<()>>>,
+        pub(crate) camera_animation_tick_id: RefCell<Option<gtk4::TickCallbackId>>,
 
         pub(crate) output_file: RefCell<Option<gio::File>>,
         pub(crate) output_file_monitor: RefCell<Option<gio::FileMonitor>>,
@@ -102,8 +104,12 @@ mod imp {
                 .actions(gdk::DragAction::COPY)
                 .build();
 
-            // the order here is important: first files, then text
-            drop_target.set_types(&[gio::File::static_type(), glib::types::Type::STRING]);
+            // the order here is important: first file lists, then files, then text
+            drop_target.set_types(&[
+                gdk::FileList::static_type(),
+                gio::File::static_type(),
+                glib::types::Type::STRING,
+            ]);
 
             let regular_cursor_icon_name = String::from("cursor-dot-medium");
             let regular_cursor = gdk::Cursor::from_texture(
@@ -161,6 +167,7 @@ mod imp {
 
                 engine: RefCell::new(engine),
                 engine_task_handler_handle: RefCell::new(None),
+                camera_animation_tick_id: RefCell::new(None),
 
                 output_file: RefCell::new(None),
                 output_file_monitor: RefCell::new(None),
@@ -464,6 +471,13 @@ mod imp {
                 }),
             );
 
+            // The preedit text is displayed inline while composing with the IM context
+            self.key_controller_im_context.connect_preedit_changed(
+                clone!(@weak obj as canvas => move |cx| {
+                    super::input::handle_imcontext_preedit_changed(&canvas, cx);
+                }),
+            );
+
             // Key controller
             self.key_controller.connect_key_pressed(clone!(@weak obj as canvas => @default-return Inhibit(false), move |_, key, _raw, modifier| {
                 super::input::handle_key_controller_key_pressed(&canvas, key, modifier)
@@ -959,9 +973,14 @@ impl RnCanvas {
             }),
         );
 
-        // one per-appwindow property for touch-drawing
+        // one per-appwindow property for touch-drawing, which the per-document policy can override
         let appwindow_touch_drawing = appwindow
             .bind_property("touch-drawing", self, "touch-drawing")
+            .transform_to(
+                clone!(@weak self as canvas => @default-return None, move |_, touch_drawing: bool| {
+                    Some(canvas.engine_ref().document.touch_drawing_policy.finger_draws(touch_drawing))
+                }),
+            )
             .sync_create()
             .build();
 
@@ -997,7 +1016,28 @@ impl RnCanvas {
                     na::point![x,y]).coords;
                 let mut accept_drop = false;
 
-                if value.is::<gio::File>() {
+                if value.is::<gdk::FileList>() {
+                    let files = value.get::<gdk::FileList>().map(|l| l.files()).unwrap_or_default();
+                    if files.len() == 1 {
+                        let file = files.into_iter().next().unwrap();
+                        glib::MainContext::default().spawn_local(clone!(@weak appwindow => async move {
+                            appwindow.open_file_w_dialogs(file, Some(pos), true).await;
+                        }));
+                        accept_drop = true;
+                    } else if !files.is_empty() {
+                        glib::MainContext::default().spawn_local(clone!(@weak canvas, @weak appwindow => async move {
+                            appwindow.overlays().progressbar_start_pulsing();
+                            if let Err(e) = canvas.load_in_multiple_files(files, Some(pos)).await {
+                                log::error!("failed to insert dropped in files, Err: {e:?}");
+                                appwindow.overlays().dispatch_toast_error(&gettext("Inserting files failed"));
+                                appwindow.overlays().progressbar_abort();
+                            } else {
+                                appwindow.overlays().progressbar_finish();
+                            }
+                        }));
+                        accept_drop = true;
+                    }
+                } else if value.is::<gio::File>() {
                     // In some scenarios, get() can fail with `UnexpectedNone` even though is() returned true, e.g. when dealing with trashed files.
                     match value.get::<gio::File>() {
                         Ok(file) => {
@@ -1191,7 +1231,35 @@ impl RnCanvas {
     /// Centering the view to the origin page
     ///
     /// engine rendering then needs to be updated.
-    pub(crate) fn return_to_origin_page(&self) {
+    /// Drive the camera animation of the engine with the frame clock, until it has finished.
+    pub(crate) fn drive_camera_animation(&self) {
+        if self.imp().camera_animation_tick_id.borrow().is_some() {
+            return;
+        }
+        let tick_id = self.add_tick_callback(|canvas, _frame_clock| {
+            let (widget_flags, running) = canvas.engine_mut().camera_animation_tick(Instant::now());
+            canvas.emit_handle_widget_flags(widget_flags);
+            if running {
+                glib::source::Continue(true)
+            } else {
+                canvas.imp().camera_animation_tick_id.take();
+                glib::source::Continue(false)
+            }
+        });
+        self.imp().camera_animation_tick_id.replace(Some(tick_id));
+    }
+
+    /// Stop a running camera animation, e.g. when the user starts to move the camera.
+    pub(crate) fn cancel_camera_animation(&self) {
+        if let Some(tick_id) = self.imp().camera_animation_tick_id.take() {
+            tick_id.remove();
+        }
+        let widget_flags = self.engine_mut().camera_cancel_animation();
+        self.emit_handle_widget_flags(widget_flags);
+    }
+
+    /// Move the view to the origin page, animated when `animate` is true.
+    pub(crate) fn return_to_origin_page(&self, animate: bool) {
         let zoom = self.engine_ref().camera.zoom();
         let Some(parent) = self.parent() else {
             log::debug!("self.parent() is None in `return_to_origin_page()");
@@ -1213,9 +1281,16 @@ impl RnCanvas {
                 ]
             };
 
-        let mut widget_flags = self.engine_mut().camera_set_offset(new_offset);
-        widget_flags.merge(self.engine_mut().doc_expand_autoexpand());
-        self.emit_handle_widget_flags(widget_flags);
+        if animate {
+            let size = self.engine_ref().camera.size();
+            let new_center = (new_offset + size * 0.5) / zoom;
+            let widget_flags = self.engine_mut().camera_animate_to(zoom, new_center, true);
+            self.emit_handle_widget_flags(widget_flags);
+        } else {
+            let mut widget_flags = self.engine_mut().camera_set_offset(new_offset);
+            widget_flags.merge(self.engine_mut().doc_expand_autoexpand());
+            self.emit_handle_widget_flags(widget_flags);
+        }
     }
 
     /// Updates the rendering of the background and strokes that are flagged for rerendering for the current viewport.
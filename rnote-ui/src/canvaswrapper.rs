@@ -2,11 +2,13 @@
 use crate::{RnAppWindow, RnCanvas};
 use gtk4::{
     gdk, glib, glib::clone, prelude::*, subclass::prelude::*, CompositeTemplate, CornerType,
-    EventControllerMotion, EventControllerScroll, EventControllerScrollFlags, EventSequenceState,
-    GestureDrag, GestureLongPress, GestureZoom, Inhibit, PropagationPhase, ScrolledWindow, Widget,
+    EventControllerLegacy, EventControllerMotion, EventControllerScroll,
+    EventControllerScrollFlags, EventSequenceState, GestureDrag, GestureLongPress, GestureZoom,
+    Inhibit, PropagationPhase, ScrolledWindow, Widget,
 };
 use once_cell::sync::Lazy;
 use rnote_compose::penevents::ShortcutKey;
+use rnote_engine::engine::{PasteMode, PastePlacement, TouchPhase};
 use rnote_engine::Camera;
 use std::cell::{Cell, RefCell};
 use std::rc::Rc;
@@ -37,6 +39,7 @@ mod imp {
         pub(crate) canvas_alt_drag_gesture: GestureDrag,
         pub(crate) canvas_alt_shift_drag_gesture: GestureDrag,
         pub(crate) touch_two_finger_long_press_gesture: GestureLongPress,
+        pub(crate) touch_gestures_controller: EventControllerLegacy,
 
         #[template_child]
         pub(crate) scroller: TemplateChild<ScrolledWindow>,
@@ -103,6 +106,12 @@ mod imp {
                 .propagation_phase(PropagationPhase::Capture)
                 .build();
 
+            // Only observes touch events to recognize multi-finger gestures, never claims them.
+            let touch_gestures_controller = EventControllerLegacy::builder()
+                .name("touch_gestures_controller")
+                .propagation_phase(PropagationPhase::Capture)
+                .build();
+
             Self {
                 show_scrollbars: Cell::new(false),
                 block_pinch_zoom: Cell::new(false),
@@ -123,6 +132,7 @@ mod imp {
                 canvas_alt_drag_gesture,
                 canvas_alt_shift_drag_gesture,
                 touch_two_finger_long_press_gesture,
+                touch_gestures_controller,
 
                 scroller: TemplateChild::<ScrolledWindow>::default(),
                 canvas: TemplateChild::<RnCanvas>::default(),
@@ -167,6 +177,8 @@ mod imp {
                 .add_controller(self.canvas_alt_shift_drag_gesture.clone());
             self.scroller
                 .add_controller(self.touch_two_finger_long_press_gesture.clone());
+            self.scroller
+                .add_controller(self.touch_gestures_controller.clone());
 
             // group
             self.touch_two_finger_long_press_gesture
@@ -302,6 +314,7 @@ mod imp {
                     clone!(@weak obj as canvaswrapper => @default-return Inhibit(false), move |controller, _, dy| {
                     if controller.current_event_state() == gdk::ModifierType::CONTROL_MASK {
                         let canvas = canvaswrapper.canvas();
+                        canvas.cancel_camera_animation();
                         let old_zoom = canvas.engine_ref().camera.total_zoom();
                         let new_zoom = old_zoom * (1.0 - dy * RnCanvas::ZOOM_SCROLL_STEP);
 
@@ -313,6 +326,7 @@ mod imp {
                                     let p = canvaswrapper.translate_coordinates(&canvas, p[0], p[1]).unwrap();
                                     na::vector![p.0, p.1]
                                 })
+                                .map(|p| canvas.engine_ref().camera.unrotate_surface_pos(p))
                                 .unwrap_or_else(|| camera_size * 0.5);
                             let new_camera_offset = (((camera_offset + screen_offset) / old_zoom) * new_zoom) - screen_offset;
 
@@ -338,6 +352,7 @@ mod imp {
                     clone!(@strong touch_drag_start, @weak obj as canvaswrapper => move |_, _, _| {
                         // We don't claim the sequence, because we we want to allow touch zooming.
                         // When the zoom gesture is recognized, it claims it and denies this touch drag gesture.
+                        canvaswrapper.canvas().cancel_camera_animation();
 
                         touch_drag_start.set(na::vector![
                             canvaswrapper.canvas().hadjustment().unwrap().value(),
@@ -348,7 +363,7 @@ mod imp {
                 self.canvas_drag_gesture.connect_drag_update(
                     clone!(@strong touch_drag_start, @weak obj as canvaswrapper => move |_, x, y| {
                         let canvas = canvaswrapper.canvas();
-                        let new_offset = touch_drag_start.get() - na::vector![x,y];
+                        let new_offset = touch_drag_start.get() - canvas.engine_ref().camera.unrotate_surface_vec(na::vector![x,y]);
 
                         let mut widget_flags = canvas.engine_mut().camera_set_offset(new_offset);
                         widget_flags.merge(canvas.engine_mut().doc_expand_autoexpand());
@@ -368,13 +383,14 @@ mod imp {
 
                 self.canvas_mouse_drag_middle_gesture.connect_drag_begin(
                     clone!(@strong mouse_drag_start, @weak obj as canvaswrapper => move |_, _, _| {
+                        canvaswrapper.canvas().cancel_camera_animation();
                         mouse_drag_start.set(canvaswrapper.canvas().engine_ref().camera.offset());
                     }),
                 );
                 self.canvas_mouse_drag_middle_gesture.connect_drag_update(
                     clone!(@strong mouse_drag_start, @weak obj as canvaswrapper => move |_, x, y| {
                         let canvas = canvaswrapper.canvas();
-                        let new_offset = mouse_drag_start.get() - na::vector![x,y];
+                        let new_offset = mouse_drag_start.get() - canvas.engine_ref().camera.unrotate_surface_vec(na::vector![x,y]);
 
                         let mut widget_flags = canvas.engine_mut().camera_set_offset(new_offset);
                         widget_flags.merge(canvas.engine_mut().doc_expand_autoexpand());
@@ -404,13 +420,15 @@ mod imp {
                     @strong offset_begin,
                     @weak obj as canvaswrapper => move |gesture, _| {
                         gesture.set_state(EventSequenceState::Claimed);
+                        canvaswrapper.canvas().cancel_camera_animation();
                         let current_zoom = canvaswrapper.canvas().engine_ref().camera.total_zoom();
 
                         zoom_begin.set(current_zoom);
                         new_zoom.set(current_zoom);
                         prev_scale.set(1.0);
 
-                        bbcenter_begin.set(gesture.bounding_box_center().map(|coords| na::vector![coords.0, coords.1]));
+                        let camera = canvaswrapper.canvas().engine_ref().camera.clone();
+                        bbcenter_begin.set(gesture.bounding_box_center().map(|coords| camera.unrotate_surface_pos(na::vector![coords.0, coords.1])));
                         offset_begin.set(canvaswrapper.canvas().engine_ref().camera.offset());
                     })
                 );
@@ -431,7 +449,8 @@ mod imp {
 
                         let mut widget_flags = canvas.engine_mut().zoom_w_timeout(new_zoom.get());
 
-                        if let Some(bbcenter_current) = gesture.bounding_box_center().map(|coords| na::vector![coords.0, coords.1]) {
+                        let camera = canvas.engine_ref().camera.clone();
+                        if let Some(bbcenter_current) = gesture.bounding_box_center().map(|coords| camera.unrotate_surface_pos(na::vector![coords.0, coords.1])) {
                             let bbcenter_begin = if let Some(bbcenter_begin) = bbcenter_begin.get() {
                                 bbcenter_begin
                             } else {
@@ -486,7 +505,7 @@ mod imp {
                 self.canvas_alt_drag_gesture.connect_drag_update(
                     clone!(@strong offset_start, @weak obj as canvaswrapper => move |_, offset_x, offset_y| {
                         let canvas = canvaswrapper.canvas();
-                        let new_offset = offset_start.get() - na::vector![offset_x, offset_y];
+                        let new_offset = offset_start.get() - canvas.engine_ref().camera.unrotate_surface_vec(na::vector![offset_x, offset_y]);
 
                         let mut widget_flags = canvas.engine_mut().camera_set_offset(new_offset);
                         widget_flags.merge(canvas.engine_mut().doc_expand_autoexpand());
@@ -557,6 +576,42 @@ mod imp {
                 );
             }
 
+            {
+                // Multi-finger tap and swipe gestures.
+                // When touch drawing is enabled, touch input is consumed by the pens instead.
+                self.touch_gestures_controller.connect_event(
+                    clone!(@weak obj as canvaswrapper => @default-return Inhibit(false), move |_, event| {
+                        let phase = match event.event_type() {
+                            gdk::EventType::TouchBegin => TouchPhase::Begin,
+                            gdk::EventType::TouchUpdate => TouchPhase::Update,
+                            gdk::EventType::TouchEnd => TouchPhase::End,
+                            gdk::EventType::TouchCancel => TouchPhase::Cancel,
+                            _ => return Inhibit(false),
+                        };
+                        let canvas = canvaswrapper.canvas();
+                        if canvas.touch_drawing() {
+                            return Inhibit(false);
+                        }
+                        let (Some(sequence), Some((x, y))) = (event.event_sequence(), event.position()) else {
+                            return Inhibit(false);
+                        };
+                        // The sequence pointer is unique while the touch point is active
+                        let id = sequence.as_ptr() as u64;
+                        let widget_flags = canvas.engine_mut().handle_touch_event(
+                            id,
+                            phase,
+                            na::vector![x, y],
+                            Instant::now(),
+                        );
+                        if widget_flags.store_modified {
+                            canvas.update_rendering_current_viewport();
+                        }
+                        canvas.emit_handle_widget_flags(widget_flags);
+                        Inhibit(false)
+                    }),
+                );
+            }
+
             {
                 // Shortcut with touch two-finger long-press.
                 self.touch_two_finger_long_press_gesture.connect_pressed(clone!(@weak obj as canvaswrapper => move |_gesture, _, _| {
@@ -653,6 +708,37 @@ impl RnCanvasWrapper {
     /// this function also disconnects and replaces all existing old connections
     ///
     /// The same method of the canvas child is chained up in here.
+    /// The pointer position in document coordinate space, if the pointer is above the canvas.
+    pub(crate) fn pointer_doc_pos(&self) -> Option<na::Vector2<f64>> {
+        let canvas = self.canvas();
+        let pointer_pos = self.imp().pointer_pos.get()?;
+        let (x, y) = self.translate_coordinates(&canvas, pointer_pos[0], pointer_pos[1])?;
+        let doc_pos = canvas
+            .engine_ref()
+            .camera
+            .transform()
+            .inverse()
+            .transform_point(&na::point![x, y])
+            .coords;
+        Some(doc_pos)
+    }
+
+    /// The placement of pasted content according to the paste mode of the engine.
+    ///
+    /// Falls back to the viewport when pasting at the cursor but the pointer is not above the canvas.
+    pub(crate) fn paste_placement(&self) -> PastePlacement {
+        let paste_mode = self.canvas().engine_ref().import_prefs.paste_mode;
+        match paste_mode {
+            PasteMode::Viewport => PastePlacement::Viewport,
+            PasteMode::InPlace => PastePlacement::InPlace,
+            PasteMode::AtCursor => self
+                .pointer_doc_pos()
+                .map(PastePlacement::CenteredAt)
+                .unwrap_or(PastePlacement::Viewport),
+            PasteMode::Cascaded => PastePlacement::Cascaded,
+        }
+    }
+
     pub(crate) fn init_reconnect(&self, appwindow: &RnAppWindow) {
         let imp = self.imp();
         self.imp().canvas.init_reconnect(appwindow);
@@ -18,6 +18,8 @@ use rnote_engine::engine::export::{
     DocExportFormat, DocExportPrefs, DocPagesExportFormat, DocPagesExportPrefs,
     SelectionExportFormat, SelectionExportPrefs,
 };
+use rnote_engine::exporters::{self, CustomExporter};
+use rnote_engine::{EngineError, Progress};
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -90,6 +92,12 @@ pub(crate) async fn dialog_export_doc_w_prefs(appwindow: &RnAppWindow, canvas: &
         builder.object("export_doc_with_background_switch").unwrap();
     let with_pattern_row: adw::ActionRow = builder.object("export_doc_with_pattern_row").unwrap();
     let with_pattern_switch: Switch = builder.object("export_doc_with_pattern_switch").unwrap();
+    let fit_to_printable_area_row: adw::ActionRow = builder
+        .object("export_doc_fit_to_printable_area_row")
+        .unwrap();
+    let fit_to_printable_area_switch: Switch = builder
+        .object("export_doc_fit_to_printable_area_switch")
+        .unwrap();
     let export_format_row: adw::ComboRow = builder.object("export_doc_export_format_row").unwrap();
     let page_order_row: adw::ComboRow = builder.object("export_doc_page_order_row").unwrap();
     let export_file_label: Label = builder.object("export_doc_export_file_label").unwrap();
@@ -100,10 +108,28 @@ pub(crate) async fn dialog_export_doc_w_prefs(appwindow: &RnAppWindow, canvas: &
     let doc_layout = canvas.engine_ref().document.layout;
     dialog.set_transient_for(Some(appwindow));
 
+    // The registered custom exporters are appended to the built-in formats
+    let custom_exporters = exporters::custom_exporters();
+    let selected_custom_exporter: Rc<RefCell<Option<CustomExporter>>> = Rc::new(RefCell::new(None));
+    let export_format_model = export_format_row
+        .model()
+        .unwrap()
+        .downcast::<gtk4::StringList>()
+        .unwrap();
+    let n_builtin_formats = export_format_model.n_items();
+    for exporter in custom_exporters.iter() {
+        export_format_model.append(&exporter.name);
+    }
+
     // initial widget state with the preferences
     let selected_file: Rc<RefCell<Option<gio::File>>> = Rc::new(RefCell::new(None));
     with_background_switch.set_active(initial_doc_export_prefs.with_background);
     with_pattern_switch.set_active(initial_doc_export_prefs.with_pattern);
+    fit_to_printable_area_switch.set_active(initial_doc_export_prefs.fit_to_printable_area);
+    fit_to_printable_area_row.set_sensitive(
+        initial_doc_export_prefs.export_format == DocExportFormat::Pdf
+            && !canvas.engine_ref().document.format.margins.is_zero(),
+    );
     preview.set_draw_background(initial_doc_export_prefs.with_background);
     preview.set_draw_pattern(initial_doc_export_prefs.with_pattern);
     preview.set_contents(
@@ -121,13 +147,17 @@ pub(crate) async fn dialog_export_doc_w_prefs(appwindow: &RnAppWindow, canvas: &
     // Update prefs
 
     export_file_button.connect_clicked(
-        clone!(@strong selected_file, @weak export_file_label, @weak button_confirm, @weak dialog, @weak canvas, @weak appwindow => move |_| {
-            glib::MainContext::default().spawn_local(clone!(@strong selected_file, @weak export_file_label, @weak button_confirm, @weak dialog, @weak canvas, @weak appwindow => async move {
+        clone!(@strong selected_file, @strong selected_custom_exporter, @weak export_file_label, @weak button_confirm, @weak dialog, @weak canvas, @weak appwindow => move |_| {
+            glib::MainContext::default().spawn_local(clone!(@strong selected_file, @strong selected_custom_exporter, @weak export_file_label, @weak button_confirm, @weak dialog, @weak canvas, @weak appwindow => async move {
                 dialog.hide();
 
                 let doc_export_prefs = canvas.engine_mut().export_prefs.doc_export_prefs;
-                let filedialog =
-                    create_filedialog_export_doc(&appwindow, canvas.output_file(), &doc_export_prefs);
+                let filedialog = create_filedialog_export_doc(
+                    &appwindow,
+                    canvas.output_file(),
+                    &doc_export_prefs,
+                    selected_custom_exporter.borrow().as_ref(),
+                );
                 match filedialog.save_future(Some(&appwindow)).await {
                     Ok(f) => {
                         if let Some(path_string) = f.path().map(|p| p.to_string_lossy().to_string()) {
@@ -174,9 +204,29 @@ pub(crate) async fn dialog_export_doc_w_prefs(appwindow: &RnAppWindow, canvas: &
         }),
     );
 
-    export_format_row.connect_selected_notify(clone!(@strong selected_file, @weak export_file_label, @weak page_order_row, @weak button_confirm, @weak canvas, @weak appwindow => move |row| {
-        let export_format = DocExportFormat::try_from(row.selected()).unwrap();
-        canvas.engine_mut().export_prefs.doc_export_prefs.export_format = export_format;
+    fit_to_printable_area_switch.connect_active_notify(
+        clone!(@weak canvas, @weak appwindow => move |fit_to_printable_area_switch| {
+            canvas.engine_mut().export_prefs.doc_export_prefs.fit_to_printable_area = fit_to_printable_area_switch.is_active();
+        }),
+    );
+
+    export_format_row.connect_selected_notify(clone!(@strong selected_file, @strong selected_custom_exporter, @weak export_file_label, @weak page_order_row, @weak fit_to_printable_area_row, @weak button_confirm, @weak canvas, @weak appwindow => move |row| {
+        if row.selected() >= n_builtin_formats {
+            selected_custom_exporter.replace(
+                custom_exporters
+                    .get((row.selected() - n_builtin_formats) as usize)
+                    .cloned(),
+            );
+            fit_to_printable_area_row.set_sensitive(false);
+        } else {
+            let export_format = DocExportFormat::try_from(row.selected()).unwrap();
+            selected_custom_exporter.replace(None);
+            canvas.engine_mut().export_prefs.doc_export_prefs.export_format = export_format;
+            fit_to_printable_area_row.set_sensitive(
+                export_format == DocExportFormat::Pdf
+                    && !canvas.engine_ref().document.format.margins.is_zero(),
+            );
+        }
 
         // force the user to pick another file
         export_file_label.set_label(&gettext("- no file selected -"));
@@ -201,17 +251,49 @@ pub(crate) async fn dialog_export_doc_w_prefs(appwindow: &RnAppWindow, canvas: &
     match response {
         ResponseType::Apply => {
             if let Some(file) = selected_file.take() {
+                let custom_exporter = selected_custom_exporter.take();
                 glib::MainContext::default().spawn_local(clone!(@weak canvas, @weak appwindow => async move {
-                            appwindow.overlays().progressbar_start_pulsing();
-
                             let file_title = crate::utils::default_file_title_for_export(
                                 Some(file.clone()),
                                 Some(&canvas::OUTPUT_FILE_NEW_TITLE),
                                 None,
                             );
-                            if let Err(e) = canvas.export_doc(&file, file_title, None).await {
+                            let result = match &custom_exporter {
+                                Some(exporter) => {
+                                    // Custom exporters don't report their progress
+                                    appwindow.overlays().progressbar_start_pulsing();
+                                    canvas.export_doc_w_custom_exporter(&file, file_title.clone(), &exporter.file_ext).await
+                                }
+                                None => {
+                                    let progress = Progress::new();
+                                    let progress_toast = appwindow.overlays().progressbar_track(&progress, &gettext("Exporting document…"));
+                                    let result = canvas.export_doc(&file, file_title.clone(), None, progress).await;
+                                    progress_toast.dismiss();
+                                    result
+                                }
+                            };
+                            if let Err(e) = result {
+                                if EngineError::is_cancelled(&e) {
+                                    appwindow.overlays().progressbar_abort();
+                                    appwindow.overlays().dispatch_toast_text(&gettext("Exporting document cancelled"), crate::overlays::TEXT_TOAST_TIMEOUT_DEFAULT);
+                                    return;
+                                }
                                 log::error!("exporting document failed, Error: `{e:?}`");
-                                appwindow.overlays().dispatch_toast_error(&gettext("Exporting document failed"));
+                                if custom_exporter.is_none() && matches!(EngineError::find(&e), Some(EngineError::Background)) {
+                                    // The export might still succeed without the background
+                                    appwindow.overlays().dispatch_toast_w_button(
+                                        &gettext("Exporting document failed, the background could not be rendered"),
+                                        &gettext("Retry without Background"),
+                                        clone!(@weak canvas, @weak appwindow, @strong file, @strong file_title => move |_toast| {
+                                            glib::MainContext::default().spawn_local(clone!(@weak canvas, @weak appwindow, @strong file, @strong file_title => async move {
+                                                export_doc_without_background(&appwindow, &canvas, &file, file_title).await;
+                                            }));
+                                        }),
+                                        0,
+                                    );
+                                } else {
+                                    appwindow.overlays().dispatch_toast_error(&gettext("Exporting document failed"));
+                                }
                             } else {
                                 appwindow.overlays().dispatch_toast_text(&gettext("Exported document successfully"), crate::overlays::TEXT_TOAST_TIMEOUT_DEFAULT);
                             }
@@ -228,10 +310,45 @@ pub(crate) async fn dialog_export_doc_w_prefs(appwindow: &RnAppWindow, canvas: &
     }
 }
 
+/// Retry a failed document export with the background disabled.
+async fn export_doc_without_background(
+    appwindow: &RnAppWindow,
+    canvas: &RnCanvas,
+    file: &gio::File,
+    file_title: String,
+) {
+    appwindow.overlays().progressbar_start_pulsing();
+
+    let mut doc_export_prefs = canvas.engine_ref().export_prefs.doc_export_prefs;
+    doc_export_prefs.with_background = false;
+    if let Err(e) = canvas
+        .export_doc(
+            file,
+            file_title,
+            Some(doc_export_prefs),
+            Progress::default(),
+        )
+        .await
+    {
+        log::error!("exporting document without background failed, Error: `{e:?}`");
+        appwindow
+            .overlays()
+            .dispatch_toast_error(&gettext("Exporting document failed"));
+    } else {
+        appwindow.overlays().dispatch_toast_text(
+            &gettext("Exported document without background successfully"),
+            crate::overlays::TEXT_TOAST_TIMEOUT_DEFAULT,
+        );
+    }
+
+    appwindow.overlays().progressbar_finish();
+}
+
 fn create_filedialog_export_doc(
     appwindow: &RnAppWindow,
     output_file: Option<gio::File>,
     doc_export_prefs: &DocExportPrefs,
+    custom_exporter: Option<&CustomExporter>,
 ) -> FileDialog {
     let filedialog = FileDialog::builder()
         .title(gettext("Export Document"))
@@ -240,24 +357,38 @@ fn create_filedialog_export_doc(
         .build();
 
     let filter = FileFilter::new();
-    match doc_export_prefs.export_format {
-        DocExportFormat::Svg => {
-            filter.add_mime_type("image/svg+xml");
-            filter.add_suffix("svg");
-            filter.set_name(Some(&gettext("Svg")));
-        }
-        DocExportFormat::Pdf => {
-            filter.add_mime_type("application/pdf");
-            filter.add_suffix("pdf");
-            filter.set_name(Some(&gettext("Pdf")));
-        }
-        DocExportFormat::Xopp => {
-            filter.add_mime_type("application/x-xopp");
-            filter.add_suffix("xopp");
-            filter.set_name(Some(&gettext("Xopp")));
+    if let Some(exporter) = custom_exporter {
+        filter.add_mime_type(&exporter.mime_type);
+        filter.add_suffix(&exporter.file_ext);
+        filter.set_name(Some(&exporter.name));
+    } else {
+        match doc_export_prefs.export_format {
+            DocExportFormat::Svg => {
+                filter.add_mime_type("image/svg+xml");
+                filter.add_suffix("svg");
+                filter.set_name(Some(&gettext("Svg")));
+            }
+            DocExportFormat::Pdf => {
+                filter.add_mime_type("application/pdf");
+                filter.add_suffix("pdf");
+                filter.set_name(Some(&gettext("Pdf")));
+            }
+            DocExportFormat::Xopp => {
+                filter.add_mime_type("application/x-xopp");
+                filter.add_suffix("xopp");
+                filter.set_name(Some(&gettext("Xopp")));
+            }
+            DocExportFormat::Tiff => {
+                filter.add_mime_type("image/tiff");
+                filter.add_suffix("tiff");
+                filter.add_suffix("tif");
+                filter.set_name(Some(&gettext("Tiff")));
+            }
         }
     }
-    let file_ext = doc_export_prefs.export_format.file_ext();
+    let file_ext = custom_exporter
+        .map(|exporter| exporter.file_ext.clone())
+        .unwrap_or_else(|| doc_export_prefs.export_format.file_ext());
     let file_name = crate::utils::default_file_title_for_export(
         output_file,
         Some(&canvas::OUTPUT_FILE_NEW_TITLE),
@@ -338,7 +469,8 @@ pub(crate) async fn dialog_export_doc_pages_w_prefs(appwindow: &RnAppWindow, can
     page_order_row.set_selected(initial_doc_pages_export_prefs.page_order.to_u32().unwrap());
     bitmap_scalefactor_row.set_sensitive(
         initial_doc_pages_export_prefs.export_format == DocPagesExportFormat::Png
-            || initial_doc_pages_export_prefs.export_format == DocPagesExportFormat::Jpeg,
+            || initial_doc_pages_export_prefs.export_format == DocPagesExportFormat::Jpeg
+            || initial_doc_pages_export_prefs.export_format == DocPagesExportFormat::Tiff,
     );
     bitmap_scalefactor_spinbutton.set_value(initial_doc_pages_export_prefs.bitmap_scalefactor);
     jpeg_quality_row
@@ -435,7 +567,7 @@ pub(crate) async fn dialog_export_doc_pages_w_prefs(appwindow: &RnAppWindow, can
             canvas.engine_mut().export_prefs.doc_pages_export_prefs.export_format = export_format;
 
             // Set the bitmap scalefactor sensitive only when exporting to a bitmap image
-            bitmap_scalefactor_row.set_sensitive(export_format == DocPagesExportFormat::Png || export_format == DocPagesExportFormat::Jpeg);
+            bitmap_scalefactor_row.set_sensitive(export_format == DocPagesExportFormat::Png || export_format == DocPagesExportFormat::Jpeg || export_format == DocPagesExportFormat::Tiff);
             // Set the jpeg quality pref only sensitive when jpeg is actually selected
             jpeg_quality_row.set_sensitive(export_format == DocPagesExportFormat::Jpeg);
             // update file naming preview
@@ -548,6 +680,12 @@ fn create_filedialog_export_doc_pages(
             filter.add_suffix("jpeg");
             filter.set_name(Some(&gettext("Jpeg")));
         }
+        DocPagesExportFormat::Tiff => {
+            filter.add_mime_type("image/tiff");
+            filter.add_suffix("tiff");
+            filter.add_suffix("tif");
+            filter.set_name(Some(&gettext("Tiff")));
+        }
     }
 
     filedialog.set_default_filter(Some(&filter));
@@ -791,6 +929,11 @@ fn create_filedialog_export_selection(
             filter.add_suffix("jpeg");
             filter.set_name(Some(&gettext("Jpeg")));
         }
+        SelectionExportFormat::Pdf => {
+            filter.add_mime_type("application/pdf");
+            filter.add_suffix("pdf");
+            filter.set_name(Some(&gettext("Pdf")));
+        }
     }
     let file_ext = selection_export_prefs.export_format.file_ext();
     let file_name = crate::utils::default_file_title_for_export(
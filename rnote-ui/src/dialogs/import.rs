@@ -8,10 +8,11 @@ use adw::prelude::*;
 use gettextrs::gettext;
 use gtk4::{
     gio, glib, glib::clone, Builder, Dialog, FileDialog, FileFilter, Label, ResponseType,
-    SpinButton, ToggleButton,
+    SpinButton, Switch, ToggleButton,
 };
 use num_traits::ToPrimitive;
 use rnote_engine::engine::import::{PdfImportPageSpacing, PdfImportPagesType};
+use rnote_engine::Progress;
 
 /// Opens a new rnote save file in a new tab
 pub(crate) async fn filedialog_open_doc(appwindow: &RnAppWindow) {
@@ -50,13 +51,23 @@ pub(crate) async fn filedialog_import_file(appwindow: &RnAppWindow) {
     filter.add_mime_type("image/svg+xml");
     filter.add_mime_type("image/png");
     filter.add_mime_type("image/jpeg");
+    filter.add_mime_type("image/heif");
+    filter.add_mime_type("image/heic");
+    filter.add_mime_type("text/plain");
+    filter.add_mime_type("text/markdown");
     filter.add_suffix("xopp");
     filter.add_suffix("pdf");
     filter.add_suffix("svg");
     filter.add_suffix("png");
     filter.add_suffix("jpg");
     filter.add_suffix("jpeg");
-    filter.set_name(Some(&gettext("Jpg, Pdf, Png, Svg, Xopp")));
+    filter.add_suffix("heif");
+    filter.add_suffix("heic");
+    filter.add_suffix("txt");
+    filter.add_suffix("md");
+    filter.set_name(Some(&gettext(
+        "Heic, Jpg, Markdown, Pdf, Png, Svg, Text, Xopp",
+    )));
 
     let dialog = FileDialog::builder()
         .title(gettext("Import File"))
@@ -111,6 +122,14 @@ pub(crate) async fn dialog_import_pdf_w_prefs(
     let pdf_import_bitmap_scalefactor_spinbutton: SpinButton = builder
         .object("pdf_import_bitmap_scalefactor_spinbutton")
         .unwrap();
+    let pdf_import_ink_to_brushstrokes_row: adw::ActionRow = builder
+        .object("pdf_import_ink_to_brushstrokes_row")
+        .unwrap();
+    let pdf_import_ink_to_brushstrokes_switch: Switch = builder
+        .object("pdf_import_ink_to_brushstrokes_switch")
+        .unwrap();
+    let pdf_import_page_borders_switch: Switch =
+        builder.object("pdf_import_page_borders_switch").unwrap();
 
     dialog.set_transient_for(Some(appwindow));
 
@@ -124,12 +143,16 @@ pub(crate) async fn dialog_import_pdf_w_prefs(
         PdfImportPagesType::Bitmap => {
             pdf_import_as_bitmap_toggle.set_active(true);
             pdf_import_bitmap_scalefactor_row.set_sensitive(true);
+            pdf_import_ink_to_brushstrokes_row.set_sensitive(false);
         }
         PdfImportPagesType::Vector => {
             pdf_import_as_vector_toggle.set_active(true);
             pdf_import_bitmap_scalefactor_row.set_sensitive(false);
+            pdf_import_ink_to_brushstrokes_row.set_sensitive(true);
         }
     }
+    pdf_import_ink_to_brushstrokes_switch.set_active(pdf_import_prefs.ink_to_brushstrokes);
+    pdf_import_page_borders_switch.set_active(pdf_import_prefs.page_borders);
     pdf_import_page_spacing_row.set_selected(pdf_import_prefs.page_spacing.to_u32().unwrap());
     pdf_import_bitmap_scalefactor_spinbutton.set_value(pdf_import_prefs.bitmap_scalefactor);
 
@@ -144,23 +167,37 @@ pub(crate) async fn dialog_import_pdf_w_prefs(
 
     // Update preferences
     pdf_import_as_vector_toggle.connect_toggled(
-        clone!(@weak pdf_import_bitmap_scalefactor_row, @weak canvas, @weak appwindow => move |toggle| {
+        clone!(@weak pdf_import_bitmap_scalefactor_row, @weak pdf_import_ink_to_brushstrokes_row, @weak canvas, @weak appwindow => move |toggle| {
             if toggle.is_active() {
                 canvas.engine_mut().import_prefs.pdf_import_prefs.pages_type = PdfImportPagesType::Vector;
                 pdf_import_bitmap_scalefactor_row.set_sensitive(false);
+                pdf_import_ink_to_brushstrokes_row.set_sensitive(true);
             }
         }),
     );
 
     pdf_import_as_bitmap_toggle.connect_toggled(
-        clone!(@weak pdf_import_bitmap_scalefactor_row, @weak canvas, @weak appwindow => move |toggle| {
+        clone!(@weak pdf_import_bitmap_scalefactor_row, @weak pdf_import_ink_to_brushstrokes_row, @weak canvas, @weak appwindow => move |toggle| {
             if toggle.is_active() {
                 canvas.engine_mut().import_prefs.pdf_import_prefs.pages_type = PdfImportPagesType::Bitmap;
                 pdf_import_bitmap_scalefactor_row.set_sensitive(true);
+                pdf_import_ink_to_brushstrokes_row.set_sensitive(false);
             }
         }),
     );
 
+    pdf_import_ink_to_brushstrokes_switch.connect_active_notify(
+        clone!(@weak canvas, @weak appwindow => move |switch| {
+            canvas.engine_mut().import_prefs.pdf_import_prefs.ink_to_brushstrokes = switch.is_active();
+        }),
+    );
+
+    pdf_import_page_borders_switch.connect_active_notify(
+        clone!(@weak canvas, @weak appwindow => move |switch| {
+            canvas.engine_mut().import_prefs.pdf_import_prefs.page_borders = switch.is_active();
+        }),
+    );
+
     pdf_import_bitmap_scalefactor_spinbutton.connect_value_changed(clone!(@weak canvas, @weak appwindow => move |spinbutton| {
         canvas.engine_mut().import_prefs.pdf_import_prefs.bitmap_scalefactor = spinbutton.value();
     }));
@@ -239,9 +276,15 @@ pub(crate) async fn dialog_import_pdf_w_prefs(
             let page_range = (pdf_page_start_spinbutton.value() as u32 - 1)
                 ..pdf_page_end_spinbutton.value() as u32;
             let (bytes, _) = input_file.load_bytes_future().await?;
-            canvas
-                .load_in_pdf_bytes(bytes.to_vec(), target_pos, Some(page_range))
-                .await?;
+            let progress = Progress::new();
+            let progress_toast = appwindow
+                .overlays()
+                .progressbar_track(&progress, &gettext("Importing Pdf…"));
+            let result = canvas
+                .load_in_pdf_bytes(bytes.to_vec(), target_pos, Some(page_range), progress)
+                .await;
+            progress_toast.dismiss();
+            result?;
             Ok(true)
         }
         _ => {
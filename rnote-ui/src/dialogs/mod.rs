@@ -76,7 +76,7 @@ pub(crate) async fn dialog_clear_doc(appwindow: &RnAppWindow, canvas: &RnCanvas)
             let prev_empty = canvas.empty();
 
             let mut widget_flags = canvas.engine_mut().clear();
-            canvas.return_to_origin_page();
+            canvas.return_to_origin_page(false);
             widget_flags.merge(canvas.engine_mut().doc_resize_autoexpand());
             if !prev_empty {
                 canvas.set_unsaved_changes(true);
@@ -100,7 +100,7 @@ pub(crate) async fn dialog_new_doc(appwindow: &RnAppWindow, canvas: &RnCanvas) {
 
     let new_doc = |appwindow: &RnAppWindow, canvas: &RnCanvas| {
         let mut widget_flags = canvas.engine_mut().clear();
-        canvas.return_to_origin_page();
+        canvas.return_to_origin_page(false);
         widget_flags.merge(canvas.engine_mut().doc_resize_autoexpand());
         canvas.update_rendering_current_viewport();
         canvas.set_unsaved_changes(false);
@@ -20,6 +20,7 @@ pub(crate) mod iconpicker;
 pub(crate) mod mainheader;
 pub(crate) mod overlays;
 pub(crate) mod penssidebar;
+pub(crate) mod screenshot;
 pub(crate) mod settingspanel;
 pub(crate) mod strokecontentpaintable;
 pub(crate) mod strokecontentpreview;
@@ -2,6 +2,7 @@
 use crate::canvaswrapper::RnCanvasWrapper;
 use crate::RnPensSideBar;
 use crate::{dialogs, RnAppWindow, RnColorPicker};
+use gettextrs::gettext;
 use gtk4::{
     gio, glib, glib::clone, prelude::*, subclass::prelude::*, Button, CompositeTemplate, Overlay,
     ProgressBar, ScrolledWindow, ToggleButton, Widget,
@@ -9,6 +10,7 @@ use gtk4::{
 use rnote_engine::engine::EngineViewMut;
 use rnote_engine::pens::{Pen, PenStyle};
 use rnote_engine::utils::GdkRGBAHelpers;
+use rnote_engine::Progress;
 use std::cell::RefCell;
 use std::time::Instant;
 
@@ -292,13 +294,15 @@ impl RnOverlays {
                                 appwindow.handle_widget_flags(widget_flags, &canvas);
                             }
                         }
-                        PenStyle::Brush | PenStyle::Shaper | PenStyle::Eraser | PenStyle::Tools => {}
+                        PenStyle::Brush | PenStyle::Shaper | PenStyle::Eraser | PenStyle::Tools | PenStyle::Plugin => {}
                     }
 
                     // We have a global colorpicker, so we apply it to all styles
                     engine.pens_config.brush_config.marker_options.stroke_color = Some(stroke_color);
                     engine.pens_config.brush_config.solid_options.stroke_color = Some(stroke_color);
                     engine.pens_config.brush_config.textured_options.stroke_color = Some(stroke_color);
+                    engine.pens_config.brush_config.grain_options.stroke_color = Some(stroke_color);
+                    engine.pens_config.brush_config.airbrush_options.stroke_color = Some(stroke_color);
                     engine.pens_config.shaper_config.smooth_options.stroke_color = Some(stroke_color);
                     engine.pens_config.shaper_config.rough_options.stroke_color = Some(stroke_color);
                     engine.pens_config.typewriter_config.text_style.color = stroke_color;
@@ -323,7 +327,7 @@ impl RnOverlays {
                             appwindow.handle_widget_flags(widget_flags, &canvas);
                         }
                     }
-                    PenStyle::Typewriter | PenStyle::Brush | PenStyle::Shaper | PenStyle::Eraser | PenStyle::Tools => {}
+                    PenStyle::Typewriter | PenStyle::Brush | PenStyle::Shaper | PenStyle::Eraser | PenStyle::Tools | PenStyle::Plugin => {}
                 }
 
                 // We have a global colorpicker, so we apply it to all styles
@@ -447,7 +451,33 @@ impl RnOverlays {
         );
     }
 
-    #[allow(unused)]
+    /// Shows the progress of a long running operation in the progressbar,
+    /// and dispatches a toast with the given text and a button to cancel the operation.
+    ///
+    /// The progressbar follows the progress until it is finished or aborted, the returned toast should be dismissed then.
+    pub(crate) fn progressbar_track(&self, progress: &Progress, text: &str) -> adw::Toast {
+        const UPDATE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+        self.progressbar().set_fraction(progress.fraction());
+        if let Some(src) = self.imp().progresspulse_id.replace(Some(glib::source::timeout_add_local(
+            UPDATE_INTERVAL,
+            clone!(@weak self as appwindow, @strong progress => @default-return glib::source::Continue(false), move || {
+                appwindow.progressbar().set_fraction(progress.fraction());
+
+                glib::source::Continue(true)
+            })),
+        )) {
+            src.remove();
+        }
+        self.dispatch_toast_w_button(
+            text,
+            &gettext("Cancel"),
+            clone!(@strong progress => move |_toast| {
+                progress.cancel();
+            }),
+            0,
+        )
+    }
+
     pub(crate) fn progressbar_abort(&self) {
         if let Some(src) = self.imp().progresspulse_id.take() {
             src.remove();
@@ -7,6 +7,8 @@ use gtk4::{
 };
 use num_traits::cast::ToPrimitive;
 use rnote_compose::builders::PenPathBuilderType;
+use rnote_compose::style::airbrush::AirbrushOptions;
+use rnote_compose::style::grain::GrainTexture;
 use rnote_compose::style::textured::{TexturedDotsDistribution, TexturedOptions};
 use rnote_compose::style::PressureCurve;
 use rnote_engine::pens::pensconfig::brushconfig::{BrushStyle, SolidOptions};
@@ -29,6 +31,10 @@ mod imp {
         #[template_child]
         pub(crate) brushstyle_textured_row: TemplateChild<adw::ActionRow>,
         #[template_child]
+        pub(crate) brushstyle_grain_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub(crate) brushstyle_airbrush_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
         pub(crate) brushconfig_menubutton: TemplateChild<MenuButton>,
         #[template_child]
         pub(crate) brushconfig_popover: TemplateChild<Popover>,
@@ -41,12 +47,28 @@ mod imp {
         #[template_child]
         pub(crate) brush_buildertype_modeled: TemplateChild<adw::ActionRow>,
         #[template_child]
+        pub(crate) axis_lock_angle_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub(crate) smoothing_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub(crate) input_prediction_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub(crate) stabilizer_rope_length_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
         pub(crate) solidstyle_pressure_curves_row: TemplateChild<adw::ComboRow>,
         #[template_child]
         pub(crate) texturedstyle_density_spinbutton: TemplateChild<SpinButton>,
         #[template_child]
         pub(crate) texturedstyle_distribution_row: TemplateChild<adw::ComboRow>,
         #[template_child]
+        pub(crate) grainstyle_texture_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub(crate) grainstyle_pressure_curves_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub(crate) airbrushstyle_flow_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub(crate) airbrushstyle_pressure_curves_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
         pub(crate) stroke_width_picker: TemplateChild<RnStrokeWidthPicker>,
     }
 
@@ -123,6 +145,14 @@ impl RnBrushPage {
                 .imp()
                 .brushstyle_listbox
                 .select_row(Some(&*self.imp().brushstyle_textured_row)),
+            BrushStyle::Grain => self
+                .imp()
+                .brushstyle_listbox
+                .select_row(Some(&*self.imp().brushstyle_grain_row)),
+            BrushStyle::Airbrush => self
+                .imp()
+                .brushstyle_listbox
+                .select_row(Some(&*self.imp().brushstyle_airbrush_row)),
         }
     }
 
@@ -182,6 +212,51 @@ impl RnBrushPage {
             .set_selected(position);
     }
 
+    pub(crate) fn grainstyle_texture(&self) -> GrainTexture {
+        GrainTexture::try_from(self.imp().grainstyle_texture_row.get().selected()).unwrap()
+    }
+
+    pub(crate) fn set_grainstyle_texture(&self, texture: GrainTexture) {
+        let position = texture.to_u32().unwrap();
+
+        self.imp()
+            .grainstyle_texture_row
+            .get()
+            .set_selected(position);
+    }
+
+    pub(crate) fn grainstyle_pressure_curve(&self) -> PressureCurve {
+        PressureCurve::try_from(self.imp().grainstyle_pressure_curves_row.get().selected()).unwrap()
+    }
+
+    pub(crate) fn set_grainstyle_pressure_curve(&self, pressure_curve: PressureCurve) {
+        let position = pressure_curve.to_u32().unwrap();
+
+        self.imp()
+            .grainstyle_pressure_curves_row
+            .get()
+            .set_selected(position);
+    }
+
+    pub(crate) fn airbrushstyle_pressure_curve(&self) -> PressureCurve {
+        PressureCurve::try_from(
+            self.imp()
+                .airbrushstyle_pressure_curves_row
+                .get()
+                .selected(),
+        )
+        .unwrap()
+    }
+
+    pub(crate) fn set_airbrushstyle_pressure_curve(&self, pressure_curve: PressureCurve) {
+        let position = pressure_curve.to_u32().unwrap();
+
+        self.imp()
+            .airbrushstyle_pressure_curves_row
+            .get()
+            .set_selected(position);
+    }
+
     pub(crate) fn stroke_width_picker(&self) -> RnStrokeWidthPicker {
         self.imp().stroke_width_picker.get()
     }
@@ -214,6 +289,12 @@ impl RnBrushPage {
                     BrushStyle::Textured => {
                         engine.pens_config.brush_config.textured_options.stroke_width = stroke_width;
                     },
+                    BrushStyle::Grain => {
+                        engine.pens_config.brush_config.grain_options.stroke_width = stroke_width;
+                    },
+                    BrushStyle::Airbrush => {
+                        engine.pens_config.brush_config.airbrush_options.stroke_width = stroke_width;
+                    },
                 }
             }),
         );
@@ -241,6 +322,16 @@ impl RnBrushPage {
                             brushpage.imp().stroke_width_picker.set_stroke_width(stroke_width);
                             brushpage.imp().brushstyle_menubutton.set_icon_name("pen-brush-style-textured-symbolic");
                         },
+                        BrushStyle::Grain => {
+                            let stroke_width = appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.brush_config.grain_options.stroke_width;
+                            brushpage.imp().stroke_width_picker.set_stroke_width(stroke_width);
+                            brushpage.imp().brushstyle_menubutton.set_icon_name("pen-brush-style-grain-symbolic");
+                        },
+                        BrushStyle::Airbrush => {
+                            let stroke_width = appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.brush_config.airbrush_options.stroke_width;
+                            brushpage.imp().stroke_width_picker.set_stroke_width(stroke_width);
+                            brushpage.imp().brushstyle_menubutton.set_icon_name("pen-brush-style-airbrush-symbolic");
+                        },
                     }
                 }
             }),
@@ -255,6 +346,73 @@ impl RnBrushPage {
             }),
         );
 
+        // Axis lock angle
+        imp.axis_lock_angle_spinbutton
+            .get()
+            .set_increments(1.0, 15.0);
+        imp.axis_lock_angle_spinbutton.get().set_range(
+            BrushConfig::AXIS_LOCK_ANGLE_MIN,
+            BrushConfig::AXIS_LOCK_ANGLE_MAX,
+        );
+        // set value after the range!
+        imp.axis_lock_angle_spinbutton
+            .get()
+            .set_value(BrushConfig::AXIS_LOCK_ANGLE_DEFAULT);
+
+        imp.axis_lock_angle_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |spinbutton| {
+                appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.brush_config.axis_lock_angle = spinbutton.value();
+            }),
+        );
+
+        // Smoothing
+        imp.smoothing_spinbutton.get().set_increments(0.05, 0.2);
+        imp.smoothing_spinbutton
+            .get()
+            .set_range(BrushConfig::SMOOTHING_MIN, BrushConfig::SMOOTHING_MAX);
+        // set value after the range!
+        imp.smoothing_spinbutton.get().set_value(0.0);
+
+        imp.smoothing_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |spinbutton| {
+                appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.brush_config.smoothing = spinbutton.value();
+            }),
+        );
+
+        // Input prediction
+        imp.input_prediction_spinbutton
+            .get()
+            .set_increments(1.0, 5.0);
+        imp.input_prediction_spinbutton.get().set_range(
+            BrushConfig::INPUT_PREDICTION_MIN,
+            BrushConfig::INPUT_PREDICTION_MAX,
+        );
+        // set value after the range!
+        imp.input_prediction_spinbutton.get().set_value(0.0);
+
+        imp.input_prediction_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |spinbutton| {
+                appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.brush_config.input_prediction = spinbutton.value();
+            }),
+        );
+
+        // Stabilizer
+        imp.stabilizer_rope_length_spinbutton
+            .get()
+            .set_increments(1.0, 10.0);
+        imp.stabilizer_rope_length_spinbutton.get().set_range(
+            BrushConfig::STABILIZER_ROPE_LENGTH_MIN,
+            BrushConfig::STABILIZER_ROPE_LENGTH_MAX,
+        );
+        // set value after the range!
+        imp.stabilizer_rope_length_spinbutton.get().set_value(0.0);
+
+        imp.stabilizer_rope_length_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |spinbutton| {
+                appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.brush_config.stabilizer_rope_length = spinbutton.value();
+            }),
+        );
+
         // Solid style
         // Pressure curve
         imp.solidstyle_pressure_curves_row.get().connect_selected_notify(clone!(@weak self as brushpage, @weak appwindow => move |_smoothstyle_pressure_curves_row| {
@@ -284,6 +442,41 @@ impl RnBrushPage {
         imp.texturedstyle_distribution_row.get().connect_selected_notify(clone!(@weak self as brushpage, @weak appwindow => move |_texturedstyle_distribution_row| {
             appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.brush_config.textured_options.distribution = brushpage.texturedstyle_dots_distribution();
         }));
+
+        // Grain style
+        // Texture
+        imp.grainstyle_texture_row.get().connect_selected_notify(clone!(@weak self as brushpage, @weak appwindow => move |_grainstyle_texture_row| {
+            appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.brush_config.grain_options.texture = brushpage.grainstyle_texture();
+        }));
+
+        // Pressure curve
+        imp.grainstyle_pressure_curves_row.get().connect_selected_notify(clone!(@weak self as brushpage, @weak appwindow => move |_grainstyle_pressure_curves_row| {
+            appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.brush_config.grain_options.pressure_curve = brushpage.grainstyle_pressure_curve();
+        }));
+
+        // Airbrush style
+        // Flow
+        imp.airbrushstyle_flow_spinbutton
+            .get()
+            .set_increments(10.0, 100.0);
+        imp.airbrushstyle_flow_spinbutton
+            .get()
+            .set_range(AirbrushOptions::FLOW_MIN, AirbrushOptions::FLOW_MAX);
+        // set value after the range!
+        imp.airbrushstyle_flow_spinbutton
+            .get()
+            .set_value(AirbrushOptions::FLOW_DEFAULT);
+
+        imp.airbrushstyle_flow_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |spinbutton| {
+                appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.brush_config.airbrush_options.flow = spinbutton.value();
+            }),
+        );
+
+        // Pressure curve
+        imp.airbrushstyle_pressure_curves_row.get().connect_selected_notify(clone!(@weak self as brushpage, @weak appwindow => move |_airbrushstyle_pressure_curves_row| {
+            appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.brush_config.airbrush_options.pressure_curve = brushpage.airbrushstyle_pressure_curve();
+        }));
     }
 
     pub(crate) fn refresh_ui(&self, active_tab: &RnCanvasWrapper) {
@@ -295,10 +488,22 @@ impl RnBrushPage {
             .brush_config
             .clone();
 
+        imp.axis_lock_angle_spinbutton
+            .set_value(brush_config.axis_lock_angle);
+        imp.smoothing_spinbutton.set_value(brush_config.smoothing);
+        imp.input_prediction_spinbutton
+            .set_value(brush_config.input_prediction);
+        imp.stabilizer_rope_length_spinbutton
+            .set_value(brush_config.stabilizer_rope_length);
         self.set_solidstyle_pressure_curve(brush_config.solid_options.pressure_curve);
         imp.texturedstyle_density_spinbutton
             .set_value(brush_config.textured_options.density);
         self.set_texturedstyle_distribution_variant(brush_config.textured_options.distribution);
+        self.set_grainstyle_texture(brush_config.grain_options.texture);
+        self.set_grainstyle_pressure_curve(brush_config.grain_options.pressure_curve);
+        imp.airbrushstyle_flow_spinbutton
+            .set_value(brush_config.airbrush_options.flow);
+        self.set_airbrushstyle_pressure_curve(brush_config.airbrush_options.pressure_curve);
 
         self.set_brush_style(brush_config.style);
         self.set_buildertype(brush_config.builder_type);
@@ -316,6 +521,14 @@ impl RnBrushPage {
                 imp.stroke_width_picker
                     .set_stroke_width(brush_config.textured_options.stroke_width);
             }
+            BrushStyle::Grain => {
+                imp.stroke_width_picker
+                    .set_stroke_width(brush_config.grain_options.stroke_width);
+            }
+            BrushStyle::Airbrush => {
+                imp.stroke_width_picker
+                    .set_stroke_width(brush_config.airbrush_options.stroke_width);
+            }
         }
     }
 }
@@ -55,6 +55,10 @@ mod imp {
         pub(crate) constraint_three_to_two_switch: TemplateChild<Switch>,
         #[template_child]
         pub(crate) constraint_golden_switch: TemplateChild<Switch>,
+        #[template_child]
+        pub(crate) perfect_shape_hold_switch: TemplateChild<Switch>,
+        #[template_child]
+        pub(crate) perfect_shape_hold_duration_spinbutton: TemplateChild<SpinButton>,
     }
 
     #[glib::object_subclass]
@@ -285,6 +289,29 @@ impl RnShaperPage {
                     appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.shaper_config.constraints.ratios.remove(&ConstraintRatio::Golden);
                 }
             }));
+
+        // Perfect shape hold
+        imp
+            .perfect_shape_hold_switch
+            .get()
+            .connect_state_notify(clone!(@weak appwindow => move |switch|  {
+                appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.shaper_config.perfect_shape_hold = switch.state();
+            }));
+
+        imp.perfect_shape_hold_duration_spinbutton
+            .set_increments(50.0, 200.0);
+        imp.perfect_shape_hold_duration_spinbutton.set_range(
+            ShaperConfig::PERFECT_SHAPE_HOLD_DURATION_MIN,
+            ShaperConfig::PERFECT_SHAPE_HOLD_DURATION_MAX,
+        );
+        imp.perfect_shape_hold_duration_spinbutton
+            .set_value(ShaperConfig::PERFECT_SHAPE_HOLD_DURATION_DEFAULT);
+
+        imp.perfect_shape_hold_duration_spinbutton.connect_value_changed(
+            clone!(@weak appwindow => move |spinbutton| {
+                appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.shaper_config.perfect_shape_hold_duration = spinbutton.value();
+            }),
+        );
     }
 
     pub(crate) fn refresh_ui(&self, active_tab: &RnCanvasWrapper) {
@@ -339,6 +366,12 @@ impl RnShaperPage {
                 .ratios
                 .contains(&ConstraintRatio::Golden),
         );
+
+        // perfect shape hold
+        imp.perfect_shape_hold_switch
+            .set_state(shaper_config.perfect_shape_hold);
+        imp.perfect_shape_hold_duration_spinbutton
+            .set_value(shaper_config.perfect_shape_hold_duration);
     }
 }
 
@@ -1,7 +1,11 @@
 // Imports
 use crate::{RnAppWindow, RnCanvasWrapper};
-use gtk4::{glib, glib::clone, prelude::*, subclass::prelude::*, CompositeTemplate, ToggleButton};
+use gtk4::{
+    glib, glib::clone, prelude::*, subclass::prelude::*, CompositeTemplate, MenuButton, Popover,
+    SpinButton, ToggleButton,
+};
 use rnote_engine::pens::pensconfig::toolsconfig::ToolStyle;
+use rnote_engine::pens::pensconfig::ToolsConfig;
 
 mod imp {
     use super::*;
@@ -15,6 +19,24 @@ mod imp {
         pub(crate) toolstyle_offsetcamera_toggle: TemplateChild<ToggleButton>,
         #[template_child]
         pub(crate) toolstyle_zoom_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub(crate) toolstyle_editnodes_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub(crate) toolstyle_fill_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub(crate) toolstyle_eyedropper_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub(crate) toolstyle_measure_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub(crate) toolstyle_selecttext_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub(crate) fillconfig_menubutton: TemplateChild<MenuButton>,
+        #[template_child]
+        pub(crate) fillconfig_popover: TemplateChild<Popover>,
+        #[template_child]
+        pub(crate) fill_tolerance_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub(crate) fill_gap_closing_spinbutton: TemplateChild<SpinButton>,
     }
 
     #[glib::object_subclass]
@@ -64,6 +86,10 @@ impl RnToolsPage {
         glib::Object::new()
     }
 
+    pub(crate) fn fillconfig_menubutton(&self) -> MenuButton {
+        self.imp().fillconfig_menubutton.get()
+    }
+
     #[allow(unused)]
     pub(crate) fn tool_style(&self) -> Option<ToolStyle> {
         let imp = self.imp();
@@ -74,6 +100,16 @@ impl RnToolsPage {
             Some(ToolStyle::OffsetCamera)
         } else if imp.toolstyle_zoom_toggle.is_active() {
             Some(ToolStyle::Zoom)
+        } else if imp.toolstyle_editnodes_toggle.is_active() {
+            Some(ToolStyle::EditNodes)
+        } else if imp.toolstyle_fill_toggle.is_active() {
+            Some(ToolStyle::Fill)
+        } else if imp.toolstyle_eyedropper_toggle.is_active() {
+            Some(ToolStyle::Eyedropper)
+        } else if imp.toolstyle_measure_toggle.is_active() {
+            Some(ToolStyle::Measure)
+        } else if imp.toolstyle_selecttext_toggle.is_active() {
+            Some(ToolStyle::SelectText)
         } else {
             None
         }
@@ -87,6 +123,11 @@ impl RnToolsPage {
             ToolStyle::VerticalSpace => imp.toolstyle_verticalspace_toggle.set_active(true),
             ToolStyle::OffsetCamera => imp.toolstyle_offsetcamera_toggle.set_active(true),
             ToolStyle::Zoom => imp.toolstyle_zoom_toggle.set_active(true),
+            ToolStyle::EditNodes => imp.toolstyle_editnodes_toggle.set_active(true),
+            ToolStyle::Fill => imp.toolstyle_fill_toggle.set_active(true),
+            ToolStyle::Eyedropper => imp.toolstyle_eyedropper_toggle.set_active(true),
+            ToolStyle::Measure => imp.toolstyle_measure_toggle.set_active(true),
+            ToolStyle::SelectText => imp.toolstyle_selecttext_toggle.set_active(true),
         }
     }
 
@@ -110,9 +151,78 @@ impl RnToolsPage {
                 appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.tools_config.style = ToolStyle::Zoom;
             }
         }));
+
+        imp.toolstyle_editnodes_toggle.connect_toggled(clone!(@weak appwindow => move |toggle| {
+            if toggle.is_active() {
+                appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.tools_config.style = ToolStyle::EditNodes;
+            }
+        }));
+
+        imp.toolstyle_fill_toggle.connect_toggled(clone!(@weak appwindow => move |toggle| {
+            if toggle.is_active() {
+                appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.tools_config.style = ToolStyle::Fill;
+            }
+        }));
+
+        imp.toolstyle_eyedropper_toggle.connect_toggled(clone!(@weak appwindow => move |toggle| {
+            if toggle.is_active() {
+                appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.tools_config.style = ToolStyle::Eyedropper;
+            }
+        }));
+
+        imp.toolstyle_measure_toggle.connect_toggled(clone!(@weak appwindow => move |toggle| {
+            if toggle.is_active() {
+                appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.tools_config.style = ToolStyle::Measure;
+            }
+        }));
+
+        imp.toolstyle_selecttext_toggle.connect_toggled(clone!(@weak appwindow => move |toggle| {
+            if toggle.is_active() {
+                appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.tools_config.style = ToolStyle::SelectText;
+            }
+        }));
+
+        // Fill tolerance
+        imp.fill_tolerance_spinbutton
+            .get()
+            .set_increments(0.05, 0.1);
+        imp.fill_tolerance_spinbutton.get().set_range(
+            ToolsConfig::FILL_TOLERANCE_MIN,
+            ToolsConfig::FILL_TOLERANCE_MAX,
+        );
+        // set value after the range!
+        imp.fill_tolerance_spinbutton
+            .get()
+            .set_value(ToolsConfig::FILL_TOLERANCE_DEFAULT);
+
+        imp.fill_tolerance_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |spinbutton| {
+                appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.tools_config.fill_tolerance = spinbutton.value();
+            }),
+        );
+
+        // Fill gap closing
+        imp.fill_gap_closing_spinbutton
+            .get()
+            .set_increments(1.0, 5.0);
+        imp.fill_gap_closing_spinbutton.get().set_range(
+            ToolsConfig::FILL_GAP_CLOSING_MIN,
+            ToolsConfig::FILL_GAP_CLOSING_MAX,
+        );
+        // set value after the range!
+        imp.fill_gap_closing_spinbutton
+            .get()
+            .set_value(ToolsConfig::FILL_GAP_CLOSING_DEFAULT);
+
+        imp.fill_gap_closing_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |spinbutton| {
+                appwindow.active_tab_wrapper().canvas().engine_mut().pens_config.tools_config.fill_gap_closing = spinbutton.value();
+            }),
+        );
     }
 
     pub(crate) fn refresh_ui(&self, active_tab: &RnCanvasWrapper) {
+        let imp = self.imp();
         let tools_config = active_tab
             .canvas()
             .engine_ref()
@@ -121,5 +231,9 @@ impl RnToolsPage {
             .clone();
 
         self.set_tool_style(tools_config.style);
+        imp.fill_tolerance_spinbutton
+            .set_value(tools_config.fill_tolerance);
+        imp.fill_gap_closing_spinbutton
+            .set_value(tools_config.fill_gap_closing);
     }
 }
@@ -0,0 +1,101 @@
+// Imports
+use futures::channel::oneshot;
+use gtk4::{gio, glib, prelude::*};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+const PORTAL_BUS_NAME: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_OBJECT_PATH: &str = "/org/freedesktop/portal/desktop";
+const PORTAL_SCREENSHOT_INTERFACE: &str = "org.freedesktop.portal.Screenshot";
+const PORTAL_REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+
+/// Capture a screenshot through the screenshot portal of xdg-desktop-portal.
+///
+/// The capture is interactive, so the user can select a screen region or window in the dialog of the desktop environment.
+/// Returns the file of the captured screenshot, or None when the user cancelled the capture.
+pub(crate) async fn capture_screenshot_w_portal() -> anyhow::Result<Option<gio::File>> {
+    let connection = gio::bus_get_future(gio::BusType::Session).await?;
+    let Some(unique_name) = connection.unique_name() else {
+        return Err(anyhow::anyhow!(
+            "D-Bus session connection has no unique name"
+        ));
+    };
+    let handle_token = format!("rnote{}", glib::random_int());
+    // The portal creates the request object at a path that is derived from the unique name and the token
+    let request_path = format!(
+        "{PORTAL_OBJECT_PATH}/request/{}/{handle_token}",
+        unique_name.trim_start_matches(':').replace('.', "_")
+    );
+
+    // Subscribe to the response before calling the method to avoid missing it
+    let (response_sender, response_receiver) =
+        oneshot::channel::<anyhow::Result<(u32, HashMap<String, glib::Variant>)>>();
+    let response_sender = RefCell::new(Some(response_sender));
+    let subscription = connection.signal_subscribe(
+        Some(PORTAL_BUS_NAME),
+        Some(PORTAL_REQUEST_INTERFACE),
+        Some("Response"),
+        Some(&request_path),
+        None,
+        gio::DBusSignalFlags::NONE,
+        move |_, _, _, _, _, parameters| {
+            let Some(response_sender) = response_sender.borrow_mut().take() else {
+                return;
+            };
+            let response = parameters
+                .get::<(u32, HashMap<String, glib::Variant>)>()
+                .ok_or_else(|| anyhow::anyhow!("Invalid screenshot portal response parameters"));
+            if response_sender.send(response).is_err() {
+                log::error!("Sending screenshot portal response failed, receiver already dropped.");
+            }
+        },
+    );
+
+    let options = glib::VariantDict::new(None);
+    options.insert_value("handle_token", &handle_token.to_variant());
+    options.insert_value("modal", &true.to_variant());
+    options.insert_value("interactive", &true.to_variant());
+    let call_result = connection
+        .call_future(
+            Some(PORTAL_BUS_NAME),
+            PORTAL_OBJECT_PATH,
+            PORTAL_SCREENSHOT_INTERFACE,
+            "Screenshot",
+            Some(&glib::Variant::tuple_from_iter([
+                // Parent window identifier, left empty
+                "".to_variant(),
+                options.end(),
+            ])),
+            Some(glib::VariantTy::new("(o)")?),
+            gio::DBusCallFlags::NONE,
+            -1,
+        )
+        .await;
+
+    let response = match call_result {
+        Ok(_) => response_receiver.await,
+        Err(e) => {
+            connection.signal_unsubscribe(subscription);
+            return Err(anyhow::anyhow!(
+                "Calling screenshot portal failed, Err: {e:?}"
+            ));
+        }
+    };
+    connection.signal_unsubscribe(subscription);
+    let (response, results) = response??;
+
+    match response {
+        0 => {
+            let uri = results
+                .get("uri")
+                .and_then(|uri| uri.get::<String>())
+                .ok_or_else(|| anyhow::anyhow!("Screenshot portal response has no uri"))?;
+            Ok(Some(gio::File::for_uri(&uri)))
+        }
+        // Cancelled by the user
+        1 => Ok(None),
+        _ => Err(anyhow::anyhow!(
+            "Screenshot portal request failed with response code {response}"
+        )),
+    }
+}
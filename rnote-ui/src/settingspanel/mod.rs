@@ -17,7 +17,8 @@ use gtk4::{
 use num_traits::ToPrimitive;
 use rnote_compose::penevents::ShortcutKey;
 use rnote_engine::document::background::PatternStyle;
-use rnote_engine::document::format::{self, Format, PredefinedFormat};
+use rnote_engine::document::format::{self, Format, MeasureUnit, PredefinedFormat};
+use rnote_engine::document::Document;
 use rnote_engine::utils::GdkRGBAHelpers;
 use std::cell::RefCell;
 
@@ -91,10 +92,18 @@ mod imp {
         #[template_child]
         pub(crate) doc_background_pattern_height_unitentry: TemplateChild<RnUnitEntry>,
         #[template_child]
+        pub(crate) doc_measure_unit_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub(crate) doc_measure_scale_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub(crate) doc_deterministic_exports_switch: TemplateChild<Switch>,
+        #[template_child]
         pub(crate) penshortcut_stylus_button_primary_row: TemplateChild<RnPenShortcutRow>,
         #[template_child]
         pub(crate) penshortcut_stylus_button_secondary_row: TemplateChild<RnPenShortcutRow>,
         #[template_child]
+        pub(crate) penshortcut_stylus_eraser_end_row: TemplateChild<RnPenShortcutRow>,
+        #[template_child]
         pub(crate) penshortcut_mouse_button_secondary_row: TemplateChild<RnPenShortcutRow>,
         #[template_child]
         pub(crate) penshortcut_touch_two_finger_long_press_row: TemplateChild<RnPenShortcutRow>,
@@ -283,6 +292,13 @@ mod imp {
                 self.format_height_unitentry
                     .get()
                     .set_value(format_dimensions_mm.1);
+
+                // Display the dimensions in the unit of the document, if it is a physical one
+                let doc_unit = self.obj().measure_unit();
+                if doc_unit.is_physical() {
+                    self.format_width_unitentry.get().set_unit(doc_unit);
+                    self.format_height_unitentry.get().set_unit(doc_unit);
+                }
             }
         }
     }
@@ -334,6 +350,27 @@ impl RnSettingsPanel {
             .set_selected(position);
     }
 
+    pub(crate) fn measure_unit(&self) -> MeasureUnit {
+        MeasureUnit::try_from(self.imp().doc_measure_unit_row.get().selected()).unwrap()
+    }
+
+    pub(crate) fn set_measure_unit(&self, unit: MeasureUnit) {
+        self.imp()
+            .doc_measure_unit_row
+            .get()
+            .set_selected(unit.to_u32().unwrap())
+    }
+
+    /// Display the size inputs of the document in the unit.
+    fn apply_measure_unit(&self, unit: MeasureUnit) {
+        let imp = self.imp();
+
+        imp.format_width_unitentry.set_unit(unit);
+        imp.format_height_unitentry.set_unit(unit);
+        imp.doc_background_pattern_width_unitentry.set_unit(unit);
+        imp.doc_background_pattern_height_unitentry.set_unit(unit);
+    }
+
     pub(crate) fn set_format_orientation(&self, orientation: format::Orientation) {
         if orientation == format::Orientation::Portrait {
             self.imp()
@@ -421,6 +458,15 @@ impl RnSettingsPanel {
             .set_dpi(format.dpi);
         imp.doc_background_pattern_height_unitentry
             .set_value_in_px(background.pattern_size[1]);
+        let measure_unit = canvas.engine_ref().document.measure_unit;
+        self.set_measure_unit(measure_unit);
+        if measure_unit.is_physical() {
+            self.apply_measure_unit(measure_unit);
+        }
+        imp.doc_measure_scale_spinbutton
+            .set_value(canvas.engine_ref().document.measure_scale);
+        imp.doc_deterministic_exports_switch
+            .set_active(canvas.engine_ref().document.deterministic_exports);
     }
 
     fn refresh_shortcuts_ui(&self, active_tab: &RnCanvasWrapper) {
@@ -438,6 +484,9 @@ impl RnSettingsPanel {
                     imp.penshortcut_stylus_button_secondary_row
                         .set_action(action);
                 }
+                ShortcutKey::StylusEraserEnd => {
+                    imp.penshortcut_stylus_eraser_end_row.set_action(action);
+                }
                 ShortcutKey::MouseSecondaryButton => {
                     imp.penshortcut_mouse_button_secondary_row
                         .set_action(action);
@@ -699,6 +748,43 @@ impl RnSettingsPanel {
                         canvas.update_rendering_current_viewport();
                 }),
             );
+
+        imp.doc_measure_unit_row.get().connect_selected_item_notify(
+            clone!(@weak self as settings_panel, @weak appwindow => move |_| {
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                let unit = settings_panel.measure_unit();
+
+                canvas.engine_mut().document.measure_unit = unit;
+                settings_panel.apply_measure_unit(unit);
+                canvas.queue_draw();
+            }),
+        );
+
+        imp.doc_measure_scale_spinbutton.set_increments(1.0, 10.0);
+        imp.doc_measure_scale_spinbutton
+            .set_range(Document::MEASURE_SCALE_MIN, Document::MEASURE_SCALE_MAX);
+        imp.doc_measure_scale_spinbutton.set_value(1.0);
+
+        imp.doc_measure_scale_spinbutton.connect_value_changed(
+            clone!(@weak appwindow => move |spinbutton| {
+                let canvas = appwindow.active_tab_wrapper().canvas();
+
+                canvas.engine_mut().document.measure_scale = spinbutton.value();
+                canvas.queue_draw();
+            }),
+        );
+
+        imp.doc_deterministic_exports_switch.connect_active_notify(
+            clone!(@weak appwindow => move |switch| {
+                appwindow
+                    .active_tab_wrapper()
+                    .canvas()
+                    .engine_mut()
+                    .document
+                    .deterministic_exports = switch.is_active();
+            }),
+        );
     }
 
     fn setup_shortcuts(&self, appwindow: &RnAppWindow) {
@@ -706,6 +792,7 @@ impl RnSettingsPanel {
         let penshortcut_stylus_button_primary_row = imp.penshortcut_stylus_button_primary_row.get();
         let penshortcut_stylus_button_secondary_row =
             imp.penshortcut_stylus_button_secondary_row.get();
+        let penshortcut_stylus_eraser_end_row = imp.penshortcut_stylus_eraser_end_row.get();
         let penshortcut_mouse_button_secondary_row =
             imp.penshortcut_mouse_button_secondary_row.get();
         let penshortcut_touch_two_finger_long_press_row =
@@ -727,6 +814,12 @@ impl RnSettingsPanel {
             None
         }));
 
+        imp.penshortcut_stylus_eraser_end_row.connect_local("action-changed", false, clone!(@weak penshortcut_stylus_eraser_end_row, @weak appwindow => @default-return None, move |_values| {
+            let action = penshortcut_stylus_eraser_end_row.action();
+            appwindow.active_tab_wrapper().canvas().engine_mut().penholder.register_shortcut(ShortcutKey::StylusEraserEnd, action);
+            None
+        }));
+
         imp.penshortcut_mouse_button_secondary_row.connect_local("action-changed", false, clone!(@weak penshortcut_mouse_button_secondary_row, @weak appwindow => @default-return None, move |_values| {
             let action = penshortcut_mouse_button_secondary_row.action();
             appwindow.active_tab_wrapper().canvas().engine_mut().penholder.register_shortcut(ShortcutKey::MouseSecondaryButton, action);
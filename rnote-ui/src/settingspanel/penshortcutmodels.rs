@@ -9,6 +9,44 @@ use rnote_engine::pens::PenStyle;
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
 
+/// The list model item for the pan shortcut action, placed after the pen styles.
+pub(crate) const PAN_ITEM: &str = "pan";
+/// The list model item for the undo shortcut action.
+pub(crate) const UNDO_ITEM: &str = "undo";
+/// The list model item for the redo shortcut action.
+pub(crate) const REDO_ITEM: &str = "redo";
+/// The list model item for the cycle preset shortcut action.
+pub(crate) const CYCLE_PRESET_ITEM: &str = "cycle-preset";
+
+/// Returns the label and icon name for an item of the list model.
+fn item_label_icon_name(item: &str) -> (String, String) {
+    match item {
+        PAN_ITEM => (
+            gettext("Pan"),
+            String::from("pen-tools-offsetcameratool-symbolic"),
+        ),
+        UNDO_ITEM => (gettext("Undo"), String::from("edit-undo-symbolic")),
+        REDO_ITEM => (gettext("Redo"), String::from("edit-redo-symbolic")),
+        CYCLE_PRESET_ITEM => (
+            gettext("Cycle Pen Presets"),
+            String::from("view-refresh-symbolic"),
+        ),
+        item => {
+            let pen_style = PenStyle::from_str(item).unwrap();
+            let label = match pen_style {
+                PenStyle::Brush => gettext("Brush"),
+                PenStyle::Shaper => gettext("Shaper"),
+                PenStyle::Typewriter => gettext("Typewriter"),
+                PenStyle::Eraser => gettext("Eraser"),
+                PenStyle::Selector => gettext("Selector"),
+                PenStyle::Tools => gettext("Tools"),
+                PenStyle::Plugin => gettext("Plugin"),
+            };
+            (label, pen_style.icon_name())
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct ChangePenStyleListModel(StringList);
 
@@ -21,6 +59,10 @@ impl Default for ChangePenStyleListModel {
             &PenStyle::Eraser.to_string(),
             &PenStyle::Selector.to_string(),
             &PenStyle::Tools.to_string(),
+            PAN_ITEM,
+            UNDO_ITEM,
+            REDO_ITEM,
+            CYCLE_PRESET_ITEM,
         ]))
     }
 }
@@ -63,28 +105,19 @@ impl Default for ChangePenStyleListFactory {
         });
         factory.connect_bind(move |_factory, list_item| {
             let list_item = list_item.downcast_ref::<ListItem>().unwrap();
-            let pen_style = PenStyle::from_str(
+            let (label, icon_name) = item_label_icon_name(
                 &list_item
                     .item()
                     .unwrap()
                     .downcast::<StringObject>()
                     .unwrap()
                     .string(),
-            )
-            .unwrap();
+            );
             let item_box = list_item.child().unwrap().downcast::<gtk4::Box>().unwrap();
 
             let mut child = item_box.first_child();
             while let Some(ref next_child) = child {
                 if next_child.type_() == Label::static_type() {
-                    let label = match pen_style {
-                        PenStyle::Brush => gettext("Brush"),
-                        PenStyle::Shaper => gettext("Shaper"),
-                        PenStyle::Typewriter => gettext("Typewriter"),
-                        PenStyle::Eraser => gettext("Eraser"),
-                        PenStyle::Selector => gettext("Selector"),
-                        PenStyle::Tools => gettext("Tools"),
-                    };
                     next_child
                         .downcast_ref::<Label>()
                         .unwrap()
@@ -93,7 +126,7 @@ impl Default for ChangePenStyleListFactory {
                     next_child
                         .downcast_ref::<Image>()
                         .unwrap()
-                        .set_icon_name(Some(pen_style.icon_name().as_str()));
+                        .set_icon_name(Some(icon_name.as_str()));
                 }
 
                 child = next_child.next_sibling();
@@ -131,20 +164,19 @@ impl Default for ChangePenStyleIconFactory {
         });
         factory.connect_bind(move |_factory, list_item| {
             let list_item = list_item.downcast_ref::<ListItem>().unwrap();
-            let pen_style = PenStyle::from_str(
+            let (_, icon_name) = item_label_icon_name(
                 &list_item
                     .item()
                     .unwrap()
                     .downcast::<StringObject>()
                     .unwrap()
                     .string(),
-            )
-            .unwrap();
+            );
             let image = list_item.child().unwrap().downcast::<Image>().unwrap();
             image
                 .downcast_ref::<Image>()
                 .unwrap()
-                .set_icon_name(Some(pen_style.icon_name().as_str()));
+                .set_icon_name(Some(icon_name.as_str()));
         });
         Self(factory)
     }
@@ -1,15 +1,17 @@
 // Imports
 use super::penshortcutmodels::{
     ChangePenStyleIconFactory, ChangePenStyleListFactory, ChangePenStyleListModel,
+    CYCLE_PRESET_ITEM, PAN_ITEM, REDO_ITEM, UNDO_ITEM,
 };
 use adw::{prelude::*, subclass::prelude::*};
-use gtk4::{glib, glib::clone, glib::subclass::*, CompositeTemplate, DropDown};
+use gtk4::{glib, glib::clone, glib::subclass::*, CompositeTemplate, DropDown, StringObject};
 use num_traits::ToPrimitive;
 use once_cell::sync::Lazy;
 use rnote_engine::pens::shortcuts::ShortcutAction;
 use rnote_engine::pens::shortcuts::ShortcutMode;
 use rnote_engine::pens::PenStyle;
 use std::cell::RefCell;
+use std::str::FromStr;
 
 mod imp {
     use super::*;
@@ -67,22 +69,19 @@ mod imp {
             obj.set_factory(Some(&*icon_factory));
 
             obj.connect_selected_item_notify(move |row| {
-                let new_pen_style = row.pen_style();
-
-                match &mut *row.imp().action.borrow_mut() {
-                    ShortcutAction::ChangePenStyle { style, .. } => {
-                        *style = new_pen_style;
-                    }
-                }
+                let Some(new_action) = row.selected_action() else {
+                    return;
+                };
+                *row.imp().action.borrow_mut() = new_action;
                 row.emit_by_name::<()>("action-changed", &[]);
             });
 
             self.mode_dropdown.get().connect_selected_notify(
                 clone!(@weak obj as penshortcutrow => move |_| {
-                    match &mut *penshortcutrow.imp().action.borrow_mut() {
-                        ShortcutAction::ChangePenStyle { mode, .. } => {
-                            *mode = penshortcutrow.shortcut_mode();
-                        }
+                    if let ShortcutAction::ChangePenStyle { mode, .. } =
+                        &mut *penshortcutrow.imp().action.borrow_mut()
+                    {
+                        *mode = penshortcutrow.shortcut_mode();
                     }
                     penshortcutrow.emit_by_name::<()>("action-changed", &[]);
                 }),
@@ -142,12 +141,35 @@ impl RnPenShortcutRow {
         self.emit_by_name::<()>("action-changed", &[]);
     }
 
-    pub(crate) fn pen_style(&self) -> PenStyle {
-        PenStyle::try_from(self.selected()).unwrap()
+    /// Select the item of the pen style, looked up by its name in the model.
+    pub(crate) fn set_pen_style(&self, style: PenStyle) {
+        self.select_item(&style.to_string())
     }
 
-    pub(crate) fn set_pen_style(&self, style: PenStyle) {
-        self.set_selected(style.to_u32().unwrap())
+    /// The action for the currently selected item, with the mode taken from the mode dropdown.
+    fn selected_action(&self) -> Option<ShortcutAction> {
+        let item = self
+            .selected_item()?
+            .downcast::<StringObject>()
+            .ok()?
+            .string();
+        match item.as_str() {
+            PAN_ITEM => Some(ShortcutAction::Pan),
+            UNDO_ITEM => Some(ShortcutAction::Undo),
+            REDO_ITEM => Some(ShortcutAction::Redo),
+            CYCLE_PRESET_ITEM => Some(ShortcutAction::CyclePreset),
+            item => Some(ShortcutAction::ChangePenStyle {
+                style: PenStyle::from_str(item).ok()?,
+                mode: self.shortcut_mode(),
+            }),
+        }
+    }
+
+    fn select_item(&self, item: &str) {
+        let model = &self.imp().changepenstyle_model;
+        if let Some(i) = (0..model.n_items()).find(|&i| model.string(i).as_deref() == Some(item)) {
+            self.set_selected(i);
+        }
     }
 
     pub(crate) fn shortcut_mode(&self) -> ShortcutMode {
@@ -166,6 +188,15 @@ impl RnPenShortcutRow {
                 self.set_pen_style(style);
                 self.set_shortcut_mode(mode);
             }
+            ShortcutAction::Pan => self.select_item(PAN_ITEM),
+            ShortcutAction::Undo => self.select_item(UNDO_ITEM),
+            ShortcutAction::Redo => self.select_item(REDO_ITEM),
+            ShortcutAction::CyclePreset => self.select_item(CYCLE_PRESET_ITEM),
         }
+        // The mode only applies to changing the pen style
+        self.imp().mode_dropdown.set_sensitive(matches!(
+            self.action(),
+            ShortcutAction::ChangePenStyle { .. }
+        ));
     }
 }
@@ -181,6 +181,14 @@ mod imp {
         const CLIMB_RATE_CM: f64 = 0.2;
         const DIGITS_CM: u32 = 2;
 
+        const STEP_INCREMENT_IN: f64 = 0.05;
+        const CLIMB_RATE_IN: f64 = 0.1;
+        const DIGITS_IN: u32 = 2;
+
+        const STEP_INCREMENT_PT: f64 = 1.0;
+        const CLIMB_RATE_PT: f64 = 2.0;
+        const DIGITS_PT: u32 = 1;
+
         fn configure_spinner(&self, unit: MeasureUnit, dpi: f64) {
             let min_val = MeasureUnit::convert_measurement(
                 Self::MIN_VAL_IN_PX,
@@ -213,6 +221,16 @@ mod imp {
                     Self::CLIMB_RATE_CM,
                     Self::DIGITS_CM,
                 ),
+                MeasureUnit::In => (
+                    Self::STEP_INCREMENT_IN,
+                    Self::CLIMB_RATE_IN,
+                    Self::DIGITS_IN,
+                ),
+                MeasureUnit::Pt => (
+                    Self::STEP_INCREMENT_PT,
+                    Self::CLIMB_RATE_PT,
+                    Self::DIGITS_PT,
+                ),
             };
 
             self.value_spinner.set_range(min_val, max_val);
@@ -18,6 +18,8 @@ pub(crate) enum FileType {
     BitmapImageFile,
     XoppFile,
     PdfFile,
+    PlainTextFile,
+    MarkdownFile,
     Unsupported,
 }
 
@@ -38,7 +40,7 @@ impl FileType {
                             "image/svg+xml" => {
                                 return Self::VectorImageFile;
                             }
-                            "image/png" | "image/jpeg" => {
+                            "image/png" | "image/jpeg" | "image/heif" | "image/heic" => {
                                 return Self::BitmapImageFile;
                             }
                             "application/x-xopp" => {
@@ -47,6 +49,12 @@ impl FileType {
                             "application/pdf" => {
                                 return Self::PdfFile;
                             }
+                            "text/plain" => {
+                                return Self::PlainTextFile;
+                            }
+                            "text/markdown" | "text/x-markdown" => {
+                                return Self::MarkdownFile;
+                            }
                             _ => {}
                         }
                     }
@@ -73,7 +81,7 @@ impl FileType {
                     "svg" => {
                         return Self::VectorImageFile;
                     }
-                    "jpg" | "jpeg" | "png" => {
+                    "jpg" | "jpeg" | "png" | "heif" | "heic" => {
                         return Self::BitmapImageFile;
                     }
                     "xopp" => {
@@ -82,6 +90,12 @@ impl FileType {
                     "pdf" => {
                         return Self::PdfFile;
                     }
+                    "txt" => {
+                        return Self::PlainTextFile;
+                    }
+                    "md" | "markdown" => {
+                        return Self::MarkdownFile;
+                    }
                     _ => {}
                 }
             }